@@ -1,4 +1,5 @@
 import type {
+  BuildActivityMessage,
   ClientMessage,
   HmrUpdateEntry,
   Issue,
@@ -16,6 +17,7 @@ import stripAnsi from "@vercel/turbopack-next/compiled/strip-ansi";
 
 import {
   onBeforeRefresh,
+  onBuildActivity,
   onBuildOk,
   onRefresh,
   onTurbopackIssues,
@@ -35,8 +37,14 @@ export function connect({ assetPrefix }: ClientOptions) {
         handleSocketConnected();
         break;
       case "message":
-        const msg: ServerMessage = JSON.parse(event.message.data);
-        handleSocketMessage(msg);
+        const msg: ServerMessage | BuildActivityMessage = JSON.parse(
+          event.message.data
+        );
+        if (isBuildActivityMessage(msg)) {
+          onBuildActivity(msg);
+        } else {
+          handleSocketMessage(msg);
+        }
         break;
     }
   });
@@ -94,7 +102,18 @@ function subscribeToUpdates(resource: ResourceIdentifier): () => void {
   };
 }
 
+function isBuildActivityMessage(
+  msg: ServerMessage | BuildActivityMessage
+): msg is BuildActivityMessage {
+  return (
+    msg.type === "buildingStarted" ||
+    msg.type === "building" ||
+    msg.type === "buildingFinished"
+  );
+}
+
 function handleSocketConnected() {
+  sendJSON({ type: "subscribeBuildActivity" });
   for (const key of updateCallbackSets.keys()) {
     subscribeToUpdates(JSON.parse(key));
   }
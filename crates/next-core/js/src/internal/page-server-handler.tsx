@@ -35,7 +35,7 @@ type IpcOutgoingMessage =
       headers: Array<[string, string]>;
       body: string;
     }
-  | { type: "rewrite"; path: string };
+  | { type: "rewrite"; path: string; headers?: Array<[string, string]> };
 
 const MIME_APPLICATION_JAVASCRIPT = "application/javascript";
 const MIME_TEXT_HTML_UTF8 = "text/html; charset=utf-8";
@@ -164,6 +164,7 @@ export default function startHandler({
       // TODO(WEB-583) this isn't correct, instead it should set `dev: true`
       nextExport: true,
       resolvedUrl: renderData.url,
+      locale: renderData.locale,
       optimizeFonts: false,
       optimizeCss: false,
       nextScriptWorkers: false,
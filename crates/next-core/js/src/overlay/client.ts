@@ -1,4 +1,7 @@
-import type { Issue } from "@vercel/turbopack-runtime/types/protocol";
+import type {
+  BuildActivityMessage,
+  Issue,
+} from "@vercel/turbopack-runtime/types/protocol";
 
 import * as Bus from "./internal/bus";
 import { parseStack } from "./internal/helpers/parseStack";
@@ -89,6 +92,10 @@ function onTurbopackIssues(issues: Issue[]) {
   Bus.emit({ type: Bus.TYPE_TURBOPACK_ISSUES, issues });
 }
 
+function onBuildActivity(activity: BuildActivityMessage) {
+  Bus.emit({ type: Bus.TYPE_BUILD_ACTIVITY, activity });
+}
+
 function onBeforeRefresh() {
   Bus.emit({ type: Bus.TYPE_BEFORE_REFRESH });
 }
@@ -103,6 +110,7 @@ export { default as ReactDevOverlay } from "./internal/ReactDevOverlay";
 export {
   onBuildOk,
   onTurbopackIssues,
+  onBuildActivity,
   register,
   unregister,
   onBeforeRefresh,
@@ -1,9 +1,13 @@
 import * as React from "react";
 
-import type { Issue } from "@vercel/turbopack-runtime/types/protocol";
+import type {
+  BuildActivityMessage,
+  Issue,
+} from "@vercel/turbopack-runtime/types/protocol";
 
 import * as Bus from "./bus";
 import { ShadowPortal } from "./components/ShadowPortal";
+import { BuildActivity } from "./container/BuildActivity";
 import { Errors, SupportedErrorEvent } from "./container/Errors";
 import { ErrorBoundary } from "./ErrorBoundary";
 import { Base } from "./styles/Base";
@@ -32,6 +36,9 @@ type OverlayState = {
   errors: SupportedErrorEvent[];
 
   refreshState: RefreshState;
+
+  // the last build activity reported by turbopack
+  buildActivity: BuildActivityMessage | null;
 };
 
 function pushErrorFilterDuplicates(
@@ -55,6 +62,9 @@ function reducer(state: OverlayState, ev: Bus.BusEvent): OverlayState {
     case Bus.TYPE_TURBOPACK_ISSUES: {
       return { ...state, issues: ev.issues };
     }
+    case Bus.TYPE_BUILD_ACTIVITY: {
+      return { ...state, buildActivity: ev.activity };
+    }
     case Bus.TYPE_BEFORE_REFRESH: {
       return { ...state, refreshState: { type: "pending", errors: [] } };
     }
@@ -142,6 +152,7 @@ export default function ReactDevOverlay({
     refreshState: {
       type: "idle",
     },
+    buildActivity: null,
   });
 
   React.useEffect(() => {
@@ -167,7 +178,11 @@ export default function ReactDevOverlay({
     ? "runtime"
     : null;
 
-  const isMounted = hasBuildError || hasRuntimeErrors;
+  const isBuilding =
+    state.buildActivity != null &&
+    state.buildActivity.type !== "buildingFinished";
+
+  const isMounted = hasBuildError || hasRuntimeErrors || isBuilding;
 
   return (
     <React.Fragment>
@@ -191,6 +206,7 @@ export default function ReactDevOverlay({
           <Base />
           <ComponentStyles />
 
+          {isBuilding ? <BuildActivity activity={state.buildActivity!} /> : null}
           {shouldPreventDisplay(errorType, preventDisplay) ? null : (
             <Errors issues={state.issues} errors={state.errors} />
           )}
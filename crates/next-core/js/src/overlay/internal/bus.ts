@@ -1,9 +1,13 @@
 import { StackFrame } from "stacktrace-parser";
 
-import type { Issue } from "@vercel/turbopack-runtime/types/protocol";
+import type {
+  BuildActivityMessage,
+  Issue,
+} from "@vercel/turbopack-runtime/types/protocol";
 
 export const TYPE_BUILD_OK = "build-ok";
 export const TYPE_TURBOPACK_ISSUES = "turbopack-error";
+export const TYPE_BUILD_ACTIVITY = "build-activity";
 export const TYPE_BEFORE_REFRESH = "before-fast-refresh";
 export const TYPE_REFRESH = "fast-refresh";
 export const TYPE_UNHANDLED_ERROR = "unhandled-error";
@@ -14,6 +18,10 @@ export type TurbopackIssues = {
   type: typeof TYPE_TURBOPACK_ISSUES;
   issues: Issue[];
 };
+export type BuildActivity = {
+  type: typeof TYPE_BUILD_ACTIVITY;
+  activity: BuildActivityMessage;
+};
 export type BeforeFastRefresh = { type: typeof TYPE_BEFORE_REFRESH };
 export type FastRefresh = { type: typeof TYPE_REFRESH };
 export type UnhandledError = {
@@ -29,6 +37,7 @@ export type UnhandledRejection = {
 export type BusEvent =
   | BuildOk
   | TurbopackIssues
+  | BuildActivity
   | BeforeFastRefresh
   | FastRefresh
   | UnhandledError
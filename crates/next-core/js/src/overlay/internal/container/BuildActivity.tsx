@@ -0,0 +1,57 @@
+import type { BuildActivityMessage } from "@vercel/turbopack-runtime/types/protocol";
+
+import { noop as css } from "../helpers/noop-template";
+
+export type BuildActivityProps = {
+  activity: BuildActivityMessage;
+};
+
+export function BuildActivity({ activity }: BuildActivityProps) {
+  if (activity.type === "buildingFinished") {
+    return null;
+  }
+
+  return (
+    <div data-nextjs-build-activity className="build-activity">
+      <span className="build-activity-spinner" />
+      <span>
+        {activity.type === "building"
+          ? `Building (${activity.remaining} remaining)`
+          : "Building"}
+      </span>
+    </div>
+  );
+}
+
+export const styles = css`
+  .build-activity {
+    position: fixed;
+    bottom: var(--size-gap-double);
+    right: var(--size-gap-double);
+    z-index: 9000;
+    display: flex;
+    align-items: center;
+    padding: var(--size-gap) var(--size-gap-double);
+    border-radius: var(--size-gap-half);
+    font-size: var(--size-font-small);
+    color: var(--color-text-white);
+    background-color: rgba(0, 0, 0, 0.75);
+    pointer-events: none;
+  }
+
+  .build-activity-spinner {
+    width: 10px;
+    height: 10px;
+    margin-right: var(--size-gap);
+    border: 2px solid currentColor;
+    border-right-color: transparent;
+    border-radius: 50%;
+    animation: build-activity-spin 0.8s linear infinite;
+  }
+
+  @keyframes build-activity-spin {
+    to {
+      transform: rotate(360deg);
+    }
+  }
+`;
@@ -7,6 +7,7 @@ import { styles as overlay } from "../components/Overlay";
 import { styles as tabs } from "../components/Tabs";
 import { styles as terminal } from "../components/Terminal";
 import { styles as toast } from "../components/Toast";
+import { styles as containerBuildActivityStyles } from "../container/BuildActivity";
 import { styles as containerErrorStyles } from "../container/Errors";
 import { styles as containerErrorToastStyles } from "../container/ErrorsToast";
 import { styles as containerRuntimeErrorStyles } from "../container/RuntimeError";
@@ -25,6 +26,7 @@ export function ComponentStyles() {
         ${terminal}
         ${tabs}
 
+        ${containerBuildActivityStyles}
         ${containerErrorStyles}
         ${containerErrorToastStyles}
         ${containerRuntimeErrorStyles}
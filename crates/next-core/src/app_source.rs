@@ -327,6 +327,7 @@ pub async fn create_app_source(
         server_root,
         LayoutSegmentsVc::cell(Vec::new()),
         output_path,
+        execution_context.await?.max_message_size,
     )
     .into())
 }
@@ -348,6 +349,7 @@ async fn create_app_source_for_directory(
     url: FileSystemPathVc,
     layouts: LayoutSegmentsVc,
     intermediate_output_path: FileSystemPathVc,
+    max_message_size: Option<usize>,
 ) -> Result<CombinedContentSourceVc> {
     let mut layouts = layouts;
     let mut sources = Vec::new();
@@ -445,6 +447,7 @@ async fn create_app_source_for_directory(
                 target,
                 project_path,
                 intermediate_output_path,
+                max_message_size,
             }
             .cell()
             .into(),
@@ -493,6 +496,7 @@ async fn create_app_source_for_directory(
                 new_url,
                 layouts,
                 intermediate_output_path,
+                max_message_size,
             )
             .into(),
         );
@@ -511,6 +515,7 @@ struct AppRenderer {
     target: FileSystemPathVc,
     project_path: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
+    max_message_size: Option<usize>,
 }
 
 #[turbo_tasks::value_impl]
@@ -655,14 +660,16 @@ import BOOTSTRAP from {};
                 context,
                 Value::new(EcmascriptModuleAssetType::Typescript),
                 EcmascriptInputTransformsVc::cell(vec![
-                    EcmascriptInputTransform::React { refresh: false },
+                    EcmascriptInputTransform::React { refresh: None },
                     EcmascriptInputTransform::TypeScript,
                 ]),
+                Value::new(Default::default()),
                 context.environment(),
             ),
             chunking_context,
             intermediate_output_path,
             output_root: intermediate_output_path.root(),
+            max_message_size: this.max_message_size,
         }
         .cell())
     }
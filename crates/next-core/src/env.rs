@@ -1,42 +1,29 @@
 use anyhow::Result;
 use indexmap::indexmap;
 use turbo_tasks_env::{
-    CommandLineProcessEnvVc, CustomProcessEnvVc, EnvMapVc, FilterProcessEnvVc, ProcessEnv,
-    ProcessEnvVc,
+    CustomProcessEnvVc, DotenvOptions, DotenvOptionsVc, EnvMapVc, EnvVc, ProcessEnvVc,
 };
 use turbo_tasks_fs::FileSystemPathVc;
-use turbopack_env::{EmbeddableProcessEnvVc, TryDotenvProcessEnvVc};
+use turbopack_env::EmbeddableProcessEnvVc;
 
 use crate::next_config::NextConfigVc;
 
+/// How Next.js loads dotenv files: in the mode of `NODE_ENV`, and only
+/// `NEXT_PUBLIC_` variables are exposed to the client.
+#[turbo_tasks::function]
+fn next_dotenv_options() -> DotenvOptionsVc {
+    DotenvOptions {
+        mode: None,
+        client_prefixes: vec!["NEXT_PUBLIC_".to_string()],
+    }
+    .cell()
+}
+
 /// Loads a series of dotenv files according to the precedence rules set by
 /// https://nextjs.org/docs/basic-features/environment-variables#environment-variable-load-order
 #[turbo_tasks::function]
-pub async fn load_env(project_path: FileSystemPathVc) -> Result<ProcessEnvVc> {
-    let env = CommandLineProcessEnvVc::new().as_process_env();
-
-    let node_env = env.read("NODE_ENV").await?;
-    let node_env = node_env.as_deref().unwrap_or("development");
-
-    let files = [
-        Some(format!(".env.{node_env}.local")),
-        if node_env == "test" {
-            None
-        } else {
-            Some(".env.local".into())
-        },
-        Some(format!(".env.{node_env}")),
-        Some(".env".into()),
-    ]
-    .into_iter()
-    .flatten();
-
-    let env = files.fold(env, |prior, f| {
-        let path = project_path.join(&f);
-        TryDotenvProcessEnvVc::new(prior, path).as_process_env()
-    });
-
-    Ok(env)
+pub fn load_env(project_path: FileSystemPathVc) -> ProcessEnvVc {
+    EnvVc::load_dotenv(project_path, next_dotenv_options()).process_env()
 }
 
 /// Creates a ProcessEnvVc safe to use in JS, by stringifying and encoding as
@@ -52,7 +39,7 @@ pub async fn env_for_js(
     next_config: NextConfigVc,
 ) -> Result<ProcessEnvVc> {
     let env = if client {
-        FilterProcessEnvVc::new(env, "NEXT_PUBLIC_".to_string()).into()
+        EnvVc::new(env).client(next_dotenv_options()).process_env()
     } else {
         env
     };
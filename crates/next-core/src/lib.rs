@@ -31,7 +31,9 @@ mod web_entry_source;
 pub use app_source::create_app_source;
 pub use page_source::create_page_source;
 pub use turbopack_node::source_map;
-pub use web_entry_source::create_web_entry_source;
+pub use web_entry_source::{
+    create_web_entry_glob_source, create_web_entry_source, WebEntryOptions,
+};
 
 pub fn register() {
     turbo_tasks::register();
@@ -2,8 +2,9 @@ use core::{default::Default, result::Result::Ok};
 use std::collections::HashMap;
 
 use anyhow::Result;
+use indexmap::indexmap;
 use turbo_tasks::Value;
-use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_env::{CustomProcessEnvVc, EnvMapVc, ProcessEnvVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::{
     module_options::{
@@ -15,11 +16,15 @@ use turbopack::{
     ModuleAssetContextVc,
 };
 use turbopack_core::{
-    chunk::{dev::DevChunkingContextVc, ChunkingContextVc},
+    chunk::{
+        dev::{normalize_base_path, DevChunkingContextVc},
+        ChunkingContextVc,
+    },
     context::AssetContextVc,
     environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
     resolve::{parse::RequestVc, pattern::Pattern},
 };
+use turbopack_ecmascript::CompileTimeDefinesVc;
 use turbopack_env::ProcessEnvAssetVc;
 use turbopack_node::execution_context::ExecutionContextVc;
 
@@ -35,7 +40,7 @@ use crate::{
         get_next_client_resolved_map,
     },
     react_refresh::assert_can_resolve_react_refresh,
-    util::foreign_code_context_condition,
+    util::{foreign_code_context_condition, server_only_forbidden_import},
 };
 
 #[turbo_tasks::function]
@@ -80,6 +85,7 @@ pub async fn get_client_resolve_options_context(
         resolved_map: Some(next_client_resolved_map),
         browser: true,
         module: true,
+        forbidden_imports: vec![server_only_forbidden_import()],
         ..Default::default()
     };
     Ok(ResolveOptionsContext {
@@ -111,6 +117,7 @@ pub async fn get_client_module_options_context(
 
     let module_options_context = ModuleOptionsContext {
         preset_env_versions: Some(env),
+        compile_time_defines: Some(CompileTimeDefinesVc::node_env("development")),
         execution_context: Some(execution_context),
         ..Default::default()
     };
@@ -175,6 +182,19 @@ pub fn get_client_chunking_context(
     server_root: FileSystemPathVc,
     environment: EnvironmentVc,
     ty: Value<ClientContextType>,
+) -> ChunkingContextVc {
+    get_client_chunking_context_with_base_path(project_path, server_root, environment, ty, "")
+}
+
+/// Like [get_client_chunking_context], for chunks which are served under a
+/// subpath, e.g. `/app`.
+#[turbo_tasks::function]
+pub fn get_client_chunking_context_with_base_path(
+    project_path: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    environment: EnvironmentVc,
+    ty: Value<ClientContextType>,
+    base_path: &str,
 ) -> ChunkingContextVc {
     DevChunkingContextVc::builder(
         project_path,
@@ -188,7 +208,9 @@ pub fn get_client_chunking_context(
         get_client_assets_path(server_root, ty),
         environment,
     )
+    .base_path(base_path)
     .hot_module_replacement()
+    .lazy_compilation()
     .build()
 }
 
@@ -206,11 +228,26 @@ pub fn get_client_assets_path(
 }
 
 #[turbo_tasks::function]
-pub async fn get_client_runtime_entries(
+pub fn get_client_runtime_entries(
     project_root: FileSystemPathVc,
     env: ProcessEnvVc,
     ty: Value<ClientContextType>,
     next_config: NextConfigVc,
+) -> RuntimeEntriesVc {
+    get_client_runtime_entries_with_base_path(project_root, env, ty, next_config, "")
+}
+
+/// Like [get_client_runtime_entries], for apps which are served under a
+/// subpath, e.g. `/app`. The HMR client connects to the dev server under it,
+/// reading it from `process.env.__NEXT_ROUTER_BASEPATH` like the error
+/// overlay.
+#[turbo_tasks::function]
+pub async fn get_client_runtime_entries_with_base_path(
+    project_root: FileSystemPathVc,
+    env: ProcessEnvVc,
+    ty: Value<ClientContextType>,
+    next_config: NextConfigVc,
+    base_path: &str,
 ) -> Result<RuntimeEntriesVc> {
     let resolve_options_context = get_client_resolve_options_context(project_root, ty, next_config);
     let enable_react_refresh =
@@ -218,10 +255,19 @@ pub async fn get_client_runtime_entries(
             .await?
             .as_request();
 
-    let mut runtime_entries = vec![RuntimeEntry::Ecmascript(
-        ProcessEnvAssetVc::new(project_root, env_for_js(env, true, next_config)).into(),
-    )
-    .cell()];
+    let mut env = env_for_js(env, true, next_config);
+    if !base_path.is_empty() {
+        let base_path = normalize_base_path(base_path);
+        env = CustomProcessEnvVc::new(
+            env,
+            EnvMapVc::cell(indexmap! {
+                "__NEXT_ROUTER_BASEPATH".to_string() => serde_json::to_string(&base_path)?,
+            }),
+        )
+        .into();
+    }
+    let mut runtime_entries =
+        vec![RuntimeEntry::Ecmascript(ProcessEnvAssetVc::new(project_root, env).into()).cell()];
 
     // It's important that React Refresh come before the regular bootstrap file,
     // because the bootstrap contains JSX which requires Refresh's global
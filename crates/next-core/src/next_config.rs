@@ -541,6 +541,7 @@ pub async fn load_next_config(execution_context: ExecutionContextVc) -> Result<N
     let ExecutionContext {
         project_root,
         intermediate_output_path,
+        ..
     } = *execution_context.await?;
     let mut import_map = ImportMap::default();
 
@@ -563,6 +564,7 @@ pub async fn load_next_config(execution_context: ExecutionContextVc) -> Result<N
             context,
             Value::new(EcmascriptModuleAssetType::Ecmascript),
             EcmascriptInputTransformsVc::cell(vec![]),
+            Value::new(Default::default()),
             context.environment(),
         )
         .as_ecmascript_chunk_placeable();
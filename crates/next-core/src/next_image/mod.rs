@@ -79,6 +79,7 @@ impl ContentSource for NextImageContentSource {
                     status: 302,
                     headers: vec!["Location".to_string(), url.clone()],
                     body: "".into(),
+                    trailers: vec![],
                 }
                 .cell(),
             )
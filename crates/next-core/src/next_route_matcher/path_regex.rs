@@ -1,14 +1,28 @@
 use anyhow::{Context, Result};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::primitives::{BoolVc, Regex};
-use turbopack_node::route_matcher::{ParamsVc, RouteMatcher};
+use turbopack_node::route_matcher::{Param, ParamsVc, RouteMatcher};
 
 /// A regular expression that matches a path, with named capture groups for the
 /// dynamic parts of the path.
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct PathRegex {
     regex: Regex,
-    named_params: Vec<String>,
+    named_params: Vec<NamedParam>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+struct NamedParam {
+    name: String,
+    kind: NamedParamKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+enum NamedParamKind {
+    Single,
+    CatchAll,
+    OptionalCatchAll,
 }
 
 impl std::fmt::Display for PathRegex {
@@ -17,32 +31,52 @@ impl std::fmt::Display for PathRegex {
     }
 }
 
-impl RouteMatcher for PathRegex {
-    fn matches(&self, path: &str) -> BoolVc {
-        BoolVc::cell(self.regex.is_match(path))
-    }
-
-    fn params(&self, path: &str) -> ParamsVc {
-        ParamsVc::cell(self.regex.captures(path).map(|capture| {
+impl PathRegex {
+    /// The params captured from `path`, or `None` when it doesn't match.
+    fn captured_params(&self, path: &str) -> Option<IndexMap<String, Param>> {
+        self.regex.captures(path).map(|capture| {
             self.named_params
                 .iter()
                 .enumerate()
-                .filter_map(|(idx, name)| {
-                    if name.is_empty() {
+                .filter_map(|(idx, param)| {
+                    if param.name.is_empty() {
                         return None;
                     }
-                    let value = capture.get(idx + 1)?;
-                    Some((name.to_string(), value.as_str().to_string()))
+                    // An optional catch all that didn't match is omitted.
+                    let value = capture.get(idx + 1)?.as_str();
+                    let value = match param.kind {
+                        NamedParamKind::Single => Param::Single(value.to_string()),
+                        NamedParamKind::CatchAll | NamedParamKind::OptionalCatchAll => {
+                            Param::Multi(
+                                value
+                                    .split('/')
+                                    .filter(|segment| !segment.is_empty())
+                                    .map(|segment| segment.to_string())
+                                    .collect(),
+                            )
+                        }
+                    };
+                    Some((param.name.clone(), value))
                 })
                 .collect()
-        }))
+        })
+    }
+}
+
+impl RouteMatcher for PathRegex {
+    fn matches(&self, path: &str) -> BoolVc {
+        BoolVc::cell(self.regex.is_match(path))
+    }
+
+    fn params(&self, path: &str) -> ParamsVc {
+        ParamsVc::cell(self.captured_params(path))
     }
 }
 
 /// Builder for [PathRegex].
 pub struct PathRegexBuilder {
     regex_str: String,
-    named_params: Vec<String>,
+    named_params: Vec<NamedParam>,
 }
 
 impl PathRegexBuilder {
@@ -62,6 +96,13 @@ impl PathRegexBuilder {
         self.regex_str.push_str(str);
     }
 
+    fn push_named_param<N: Into<String>>(&mut self, name: N, kind: NamedParamKind) {
+        self.named_params.push(NamedParam {
+            name: name.into(),
+            kind,
+        });
+    }
+
     /// Pushes an optional catch all segment to the regex.
     pub fn push_optional_catch_all<N, R>(&mut self, name: N, rem: R)
     where
@@ -74,7 +115,7 @@ impl PathRegexBuilder {
             "([^?]+)?"
         });
         self.push_str(&regex::escape(rem.as_ref()));
-        self.named_params.push(name.into());
+        self.push_named_param(name, NamedParamKind::OptionalCatchAll);
     }
 
     /// Pushes a catch all segment to the regex.
@@ -88,7 +129,7 @@ impl PathRegexBuilder {
         }
         self.push_str("([^?]+)");
         self.push_str(&regex::escape(rem.as_ref()));
-        self.named_params.push(name.into());
+        self.push_named_param(name, NamedParamKind::CatchAll);
     }
 
     /// Pushes a dynamic segment to the regex.
@@ -102,7 +143,7 @@ impl PathRegexBuilder {
         }
         self.push_str("([^?/]+)");
         self.push_str(&regex::escape(rem.as_ref()));
-        self.named_params.push(name.into());
+        self.push_named_param(name, NamedParamKind::Single);
     }
 
     /// Pushes a static segment to the regex.
@@ -131,3 +172,44 @@ impl Default for PathRegexBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_typed_params() {
+        let mut builder = PathRegexBuilder::new();
+        builder.push_static_segment("blog");
+        builder.push_dynamic_segment("id", "");
+        builder.push_catch_all("slug", "");
+        let regex = builder.build().unwrap();
+        assert_eq!(
+            regex.captured_params("blog/1/a/b"),
+            Some(IndexMap::from([
+                ("id".to_string(), Param::Single("1".to_string())),
+                (
+                    "slug".to_string(),
+                    Param::Multi(vec!["a".to_string(), "b".to_string()])
+                ),
+            ]))
+        );
+        assert_eq!(regex.captured_params("blog/1"), None);
+    }
+
+    #[test]
+    fn omits_unmatched_optional_catch_all() {
+        let mut builder = PathRegexBuilder::new();
+        builder.push_static_segment("docs");
+        builder.push_optional_catch_all("path", "");
+        let regex = builder.build().unwrap();
+        assert_eq!(
+            regex.captured_params("docs/a/b"),
+            Some(IndexMap::from([(
+                "path".to_string(),
+                Param::Multi(vec!["a".to_string(), "b".to_string()])
+            )]))
+        );
+        assert_eq!(regex.captured_params("docs"), Some(IndexMap::new()));
+    }
+}
@@ -12,14 +12,14 @@ use turbopack_core::environment::{
 use turbopack_ecmascript::EcmascriptInputTransform;
 use turbopack_node::execution_context::ExecutionContextVc;
 
-use super::{
-    resolve::ExternalCjsModulesResolvePluginVc, transforms::get_next_server_transforms_rules,
-};
+use super::transforms::get_next_server_transforms_rules;
 use crate::{
     next_build::get_postcss_package_mapping,
     next_config::NextConfigVc,
     next_import_map::{get_next_build_import_map, get_next_server_import_map},
-    util::foreign_code_context_condition,
+    util::{
+        client_only_forbidden_import, foreign_code_context_condition, server_only_forbidden_import,
+    },
 };
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -42,19 +42,16 @@ pub async fn get_server_resolve_options_context(
 
     Ok(match ty.into_value() {
         ServerContextType::Pages { .. } | ServerContextType::PagesData { .. } => {
-            let external_cjs_modules_plugin = ExternalCjsModulesResolvePluginVc::new(
-                project_path,
-                next_config.transpile_packages(),
-            );
-
             let resolve_options_context = ResolveOptionsContext {
                 enable_node_modules: true,
                 enable_node_externals: true,
                 enable_node_native_modules: true,
+                enable_external_packages: true,
+                enable_external_native_packages: true,
+                bundled_packages: next_config.transpile_packages().await?.clone_value(),
                 module: true,
                 custom_conditions: vec!["development".to_string()],
                 import_map: Some(next_server_import_map),
-                plugins: vec![external_cjs_modules_plugin.into()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -72,9 +69,11 @@ pub async fn get_server_resolve_options_context(
                 enable_node_modules: true,
                 enable_node_externals: true,
                 enable_node_native_modules: true,
+                enable_external_native_packages: true,
                 module: true,
                 custom_conditions: vec!["development".to_string()],
                 import_map: Some(next_server_import_map),
+                forbidden_imports: vec![server_only_forbidden_import()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -92,9 +91,11 @@ pub async fn get_server_resolve_options_context(
                 enable_node_modules: true,
                 enable_node_externals: true,
                 enable_node_native_modules: true,
+                enable_external_native_packages: true,
                 module: true,
                 custom_conditions: vec!["development".to_string(), "react-server".to_string()],
                 import_map: Some(next_server_import_map),
+                forbidden_imports: vec![client_only_forbidden_import()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -1,3 +1,2 @@
 pub(crate) mod context;
-pub(crate) mod resolve;
 pub(crate) mod transforms;
@@ -84,6 +84,7 @@ impl PageLoaderAssetVc {
             this.client_context,
             turbo_tasks::Value::new(EcmascriptModuleAssetType::Typescript),
             EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+            turbo_tasks::Value::new(Default::default()),
             this.client_context.environment(),
         );
 
@@ -232,6 +232,7 @@ pub async fn create_page_source(
 
     let server_runtime_entries = EcmascriptChunkPlaceablesVc::cell(server_runtime_entries);
     let page_extensions = next_config.page_extensions();
+    let max_message_size = execution_context.await?.max_message_size;
     let force_not_found_source = create_not_found_page_source(
         project_path,
         server_context,
@@ -244,6 +245,7 @@ pub async fn create_page_source(
         output_path.join("force_not_found"),
         SpecificityVc::exact(),
         NextExactMatcherVc::new(StringVc::cell("_next/404".to_string())).into(),
+        max_message_size,
     );
     let fallback_not_found_source = create_not_found_page_source(
         project_path,
@@ -257,6 +259,7 @@ pub async fn create_page_source(
         output_path.join("fallback_not_found"),
         SpecificityVc::not_found(),
         NextFallbackMatcherVc::new().into(),
+        max_message_size,
     );
     let page_source = create_page_source_for_directory(
         project_path,
@@ -275,6 +278,7 @@ pub async fn create_page_source(
         server_root.join("api"),
         output_path,
         output_path,
+        max_message_size,
     );
     let fallback_source =
         AssetGraphContentSourceVc::new_eager(server_root, fallback_page.as_asset());
@@ -309,6 +313,7 @@ async fn create_page_source_for_file(
     is_api_path: BoolVc,
     intermediate_output_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
+    max_message_size: Option<usize>,
 ) -> Result<ContentSourceVc> {
     let entry_asset = server_context.process(
         page_asset,
@@ -375,6 +380,7 @@ async fn create_page_source_for_file(
                 chunking_context: server_chunking_context,
                 intermediate_output_path,
                 output_root,
+                max_message_size,
             }
             .cell()
             .into(),
@@ -391,6 +397,7 @@ async fn create_page_source_for_file(
             chunking_context: server_chunking_context,
             intermediate_output_path,
             output_root,
+            max_message_size,
         }
         .cell()
         .into();
@@ -402,6 +409,7 @@ async fn create_page_source_for_file(
             chunking_context: server_data_chunking_context,
             intermediate_output_path: data_intermediate_output_path,
             output_root,
+            max_message_size,
         }
         .cell()
         .into();
@@ -465,6 +473,7 @@ async fn create_not_found_page_source(
     intermediate_output_path: FileSystemPathVc,
     specificity: SpecificityVc,
     route_matcher: RouteMatcherVc,
+    max_message_size: Option<usize>,
 ) -> Result<ContentSourceVc> {
     let server_chunking_context = DevChunkingContextVc::builder(
         context_path,
@@ -514,6 +523,7 @@ async fn create_not_found_page_source(
         chunking_context: server_chunking_context,
         intermediate_output_path,
         output_root: intermediate_output_path,
+        max_message_size,
     }
     .cell()
     .into();
@@ -562,6 +572,7 @@ async fn create_page_source_for_directory(
     server_api_path: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
+    max_message_size: Option<usize>,
 ) -> Result<CombinedContentSourceVc> {
     let page_extensions_raw = &*page_extensions.await?;
 
@@ -609,6 +620,7 @@ async fn create_page_source_for_directory(
                                     dev_server_path.is_inside(server_api_path),
                                     intermediate_output_path,
                                     output_root,
+                                    max_message_size,
                                 ),
                             ));
                         }
@@ -634,6 +646,7 @@ async fn create_page_source_for_directory(
                             server_api_path,
                             intermediate_output_path.join(name),
                             output_root,
+                            max_message_size,
                         )
                         .into(),
                     ));
@@ -671,6 +684,7 @@ struct SsrEntry {
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
+    max_message_size: Option<usize>,
 }
 
 #[turbo_tasks::value_impl]
@@ -704,13 +718,15 @@ impl SsrEntryVc {
                 Value::new(EcmascriptModuleAssetType::Typescript),
                 EcmascriptInputTransformsVc::cell(vec![
                     EcmascriptInputTransform::TypeScript,
-                    EcmascriptInputTransform::React { refresh: false },
+                    EcmascriptInputTransform::React { refresh: None },
                 ]),
+                Value::new(Default::default()),
                 this.context.environment(),
             ),
             chunking_context: this.chunking_context,
             intermediate_output_path: this.intermediate_output_path,
             output_root: this.output_root,
+            max_message_size: this.max_message_size,
         }
         .cell())
     }
@@ -131,6 +131,7 @@ async fn extra_configs(
         context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
         context.environment(),
     )
     .as_ecmascript_chunk_placeable();
@@ -144,6 +145,7 @@ fn route_executor(context: AssetContextVc, project_path: FileSystemPathVc) -> As
         context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
         context.environment(),
     )
     .into()
@@ -157,6 +159,7 @@ pub async fn route(
     let ExecutionContext {
         project_root,
         intermediate_output_path,
+        ..
     } = *execution_context.await?;
     let project_path = wrap_with_next_js_fs(project_root);
     let context = node_evaluate_asset_context(Some(get_next_build_import_map(project_path)));
@@ -1,6 +1,5 @@
-use std::collections::HashSet;
-
 use anyhow::Result;
+use indexmap::IndexSet;
 use turbo_tasks::{primitives::StringVc, Value};
 use turbopack_core::introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc};
 use turbopack_dev_server::source::{
@@ -104,6 +103,7 @@ impl ContentSource for NextRouterContentSource {
                         status: data.headers.status_code,
                         headers: data.headers.headers.clone(),
                         body: data.body.clone().into(),
+                        trailers: vec![],
                     }
                     .cell(),
                 )
@@ -128,7 +128,7 @@ impl Introspectable for NextRouterContentSource {
 
     #[turbo_tasks::function]
     async fn children(&self) -> Result<IntrospectableChildrenVc> {
-        let mut children = HashSet::new();
+        let mut children = IndexSet::new();
         if let Some(inner) = IntrospectableVc::resolve_from(self.inner).await? {
             children.insert((StringVc::cell("inner".to_string()), inner));
         }
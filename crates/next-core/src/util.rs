@@ -7,6 +7,7 @@ use turbopack::condition::ContextCondition;
 use turbopack_core::{
     asset::{Asset, AssetVc},
     issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    resolve::options::ForbiddenImport,
 };
 use turbopack_ecmascript::{
     analyzer::{JsValue, ObjectPart},
@@ -85,6 +86,27 @@ pub enum NextRuntime {
     Edge,
 }
 
+/// Forbids importing `server-only` in code that runs on the client.
+pub fn server_only_forbidden_import() -> ForbiddenImport {
+    ForbiddenImport {
+        module: "server-only".to_string(),
+        reason: "This module can only be used on the server, but it's imported by code that runs \
+                 on the client."
+            .to_string(),
+    }
+}
+
+/// Forbids importing `client-only` in Server Components.
+pub fn client_only_forbidden_import() -> ForbiddenImport {
+    ForbiddenImport {
+        module: "client-only".to_string(),
+        reason: "This module can only be used on the client, but it's imported by a Server \
+                 Component. Add the \"use client\" directive to a module in the import chain to \
+                 turn it into a Client Component."
+            .to_string(),
+    }
+}
+
 #[turbo_tasks::value]
 #[derive(Default)]
 pub struct NextSourceConfig {
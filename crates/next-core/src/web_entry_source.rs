@@ -1,38 +1,80 @@
-use anyhow::{anyhow, Result};
-use turbo_tasks::{TryJoinIterExt, Value};
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexSet;
+use turbo_tasks::{TryJoinIterExt, Value, ValueToString};
 use turbo_tasks_env::ProcessEnvVc;
 use turbo_tasks_fs::FileSystemPathVc;
-use turbopack::ecmascript::EcmascriptModuleAssetVc;
+use turbopack::{
+    ecmascript::{
+        chunk::EcmascriptChunkPlaceableVc, service_worker::ServiceWorkerAssetVc,
+        EcmascriptModuleAssetVc,
+    },
+    html::HtmlModuleAssetVc,
+};
 use turbopack_core::{
-    chunk::{ChunkGroupVc, ChunkableAsset, ChunkableAssetVc},
+    asset::{Asset, AssetVc, AssetsSetVc, AssetsVc},
+    chunk::{
+        profile::UsageProfileVc, ChunkGroupVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
+    },
+    glob_entries::entries_from_glob,
     reference_type::{EntryReferenceSubType, ReferenceType},
     resolve::{origin::PlainResolveOriginVc, parse::RequestVc},
 };
 use turbopack_dev_server::{
-    html::DevHtmlAssetVc,
-    source::{asset_graph::AssetGraphContentSourceVc, ContentSourceVc},
+    html::{DevHtmlAssetVc, DevHtmlOptions, DevHtmlOptionsVc, REQUEST_NONCE_PLACEHOLDER},
+    source::{
+        asset_graph::AssetGraphContentSourceVc, combined::CombinedContentSourceVc, ContentSourceVc,
+    },
 };
 use turbopack_node::execution_context::ExecutionContextVc;
 
 use crate::{
     embed_js::wrap_with_next_js_fs,
     next_client::context::{
-        get_client_asset_context, get_client_chunking_context, get_client_environment,
-        get_client_runtime_entries, ClientContextType,
+        get_client_asset_context, get_client_chunking_context_with_base_path,
+        get_client_environment, get_client_runtime_entries_with_base_path, ClientContextType,
     },
     next_config::NextConfigVc,
 };
 
+/// Opt-in options of the chunking of web entries.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Default, PartialOrd, Ord, Hash)]
+pub struct WebEntryOptions {
+    /// Removes the exports which are not used by any module reachable from
+    /// the entries.
+    pub tree_shaking: bool,
+    /// Downlevels modern syntax to the browsers of the browserslist query,
+    /// using the `@swc/helpers` of the project.
+    pub downlevel_syntax: bool,
+    /// Serves the pages with a Content Security Policy which only allows
+    /// scripts with a nonce, that is new for every request, and a runtime
+    /// which works under it.
+    pub strict_csp: bool,
+    /// The path of a usage profile relative to the project, which splits the
+    /// chunks by the routes their modules are executed on. See
+    /// [turbopack_core::chunk::profile].
+    pub usage_profile: Option<String>,
+}
+
+/// Serves an HTML page which loads the entries. HTML entries are served as
+/// they are, with the URLs of their scripts, stylesheets and images replaced,
+/// e.g. `src/index.html` as `/index.html`. The optional service worker entry
+/// is served as `/sw.js`, and precaches the pages. The URLs of chunks are
+/// prefixed with `base_path`, which is stripped by the dev server.
+#[allow(clippy::too_many_arguments)]
 #[turbo_tasks::function]
 pub async fn create_web_entry_source(
     project_root: FileSystemPathVc,
     execution_context: ExecutionContextVc,
     entry_requests: Vec<RequestVc>,
+    service_worker_request: Option<RequestVc>,
     server_root: FileSystemPathVc,
+    base_path: &str,
     env: ProcessEnvVc,
     eager_compile: bool,
     browserslist_query: &str,
     next_config: NextConfigVc,
+    options: Value<WebEntryOptions>,
 ) -> Result<ContentSourceVc> {
     let project_root = wrap_with_next_js_fs(project_root);
 
@@ -45,8 +87,15 @@ pub async fn create_web_entry_source(
         ty,
         next_config,
     );
-    let chunking_context = get_client_chunking_context(project_root, server_root, environment, ty);
-    let entries = get_client_runtime_entries(project_root, env, ty, next_config);
+    let chunking_context = get_client_chunking_context_with_base_path(
+        project_root,
+        server_root,
+        environment,
+        ty,
+        base_path,
+    );
+    let entries =
+        get_client_runtime_entries_with_base_path(project_root, env, ty, next_config, base_path);
 
     let runtime_entries = entries.resolve_entries(context);
 
@@ -64,9 +113,51 @@ pub async fn create_web_entry_source(
         })
         .try_join()
         .await?;
-    let chunks: Vec<_> = entries
+    let chunking_context = if options.tree_shaking {
+        let roots = entries
+            .iter()
+            .flatten()
+            .copied()
+            .chain(runtime_entries.await?.iter().map(|entry| entry.as_asset()))
+            .collect();
+        chunking_context.with_tree_shaking(AssetsVc::cell(roots))
+    } else {
+        chunking_context
+    };
+    let chunking_context = if options.downlevel_syntax {
+        chunking_context.with_downlevel_syntax()
+    } else {
+        chunking_context
+    };
+    let chunking_context = if options.strict_csp {
+        chunking_context.with_strict_csp()
+    } else {
+        chunking_context
+    };
+    let chunking_context = if let Some(usage_profile) = &options.usage_profile {
+        chunking_context.with_usage_profile(UsageProfileVc::read(project_root.join(usage_profile)))
+    } else {
+        chunking_context
+    };
+    let mut root_assets: IndexSet<AssetVc> = IndexSet::new();
+    let mut modules = Vec::new();
+    for module in entries.into_iter().flatten() {
+        if let Some(html) = HtmlModuleAssetVc::resolve_from(module).await? {
+            let file_name = html.path().await?.file_name().to_string();
+            root_assets.insert(
+                html.as_entry_asset(
+                    chunking_context,
+                    server_root.join(&file_name),
+                    Some(runtime_entries),
+                )
+                .into(),
+            );
+        } else {
+            modules.push(module);
+        }
+    }
+    let chunks: Vec<_> = modules
         .into_iter()
-        .flatten()
         .enumerate()
         .map(|(i, module)| async move {
             if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
@@ -87,17 +178,121 @@ pub async fn create_web_entry_source(
         .try_join()
         .await?;
 
-    let entry_asset = DevHtmlAssetVc::new(
-        server_root.join("index.html"),
-        chunks.into_iter().map(ChunkGroupVc::from_chunk).collect(),
-    )
-    .into();
+    if !chunks.is_empty() || root_assets.is_empty() {
+        let html = DevHtmlAssetVc::new(
+            server_root.join("index.html"),
+            chunks.into_iter().map(ChunkGroupVc::from_chunk).collect(),
+        )
+        .with_base_path(base_path);
+        let html = if options.strict_csp {
+            html.with_options(strict_csp_options())
+        } else {
+            html
+        };
+        root_assets.insert(html.into());
+    }
+    let pages: Vec<_> = root_assets.iter().copied().collect();
 
+    if let Some(request) = service_worker_request {
+        let ty = Value::new(ReferenceType::Entry(EntryReferenceSubType::Web));
+        let module = origin
+            .resolve_asset(request, origin.resolve_options(ty.clone()), ty)
+            .primary_assets()
+            .await?
+            .first()
+            .copied();
+        let Some(module) = module else {
+            bail!(
+                "service worker entry {} not found",
+                request.to_string().await?
+            );
+        };
+        let Some(entry) = EcmascriptChunkPlaceableVc::resolve_from(module).await? else {
+            bail!(
+                "service worker entry {} is not an ecmascript module",
+                request.to_string().await?
+            );
+        };
+        root_assets.insert(
+            ServiceWorkerAssetVc::new(
+                server_root.join("sw.js"),
+                chunking_context,
+                entry,
+                AssetsVc::cell(pages),
+            )
+            .into(),
+        );
+    }
+
+    let root_assets = AssetsSetVc::cell(root_assets);
     let graph = if eager_compile {
-        AssetGraphContentSourceVc::new_eager(server_root, entry_asset)
+        AssetGraphContentSourceVc::new_eager_multiple(server_root, root_assets)
     } else {
-        AssetGraphContentSourceVc::new_lazy(server_root, entry_asset)
+        AssetGraphContentSourceVc::new_lazy_multiple(server_root, root_assets)
     }
     .into();
     Ok(graph)
 }
+
+/// Gives the page a nonce of its own for every request, and a policy which
+/// only allows the scripts with the nonce.
+#[turbo_tasks::function]
+fn strict_csp_options() -> DevHtmlOptionsVc {
+    DevHtmlOptions {
+        head_tags: vec![format!(
+            "<meta http-equiv=\"Content-Security-Policy\" content=\"script-src              \
+             'nonce-{REQUEST_NONCE_PLACEHOLDER}'\">"
+        )],
+        nonce: Some(REQUEST_NONCE_PLACEHOLDER.to_string()),
+        ..Default::default()
+    }
+    .cell()
+}
+
+/// Serves a page for every file matched by `pattern`, which is relative to
+/// the project, e.g. `src/pages/**/*.tsx`. The page of `src/pages/about.tsx`
+/// is served at `/about/`, and the page of `src/pages/index.tsx` at `/`.
+/// Pages are added and removed as matching files are added and removed.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub async fn create_web_entry_glob_source(
+    project_root: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    pattern: &str,
+    server_root: FileSystemPathVc,
+    base_path: &str,
+    env: ProcessEnvVc,
+    eager_compile: bool,
+    browserslist_query: &str,
+    next_config: NextConfigVc,
+    options: Value<WebEntryOptions>,
+) -> Result<ContentSourceVc> {
+    let project_root_value = project_root.await?;
+    let mut sources = Vec::new();
+    for entry in entries_from_glob(project_root, pattern).await?.iter() {
+        let entry = entry.await?;
+        let path = entry.path.await?;
+        let Some(relative_path) = project_root_value.get_relative_path_to(&path) else {
+            continue;
+        };
+        let page_path = if entry.name == "index" {
+            ""
+        } else {
+            entry.name.strip_suffix("/index").unwrap_or(&entry.name)
+        };
+        sources.push(create_web_entry_source(
+            project_root,
+            execution_context,
+            vec![RequestVc::relative(Value::new(relative_path.into()), false)],
+            None,
+            server_root.join(page_path),
+            base_path,
+            env,
+            eager_compile,
+            browserslist_query,
+            next_config,
+            options.clone(),
+        ));
+    }
+    Ok(CombinedContentSourceVc::new(sources).into())
+}
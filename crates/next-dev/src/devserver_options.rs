@@ -2,7 +2,7 @@ use std::{net::IpAddr, path::PathBuf};
 
 #[cfg(feature = "cli")]
 use clap::Parser;
-use turbopack_cli_utils::issue::IssueSeverityCliOption;
+use turbopack_cli_utils::{diagnostics::DiagnosticsFormat, issue::IssueSeverityCliOption};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "cli", derive(Parser))]
@@ -73,6 +73,105 @@ pub struct DevServerOptions {
     #[cfg_attr(feature = "serializable", serde(default))]
     pub log_detail: bool,
 
+    /// The format of logged issues. `json` logs one issue per line as JSON,
+    /// for editors and CI.
+    #[cfg_attr(
+        feature = "cli",
+        clap(long, value_enum, default_value_t = DiagnosticsFormat::Human)
+    )]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub diagnostics_format: DiagnosticsFormat,
+
+    /// Don't serve source maps.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub no_source_maps: bool,
+
+    /// Only serve source maps to clients connecting from localhost.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub source_maps_localhost_only: bool,
+
+    /// Serve source maps without the content of the sources. Stack traces can
+    /// still be symbolicated.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub source_maps_without_sources: bool,
+
+    /// A module of the project, e.g. `src/sw`, that is served as service
+    /// worker at `/sw.js`. `self.__TURBOPACK_PRECACHE_MANIFEST__` in its code
+    /// is replaced with a precache manifest of the assets of the app.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub service_worker: Option<String>,
+
+    /// A glob pattern relative to the project, e.g. `src/pages/**/*.tsx`.
+    /// Every matched file is served as a page of its own, e.g.
+    /// `src/pages/about.tsx` at `/about/`. Pages are added and removed as
+    /// files are added and removed.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub entry_glob: Option<String>,
+
+    /// Serve the entries under a subpath, e.g. `/app`. Requests outside of it
+    /// are not found, and the URLs of chunks and of the HMR socket include
+    /// it. Next.js pages don't support it yet.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub base_path: Option<String>,
+
+    /// Remove the exports of the entries' modules which no module uses.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub tree_shaking: bool,
+
+    /// Downlevel the syntax of the web entries to the browsers of the
+    /// project's browserslist config.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub downlevel_syntax: bool,
+
+    /// Serve the web entries with a Content Security Policy which only allows
+    /// scripts with a nonce, and a runtime which doesn't use `eval`.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub strict_csp: bool,
+
+    /// Split the chunks of the web entries by the routes their modules are
+    /// executed on, according to a usage profile relative to the project. A
+    /// profile maps routes to the ids of the executed modules, which the
+    /// runtime collects in `globalThis.TURBOPACK_EXECUTED_MODULES`.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub usage_profile: Option<String>,
+
+    /// Keep serving the last successful content of routes while a rebuild
+    /// has fatal issues, e.g. a syntax error in a file which the routes don't
+    /// use. Routes with fatal issues of their own still respond with the
+    /// error.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub serve_last_good_build: bool,
+
+    /// Respond with a "still compiling" page to page navigations which take
+    /// longer than this many seconds, instead of letting browsers time out.
+    /// The compilation continues in the background.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub request_deadline: Option<u64>,
+
+    /// Respond with 413 to requests whose body is larger than this many MB.
+    /// Defaults to 100 MB.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub max_request_body_size: Option<usize>,
+
+    /// Fail renders whose output is larger than this many MB, instead of
+    /// buffering runaway output. Defaults to 256 MB.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub max_render_response_size: Option<usize>,
+
     /// Whether to enable full task stats recording in Turbo Engine.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
@@ -18,10 +18,14 @@ use std::{
 use anyhow::{anyhow, Context, Result};
 use devserver_options::DevServerOptions;
 use next_core::{
-    create_app_source, create_page_source, create_web_entry_source, env::load_env,
-    manifest::DevManifestContentSource, next_config::load_next_config,
-    next_image::NextImageContentSourceVc, router_source::NextRouterContentSourceVc,
-    source_map::NextSourceMapTraceContentSourceVc,
+    create_app_source, create_page_source, create_web_entry_glob_source, create_web_entry_source,
+    env::load_env,
+    manifest::DevManifestContentSource,
+    next_config::load_next_config,
+    next_image::NextImageContentSourceVc,
+    router_source::NextRouterContentSourceVc,
+    source_map::{NextSourceMapTraceContentSourceVc, StackTraceContentSourceVc},
+    WebEntryOptions,
 };
 use owo_colors::OwoColorize;
 use turbo_malloc::TurboMalloc;
@@ -31,18 +35,23 @@ use turbo_tasks::{
 };
 use turbo_tasks_fs::{DiskFileSystemVc, FileSystem, FileSystemVc};
 use turbo_tasks_memory::MemoryBackend;
-use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc, LogOptions};
+use turbopack_cli_utils::{
+    diagnostics::DiagnosticsFormat,
+    issue::{ConsoleUi, ConsoleUiVc, LogOptions},
+};
 use turbopack_core::{
-    environment::ServerAddr,
+    environment::{self, ServerAddr},
     issue::IssueSeverity,
-    resolve::{parse::RequestVc, pattern::QueryMapVc},
+    resolve::{lockfile::find_lockfile, parse::RequestVc, pattern::QueryMapVc},
     server_fs::ServerFileSystemVc,
 };
 use turbopack_dev_server::{
     introspect::IntrospectionSource,
     source::{
-        combined::CombinedContentSourceVc, router::RouterContentSource,
-        source_maps::SourceMapContentSourceVc, static_assets::StaticAssetsContentSourceVc,
+        combined::CombinedContentSourceVc,
+        router::RouterContentSource,
+        source_maps::{SourceMapContentSourceVc, SourceMapServingOptions},
+        static_assets::StaticAssetsContentSourceVc,
         ContentSourceVc,
     },
     DevServer, DevServerBuilder,
@@ -60,6 +69,9 @@ pub struct NextDevServerBuilder {
     project_dir: String,
     root_dir: String,
     entry_requests: Vec<EntryRequest>,
+    service_worker_request: Option<EntryRequest>,
+    entry_glob: Option<String>,
+    base_path: String,
     eager_compile: bool,
     hostname: Option<IpAddr>,
     port: Option<u16>,
@@ -67,7 +79,14 @@ pub struct NextDevServerBuilder {
     log_level: IssueSeverity,
     show_all: bool,
     log_detail: bool,
+    diagnostics_format: DiagnosticsFormat,
     allow_retry: bool,
+    serve_last_good_build: bool,
+    request_deadline: Option<Duration>,
+    max_request_body_size: Option<usize>,
+    max_render_response_size: Option<usize>,
+    source_map_options: SourceMapServingOptions,
+    web_entry_options: WebEntryOptions,
 }
 
 impl NextDevServerBuilder {
@@ -81,6 +100,9 @@ impl NextDevServerBuilder {
             project_dir,
             root_dir,
             entry_requests: vec![],
+            service_worker_request: None,
+            entry_glob: None,
+            base_path: String::new(),
             eager_compile: false,
             hostname: None,
             port: None,
@@ -90,7 +112,14 @@ impl NextDevServerBuilder {
             log_level: IssueSeverity::Warning,
             show_all: false,
             log_detail: false,
+            diagnostics_format: DiagnosticsFormat::Human,
             allow_retry: false,
+            serve_last_good_build: false,
+            request_deadline: None,
+            max_request_body_size: None,
+            max_render_response_size: None,
+            source_map_options: SourceMapServingOptions::default(),
+            web_entry_options: WebEntryOptions::default(),
         }
     }
 
@@ -99,6 +128,26 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Serves the module as service worker at `/sw.js`.
+    pub fn service_worker_request(mut self, request: EntryRequest) -> NextDevServerBuilder {
+        self.service_worker_request = Some(request);
+        self
+    }
+
+    /// Serves a page for every file matched by the pattern, e.g.
+    /// `src/pages/**/*.tsx`. Pages are added and removed while the server is
+    /// running.
+    pub fn entry_glob(mut self, pattern: String) -> NextDevServerBuilder {
+        self.entry_glob = Some(pattern);
+        self
+    }
+
+    /// Serves the entries under a subpath, e.g. `/app`.
+    pub fn base_path(mut self, base_path: String) -> NextDevServerBuilder {
+        self.base_path = base_path;
+        self
+    }
+
     pub fn eager_compile(mut self, eager_compile: bool) -> NextDevServerBuilder {
         self.eager_compile = eager_compile;
         self
@@ -134,11 +183,59 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Keeps serving the last successful content of routes while the app has
+    /// fatal issues.
+    pub fn serve_last_good_build(mut self, serve_last_good_build: bool) -> NextDevServerBuilder {
+        self.serve_last_good_build = serve_last_good_build;
+        self
+    }
+
+    /// Responds with a "still compiling" page to requests which take longer
+    /// than the `deadline`.
+    pub fn request_deadline(mut self, deadline: Option<Duration>) -> NextDevServerBuilder {
+        self.request_deadline = deadline;
+        self
+    }
+
+    /// Rejects requests whose body is larger than `size` bytes.
+    pub fn max_request_body_size(mut self, size: usize) -> NextDevServerBuilder {
+        self.max_request_body_size = Some(size);
+        self
+    }
+
+    /// Fails renders whose output is larger than `size` bytes.
+    pub fn max_render_response_size(mut self, size: usize) -> NextDevServerBuilder {
+        self.max_render_response_size = Some(size);
+        self
+    }
+
     pub fn log_detail(mut self, log_detail: bool) -> NextDevServerBuilder {
         self.log_detail = log_detail;
         self
     }
 
+    pub fn diagnostics_format(
+        mut self,
+        diagnostics_format: DiagnosticsFormat,
+    ) -> NextDevServerBuilder {
+        self.diagnostics_format = diagnostics_format;
+        self
+    }
+
+    pub fn source_map_options(
+        mut self,
+        source_map_options: SourceMapServingOptions,
+    ) -> NextDevServerBuilder {
+        self.source_map_options = source_map_options;
+        self
+    }
+
+    /// Sets the opt-in chunking options of the entries, e.g. tree shaking.
+    pub fn web_entry_options(mut self, web_entry_options: WebEntryOptions) -> NextDevServerBuilder {
+        self.web_entry_options = web_entry_options;
+        self
+    }
+
     /// Attempts to find an open port to bind.
     fn find_port(&self, host: IpAddr, port: u16, max_attempts: u16) -> Result<DevServerBuilder> {
         // max_attempts of 1 means we loop 0 times.
@@ -183,7 +280,14 @@ impl NextDevServerBuilder {
         let port = self.port.context("port must be set")?;
         let host = self.hostname.context("hostname must be set")?;
 
-        let server = self.find_port(host, port, 10)?;
+        let mut server = self
+            .find_port(host, port, 10)?
+            .base_path(&self.base_path)
+            .serve_last_good_build(self.serve_last_good_build)
+            .request_deadline(self.request_deadline);
+        if let Some(size) = self.max_request_body_size {
+            server = server.max_request_body_size(size);
+        }
 
         let turbo_tasks = self.turbo_tasks;
         let project_dir = self.project_dir;
@@ -192,13 +296,20 @@ impl NextDevServerBuilder {
         let show_all = self.show_all;
         let log_detail = self.log_detail;
         let browserslist_query = self.browserslist_query;
+        let source_map_options = self.source_map_options;
+        let web_entry_options = self.web_entry_options;
+        let max_render_response_size = self.max_render_response_size;
         let log_options = LogOptions {
             current_dir: current_dir().unwrap(),
             show_all,
             log_detail,
             log_level: self.log_level,
+            diagnostics_format: self.diagnostics_format,
         };
         let entry_requests = Arc::new(self.entry_requests);
+        let service_worker_request = Arc::new(self.service_worker_request);
+        let entry_glob = self.entry_glob;
+        let base_path = self.base_path;
         let console_ui = Arc::new(ConsoleUi::new(log_options));
         let console_ui_to_dev_server = console_ui.clone();
         let server_addr = Arc::new(server.addr);
@@ -208,11 +319,17 @@ impl NextDevServerBuilder {
                 root_dir.clone(),
                 project_dir.clone(),
                 entry_requests.clone().into(),
+                service_worker_request.clone().into(),
+                entry_glob.clone(),
+                base_path.clone(),
                 eager_compile,
                 turbo_tasks.clone().into(),
                 console_ui.clone().into(),
                 browserslist_query.clone(),
                 server_addr.clone().into(),
+                Value::new(source_map_options),
+                Value::new(web_entry_options.clone()),
+                max_render_response_size,
             )
         };
 
@@ -237,6 +354,12 @@ async fn project_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSy
     let disk_fs = DiskFileSystemVc::new("project".to_string(), project_dir.to_string());
     handle_issues(disk_fs, console_ui).await?;
     disk_fs.await?.start_watching()?;
+    // Only the packages that the lockfile installs are expected to be
+    // imported, so changes of other packages are irrelevant.
+    if let Some(lockfile) = *find_lockfile(disk_fs.as_file_system().root()).await? {
+        let packages = lockfile.package_names().await?;
+        disk_fs.await?.watch_packages(packages.iter().cloned());
+    }
     Ok(disk_fs.into())
 }
 
@@ -254,11 +377,17 @@ async fn source(
     root_dir: String,
     project_dir: String,
     entry_requests: TransientInstance<Vec<EntryRequest>>,
+    service_worker_request: TransientInstance<Option<EntryRequest>>,
+    entry_glob: Option<String>,
+    base_path: String,
     eager_compile: bool,
     turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
     console_ui: TransientInstance<ConsoleUi>,
     browserslist_query: String,
     server_addr: TransientInstance<SocketAddr>,
+    source_map_options: Value<SourceMapServingOptions>,
+    web_entry_options: Value<WebEntryOptions>,
+    max_render_response_size: Option<usize>,
 ) -> Result<ContentSourceVc> {
     let console_ui = (*console_ui).clone().cell();
     let output_fs = output_fs(&project_dir, console_ui);
@@ -269,11 +398,18 @@ async fn source(
         .unwrap_or(project_relative)
         .replace(MAIN_SEPARATOR, "/");
     let project_path = fs.root().join(&project_relative);
+    // The configured query is used when the project has no browserslist config
+    let browserslist_query = environment::browserslist_query(project_path, &browserslist_query)
+        .await?
+        .clone_value();
 
     let env = load_env(project_path);
     let build_output_root = output_fs.root().join(".next/build");
 
-    let execution_context = ExecutionContextVc::new(project_path, build_output_root);
+    let mut execution_context = ExecutionContextVc::new(project_path, build_output_root);
+    if let Some(size) = max_render_response_size {
+        execution_context = execution_context.with_max_message_size(size);
+    }
 
     let next_config = load_next_config(execution_context.join("next_config"));
 
@@ -282,26 +418,42 @@ async fn source(
 
     let dev_server_fs = ServerFileSystemVc::new().as_file_system();
     let dev_server_root = dev_server_fs.root();
-    let entry_requests = entry_requests
-        .iter()
-        .map(|r| match r {
-            EntryRequest::Relative(p) => RequestVc::relative(Value::new(p.clone().into()), false),
-            EntryRequest::Module(m, p) => {
-                RequestVc::module(m.clone(), Value::new(p.clone().into()), QueryMapVc::none())
-            }
-        })
-        .collect();
+    let to_request = |r: &EntryRequest| match r {
+        EntryRequest::Relative(p) => RequestVc::relative(Value::new(p.clone().into()), false),
+        EntryRequest::Module(m, p) => {
+            RequestVc::module(m.clone(), Value::new(p.clone().into()), QueryMapVc::none())
+        }
+    };
+    let entry_requests = entry_requests.iter().map(to_request).collect();
+    let service_worker_request = (*service_worker_request).as_ref().map(to_request);
 
     let web_source = create_web_entry_source(
         project_path,
         execution_context,
         entry_requests,
+        service_worker_request,
         dev_server_root,
+        &base_path,
         env,
         eager_compile,
         &browserslist_query,
         next_config,
+        web_entry_options.clone(),
     );
+    let entry_glob_source = entry_glob.map(|pattern| {
+        create_web_entry_glob_source(
+            project_path,
+            execution_context,
+            &pattern,
+            dev_server_root,
+            &base_path,
+            env,
+            eager_compile,
+            &browserslist_query,
+            next_config,
+            web_entry_options.clone(),
+        )
+    });
     let page_source = create_page_source(
         project_path,
         execution_context,
@@ -335,21 +487,28 @@ async fn source(
     }
     .cell()
     .into();
-    let main_source = CombinedContentSourceVc::new(vec![
+    let mut main_sources = vec![
         manifest_source,
         static_source,
         app_source,
         page_source,
         web_source,
-    ]);
+    ];
+    main_sources.extend(entry_glob_source);
+    let main_source = CombinedContentSourceVc::new(main_sources);
     let introspect = IntrospectionSource {
         roots: HashSet::from([main_source.into()]),
     }
     .cell()
     .into();
     let main_source = main_source.into();
-    let source_maps = SourceMapContentSourceVc::new(main_source).into();
+    let source_maps = SourceMapContentSourceVc::new_with_options(
+        main_source,
+        source_map_options.into_value().cell(),
+    )
+    .into();
     let source_map_trace = NextSourceMapTraceContentSourceVc::new(main_source).into();
+    let stack_trace = StackTraceContentSourceVc::new(main_source).into();
     let img_source = NextImageContentSourceVc::new(
         CombinedContentSourceVc::new(vec![static_source, page_source]).into(),
     )
@@ -363,6 +522,7 @@ async fn source(
                 "__nextjs_original-stack-frame".to_string(),
                 source_map_trace,
             ),
+            ("__turbopack_stack_trace__".to_string(), stack_trace),
             // TODO: Load path from next.config.js
             ("_next/image".to_string(), img_source),
             ("__turbopack_sourcemap__/".to_string(), source_maps),
@@ -384,6 +544,12 @@ pub fn register() {
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
 
+/// Converts a size option given in MB to bytes.
+fn megabytes(size: usize, option: &str) -> Result<usize> {
+    size.checked_mul(1024 * 1024)
+        .with_context(|| format!("{option} {size} is too large, the size is given in MB"))
+}
+
 /// Start a devserver with the given options.
 pub async fn start_server(options: &DevServerOptions) -> Result<()> {
     let start = Instant::now();
@@ -431,13 +597,47 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
         .hostname(options.hostname)
         .port(options.port)
         .log_detail(options.log_detail)
+        .source_map_options(SourceMapServingOptions {
+            enabled: !options.no_source_maps,
+            localhost_only: options.source_maps_localhost_only,
+            sources_content: !options.source_maps_without_sources,
+        })
         .show_all(options.show_all)
+        .serve_last_good_build(options.serve_last_good_build)
+        .web_entry_options(WebEntryOptions {
+            tree_shaking: options.tree_shaking,
+            downlevel_syntax: options.downlevel_syntax,
+            strict_csp: options.strict_csp,
+            usage_profile: options.usage_profile.clone(),
+        })
+        .request_deadline(options.request_deadline.map(Duration::from_secs))
+        .diagnostics_format(options.diagnostics_format)
         .log_level(
             options
                 .log_level
                 .map_or_else(|| IssueSeverity::Warning, |l| l.0),
         );
 
+    if let Some(size) = options.max_request_body_size {
+        server = server.max_request_body_size(megabytes(size, "--max-request-body-size")?);
+    }
+
+    if let Some(size) = options.max_render_response_size {
+        server = server.max_render_response_size(megabytes(size, "--max-render-response-size")?);
+    }
+
+    if let Some(entry_glob) = &options.entry_glob {
+        server = server.entry_glob(entry_glob.clone());
+    }
+
+    if let Some(base_path) = &options.base_path {
+        server = server.base_path(base_path.clone());
+    }
+
+    if let Some(service_worker) = &options.service_worker {
+        server = server.service_worker_request(EntryRequest::Relative(service_worker.clone()));
+    }
+
     #[cfg(feature = "serializable")]
     {
         server = server.allow_retry(options.allow_retry);
@@ -1,11 +1,12 @@
-use std::{sync::Arc, time::Duration};
+use std::{fmt::Write, sync::Arc, time::Duration};
 
 use anyhow::Result;
-use mime::TEXT_HTML_UTF_8;
-use turbo_tasks::{get_invalidator, TurboTasks, TurboTasksBackendApi, Value};
-use turbo_tasks_fs::File;
+use mime::{TEXT_HTML_UTF_8, TEXT_PLAIN_UTF_8};
+use turbo_malloc::TurboMalloc;
+use turbo_tasks::{get_invalidator, util::FormatBytes, TurboTasks, TurboTasksBackendApi, Value};
+use turbo_tasks_fs::{rope::Rope, File};
 use turbo_tasks_memory::{
-    stats::{ReferenceType, Stats},
+    stats::{CellStats, ReferenceType, Stats},
     viz, MemoryBackend,
 };
 use turbopack_core::asset::AssetContentVc;
@@ -28,6 +29,38 @@ impl TurboTasksSourceVc {
 
 const INVALIDATION_INTERVAL: Duration = Duration::from_secs(3);
 
+/// The number of value types and task functions listed in the memory report.
+const MEMORY_REPORT_TOP_COUNT: usize = 30;
+
+/// A plain text report of what's using memory: the allocator, ropes and the
+/// cells retained by tasks.
+fn memory_report(tt: &TurboTasks<MemoryBackend>) -> Result<String> {
+    let mut stats = CellStats::new();
+    let b = tt.backend();
+    b.with_all_cached_tasks(|task| {
+        stats.add_id(b, task);
+    });
+
+    let mut report = String::new();
+    writeln!(
+        report,
+        "allocated: {}",
+        FormatBytes(TurboMalloc::memory_usage())
+    )?;
+    writeln!(report, "ropes: {}", FormatBytes(Rope::total_owned_bytes()))?;
+    writeln!(report, "tasks: {}", stats.tasks)?;
+    writeln!(report, "cells: {}", stats.cells)?;
+    writeln!(report, "\ncells by value type:")?;
+    for (name, cells) in stats.top_value_types(MEMORY_REPORT_TOP_COUNT) {
+        writeln!(report, "{cells:>10}  {name}")?;
+    }
+    writeln!(report, "\nretained cells by task:")?;
+    for (name, cells) in stats.top_task_types(MEMORY_REPORT_TOP_COUNT) {
+        writeln!(report, "{cells:>10}  {name}")?;
+    }
+    Ok(report)
+}
+
 #[turbo_tasks::value_impl]
 impl ContentSource for TurboTasksSource {
     #[turbo_tasks::function]
@@ -96,6 +129,17 @@ impl ContentSource for TurboTasksSource {
                     })));
                 }
             }
+            "memory" => {
+                return Ok(ContentSourceResultVc::exact(
+                    ContentSourceContentVc::static_content(
+                        AssetContentVc::from(
+                            File::from(memory_report(tt)?).with_content_type(TEXT_PLAIN_UTF_8),
+                        )
+                        .into(),
+                    )
+                    .into(),
+                ));
+            }
             "reset" => {
                 let b = tt.backend();
                 b.with_all_cached_tasks(|task| {
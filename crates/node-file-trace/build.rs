@@ -1,5 +1,27 @@
+use std::process::Command;
+
 use turbo_tasks_build::generate_register;
 
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
 fn main() {
     generate_register();
+
+    // The remote cache only shares task results between builds of the same
+    // commit. Builds outside of a git checkout don't use it.
+    if let Some(commit) = git(&["rev-parse", "HEAD"]) {
+        println!("cargo:rustc-env=NODE_FILE_TRACE_COMMIT={commit}");
+        // The commit changes when HEAD or the branch it points to moves.
+        for path in ["HEAD", "refs", "packed-refs"] {
+            if let Some(path) = git(&["rev-parse", "--git-path", path]) {
+                println!("cargo:rerun-if-changed={path}");
+            }
+        }
+    }
 }
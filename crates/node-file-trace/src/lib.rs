@@ -1,6 +1,7 @@
 #![feature(min_specialization)]
 
 mod nft_json;
+pub mod trace;
 
 use std::{
     collections::{BTreeSet, HashMap},
@@ -26,7 +27,8 @@ use turbo_tasks::{
     NothingVc, TaskId, TransientInstance, TransientValue, TurboTasks, TurboTasksBackendApi, Value,
 };
 use turbo_tasks_fs::{
-    glob::GlobVc, DirectoryEntry, DiskFileSystemVc, FileSystem, FileSystemVc, ReadGlobResultVc,
+    glob::GlobVc, DirectoryEntry, DiskFileSystemVc, FileSystem, FileSystemPathVc, FileSystemVc,
+    ReadGlobResultVc,
 };
 use turbo_tasks_memory::{
     stats::{ReferenceType, Stats},
@@ -37,7 +39,10 @@ use turbopack::{
     resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
     ModuleAssetContextVc,
 };
-use turbopack_cli_utils::issue::{ConsoleUi, IssueSeverityCliOption, LogOptions};
+use turbopack_cli_utils::{
+    diagnostics::DiagnosticsFormat,
+    issue::{ConsoleUi, IssueSeverityCliOption, LogOptions},
+};
 use turbopack_core::{
     asset::{Asset, AssetVc, AssetsVc},
     context::{AssetContext, AssetContextVc},
@@ -64,6 +69,20 @@ struct CacheArgs {
 
     #[clap(long)]
     cache_fully: bool,
+
+    /// Shares the results of tasks through a remote cache at this url.
+    #[clap(long, requires = "remote_cache_token")]
+    remote_cache_url: Option<String>,
+
+    #[clap(long)]
+    remote_cache_token: Option<String>,
+
+    #[clap(long)]
+    remote_cache_team: Option<String>,
+
+    /// Only downloads task results from the remote cache.
+    #[clap(long)]
+    remote_cache_read_only: bool,
 }
 
 #[cfg(not(feature = "persistent_cache"))]
@@ -121,6 +140,15 @@ pub struct CommonArgs {
     /// Expand the log details.
     log_detail: bool,
 
+    #[cfg_attr(
+        feature = "cli",
+        clap(long, value_enum, default_value_t = DiagnosticsFormat::Human)
+    )]
+    #[cfg_attr(feature = "node-api", serde(default))]
+    /// The format of logged issues. `json` logs one issue per line as JSON,
+    /// for editors and CI.
+    diagnostics_format: DiagnosticsFormat,
+
     /// Whether to skip the glob logic
     /// assume the provided input is not glob even if it contains `*` and `[]`
     #[cfg_attr(feature = "cli", clap(short, long))]
@@ -255,6 +283,26 @@ async fn input_to_modules<'a>(
         )),
         Value::new(EnvironmentIntention::Api),
     );
+    let context = trace_context(root, env, enable_mdx);
+    let mut list = Vec::new();
+    for input in input.iter() {
+        if exact {
+            let source = SourceAssetVc::new(root.join(input)).into();
+            list.push(context.process(
+                source,
+                Value::new(turbopack_core::reference_type::ReferenceType::Undefined),
+            ));
+        } else {
+            let glob = GlobVc::new(input);
+            add_glob_results(context, root.read_glob(glob, false), &mut list).await?;
+        };
+    }
+    Ok(AssetsVc::cell(list))
+}
+
+/// The context which processes the traced modules for the environment.
+#[turbo_tasks::function]
+fn trace_context(root: FileSystemPathVc, env: EnvironmentVc, enable_mdx: bool) -> AssetContextVc {
     let glob_mappings = vec![
         (
             root,
@@ -267,7 +315,7 @@ async fn input_to_modules<'a>(
             ImportMapping::Ignore.into(),
         ),
     ];
-    let context: AssetContextVc = ModuleAssetContextVc::new(
+    ModuleAssetContextVc::new(
         TransitionsByNameVc::cell(HashMap::new()),
         env,
         ModuleOptionsContext {
@@ -288,21 +336,7 @@ async fn input_to_modules<'a>(
         }
         .cell(),
     )
-    .into();
-    let mut list = Vec::new();
-    for input in input.iter() {
-        if exact {
-            let source = SourceAssetVc::new(root.join(input)).into();
-            list.push(context.process(
-                source,
-                Value::new(turbopack_core::reference_type::ReferenceType::Undefined),
-            ));
-        } else {
-            let glob = GlobVc::new(input);
-            add_glob_results(context, root.read_glob(glob, false), &mut list).await?;
-        };
-    }
-    Ok(AssetsVc::cell(list))
+    .into()
 }
 
 fn process_context(dir: &Path, context_directory: Option<&String>) -> Result<String> {
@@ -353,24 +387,54 @@ pub async fn start(
         visualize_graph,
         memory_limit,
         #[cfg(feature = "persistent_cache")]
-            cache: CacheArgs {
-            ref cache,
-            ref cache_fully,
-        },
+            cache:
+            CacheArgs {
+                ref cache,
+                ref cache_fully,
+                ref remote_cache_url,
+                ref remote_cache_token,
+                ref remote_cache_team,
+                remote_cache_read_only,
+            },
         ..
     } = args.common();
     #[cfg(feature = "persistent_cache")]
     if let Some(cache) = cache {
         use tokio::time::timeout;
+        use turbo_tasks::remote_cache::RemoteCachePersistedGraph;
         use turbo_tasks_memory::MemoryBackendWithPersistedGraph;
+        use turbo_tasks_remote_cache::{HttpRemoteCache, HttpRemoteCacheOptions};
         use turbo_tasks_rocksdb::RocksDbPersistedGraph;
 
+        // Task results are only shared with builds of the same commit.
+        let build_id = option_env!("NODE_FILE_TRACE_COMMIT")
+            .map(|commit| format!("{}-{commit}", env!("CARGO_PKG_VERSION")));
+        if remote_cache_url.is_some() && build_id.is_none() {
+            eprintln!("the remote cache is disabled, as the commit of this build is unknown");
+        }
+        let remote_cache_options = remote_cache_url
+            .as_ref()
+            .filter(|_| build_id.is_some())
+            .map(|url| HttpRemoteCacheOptions {
+                url: url.clone(),
+                token: remote_cache_token.clone().unwrap_or_default(),
+                team_id: remote_cache_team.clone(),
+                timeout: Duration::from_secs(10),
+            });
+
         run(
             &args,
             || {
                 let start = Instant::now();
                 let backend = MemoryBackendWithPersistedGraph::new(
-                    RocksDbPersistedGraph::new(cache).unwrap(),
+                    RemoteCachePersistedGraph::new(
+                        RocksDbPersistedGraph::new(cache).unwrap(),
+                        remote_cache_options
+                            .clone()
+                            .map(|options| HttpRemoteCache::new(options).unwrap()),
+                        build_id.clone().unwrap_or_default(),
+                    )
+                    .read_only(remote_cache_read_only),
                 );
                 let tt = TurboTasks::new(backend);
                 let elapsed = start.elapsed();
@@ -445,6 +509,7 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
         show_all,
         log_detail,
         log_level,
+        diagnostics_format,
         ..
     } = args.common();
 
@@ -492,6 +557,7 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
         show_all,
         log_detail,
         log_level: log_level.map_or_else(|| IssueSeverity::Error, |l| l.0),
+        diagnostics_format,
     }));
     let task = tt.spawn_root_task(move || {
         let dir = dir.clone();
@@ -0,0 +1,170 @@
+//! The files an entry needs at runtime, for tools which prune deployments to
+//! these files.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{trace::TraceRawVcs, Value};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::Asset, context::AssetContext, environment::EnvironmentVc, issue::IssueVc,
+    reference::all_assets, reference_type::ReferenceType, source_asset::SourceAssetVc,
+};
+
+use crate::trace_context;
+
+/// What a traced file is needed for.
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize, TraceRawVcs,
+)]
+pub enum TracedFileKind {
+    /// A JavaScript or TypeScript module.
+    Ecmascript,
+    /// A JSON module, or a `package.json` which affects resolving.
+    Json,
+    /// A native addon, i. e. a `.node` file.
+    Native,
+    /// Any other file, e.g. one read with `fs.readFile`.
+    Asset,
+}
+
+impl TracedFileKind {
+    fn from_path(path: &str) -> Self {
+        let extension = path
+            .rsplit_once('/')
+            .map_or(path, |(_, name)| name)
+            .rsplit_once('.')
+            .map(|(_, extension)| extension);
+        match extension {
+            Some("js" | "mjs" | "cjs" | "jsx" | "ts" | "mts" | "cts" | "tsx") => Self::Ecmascript,
+            Some("json") => Self::Json,
+            Some("node") => Self::Native,
+            _ => Self::Asset,
+        }
+    }
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct TracedFile {
+    /// The path relative to the root of the file system of the entry.
+    pub path: String,
+    pub kind: TracedFileKind,
+}
+
+/// The files needed by an entry, ordered by path. The entry is included.
+#[turbo_tasks::value(transparent)]
+pub struct TracedFiles(Vec<TracedFile>);
+
+/// Traces the files `entry` needs at runtime in `environment`: the modules it
+/// requires, the JSON files and native addons they load, and the files they
+/// access, e.g. with `fs.readFile(path.join(__dirname, "data.txt"))`.
+///
+/// The modules are analyzed like for `node-file-trace print`, so the result
+/// is the same as the files it prints for the entry. Issues of the analysis
+/// are attached to the result.
+#[turbo_tasks::function]
+pub async fn trace_files(
+    entry: FileSystemPathVc,
+    environment: EnvironmentVc,
+    enable_mdx: bool,
+) -> Result<TracedFilesVc> {
+    let context = trace_context(entry.root(), environment, enable_mdx);
+    let module = context.process(
+        SourceAssetVc::new(entry).into(),
+        Value::new(ReferenceType::Undefined),
+    );
+    let assets = all_assets(module);
+    IssueVc::attach_context(entry, "tracing files".to_string(), assets).await?;
+    let mut files = Vec::new();
+    for asset in assets.await?.iter() {
+        let path = asset.path().await?.path.clone();
+        let kind = TracedFileKind::from_path(&path);
+        files.push(TracedFile { path, kind });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|a, b| a.path == b.path);
+    Ok(TracedFilesVc::cell(files))
+}
+
+#[cfg(test)]
+mod tests {
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_fs::DiskFileSystemVc;
+    use turbo_tasks_memory::MemoryBackend;
+    use turbopack_core::environment::{
+        EnvironmentIntention, ExecutionEnvironment, NodeJsEnvironment,
+    };
+
+    use super::*;
+
+    #[test]
+    fn kinds_of_paths() {
+        assert_eq!(
+            TracedFileKind::from_path("src/index.js"),
+            TracedFileKind::Ecmascript
+        );
+        assert_eq!(
+            TracedFileKind::from_path("src/app.tsx"),
+            TracedFileKind::Ecmascript
+        );
+        assert_eq!(
+            TracedFileKind::from_path("package.json"),
+            TracedFileKind::Json
+        );
+        assert_eq!(
+            TracedFileKind::from_path("build/Release/addon.node"),
+            TracedFileKind::Native
+        );
+        assert_eq!(
+            TracedFileKind::from_path("data/file.txt"),
+            TracedFileKind::Asset
+        );
+        // Only the extension of the file name counts.
+        assert_eq!(
+            TracedFileKind::from_path("lib.js/LICENSE"),
+            TracedFileKind::Asset
+        );
+    }
+
+    #[test]
+    fn traces_required_and_read_files() {
+        crate::register();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async {
+                    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/trace");
+                    let fs = DiskFileSystemVc::new("trace".to_string(), fixture.to_string());
+                    let environment = EnvironmentVc::new(
+                        Value::new(ExecutionEnvironment::NodeJsLambda(
+                            NodeJsEnvironment::default().into(),
+                        )),
+                        Value::new(EnvironmentIntention::Api),
+                    );
+                    let files = trace_files(fs.root().join("index.js"), environment, false)
+                        .await?
+                        .iter()
+                        .map(|file| (file.path.clone(), file.kind))
+                        .collect::<Vec<_>>();
+                    // Builtin modules aren't files, and reads of paths without a
+                    // constant part aren't traced.
+                    assert_eq!(
+                        files,
+                        [
+                            ("config.json".to_string(), TracedFileKind::Json),
+                            ("data.txt".to_string(), TracedFileKind::Asset),
+                            ("index.js".to_string(), TracedFileKind::Ecmascript),
+                            ("lib.js".to_string(), TracedFileKind::Ecmascript),
+                            ("stat.txt".to_string(), TracedFileKind::Asset),
+                        ]
+                    );
+                    Ok(())
+                })
+                .await
+            })
+            .unwrap();
+    }
+}
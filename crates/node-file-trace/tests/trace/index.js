@@ -0,0 +1,9 @@
+const fs = require("fs");
+const path = require("path");
+
+const config = require("./lib.js");
+
+console.log(config, fs.readFileSync(path.join(__dirname, "data.txt"), "utf8"));
+console.log(fs.lstatSync(path.join(__dirname, "stat.txt")).size);
+// Would match every file, so it's not traced.
+console.log(fs.readFileSync(process.argv[2], "utf8"));
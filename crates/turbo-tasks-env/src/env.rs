@@ -0,0 +1,156 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::primitives::OptionStringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use crate::{
+    CommandLineProcessEnvVc, DotenvProcessEnvVc, EnvMapVc, FilterProcessEnvVc, ProcessEnv,
+    ProcessEnvVc,
+};
+
+/// How the dotenv files of a project are loaded.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default)]
+pub struct DotenvOptions {
+    /// The mode to load the files of, e.g. `development`, `production` or
+    /// `test`. Defaults to `NODE_ENV`, or `development` when it's not set.
+    pub mode: Option<String>,
+    /// Env variables starting with one of these prefixes are exposed to client
+    /// code, e.g. `NEXT_PUBLIC_`.
+    pub client_prefixes: Vec<String>,
+}
+
+/// The dotenv files that are loaded in `mode`, by priority. Like in most
+/// frameworks, `.env.local` is skipped in `test` mode so that tests have the
+/// same results for everyone.
+pub fn dotenv_files(mode: &str) -> Vec<String> {
+    let mut files = vec![format!(".env.{mode}.local")];
+    if mode != "test" {
+        files.push(".env.local".to_string());
+    }
+    files.push(format!(".env.{mode}"));
+    files.push(".env".to_string());
+    files
+}
+
+/// The environment variables of a project: the env of the process, layered
+/// with dotenv files.
+///
+/// Every key is read by its own task, and the value of a key is only updated
+/// when it actually changes. An edit to a dotenv file invalidates the file
+/// read, but only the readers of the keys whose values changed are
+/// recomputed, so the server doesn't need to be restarted for `.env.local`
+/// edits.
+#[turbo_tasks::value]
+pub struct Env {
+    process_env: ProcessEnvVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EnvVc {
+    #[turbo_tasks::function]
+    pub fn new(process_env: ProcessEnvVc) -> Self {
+        Env { process_env }.cell()
+    }
+
+    /// The env of the process, with the dotenv files `files` in
+    /// `project_path`. Files that come first have higher priority, and files
+    /// that don't exist are skipped.
+    #[turbo_tasks::function]
+    pub fn load(project_path: FileSystemPathVc, files: Vec<String>) -> Self {
+        let process_env = files.iter().fold(
+            CommandLineProcessEnvVc::new().as_process_env(),
+            |prior, file| {
+                DotenvProcessEnvVc::new(Some(prior), project_path.join(file)).as_process_env()
+            },
+        );
+        Self::new(process_env)
+    }
+
+    /// The env of the process, with the dotenv files of the mode in `options`:
+    /// `.env.$MODE.local`, `.env.local`, `.env.$MODE` and `.env`, in this
+    /// order of priority. Variables set in the process always win.
+    ///
+    /// Values can reference other variables with `$VAR` or `${VAR}`. A
+    /// reference is expanded with the value from the process, from a file with
+    /// a higher priority, or from an earlier line of the same file. `\$`
+    /// escapes a literal `$`.
+    #[turbo_tasks::function]
+    pub async fn load_dotenv(
+        project_path: FileSystemPathVc,
+        options: DotenvOptionsVc,
+    ) -> Result<Self> {
+        let mode = match &options.await?.mode {
+            Some(mode) => mode.clone(),
+            None => CommandLineProcessEnvVc::new()
+                .read("NODE_ENV")
+                .await?
+                .clone()
+                .unwrap_or_else(|| "development".to_string()),
+        };
+        Ok(Self::load(project_path, dotenv_files(&mode)))
+    }
+
+    /// The variables that are exposed to client code, i.e. the ones starting
+    /// with one of the `client_prefixes` of `options`.
+    #[turbo_tasks::function]
+    pub async fn client(self, options: DotenvOptionsVc) -> Result<Self> {
+        Ok(Self::new(
+            FilterProcessEnvVc::with_prefixes(
+                self.await?.process_env,
+                options.await?.client_prefixes.clone(),
+            )
+            .into(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn process_env(self) -> Result<ProcessEnvVc> {
+        Ok(self.await?.process_env)
+    }
+
+    /// Reads a single env variable. Ignores casing.
+    #[turbo_tasks::function]
+    pub async fn read(self, key: &str) -> Result<OptionStringVc> {
+        // The value is copied into a cell of this task, which is only updated when
+        // the value changes, so readers don't depend on the rest of the env.
+        let value = self.await?.process_env.read(key).await?;
+        Ok(OptionStringVc::cell(value.clone()))
+    }
+
+    /// Reads all env variables whose name starts with `prefix`. Ignores
+    /// casing.
+    #[turbo_tasks::function]
+    pub async fn read_prefix(self, prefix: &str) -> Result<EnvMapVc> {
+        let prefix = prefix.to_uppercase();
+        let all = self.await?.process_env.read_all().await?;
+        Ok(EnvMapVc::cell(
+            all.iter()
+                .filter(|(key, _)| key.to_uppercase().starts_with(&prefix))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<IndexMap<_, _>>(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dotenv_files;
+
+    #[test]
+    fn dotenv_files_by_priority() {
+        assert_eq!(
+            dotenv_files("development"),
+            [
+                ".env.development.local",
+                ".env.local",
+                ".env.development",
+                ".env"
+            ]
+        );
+        assert_eq!(
+            dotenv_files("test"),
+            [".env.test.local", ".env.test", ".env"]
+        );
+    }
+}
@@ -4,26 +4,45 @@ use turbo_tasks::primitives::OptionStringVc;
 
 use crate::{EnvMapVc, ProcessEnv, ProcessEnvVc};
 
-/// Filters env variables by some prefix. Casing of the env vars is ignored for
-/// filtering.
+/// Filters env variables by some prefixes. Casing of the env vars is ignored
+/// for filtering.
 #[turbo_tasks::value]
 pub struct FilterProcessEnv {
     prior: ProcessEnvVc,
-    filter: String,
+    filters: Vec<String>,
 }
 
 #[turbo_tasks::value_impl]
 impl FilterProcessEnvVc {
     #[turbo_tasks::function]
     pub fn new(prior: ProcessEnvVc, filter: String) -> Self {
+        Self::with_prefixes(prior, vec![filter])
+    }
+
+    /// Keeps the env variables that start with any of `filters`.
+    #[turbo_tasks::function]
+    pub fn with_prefixes(prior: ProcessEnvVc, filters: Vec<String>) -> Self {
         FilterProcessEnv {
             prior,
-            filter: filter.to_uppercase(),
+            filters: filters.iter().map(|f| f.to_uppercase()).collect(),
         }
         .cell()
     }
 }
 
+impl FilterProcessEnv {
+    fn matches(&self, name: &str) -> bool {
+        starts_with_any(name, &self.filters)
+    }
+}
+
+/// Whether `name` starts with one of the upper case `prefixes`, ignoring the
+/// casing of `name`.
+fn starts_with_any(name: &str, prefixes: &[String]) -> bool {
+    let name = name.to_uppercase();
+    prefixes.iter().any(|prefix| name.starts_with(prefix))
+}
+
 #[turbo_tasks::value_impl]
 impl ProcessEnv for FilterProcessEnv {
     #[turbo_tasks::function]
@@ -31,7 +50,7 @@ impl ProcessEnv for FilterProcessEnv {
         let prior = self.prior.read_all().await?;
         let mut filtered = IndexMap::new();
         for (key, value) in &*prior {
-            if key.to_uppercase().starts_with(&self.filter) {
+            if self.matches(key) {
                 filtered.insert(key.clone(), value.clone());
             }
         }
@@ -40,10 +59,26 @@ impl ProcessEnv for FilterProcessEnv {
 
     #[turbo_tasks::function]
     fn read(&self, name: &str) -> OptionStringVc {
-        if name.to_uppercase().starts_with(&self.filter) {
+        if self.matches(name) {
             self.prior.read(name)
         } else {
             OptionStringVc::cell(None)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::starts_with_any;
+
+    #[test]
+    fn filters_by_prefixes() {
+        let prefixes = vec!["NEXT_PUBLIC_".to_string(), "VITE_".to_string()];
+        assert!(starts_with_any("NEXT_PUBLIC_API", &prefixes));
+        assert!(starts_with_any("next_public_api", &prefixes));
+        assert!(starts_with_any("VITE_MODE", &prefixes));
+        assert!(!starts_with_any("API_NEXT_PUBLIC_", &prefixes));
+        assert!(!starts_with_any("SECRET", &prefixes));
+        assert!(!starts_with_any("SECRET", &[]));
+    }
+}
@@ -3,16 +3,20 @@
 mod command_line;
 mod custom;
 mod dotenv;
+mod env;
 mod filter;
 
-use std::{env, sync::Mutex};
+use std::sync::Mutex;
 
 use anyhow::Result;
 use indexmap::IndexMap;
 use turbo_tasks::primitives::OptionStringVc;
 
 pub use self::{
-    command_line::CommandLineProcessEnvVc, custom::CustomProcessEnvVc, dotenv::DotenvProcessEnvVc,
+    command_line::CommandLineProcessEnvVc,
+    custom::CustomProcessEnvVc,
+    dotenv::DotenvProcessEnvVc,
+    env::{dotenv_files, DotenvOptions, DotenvOptionsVc, Env, EnvVc},
     filter::FilterProcessEnvVc,
 };
 
@@ -0,0 +1,55 @@
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use turbo_tasks_fs::glob::Glob;
+
+/// Paths of a typical project with dependencies.
+fn paths() -> Vec<String> {
+    let mut paths = Vec::new();
+    for dir in ["src", "src/components", "src/pages/blog", "lib/utils"] {
+        for file in ["index.js", "Button.tsx", "styles.module.css", "README.md"] {
+            paths.push(format!("{dir}/{file}"));
+        }
+    }
+    for package in ["react", "next", "@swc/helpers", "lodash"] {
+        for file in [
+            "package.json",
+            "index.js",
+            "dist/esm/index.mjs",
+            "lib/a/b/c.js",
+        ] {
+            paths.push(format!("node_modules/{package}/{file}"));
+            paths.push(format!(
+                "node_modules/.pnpm/{package}@1.0.0/node_modules/{package}/{file}"
+            ));
+        }
+    }
+    paths
+}
+
+pub fn benchmark(c: &mut Criterion) {
+    let mut g = c.benchmark_group("glob");
+    let paths = paths();
+    g.throughput(Throughput::Elements(paths.len() as u64));
+
+    for pattern in [
+        "**/*.js",
+        "src/**/*.{js,jsx,ts,tsx}",
+        "**/node_modules/**",
+        "{src,lib}/**/{components,utils}/*.{ts,tsx}",
+    ] {
+        g.bench_with_input(BenchmarkId::new("parse", pattern), pattern, |b, pattern| {
+            b.iter(|| Glob::parse(pattern).unwrap())
+        });
+
+        let glob = Glob::parse(pattern).unwrap();
+        g.bench_with_input(BenchmarkId::new("execute", pattern), &glob, |b, glob| {
+            b.iter(|| {
+                paths
+                    .iter()
+                    .filter(|path| glob.execute(black_box(path)))
+                    .count()
+            })
+        });
+    }
+
+    g.finish();
+}
@@ -14,6 +14,9 @@ use notify::{watcher, RecursiveMode, Watcher};
 use tokio::runtime::Runtime;
 use turbo_tasks::event::Event;
 
+mod glob;
+mod rope;
+
 fn bench_file_watching(c: &mut Criterion) {
     let mut g = c.benchmark_group("turbo-tasks-fs");
     g.sample_size(10);
@@ -79,4 +82,6 @@ criterion_group!(
     config = Criterion::default();
     targets = bench_file_watching
 );
-criterion_main!(benches);
+criterion_group!(rope_benches, rope::benchmark);
+criterion_group!(glob_benches, glob::benchmark);
+criterion_main!(benches, rope_benches, glob_benches);
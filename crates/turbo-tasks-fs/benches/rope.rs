@@ -0,0 +1,64 @@
+use std::io::{Read, Write};
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use turbo_tasks_fs::rope::{Rope, RopeBuilder};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// A line similar to the ones of generated chunk code.
+static LINE: &str =
+    "    \"[project]/src/index.js (ecmascript)\": (({ r: __turbopack_require__ }) => {\n";
+
+/// Builds a rope the way chunk code is built: many small pushes of owned and
+/// static bytes, and some shared ropes of other modules.
+fn build_chunk(lines: usize, module: &Rope) -> Rope {
+    let mut builder = RopeBuilder::default();
+    for i in 0..lines {
+        builder += LINE;
+        write!(builder, "/* {i} */").unwrap();
+        if i % 100 == 0 {
+            builder.concat(module);
+        }
+    }
+    builder.build()
+}
+
+pub fn benchmark(c: &mut Criterion) {
+    let mut g = c.benchmark_group("rope");
+    let module = Rope::from(LINE.repeat(50));
+
+    for lines in [100, 10_000] {
+        let rope = build_chunk(lines, &module);
+        g.throughput(Throughput::Bytes(rope.len() as u64));
+
+        g.bench_with_input(BenchmarkId::new("build", lines), &lines, |b, &lines| {
+            b.iter(|| build_chunk(lines, &module))
+        });
+
+        g.bench_with_input(BenchmarkId::new("read", lines), &rope, |b, rope| {
+            b.iter(|| {
+                let mut buf = Vec::with_capacity(rope.len());
+                rope.read().read_to_end(&mut buf).unwrap();
+                buf
+            })
+        });
+
+        g.bench_with_input(BenchmarkId::new("iterate", lines), &rope, |b, rope| {
+            b.iter(|| rope.read().map(|bytes| bytes.len()).sum::<usize>())
+        });
+
+        g.bench_with_input(BenchmarkId::new("to_str", lines), &rope, |b, rope| {
+            b.iter(|| black_box(rope.to_str().unwrap().len()))
+        });
+
+        g.bench_with_input(BenchmarkId::new("hash", lines), &rope, |b, rope| {
+            b.iter(|| hash_xxh3_hash64(rope.clone()))
+        });
+
+        let flat = Rope::from(rope.to_str().unwrap().into_owned());
+        g.bench_with_input(BenchmarkId::new("eq", lines), &rope, |b, rope| {
+            b.iter(|| black_box(rope == &flat))
+        });
+    }
+
+    g.finish();
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use turbo_tasks_fs_fuzz::{build, check, BuildOp};
+
+fuzz_target!(|ops: Vec<BuildOp>| {
+    let built = build(&ops);
+    for modeled in &built {
+        check(modeled, &built);
+    }
+});
@@ -0,0 +1,61 @@
+#![no_main]
+
+use std::io::{BufRead, Read};
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use turbo_tasks_fs_fuzz::{build, BuildOp};
+
+#[derive(Arbitrary, Debug)]
+enum ReadOp {
+    Read(u8),
+    FillBufConsume(u8),
+    Next,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    build: Vec<BuildOp>,
+    reads: Vec<ReadOp>,
+}
+
+fuzz_target!(|input: Input| {
+    let modeled = build(&input.build).pop().unwrap();
+    let model = &modeled.model;
+    let mut reader = modeled.rope.read();
+    // The position of the reader in the model.
+    let mut pos = 0;
+
+    for op in &input.reads {
+        let remaining = &model[pos..];
+        match *op {
+            ReadOp::Read(len) => {
+                let mut buf = vec![0; len as usize];
+                let read = reader.read(&mut buf).unwrap();
+                assert_eq!(read, (len as usize).min(remaining.len()));
+                assert_eq!(&buf[..read], &remaining[..read]);
+                pos += read;
+            }
+            ReadOp::FillBufConsume(len) => {
+                let buf = reader.fill_buf().unwrap();
+                assert_eq!(buf.is_empty(), remaining.is_empty());
+                assert!(remaining.starts_with(buf));
+                let amount = (len as usize).min(buf.len());
+                reader.consume(amount);
+                pos += amount;
+            }
+            ReadOp::Next => match reader.next() {
+                Some(bytes) => {
+                    assert!(!bytes.is_empty(), "reader yielded an empty section");
+                    assert!(remaining.starts_with(&bytes));
+                    pos += bytes.len();
+                }
+                None => assert!(remaining.is_empty()),
+            },
+        }
+    }
+
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(&rest, &model[pos..]);
+});
@@ -0,0 +1,139 @@
+//! A reference model for [Rope], shared by the fuzz targets. Every rope is
+//! built alongside a `Vec<u8>` with the same contents, and [check] asserts
+//! that the rope agrees with it.
+
+use std::io::{Read, Write};
+
+use arbitrary::Arbitrary;
+use turbo_tasks_fs::rope::{Rope, RopeBuilder};
+use turbo_tasks_hash::{DeterministicHasher, Xxh3Hash64Hasher};
+
+/// Static memory that `push_static_bytes` takes slices of. It's longer than a
+/// `Bytes` reference, so both the owned and the shared code paths are hit.
+static STATIC_BYTES: &[u8] = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+                              eiusmod tempor ünïcödé 🦀"
+    .as_bytes();
+
+#[derive(Arbitrary, Debug)]
+pub enum BuildOp {
+    PushBytes(Vec<u8>),
+    PushStatic {
+        start: u8,
+        len: u8,
+    },
+    Write(Vec<u8>),
+    Flush,
+    /// Concatenates a previously built rope, picked by index.
+    Concat(u8),
+    /// Builds the current builder into a rope and starts a new builder.
+    Build,
+}
+
+/// A rope and the bytes it must contain.
+pub struct Modeled {
+    pub rope: Rope,
+    pub model: Vec<u8>,
+}
+
+/// Applies `ops`, returning all ropes that were built, including one for the
+/// final builder.
+pub fn build(ops: &[BuildOp]) -> Vec<Modeled> {
+    let mut built: Vec<Modeled> = Vec::new();
+    let mut builder = RopeBuilder::default();
+    let mut model = Vec::new();
+
+    for op in ops {
+        match op {
+            BuildOp::PushBytes(bytes) => {
+                builder.push_bytes(bytes);
+                model.extend_from_slice(bytes);
+            }
+            BuildOp::PushStatic { start, len } => {
+                let start = *start as usize % STATIC_BYTES.len();
+                let end = (start + *len as usize).min(STATIC_BYTES.len());
+                builder.push_static_bytes(&STATIC_BYTES[start..end]);
+                model.extend_from_slice(&STATIC_BYTES[start..end]);
+            }
+            BuildOp::Write(bytes) => {
+                assert_eq!(builder.write(bytes).unwrap(), bytes.len());
+                model.extend_from_slice(bytes);
+            }
+            BuildOp::Flush => builder.flush().unwrap(),
+            BuildOp::Concat(index) => {
+                if !built.is_empty() {
+                    let other = &built[*index as usize % built.len()];
+                    builder.concat(&other.rope);
+                    model.extend_from_slice(&other.model);
+                }
+            }
+            BuildOp::Build => {
+                assert_eq!(builder.len(), model.len());
+                let rope = std::mem::take(&mut builder).build();
+                built.push(Modeled {
+                    rope,
+                    model: std::mem::take(&mut model),
+                });
+            }
+        }
+        assert_eq!(builder.len(), model.len());
+        assert_eq!(builder.is_empty(), model.is_empty());
+    }
+
+    built.push(Modeled {
+        rope: builder.build(),
+        model,
+    });
+    built
+}
+
+fn rope_hash(rope: &Rope) -> u64 {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_ref(rope);
+    hasher.finish()
+}
+
+/// The hash a rope with the contents of `model` must have, independent of its
+/// structure.
+fn model_hash(model: &[u8]) -> u64 {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_usize(model.len());
+    hasher.write_bytes(model);
+    hasher.finish()
+}
+
+/// Asserts that the rope agrees with its model, and that comparing it to
+/// `others` agrees with comparing the models.
+pub fn check(modeled: &Modeled, others: &[Modeled]) {
+    let Modeled { rope, model } = modeled;
+    assert_eq!(rope.len(), model.len());
+    assert_eq!(rope.is_empty(), model.is_empty());
+
+    let mut read = Vec::new();
+    rope.read().read_to_end(&mut read).unwrap();
+    assert_eq!(&read, model);
+
+    // The iterator must never yield empty sections.
+    let mut iterated = Vec::new();
+    for bytes in rope.read() {
+        assert!(!bytes.is_empty(), "rope yielded an empty section");
+        iterated.extend_from_slice(&bytes);
+    }
+    assert_eq!(&iterated, model);
+
+    match std::str::from_utf8(model) {
+        Ok(str) => assert_eq!(rope.to_str().unwrap(), str),
+        Err(_) => assert!(rope.to_str().is_err()),
+    }
+
+    let flat = Rope::from(model.clone());
+    assert_eq!(rope, &flat);
+    assert_eq!(rope_hash(rope), model_hash(model));
+    assert_eq!(rope_hash(rope), rope_hash(&flat));
+
+    for other in others {
+        assert_eq!(rope == &other.rope, model == &other.model);
+        if model == &other.model {
+            assert_eq!(rope_hash(rope), rope_hash(&other.rope));
+        }
+    }
+}
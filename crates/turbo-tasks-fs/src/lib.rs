@@ -6,6 +6,7 @@
 #![feature(main_separator_str)]
 #![feature(box_syntax)]
 #![feature(round_char_boundary)]
+#![feature(write_all_vectored)]
 
 pub mod attach;
 pub mod embed;
@@ -18,6 +19,7 @@ mod retry;
 pub mod rope;
 pub mod source_context;
 pub mod util;
+mod write;
 
 use std::{
     borrow::Cow,
@@ -29,7 +31,7 @@ use std::{
     path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::{
         mpsc::{channel, RecvError, TryRecvError},
-        Arc, Mutex,
+        Arc, Mutex, RwLock,
     },
     time::Duration,
 };
@@ -63,6 +65,7 @@ use crate::util::is_windows_raw_path;
 use crate::{
     retry::{retry_blocking, retry_future},
     rope::{Rope, RopeReadRef, RopeReader},
+    write::write_file,
 };
 
 #[turbo_tasks::value_trait]
@@ -93,6 +96,11 @@ pub struct DiskFileSystem {
     #[turbo_tasks(debug_ignore, trace_ignore)]
     #[serde(skip)]
     watcher: Mutex<Option<RecommendedWatcher>>,
+    /// The packages in the top-level `node_modules` directory whose changes
+    /// are watched, or None when all are watched.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    #[serde(skip)]
+    watched_packages: Arc<RwLock<Option<HashSet<String>>>>,
 }
 
 impl DiskFileSystem {
@@ -117,6 +125,14 @@ impl DiskFileSystem {
         }
     }
 
+    /// Limits watching of the top-level `node_modules` directory to these
+    /// packages, e.g. to the packages installed by the lockfile. Changes of
+    /// other packages don't invalidate reads. Directories starting with a `.`,
+    /// like `.pnpm` or `.cache`, are always watched.
+    pub fn watch_packages(&self, packages: impl IntoIterator<Item = String>) {
+        *self.watched_packages.write().unwrap() = Some(packages.into_iter().collect());
+    }
+
     pub fn start_watching(&self) -> Result<()> {
         let mut watcher_guard = self.watcher.lock().unwrap();
         if watcher_guard.is_some() {
@@ -124,6 +140,7 @@ impl DiskFileSystem {
         }
         let invalidator_map = self.invalidator_map.clone();
         let dir_invalidator_map = self.dir_invalidator_map.clone();
+        let watched_packages = self.watched_packages.clone();
         let root = self.root.clone();
         // Create a channel to receive the events.
         let (tx, rx) = channel();
@@ -156,23 +173,34 @@ impl DiskFileSystem {
                     RecvError => TryRecvError::Disconnected,
                 });
                 loop {
+                    let is_watched =
+                        |path: &Path| is_watched_path(Path::new(&root), &watched_packages, path);
                     match event {
                         Ok(DebouncedEvent::Write(path)) => {
-                            batched_invalidate_path.insert(path);
+                            if is_watched(&path) {
+                                batched_invalidate_path.insert(path);
+                            }
                         }
                         Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Remove(path)) => {
-                            batched_invalidate_path_and_children.insert(path.clone());
-                            batched_invalidate_path_and_children_dir.insert(path.clone());
+                            if is_watched(&path) {
+                                batched_invalidate_path_and_children.insert(path.clone());
+                                batched_invalidate_path_and_children_dir.insert(path.clone());
+                            }
+                            // The listing of the parent changes either way.
                             if let Some(parent) = path.parent() {
                                 batched_invalidate_path_dir.insert(PathBuf::from(parent));
                             }
                         }
                         Ok(DebouncedEvent::Rename(source, destination)) => {
-                            batched_invalidate_path_and_children.insert(source.clone());
+                            if is_watched(&source) {
+                                batched_invalidate_path_and_children.insert(source.clone());
+                            }
                             if let Some(parent) = source.parent() {
                                 batched_invalidate_path_dir.insert(PathBuf::from(parent));
                             }
-                            batched_invalidate_path_and_children.insert(destination.clone());
+                            if is_watched(&destination) {
+                                batched_invalidate_path_and_children.insert(destination.clone());
+                            }
                             if let Some(parent) = destination.parent() {
                                 batched_invalidate_path_dir.insert(PathBuf::from(parent));
                             }
@@ -279,6 +307,42 @@ impl DiskFileSystem {
     }
 }
 
+/// Returns whether changes of the path are watched, see
+/// [DiskFileSystem::watch_packages].
+fn is_watched_path(
+    root: &Path,
+    watched_packages: &RwLock<Option<HashSet<String>>>,
+    path: &Path,
+) -> bool {
+    let Some(packages) = &*watched_packages.read().unwrap() else {
+        return true;
+    };
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    let mut components = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy());
+    if components.next().as_deref() != Some("node_modules") {
+        return true;
+    }
+    let Some(first) = components.next() else {
+        return true;
+    };
+    if first.starts_with('.') {
+        return true;
+    }
+    let name = if first.starts_with('@') {
+        let Some(second) = components.next() else {
+            return true;
+        };
+        format!("{first}/{second}")
+    } else {
+        first.into_owned()
+    };
+    packages.contains(&name)
+}
+
 pub fn path_to_key(path: impl AsRef<Path>) -> String {
     path.as_ref().to_string_lossy().to_string()
 }
@@ -298,6 +362,7 @@ impl DiskFileSystemVc {
             invalidator_map: Arc::new(InvalidatorMap::new()),
             dir_invalidator_map: Arc::new(InvalidatorMap::new()),
             watcher: Mutex::new(None),
+            watched_packages: Default::default(),
         };
 
         Ok(Self::cell(instance))
@@ -506,19 +571,9 @@ impl FileSystem for DiskFileSystem {
                             })?;
                     }
                 }
-                let full_path_to_write = full_path.clone();
-                retry_future(move || {
-                    let full_path = full_path_to_write.clone();
-                    async move {
-                        let mut f = fs::File::create(&full_path).await?;
-                        tokio::io::copy(&mut file.read(), &mut f).await?;
-                        #[cfg(target_family = "unix")]
-                        f.set_permissions(file.meta.permissions.into()).await?;
-                        Ok::<(), io::Error>(())
-                    }
-                })
-                .await
-                .with_context(|| format!("failed to write to {}", full_path.display()))?;
+                write_file(&full_path, &file.content, file.meta.permissions)
+                    .await
+                    .with_context(|| format!("failed to write to {}", full_path.display()))?;
             }
             FileContent::NotFound => {
                 retry_future(|| fs::remove_file(full_path.clone()))
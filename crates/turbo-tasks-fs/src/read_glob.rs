@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
+use turbo_tasks::TryJoinIterExt;
 
 use crate::{glob::GlobVc, DirectoryContent, DirectoryEntry, FileSystemPathVc};
 
+/// How many directories [ReadGlobResultVc::all_matches] waits for at once.
+const SCAN_BATCH_SIZE: usize = 64;
+
 #[turbo_tasks::value]
 #[derive(Default, Debug)]
 pub struct ReadGlobResult {
@@ -11,6 +15,38 @@ pub struct ReadGlobResult {
     pub inner: HashMap<String, ReadGlobResultVc>,
 }
 
+impl ReadGlobResultVc {
+    /// Collects the matches in all directories, with their paths relative to
+    /// the directory of the glob.
+    ///
+    /// The directories of a level are read in parallel, as the reads of
+    /// subdirectories start as soon as their parent is read. They are awaited
+    /// in batches of [SCAN_BATCH_SIZE] rather than one by one.
+    ///
+    /// DETERMINISM: Result is in random order. Either sort result or do not
+    /// depend on the order.
+    pub async fn all_matches(self) -> Result<Vec<(String, DirectoryEntry)>> {
+        let mut matches = Vec::new();
+        let mut level = vec![self];
+        while !level.is_empty() {
+            let mut next_level = Vec::new();
+            for batch in level.chunks(SCAN_BATCH_SIZE) {
+                for result in batch.iter().copied().try_join().await? {
+                    matches.extend(
+                        result
+                            .results
+                            .iter()
+                            .map(|(path, entry)| (path.clone(), *entry)),
+                    );
+                    next_level.extend(result.inner.values().copied());
+                }
+            }
+            level = next_level;
+        }
+        Ok(matches)
+    }
+}
+
 /// Reads matches of a glob pattern.
 ///
 /// DETERMINISM: Result is in random order. Either sort result or do not depend
@@ -75,3 +111,60 @@ async fn read_glob_internal(
     }
     Ok(ReadGlobResultVc::cell(result))
 }
+
+#[cfg(test)]
+mod tests {
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_memory::MemoryBackend;
+
+    use super::*;
+    use crate::DiskFileSystemVc;
+
+    #[test]
+    fn collects_matches_of_all_directories() {
+        crate::register();
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        };
+        write("index.js");
+        write("README.md");
+        write("lib/a/b/deep.js");
+        write("lib/a/b/deep.txt");
+        // More directories on one level than are awaited at once.
+        for i in 0..SCAN_BATCH_SIZE + 6 {
+            write(&format!("pages/{i:03}/index.js"));
+        }
+        let root = dir.path().to_string_lossy().to_string();
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async move {
+                    let fs = DiskFileSystemVc::new("test".to_string(), root);
+                    let mut matches = fs
+                        .root()
+                        .read_glob(GlobVc::new("**/*.js"), false)
+                        .all_matches()
+                        .await?
+                        .into_iter()
+                        .map(|(path, _)| path)
+                        .collect::<Vec<_>>();
+                    matches.sort();
+                    let mut expected = vec!["index.js".to_string(), "lib/a/b/deep.js".to_string()];
+                    expected
+                        .extend((0..SCAN_BATCH_SIZE + 6).map(|i| format!("pages/{i:03}/index.js")));
+                    expected.sort();
+                    assert_eq!(matches, expected);
+                    Ok(())
+                })
+                .await
+            })
+            .unwrap();
+    }
+}
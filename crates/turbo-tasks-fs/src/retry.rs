@@ -1,10 +1,18 @@
 use std::{future::Future, io, io::ErrorKind, path::Path, thread::sleep, time::Duration};
 
 use futures_retry::{ErrorHandler, FutureRetry, RetryPolicy};
-use tokio::task::spawn_blocking;
+use once_cell::sync::Lazy;
+use tokio::{sync::Semaphore, task::spawn_blocking};
 
 const MAX_RETRY_ATTEMPTS: usize = 10;
 
+/// The maximum number of blocking fs operations, e.g. directory reads, which
+/// run at the same time. Scanning a large directory tree in parallel would
+/// otherwise occupy all threads of the blocking pool.
+const MAX_CONCURRENT_IO: usize = 256;
+
+static IO_SEMAPHORE: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(MAX_CONCURRENT_IO));
+
 pub(crate) async fn retry_future<R, F, Fut>(func: F) -> io::Result<R>
 where
     F: FnMut() -> Fut + Unpin,
@@ -84,8 +92,52 @@ where
     F: FnOnce() -> io::Result<T> + Send + 'static,
     T: Send + 'static,
 {
+    let _permit = IO_SEMAPHORE
+        .acquire()
+        .await
+        .map_err(|_| io::Error::new(ErrorKind::Other, "io semaphore closed"))?;
     match spawn_blocking(f).await {
         Ok(res) => res,
         Err(_) => Err(io::Error::new(ErrorKind::Other, "background task failed")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn bounds_concurrent_io() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tasks = (0..MAX_CONCURRENT_IO + 44)
+                    .map(|_| {
+                        let running = running.clone();
+                        let max_running = max_running.clone();
+                        tokio::spawn(asyncify(move || {
+                            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_running.fetch_max(now, Ordering::SeqCst);
+                            sleep(Duration::from_millis(20));
+                            running.fetch_sub(1, Ordering::SeqCst);
+                            Ok(())
+                        }))
+                    })
+                    .collect::<Vec<_>>();
+                for task in tasks {
+                    task.await.unwrap().unwrap();
+                }
+            });
+        let max_running = max_running.load(Ordering::SeqCst);
+        assert!(max_running > 1);
+        assert!(max_running <= MAX_CONCURRENT_IO);
+    }
+}
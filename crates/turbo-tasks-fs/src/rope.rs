@@ -6,20 +6,26 @@ use std::{
     mem,
     ops::{AddAssign, Deref},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context as TaskContext, Poll},
 };
 
-use anyhow::{Context, Result};
-use bytes::{Buf, Bytes};
+use anyhow::{bail, Context, Result};
+use bytes::{Buf, Bytes, BytesMut};
 use futures::Stream;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use tokio::io::{AsyncRead, ReadBuf};
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
 use turbo_tasks_hash::{DeterministicHash, DeterministicHasher};
 use RopeElem::{Local, Shared};
 
 static EMPTY_BUF: &[u8] = &[];
 
+/// The number of local bytes held by all alive [InnerRope]s.
+static OWNED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
 /// A Rope provides an efficient structure for sharing bytes/strings between
 /// multiple sources. Cloning a Rope is extremely cheap (Arc and usize), and
 /// the sharing contents of one Rope can be shared by just cloning an Arc.
@@ -39,7 +45,30 @@ pub struct Rope {
 /// An Arc container for ropes. This indirection allows for easily sharing the
 /// contents between Ropes (and also RopeBuilders/RopeReaders).
 #[derive(Clone, Debug, Default)]
-struct InnerRope(Arc<Box<[RopeElem]>>);
+struct InnerRope(Arc<RopeElems>);
+
+/// The elements of an [InnerRope]. The local bytes are accounted in
+/// [OWNED_BYTES] for as long as the elements are alive.
+#[derive(Debug, Default)]
+struct RopeElems(Box<[RopeElem]>);
+
+impl RopeElems {
+    fn owned_bytes(&self) -> usize {
+        self.0
+            .iter()
+            .map(|el| match el {
+                Local(bytes) => bytes.len(),
+                Shared(_) => 0,
+            })
+            .sum()
+    }
+}
+
+impl Drop for RopeElems {
+    fn drop(&mut self) {
+        OWNED_BYTES.fetch_sub(self.owned_bytes(), Ordering::Relaxed);
+    }
+}
 
 /// Differentiates the types of stored bytes in a rope.
 #[derive(Clone, Debug)]
@@ -92,6 +121,13 @@ enum Uncommitted {
 }
 
 impl Rope {
+    /// The number of bytes that are held by all alive ropes. Bytes that are
+    /// shared between ropes are only counted once, but bytes that are sliced
+    /// from a larger buffer only count the slice.
+    pub fn total_owned_bytes() -> usize {
+        OWNED_BYTES.load(Ordering::Relaxed)
+    }
+
     pub fn len(&self) -> usize {
         self.length
     }
@@ -372,7 +408,7 @@ impl DeterministicHash for InnerRope {
     /// shared InnerRopes won't either), so the exact structure isn't
     /// relevant at this point.
     fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
-        for v in self.0.iter() {
+        for v in self.iter() {
             v.deterministic_hash(state);
         }
     }
@@ -395,6 +431,8 @@ impl From<Box<[RopeElem]>> for InnerRope {
                 }
             }
         }
+        let els = RopeElems(els);
+        OWNED_BYTES.fetch_add(els.owned_bytes(), Ordering::Relaxed);
         InnerRope(Arc::new(els))
     }
 }
@@ -436,10 +474,10 @@ impl PartialEq for InnerRope {
 impl Eq for InnerRope {}
 
 impl Deref for InnerRope {
-    type Target = Arc<Box<[RopeElem]>>;
+    type Target = [RopeElem];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.0 .0
     }
 }
 
@@ -461,6 +499,10 @@ pub struct RopeReader {
     /// The Rope's tree is kept as a cloned stack, allowing us to accomplish
     /// incremental yielding.
     stack: Vec<StackElem>,
+
+    /// Sections smaller than this are merged into chunks of this size when
+    /// iterating or streaming, see [RopeReader::with_chunk_size].
+    chunk_size: usize,
 }
 
 /// A StackElem holds the current index into either a Bytes or a shared Rope.
@@ -481,50 +523,79 @@ impl RopeReader {
         } else {
             RopeReader {
                 stack: vec![StackElem::from(rope)],
+                chunk_size: 0,
             }
         }
     }
 
+    /// Merges small sections of the rope into chunks of up to `size` bytes
+    /// when it's used as an [Iterator], [Stream], [BufRead] or [AsyncBufRead],
+    /// so a rope of many tiny sections doesn't turn into as many tiny writes
+    /// to a socket. Sections of at least `size` bytes are still yielded
+    /// without copying.
+    ///
+    /// [Read] and [AsyncRead] copy into the buffer of the caller and fill as
+    /// much of it as possible, so the size of their reads only depends on that
+    /// buffer. Consumers which write to a socket, e.g. `tokio::io::copy_buf`,
+    /// should use the [AsyncBufRead] implementation instead.
+    pub fn with_chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size;
+        self
+    }
+
+    /// Copies the following small sections into a chunk which starts with
+    /// `first`, until the chunk reaches the chunk size.
+    fn merge_into(&mut self, first: Bytes) -> Bytes {
+        let size = self.chunk_size;
+        let mut chunk = BytesMut::with_capacity(size);
+        chunk.extend_from_slice(&first);
+        while chunk.len() < size {
+            let bytes = self.current();
+            // Large sections are yielded on their own, instead of copying them.
+            if bytes.is_empty() || bytes.len() >= size {
+                break;
+            }
+            let amount = min(bytes.len(), size - chunk.len());
+            chunk.extend_from_slice(&bytes[0..amount]);
+            self.consume(amount);
+        }
+        chunk.freeze()
+    }
+
     /// A shared implementation for reading bytes. This takes the basic
     /// operations needed for both Read and AsyncRead.
     fn read_internal(&mut self, want: usize, buf: &mut ReadBuf<'_>) -> usize {
         let mut remaining = want;
 
         while remaining > 0 {
-            let mut bytes = match self.next() {
-                None => break,
-                Some(b) => b,
-            };
+            let bytes = self.current();
+            if bytes.is_empty() {
+                break;
+            }
 
             let amount = min(bytes.len(), remaining);
 
             buf.put_slice(&bytes[0..amount]);
 
-            if amount < bytes.len() {
-                bytes.advance(amount);
-                self.stack.push(StackElem::Local(bytes))
-            }
+            self.consume(amount);
             remaining -= amount;
         }
 
         want - remaining
     }
-}
-
-impl Iterator for RopeReader {
-    type Item = Bytes;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // Iterates the rope's elements recursively until we find the next Local
-        // section, returning its Bytes.
+    /// Expands the shared ropes on top of the stack until the top holds bytes,
+    /// and returns the unread part of them without copying. Returns an empty
+    /// slice once all bytes are read.
+    fn current(&mut self) -> &[u8] {
         loop {
             let (inner, mut index) = match self.stack.pop() {
-                None => return None,
-                Some(StackElem::Local(b)) => {
-                    debug_assert!(!b.is_empty(), "must not have empty Bytes section");
-                    return Some(b);
-                }
+                None => return EMPTY_BUF,
                 Some(StackElem::Shared(r, i)) => (r, i),
+                Some(el) => {
+                    self.stack.push(el);
+                    break;
+                }
             };
 
             let el = inner[index].clone();
@@ -535,6 +606,32 @@ impl Iterator for RopeReader {
 
             self.stack.push(StackElem::from(el));
         }
+
+        let Some(StackElem::Local(bytes)) = self.stack.last() else {
+            unreachable!()
+        };
+        debug_assert!(!bytes.is_empty(), "must not have empty Bytes section");
+        bytes
+    }
+}
+
+impl Iterator for RopeReader {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Iterates the rope's elements recursively until we find the next Local
+        // section, returning its Bytes.
+        if self.current().is_empty() {
+            return None;
+        }
+        let Some(StackElem::Local(bytes)) = self.stack.pop() else {
+            unreachable!()
+        };
+        if bytes.len() < self.chunk_size {
+            Some(self.merge_into(bytes))
+        } else {
+            Some(bytes)
+        }
     }
 }
 
@@ -558,22 +655,16 @@ impl AsyncRead for RopeReader {
 
 impl BufRead for RopeReader {
     fn fill_buf(&mut self) -> IoResult<&[u8]> {
-        // Returns the full buffer without coping any data. The same bytes will
-        // continue to be returned until [consume] is called.
-        let bytes = match self.next() {
-            None => return Ok(EMPTY_BUF),
-            Some(b) => b,
-        };
-
-        // This is just so we can get a reference to the asset that is kept alive by the
-        // RopeReader itself. We can then auto-convert that reference into the needed u8
-        // slice reference.
-        self.stack.push(StackElem::Local(bytes));
-        let Some(StackElem::Local(bytes)) = self.stack.last() else {
-            unreachable!()
-        };
-
-        Ok(bytes)
+        // Returns the full buffer without coping any data, unless small sections are
+        // merged. The same bytes will continue to be returned until [consume] is
+        // called.
+        if self.current().len() < self.chunk_size {
+            if let Some(StackElem::Local(bytes)) = self.stack.pop() {
+                let chunk = self.merge_into(bytes);
+                self.stack.push(StackElem::Local(chunk));
+            }
+        }
+        Ok(self.current())
     }
 
     fn consume(&mut self, amt: usize) {
@@ -589,6 +680,16 @@ impl BufRead for RopeReader {
     }
 }
 
+impl AsyncBufRead for RopeReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(self.get_mut().fill_buf())
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        BufRead::consume(self.get_mut(), amt)
+    }
+}
+
 impl Stream for RopeReader {
     // The Result<Bytes> item type is required for this to be streamable into a
     // [Hyper::Body].
@@ -617,9 +718,108 @@ impl From<RopeElem> for StackElem {
     }
 }
 
+/// A part of a [RopeTemplate].
+#[derive(Debug, Clone, Copy)]
+pub enum TemplatePart {
+    /// Code that is the same for every rendering of the template. It's pushed
+    /// as a reference to static memory, so it's shared by all ropes the
+    /// template is rendered into.
+    Static(&'static str),
+    /// A named placeholder, which is replaced by a [TemplateValue] when the
+    /// template is rendered.
+    Dynamic(&'static str),
+}
+
+/// A value that replaces a placeholder of a [RopeTemplate].
+#[derive(Debug, Clone, Copy)]
+pub enum TemplateValue<'a> {
+    /// Bytes that are copied into the rope.
+    Str(&'a str),
+    /// A rope whose contents are shared with the rendered rope.
+    Rope(&'a Rope),
+}
+
+impl<'a> From<&'a str> for TemplateValue<'a> {
+    fn from(value: &'a str) -> Self {
+        TemplateValue::Str(value)
+    }
+}
+
+impl<'a> From<&'a Rope> for TemplateValue<'a> {
+    fn from(value: &'a Rope) -> Self {
+        TemplateValue::Rope(value)
+    }
+}
+
+/// A template for generated code that consists of static code and named
+/// placeholders for the parts that differ between renderings, e.g.
+///
+/// ```
+/// # use turbo_tasks_fs::rope::{RopeBuilder, RopeTemplate, TemplatePart::*};
+/// static TEMPLATE: RopeTemplate = RopeTemplate::new(&[
+///     Static("const CHUNK_BASE_PATH = "),
+///     Dynamic("base_path"),
+///     Static(";\n"),
+/// ]);
+///
+/// let mut rope = RopeBuilder::default();
+/// TEMPLATE.render(&mut rope, &[("base_path", "\"/_next/\"".into())]).unwrap();
+/// ```
+///
+/// Unlike formatting the code into the rope, the static parts are not copied,
+/// so large static code is shared between all ropes the template is rendered
+/// into.
+#[derive(Debug, Clone, Copy)]
+pub struct RopeTemplate {
+    parts: &'static [TemplatePart],
+}
+
+impl RopeTemplate {
+    pub const fn new(parts: &'static [TemplatePart]) -> Self {
+        RopeTemplate { parts }
+    }
+
+    /// Pushes the template onto the rope, replacing the placeholders with the
+    /// values of the same name. Fails when a placeholder has no value.
+    pub fn render(
+        &self,
+        rope: &mut RopeBuilder,
+        values: &[(&str, TemplateValue<'_>)],
+    ) -> Result<()> {
+        for part in self.parts {
+            match *part {
+                TemplatePart::Static(code) => *rope += code,
+                TemplatePart::Dynamic(name) => {
+                    let Some((_, value)) = values.iter().find(|(n, _)| *n == name) else {
+                        bail!("no value for placeholder {name} of the template");
+                    };
+                    match value {
+                        TemplateValue::Str(str) => rope.push_bytes(str.as_bytes()),
+                        TemplateValue::Rope(other) => *rope += *other,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Rope, RopeBuilder};
+    use std::io::BufRead;
+
+    use super::{Rope, RopeBuilder, RopeTemplate, TemplatePart::*};
+
+    #[test]
+    fn counts_shared_bytes_once() {
+        let shared = Rope::from("shared".to_string());
+        let mut builder = RopeBuilder::default();
+        builder.push_bytes(b"local");
+        builder += &shared;
+        let rope = builder.build();
+        assert_eq!(rope.data.0.owned_bytes(), "local".len());
+        assert_eq!(shared.data.0.owned_bytes(), "shared".len());
+    }
 
     #[test]
     fn empty_build_without_pushes() {
@@ -671,4 +871,63 @@ mod test {
         let mut reader = empty.read();
         assert!(reader.next().is_none());
     }
+
+    #[test]
+    fn render_template() {
+        static TEMPLATE: RopeTemplate = RopeTemplate::new(&[
+            Static("(() => {\n    const runtime = "),
+            Dynamic("runtime"),
+            Static(";\n    runtime.load("),
+            Dynamic("chunk"),
+            Static(");\n})();\n"),
+        ]);
+        let runtime = Rope::from("{ load() {} }");
+        let mut builder = RopeBuilder::default();
+        TEMPLATE
+            .render(
+                &mut builder,
+                &[("chunk", "\"a.js\"".into()), ("runtime", (&runtime).into())],
+            )
+            .unwrap();
+        assert_eq!(
+            builder.build().to_str().unwrap(),
+            "(() => {\n    const runtime = { load() {} };\n    runtime.load(\"a.js\");\n})();\n"
+        );
+
+        let mut builder = RopeBuilder::default();
+        assert!(TEMPLATE
+            .render(&mut builder, &[("chunk", "\"a.js\"".into())])
+            .is_err());
+    }
+
+    #[test]
+    fn merges_small_sections() {
+        let mut builder = RopeBuilder::default();
+        let large = Rope::from("x".repeat(16));
+        for _ in 0..10 {
+            builder += &Rope::from("abc");
+        }
+        builder += &large;
+        builder += &Rope::from("abc");
+        let rope = builder.build();
+
+        assert_eq!(rope.read().count(), 12);
+        let chunks = rope.read().with_chunk_size(8).collect::<Vec<_>>();
+        let lengths = chunks.iter().map(|c| c.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, vec![8, 8, 8, 6, 16, 3]);
+        assert_eq!(chunks.concat(), rope.to_str().unwrap().as_bytes());
+
+        // Buffered reads are merged the same way.
+        let mut reader = rope.read().with_chunk_size(8);
+        let mut lengths = Vec::new();
+        loop {
+            let len = reader.fill_buf().unwrap().len();
+            if len == 0 {
+                break;
+            }
+            lengths.push(len);
+            reader.consume(len);
+        }
+        assert_eq!(lengths, vec![8, 8, 8, 6, 16, 3]);
+    }
 }
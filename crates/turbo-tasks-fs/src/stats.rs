@@ -0,0 +1,19 @@
+#[cfg(test)]
+mod tests {
+    use super::top;
+
+    #[test]
+    fn top_items_by_count_then_name() {
+        let items = [("b", 2), ("a", 2), ("c", 5), ("d", 1)]
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count));
+        assert_eq!(
+            top(items, 3),
+            [
+                ("c".to_string(), 5),
+                ("a".to_string(), 2),
+                ("b".to_string(), 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs::File,
+    io::{self, IoSlice, Write},
+    path::Path,
+    thread::available_parallelism,
+};
+
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use tokio::sync::Semaphore;
+
+use crate::{retry::retry_blocking, rope::Rope, Permissions};
+
+/// Limits the number of files that are written to disk at the same time.
+///
+/// Generating the content of files is not limited, but writing them is, so
+/// that a build which emits many files doesn't occupy all blocking threads
+/// and doesn't starve reads with a large number of concurrent writes.
+static WRITE_PERMITS: Lazy<Semaphore> = Lazy::new(|| {
+    let parallelism = available_parallelism().map_or(4, |n| n.get());
+    Semaphore::new(parallelism.clamp(4, 32))
+});
+
+/// Writes the content to a file at `path`, replacing the file when it
+/// exists. The chunks of the [Rope] are written with vectored writes, so they
+/// don't need to be copied into a contiguous buffer first.
+pub(crate) async fn write_file(
+    path: &Path,
+    content: &Rope,
+    permissions: Permissions,
+) -> io::Result<()> {
+    let _permit = WRITE_PERMITS
+        .acquire()
+        .await
+        .expect("the write semaphore is never closed");
+    let chunks = content.read().collect::<Vec<Bytes>>();
+    retry_blocking(path, move |path| {
+        let mut file = File::create(path)?;
+        let mut slices = chunks
+            .iter()
+            .map(|chunk| IoSlice::new(chunk))
+            .collect::<Vec<_>>();
+        file.write_all_vectored(&mut slices)?;
+        #[cfg(target_family = "unix")]
+        file.set_permissions(permissions.into())?;
+        #[cfg(not(target_family = "unix"))]
+        let _ = permissions;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rope::RopeBuilder;
+
+    #[test]
+    fn writes_all_chunks_and_replaces_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.js");
+        let shared = Rope::from("shared ");
+        let mut builder = RopeBuilder::from("head ");
+        builder += &shared;
+        builder += "tail";
+        let content = builder.build();
+
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                write_file(&path, &content, Permissions::Executable)
+                    .await
+                    .unwrap();
+                assert_eq!(std::fs::read_to_string(&path).unwrap(), "head shared tail");
+                #[cfg(target_family = "unix")]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+                    assert_eq!(mode & 0o777, 0o755);
+                }
+
+                // Shorter content doesn't leave the end of the old file behind.
+                write_file(&path, &Rope::from("short"), Permissions::default())
+                    .await
+                    .unwrap();
+                assert_eq!(std::fs::read_to_string(&path).unwrap(), "short");
+            });
+    }
+}
@@ -1,4 +1,7 @@
-use std::mem::Discriminant;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    mem::Discriminant,
+};
 
 pub use turbo_tasks_macros::DeterministicHash;
 
@@ -28,6 +31,10 @@ macro_rules! impl_write_number {
 /// be hashed in a replicatable way across platforms and process runs. Note that
 /// the default Hash trait used by Rust is not deterministic for our purposes.
 ///
+/// Implementations must not depend on the machine (word size, endianness), on
+/// the iteration order of hash based collections, or on absolute paths, so
+/// hashes can be shared by persistent and remote caches.
+///
 /// It's very important that Vcs never implement this, since they cannot be
 /// deterministic. The value that they wrap, however, can implement the trait.
 pub trait DeterministicHash {
@@ -111,18 +118,27 @@ impl DeterministicHash for [u8] {
 
 impl DeterministicHash for String {
     fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
-        state.write_usize(self.len());
-        state.write_bytes(self.as_bytes());
+        self.as_str().deterministic_hash(state);
     }
 }
 
-impl DeterministicHash for &str {
+impl DeterministicHash for str {
     fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
         state.write_usize(self.len());
         state.write_bytes(self.as_bytes());
     }
 }
 
+impl DeterministicHash for char {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        state.write_u32(*self as u32);
+    }
+}
+
+impl DeterministicHash for () {
+    fn deterministic_hash<H: DeterministicHasher>(&self, _state: &mut H) {}
+}
+
 impl DeterministicHash for bool {
     fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
         state.write_u8(*self as u8);
@@ -150,6 +166,77 @@ impl<T: DeterministicHash> DeterministicHash for Vec<T> {
     }
 }
 
+impl<T: ?Sized + DeterministicHash> DeterministicHash for Box<T> {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        (**self).deterministic_hash(state);
+    }
+}
+
+macro_rules! deterministic_hash_tuple {
+    ($(($($name:ident),+),)*) => {$(
+        impl<$($name: DeterministicHash),+> DeterministicHash for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+                let ($($name,)+) = self;
+                $($name.deterministic_hash(state);)+
+            }
+        }
+    )*}
+}
+
+deterministic_hash_tuple! {
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+}
+
+impl<K: DeterministicHash, V: DeterministicHash> DeterministicHash for BTreeMap<K, V> {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for (k, v) in self {
+            k.deterministic_hash(state);
+            v.deterministic_hash(state);
+        }
+    }
+}
+
+impl<T: DeterministicHash> DeterministicHash for BTreeSet<T> {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for v in self {
+            v.deterministic_hash(state);
+        }
+    }
+}
+
+/// The iteration order of a HashMap differs between process runs, so the
+/// entries are hashed in the order of their keys.
+impl<K: Ord + DeterministicHash, V: DeterministicHash, S> DeterministicHash for HashMap<K, V, S> {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        state.write_usize(entries.len());
+        for (k, v) in entries {
+            k.deterministic_hash(state);
+            v.deterministic_hash(state);
+        }
+    }
+}
+
+/// The iteration order of a HashSet differs between process runs, so the
+/// values are hashed in sorted order.
+impl<T: Ord + DeterministicHash, S> DeterministicHash for HashSet<T, S> {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        let mut values: Vec<_> = self.iter().collect();
+        values.sort();
+        state.write_usize(values.len());
+        for v in values {
+            v.deterministic_hash(state);
+        }
+    }
+}
+
 /// HasherWrapper allows the DeterministicHasher to be used as a Hasher, for
 /// standard types that do not allow us to directly access their internals.
 struct HasherWrapper<'a, D: DeterministicHasher>(&'a mut D);
@@ -161,6 +248,58 @@ impl<'a, D: DeterministicHasher> std::hash::Hasher for HasherWrapper<'a, D> {
     fn finish(&self) -> u64 {
         unimplemented!();
     }
+
+    // The default implementations write native endian bytes, and usize/isize
+    // with the size of the machine word, so they are forwarded to the
+    // DeterministicHasher instead.
+
+    fn write_u8(&mut self, i: u8) {
+        self.0.write_u8(i);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.0.write_u16(i);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.0.write_u32(i);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0.write_u64(i);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.0.write_u128(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.0.write_usize(i);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.0.write_i8(i);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.0.write_i16(i);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.0.write_i32(i);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.0.write_i64(i);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.0.write_i128(i);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.0.write_isize(i);
+    }
 }
 
 impl<T> DeterministicHash for Discriminant<T> {
@@ -171,3 +310,77 @@ impl<T> DeterministicHash for Discriminant<T> {
         std::hash::Hash::hash(self, &mut wrapper);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use super::{DeterministicHash, DeterministicHasher};
+
+    /// Records the bytes that are hashed. The expected bytes are spelled out,
+    /// so the tests fail on any machine that would hash differently.
+    #[derive(Default)]
+    struct BytesHasher(Vec<u8>);
+
+    impl DeterministicHasher for BytesHasher {
+        fn finish(&self) -> u64 {
+            unimplemented!()
+        }
+
+        fn write_bytes(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+
+    fn bytes<T: DeterministicHash>(value: T) -> Vec<u8> {
+        let mut hasher = BytesHasher::default();
+        value.deterministic_hash(&mut hasher);
+        hasher.0
+    }
+
+    #[test]
+    fn numbers_are_little_endian_and_word_size_independent() {
+        assert_eq!(bytes(0x0102_u16), [2, 1]);
+        assert_eq!(bytes(1_usize), [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes(-1_isize), [0xff; 8]);
+        assert_eq!(bytes('a'), [b'a', 0, 0, 0]);
+    }
+
+    #[test]
+    fn strings_are_length_prefixed() {
+        let expected = [2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i'];
+        assert_eq!(bytes("hi"), expected);
+        assert_eq!(bytes("hi".to_string()), expected);
+        assert_eq!(bytes(Box::<str>::from("hi")), expected);
+        assert_ne!(bytes(("a", "bc")), bytes(("ab", "c")));
+    }
+
+    #[test]
+    fn discriminants_are_word_size_independent() {
+        #[allow(dead_code)]
+        enum Enum {
+            A,
+            B(u8),
+        }
+        assert_eq!(
+            bytes(std::mem::discriminant(&Enum::B(0))),
+            [1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn hash_collections_are_order_independent() {
+        let forward: Vec<_> = (0..100_u32).map(|i| (i.to_string(), i)).collect();
+        let a: HashMap<_, _> = forward.iter().cloned().collect();
+        let b: HashMap<_, _> = forward.iter().rev().cloned().collect();
+        assert_eq!(bytes(&a), bytes(&b));
+        assert_eq!(
+            bytes(&a),
+            bytes(a.into_iter().collect::<std::collections::BTreeMap<_, _>>())
+        );
+
+        let a: HashSet<_> = (0..100_u32).collect();
+        let b: HashSet<_> = (0..100_u32).rev().collect();
+        assert_eq!(bytes(&a), bytes(&b));
+    }
+}
@@ -6,8 +6,8 @@ use syn::{
     parse_macro_input,
     punctuated::Punctuated,
     spanned::Spanned,
-    Error, Fields, FieldsUnnamed, Item, ItemEnum, ItemStruct, Lit, LitStr, Meta, MetaNameValue,
-    Result, Token,
+    Error, Fields, FieldsUnnamed, Item, ItemEnum, ItemStruct, Lit, LitInt, LitStr, Meta,
+    MetaNameValue, Path, Result, Token,
 };
 use turbo_tasks_macros_shared::{get_ref_ident, get_register_value_type_ident};
 
@@ -129,6 +129,8 @@ struct ValueArguments {
     cell_mode: CellMode,
     manual_eq: bool,
     transparent: bool,
+    version: Option<LitInt>,
+    migrate: Option<Path>,
 }
 
 impl Parse for ValueArguments {
@@ -139,6 +141,8 @@ impl Parse for ValueArguments {
             cell_mode: CellMode::Shared,
             manual_eq: false,
             transparent: false,
+            version: None,
+            migrate: None,
         };
         let punctuated: Punctuated<Meta, Token![,]> = input.parse_terminated(Meta::parse)?;
         for meta in punctuated {
@@ -193,12 +197,24 @@ impl Parse for ValueArguments {
                 ("transparent", Meta::Path(_)) => {
                     result.transparent = true;
                 }
+                (
+                    "version",
+                    Meta::NameValue(MetaNameValue {
+                        lit: Lit::Int(int), ..
+                    }),
+                ) => {
+                    int.base10_parse::<u32>()?;
+                    result.version = Some(int);
+                }
+                ("migrate", Meta::Path(path)) => {
+                    result.migrate = Some(path);
+                }
                 (_, meta) => {
                     return Err(Error::new_spanned(
                         &meta,
                         format!(
                             "unexpected {:?}, expected \"shared\", \"into\", \"serialization\", \
-                             \"cell\", \"eq\", \"transparent\"",
+                             \"cell\", \"eq\", \"transparent\", \"version\", \"migrate\"",
                             meta
                         ),
                     ))
@@ -218,6 +234,8 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         cell_mode,
         manual_eq,
         transparent,
+        version,
+        migrate,
     } = parse_macro_input!(args as ValueArguments);
 
     let (vis, ident) = match &item {
@@ -388,6 +406,26 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     };
 
+    let set_version = version.map(|version| {
+        quote! {
+            value.set_version(#version);
+        }
+    });
+    let register_migration = match (migrate, &serialization_mode) {
+        (None, _) => quote! {},
+        (Some(migrate), SerializationMode::None) => {
+            return Error::new_spanned(migrate, "values without serialization can't be migrated")
+                .to_compile_error()
+                .into();
+        }
+        (Some(_), SerializationMode::Auto | SerializationMode::Custom) => quote! {
+            value.register_migration::<#ident>();
+        },
+        (Some(_), SerializationMode::AutoForInput | SerializationMode::CustomForInput) => quote! {
+            value.register_magic_migration::<#ident>();
+        },
+    };
+
     let for_input_marker = match serialization_mode {
         SerializationMode::None | SerializationMode::Auto | SerializationMode::Custom => quote! {},
         SerializationMode::AutoForInput | SerializationMode::CustomForInput => quote! {
@@ -575,6 +613,8 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         ) {
             #value_type_init_ident.get_or_init(|| {
                 let mut value = #new_value_type;
+                #set_version
+                #register_migration
                 f(&mut value);
                 value
             }).register(global_name);
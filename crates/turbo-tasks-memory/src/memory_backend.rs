@@ -326,6 +326,7 @@ impl Backend for MemoryBackend {
         duration: Duration,
         instant: Instant,
         stateful: bool,
+        _reads_external: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool {
         let reexecute = self.with_task(task_id, |task| {
@@ -74,6 +74,7 @@ struct MemoryTaskState {
     output_dependent: AutoSet<TaskId>,
     dependencies: AutoSet<RawVc>,
     children: AutoSet<TaskId>,
+    reads_external: bool,
     event: Event,
     event_cells: Event,
 }
@@ -89,6 +90,7 @@ impl MemoryTaskState {
             output_dependent: Default::default(),
             dependencies: Default::default(),
             children: Default::default(),
+            reads_external: Default::default(),
             event: Event::new(move || format!("MemoryTaskState({task})::event")),
             event_cells: Event::new(move || format!("MemoryTaskState({task})::event_cells")),
         }
@@ -277,6 +279,7 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                     output_dependent: AutoSet::new(),
                     dependencies: data.dependencies.into_iter().collect(),
                     children: data.children.into_iter().collect(),
+                    reads_external: data.reads_external,
                     need_persist: Default::default(),
                     has_changes: Default::default(),
                     event: Event::new(move || format!("MemoryTaskState({task})::event")),
@@ -755,6 +758,7 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                                 ref children,
                                 ref dependencies,
                                 ref cells,
+                                reads_external,
                                 ..
                             }),
                         ..
@@ -792,6 +796,7 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                                                 .map(|(k, (s, _))| (*k, s.clone()))
                                                 .collect(),
                                             output: *output,
+                                            reads_external,
                                         };
                                         let externally_active =
                                             task_info.active_parents.load(Ordering::Acquire) > 0;
@@ -1110,7 +1115,8 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         task: TaskId,
         duration: Duration,
         _instant: Instant,
-        _stateful: bool,
+        stateful: bool,
+        reads_external: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool {
         #[cfg(feature = "log_running_tasks")]
@@ -1132,6 +1138,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             return true;
         }
         mem_state.freshness = TaskFreshness::Done;
+        mem_state.reads_external = stateful || reads_external;
         *scheduled = false;
         mem_state.event.notify(usize::MAX);
         mem_state.event_cells.notify(usize::MAX);
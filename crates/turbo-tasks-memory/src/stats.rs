@@ -6,7 +6,7 @@ use std::{
     time::Duration,
 };
 
-use turbo_tasks::{registry, FunctionId, TaskId, TraitTypeId};
+use turbo_tasks::{registry, FunctionId, TaskId, TraitTypeId, ValueTypeId};
 
 use crate::{
     scope::TaskScopeId,
@@ -344,3 +344,63 @@ pub struct GroupTree {
     pub children: Vec<GroupTree>,
     pub task_types: Vec<(StatsTaskType, ExportedTaskStats)>,
 }
+
+/// Counts the cells that hold a value, by value type and by the type of the
+/// task that owns them. This shows what retains memory in a long running
+/// process.
+#[derive(Default)]
+pub struct CellStats {
+    pub tasks: usize,
+    pub cells: usize,
+    by_value_type: HashMap<ValueTypeId, usize>,
+    by_task_type: HashMap<StatsTaskType, usize>,
+}
+
+impl CellStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_id(&mut self, backend: &MemoryBackend, id: TaskId) {
+        let (ty, counts) =
+            backend.with_task(id, |task| (task.get_stats_type(), task.get_cell_counts()));
+        self.tasks += 1;
+        let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+        if total == 0 {
+            return;
+        }
+        self.cells += total;
+        *self.by_task_type.entry(ty).or_default() += total;
+        for (value_type, count) in counts {
+            *self.by_value_type.entry(value_type).or_default() += count;
+        }
+    }
+
+    /// The value types with the most cells, with their number of cells.
+    pub fn top_value_types(&self, count: usize) -> Vec<(String, usize)> {
+        top(
+            self.by_value_type
+                .iter()
+                .map(|(ty, cells)| (registry::get_value_type(*ty).name.clone(), *cells)),
+            count,
+        )
+    }
+
+    /// The task types (usually task functions) that retain the most cells,
+    /// with their number of cells.
+    pub fn top_task_types(&self, count: usize) -> Vec<(String, usize)> {
+        top(
+            self.by_task_type
+                .iter()
+                .map(|(ty, cells)| (ty.to_string(), *cells)),
+            count,
+        )
+    }
+}
+
+fn top(items: impl Iterator<Item = (String, usize)>, count: usize) -> Vec<(String, usize)> {
+    let mut items = items.collect::<Vec<_>>();
+    items.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+    items.truncate(count);
+    items
+}
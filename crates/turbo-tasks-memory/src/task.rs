@@ -1662,6 +1662,20 @@ impl Task {
         }
     }
 
+    /// The number of cells of the task that hold a value, by value type.
+    pub fn get_cell_counts(&self) -> Vec<(ValueTypeId, usize)> {
+        if let TaskMetaStateReadGuard::Full(state) = self.state() {
+            state
+                .cells
+                .iter()
+                .map(|(ty, cells)| (*ty, cells.iter().filter(|cell| cell.has_value()).count()))
+                .filter(|(_, count)| *count > 0)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
     pub fn get_stats_type(self: &Task) -> StatsTaskType {
         match &self.ty {
             TaskType::Root(_) => StatsTaskType::Root(self.id),
@@ -0,0 +1,84 @@
+#![feature(min_specialization)]
+
+use std::{sync::Mutex, time::Duration};
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, primitives::U32Vc, Invalidator};
+use turbo_tasks_testing::{executions::ExecutionCounter, register, run, time};
+
+register!();
+
+static DOUBLE: ExecutionCounter = ExecutionCounter::new();
+static CONSTANT: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn executions_after_change() {
+    run! {
+        let counter = CounterVc::cell(Counter { value: Mutex::new((1, None)) });
+        assert_eq!(*double(counter).strongly_consistent().await?, 2);
+        assert_eq!(*constant(counter).strongly_consistent().await?, 42);
+        DOUBLE.assert_executed(1);
+        CONSTANT.assert_executed(1);
+
+        // cached
+        assert_eq!(*double(counter).strongly_consistent().await?, 2);
+        DOUBLE.assert_executed(0);
+
+        counter.await?.incr();
+        assert_eq!(*double(counter).strongly_consistent().await?, 4);
+        assert_eq!(*constant(counter).strongly_consistent().await?, 42);
+        DOUBLE.assert_executed(1);
+        CONSTANT.assert_executed(0);
+    }
+}
+
+#[tokio::test]
+async fn virtual_time() {
+    time::pause();
+    let start = tokio::time::Instant::now();
+    time::advance(Duration::from_secs(60)).await;
+    assert!(start.elapsed() >= Duration::from_secs(60));
+    time::resume();
+}
+
+#[turbo_tasks::function]
+async fn double(counter: CounterVc) -> Result<U32Vc> {
+    DOUBLE.record();
+    Ok(U32Vc::cell(*counter.get_value().await? * 2))
+}
+
+#[turbo_tasks::function]
+fn constant(_counter: CounterVc) -> U32Vc {
+    CONSTANT.record();
+    U32Vc::cell(42)
+}
+
+#[turbo_tasks::value(transparent)]
+struct CounterValue(u32);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Counter {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(u32, Option<Invalidator>)>,
+}
+
+impl Counter {
+    fn incr(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate();
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CounterVc {
+    #[turbo_tasks::function]
+    pub async fn get_value(self) -> Result<CounterValueVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(CounterValueVc::cell(lock.0))
+    }
+}
@@ -0,0 +1,174 @@
+//! An HTTP client for a remote task cache, to be used with
+//! [turbo_tasks::remote_cache::RemoteCachePersistedGraph].
+//!
+//! The API is the same as the one of turborepo's remote cache: entries are
+//! read with `GET {url}/v8/artifacts/{key}` and written with
+//! `PUT {url}/v8/artifacts/{key}`, authenticated with a bearer token. Entries
+//! are gzip compressed.
+
+use std::{
+    future::Future,
+    io::{Read, Write},
+    sync::mpsc,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use reqwest::{Client, StatusCode};
+use tokio::runtime::Runtime;
+use turbo_tasks::remote_cache::RemoteCache;
+
+#[derive(Clone, Debug)]
+pub struct HttpRemoteCacheOptions {
+    /// The base url of the API, e.g. `https://cache.example.com`.
+    pub url: String,
+    pub token: String,
+    /// Scopes the cache to a team, passed as the `teamId` query param.
+    pub team_id: Option<String>,
+    pub timeout: Duration,
+}
+
+pub struct HttpRemoteCache {
+    client: Client,
+    options: HttpRemoteCacheOptions,
+    /// The cache is called synchronously by the persisted graph, which runs
+    /// within the runtime of turbo-tasks, so requests run on a runtime of
+    /// their own.
+    runtime: Option<Runtime>,
+}
+
+impl HttpRemoteCache {
+    pub fn new(options: HttpRemoteCacheOptions) -> Result<Self> {
+        let client = Client::builder().timeout(options.timeout).build()?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("remote-cache")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            client,
+            options,
+            runtime: Some(runtime),
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        let url = format!(
+            "{}/v8/artifacts/{key}",
+            self.options.url.trim_end_matches('/')
+        );
+        match &self.options.team_id {
+            Some(team_id) => format!("{url}?teamId={}", urlencoding::encode(team_id)),
+            None => url,
+        }
+    }
+
+    /// Runs a request on the runtime of the cache and waits for it.
+    fn run<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = Result<T>> + Send + 'static,
+    ) -> Result<T> {
+        let (sender, receiver) = mpsc::channel();
+        self.runtime.as_ref().unwrap().spawn(async move {
+            let _ = sender.send(future.await);
+        });
+        receiver
+            .recv()
+            .context("the remote cache request was cancelled")?
+    }
+}
+
+impl Drop for HttpRemoteCache {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which isn't allowed within another
+        // runtime.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl RemoteCache for HttpRemoteCache {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let request = self
+            .client
+            .get(self.url(key))
+            .bearer_auth(&self.options.token);
+        let key = key.to_string();
+        self.run(async move {
+            let response = request.send().await?;
+            match response.status() {
+                StatusCode::NOT_FOUND => Ok(None),
+                status if status.is_success() => {
+                    let mut data = Vec::new();
+                    GzDecoder::new(&*response.bytes().await?).read_to_end(&mut data)?;
+                    Ok(Some(data))
+                }
+                status => bail!("remote cache responded with {status} for {key}"),
+            }
+        })
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let request = self
+            .client
+            .put(self.url(key))
+            .bearer_auth(&self.options.token)
+            .header("Content-Type", "application/octet-stream")
+            .body(encoder.finish()?);
+        let key = key.to_string();
+        self.run(async move {
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                bail!(
+                    "remote cache responded with {} for {key}",
+                    response.status()
+                );
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(url: &str, team_id: Option<&str>) -> HttpRemoteCache {
+        HttpRemoteCache::new(HttpRemoteCacheOptions {
+            url: url.to_string(),
+            token: "token".to_string(),
+            team_id: team_id.map(|team_id| team_id.to_string()),
+            timeout: Duration::from_secs(1),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn builds_artifact_urls() {
+        assert_eq!(
+            cache("https://cache.example.com/", None).url("abc"),
+            "https://cache.example.com/v8/artifacts/abc"
+        );
+        assert_eq!(
+            cache("https://cache.example.com", Some("team")).url("abc"),
+            "https://cache.example.com/v8/artifacts/abc?teamId=team"
+        );
+        assert_eq!(
+            cache("https://cache.example.com", Some("a&b=c d")).url("abc"),
+            "https://cache.example.com/v8/artifacts/abc?teamId=a%26b%3Dc%20d"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn can_be_used_and_dropped_within_a_runtime() {
+        // Nothing listens on the port, so the request fails instead of
+        // panicking.
+        let cache = cache("http://127.0.0.1:1", None);
+        assert!(cache.get("abc").is_err());
+        drop(cache);
+    }
+}
@@ -731,6 +731,7 @@ impl Backend for RocksDbBackend {
         task: TaskId,
         result: Result<RawVc>,
         _stateful: bool,
+        _reads_external: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool {
         #[cfg(feature = "log_backend")]
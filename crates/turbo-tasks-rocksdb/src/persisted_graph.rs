@@ -69,6 +69,7 @@ pub struct Stats {
     lookups: AtomicUsize,
     reads: AtomicUsize,
     reads_by_function: CountsByFunction,
+    discarded_reads: AtomicUsize,
     activates: AtomicUsize,
     deactivates: AtomicUsize,
     persists: AtomicUsize,
@@ -216,7 +217,17 @@ impl PersistedGraph for RocksDbPersistedGraph {
         self.stats.reads.fetch_add(1, Ordering::Relaxed);
         with_task_id_mapping(&mapping, || {
             let db = &self.database;
-            if let Some(data) = db.data.get(&db_task)? {
+            let data = match db.data.get(&db_task) {
+                Ok(data) => data,
+                // The data contains values that can't be read by this build, e.g. because
+                // their type has a newer version. The task is recomputed instead.
+                Err(err) if err.is::<bincode::Error>() => {
+                    self.stats.discarded_reads.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+                Err(err) => return Err(err),
+            };
+            if let Some(data) = data {
                 if let Some(TaskState {
                     internal: Some(InternalTaskState { clean }),
                     active_parents,
@@ -0,0 +1,50 @@
+//! Counting executions of functions, to assert that a change invalidates
+//! exactly the expected tasks.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts how often a function was executed. It's usually a `static` which is
+/// recorded in the function under test:
+///
+/// ```ignore
+/// static DOUBLE: ExecutionCounter = ExecutionCounter::new();
+///
+/// #[turbo_tasks::function]
+/// fn double(value: u32) -> U32Vc {
+///     DOUBLE.record();
+///     U32Vc::cell(value * 2)
+/// }
+/// ```
+pub struct ExecutionCounter(AtomicUsize);
+
+impl ExecutionCounter {
+    pub const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    pub fn record(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of executions since the last call and resets it.
+    pub fn take(&self) -> usize {
+        self.0.swap(0, Ordering::SeqCst)
+    }
+
+    /// Asserts that the function was executed exactly `expected` times since
+    /// the last call to [ExecutionCounter::take] or this function.
+    #[track_caller]
+    pub fn assert_executed(&self, expected: usize) {
+        let executions = self.take();
+        assert_eq!(
+            executions, expected,
+            "expected {expected} executions, but there were {executions}"
+        );
+    }
+}
+
+impl Default for ExecutionCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
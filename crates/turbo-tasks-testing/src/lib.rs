@@ -2,8 +2,10 @@
 
 #![feature(box_syntax)]
 
+pub mod executions;
 mod macros;
 pub mod retry;
+pub mod time;
 
 use std::{
     borrow::Cow,
@@ -11,16 +13,17 @@ use std::{
     future::Future,
     mem::replace,
     sync::{Arc, Mutex, Weak},
+    time::Duration,
 };
 
 use anyhow::Result;
 use auto_hash_map::AutoSet;
 use turbo_tasks::{
-    backend::CellContent,
+    backend::{Backend, CellContent},
     event::{Event, EventListener},
     registry,
     test_helpers::{current_task_for_testing, with_turbo_tasks_for_testing},
-    CellId, RawVc, TaskId, TraitTypeId, TurboTasksApi, TurboTasksCallApi,
+    CellId, RawVc, TaskId, TraitTypeId, TurboTasks, TurboTasksApi, TurboTasksCallApi,
 };
 
 enum Task {
@@ -203,6 +206,10 @@ impl TurboTasksApi for VcStorage {
         let cell = map.entry((task, index)).or_default();
         *cell = content;
     }
+
+    fn in_progress_count(&self) -> usize {
+        0
+    }
 }
 
 impl VcStorage {
@@ -217,3 +224,21 @@ impl VcStorage {
         )
     }
 }
+
+/// Waits until all scheduled tasks and background jobs have finished, e.g.
+/// after a change invalidated tasks. Together with
+/// [executions::ExecutionCounter] this allows to assert which tasks were
+/// executed again.
+pub async fn drain<B: Backend + 'static>(tt: &TurboTasks<B>) {
+    loop {
+        // Let spawned tasks start before checking whether any are in progress.
+        tokio::task::yield_now().await;
+        if tt.get_in_progress_count() == 0 {
+            tt.wait_background_done().await;
+            if tt.get_in_progress_count() == 0 {
+                return;
+            }
+        }
+        tt.get_or_wait_update_info(Duration::ZERO).await;
+    }
+}
@@ -0,0 +1,24 @@
+//! Virtual time for tests of debounce and timeout code paths.
+//!
+//! This is based on tokio's paused clock, so it only affects `tokio::time`
+//! and requires a current thread runtime, which is the default of
+//! `#[tokio::test]`. When the clock is paused and the runtime has no work
+//! left, timers fire immediately instead of waiting for real time.
+
+use std::time::Duration;
+
+/// Stops the clock. Time only moves forward with [advance], or when the
+/// runtime is idle.
+pub fn pause() {
+    tokio::time::pause();
+}
+
+/// Lets the clock run in real time again.
+pub fn resume() {
+    tokio::time::resume();
+}
+
+/// Moves the clock forward and runs the timers that are due.
+pub async fn advance(duration: Duration) {
+    tokio::time::advance(duration).await;
+}
@@ -219,6 +219,7 @@ pub trait Backend: Sync + Send {
         duration: Duration,
         instant: Instant,
         stateful: bool,
+        reads_external: bool,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> bool;
 
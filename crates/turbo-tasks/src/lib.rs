@@ -53,6 +53,7 @@ pub mod primitives;
 mod raw_vc;
 mod read_ref;
 pub mod registry;
+pub mod remote_cache;
 pub mod small_duration;
 mod state;
 mod task_input;
@@ -85,8 +86,8 @@ pub use task_input::{FromTaskInput, SharedReference, SharedValue, TaskInput};
 pub use turbo_tasks_macros::{function, value, value_impl, value_trait};
 pub use value::{TransientInstance, TransientValue, Value};
 pub use value_type::{
-    FromSubTrait, IntoSuperTrait, TraitMethod, TraitType, Typed, TypedForInput, ValueTraitVc,
-    ValueType, ValueVc,
+    FromSubTrait, IntoSuperTrait, MigrateValue, TraitMethod, TraitType, Typed, TypedForInput,
+    ValueTraitVc, ValueType, ValueVc,
 };
 
 #[doc(hidden)]
@@ -10,6 +10,8 @@ use std::{
 
 use serde::{de::DeserializeSeed, Deserialize, Serialize};
 
+use crate::value_type::MigrateValue;
+
 pub trait MagicAny: mopa::Any + Send + Sync {
     fn magic_any_arc(self: Arc<Self>) -> Arc<dyn Any + Sync + Send>;
 
@@ -140,12 +142,15 @@ impl dyn MagicAny {
     }
 }
 
-type MagicAnyDeserializeSeedFunctor =
-    fn(&mut dyn erased_serde::Deserializer<'_>) -> Result<Box<dyn MagicAny>, erased_serde::Error>;
+type MagicAnyDeserializeSeedFunctor = fn(
+    u32,
+    &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<Box<dyn MagicAny>, erased_serde::Error>;
 
 #[derive(Clone, Copy)]
 pub struct MagicAnyDeserializeSeed {
     functor: MagicAnyDeserializeSeedFunctor,
+    version: u32,
 }
 
 impl MagicAnyDeserializeSeed {
@@ -156,6 +161,7 @@ impl MagicAnyDeserializeSeed {
         fn deserialize<
             T: Debug + Eq + Ord + Hash + for<'de> Deserialize<'de> + Send + Sync + 'static,
         >(
+            _version: u32,
             deserializer: &mut dyn erased_serde::Deserializer<'_>,
         ) -> Result<Box<dyn MagicAny>, erased_serde::Error> {
             let value: T = erased_serde::deserialize(deserializer)?;
@@ -163,7 +169,28 @@ impl MagicAnyDeserializeSeed {
         }
         Self {
             functor: deserialize::<T>,
+            version: 0,
+        }
+    }
+
+    /// Deserializes values of an older version of `T` with
+    /// [MigrateValue::migrate].
+    pub fn migrate<T: MigrateValue + Debug + Eq + Ord + Hash + Send + Sync + 'static>() -> Self {
+        fn migrate<T: MigrateValue + Debug + Eq + Ord + Hash + Send + Sync + 'static>(
+            version: u32,
+            deserializer: &mut dyn erased_serde::Deserializer<'_>,
+        ) -> Result<Box<dyn MagicAny>, erased_serde::Error> {
+            Ok(Box::new(T::migrate(version, deserializer)?))
         }
+        Self {
+            functor: migrate::<T>,
+            version: 0,
+        }
+    }
+
+    /// Sets the version the deserialized values were serialized with.
+    pub fn for_version(self, version: u32) -> Self {
+        Self { version, ..self }
     }
 }
 
@@ -175,17 +202,19 @@ impl<'de> DeserializeSeed<'de> for MagicAnyDeserializeSeed {
         D: serde::Deserializer<'de>,
     {
         let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
-        (self.functor)(&mut deserializer).map_err(serde::de::Error::custom)
+        (self.functor)(self.version, &mut deserializer).map_err(serde::de::Error::custom)
     }
 }
 
 type AnyDeserializeSeedFunctor = fn(
+    u32,
     &mut dyn erased_serde::Deserializer<'_>,
 ) -> Result<Box<dyn Any + Sync + Send>, erased_serde::Error>;
 
 #[derive(Clone, Copy)]
 pub struct AnyDeserializeSeed {
     functor: AnyDeserializeSeedFunctor,
+    version: u32,
 }
 
 impl AnyDeserializeSeed {
@@ -194,6 +223,7 @@ impl AnyDeserializeSeed {
         T: for<'de> Deserialize<'de>,
     {
         fn deserialize<T: Any + for<'de> Deserialize<'de> + Send + Sync + 'static>(
+            _version: u32,
             deserializer: &mut dyn erased_serde::Deserializer<'_>,
         ) -> Result<Box<dyn Any + Sync + Send>, erased_serde::Error> {
             let value: T = erased_serde::deserialize(deserializer)?;
@@ -201,8 +231,29 @@ impl AnyDeserializeSeed {
         }
         Self {
             functor: deserialize::<T>,
+            version: 0,
+        }
+    }
+
+    /// Deserializes values of an older version of `T` with
+    /// [MigrateValue::migrate].
+    pub fn migrate<T: MigrateValue + Any + Send + Sync + 'static>() -> Self {
+        fn migrate<T: MigrateValue + Any + Send + Sync + 'static>(
+            version: u32,
+            deserializer: &mut dyn erased_serde::Deserializer<'_>,
+        ) -> Result<Box<dyn Any + Sync + Send>, erased_serde::Error> {
+            Ok(Box::new(T::migrate(version, deserializer)?))
+        }
+        Self {
+            functor: migrate::<T>,
+            version: 0,
         }
     }
+
+    /// Sets the version the deserialized values were serialized with.
+    pub fn for_version(self, version: u32) -> Self {
+        Self { version, ..self }
+    }
 }
 
 impl<'de> DeserializeSeed<'de> for AnyDeserializeSeed {
@@ -213,6 +264,6 @@ impl<'de> DeserializeSeed<'de> for AnyDeserializeSeed {
         D: serde::Deserializer<'de>,
     {
         let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
-        (self.functor)(&mut deserializer).map_err(serde::de::Error::custom)
+        (self.functor)(self.version, &mut deserializer).map_err(serde::de::Error::custom)
     }
 }
@@ -111,6 +111,10 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
 
     fn read_current_task_cell(&self, index: CellId) -> Result<CellContent>;
     fn update_current_task_cell(&self, index: CellId, content: CellContent);
+
+    /// The number of tasks which are scheduled or executing. It's 0 when
+    /// all work is done, e.g. when a change was fully processed.
+    fn in_progress_count(&self) -> usize;
 }
 
 /// The type of stats reporting.
@@ -233,6 +237,9 @@ struct CurrentTaskState {
 
     // true, if the current task has state in cells
     stateful: bool,
+
+    // true, if the current task reads state outside of turbo-tasks, e.g. files
+    reads_external: bool,
 }
 
 // TODO implement our own thread pool and make these thread locals instead
@@ -427,10 +434,15 @@ impl<B: Backend> TurboTasks<B> {
                             },
                         });
                         this.backend.task_execution_result(task_id, result, &*this);
-                        let stateful = this.finish_current_task_state();
-                        let reexecute = this
-                            .backend
-                            .task_execution_completed(task_id, duration, instant, stateful, &*this);
+                        let (stateful, reads_external) = this.finish_current_task_state();
+                        let reexecute = this.backend.task_execution_completed(
+                            task_id,
+                            duration,
+                            instant,
+                            stateful,
+                            reads_external,
+                            &*this,
+                        );
                         if !reexecute {
                             return false;
                         }
@@ -684,17 +696,20 @@ impl<B: Backend> TurboTasks<B> {
         }));
     }
 
-    fn finish_current_task_state(&self) -> bool {
+    /// Returns whether the current task is stateful and whether it reads
+    /// external state.
+    fn finish_current_task_state(&self) -> (bool, bool) {
         CURRENT_TASK_STATE.with(|cell| {
             let CurrentTaskState {
                 tasks_to_notify,
                 stateful,
+                reads_external,
             } = &mut *cell.borrow_mut();
             let tasks = take(tasks_to_notify);
             if !tasks.is_empty() {
                 self.backend.invalidate_tasks(tasks, self);
             }
-            *stateful
+            (*stateful, *reads_external)
         })
     }
 
@@ -866,6 +881,10 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
             self,
         );
     }
+
+    fn in_progress_count(&self) -> usize {
+        self.get_in_progress_count()
+    }
 }
 
 impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
@@ -1135,14 +1154,20 @@ pub fn current_task_for_testing() -> TaskId {
 }
 
 /// Get an [Invalidator] that can be used to invalidate the current [Task]
-/// based on external events.
+/// based on external events. This marks the current task as reading external
+/// state, so its result is never shared between machines.
 pub fn get_invalidator() -> Invalidator {
     let handle = Handle::current();
-    Invalidator {
+    let invalidator = Invalidator {
         task: current_task("turbo_tasks::get_invalidator()"),
         turbo_tasks: weak_turbo_tasks(),
         handle,
-    }
+    };
+    CURRENT_TASK_STATE.with(|cell| {
+        let CurrentTaskState { reads_external, .. } = &mut *cell.borrow_mut();
+        *reads_external = true;
+    });
+    invalidator
 }
 
 /// Marks the current task as stateful. This prevents the tasks from being
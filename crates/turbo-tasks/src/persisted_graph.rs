@@ -24,6 +24,9 @@ pub struct TaskData {
     pub dependencies: Vec<RawVc>,
     pub cells: Vec<(CellId, TaskCell)>,
     pub output: RawVc,
+    /// The task read state outside of turbo-tasks, e.g. files, or had state
+    /// of its own.
+    pub reads_external: bool,
 }
 pub struct ReadTaskState {
     pub clean: bool,
@@ -0,0 +1,634 @@
+//! Sharing of task results between machines through a remote cache.
+//!
+//! Task ids are only valid within one persisted graph, so task results are
+//! stored with the task types that they reference instead. The key of a task
+//! is the hash of its task type, which includes the keys of the tasks that are
+//! referenced by its inputs, so it's the same on every machine.
+//!
+//! The key also includes the build id of the [RemoteCachePersistedGraph],
+//! which identifies the code of the task functions, so builds with other code
+//! don't share entries.
+//!
+//! The key doesn't include any state outside of turbo-tasks, e.g. the contents
+//! of files. Only pure tasks are shared, which are tasks that don't read
+//! external state (see [crate::get_invalidator]) and only depend on other pure
+//! tasks. Their results are fully determined by their task type.
+//!
+//! [RemoteCachePersistedGraph] layers a [RemoteCache] under a
+//! [PersistedGraph]: tasks that are missing in the persisted graph are looked
+//! up in the remote cache, and persisted pure tasks are uploaded to it.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{hash_map::Entry, HashMap, HashSet},
+};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use turbo_tasks_hash::{encode_hex, DeterministicHasher, Xxh3Hash64Hasher};
+
+use crate::{
+    backend::PersistentTaskType,
+    persisted_graph::{
+        ActivateResult, DeactivateResult, PersistResult, PersistTaskState, PersistedGraph,
+        PersistedGraphApi, ReadTaskState, TaskCell, TaskData,
+    },
+    with_task_id_mapping, IdMapping, RawVc, TaskId,
+};
+
+/// Bump this when the format of [RemoteTaskData] changes, so old entries are
+/// not read anymore.
+const REMOTE_CACHE_VERSION: u32 = 1;
+
+/// A store for task results, e.g. an HTTP API.
+///
+/// Errors are not fatal for a build: a failing `get` is treated as a cache
+/// miss and a failing `put` is ignored.
+pub trait RemoteCache: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+}
+
+/// An optional [RemoteCache]. Without a cache every lookup is a miss.
+impl<C: RemoteCache> RemoteCache for Option<C> {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            Some(cache) => cache.get(key),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        match self {
+            Some(cache) => cache.put(key, data),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A value with the task ids replaced by indices into the task table of the
+/// [RemoteTaskData].
+#[derive(Serialize, Deserialize, Debug)]
+struct EncodedValue {
+    value: serde_json::Value,
+    /// Maps the ids within `value` to indices into the task table.
+    refs: Vec<usize>,
+}
+
+/// The data of a task as it's stored in the remote cache.
+#[derive(Serialize, Deserialize, Debug)]
+struct RemoteTaskData {
+    /// The types of all tasks that are referenced by `data`, transitively.
+    tasks: Vec<EncodedValue>,
+    /// The [TaskData].
+    data: EncodedValue,
+}
+
+/// Collects the task ids that are serialized, and assigns them local indices.
+#[derive(Default)]
+struct CollectingMapping {
+    ids: RefCell<Vec<TaskId>>,
+}
+
+impl IdMapping<TaskId> for CollectingMapping {
+    fn forward(&self, id: TaskId) -> usize {
+        let mut ids = self.ids.borrow_mut();
+        if let Some(index) = ids.iter().position(|&i| i == id) {
+            return index;
+        }
+        ids.push(id);
+        ids.len() - 1
+    }
+
+    fn backward(&self, _id: usize) -> TaskId {
+        unreachable!("CollectingMapping is only used for serialization")
+    }
+}
+
+/// Maps local indices back to the task ids that were resolved for them.
+struct ResolvedMapping<'a> {
+    ids: &'a [TaskId],
+    out_of_range: Cell<bool>,
+}
+
+impl IdMapping<TaskId> for ResolvedMapping<'_> {
+    fn forward(&self, _id: TaskId) -> usize {
+        unreachable!("ResolvedMapping is only used for deserialization")
+    }
+
+    fn backward(&self, id: usize) -> TaskId {
+        self.ids.get(id).copied().unwrap_or_else(|| {
+            self.out_of_range.set(true);
+            TaskId::from(0)
+        })
+    }
+}
+
+/// Serializes a value and returns the task ids it contains.
+fn serialize_value<T: Serialize>(value: &T) -> Result<(serde_json::Value, Vec<TaskId>)> {
+    let mapping = CollectingMapping::default();
+    let value = with_task_id_mapping(&mapping, || serde_json::to_value(value))?;
+    Ok((value, mapping.ids.into_inner()))
+}
+
+struct Encoder<'a> {
+    api: &'a dyn PersistedGraphApi,
+    build_id: &'a str,
+    indices: HashMap<TaskId, usize>,
+    tasks: Vec<Option<EncodedValue>>,
+    keys: HashMap<TaskId, String>,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(api: &'a dyn PersistedGraphApi, build_id: &'a str) -> Self {
+        Self {
+            api,
+            build_id,
+            indices: HashMap::new(),
+            tasks: Vec::new(),
+            keys: HashMap::new(),
+        }
+    }
+
+    fn encode<T: Serialize>(&mut self, value: &T) -> Result<EncodedValue> {
+        let (value, ids) = serialize_value(value)?;
+        let refs = ids
+            .into_iter()
+            .map(|id| self.task_index(id))
+            .collect::<Result<_>>()?;
+        Ok(EncodedValue { value, refs })
+    }
+
+    fn task_index(&mut self, task: TaskId) -> Result<usize> {
+        if let Some(&index) = self.indices.get(&task) {
+            return Ok(index);
+        }
+        let index = self.tasks.len();
+        self.indices.insert(task, index);
+        self.tasks.push(None);
+        let api = self.api;
+        let encoded = self.encode(api.lookup_task_type(task))?;
+        self.tasks[index] = Some(encoded);
+        Ok(index)
+    }
+
+    /// The key of a task type, which is the same on every machine.
+    fn key(&mut self, ty: &PersistentTaskType) -> Result<String> {
+        let (value, ids) = serialize_value(ty)?;
+        let mut hasher = Xxh3Hash64Hasher::new();
+        hasher.write_u32(REMOTE_CACHE_VERSION);
+        hasher.write_ref(self.build_id);
+        hasher.write_ref(&serde_json::to_string(&value)?);
+        for id in ids {
+            let key = self.task_key(id)?;
+            hasher.write_ref(&key);
+        }
+        Ok(encode_hex(hasher.finish()))
+    }
+
+    fn task_key(&mut self, task: TaskId) -> Result<String> {
+        if let Some(key) = self.keys.get(&task) {
+            return Ok(key.clone());
+        }
+        let api = self.api;
+        let key = self.key(api.lookup_task_type(task))?;
+        self.keys.insert(task, key.clone());
+        Ok(key)
+    }
+
+    fn finish(self, data: EncodedValue) -> Result<RemoteTaskData> {
+        Ok(RemoteTaskData {
+            tasks: self
+                .tasks
+                .into_iter()
+                .map(|task| task.ok_or_else(|| anyhow!("task type was not encoded")))
+                .collect::<Result<_>>()?,
+            data,
+        })
+    }
+}
+
+struct Decoder<'a> {
+    api: &'a dyn PersistedGraphApi,
+    tasks: &'a [EncodedValue],
+    /// `None` while the task type is decoded, to reject cyclic task types.
+    resolved: HashMap<usize, Option<TaskId>>,
+}
+
+impl<'a> Decoder<'a> {
+    fn decode<T: DeserializeOwned>(&mut self, value: &EncodedValue) -> Result<T> {
+        let ids = value
+            .refs
+            .iter()
+            .map(|&index| self.task(index))
+            .collect::<Result<Vec<_>>>()?;
+        let mapping = ResolvedMapping {
+            ids: &ids,
+            out_of_range: Cell::new(false),
+        };
+        let decoded =
+            with_task_id_mapping(&mapping, || serde_json::from_value(value.value.clone()))?;
+        if mapping.out_of_range.get() {
+            bail!("task id is out of range");
+        }
+        Ok(decoded)
+    }
+
+    fn task(&mut self, index: usize) -> Result<TaskId> {
+        match self.resolved.entry(index) {
+            Entry::Occupied(entry) => {
+                return entry
+                    .get()
+                    .ok_or_else(|| anyhow!("task type {index} references itself"));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(None);
+            }
+        }
+        let encoded = self
+            .tasks
+            .get(index)
+            .ok_or_else(|| anyhow!("task index {index} is out of range"))?;
+        let ty: PersistentTaskType = self.decode(encoded)?;
+        let task = self.api.get_or_create_task_type(ty);
+        self.resolved.insert(index, Some(task));
+        Ok(task)
+    }
+}
+
+/// A [PersistedGraph] which looks up missing tasks in a [RemoteCache] and
+/// uploads persisted pure tasks to it.
+pub struct RemoteCachePersistedGraph<G: PersistedGraph, C: RemoteCache> {
+    inner: G,
+    cache: C,
+    build_id: String,
+    read_only: bool,
+    /// The tasks that were persisted or downloaded by this process and are
+    /// known to be pure. Dependents of tasks that aren't in here, e.g. because
+    /// they were persisted by a previous process, are not shared.
+    pure_tasks: parking_lot::Mutex<HashSet<TaskId>>,
+}
+
+impl<G: PersistedGraph, C: RemoteCache> RemoteCachePersistedGraph<G, C> {
+    /// The `build_id` identifies the code of the task functions, e.g. the
+    /// version and the git commit of the binary. Task results are only
+    /// shared between builds with the same id, as the functions of other
+    /// builds may compute other results.
+    pub fn new(inner: G, cache: C, build_id: impl Into<String>) -> Self {
+        Self {
+            inner,
+            cache,
+            build_id: build_id.into(),
+            read_only: false,
+            pure_tasks: Default::default(),
+        }
+    }
+
+    /// A read-only cache only downloads task results, e.g. for builds of
+    /// untrusted branches.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn download(
+        &self,
+        task_type: &PersistentTaskType,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<TaskId>> {
+        let key = Encoder::new(api, &self.build_id).key(task_type)?;
+        let Some(bytes) = self.cache.get(&key)? else {
+            return Ok(None);
+        };
+        let remote: RemoteTaskData = serde_json::from_slice(&bytes)?;
+        let mut decoder = Decoder {
+            api,
+            tasks: &remote.tasks,
+            resolved: HashMap::new(),
+        };
+        let data: TaskData = decoder.decode(&remote.data)?;
+        let task = api.get_or_create_task_type(task_type.clone());
+        let persisted = self.inner.persist(
+            task,
+            data,
+            PersistTaskState {
+                externally_active: false,
+            },
+            api,
+        )?;
+        if persisted.is_none() {
+            return Ok(None);
+        }
+        self.pure_tasks.lock().insert(task);
+        Ok(Some(task))
+    }
+
+    /// Updates whether `task` is pure and returns it.
+    fn update_pure(&self, task: TaskId, data: &TaskData) -> bool {
+        let mut pure_tasks = self.pure_tasks.lock();
+        let pure = !data.reads_external
+            && data
+                .dependencies
+                .iter()
+                .all(|dependency| pure_tasks.contains(&dependency.get_task_id()));
+        if pure {
+            pure_tasks.insert(task);
+        } else {
+            pure_tasks.remove(&task);
+        }
+        pure
+    }
+
+    fn upload(&self, task: TaskId, data: &TaskData, api: &dyn PersistedGraphApi) -> Result<()> {
+        // Cells that need to be computed again are not worth sharing.
+        if data
+            .cells
+            .iter()
+            .any(|(_, cell)| matches!(cell, TaskCell::NeedComputation))
+        {
+            return Ok(());
+        }
+        let mut encoder = Encoder::new(api, &self.build_id);
+        let key = encoder.task_key(task)?;
+        let data = encoder.encode(data)?;
+        let remote = encoder.finish(data)?;
+        self.cache.put(&key, &serde_json::to_vec(&remote)?)
+    }
+}
+
+impl<G: PersistedGraph, C: RemoteCache> PersistedGraph for RemoteCachePersistedGraph<G, C> {
+    fn read(
+        &self,
+        task: TaskId,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<(TaskData, ReadTaskState)>> {
+        self.inner.read(task, api)
+    }
+
+    fn lookup(
+        &self,
+        partial_task_type: &PersistentTaskType,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<bool> {
+        self.inner.lookup(partial_task_type, api)
+    }
+
+    fn lookup_one(
+        &self,
+        task_type: &PersistentTaskType,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<TaskId>> {
+        if let Some(task) = self.inner.lookup_one(task_type, api)? {
+            return Ok(Some(task));
+        }
+        // The remote cache is best effort, a failure is a cache miss.
+        Ok(self.download(task_type, api).unwrap_or(None))
+    }
+
+    fn is_persisted(&self, task: TaskId, api: &dyn PersistedGraphApi) -> Result<bool> {
+        self.inner.is_persisted(task, api)
+    }
+
+    fn persist(
+        &self,
+        task: TaskId,
+        data: TaskData,
+        state: PersistTaskState,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<PersistResult>> {
+        // Dependencies are persisted before their dependents, so their purity
+        // is known at this point.
+        if self.update_pure(task, &data) && !self.read_only {
+            // The remote cache is best effort, a failed upload is ignored.
+            let _ = self.upload(task, &data, api);
+        }
+        self.inner.persist(task, data, state, api)
+    }
+
+    fn activate_when_needed(
+        &self,
+        task: TaskId,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<ActivateResult>> {
+        self.inner.activate_when_needed(task, api)
+    }
+
+    fn deactivate_when_needed(
+        &self,
+        task: TaskId,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<DeactivateResult>> {
+        self.inner.deactivate_when_needed(task, api)
+    }
+
+    fn set_externally_active(&self, task: TaskId, api: &dyn PersistedGraphApi) -> Result<bool> {
+        self.inner.set_externally_active(task, api)
+    }
+
+    fn unset_externally_active(&self, task: TaskId, api: &dyn PersistedGraphApi) -> Result<bool> {
+        self.inner.unset_externally_active(task, api)
+    }
+
+    fn remove_outdated_externally_active(
+        &self,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Vec<TaskId>> {
+        self.inner.remove_outdated_externally_active(api)
+    }
+
+    fn make_dirty(&self, task: TaskId, api: &dyn PersistedGraphApi) -> Result<bool> {
+        self.inner.make_dirty(task, api)
+    }
+
+    fn make_clean(&self, task: TaskId, api: &dyn PersistedGraphApi) -> Result<()> {
+        self.inner.make_clean(task, api)
+    }
+
+    fn make_dependent_dirty(&self, vc: RawVc, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        self.inner.make_dependent_dirty(vc, api)
+    }
+
+    fn get_active_external_tasks(&self, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        self.inner.get_active_external_tasks(api)
+    }
+
+    fn get_dirty_active_tasks(&self, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        self.inner.get_dirty_active_tasks(api)
+    }
+
+    fn get_pending_active_update(
+        &self,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<(Vec<TaskId>, Vec<TaskId>)> {
+        self.inner.get_pending_active_update(api)
+    }
+
+    fn stop(&self, api: &dyn PersistedGraphApi) -> Result<()> {
+        self.inner.stop(api)
+    }
+}
+
+/// A [RemoteCache] in memory, e.g. for tests.
+#[derive(Default)]
+pub struct MemoryRemoteCache {
+    entries: parking_lot::Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl RemoteCache for MemoryRemoteCache {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.entries.lock().get(key).cloned())
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        if let Entry::Vacant(entry) = self.entries.lock().entry(key.to_string()) {
+            entry.insert(data.to_vec());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{native_function::NativeFunction, registry, TaskInput};
+
+    struct TestApi {
+        types: Vec<PersistentTaskType>,
+    }
+
+    impl PersistedGraphApi for TestApi {
+        fn get_or_create_task_type(&self, ty: PersistentTaskType) -> TaskId {
+            let index = self.types.iter().position(|t| *t == ty).unwrap();
+            TaskId::from(index)
+        }
+
+        fn lookup_task_type(&self, id: TaskId) -> &PersistentTaskType {
+            &self.types[*id]
+        }
+    }
+
+    fn task_type(inputs: Vec<TaskInput>) -> PersistentTaskType {
+        static FUNCTION: once_cell::sync::Lazy<&'static NativeFunction> =
+            once_cell::sync::Lazy::new(|| {
+                let function = Box::leak(Box::new(NativeFunction::new("test".to_string(), |_| {
+                    bail!("not callable")
+                })));
+                function.register("turbo-tasks::remote_cache::tests::test");
+                function
+            });
+        PersistentTaskType::Native(registry::get_function_id(*FUNCTION), inputs)
+    }
+
+    fn data(dependencies: Vec<TaskId>, reads_external: bool) -> TaskData {
+        TaskData {
+            children: Vec::new(),
+            dependencies: dependencies.into_iter().map(RawVc::TaskOutput).collect(),
+            cells: Vec::new(),
+            output: RawVc::TaskOutput(TaskId::from(0)),
+            reads_external,
+        }
+    }
+
+    fn persist(
+        graph: &RemoteCachePersistedGraph<(), MemoryRemoteCache>,
+        task: usize,
+        data: TaskData,
+        api: &TestApi,
+    ) {
+        graph
+            .persist(
+                TaskId::from(task),
+                data,
+                PersistTaskState {
+                    externally_active: false,
+                },
+                api,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn only_shares_pure_tasks() {
+        let api = TestApi {
+            types: vec![
+                task_type(vec![TaskInput::String("external".to_string())]),
+                task_type(vec![TaskInput::String("pure".to_string())]),
+                task_type(vec![TaskInput::TaskOutput(TaskId::from(0))]),
+                task_type(vec![TaskInput::TaskOutput(TaskId::from(1))]),
+            ],
+        };
+        let graph = RemoteCachePersistedGraph::new((), MemoryRemoteCache::default(), "build");
+        let key = |task: usize| {
+            Encoder::new(&api, "build")
+                .task_key(TaskId::from(task))
+                .unwrap()
+        };
+        let is_shared = |task: usize| graph.cache.get(&key(task)).unwrap().is_some();
+
+        persist(&graph, 0, data(vec![], true), &api);
+        persist(&graph, 1, data(vec![], false), &api);
+        // Depends on a task which reads external state.
+        persist(
+            &graph,
+            2,
+            data(vec![TaskId::from(0), TaskId::from(1)], false),
+            &api,
+        );
+        persist(&graph, 3, data(vec![TaskId::from(1)], false), &api);
+        assert!(!is_shared(0));
+        assert!(is_shared(1));
+        assert!(!is_shared(2));
+        assert!(is_shared(3));
+
+        // A task is not pure anymore after it read external state.
+        persist(&graph, 1, data(vec![], true), &api);
+        persist(&graph, 3, data(vec![TaskId::from(1)], false), &api);
+        assert!(!graph.pure_tasks.lock().contains(&TaskId::from(3)));
+    }
+
+    #[test]
+    fn keys_depend_on_referenced_task_types() {
+        let api = TestApi {
+            types: vec![
+                task_type(vec![TaskInput::String("a".to_string())]),
+                task_type(vec![TaskInput::String("b".to_string())]),
+                task_type(vec![TaskInput::TaskOutput(TaskId::from(0))]),
+                task_type(vec![TaskInput::TaskOutput(TaskId::from(1))]),
+            ],
+        };
+        // The same task types with other ids.
+        let reordered = TestApi {
+            types: vec![
+                task_type(vec![TaskInput::String("b".to_string())]),
+                task_type(vec![TaskInput::String("a".to_string())]),
+                task_type(vec![TaskInput::TaskOutput(TaskId::from(1))]),
+            ],
+        };
+        let key = |api: &TestApi, task: usize| {
+            Encoder::new(api, "build")
+                .task_key(TaskId::from(task))
+                .unwrap()
+        };
+        assert_eq!(key(&api, 2), key(&reordered, 2));
+        assert_ne!(key(&api, 2), key(&api, 3));
+    }
+
+    #[test]
+    fn keys_depend_on_the_build() {
+        let api = TestApi {
+            types: vec![
+                task_type(vec![TaskInput::String("a".to_string())]),
+                task_type(vec![TaskInput::TaskOutput(TaskId::from(0))]),
+            ],
+        };
+        let key = |build_id: &str, task: usize| {
+            Encoder::new(&api, build_id)
+                .task_key(TaskId::from(task))
+                .unwrap()
+        };
+        assert_eq!(key("1.0.0-abc", 1), key("1.0.0-abc", 1));
+        assert_ne!(key("1.0.0-abc", 0), key("1.0.0-def", 0));
+        assert_ne!(key("1.0.0-abc", 1), key("1.0.0-def", 1));
+    }
+}
@@ -22,6 +22,37 @@ use crate::{
     CellId, RawVc, TaskId, TraitType, Typed, ValueTypeId,
 };
 
+/// Values are tagged with the global name of their type, followed by
+/// `@version` when the type has a version, so that values persisted before a
+/// type got a version are still read as version 0.
+fn versioned_type_name(ty: ValueTypeId) -> Cow<'static, str> {
+    let global_name = registry::get_value_type_global_name(ty);
+    match registry::get_value_type(ty).version {
+        0 => Cow::Borrowed(global_name),
+        version => Cow::Owned(format!("{global_name}@{version}")),
+    }
+}
+
+fn parse_versioned_type_name(name: &str) -> (&str, u32) {
+    name.rsplit_once('@')
+        .and_then(|(global_name, version)| Some((global_name, version.parse().ok()?)))
+        .unwrap_or((name, 0))
+}
+
+fn not_deserializable<E: serde::de::Error>(ty: ValueTypeId, version: u32) -> E {
+    let value_type = registry::get_value_type(ty);
+    if version == value_type.version {
+        E::custom(format!("{ty} is not deserializable"))
+    } else {
+        // This happens when reading a persistent cache that was written by
+        // another version of the crate. The value is discarded.
+        E::custom(format!(
+            "{ty} was persisted with version {version}, which can't be read by version {}",
+            value_type.version
+        ))
+    }
+}
+
 #[derive(Clone)]
 pub struct SharedReference(pub Option<ValueTypeId>, pub Arc<dyn Any + Send + Sync>);
 
@@ -82,7 +113,7 @@ impl Serialize for SharedReference {
             let value_type = registry::get_value_type(*ty);
             if let Some(serializable) = value_type.any_as_serializable(arc) {
                 let mut t = serializer.serialize_tuple(2)?;
-                t.serialize_element(registry::get_value_type_global_name(*ty))?;
+                t.serialize_element(&versioned_type_name(*ty))?;
                 t.serialize_element(serializable)?;
                 t.end()
             } else {
@@ -127,9 +158,11 @@ impl<'de> Deserialize<'de> for SharedReference {
             where
                 A: serde::de::SeqAccess<'de>,
             {
-                if let Some(global_name) = seq.next_element()? {
+                if let Some(name) = seq.next_element()? {
+                    let (global_name, version) = parse_versioned_type_name(name);
                     if let Some(ty) = registry::get_value_type_id_by_global_name(global_name) {
-                        if let Some(seed) = registry::get_value_type(ty).get_any_deserialize_seed()
+                        if let Some(seed) = registry::get_value_type(ty)
+                            .get_any_deserialize_seed_for_version(version)
                         {
                             if let Some(value) = seq.next_element_seed(seed)? {
                                 Ok(SharedReference(Some(ty), value.into()))
@@ -140,9 +173,7 @@ impl<'de> Deserialize<'de> for SharedReference {
                                 ))
                             }
                         } else {
-                            Err(serde::de::Error::custom(format!(
-                                "{ty} is not deserializable"
-                            )))
+                            Err(not_deserializable(ty, version))
                         }
                     } else {
                         Err(serde::de::Error::unknown_variant(global_name, &[]))
@@ -252,7 +283,7 @@ impl Serialize for SharedValue {
             let value_type = registry::get_value_type(*ty);
             if let Some(serializable) = value_type.magic_as_serializable(arc) {
                 let mut t = serializer.serialize_tuple(2)?;
-                t.serialize_element(registry::get_value_type_global_name(*ty))?;
+                t.serialize_element(&versioned_type_name(*ty))?;
                 t.serialize_element(serializable)?;
                 t.end()
             } else {
@@ -287,10 +318,11 @@ impl<'de> Deserialize<'de> for SharedValue {
             where
                 A: serde::de::SeqAccess<'de>,
             {
-                if let Some(global_name) = seq.next_element()? {
+                if let Some(name) = seq.next_element()? {
+                    let (global_name, version) = parse_versioned_type_name(name);
                     if let Some(ty) = registry::get_value_type_id_by_global_name(global_name) {
-                        if let Some(seed) =
-                            registry::get_value_type(ty).get_magic_deserialize_seed()
+                        if let Some(seed) = registry::get_value_type(ty)
+                            .get_magic_deserialize_seed_for_version(version)
                         {
                             if let Some(value) = seq.next_element_seed(seed)? {
                                 Ok(SharedValue(Some(ty), value.into()))
@@ -301,9 +333,7 @@ impl<'de> Deserialize<'de> for SharedValue {
                                 ))
                             }
                         } else {
-                            Err(serde::de::Error::custom(format!(
-                                "{ty} is not deserializable"
-                            )))
+                            Err(not_deserializable(ty, version))
                         }
                     } else {
                         Err(serde::de::Error::unknown_variant(global_name, &[]))
@@ -906,3 +936,20 @@ tuple_impls! { A B C D E F G H I }
 tuple_impls! { A B C D E F G H I J }
 tuple_impls! { A B C D E F G H I J K }
 tuple_impls! { A B C D E F G H I J K L }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_versioned_type_name;
+
+    #[test]
+    fn parses_versioned_type_name() {
+        assert_eq!(
+            parse_versioned_type_name("turbo-tasks@a1b2c3::module::Type"),
+            ("turbo-tasks@a1b2c3::module::Type", 0)
+        );
+        assert_eq!(
+            parse_versioned_type_name("turbo-tasks@a1b2c3::module::Type@2"),
+            ("turbo-tasks@a1b2c3::module::Type", 2)
+        );
+    }
+}
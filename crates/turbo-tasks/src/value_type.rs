@@ -1,6 +1,7 @@
 use std::{
     any::{type_name, Any},
     borrow::Cow,
+    cmp::Ordering,
     fmt::{self, Debug, Display, Formatter},
     hash::Hash,
     sync::Arc,
@@ -92,6 +93,22 @@ where
 /// or avoid Value<...> in favor of a real Vc
 pub trait TypedForInput: Typed {}
 
+/// Reads values that were persisted by an older version of a value type.
+///
+/// Value types that change their serialized format bump their version with
+/// `#[turbo_tasks::value(version = 2)]`. Persisted values of other versions
+/// are discarded, unless the type opts into migrating them with
+/// `#[turbo_tasks::value(version = 2, migrate)]` and implements this trait.
+pub trait MigrateValue: Sized {
+    /// Deserializes a value that was serialized with `version`, which is
+    /// lower than the current version of the type. Returning an error
+    /// discards the persisted value.
+    fn migrate(
+        version: u32,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Self, erased_serde::Error>;
+}
+
 type MagicSerializationFn = fn(&dyn MagicAny) -> &dyn erased_serde::Serialize;
 type AnySerializationFn = fn(&(dyn Any + Sync + Send)) -> &dyn erased_serde::Serialize;
 
@@ -112,9 +129,16 @@ pub struct ValueType {
     /// List of trait methods available
     pub trait_methods: AutoMap<(TraitTypeId, Cow<'static, str>), FunctionId>,
 
+    /// The version of the serialized format of the type. Persisted values of
+    /// other versions can't be deserialized without a migration.
+    pub version: u32,
+
     /// Functors for serialization
     magic_serialization: Option<(MagicSerializationFn, MagicAnyDeserializeSeed)>,
     any_serialization: Option<(AnySerializationFn, AnyDeserializeSeed)>,
+    /// Functors for deserializing older versions
+    magic_migration: Option<MagicAnyDeserializeSeed>,
+    any_migration: Option<AnyDeserializeSeed>,
 }
 
 impl Hash for ValueType {
@@ -172,8 +196,11 @@ impl ValueType {
             name: std::any::type_name::<T>().to_string(),
             traits: AutoSet::new(),
             trait_methods: AutoMap::new(),
+            version: 0,
             magic_serialization: None,
             any_serialization: None,
+            magic_migration: None,
+            any_migration: None,
         }
     }
 
@@ -185,11 +212,14 @@ impl ValueType {
             name: std::any::type_name::<T>().to_string(),
             traits: AutoSet::new(),
             trait_methods: AutoMap::new(),
+            version: 0,
             magic_serialization: Some((
                 <dyn MagicAny>::as_serialize::<T>,
                 MagicAnyDeserializeSeed::new::<T>(),
             )),
             any_serialization: Some((any_as_serialize::<T>, AnyDeserializeSeed::new::<T>())),
+            magic_migration: None,
+            any_migration: None,
         }
     }
 
@@ -201,8 +231,11 @@ impl ValueType {
             name: std::any::type_name::<T>().to_string(),
             traits: AutoSet::new(),
             trait_methods: AutoMap::new(),
+            version: 0,
             magic_serialization: None,
             any_serialization: Some((any_as_serialize::<T>, AnyDeserializeSeed::new::<T>())),
+            magic_migration: None,
+            any_migration: None,
         }
     }
 
@@ -237,6 +270,52 @@ impl ValueType {
         self.any_serialization.map(|s| s.1)
     }
 
+    /// Returns the seed for values that were serialized with `version`, which
+    /// migrates values of older versions. Returns `None` when such values
+    /// can't be deserialized.
+    pub fn get_magic_deserialize_seed_for_version(
+        &self,
+        version: u32,
+    ) -> Option<MagicAnyDeserializeSeed> {
+        match version.cmp(&self.version) {
+            Ordering::Equal => self.get_magic_deserialize_seed(),
+            Ordering::Less => self.magic_migration.map(|seed| seed.for_version(version)),
+            Ordering::Greater => None,
+        }
+    }
+
+    /// Returns the seed for values that were serialized with `version`, which
+    /// migrates values of older versions. Returns `None` when such values
+    /// can't be deserialized.
+    pub fn get_any_deserialize_seed_for_version(&self, version: u32) -> Option<AnyDeserializeSeed> {
+        match version.cmp(&self.version) {
+            Ordering::Equal => self.get_any_deserialize_seed(),
+            Ordering::Less => self.any_migration.map(|seed| seed.for_version(version)),
+            Ordering::Greater => None,
+        }
+    }
+
+    /// This is internally used by `#[turbo_tasks::value(version = ...)]`
+    pub fn set_version(&mut self, version: u32) {
+        self.version = version;
+    }
+
+    /// This is internally used by `#[turbo_tasks::value(migrate)]`
+    pub fn register_migration<T: MigrateValue + Any + Send + Sync + 'static>(&mut self) {
+        self.any_migration = Some(AnyDeserializeSeed::migrate::<T>());
+    }
+
+    /// This is internally used by `#[turbo_tasks::value(migrate)]` for types
+    /// that are serializable as task inputs.
+    pub fn register_magic_migration<
+        T: MigrateValue + Debug + Eq + Ord + Hash + Send + Sync + 'static,
+    >(
+        &mut self,
+    ) {
+        self.magic_migration = Some(MagicAnyDeserializeSeed::migrate::<T>());
+        self.any_migration = Some(AnyDeserializeSeed::migrate::<T>());
+    }
+
     /// This is internally used by `#[turbo_tasks::value_impl]`
     pub fn register_trait_method(
         &mut self,
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use turbo_tasks::trace::TraceRawVcs;
+use turbopack_core::{
+    issue::{PlainIssue, PlainIssueSource},
+    source_pos::SourcePos,
+};
+
+/// How issues are logged.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticsFormat {
+    /// Grouped and colored issues for humans.
+    #[default]
+    Human,
+    /// One [Diagnostic] per line (newline-delimited JSON), for editors and CI
+    /// annotators.
+    Json,
+}
+
+/// A 1-indexed position, as used by editors and CI annotations.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl From<SourcePos> for DiagnosticPosition {
+    fn from(pos: SourcePos) -> Self {
+        DiagnosticPosition {
+            line: pos.line.saturating_add(1),
+            column: pos.column.saturating_add(1),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticRange {
+    pub start: DiagnosticPosition,
+    pub end: DiagnosticPosition,
+}
+
+impl From<&PlainIssueSource> for DiagnosticRange {
+    fn from(source: &PlainIssueSource) -> Self {
+        DiagnosticRange {
+            start: source.start.into(),
+            end: source.end.into(),
+        }
+    }
+}
+
+/// Information related to a [Diagnostic], e.g. the sub issues of an issue.
+#[derive(Serialize, Debug)]
+pub struct RelatedDiagnostic<'a> {
+    pub file: &'a str,
+    pub range: Option<DiagnosticRange>,
+    pub message: &'a str,
+}
+
+/// The machine-readable form of an issue. This is a stable schema: fields may
+/// be added, but existing fields keep their name and meaning.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic<'a> {
+    /// The category of the issue, e.g. `resolve` or `parse`.
+    pub code: &'a str,
+    pub severity: &'static str,
+    /// The path of the file relative to the project root.
+    pub file: &'a str,
+    pub range: Option<DiagnosticRange>,
+    pub message: &'a str,
+    pub description: &'a str,
+    pub detail: &'a str,
+    pub documentation_link: &'a str,
+    pub related: Vec<RelatedDiagnostic<'a>>,
+    /// Set when a previously reported issue has disappeared in a rebuild.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub resolved: bool,
+}
+
+fn project_relative(context: &str) -> &str {
+    context.strip_prefix("[project]/").unwrap_or(context)
+}
+
+impl<'a> From<&'a PlainIssue> for Diagnostic<'a> {
+    fn from(issue: &'a PlainIssue) -> Self {
+        Diagnostic {
+            code: &issue.category,
+            severity: issue.severity.as_str(),
+            file: project_relative(&issue.context),
+            range: issue.source.as_deref().map(DiagnosticRange::from),
+            message: &issue.title,
+            description: &issue.description,
+            detail: &issue.detail,
+            documentation_link: &issue.documentation_link,
+            related: issue
+                .sub_issues
+                .iter()
+                .map(|sub_issue| RelatedDiagnostic {
+                    file: project_relative(&sub_issue.context),
+                    range: sub_issue.source.as_deref().map(DiagnosticRange::from),
+                    message: &sub_issue.title,
+                })
+                .collect(),
+            resolved: false,
+        }
+    }
+}
+
+impl Diagnostic<'_> {
+    /// Serializes the diagnostic as a single line of JSON, without the
+    /// trailing newline.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("diagnostics are always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use turbopack_core::issue::{IssueSeverity, PlainIssue};
+
+    use super::Diagnostic;
+
+    fn issue(context: &str, title: &str) -> PlainIssue {
+        PlainIssue {
+            severity: IssueSeverity::Error,
+            context: context.to_string(),
+            category: "resolve".to_string(),
+            title: title.to_string(),
+            description: "The module could not be found".to_string(),
+            detail: String::new(),
+            documentation_link: String::new(),
+            source: None,
+            sub_issues: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn serializes_to_a_single_line() {
+        let issue = issue("[project]/src/index.js", "Module not found:\n\"foo\"");
+        let line = Diagnostic::from(&issue).to_json_line();
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            line,
+            r#"{"code":"resolve","severity":"error","file":"src/index.js","range":null,"message":"Module not found:\n\"foo\"","description":"The module could not be found","detail":"","documentationLink":"","related":[]}"#
+        );
+    }
+
+    #[test]
+    fn marks_resolved_issues() {
+        let issue = issue("[project]/src/index.js", "Module not found");
+        let mut diagnostic = Diagnostic::from(&issue);
+        diagnostic.resolved = true;
+        assert!(diagnostic
+            .to_json_line()
+            .ends_with(r#""related":[],"resolved":true}"#));
+    }
+}
@@ -11,16 +11,15 @@ use anyhow::{anyhow, Result};
 use crossterm::style::{StyledContent, Stylize};
 use owo_colors::{OwoColorize as _, Style};
 use turbo_tasks::{RawVc, TransientValue, TryJoinIterExt, ValueToString};
-use turbo_tasks_fs::{
-    attach::AttachedFileSystemVc,
-    source_context::{get_source_context, SourceContextLine},
-    to_sys_path, FileLinesContent, FileSystemPathVc,
-};
+use turbo_tasks_fs::{attach::AttachedFileSystemVc, to_sys_path, FileSystemPathVc};
 use turbopack_core::issue::{
+    code_frame::{CodeFrame, CodeFrameLine},
     Issue, IssueProcessingPathItem, IssueSeverity, IssueVc, OptionIssueProcessingPathItemsVc,
-    PlainIssue, PlainIssueSource,
+    PlainIssue, PlainIssueReadRef, PlainIssueSource,
 };
 
+use crate::diagnostics::{Diagnostic, DiagnosticsFormat};
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct IssueSeverityCliOption(pub IssueSeverity);
 
@@ -79,93 +78,88 @@ fn severity_to_style(severity: IssueSeverity) -> Style {
 }
 
 fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String) {
-    if let FileLinesContent::Lines(lines) = source.asset.content.lines() {
-        let start_line = source.start.line;
-        let end_line = source.end.line;
-        let start_column = source.start.column;
-        let end_column = source.end.column;
-        let lines = lines.iter().map(|l| l.content.as_str());
-        let ctx = get_source_context(lines, start_line, start_column, end_line, end_column);
-        let f = formatted_issue;
-        for line in ctx.0 {
-            match line {
-                SourceContextLine::Context { line, outside } => {
-                    writeln!(f, "{}", format_args!("{line:>6} | {outside}").dimmed()).unwrap();
-                }
-                SourceContextLine::Start {
-                    line,
-                    before,
-                    inside,
-                } => {
+    if let Some(code_frame) = &source.code_frame {
+        format_code_frame(code_frame, formatted_issue);
+    }
+}
+
+/// Renders a colored code frame, with the highlighted range marked by `v` and
+/// `^`.
+pub fn format_code_frame(code_frame: &CodeFrame, f: &mut String) {
+    for frame_line in code_frame.lines.iter() {
+        let CodeFrameLine {
+            line,
+            before,
+            inside,
+            after,
+            is_start,
+            is_end,
+            highlighted,
+        } = frame_line;
+        match (*highlighted, *is_start, *is_end) {
+            (false, _, _) => {
+                writeln!(f, "{}", format_args!("{line:>6} | {before}").dimmed()).unwrap();
+            }
+            (true, true, false) => {
+                writeln!(
+                    f,
+                    "       | {}{}{}",
+                    " ".repeat(before.len()),
+                    "v".bold(),
+                    "-".repeat(inside.len()).bold(),
+                )
+                .unwrap();
+                writeln!(f, "{line:>6} + {}{}", before.dimmed(), inside.bold()).unwrap();
+            }
+            (true, false, true) => {
+                writeln!(f, "{line:>6} + {}{}", inside.bold(), after.dimmed()).unwrap();
+                writeln!(
+                    f,
+                    "       +{}{}",
+                    "-".repeat(inside.len()).bold(),
+                    "^".bold()
+                )
+                .unwrap();
+            }
+            (true, true, true) => {
+                if inside.len() >= 2 {
                     writeln!(
                         f,
-                        "       | {}{}{}",
+                        "       + {}{}{}{}",
                         " ".repeat(before.len()),
                         "v".bold(),
-                        "-".repeat(inside.len()).bold(),
-                    )
-                    .unwrap();
-                    writeln!(f, "{line:>6} + {}{}", before.dimmed(), inside.bold()).unwrap();
-                }
-                SourceContextLine::End {
-                    line,
-                    inside,
-                    after,
-                } => {
-                    writeln!(f, "{line:>6} + {}{}", inside.bold(), after.dimmed()).unwrap();
-                    writeln!(
-                        f,
-                        "       +{}{}",
-                        "-".repeat(inside.len()).bold(),
-                        "^".bold()
+                        "-".repeat(inside.len() - 2).bold(),
+                        "v".bold(),
                     )
                     .unwrap();
+                } else {
+                    writeln!(f, "       | {}{}", " ".repeat(before.len()), "v".bold()).unwrap();
                 }
-                SourceContextLine::StartAndEnd {
-                    line,
-                    before,
-                    inside,
-                    after,
-                } => {
-                    if inside.len() >= 2 {
-                        writeln!(
-                            f,
-                            "       + {}{}{}{}",
-                            " ".repeat(before.len()),
-                            "v".bold(),
-                            "-".repeat(inside.len() - 2).bold(),
-                            "v".bold(),
-                        )
-                        .unwrap();
-                    } else {
-                        writeln!(f, "       | {}{}", " ".repeat(before.len()), "v".bold()).unwrap();
-                    }
+                writeln!(
+                    f,
+                    "{line:>6} + {}{}{}",
+                    before.dimmed(),
+                    inside.bold(),
+                    after.dimmed()
+                )
+                .unwrap();
+                if inside.len() >= 2 {
                     writeln!(
                         f,
-                        "{line:>6} + {}{}{}",
-                        before.dimmed(),
-                        inside.bold(),
-                        after.dimmed()
+                        "       + {}{}{}{}",
+                        " ".repeat(before.len()),
+                        "^".bold(),
+                        "-".repeat(inside.len() - 2).bold(),
+                        "^".bold(),
                     )
                     .unwrap();
-                    if inside.len() >= 2 {
-                        writeln!(
-                            f,
-                            "       + {}{}{}{}",
-                            " ".repeat(before.len()),
-                            "^".bold(),
-                            "-".repeat(inside.len() - 2).bold(),
-                            "^".bold(),
-                        )
-                        .unwrap();
-                    } else {
-                        writeln!(f, "       | {}{}", " ".repeat(before.len()), "^".bold()).unwrap();
-                    }
-                }
-                SourceContextLine::Inside { line, inside } => {
-                    writeln!(f, "{:>6} + {}", line.bold(), inside.bold()).unwrap();
+                } else {
+                    writeln!(f, "       | {}{}", " ".repeat(before.len()), "^".bold()).unwrap();
                 }
             }
+            (true, false, false) => {
+                writeln!(f, "{:>6} + {}", line.bold(), inside.bold()).unwrap();
+            }
         }
     }
 }
@@ -283,6 +277,11 @@ pub type GroupedIssues = HashMap<IssueSeverity, HashMap<String, HashMap<String,
 
 const DEFAULT_SHOW_COUNT: usize = 3;
 
+/// Identical issues (same severity, category and title) in more files than
+/// this are collapsed into a single summary, unless all issues are shown.
+/// This happens e.g. when a missing package is imported all over a project.
+const MAX_IDENTICAL_ISSUES: usize = 5;
+
 const ORDERED_GROUPS: &[IssueSeverity] = &[
     IssueSeverity::Bug,
     IssueSeverity::Fatal,
@@ -301,6 +300,7 @@ pub struct LogOptions {
     pub show_all: bool,
     pub log_detail: bool,
     pub log_level: IssueSeverity,
+    pub diagnostics_format: DiagnosticsFormat,
 }
 
 /// Tracks the state of currently seen issues.
@@ -319,8 +319,8 @@ pub struct LogOptions {
 /// 4. An issue seen in a previous pull was not repulled, and the issue's
 /// count is decremented.
 ///
-/// Once an issue's count reaches zero, it's removed. If it is ever seen again,
-/// it is considered new and will be relogged.
+/// Once an issue's count reaches zero, it's removed and reported as resolved.
+/// If it is ever seen again, it is considered new and will be relogged.
 #[derive(Default)]
 struct SeenIssues {
     /// Keeps track of all issue pulled from the source. Used so that we can
@@ -332,6 +332,19 @@ struct SeenIssues {
     /// "seen" and will not be relogged. Once the count reaches zero, the
     /// issue is removed and the next time its seen it will be considered new.
     issues_count: HashMap<u64, usize>,
+
+    /// The issues that are currently seen, so that they can be reported once
+    /// they are resolved.
+    issues: HashMap<u64, PlainIssueReadRef>,
+}
+
+/// The result of synchronizing a pull of issues with [SeenIssues].
+#[derive(Default, Debug, PartialEq, Eq)]
+struct IssueChanges {
+    /// Issues that weren't seen before.
+    new: HashSet<u64>,
+    /// Issues that are no longer pulled by any source.
+    resolved: Vec<u64>,
 }
 
 impl SeenIssues {
@@ -341,7 +354,7 @@ impl SeenIssues {
 
     /// Synchronizes state between the issues previously pulled from this
     /// source, to the issues now pulled.
-    fn new_ids(&mut self, source: RawVc, issue_ids: HashSet<u64>) -> HashSet<u64> {
+    fn new_ids(&mut self, source: RawVc, issue_ids: HashSet<u64>) -> IssueChanges {
         let old = self.source_to_issue_ids.entry(source).or_default();
 
         // difference is the issues that were never counted before.
@@ -371,6 +384,7 @@ impl SeenIssues {
             .collect::<HashSet<_>>();
 
         // Old now contains only the ids that were not present in the new issue_ids.
+        let mut resolved = Vec::new();
         for id in old.iter() {
             match self.issues_count.entry(*id) {
                 Entry::Vacant(_) => unreachable!("issue must already be tracked to appear in old"),
@@ -380,6 +394,7 @@ impl SeenIssues {
                         // If this was the last counter of the issue, then we need to prune the
                         // value to free memory.
                         e.remove();
+                        resolved.push(*id);
                     } else {
                         // Another source counted the issue, and it must not be relogged until all
                         // sources remove it.
@@ -390,7 +405,29 @@ impl SeenIssues {
         }
 
         *old = issue_ids;
-        difference
+        IssueChanges {
+            new: difference,
+            resolved,
+        }
+    }
+
+    /// Like [SeenIssues::new_ids], but also remembers the new issues and
+    /// returns the issues that have been resolved.
+    fn update(
+        &mut self,
+        source: RawVc,
+        issues: impl IntoIterator<Item = (u64, PlainIssueReadRef)>,
+    ) -> (HashSet<u64>, Vec<PlainIssueReadRef>) {
+        let issues = issues.into_iter().collect::<HashMap<_, _>>();
+        let IssueChanges { new, resolved } = self.new_ids(source, issues.keys().copied().collect());
+        for id in &new {
+            self.issues.insert(*id, issues[id].clone());
+        }
+        let resolved = resolved
+            .iter()
+            .filter_map(|id| self.issues.remove(id))
+            .collect();
+        (new, resolved)
     }
 }
 
@@ -445,6 +482,7 @@ impl ConsoleUiVc {
             show_all,
             log_detail,
             log_level,
+            diagnostics_format,
             ..
         } = &this.options;
         let mut grouped_issues: GroupedIssues = HashMap::new();
@@ -460,26 +498,82 @@ impl ConsoleUiVc {
             .try_join()
             .await?;
 
-        let issue_ids = issues
-            .iter()
-            .map(|(_, _, _, id)| *id)
-            .collect::<HashSet<_>>();
-        let mut new_ids = this.seen.lock().unwrap().new_ids(source, issue_ids);
+        let (mut new_ids, resolved) = this.seen.lock().unwrap().update(
+            source,
+            issues
+                .iter()
+                .map(|(plain_issue, _, _, id)| (*id, plain_issue.clone())),
+        );
 
         let mut has_fatal = false;
         let has_issues = !issues.is_empty();
         let has_new_issues = !new_ids.is_empty();
 
-        for (plain_issue, path, context, id) in issues {
-            if !new_ids.remove(&id) {
+        for plain_issue in resolved
+            .iter()
+            .filter(|plain_issue| plain_issue.severity <= log_level)
+        {
+            if diagnostics_format == DiagnosticsFormat::Json {
+                let mut diagnostic = Diagnostic::from(&**plain_issue);
+                diagnostic.resolved = true;
+                println!("{}", diagnostic.to_json_line());
+            } else {
+                println!(
+                    "{} - [{}] {}  {}",
+                    "resolved".green(),
+                    plain_issue.category,
+                    plain_issue
+                        .context
+                        .strip_prefix("[project]/")
+                        .unwrap_or(&plain_issue.context)
+                        .bright_blue(),
+                    plain_issue.title.as_str().bold()
+                );
+            }
+        }
+
+        // Counts the new identical issues, to collapse floods of them. The JSON
+        // format is consumed by tools, which get every issue.
+        let mut identical_counts: HashMap<(IssueSeverity, &str, &str), usize> = HashMap::new();
+        if !show_all && diagnostics_format == DiagnosticsFormat::Human {
+            for (plain_issue, _, _, id) in issues.iter() {
+                if new_ids.contains(id) {
+                    *identical_counts
+                        .entry((
+                            plain_issue.severity,
+                            &plain_issue.category,
+                            &plain_issue.title,
+                        ))
+                        .or_default() += 1;
+                }
+            }
+        }
+        let mut floods: HashMap<(IssueSeverity, &str, &str), Vec<String>> = HashMap::new();
+
+        for (plain_issue, path, context, id) in issues.iter() {
+            if !new_ids.remove(id) {
                 continue;
             }
 
             let severity = plain_issue.severity;
-            let context_path = make_relative_to_cwd(context, current_dir).await?;
+            if diagnostics_format == DiagnosticsFormat::Json {
+                has_fatal = severity == IssueSeverity::Fatal;
+                if severity <= log_level {
+                    println!("{}", Diagnostic::from(&**plain_issue).to_json_line());
+                }
+                continue;
+            }
+            let context_path = make_relative_to_cwd(*context, current_dir).await?;
             let category = &plain_issue.category;
             let title = &plain_issue.title;
             has_fatal = severity == IssueSeverity::Fatal;
+
+            let key = (severity, category.as_str(), title.as_str());
+            if identical_counts.get(&key).copied().unwrap_or_default() > MAX_IDENTICAL_ISSUES {
+                floods.entry(key).or_default().push(context_path);
+                continue;
+            }
+
             let severity_map = grouped_issues
                 .entry(severity)
                 .or_insert_with(Default::default);
@@ -522,11 +616,34 @@ impl ConsoleUiVc {
                 if !documentation_link.is_empty() {
                     writeln!(&mut styled_issue, "\ndocumentation: {documentation_link}")?;
                 }
-                format_optional_path(&path, &mut styled_issue).await?;
+                format_optional_path(path, &mut styled_issue).await?;
             }
             issues.push(styled_issue);
         }
 
+        for ((severity, category, title), mut context_paths) in floods {
+            context_paths.sort();
+            let mut styled_issue = format!("{} (in {} files)", title.bold(), context_paths.len());
+            for context_path in context_paths.iter().skip(1).take(DEFAULT_SHOW_COUNT) {
+                write!(&mut styled_issue, "\n  also in {context_path}")?;
+            }
+            if context_paths.len() > DEFAULT_SHOW_COUNT + 1 {
+                write!(
+                    &mut styled_issue,
+                    "\n  {}",
+                    show_all_message("files", context_paths.len() - 1)
+                )?;
+            }
+            grouped_issues
+                .entry(severity)
+                .or_insert_with(Default::default)
+                .entry(category.to_string())
+                .or_insert_with(Default::default)
+                .entry(context_paths.swap_remove(0))
+                .or_insert_with(Default::default)
+                .push(styled_issue);
+        }
+
         for severity in ORDERED_GROUPS.iter().copied().filter(|l| *l <= log_level) {
             if let Some(severity_map) = grouped_issues.get_mut(&severity) {
                 let severity_map_size = severity_map.len();
@@ -2,6 +2,7 @@
 #![feature(min_specialization)]
 #![feature(round_char_boundary)]
 
+pub mod diagnostics;
 pub mod issue;
 
 pub fn register() {
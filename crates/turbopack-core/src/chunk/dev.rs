@@ -1,14 +1,25 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use turbo_tasks::{
     primitives::{BoolVc, StringVc},
     Value, ValueToString,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{
+    glob::Glob, rope::RopeBuilder, FileJsonContent, FileSystemPathOptionVc, FileSystemPathVc,
+};
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
-use super::{ChunkingContext, ChunkingContextVc};
+use super::{
+    profile::{OptionUsageProfileVc, UsageProfileVc},
+    ChunkBanner, ChunkBannerRule, ChunkBannerVc, ChunkingContext, ChunkingContextVc,
+    CommentsPolicy, CommentsPolicyVc, CommonJsInterop, CommonJsInteropVc, ImportMetaEnvVc,
+    MinifyOptions, MinifyOptionsVc, OptionMinifyOptionsVc, OutputFormat, OutputFormatVc,
+    RuntimeChunking, RuntimeChunkingVc,
+};
 use crate::{
-    asset::{Asset, AssetVc},
+    asset::{Asset, AssetVc, AssetsVc},
+    config::ChunkingConfig,
     environment::EnvironmentVc,
 };
 
@@ -32,11 +43,149 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    /// Serves the output root under a subpath, e.g. `/app/`, which is
+    /// prepended to the URLs of chunks.
+    pub fn base_path(mut self, base_path: &str) -> Self {
+        self.context.base_path = normalize_base_path(base_path);
+        self
+    }
+
+    /// Enables tree shaking of unused exports. All entries of the build need
+    /// to be passed as roots.
+    pub fn tree_shaking(mut self, roots: AssetsVc) -> Self {
+        self.context.tree_shaking_roots = Some(roots);
+        self
+    }
+
+    /// Minifies emitted chunks with the given options.
+    pub fn minify(mut self, options: MinifyOptions) -> Self {
+        self.context.minify = Some(options);
+        self
+    }
+
+    pub fn commonjs_interop(mut self, interop: CommonJsInterop) -> Self {
+        self.context.commonjs_interop = interop;
+        self
+    }
+
+    /// Adds a variable to the `import.meta.env` object.
+    pub fn import_meta_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context
+            .import_meta_env
+            .insert(name.into(), value.into());
+        self
+    }
+
+    /// Downlevels modern syntax to the runtime versions of the environment,
+    /// e. g. the browserslist targets for the browser.
+    pub fn downlevel_syntax(mut self) -> Self {
+        self.context.downlevel_syntax = true;
+        self
+    }
+
+    /// Only compiles the modules of `import()` expressions when they are
+    /// requested for the first time.
+    pub fn lazy_compilation(mut self) -> Self {
+        self.context.lazy_compilation = true;
+        self
+    }
+
+    /// Sets which comments are kept in emitted chunks, e. g. only legal
+    /// comments for production builds.
+    pub fn comments_policy(mut self, policy: CommentsPolicy) -> Self {
+        self.context.comments_policy = policy;
+        self
+    }
+
+    /// Sets how the runtime of the chunks is emitted, e. g. as a separate
+    /// chunk that can be cached for a long time.
+    pub fn runtime_chunking(mut self, runtime_chunking: RuntimeChunking) -> Self {
+        self.context.runtime_chunking = runtime_chunking;
+        self
+    }
+
+    /// Sets the format of the emitted JavaScript chunks, e. g. ES modules for
+    /// modern targets.
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.context.output_format = output_format;
+        self
+    }
+
+    /// Emits chunks which are executed by Node.js on a server next to the
+    /// project at `project_root`. Whether the chunks are CommonJS or ES
+    /// modules follows the `type` in the package.json of the project, and
+    /// `__dirname` and `__filename` are the paths of the source files.
+    pub fn node_server(mut self, project_root: FileSystemPathVc) -> Self {
+        self.context.node_server_project_root = Some(project_root);
+        self
+    }
+
+    /// Uses a usage profile to place the modules that are executed on the same
+    /// routes together, apart from the modules that are not executed.
+    pub fn usage_profile(mut self, profile: UsageProfileVc) -> Self {
+        self.context.usage_profile = Some(profile);
+        self
+    }
+
+    /// Makes the runtime work under a strict Content Security Policy, which
+    /// doesn't allow `unsafe-eval`.
+    pub fn strict_csp(mut self) -> Self {
+        self.context.strict_csp = true;
+        self
+    }
+
+    /// Adds the module federation runtime to the runtime.
+    pub fn federation(mut self) -> Self {
+        self.context.federation = true;
+        self
+    }
+
+    /// Emits every entry as a single self-contained JavaScript file, with the
+    /// runtime embedded. See [ChunkingContext::single_file].
+    pub fn single_file(mut self) -> Self {
+        self.context.single_file = true;
+        self
+    }
+
+    /// Adds a banner and footer to the emitted chunks that match the rule.
+    /// When multiple rules match, their banners and footers are added in
+    /// order.
+    pub fn chunk_banner(mut self, rule: ChunkBannerRule) -> Self {
+        self.context.chunk_banners.push(rule);
+        self
+    }
+
+    /// Applies the chunking section of a project config. Options which the
+    /// config doesn't set are left as they are.
+    pub fn config(mut self, config: &ChunkingConfig) -> Self {
+        if let Some(base_path) = &config.base_path {
+            self = self.base_path(base_path);
+        }
+        if let Some(lazy_compilation) = config.lazy_compilation {
+            self.context.lazy_compilation = lazy_compilation;
+        }
+        if let Some(strict_csp) = config.strict_csp {
+            self.context.strict_csp = strict_csp;
+        }
+        self
+    }
+
     pub fn build(self) -> ChunkingContextVc {
         DevChunkingContextVc::new(Value::new(self.context)).into()
     }
 }
 
+/// Normalizes a base path to either an empty string or a path with a leading
+/// and without a trailing `/`, e.g. `app/` to `/app`.
+pub fn normalize_base_path(base_path: &str) -> String {
+    let base_path = base_path.trim_matches('/');
+    if base_path.is_empty() {
+        String::new()
+    } else {
+        format!("/{base_path}")
+    }
+}
+
 /// A chunking context for development mode.
 /// It uses readable filenames and module ids to improve development.
 /// It also uses a chunking heuristic that is incremental and cacheable.
@@ -53,6 +202,8 @@ pub struct DevChunkingContext {
     chunk_root_path: FileSystemPathVc,
     /// Css Chunks are placed at this path
     css_chunk_root_path: Option<FileSystemPathVc>,
+    /// The path the output root is served under
+    base_path: String,
     /// Static assets are placed at this path
     asset_root_path: FileSystemPathVc,
     /// Layer name within this context
@@ -61,6 +212,36 @@ pub struct DevChunkingContext {
     enable_hot_module_replacement: bool,
     /// The environment chunks will be evaluated in.
     environment: EnvironmentVc,
+    /// Entries used to compute the export usage when tree shaking is enabled
+    tree_shaking_roots: Option<AssetsVc>,
+    /// Options for minifying emitted chunks
+    minify: Option<MinifyOptions>,
+    /// How ES modules import CommonJS modules
+    commonjs_interop: CommonJsInterop,
+    /// Variables of the `import.meta.env` object
+    import_meta_env: BTreeMap<String, String>,
+    /// Downlevel modern syntax to the environment
+    downlevel_syntax: bool,
+    /// Compile the modules of `import()` expressions on first request
+    lazy_compilation: bool,
+    /// Which comments are kept in emitted chunks
+    comments_policy: CommentsPolicy,
+    /// Banners and footers of emitted chunks
+    chunk_banners: Vec<ChunkBannerRule>,
+    /// How the runtime of the chunks is emitted
+    runtime_chunking: RuntimeChunking,
+    /// The format of the emitted JavaScript chunks
+    output_format: OutputFormat,
+    /// The project root when chunks are executed by Node.js on a server
+    node_server_project_root: Option<FileSystemPathVc>,
+    /// The usage profile that guides the chunking
+    usage_profile: Option<UsageProfileVc>,
+    /// Avoid `eval` in the runtime
+    strict_csp: bool,
+    /// Include the module federation runtime
+    federation: bool,
+    /// Emit a single file per entry
+    single_file: bool,
 }
 
 impl DevChunkingContextVc {
@@ -77,10 +258,26 @@ impl DevChunkingContextVc {
                 output_root_path,
                 chunk_root_path,
                 css_chunk_root_path: None,
+                base_path: String::new(),
                 asset_root_path,
                 layer: None,
                 enable_hot_module_replacement: false,
                 environment,
+                tree_shaking_roots: None,
+                minify: None,
+                commonjs_interop: CommonJsInterop::default(),
+                import_meta_env: BTreeMap::new(),
+                downlevel_syntax: false,
+                lazy_compilation: false,
+                comments_policy: CommentsPolicy::default(),
+                chunk_banners: Vec::new(),
+                runtime_chunking: RuntimeChunking::default(),
+                output_format: OutputFormat::default(),
+                node_server_project_root: None,
+                usage_profile: None,
+                strict_csp: false,
+                federation: false,
+                single_file: false,
             },
         }
     }
@@ -204,10 +401,208 @@ impl ChunkingContext for DevChunkingContext {
         StringVc::cell(self.layer.clone().unwrap_or_default())
     }
 
+    #[turbo_tasks::function]
+    fn base_path(&self) -> StringVc {
+        StringVc::cell(self.base_path.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn tree_shaking_roots(&self) -> AssetsVc {
+        self.tree_shaking_roots.unwrap_or_else(AssetsVc::empty)
+    }
+
+    #[turbo_tasks::function]
+    fn minify_options(&self) -> OptionMinifyOptionsVc {
+        OptionMinifyOptionsVc::cell(self.minify.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn commonjs_interop(&self) -> CommonJsInteropVc {
+        self.commonjs_interop.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn import_meta_env(&self) -> ImportMetaEnvVc {
+        ImportMetaEnvVc::cell(self.import_meta_env.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn downlevel_syntax(&self) -> BoolVc {
+        BoolVc::cell(self.downlevel_syntax)
+    }
+
+    #[turbo_tasks::function]
+    fn lazy_compilation(&self) -> BoolVc {
+        BoolVc::cell(self.lazy_compilation)
+    }
+
+    #[turbo_tasks::function]
+    fn comments_policy(&self) -> CommentsPolicyVc {
+        self.comments_policy.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn runtime_chunking(&self) -> RuntimeChunkingVc {
+        if self.single_file {
+            return RuntimeChunking::Embedded.cell();
+        }
+        self.runtime_chunking.cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn output_format(&self) -> Result<OutputFormatVc> {
+        if let Some(project_root) = self.node_server_project_root {
+            // Node.js treats `.js` files as ES modules when the package.json says so.
+            if let FileJsonContent::Content(package) =
+                &*project_root.join("package.json").read_json().await?
+            {
+                return Ok(match package["type"].as_str() {
+                    Some("module") => OutputFormat::EsModule,
+                    _ => OutputFormat::Script,
+                }
+                .cell());
+            }
+        }
+        Ok(self.output_format.cell())
+    }
+
+    #[turbo_tasks::function]
+    fn node_server_project_root(&self) -> FileSystemPathOptionVc {
+        FileSystemPathOptionVc::cell(self.node_server_project_root)
+    }
+
+    #[turbo_tasks::function]
+    fn usage_profile(&self) -> OptionUsageProfileVc {
+        OptionUsageProfileVc::cell(self.usage_profile)
+    }
+
+    #[turbo_tasks::function]
+    fn strict_csp(&self) -> BoolVc {
+        BoolVc::cell(self.strict_csp)
+    }
+
+    #[turbo_tasks::function]
+    fn federation(&self) -> BoolVc {
+        BoolVc::cell(self.federation)
+    }
+
+    #[turbo_tasks::function]
+    fn single_file(&self) -> BoolVc {
+        BoolVc::cell(self.single_file)
+    }
+
+    #[turbo_tasks::function]
+    async fn chunk_banner(&self, chunk_path: FileSystemPathVc) -> Result<ChunkBannerVc> {
+        if self.chunk_banners.is_empty() {
+            return Ok(ChunkBanner::default().cell());
+        }
+        let output_root = self.output_root_path.await?;
+        let chunk_path = chunk_path.await?;
+        let path = output_root
+            .get_path_to(&chunk_path)
+            .unwrap_or(chunk_path.path.as_str());
+        let mut banner = RopeBuilder::default();
+        let mut footer = RopeBuilder::default();
+        for rule in self.chunk_banners.iter() {
+            if let Some(pattern) = &rule.pattern {
+                if !Glob::parse(pattern)?.execute(path) {
+                    continue;
+                }
+            }
+            if let Some(text) = &rule.banner {
+                banner.push_bytes(text.as_bytes());
+                banner += "\n";
+            }
+            if let Some(text) = &rule.footer {
+                footer += "\n";
+                footer.push_bytes(text.as_bytes());
+            }
+        }
+        Ok(ChunkBanner {
+            banner: (!banner.is_empty()).then(|| banner.build()),
+            footer: (!footer.is_empty()).then(|| footer.build()),
+        }
+        .cell())
+    }
+
     #[turbo_tasks::function]
     async fn with_layer(self_vc: DevChunkingContextVc, layer: &str) -> Result<ChunkingContextVc> {
         let mut context = self_vc.await?.clone_value();
         context.layer = (!layer.is_empty()).then(|| layer.to_string());
         Ok(DevChunkingContextVc::new(Value::new(context)).into())
     }
+
+    #[turbo_tasks::function]
+    async fn with_environment(
+        self_vc: DevChunkingContextVc,
+        environment: EnvironmentVc,
+    ) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.environment = environment;
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn with_tree_shaking(
+        self_vc: DevChunkingContextVc,
+        roots: AssetsVc,
+    ) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.tree_shaking_roots = Some(roots);
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn with_minify(
+        self_vc: DevChunkingContextVc,
+        options: MinifyOptionsVc,
+    ) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.minify = Some(options.await?.clone_value());
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn with_downlevel_syntax(self_vc: DevChunkingContextVc) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.downlevel_syntax = true;
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn with_strict_csp(self_vc: DevChunkingContextVc) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.strict_csp = true;
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn with_federation(self_vc: DevChunkingContextVc) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.federation = true;
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+    #[turbo_tasks::function]
+    async fn with_usage_profile(
+        self_vc: DevChunkingContextVc,
+        profile: UsageProfileVc,
+    ) -> Result<ChunkingContextVc> {
+        let mut context = self_vc.await?.clone_value();
+        context.usage_profile = Some(profile);
+        Ok(DevChunkingContextVc::new(Value::new(context)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_base_path;
+
+    #[test]
+    fn normalizes_base_path() {
+        assert_eq!(normalize_base_path(""), "");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("app"), "/app");
+        assert_eq!(normalize_base_path("/app/"), "/app");
+        assert_eq!(normalize_base_path("app/nested/"), "/app/nested");
+    }
 }
@@ -1,24 +1,28 @@
 pub mod dev;
 pub mod optimize;
+pub mod profile;
 
 use std::{
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     fmt::{Debug, Display},
 };
 
 use anyhow::{anyhow, Result};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
     debug::ValueDebugFormat,
     primitives::{BoolVc, StringVc},
     trace::TraceRawVcs,
-    TryFlatMapRecursiveJoinIterExt, TryJoinIterExt, ValueToString, ValueToStringVc,
+    TryFlatMapRecursiveJoinIterExt, TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{rope::Rope, FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::DeterministicHash;
 
-use self::optimize::optimize;
+use self::{
+    optimize::optimize,
+    profile::{OptionUsageProfileVc, UsageProfileVc},
+};
 use crate::{
     asset::{Asset, AssetVc, AssetsVc},
     environment::EnvironmentVc,
@@ -65,6 +69,115 @@ impl ModuleId {
 #[turbo_tasks::value(transparent, shared)]
 pub struct ModuleIds(Vec<ModuleIdVc>);
 
+/// Options for minifying emitted chunks.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub struct MinifyOptions {
+    /// Shortens the names of local variables and functions.
+    pub mangle: bool,
+    /// The number of compress passes. No compression is done when this is 0.
+    pub compress_passes: usize,
+    /// Names that are never renamed when mangling.
+    pub reserved_names: Vec<String>,
+}
+
+impl Default for MinifyOptions {
+    fn default() -> Self {
+        Self {
+            mangle: true,
+            compress_passes: 1,
+            reserved_names: Vec::new(),
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionMinifyOptions(Option<MinifyOptions>);
+
+/// How ES modules import CommonJS modules that set `__esModule`.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum CommonJsInterop {
+    /// The default import is `exports.default`, like with Babel and webpack.
+    #[default]
+    Babel,
+    /// The default import is `module.exports`, like in Node.js.
+    Node,
+}
+
+/// Which comments are kept in the emitted chunks.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum CommentsPolicy {
+    /// All comments are removed.
+    #[default]
+    None,
+    /// Legal comments, i. e. `/*! ... */` comments and comments containing
+    /// `@license` or `@preserve`, are kept in the chunks.
+    Legal,
+    /// Legal comments are removed from the chunks and written to a
+    /// `.LICENSE.txt` file next to each chunk instead.
+    ExtractLegal,
+    /// All comments are kept.
+    All,
+}
+
+/// How the runtime of the chunks is emitted.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum RuntimeChunking {
+    /// The runtime is embedded in every chunk that evaluates entries.
+    #[default]
+    Embedded,
+    /// The runtime is emitted as a chunk of its own, which is shared by all
+    /// evaluated chunks. It only changes when the runtime changes, so it can be
+    /// cached for a long time.
+    Separate,
+    /// The runtime is inlined into the HTML that loads the chunks, which saves
+    /// a request. It's emitted as a separate chunk for other consumers.
+    Inline,
+}
+
+/// The format of the emitted JavaScript chunks.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum OutputFormat {
+    /// Scripts which register themselves with the runtime through a global.
+    #[default]
+    Script,
+    /// ES modules which import the chunks they depend on and export the
+    /// exports of their entry, for modern browsers and Node.js. It's only
+    /// used when the chunks are loaded in the DOM or in Node.js.
+    EsModule,
+}
+
+/// Code that is added to the start and the end of the emitted chunks, e. g. a
+/// shebang for Node.js CLIs or a license banner.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub struct ChunkBannerRule {
+    /// A glob that is matched against the path of the chunk relative to the
+    /// output root. The rule applies to all chunks when this is None.
+    pub pattern: Option<String>,
+    /// Added to the start of the chunk, followed by a newline.
+    pub banner: Option<String>,
+    /// Added to the end of the chunk, preceded by a newline.
+    pub footer: Option<String>,
+}
+
+/// The code that is added to the start and the end of a chunk.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default)]
+pub struct ChunkBanner {
+    pub banner: Option<Rope>,
+    pub footer: Option<Rope>,
+}
+
+/// The variables of the `import.meta.env` object, which is inlined into the
+/// modules of a chunking context.
+#[turbo_tasks::value(transparent)]
+pub struct ImportMetaEnv(BTreeMap<String, String>);
+
 /// A context for the chunking that influences the way chunks are created
 #[turbo_tasks::value_trait]
 pub trait ChunkingContext {
@@ -88,7 +201,136 @@ pub trait ChunkingContext {
         StringVc::cell("".to_string())
     }
 
+    /// The path the output root is served under, e.g. `/app` for an app
+    /// hosted in a subpath. It's either empty or starts with a `/` and has no
+    /// trailing `/`, so the URL of a chunk is `{base_path}/{path}`.
+    fn base_path(&self) -> StringVc {
+        StringVc::cell("".to_string())
+    }
+
     fn with_layer(&self, layer: &str) -> ChunkingContextVc;
+
+    /// The entry [Asset]s of the whole build. When non-empty, exports that
+    /// are not used by any module reachable from these roots are removed
+    /// from the emitted chunks.
+    fn tree_shaking_roots(&self) -> AssetsVc {
+        AssetsVc::empty()
+    }
+
+    /// When set, emitted chunks are minified with these options.
+    fn minify_options(&self) -> OptionMinifyOptionsVc {
+        OptionMinifyOptionsVc::cell(None)
+    }
+
+    fn commonjs_interop(&self) -> CommonJsInteropVc {
+        CommonJsInterop::default().cell()
+    }
+
+    fn import_meta_env(&self) -> ImportMetaEnvVc {
+        ImportMetaEnvVc::cell(BTreeMap::new())
+    }
+
+    /// The code that is added to the start and the end of the chunk at the
+    /// given path.
+    fn chunk_banner(&self, _chunk_path: FileSystemPathVc) -> ChunkBannerVc {
+        ChunkBanner::default().cell()
+    }
+
+    /// Which comments of the modules are kept in the emitted chunks.
+    fn comments_policy(&self) -> CommentsPolicyVc {
+        CommentsPolicy::default().cell()
+    }
+
+    /// How the runtime of the chunks is emitted.
+    fn runtime_chunking(&self) -> RuntimeChunkingVc {
+        RuntimeChunking::default().cell()
+    }
+
+    /// The format of the emitted JavaScript chunks.
+    fn output_format(&self) -> OutputFormatVc {
+        OutputFormat::default().cell()
+    }
+
+    /// The root of the project on disk, when the chunks are executed by
+    /// Node.js on a server next to the project. `__dirname` and `__filename`
+    /// of the modules in the project are the paths of their source files then.
+    fn node_server_project_root(&self) -> FileSystemPathOptionVc {
+        FileSystemPathOptionVc::cell(None)
+    }
+
+    /// The usage profile that guides the chunking, so modules that are
+    /// executed on the same routes are placed together, apart from the modules
+    /// that are not executed.
+    fn usage_profile(&self) -> OptionUsageProfileVc {
+        OptionUsageProfileVc::cell(None)
+    }
+
+    /// Whether modern syntax is downleveled to the runtime versions of the
+    /// environment when chunk items are generated.
+    fn downlevel_syntax(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether the modules of `import()` expressions are only compiled when
+    /// they are requested by the runtime for the first time, instead of when
+    /// the chunk that contains the `import()` is referenced.
+    fn lazy_compilation(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether the runtime avoids `eval` and `new Function`, so the chunks can
+    /// run on pages with a strict Content Security Policy. Hot module
+    /// replacement adds updates as script elements with the nonce of the
+    /// page then.
+    fn strict_csp(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether the runtime includes the module federation runtime, which the
+    /// modules generated for federated modules use.
+    fn federation(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether every entry is emitted as a single self-contained JavaScript
+    /// file, for environments which can't load additional chunks, like
+    /// browser extensions and userscripts. Modules are never split into
+    /// parallel chunks and `import()` expressions are converted to eager
+    /// imports. Stylesheets and the scripts of workers are still emitted as
+    /// separate files.
+    fn single_file(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Returns a chunking context that emits chunks for the given
+    /// environment, e. g. for web workers spawned from the current one.
+    fn with_environment(&self, environment: EnvironmentVc) -> ChunkingContextVc;
+
+    /// Returns a chunking context that removes the exports which are not used
+    /// by any module reachable from `roots`. See
+    /// [ChunkingContext::tree_shaking_roots].
+    fn with_tree_shaking(&self, roots: AssetsVc) -> ChunkingContextVc;
+
+    /// Returns a chunking context that minifies the emitted chunks with the
+    /// given options.
+    fn with_minify(&self, options: MinifyOptionsVc) -> ChunkingContextVc;
+
+    /// Returns a chunking context that downlevels modern syntax to the
+    /// runtime versions of its environment. See
+    /// [ChunkingContext::downlevel_syntax].
+    fn with_downlevel_syntax(&self) -> ChunkingContextVc;
+
+    /// Returns a chunking context whose runtime works under a strict Content
+    /// Security Policy. See [ChunkingContext::strict_csp].
+    fn with_strict_csp(&self) -> ChunkingContextVc;
+
+    /// Returns a chunking context whose runtime includes the module federation
+    /// runtime. See [ChunkingContext::federation].
+    fn with_federation(&self) -> ChunkingContextVc;
+
+    /// Returns a chunking context which is guided by a usage profile. See
+    /// [ChunkingContext::usage_profile].
+    fn with_usage_profile(&self, profile: UsageProfileVc) -> ChunkingContextVc;
 }
 
 /// An [Asset] that can be converted into a [Chunk].
@@ -233,6 +475,22 @@ pub enum ChunkingType {
 #[turbo_tasks::value(transparent)]
 pub struct ChunkingTypeOption(Option<ChunkingType>);
 
+/// Hints how a chunk group that is loaded asynchronously (see
+/// [ChunkingType::SeparateAsync]) should be fetched ahead of time.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum AsyncLoadingHint {
+    /// The chunk group is only fetched when it is requested.
+    #[default]
+    None,
+    /// The chunk group will likely be needed for a future navigation and can be
+    /// fetched while the browser is idle.
+    Prefetch,
+    /// The chunk group will be needed by the current page and should be
+    /// fetched in parallel with the referencing chunk group.
+    Preload,
+}
+
 /// An [AssetReference] implementing this trait and returning true for
 /// [ChunkableAssetReference::is_chunkable] are considered as potentially
 /// chunkable references. When all [Asset]s of such a reference implement
@@ -244,6 +502,11 @@ pub trait ChunkableAssetReference: AssetReference + ValueToString {
     fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
         ChunkingTypeOptionVc::cell(Some(ChunkingType::default()))
     }
+
+    /// Only relevant for [ChunkingType::SeparateAsync] references.
+    fn async_loading_hint(&self) -> AsyncLoadingHintVc {
+        AsyncLoadingHint::None.cell()
+    }
 }
 
 /// A reference to a [Chunk]. Can be loaded in parallel, see [Chunk].
@@ -291,6 +554,54 @@ impl ValueToString for ChunkReference {
     }
 }
 
+/// A reference from a chunk that evaluates entries to the runtime, when the
+/// runtime is not embedded in the chunk. The runtime needs to be loaded
+/// together with the chunk.
+#[turbo_tasks::value]
+pub struct RuntimeAssetReference {
+    runtime: AssetVc,
+    inline: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl RuntimeAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(runtime: AssetVc, inline: bool) -> Self {
+        Self::cell(RuntimeAssetReference { runtime, inline })
+    }
+
+    #[turbo_tasks::function]
+    pub async fn runtime(self) -> Result<AssetVc> {
+        Ok(self.await?.runtime)
+    }
+
+    /// Whether the runtime should be inlined into the HTML that loads the
+    /// chunk.
+    #[turbo_tasks::function]
+    pub async fn is_inline(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(self.await?.inline))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for RuntimeAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        ResolveResult::asset(self.runtime).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for RuntimeAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "runtime {}",
+            self.runtime.path().to_string().await?
+        )))
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl ParallelChunkReference for ChunkReference {
     #[turbo_tasks::function]
@@ -303,13 +614,32 @@ impl ParallelChunkReference for ChunkReference {
 #[turbo_tasks::value]
 pub struct ChunkGroupReference {
     chunk_group: ChunkGroupVc,
+    hint: AsyncLoadingHint,
 }
 
 #[turbo_tasks::value_impl]
 impl ChunkGroupReferenceVc {
     #[turbo_tasks::function]
     pub fn new(chunk_group: ChunkGroupVc) -> Self {
-        Self::cell(ChunkGroupReference { chunk_group })
+        Self::cell(ChunkGroupReference {
+            chunk_group,
+            hint: AsyncLoadingHint::None,
+        })
+    }
+
+    /// Creates a reference to a chunk group which should be fetched ahead of
+    /// time as described by `hint`.
+    #[turbo_tasks::function]
+    pub fn new_with_hint(chunk_group: ChunkGroupVc, hint: Value<AsyncLoadingHint>) -> Self {
+        Self::cell(ChunkGroupReference {
+            chunk_group,
+            hint: hint.into_value(),
+        })
+    }
+
+    #[turbo_tasks::function]
+    pub async fn async_loading_hint(self) -> Result<AsyncLoadingHintVc> {
+        Ok(self.await?.hint.cell())
     }
 }
 
@@ -339,10 +669,36 @@ impl ValueToString for ChunkGroupReference {
     }
 }
 
+/// The chunks of the chunk groups which `chunks` reference with an
+/// [AsyncLoadingHint], with the strongest hint of each chunk. They are fetched
+/// ahead of time, e.g. by `<link rel="prefetch">` tags of a page.
+pub async fn hinted_chunks(chunks: &[ChunkVc]) -> Result<Vec<(AssetVc, AsyncLoadingHint)>> {
+    let mut hinted = IndexMap::<AssetVc, AsyncLoadingHint>::new();
+    for chunk in chunks {
+        for reference in chunk.references().await?.iter() {
+            let Some(reference) = ChunkGroupReferenceVc::resolve_from(reference).await? else {
+                continue;
+            };
+            let hint = *reference.async_loading_hint().await?;
+            if hint == AsyncLoadingHint::None {
+                continue;
+            }
+            for &asset in reference.resolve_reference().primary_assets().await?.iter() {
+                let entry = hinted.entry(asset.resolve().await?).or_default();
+                *entry = hint.max(*entry);
+            }
+        }
+    }
+    Ok(hinted.into_iter().collect())
+}
+
 pub struct ChunkContentResult<I> {
     pub chunk_items: Vec<I>,
     pub chunks: Vec<ChunkVc>,
     pub async_chunk_groups: Vec<ChunkGroupVc>,
+    /// Chunk groups of asynchronously loaded assets that should be fetched
+    /// ahead of time.
+    pub hinted_chunk_groups: Vec<(ChunkGroupVc, AsyncLoadingHint)>,
     pub external_asset_references: Vec<AssetReferenceVc>,
 }
 
@@ -379,6 +735,7 @@ enum ChunkContentWorkItem {
         result: ResolveResultVc,
         reference: AssetReferenceVc,
         chunking_type: ChunkingType,
+        async_loading_hint: AsyncLoadingHintVc,
     },
 }
 
@@ -392,8 +749,10 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
     let mut processed_assets = IndexSet::new();
     let mut chunks = Vec::new();
     let mut async_chunk_groups = Vec::new();
+    let mut hinted_chunk_groups = Vec::new();
     let mut external_asset_references = Vec::new();
     let mut queue = VecDeque::with_capacity(32);
+    let single_file = *context.single_file().await?;
 
     let chunk_item = I::from_asset(context, entry).await?.unwrap();
     queue.push_back(ChunkContentWorkItem::AssetReferences(
@@ -423,6 +782,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                                 result: r.resolve_reference(),
                                 reference: *r,
                                 chunking_type,
+                                async_loading_hint: pc.async_loading_hint(),
                             });
                             continue;
                         }
@@ -434,6 +794,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 result,
                 reference,
                 chunking_type,
+                async_loading_hint,
             } => {
                 // It's important to temporary store these results in these variables
                 // so that we can cancel to complete list of assets by that references together
@@ -450,7 +811,22 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 // not loaded in parallel
                 let mut inner_chunk_groups = Vec::new();
 
+                // Chunk groups that should be fetched ahead of time
+                let mut inner_hinted_chunk_groups = Vec::new();
+
                 let result = result.await?;
+                // An external keeps its reference, so the files it references,
+                // e.g. the binaries of a native package, stay in the graph.
+                let is_external = result.primary.iter().any(|result| {
+                    matches!(
+                        result,
+                        PrimaryResolveResult::OriginalReferenceExternal
+                            | PrimaryResolveResult::OriginalReferenceTypeExternal(_)
+                    )
+                });
+                if is_external && !result.references.is_empty() {
+                    external_asset_references.push(reference);
+                }
                 let assets = result.primary.iter().filter_map(|result| {
                     if let PrimaryResolveResult::Asset(asset) = *result {
                         if processed_assets.insert((chunking_type, asset)) {
@@ -481,12 +857,20 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                             }
                         }
                         ChunkingType::Parallel => {
+                            if single_file {
+                                if let Some(chunk_item) = I::from_asset(context, asset).await? {
+                                    inner_chunk_items.push(chunk_item);
+                                    continue;
+                                }
+                            }
                             let chunk = chunkable_asset.as_chunk(context);
                             inner_chunks.push(chunk);
                         }
                         ChunkingType::PlacedOrParallel => {
                             // heuristic for being in the same chunk
-                            if !split && *context.can_be_in_same_chunk(entry, asset).await? {
+                            if single_file
+                                || !split && *context.can_be_in_same_chunk(entry, asset).await?
+                            {
                                 // chunk item, chunk or other asset?
                                 if let Some(chunk_item) = I::from_asset(context, asset).await? {
                                     inner_chunk_items.push(chunk_item);
@@ -508,6 +892,17 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                                 inner_chunk_items.push(manifest_loader_item);
                                 inner_chunk_groups
                                     .push(ChunkGroupVc::from_asset(manifest_chunk, context));
+                                let hint = *async_loading_hint.await?;
+                                // Prefetching would compile the chunk group
+                                // ahead of time, which defeats lazy compilation.
+                                let skip_hint = hint == AsyncLoadingHint::Prefetch
+                                    && *context.lazy_compilation().await?;
+                                if hint != AsyncLoadingHint::None && !skip_hint {
+                                    inner_hinted_chunk_groups.push((
+                                        ChunkGroupVc::from_asset(chunkable_asset, context),
+                                        hint,
+                                    ));
+                                }
                             } else {
                                 external_asset_references.push(reference);
                                 continue 'outer;
@@ -526,11 +921,13 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 }
                 chunks.extend(inner_chunks);
                 async_chunk_groups.extend(inner_chunk_groups);
+                hinted_chunk_groups.extend(inner_hinted_chunk_groups);
 
                 // Make sure the chunk doesn't become too large.
                 // This will hurt performance in many aspects.
                 let chunk_items_count = chunk_items.len();
                 if !split
+                    && !single_file
                     && prev_chunk_items != chunk_items_count
                     && chunk_items_count > 5000
                     && prev_chunk_items > 1
@@ -547,6 +944,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
         chunk_items,
         chunks,
         async_chunk_groups,
+        hinted_chunk_groups,
         external_asset_references,
     }))
 }
@@ -0,0 +1,331 @@
+//! Usage profiles record which modules are executed on each route of an
+//! application. The dev runtime collects the ids of the executed modules in
+//! `globalThis.TURBOPACK_EXECUTED_MODULES`. The chunk optimizers use a profile
+//! to split chunks by the usage of their modules: the modules that were
+//! executed on the same routes (hot) are placed together, and the modules that
+//! were not executed (cold) are kept apart from them. Cold modules are still
+//! loaded with their chunk group, since a profile can miss modules which are
+//! executed.
+//!
+//! A profile is stored as JSON which maps routes to the ids of the modules
+//! that were executed on them:
+//!
+//! ```json
+//! { "/": ["[project]/src/index.js (ecmascript)"] }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+};
+
+use anyhow::{bail, Result};
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use turbo_tasks::trace::TraceRawVcs;
+use turbo_tasks_fs::{File, FileJsonContent, FileSystemPathVc};
+
+use crate::{
+    asset::{Asset, AssetContentVc},
+    reference::AssetReferencesVc,
+};
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+pub struct UsageProfile {
+    /// The ids of the modules executed on each route.
+    pub routes: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionUsageProfile(Option<UsageProfileVc>);
+
+/// The usage of a group of modules according to a usage profile.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, TraceRawVcs,
+)]
+pub enum ModuleUsage {
+    /// The modules that were executed on exactly these routes.
+    Routes(BTreeSet<String>),
+    /// The modules that were executed on any route.
+    Executed,
+    /// The modules that were not executed on any route.
+    NotExecuted,
+}
+
+impl ModuleUsage {
+    /// Whether a module belongs to the group.
+    pub fn includes(&self, profile: &UsageProfile, module_id: &str) -> bool {
+        match self {
+            ModuleUsage::Routes(routes) => profile.routes_of(module_id) == *routes,
+            ModuleUsage::Executed => profile.is_executed(module_id),
+            ModuleUsage::NotExecuted => !profile.is_executed(module_id),
+        }
+    }
+}
+
+impl Display for ModuleUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleUsage::Routes(routes) => {
+                write!(f, "executed on ")?;
+                for (i, route) in routes.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{route}")?;
+                }
+                Ok(())
+            }
+            ModuleUsage::Executed => write!(f, "executed"),
+            ModuleUsage::NotExecuted => write!(f, "not executed"),
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ModuleUsages(Vec<ModuleUsage>);
+
+/// Selects the modules of a chunk which have a usage in a profile, so a chunk
+/// can be split into parts.
+#[turbo_tasks::value(shared)]
+pub struct UsageFilter {
+    pub profile: UsageProfileVc,
+    pub usage: ModuleUsage,
+}
+
+impl UsageProfile {
+    /// Parses a profile from its JSON representation.
+    pub fn from_json(json: &JsonValue) -> Result<Self> {
+        let Some(routes) = json.as_object() else {
+            bail!("a usage profile must be an object of routes");
+        };
+        let mut profile = UsageProfile::default();
+        for (route, modules) in routes {
+            let Some(modules) = modules.as_array() else {
+                bail!("the modules of the route {route} must be an array");
+            };
+            // Module ids are numbers or strings.
+            profile.record(
+                route.clone(),
+                modules.iter().filter_map(|id| match id {
+                    JsonValue::String(id) => Some(id.clone()),
+                    JsonValue::Number(id) => Some(id.to_string()),
+                    _ => None,
+                }),
+            );
+        }
+        Ok(profile)
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(
+            self.routes
+                .iter()
+                .map(|(route, modules)| {
+                    (
+                        route.clone(),
+                        JsonValue::Array(modules.iter().cloned().map(JsonValue::String).collect()),
+                    )
+                })
+                .collect::<Map<_, _>>(),
+        )
+    }
+
+    /// Records modules that were executed on a route.
+    pub fn record(&mut self, route: String, modules: impl IntoIterator<Item = String>) {
+        self.routes.entry(route).or_default().extend(modules);
+    }
+
+    /// Adds the routes and modules of another profile, e.g. of another
+    /// session.
+    pub fn merge(&mut self, other: &UsageProfile) {
+        for (route, modules) in other.routes.iter() {
+            self.record(route.clone(), modules.iter().cloned());
+        }
+    }
+
+    /// The number of routes the module was executed on.
+    pub fn route_count(&self, module_id: &str) -> usize {
+        self.routes
+            .values()
+            .filter(|modules| modules.contains(module_id))
+            .count()
+    }
+
+    /// Whether the module was executed on any route.
+    pub fn is_executed(&self, module_id: &str) -> bool {
+        self.routes
+            .values()
+            .any(|modules| modules.contains(module_id))
+    }
+
+    /// The routes the module was executed on.
+    pub fn routes_of(&self, module_id: &str) -> BTreeSet<String> {
+        self.routes
+            .iter()
+            .filter(|(_, modules)| modules.contains(module_id))
+            .map(|(route, _)| route.clone())
+            .collect()
+    }
+
+    /// The usages a group of modules is split by: one for each set of routes
+    /// the modules were executed on, with the modules shared by the most
+    /// routes first, followed by the modules that were not executed. When
+    /// there are more than `max_parts` sets of routes, all executed modules
+    /// are placed together.
+    pub fn split<'a>(
+        &self,
+        module_ids: impl IntoIterator<Item = &'a str>,
+        max_parts: usize,
+    ) -> Vec<ModuleUsage> {
+        let mut route_sets = IndexSet::new();
+        let mut not_executed = false;
+        for module_id in module_ids {
+            let routes = self.routes_of(module_id);
+            if routes.is_empty() {
+                not_executed = true;
+            } else {
+                route_sets.insert(routes);
+            }
+        }
+        let mut usages = if route_sets.len() > max_parts {
+            vec![ModuleUsage::Executed]
+        } else {
+            let mut route_sets = route_sets.into_iter().collect::<Vec<_>>();
+            route_sets.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+            route_sets.into_iter().map(ModuleUsage::Routes).collect()
+        };
+        if not_executed {
+            usages.push(ModuleUsage::NotExecuted);
+        }
+        usages
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl UsageProfileVc {
+    /// Reads a profile from a JSON file. A missing file is an empty profile.
+    #[turbo_tasks::function]
+    pub async fn read(path: FileSystemPathVc) -> Result<Self> {
+        Ok(match &*path.read_json().await? {
+            FileJsonContent::Content(json) => UsageProfile::from_json(json)?.cell(),
+            FileJsonContent::NotFound => UsageProfile::default().cell(),
+            FileJsonContent::Unparseable(_) => bail!("{} is not valid JSON", path.await?.path),
+        })
+    }
+}
+
+/// Emits a usage profile as a JSON file, which can be read with
+/// [UsageProfileVc::read].
+#[turbo_tasks::value]
+pub struct UsageProfileAsset {
+    path: FileSystemPathVc,
+    profile: UsageProfileVc,
+}
+
+#[turbo_tasks::value_impl]
+impl UsageProfileAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(path: FileSystemPathVc, profile: UsageProfileVc) -> Self {
+        Self::cell(UsageProfileAsset { path, profile })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for UsageProfileAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let json = self.profile.await?.to_json();
+        Ok(File::from(serde_json::to_string_pretty(&json)?).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{ModuleUsage, UsageProfile};
+
+    #[test]
+    fn parse_and_merge() {
+        let mut profile = UsageProfile::from_json(&json!({
+            "/": ["a", "b"],
+            "/about": ["a"],
+        }))
+        .unwrap();
+        assert_eq!(profile.route_count("a"), 2);
+        assert_eq!(profile.route_count("b"), 1);
+        assert!(!profile.is_executed("c"));
+
+        profile.merge(&UsageProfile::from_json(&json!({ "/about": ["c"] })).unwrap());
+        assert!(profile.is_executed("c"));
+        assert_eq!(
+            profile.to_json(),
+            json!({
+                "/": ["a", "b"],
+                "/about": ["a", "c"],
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_numeric_module_ids() {
+        let profile = UsageProfile::from_json(&json!({ "/": [1, "a"] })).unwrap();
+        assert!(profile.is_executed("1"));
+        assert!(profile.is_executed("a"));
+    }
+
+    #[test]
+    fn split_by_routes() {
+        let profile = UsageProfile::from_json(&json!({
+            "/": ["shared", "home"],
+            "/about": ["shared", "about"],
+        }))
+        .unwrap();
+        let routes = |routes: &[&str]| {
+            ModuleUsage::Routes(routes.iter().map(|route| route.to_string()).collect())
+        };
+
+        assert_eq!(
+            profile.split(["home", "cold", "about", "shared", "home"], 3),
+            vec![
+                routes(&["/", "/about"]),
+                routes(&["/"]),
+                routes(&["/about"]),
+                ModuleUsage::NotExecuted,
+            ]
+        );
+        assert_eq!(profile.split(["home"], 3), vec![routes(&["/"])]);
+        assert_eq!(
+            profile.split(["home", "about", "shared", "cold"], 2),
+            vec![ModuleUsage::Executed, ModuleUsage::NotExecuted]
+        );
+
+        assert!(routes(&["/"]).includes(&profile, "home"));
+        assert!(!routes(&["/"]).includes(&profile, "shared"));
+        assert!(ModuleUsage::Executed.includes(&profile, "shared"));
+        assert!(ModuleUsage::NotExecuted.includes(&profile, "cold"));
+        assert_eq!(
+            routes(&["/", "/about"]).to_string(),
+            "executed on /, /about"
+        );
+    }
+
+    #[test]
+    fn reject_invalid() {
+        assert!(UsageProfile::from_json(&json!(["a"])).is_err());
+        assert!(UsageProfile::from_json(&json!({ "/": "a" })).is_err());
+    }
+}
@@ -6,7 +6,7 @@ use std::{
 
 use anyhow::Result;
 use sourcemap::SourceMapBuilder;
-use turbo_tasks_fs::rope::{Rope, RopeBuilder};
+use turbo_tasks_fs::rope::{Rope, RopeBuilder, RopeTemplate, TemplateValue};
 
 use crate::{
     source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapSection, SourceMapVc},
@@ -60,6 +60,17 @@ impl CodeBuilder {
         self.code += code;
     }
 
+    /// Pushes synthetic code rendered from a template. The static parts of
+    /// the template are shared instead of copied into the code.
+    pub fn push_template(
+        &mut self,
+        template: &RopeTemplate,
+        values: &[(&str, TemplateValue<'_>)],
+    ) -> Result<()> {
+        self.push_map(None);
+        template.render(&mut self.code, values)
+    }
+
     /// Copies the Synthetic/Original code of an already constructed Code into
     /// this instance.
     pub fn push_code(&mut self, prebuilt: &Code) {
@@ -0,0 +1,360 @@
+//! The config of a project, which is loaded from a `turbopack.config.json`,
+//! `turbopack.config.js` or `turbopack.config.ts` in the project.
+//!
+//! The config is reloaded when the file changes. Every section, and every
+//! field of the resolve section, is a cell of its own which is only updated
+//! when its value changed, so e.g. a change to one alias doesn't invalidate
+//! the options derived from the other fields.
+
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{
+    primitives::{JsonValueVc, OptionStringVc, StringVc, StringsVc},
+    trace::TraceRawVcs,
+};
+use turbo_tasks_fs::{json::UnparseableJson, FileContent, FileSystemPathVc};
+
+use crate::{
+    issue::{
+        Issue, IssueSeverity, IssueSeverityVc, IssueSource, IssueSourceVc, IssueVc,
+        OptionIssueSourceVc,
+    },
+    resolve::{find_context_file, FindContextFileResultVc},
+    source_asset::SourceAssetVc,
+    source_pos::SourcePos,
+};
+
+#[turbo_tasks::value(eq = "manual")]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TurbopackConfig {
+    #[serde(default)]
+    pub resolve: ResolveConfig,
+    #[serde(default)]
+    pub transform: TransformConfig,
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+}
+
+#[turbo_tasks::value(eq = "manual")]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ResolveConfig {
+    /// Requests which are replaced by other requests, e.g. `"lodash":
+    /// "lodash-es"` or `"@/*": "./src/*"`. Relative requests are relative to
+    /// the config file.
+    #[serde(default)]
+    pub alias: IndexMap<String, String>,
+    /// Additional export conditions, e.g. `react-native`.
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    /// Suffixes of platform specific files, e.g. `.ios`. When empty, the
+    /// suffixes of the environment are used.
+    #[serde(default)]
+    pub platform_suffixes: Vec<FileSuffix>,
+    /// Logical names of assets with the requests of their files, e.g.
+    /// `"og-image": "./public/og.png"`. Application code can import their URLs
+    /// from the `__turbopack_manifest__` module.
+    #[serde(default)]
+    pub assets: IndexMap<String, String>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ConfigAssets(IndexMap<String, String>);
+
+/// Overrides of the transforms which are enabled by default.
+#[turbo_tasks::value(eq = "manual")]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TransformConfig {
+    pub jsx: Option<bool>,
+    pub typescript: Option<bool>,
+    pub styled_components: Option<bool>,
+    pub emotion: Option<bool>,
+}
+
+#[turbo_tasks::value(eq = "manual")]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ChunkingConfig {
+    /// The subpath under which chunks are served, e.g. `/app/`.
+    pub base_path: Option<String>,
+    /// Compiles dynamic imports only once they're requested.
+    pub lazy_compilation: Option<bool>,
+    /// Avoids inline scripts and `eval`, so chunks work with a strict Content
+    /// Security Policy.
+    pub strict_csp: Option<bool>,
+}
+
+/// A suffix of file names which starts with a `.`, e.g. `.ios`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(try_from = "String", into = "String")]
+pub struct FileSuffix(String);
+
+impl FileSuffix {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for FileSuffix {
+    type Error = anyhow::Error;
+
+    fn try_from(suffix: String) -> Result<Self> {
+        if !suffix.starts_with('.') || suffix.len() == 1 {
+            bail!("the suffix `{suffix}` needs to start with a `.`, e.g. `.ios`");
+        }
+        Ok(FileSuffix(suffix))
+    }
+}
+
+impl From<FileSuffix> for String {
+    fn from(suffix: FileSuffix) -> Self {
+        suffix.0
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl TurbopackConfigVc {
+    #[turbo_tasks::function]
+    pub async fn resolve(self) -> Result<ResolveConfigVc> {
+        Ok(self.await?.resolve.clone().cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn transform(self) -> Result<TransformConfigVc> {
+        Ok(self.await?.transform.clone().cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn chunking(self) -> Result<ChunkingConfigVc> {
+        Ok(self.await?.chunking.clone().cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ResolveConfigVc {
+    /// The aliased requests, without their replacements.
+    #[turbo_tasks::function]
+    pub async fn alias_keys(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(self.await?.alias.keys().cloned().collect()))
+    }
+
+    /// The replacement of an aliased request.
+    #[turbo_tasks::function]
+    pub async fn alias(self, key: &str) -> Result<OptionStringVc> {
+        Ok(OptionStringVc::cell(self.await?.alias.get(key).cloned()))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn assets(self) -> Result<ConfigAssetsVc> {
+        Ok(ConfigAssetsVc::cell(self.await?.assets.clone()))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn conditions(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(self.await?.conditions.clone()))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn platform_suffixes(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(
+            self.await?
+                .platform_suffixes
+                .iter()
+                .map(|suffix| suffix.as_str().to_string())
+                .collect(),
+        ))
+    }
+}
+
+#[turbo_tasks::function]
+fn config_file_names() -> StringsVc {
+    StringsVc::cell(
+        [
+            "turbopack.config.json",
+            "turbopack.config.js",
+            "turbopack.config.ts",
+        ]
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect(),
+    )
+}
+
+/// Finds the config file of the project in `context` or one of its parents.
+#[turbo_tasks::function]
+pub fn find_config_file(context: FileSystemPathVc) -> FindContextFileResultVc {
+    find_context_file(context, config_file_names())
+}
+
+/// The last valid config of a config file, which is used while the file is
+/// invalid, e.g. while it's edited, instead of invalidating everything with
+/// the default config.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+struct LastValidConfig {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    config: Mutex<Option<TurbopackConfig>>,
+}
+
+impl LastValidConfig {
+    fn store(&self, config: TurbopackConfig) -> TurbopackConfigVc {
+        *self.config.lock().unwrap() = Some(config.clone());
+        config.cell()
+    }
+
+    fn get(&self) -> TurbopackConfigVc {
+        self.config
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_default()
+            .cell()
+    }
+}
+
+/// Doesn't read anything, so it's only executed once per config file.
+#[turbo_tasks::function]
+fn last_valid_config(_path: FileSystemPathVc) -> LastValidConfigVc {
+    LastValidConfig {
+        config: Mutex::new(None),
+    }
+    .cell()
+}
+
+/// Loads a `turbopack.config.json`. When it's invalid, an issue is reported at
+/// the location of the error in the file and the last valid config, or the
+/// default config, is used.
+#[turbo_tasks::function]
+pub async fn load_json_config(path: FileSystemPathVc) -> Result<TurbopackConfigVc> {
+    let FileContent::Content(file) = &*path.read().await? else {
+        return Ok(TurbopackConfig::default().cell());
+    };
+    let text = file.content().to_str()?;
+    let de = &mut serde_json::Deserializer::from_str(&text);
+    match serde_path_to_error::deserialize(de) {
+        Ok(config) => Ok(last_valid_config(path).await?.store(config)),
+        Err(err) => {
+            let err = UnparseableJson::from_serde_path_to_error(err);
+            let source = err.start_location.map(|(line, column)| {
+                let pos = SourcePos { line, column };
+                IssueSource {
+                    asset: SourceAssetVc::new(path).into(),
+                    start: pos,
+                    end: pos,
+                }
+                .cell()
+            });
+            ConfigIssue {
+                path,
+                message: err.to_string(),
+                source,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            Ok(last_valid_config(path).await?.get())
+        }
+    }
+}
+
+/// Validates the value which a `turbopack.config.js` or `turbopack.config.ts`
+/// at `path` evaluated to. When it's invalid, an issue names the invalid field
+/// and the last valid config, or the default config, is used.
+#[turbo_tasks::function]
+pub async fn parse_config_value(
+    path: FileSystemPathVc,
+    value: JsonValueVc,
+) -> Result<TurbopackConfigVc> {
+    let value = value.await?;
+    match serde_path_to_error::deserialize(&*value) {
+        Ok(config) => Ok(last_valid_config(path).await?.store(config)),
+        Err(err) => {
+            ConfigIssue {
+                path,
+                message: format!("{}\n  at {}", err.inner(), err.path()),
+                source: None,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            Ok(last_valid_config(path).await?.get())
+        }
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub struct ConfigIssue {
+    pub path: FileSystemPathVc,
+    pub message: String,
+    pub source: Option<IssueSourceVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ConfigIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Invalid Turbopack config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.message.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections() {
+        let config: TurbopackConfig = serde_json::from_str(
+            r#"{
+                "resolve": { "alias": { "@/*": "./src/*" }, "platformSuffixes": [".ios"] },
+                "chunking": { "strictCsp": true }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.resolve.alias["@/*"], "./src/*");
+        assert_eq!(config.resolve.platform_suffixes[0].as_str(), ".ios");
+        assert_eq!(config.chunking.strict_csp, Some(true));
+        assert_eq!(config.transform, TransformConfig::default());
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_invalid_suffixes() {
+        let err = serde_json::from_str::<TurbopackConfig>(r#"{ "resolv": {} }"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `resolv`"));
+
+        let err = serde_json::from_str::<TurbopackConfig>(
+            "{\n  \"resolve\": { \"platformSuffixes\": [\"ios\"] }\n}",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("needs to start with a `.`"));
+        assert_eq!(err.line(), 2);
+    }
+}
@@ -11,6 +11,7 @@ use turbo_tasks::{
     Value,
 };
 use turbo_tasks_env::{ProcessEnv, ProcessEnvVc};
+use turbo_tasks_fs::{FileContent, FileJsonContent, FileSystemPathVc};
 
 use crate::target::CompileTargetVc;
 
@@ -72,6 +73,8 @@ pub enum ChunkLoading {
     NodeJs,
     /// <script> and <link> tags in the browser
     Dom,
+    /// importScripts in a web worker or a service worker
+    WebWorker,
 }
 
 #[turbo_tasks::value]
@@ -191,6 +194,25 @@ impl EnvironmentVc {
         })
     }
 
+    /// The suffixes of files which are specific to the environment, e.g.
+    /// `.browser` for `file.browser.js`, in order of precedence.
+    #[turbo_tasks::function]
+    pub async fn platform_suffixes(self) -> Result<StringsVc> {
+        let env = self.await?;
+        Ok(match env.execution {
+            // Node.js doesn't resolve platform specific files, and traced files
+            // need to match what Node.js loads at runtime.
+            ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
+                StringsVc::empty()
+            }
+            ExecutionEnvironment::Browser(_) => {
+                StringsVc::cell(vec![".browser".to_string(), ".web".to_string()])
+            }
+            ExecutionEnvironment::EdgeWorker(_) => StringsVc::cell(vec![".worker".to_string()]),
+            ExecutionEnvironment::Custom(_) => todo!(),
+        })
+    }
+
     #[turbo_tasks::function]
     pub async fn resolve_node_modules(self) -> Result<BoolVc> {
         let env = self.await?;
@@ -252,10 +274,67 @@ impl EnvironmentVc {
                 ChunkLoading::NodeJs.cell()
             }
             ExecutionEnvironment::EdgeWorker(_) => ChunkLoading::None.cell(),
-            ExecutionEnvironment::Browser(_) => ChunkLoading::Dom.cell(),
+            ExecutionEnvironment::Browser(browser_env) => {
+                let browser_env = browser_env.await?;
+                if (browser_env.web_worker || browser_env.service_worker) && !browser_env.dom {
+                    ChunkLoading::WebWorker.cell()
+                } else {
+                    ChunkLoading::Dom.cell()
+                }
+            }
             _ => ChunkLoading::None.cell(),
         })
     }
+
+    /// Returns the environment a web worker spawned from this environment
+    /// runs in. Non-browser environments are returned unchanged.
+    #[turbo_tasks::function]
+    pub async fn web_worker(self) -> Result<EnvironmentVc> {
+        let env = self.await?;
+        Ok(match env.execution {
+            ExecutionEnvironment::Browser(browser_env) => {
+                let browser_env = browser_env.await?;
+                EnvironmentVc::new(
+                    Value::new(ExecutionEnvironment::Browser(
+                        BrowserEnvironment {
+                            dom: false,
+                            web_worker: true,
+                            service_worker: false,
+                            browserslist_query: browser_env.browserslist_query.clone(),
+                        }
+                        .into(),
+                    )),
+                    Value::new(env.intention),
+                )
+            }
+            _ => self,
+        })
+    }
+
+    /// Returns the environment a service worker registered from this
+    /// environment runs in. Non-browser environments are returned unchanged.
+    #[turbo_tasks::function]
+    pub async fn service_worker(self) -> Result<EnvironmentVc> {
+        let env = self.await?;
+        Ok(match env.execution {
+            ExecutionEnvironment::Browser(browser_env) => {
+                let browser_env = browser_env.await?;
+                EnvironmentVc::new(
+                    Value::new(ExecutionEnvironment::Browser(
+                        BrowserEnvironment {
+                            dom: false,
+                            web_worker: false,
+                            service_worker: true,
+                            browserslist_query: browser_env.browserslist_query.clone(),
+                        }
+                        .into(),
+                    )),
+                    Value::new(env.intention),
+                )
+            }
+            _ => self,
+        })
+    }
 }
 
 pub enum NodeEnvironmentType {
@@ -331,6 +410,69 @@ pub struct BrowserEnvironment {
     pub browserslist_query: String,
 }
 
+/// Finds the browserslist config of a project, which is either a
+/// `.browserslistrc` file or the `browserslist` field of a `package.json`,
+/// in `project_path` or one of its parents. Returns `default_query` when there
+/// is no config.
+#[turbo_tasks::function]
+pub async fn browserslist_query(
+    project_path: FileSystemPathVc,
+    default_query: &str,
+) -> Result<StringVc> {
+    let mut current = project_path.resolve().await?;
+    loop {
+        if let FileContent::Content(file) = &*current.join(".browserslistrc").read().await? {
+            let queries = parse_browserslistrc(&file.content().to_str()?);
+            if !queries.is_empty() {
+                return Ok(StringVc::cell(queries.join(", ")));
+            }
+        }
+        if let FileJsonContent::Content(package_json) =
+            &*current.join("package.json").read_json().await?
+        {
+            let config = &package_json["browserslist"];
+            // Environment specific configs use the production queries
+            let config = config.get("production").unwrap_or(config);
+            let queries = match config {
+                serde_json::Value::String(query) => vec![query.as_str()],
+                serde_json::Value::Array(queries) => {
+                    queries.iter().filter_map(|q| q.as_str()).collect()
+                }
+                _ => Vec::new(),
+            };
+            if !queries.is_empty() {
+                return Ok(StringVc::cell(queries.join(", ")));
+            }
+        }
+        let parent = current.parent().resolve().await?;
+        if parent == current {
+            return Ok(StringVc::cell(default_query.to_string()));
+        }
+        current = parent;
+    }
+}
+
+/// Returns the queries of a `.browserslistrc` file. Queries in environment
+/// sections are only used for the `production` environment.
+fn parse_browserslistrc(content: &str) -> Vec<&str> {
+    let mut queries = Vec::new();
+    let mut skip_section = false;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            skip_section = section.split_whitespace().all(|env| env != "production");
+            continue;
+        }
+        if !skip_section {
+            queries.push(line);
+        }
+    }
+    queries
+}
+
 #[turbo_tasks::value(shared)]
 pub struct EdgeWorkerEnvironment {
     pub server_addr: ServerAddrVc,
@@ -359,3 +501,38 @@ pub async fn get_current_nodejs_version(env: ProcessEnvVc) -> Result<StringVc> {
             .to_owned(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_browserslistrc;
+
+    #[test]
+    fn parses_browserslistrc() {
+        let content = r#"
+# Browsers that we support
+
+> 0.5%
+last 2 versions # the latest ones
+not dead
+"#;
+        assert_eq!(
+            parse_browserslistrc(content),
+            vec!["> 0.5%", "last 2 versions", "not dead"]
+        );
+    }
+
+    #[test]
+    fn uses_production_section() {
+        let content = r#"
+defaults
+
+[development]
+last 1 chrome version
+
+[production staging]
+> 1%
+"#;
+        assert_eq!(parse_browserslistrc(content), vec!["defaults", "> 1%"]);
+        assert!(parse_browserslistrc("[development]\nlast 1 chrome version").is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+use turbo_tasks_fs::{glob::GlobVc, DirectoryEntry, FileSystemPathVc};
+
+/// A file matched by [entries_from_glob].
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct GlobEntry {
+    pub path: FileSystemPathVc,
+    /// The path of the file relative to the static prefix of the pattern,
+    /// without its extension, e.g. `blog/[slug]` for
+    /// `src/pages/blog/[slug].tsx` matched by `src/pages/**/*.tsx`.
+    pub name: String,
+}
+
+/// The files matched by [entries_from_glob], in [compare_entry_names] order.
+#[turbo_tasks::value(transparent)]
+pub struct GlobEntries(Vec<GlobEntryVc>);
+
+/// Finds the entries of a multi-page application, e.g. every page matched by
+/// `src/pages/**/*.tsx`, relative to `directory`.
+///
+/// The directories read for the pattern are tracked like any other file
+/// system read, so when the file system is watched, the entries are
+/// recomputed as files are added or removed, and an embedder which creates
+/// its content sources from the entries picks up new pages without a
+/// restart.
+#[turbo_tasks::function]
+pub async fn entries_from_glob(
+    directory: FileSystemPathVc,
+    pattern: &str,
+) -> Result<GlobEntriesVc> {
+    let (prefix, pattern) = split_static_prefix(pattern);
+    let base = if prefix.is_empty() {
+        directory
+    } else {
+        directory.join(prefix)
+    };
+    let mut entries = base
+        .read_glob(GlobVc::new(pattern), false)
+        .all_matches()
+        .await?
+        .into_iter()
+        .filter_map(|(relative_path, entry)| match entry {
+            DirectoryEntry::File(path) | DirectoryEntry::Symlink(path) => {
+                Some((strip_extension(&relative_path).to_string(), path))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| compare_entry_names(a, b));
+    Ok(GlobEntriesVc::cell(
+        entries
+            .into_iter()
+            .map(|(name, path)| GlobEntry { path, name }.cell())
+            .collect(),
+    ))
+}
+
+fn strip_extension(path: &str) -> &str {
+    let file_name_start = path.rfind('/').map_or(0, |slash| slash + 1);
+    match path[file_name_start..].rfind('.') {
+        Some(0) | None => path,
+        Some(dot) => &path[..file_name_start + dot],
+    }
+}
+
+/// Splits the directories without glob characters off the pattern, e.g.
+/// `src/pages/**/*.tsx` into `src/pages` and `**/*.tsx`.
+fn split_static_prefix(pattern: &str) -> (&str, &str) {
+    let pattern = pattern.trim_start_matches("./");
+    let dynamic = pattern.find(['*', '?', '[', '{']).unwrap_or(pattern.len());
+    match pattern[..dynamic].rfind('/') {
+        Some(slash) => (&pattern[..slash], &pattern[slash + 1..]),
+        None => ("", pattern),
+    }
+}
+
+/// How specific a segment of an entry name is, most specific first.
+fn segment_rank(segment: &str) -> u8 {
+    if segment.starts_with("[[...") {
+        3
+    } else if segment.starts_with("[...") {
+        2
+    } else if segment.starts_with('[') {
+        1
+    } else {
+        0
+    }
+}
+
+/// Orders entry names by specificity, segment by segment: static segments
+/// before dynamic segments like `[slug]`, before catch-all segments like
+/// `[...slug]`, before optional catch-all segments like `[[...slug]]`.
+/// Entries of the same specificity are ordered by name, so the order doesn't
+/// depend on the order the files were found in.
+pub fn compare_entry_names(a: &str, b: &str) -> Ordering {
+    let ranks = |name: &str| name.split('/').map(segment_rank).collect::<Vec<_>>();
+    ranks(a).cmp(&ranks(b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_entry_names, split_static_prefix, strip_extension};
+
+    #[test]
+    fn splits_static_prefix() {
+        assert_eq!(
+            split_static_prefix("src/pages/**/*.tsx"),
+            ("src/pages", "**/*.tsx")
+        );
+        assert_eq!(split_static_prefix("./pages/*.html"), ("pages", "*.html"));
+        assert_eq!(split_static_prefix("*.html"), ("", "*.html"));
+        assert_eq!(
+            split_static_prefix("src/{a,b}/index.js"),
+            ("src", "{a,b}/index.js")
+        );
+    }
+
+    #[test]
+    fn strips_extension() {
+        assert_eq!(strip_extension("blog/[slug].tsx"), "blog/[slug]");
+        assert_eq!(strip_extension("v1.2/index.page.js"), "v1.2/index.page");
+        assert_eq!(strip_extension("v1.2/index"), "v1.2/index");
+        assert_eq!(strip_extension(".hidden"), ".hidden");
+    }
+
+    #[test]
+    fn orders_by_specificity() {
+        let mut names = vec![
+            "[[...rest]]",
+            "blog/[...slug]",
+            "blog/[id]",
+            "[page]",
+            "blog/index",
+            "about",
+            "blog/archive",
+            "index",
+        ];
+        names.sort_by(|a, b| compare_entry_names(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "about",
+                "index",
+                "blog/archive",
+                "blog/index",
+                "blog/[id]",
+                "blog/[...slug]",
+                "[page]",
+                "[[...rest]]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs};
+
+use super::{Introspectable, IntrospectableVc};
+
+/// An introspectable on the way from a root to a target, and the kind of edge
+/// it was reached with, e.g. `reference` or `entry module`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct ReasonStep {
+    pub ty: String,
+    pub title: String,
+    /// The name of the edge from the previous step. Empty for the root.
+    pub via: String,
+}
+
+/// Answers why a target, e.g. a module, is reachable from a root, e.g. a
+/// chunk. This is similar to the `reasons` of webpack's stats.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct IntrospectableReasons {
+    /// The shortest chain from the root to the target, including both. Empty
+    /// when the target is not reachable.
+    pub chain: Vec<ReasonStep>,
+    /// All introspectables that have the target as a direct child, with the
+    /// edge that leads to the target.
+    pub issuers: Vec<ReasonStep>,
+}
+
+async fn step(introspectable: IntrospectableVc, via: Option<StringVc>) -> Result<ReasonStep> {
+    Ok(ReasonStep {
+        ty: introspectable.ty().await?.clone_value(),
+        title: introspectable.title().await?.clone_value(),
+        via: match via {
+            Some(via) => via.await?.clone_value(),
+            None => String::new(),
+        },
+    })
+}
+
+/// Walks the children of `root` breadth-first to find the introspectables with
+/// the title `target`, e.g. `[project]/src/index.js`.
+#[turbo_tasks::function]
+pub async fn introspectable_reasons(
+    root: IntrospectableVc,
+    target: &str,
+) -> Result<IntrospectableReasonsVc> {
+    let root = root.resolve().await?;
+    let mut parents: HashMap<IntrospectableVc, (IntrospectableVc, StringVc)> = HashMap::new();
+    let mut visited = HashSet::from([root]);
+    let mut queue = VecDeque::from([root]);
+    let mut found = None;
+    let mut issuers = Vec::new();
+    let mut seen_issuers = HashSet::new();
+    while let Some(introspectable) = queue.pop_front() {
+        for &(via, child) in introspectable.children().await?.iter() {
+            let child = child.resolve().await?;
+            if *child.title().await? == target {
+                if found.is_none() {
+                    found = Some((child, introspectable, via));
+                }
+                if seen_issuers.insert(introspectable) {
+                    issuers.push(step(introspectable, Some(via)).await?);
+                }
+            }
+            if visited.insert(child) {
+                parents.insert(child, (introspectable, via));
+                queue.push_back(child);
+            }
+        }
+    }
+
+    let mut chain = Vec::new();
+    if let Some((target, issuer, via)) = found {
+        chain.push(step(target, Some(via)).await?);
+        let mut current = issuer;
+        while let Some(&(parent, via)) = parents.get(&current) {
+            chain.push(step(current, Some(via)).await?);
+            current = parent;
+        }
+        chain.push(step(current, None).await?);
+        chain.reverse();
+    }
+
+    Ok(IntrospectableReasons { chain, issuers }.cell())
+}
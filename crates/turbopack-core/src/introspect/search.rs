@@ -0,0 +1,119 @@
+use std::collections::{HashSet, VecDeque};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{trace::TraceRawVcs, Value};
+
+use super::{Introspectable, IntrospectableVc};
+
+/// The most matches [search_introspectable] returns.
+pub const MAX_SEARCH_MATCHES: usize = 100;
+
+/// A step of an address: the name of the edge from the parent, e.g. `module`,
+/// and the title of the child, e.g. `[project]/src/index.js`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs)]
+pub struct AddressStep {
+    pub name: String,
+    pub title: String,
+}
+
+/// The steps from a root to an introspectable. Unlike the introspectable
+/// itself, an address stays valid across restarts and changes of unrelated
+/// parts of the tree, so it can be bookmarked or shared.
+pub type IntrospectableAddress = Vec<AddressStep>;
+
+/// Follows an address from `root`. At every step, the first child with the
+/// name and title of the step is taken. `None` when there is no such child.
+pub async fn resolve_address(
+    root: IntrospectableVc,
+    address: &[AddressStep],
+) -> Result<Option<IntrospectableVc>> {
+    let mut current = root;
+    'steps: for step in address {
+        for &(name, child) in current.children().await?.iter() {
+            if *name.await? == step.name && *child.title().await? == step.title {
+                current = child;
+                continue 'steps;
+            }
+        }
+        return Ok(None);
+    }
+    Ok(Some(current))
+}
+
+/// What [search_introspectable] looks for. Every field that is set needs to
+/// match.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Default, PartialOrd, Ord, Hash)]
+pub struct IntrospectableQuery {
+    /// The type, e.g. `node render content source`.
+    pub ty: Option<String>,
+    /// A substring of the title, e.g. `/api/`.
+    pub title: Option<String>,
+}
+
+impl IntrospectableQuery {
+    pub async fn matches(&self, introspectable: IntrospectableVc) -> Result<bool> {
+        if let Some(ty) = &self.ty {
+            if *introspectable.ty().await? != *ty {
+                return Ok(false);
+            }
+        }
+        if let Some(title) = &self.title {
+            if !introspectable.title().await?.contains(title.as_str()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct IntrospectableMatch {
+    pub ty: String,
+    pub title: String,
+    pub address: IntrospectableAddress,
+}
+
+/// The matches of a search, closest to the root first.
+#[turbo_tasks::value(transparent)]
+pub struct IntrospectableMatches(Vec<IntrospectableMatch>);
+
+/// Walks the children of `root` breadth-first to find the introspectables
+/// which match `query`, with the shortest address of each. At most
+/// [MAX_SEARCH_MATCHES] are returned.
+#[turbo_tasks::function]
+pub async fn search_introspectable(
+    root: IntrospectableVc,
+    query: Value<IntrospectableQuery>,
+) -> Result<IntrospectableMatchesVc> {
+    let root = root.resolve().await?;
+    let mut visited = HashSet::from([root]);
+    let mut queue = VecDeque::from([(root, Vec::new())]);
+    let mut matches = Vec::new();
+    while let Some((introspectable, address)) = queue.pop_front() {
+        if query.matches(introspectable).await? {
+            matches.push(IntrospectableMatch {
+                ty: introspectable.ty().await?.clone_value(),
+                title: introspectable.title().await?.clone_value(),
+                address: address.clone(),
+            });
+            if matches.len() == MAX_SEARCH_MATCHES {
+                break;
+            }
+        }
+        for &(name, child) in introspectable.children().await?.iter() {
+            let child = child.resolve().await?;
+            if visited.insert(child) {
+                let mut address = address.clone();
+                address.push(AddressStep {
+                    name: name.await?.clone_value(),
+                    title: child.title().await?.clone_value(),
+                });
+                queue.push_back((child, address));
+            }
+        }
+    }
+    Ok(IntrospectableMatchesVc::cell(matches))
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::trace::TraceRawVcs;
+use turbo_tasks_fs::{
+    source_context::{get_source_context, SourceContextLine},
+    FileLinesContent,
+};
+
+use super::{IssueSource, IssueSourceVc};
+use crate::source_pos::SourcePos;
+
+/// A line of a [CodeFrame]. `before`, `inside` and `after` are the parts of
+/// the line before, inside and after the highlighted range. Lines outside of
+/// the range only have `before`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct CodeFrameLine {
+    /// 1-indexed line number.
+    pub line: usize,
+    pub before: String,
+    pub inside: String,
+    pub after: String,
+    /// Whether the highlighted range starts on this line.
+    pub is_start: bool,
+    /// Whether the highlighted range ends on this line.
+    pub is_end: bool,
+    /// Whether the line is part of the highlighted range.
+    pub highlighted: bool,
+}
+
+/// The lines around the range of an [IssueSource], for rendering a code frame
+/// in the terminal or an overlay. Very long lines are shortened.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct CodeFrame {
+    pub start: SourcePos,
+    pub end: SourcePos,
+    pub lines: Vec<CodeFrameLine>,
+}
+
+impl CodeFrame {
+    /// Computes the code frame of a range in the lines of a file. Returns
+    /// `None` for files that aren't text.
+    pub fn from_lines(lines: &FileLinesContent, start: SourcePos, end: SourcePos) -> Option<Self> {
+        let FileLinesContent::Lines(lines) = lines else {
+            return None;
+        };
+        let context = get_source_context(
+            lines.iter().map(|l| l.content.as_str()),
+            start.line,
+            start.column,
+            end.line,
+            end.column,
+        );
+        let lines = context
+            .0
+            .into_iter()
+            .map(|line| {
+                let (line, before, inside, after, is_start, is_end) = match line {
+                    SourceContextLine::Context { line, outside } => {
+                        return CodeFrameLine {
+                            line,
+                            before: outside.into_owned(),
+                            inside: String::new(),
+                            after: String::new(),
+                            is_start: false,
+                            is_end: false,
+                            highlighted: false,
+                        };
+                    }
+                    SourceContextLine::Start {
+                        line,
+                        before,
+                        inside,
+                    } => (line, before, inside, Default::default(), true, false),
+                    SourceContextLine::End {
+                        line,
+                        inside,
+                        after,
+                    } => (line, Default::default(), inside, after, false, true),
+                    SourceContextLine::StartAndEnd {
+                        line,
+                        before,
+                        inside,
+                        after,
+                    } => (line, before, inside, after, true, true),
+                    SourceContextLine::Inside { line, inside } => (
+                        line,
+                        Default::default(),
+                        inside,
+                        Default::default(),
+                        false,
+                        false,
+                    ),
+                };
+                CodeFrameLine {
+                    line,
+                    before: before.into_owned(),
+                    inside: inside.into_owned(),
+                    after: after.into_owned(),
+                    is_start,
+                    is_end,
+                    highlighted: true,
+                }
+            })
+            .collect();
+        Some(CodeFrame { start, end, lines })
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionCodeFrame(Option<CodeFrameVc>);
+
+#[turbo_tasks::value_impl]
+impl IssueSourceVc {
+    /// The code frame of the source. It's read through the asset, so it's
+    /// recomputed when the file changes.
+    #[turbo_tasks::function]
+    pub async fn code_frame(self) -> Result<OptionCodeFrameVc> {
+        let IssueSource { asset, start, end } = *self.await?;
+        let lines = asset.content().lines().await?;
+        Ok(OptionCodeFrameVc::cell(
+            CodeFrame::from_lines(&lines, start, end).map(|frame| frame.cell()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use turbo_tasks_fs::{FileLine, FileLinesContent};
+
+    use super::{CodeFrame, CodeFrameLine};
+    use crate::source_pos::SourcePos;
+
+    fn lines(code: &str) -> FileLinesContent {
+        let mut bytes_offset = 0;
+        FileLinesContent::Lines(
+            code.split('\n')
+                .map(|l| {
+                    let line = FileLine {
+                        content: l.to_string(),
+                        bytes_offset,
+                    };
+                    bytes_offset += l.len() + 1;
+                    line
+                })
+                .collect(),
+        )
+    }
+
+    fn frame_line(
+        line: usize,
+        [before, inside, after]: [&str; 3],
+        is_start: bool,
+        is_end: bool,
+        highlighted: bool,
+    ) -> CodeFrameLine {
+        CodeFrameLine {
+            line,
+            before: before.to_string(),
+            inside: inside.to_string(),
+            after: after.to_string(),
+            is_start,
+            is_end,
+            highlighted,
+        }
+    }
+
+    #[test]
+    fn highlights_range_in_a_line() {
+        let frame = CodeFrame::from_lines(
+            &lines("a;\nconst x = 1;\nb;"),
+            SourcePos { line: 1, column: 6 },
+            SourcePos { line: 1, column: 7 },
+        )
+        .unwrap();
+        assert_eq!(
+            frame.lines,
+            [
+                frame_line(1, ["a;", "", ""], false, false, false),
+                frame_line(2, ["const ", "x", " = 1;"], true, true, true),
+                frame_line(3, ["b;", "", ""], false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlights_range_across_lines() {
+        let frame = CodeFrame::from_lines(
+            &lines("call(\n  a,\n  b\n);"),
+            SourcePos { line: 0, column: 4 },
+            SourcePos { line: 3, column: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            frame.lines,
+            [
+                frame_line(1, ["call", "(", ""], true, false, true),
+                frame_line(2, ["", "  a,", ""], false, false, true),
+                frame_line(3, ["", "  b", ""], false, false, true),
+                frame_line(4, ["", ")", ";"], false, true, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn has_no_frame_without_lines() {
+        let start = SourcePos { line: 0, column: 0 };
+        assert!(CodeFrame::from_lines(&FileLinesContent::NotFound, start, start).is_none());
+        assert!(CodeFrame::from_lines(&FileLinesContent::Unparseable, start, start).is_none());
+    }
+}
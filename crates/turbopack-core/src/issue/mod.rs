@@ -1,5 +1,7 @@
 pub mod analyze;
+pub mod code_frame;
 pub mod code_gen;
+pub mod package_cache;
 pub mod package_json;
 pub mod resolve;
 pub mod unsupported_module;
@@ -25,6 +27,7 @@ use turbo_tasks_fs::{
 };
 use turbo_tasks_hash::{DeterministicHash, Xxh3Hash64Hasher};
 
+use self::code_frame::CodeFrameReadRef;
 use crate::{
     asset::{Asset, AssetContent, AssetVc},
     source_pos::SourcePos,
@@ -527,6 +530,8 @@ pub struct PlainIssueSource {
     pub asset: PlainAssetReadRef,
     pub start: SourcePos,
     pub end: SourcePos,
+    #[turbo_tasks(debug_ignore)]
+    pub code_frame: Option<CodeFrameReadRef>,
 }
 
 #[turbo_tasks::value_impl]
@@ -534,10 +539,15 @@ impl IssueSourceVc {
     #[turbo_tasks::function]
     pub async fn into_plain(self) -> Result<PlainIssueSourceVc> {
         let this = self.await?;
+        let code_frame = match *self.code_frame().await? {
+            Some(code_frame) => Some(code_frame.await?),
+            None => None,
+        };
         Ok(PlainIssueSource {
             asset: PlainAssetVc::from_asset(this.asset).await?,
             start: this.start,
             end: this.end,
+            code_frame,
         }
         .cell())
     }
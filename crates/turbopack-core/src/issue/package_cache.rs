@@ -0,0 +1,42 @@
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+#[turbo_tasks::value(shared)]
+pub struct PackageCacheIssue {
+    pub path: FileSystemPathVc,
+    pub error_message: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for PackageCacheIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Failed to write the package cache".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Packages will be scanned again on the next start.\n\n{}",
+            self.error_message
+        ))
+    }
+}
@@ -4,8 +4,8 @@ use anyhow::Result;
 use turbo_tasks::{primitives::StringVc, ValueToString};
 use turbo_tasks_fs::FileSystemPathVc;
 
-use super::{Issue, IssueVc};
-use crate::resolve::{options::ResolveOptionsVc, parse::RequestVc};
+use super::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+use crate::resolve::{node::is_node_builtin, options::ResolveOptionsVc, parse::RequestVc};
 
 #[turbo_tasks::value(shared)]
 pub struct ResolvingIssue {
@@ -54,6 +54,16 @@ impl Issue for ResolvingIssue {
         } else {
             writeln!(detail, "It was not possible to find the requested file.")?;
         }
+        if let Some(request) = self.request.await?.request() {
+            if is_node_builtin(&request) {
+                writeln!(
+                    detail,
+                    "{request} is a Node.js builtin module, which is not available in this \
+                     environment. Install a package with the same name, configure a polyfill for \
+                     it or replace it with an empty module."
+                )?;
+            }
+        }
         writeln!(
             detail,
             "Parsed request as written in source code: {request}",
@@ -80,3 +90,100 @@ impl Issue for ResolvingIssue {
     // TODO add sub_issue for a description of resolve_options
     // TODO add source link
 }
+
+/// An import of a module that isn't allowed in the module graph, e.g.
+/// `server-only` in client code. The chain of imports that led to the import
+/// is the processing path of the issue.
+#[turbo_tasks::value(shared)]
+pub struct ForbiddenImportIssue {
+    pub request: RequestVc,
+    pub context: FileSystemPathVc,
+    pub reason: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ForbiddenImportIssue {
+    #[turbo_tasks::function]
+    async fn title(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{} can't be imported here",
+            self.request.to_string().await?
+        )))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.reason.clone())
+    }
+}
+
+/// A package in `node_modules` is imported, but it's not declared as
+/// dependency or the lockfile doesn't install it.
+#[turbo_tasks::value(shared)]
+pub struct UndeclaredDependencyIssue {
+    pub package: String,
+    pub context: FileSystemPathVc,
+    pub package_json: FileSystemPathVc,
+    pub declared: bool,
+    pub in_lockfile: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UndeclaredDependencyIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(if self.declared {
+            format!(
+                "{} is imported, but not installed by the lockfile",
+                self.package
+            )
+        } else {
+            format!(
+                "{} is imported, but not declared as dependency",
+                self.package
+            )
+        })
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<StringVc> {
+        let mut description = String::new();
+        if !self.declared {
+            write!(
+                description,
+                "It's missing in the dependencies of {}. ",
+                self.package_json.to_string().await?
+            )?;
+        }
+        description.push_str(
+            "It's only installed as dependency of another package or by hand, and can go missing \
+             when the packages are installed again.",
+        );
+        Ok(StringVc::cell(description))
+    }
+}
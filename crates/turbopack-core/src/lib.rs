@@ -8,8 +8,10 @@
 pub mod asset;
 pub mod chunk;
 pub mod code_builder;
+pub mod config;
 pub mod context;
 pub mod environment;
+pub mod glob_entries;
 pub mod introspect;
 pub mod issue;
 pub mod reference;
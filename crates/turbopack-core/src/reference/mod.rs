@@ -1,6 +1,7 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 
 use anyhow::Result;
+use indexmap::IndexSet;
 use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
 
 use crate::{
@@ -125,7 +126,9 @@ pub async fn all_assets(asset: AssetVc) -> Result<AssetsVc> {
     // TODO need to track import path here
     let mut queue = VecDeque::with_capacity(32);
     queue.push_back((asset, all_referenced_assets(asset)));
-    let mut assets = HashSet::new();
+    // The assets are kept in the order they are found, so that the result
+    // doesn't depend on hashing.
+    let mut assets = IndexSet::new();
     assets.insert(asset);
     while let Some((parent, references)) = queue.pop_front() {
         IssueVc::attach_context(
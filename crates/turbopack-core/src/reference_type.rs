@@ -13,10 +13,21 @@ pub enum CommonJsReferenceSubType {
     Undefined,
 }
 
+/// The module type requested by the `type` import attribute, e. g.
+/// `import data from "./data.json" with { type: "json" }`.
 #[turbo_tasks::value(serialization = "auto_for_input")]
-#[derive(Debug, Clone, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash)]
+pub enum ImportWithType {
+    Json,
+    Css,
+}
+
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, PartialOrd, Ord, Hash)]
 pub enum EcmaScriptModulesReferenceSubType {
+    ImportWithType(ImportWithType),
     Custom(u8),
+    #[default]
     Undefined,
 }
 
@@ -33,6 +44,7 @@ pub enum CssReferenceSubType {
 pub enum UrlReferenceSubType {
     EcmaScriptNewUrl,
     CssUrl,
+    HtmlUrl,
     Custom(u8),
     Undefined,
 }
@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use turbo_tasks::primitives::StringsVc;
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+
+use super::{find_context_file, FindContextFileResult};
+
+/// The lockfiles of the supported package managers, in the order they are
+/// looked up.
+pub const LOCKFILES: [&str; 3] = ["pnpm-lock.yaml", "yarn.lock", "package-lock.json"];
+
+/// The installed versions of each package, keyed by package name.
+#[turbo_tasks::value(transparent)]
+pub struct PackageVersions(BTreeMap<String, BTreeSet<String>>);
+
+/// The packages that a lockfile of pnpm, yarn or npm installs.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+pub struct Lockfile {
+    pub packages: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Lockfile {
+    /// Parses a lockfile. `name` is the file name of the lockfile, which
+    /// determines its format.
+    pub fn parse(name: &str, content: &str) -> Result<Self> {
+        let mut lockfile = Lockfile::default();
+        match name {
+            "pnpm-lock.yaml" => lockfile.add_pnpm_packages(content)?,
+            "yarn.lock" => lockfile.add_yarn_packages(content),
+            "package-lock.json" => lockfile.add_npm_packages(content)?,
+            _ => bail!("{name} is not a supported lockfile"),
+        }
+        Ok(lockfile)
+    }
+
+    /// Returns whether the lockfile installs a package with this name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.packages.contains_key(name)
+    }
+
+    fn add(&mut self, name: &str, version: &str) {
+        if name.is_empty() || version.is_empty() {
+            return;
+        }
+        self.packages
+            .entry(name.to_string())
+            .or_default()
+            .insert(version.to_string());
+    }
+
+    /// Package keys are `/name/1.0.0_peer@1.0.0` in lockfile version 5 and
+    /// `/name@1.0.0(peer@1.0.0)` in version 6.
+    fn add_pnpm_packages(&mut self, content: &str) -> Result<()> {
+        let lockfile: YamlValue = serde_yaml::from_str(content).context("invalid YAML")?;
+        let Some(packages) = lockfile.get("packages").and_then(YamlValue::as_mapping) else {
+            return Ok(());
+        };
+        for key in packages.keys().filter_map(YamlValue::as_str) {
+            let key = key.strip_prefix('/').unwrap_or(key);
+            // The name of scoped packages contains a `/` itself.
+            let offset = if key.starts_with('@') {
+                key.find('/').map_or(key.len(), |i| i + 1)
+            } else {
+                0
+            };
+            let Some(i) = key[offset..].find(['@', '/']) else {
+                continue;
+            };
+            let (name, version) = key.split_at(offset + i);
+            let version = version[1..].split(['(', '_']).next().unwrap_or_default();
+            self.add(name, version);
+        }
+        Ok(())
+    }
+
+    /// Entries start with an unindented line of comma separated descriptors,
+    /// e.g. `"a@^1.0.0", a@^1.1.0:`, followed by indented fields, of which the
+    /// version is `version "1.1.0"` in yarn 1 and `version: 1.1.0` in later
+    /// versions.
+    fn add_yarn_packages(&mut self, content: &str) {
+        let mut name = None;
+        for line in content.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            if !line.starts_with(' ') {
+                name = line
+                    .trim_end_matches(':')
+                    .split(',')
+                    .next()
+                    .map(|descriptor| descriptor.trim().trim_matches('"'))
+                    .and_then(|descriptor| {
+                        let i = descriptor.get(1..)?.find('@')?;
+                        Some(descriptor[..=i].to_string())
+                    });
+                continue;
+            }
+            let Some(name) = &name else {
+                continue;
+            };
+            // Only fields directly below the descriptors, not nested ones.
+            let field = line.trim_start();
+            if line.len() - field.len() != 2 {
+                continue;
+            }
+            if let Some(version) = field
+                .strip_prefix("version ")
+                .or_else(|| field.strip_prefix("version: "))
+            {
+                let version = version.trim().trim_matches('"').to_string();
+                self.add(name, &version);
+            }
+        }
+    }
+
+    /// Lockfile version 2 and later list the packages by their path, e.g.
+    /// `node_modules/a/node_modules/b`, version 1 nests them by name.
+    fn add_npm_packages(&mut self, content: &str) -> Result<()> {
+        let lockfile: JsonValue = serde_json::from_str(content).context("invalid JSON")?;
+        if let Some(packages) = lockfile["packages"].as_object() {
+            for (path, package) in packages {
+                let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                    // The root and workspace packages.
+                    continue;
+                };
+                if let Some(version) = package["version"].as_str() {
+                    self.add(name, version);
+                }
+            }
+        } else {
+            self.add_npm_dependencies(&lockfile["dependencies"]);
+        }
+        Ok(())
+    }
+
+    fn add_npm_dependencies(&mut self, dependencies: &JsonValue) {
+        let Some(dependencies) = dependencies.as_object() else {
+            return;
+        };
+        for (name, dependency) in dependencies {
+            if let Some(version) = dependency["version"].as_str() {
+                self.add(name, version);
+            }
+            self.add_npm_dependencies(&dependency["dependencies"]);
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionLockfile(Option<LockfileVc>);
+
+#[turbo_tasks::function]
+pub(super) fn lockfiles() -> StringsVc {
+    StringsVc::cell(LOCKFILES.iter().map(|name| name.to_string()).collect())
+}
+
+/// Returns the lockfile of the project that contains `context`, which is the
+/// closest lockfile in `context` or one of its parents.
+#[turbo_tasks::function]
+pub async fn find_lockfile(context: FileSystemPathVc) -> Result<OptionLockfileVc> {
+    let FindContextFileResult::Found(path, _) = &*find_context_file(context, lockfiles()).await?
+    else {
+        return Ok(OptionLockfileVc::cell(None));
+    };
+    Ok(OptionLockfileVc::cell(Some(read_lockfile(*path))))
+}
+
+#[turbo_tasks::function]
+async fn read_lockfile(path: FileSystemPathVc) -> Result<LockfileVc> {
+    let path_value = path.await?;
+    let FileContent::Content(file) = &*path.read().await? else {
+        return Ok(Lockfile::default().cell());
+    };
+    Ok(
+        Lockfile::parse(path_value.file_name(), &file.content().to_str()?)
+            .with_context(|| format!("failed to parse {}", path_value.path))?
+            .cell(),
+    )
+}
+
+#[turbo_tasks::value_impl]
+impl LockfileVc {
+    /// Returns the packages of which more than one version is installed.
+    #[turbo_tasks::function]
+    pub async fn duplicate_packages(self) -> Result<PackageVersionsVc> {
+        Ok(PackageVersionsVc::cell(
+            self.await?
+                .packages
+                .iter()
+                .filter(|(_, versions)| versions.len() > 1)
+                .map(|(name, versions)| (name.clone(), versions.clone()))
+                .collect(),
+        ))
+    }
+
+    /// Returns the names of all installed packages.
+    #[turbo_tasks::function]
+    pub async fn package_names(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(
+            self.await?.packages.keys().cloned().collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(lockfile: &Lockfile, name: &str) -> Vec<String> {
+        lockfile.packages[name].iter().cloned().collect()
+    }
+
+    #[test]
+    fn parses_pnpm_lockfile() {
+        let lockfile = Lockfile::parse(
+            "pnpm-lock.yaml",
+            r#"
+lockfileVersion: 5.4
+packages:
+  /a/1.0.0:
+    resolution: {integrity: sha512-a}
+  /a/2.0.0_b@1.0.0:
+    resolution: {integrity: sha512-a}
+  /@scope/b@1.0.0(a@2.0.0):
+    resolution: {integrity: sha512-b}
+"#,
+        )
+        .unwrap();
+        assert_eq!(versions(&lockfile, "a"), ["1.0.0", "2.0.0"]);
+        assert_eq!(versions(&lockfile, "@scope/b"), ["1.0.0"]);
+    }
+
+    #[test]
+    fn parses_yarn_lockfile() {
+        let lockfile = Lockfile::parse(
+            "yarn.lock",
+            r#"# yarn lockfile v1
+
+"@scope/a@^1.0.0", "@scope/a@^1.1.0":
+  version "1.1.0"
+  dependencies:
+    b "^2.0.0"
+
+b@^2.0.0:
+  version: 2.0.1
+"#,
+        )
+        .unwrap();
+        assert_eq!(versions(&lockfile, "@scope/a"), ["1.1.0"]);
+        assert_eq!(versions(&lockfile, "b"), ["2.0.1"]);
+    }
+
+    #[test]
+    fn parses_npm_lockfile() {
+        let lockfile = Lockfile::parse(
+            "package-lock.json",
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "name": "app" },
+                    "node_modules/a": { "version": "1.0.0" },
+                    "node_modules/b/node_modules/a": { "version": "2.0.0" }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(versions(&lockfile, "a"), ["1.0.0", "2.0.0"]);
+        assert!(!lockfile.contains("app"));
+    }
+}
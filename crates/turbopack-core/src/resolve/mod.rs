@@ -18,11 +18,14 @@ use turbo_tasks_fs::{
 
 use self::{
     exports::ExportsField,
+    lockfile::find_lockfile,
+    node::is_node_builtin,
     options::{
         resolve_modules_options, ImportMapResult, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveModulesOptionsVc, ResolveOptionsVc,
     },
     origin::ResolveOriginVc,
+    package_cache::read_package_json,
     parse::{Request, RequestVc},
     pattern::QueryMapVc,
 };
@@ -30,7 +33,9 @@ use crate::{
     asset::{Asset, AssetOptionVc, AssetVc, AssetsVc},
     issue::{
         package_json::{PackageJsonIssue, PackageJsonIssueVc},
-        resolve::{ResolvingIssue, ResolvingIssueVc},
+        resolve::{
+            ForbiddenImportIssue, ResolvingIssue, ResolvingIssueVc, UndeclaredDependencyIssue,
+        },
     },
     reference::{AssetReference, AssetReferenceVc},
     reference_type::ReferenceType,
@@ -45,9 +50,11 @@ use crate::{
 
 mod alias_map;
 pub(crate) mod exports;
+pub mod lockfile;
 pub mod node;
 pub mod options;
 pub mod origin;
+mod package_cache;
 pub mod parse;
 pub mod pattern;
 pub mod plugin;
@@ -945,7 +952,7 @@ async fn resolve_module_request(
     // try both.
     for package_path in &result.packages {
         let package_json_path = package_path.join("package.json");
-        let package_json = package_json_path.read_json();
+        let package_json = read_package_json(*package_path);
         if is_match {
             results.push(
                 resolve_into_folder(*package_path, package_json, package_json_path, options)
@@ -1250,6 +1257,8 @@ impl ValueToString for AffectingResolvingAssetReference {
     }
 }
 
+/// Reports requests that can't be resolved and requests of forbidden
+/// imports.
 pub async fn handle_resolve_error(
     result: ResolveResultVc,
     reference_type: Value<ReferenceType>,
@@ -1257,6 +1266,7 @@ pub async fn handle_resolve_error(
     request: RequestVc,
     resolve_options: ResolveOptionsVc,
 ) -> Result<ResolveResultVc> {
+    check_forbidden_imports(origin, request, resolve_options).await?;
     Ok(match result.is_unresolveable().await {
         Ok(unresolveable) => {
             if *unresolveable {
@@ -1269,6 +1279,8 @@ pub async fn handle_resolve_error(
                 }
                 .into();
                 issue.as_issue().emit();
+            } else {
+                check_declared_dependency(origin, request, resolve_options, result).await?;
             }
             result
         }
@@ -1286,3 +1298,101 @@ pub async fn handle_resolve_error(
         }
     })
 }
+
+/// The fields of a package.json which declare dependencies.
+const DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Reports requests of the app that resolve to packages in `node_modules`,
+/// but aren't declared in its package.json or not installed by its lockfile.
+/// These are phantom dependencies, which only work as long as another package
+/// depends on them.
+async fn check_declared_dependency(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    resolve_options: ResolveOptionsVc,
+    result: ResolveResultVc,
+) -> Result<()> {
+    if !resolve_options.await?.validate_dependencies {
+        return Ok(());
+    }
+    let Request::Module { module, .. } = &*request.await? else {
+        return Ok(());
+    };
+    if is_node_builtin(module) {
+        return Ok(());
+    }
+    let context = origin.origin_path();
+    // Packages declare their own dependencies.
+    if context.await?.path.contains("node_modules/") {
+        return Ok(());
+    }
+    let mut in_node_modules = false;
+    for asset in result.primary_assets().await?.iter() {
+        in_node_modules |= asset.path().await?.path.contains("node_modules/");
+    }
+    if !in_node_modules {
+        return Ok(());
+    }
+    let FindContextFileResult::Found(package_json_path, _) =
+        &*find_context_file(context.parent(), package_json()).await?
+    else {
+        return Ok(());
+    };
+    let FileJsonContent::Content(package_json) = &*package_json_path.read_json().await? else {
+        return Ok(());
+    };
+    let declared = package_json["name"].as_str() == Some(module.as_str())
+        || DEPENDENCY_FIELDS
+            .iter()
+            .any(|field| package_json[field].get(module).is_some());
+    let in_lockfile = match *find_lockfile(package_json_path.parent()).await? {
+        Some(lockfile) => lockfile.await?.contains(module),
+        None => true,
+    };
+    if declared && in_lockfile {
+        return Ok(());
+    }
+    UndeclaredDependencyIssue {
+        package: module.clone(),
+        context,
+        package_json: *package_json_path,
+        declared,
+        in_lockfile,
+    }
+    .cell()
+    .as_issue()
+    .emit();
+    Ok(())
+}
+
+async fn check_forbidden_imports(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    resolve_options: ResolveOptionsVc,
+) -> Result<()> {
+    let options = resolve_options.await?;
+    if options.forbidden_imports.is_empty() {
+        return Ok(());
+    }
+    let Request::Module { module, .. } = &*request.await? else {
+        return Ok(());
+    };
+    for forbidden in options.forbidden_imports.iter() {
+        if &forbidden.module == module {
+            ForbiddenImportIssue {
+                request,
+                context: origin.origin_path(),
+                reason: forbidden.reason.clone(),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+    Ok(())
+}
@@ -29,3 +29,78 @@ pub fn node_cjs_resolve_options(root: FileSystemPathVc) -> ResolveOptionsVc {
     }
     .cell()
 }
+
+/// The builtin modules of Node.js, which can also be imported with a `node:`
+/// prefix.
+pub const NODE_BUILTINS: [&str; 50] = [
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "dns/promises",
+    "domain",
+    "events",
+    "fs",
+    "fs/promises",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "path/posix",
+    "path/win32",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "stream/promises",
+    "stream/web",
+    "string_decoder",
+    "sys",
+    "timers",
+    "timers/promises",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "util/types",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+/// Checks whether a request refers to a Node.js builtin module, e.g. `fs` or
+/// `node:fs/promises`.
+pub fn is_node_builtin(request: &str) -> bool {
+    let request = request.strip_prefix("node:").unwrap_or(request);
+    NODE_BUILTINS.contains(&request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_node_builtin;
+
+    #[test]
+    fn node_builtins() {
+        assert!(is_node_builtin("fs"));
+        assert!(is_node_builtin("node:fs/promises"));
+        assert!(!is_node_builtin("node:pnpapi"));
+        assert!(!is_node_builtin("fs-extra"));
+    }
+}
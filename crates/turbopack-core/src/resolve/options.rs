@@ -359,6 +359,19 @@ impl ResolvedMapVc {
     }
 }
 
+/// A module that must not be imported in a module graph, e.g. `server-only`
+/// in the module graph of the client.
+#[derive(
+    TraceRawVcs, Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize, ValueDebugFormat,
+)]
+pub struct ForbiddenImport {
+    /// The name of the module, e.g. `server-only`. Imports of paths inside of
+    /// the module are forbidden too.
+    pub module: String,
+    /// Explains why the module can't be imported here.
+    pub reason: String,
+}
+
 #[turbo_tasks::value(shared)]
 #[derive(Clone, Debug, Default)]
 pub struct ResolveOptions {
@@ -375,6 +388,13 @@ pub struct ResolveOptions {
     pub fallback_import_map: Option<ImportMapVc>,
     pub resolved_map: Option<ResolvedMapVc>,
     pub plugins: Vec<ResolvePluginVc>,
+    /// Modules that can't be imported. Importing them is reported as an
+    /// error.
+    pub forbidden_imports: Vec<ForbiddenImport>,
+    /// Reports imports of packages in `node_modules` that the importing
+    /// package doesn't declare as dependency, or that the lockfile doesn't
+    /// install.
+    pub validate_dependencies: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde_json::{Map, Value as JsonValue};
+use turbo_tasks::TryJoinIterExt;
+use turbo_tasks_fs::{
+    to_sys_path, DirectoryContent, DirectoryEntry, File, FileContent, FileJsonContent,
+    FileJsonContentVc, FileSystemPathVc,
+};
+use turbo_tasks_hash::{encode_hex, DeterministicHasher, Xxh3Hash64Hasher};
+
+use super::{find_context_file, lockfile::lockfiles, FindContextFileResult};
+use crate::issue::package_cache::PackageCacheIssue;
+
+/// Needs to be bumped when the format of the cached digests changes.
+const CACHE_VERSION: u32 = 2;
+
+/// The fields of a package.json that are used to resolve requests into the
+/// package.
+const RESOLVE_FIELDS: [&str; 10] = [
+    "name", "version", "type", "main", "module", "browser", "types", "typings", "exports",
+    "imports",
+];
+
+/// The resolve fields of all packages installed in a `node_modules`
+/// directory, keyed by the path of the package.
+///
+/// The digest is stored in `node_modules/.cache/turbopack/resolve` and
+/// survives restarts, so the package.json files of the packages don't need to
+/// be read again on a cold start. It's keyed by the lockfile and the
+/// package.json of the project, so it's invalidated when packages are
+/// installed, but not when a package.json inside of `node_modules` is edited
+/// by hand.
+#[turbo_tasks::value(serialization = "none")]
+struct PackageDigest {
+    packages: HashMap<String, JsonValue>,
+}
+
+#[turbo_tasks::value(transparent)]
+struct OptionPackageDigest(Option<PackageDigestVc>);
+
+/// Returns the package.json of a package. Packages directly inside of a
+/// `node_modules` directory of a project with a lockfile are read from the
+/// package digest.
+#[turbo_tasks::function]
+pub(super) async fn read_package_json(package_path: FileSystemPathVc) -> Result<FileJsonContentVc> {
+    let path = &package_path.await?.path;
+    if let Some(node_modules) = node_modules_directory(path) {
+        let node_modules = package_path.root().join(node_modules);
+        if let Some(digest) = *package_digest(node_modules).await? {
+            if let Some(package_json) = digest.await?.packages.get(path) {
+                return Ok(FileJsonContent::Content(package_json.clone()).cell());
+            }
+        }
+    }
+    Ok(package_path.join("package.json").read_json())
+}
+
+/// Returns the `node_modules` directory which contains the package at this
+/// path, e. g. `a/node_modules` for `a/node_modules/@scope/b`, or None when
+/// the path is not a package in `node_modules`.
+fn node_modules_directory(path: &str) -> Option<&str> {
+    let (directory, package) = match path.rsplit_once("/node_modules/") {
+        Some((parent, package)) => (&path[..parent.len() + "/node_modules".len()], package),
+        None => ("node_modules", path.strip_prefix("node_modules/")?),
+    };
+    let name = match package.strip_prefix('@') {
+        Some(scoped) => scoped.split_once('/')?.1,
+        None => package,
+    };
+    (!name.is_empty() && !name.contains('/')).then_some(directory)
+}
+
+#[turbo_tasks::function]
+async fn package_digest(node_modules: FileSystemPathVc) -> Result<OptionPackageDigestVc> {
+    // The digest is written next to the packages, which requires a file system
+    // on disk.
+    if to_sys_path(node_modules).await?.is_none() {
+        return Ok(OptionPackageDigestVc::cell(None));
+    }
+    let project = node_modules.parent();
+    // In a workspace, the lockfile is in the root of the workspace instead of
+    // next to `node_modules`.
+    let FindContextFileResult::Found(lockfile, _) =
+        &*find_context_file(project, lockfiles()).await?
+    else {
+        // Without a lockfile, installs can't be detected.
+        return Ok(OptionPackageDigestVc::cell(None));
+    };
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_bytes(&CACHE_VERSION.to_le_bytes());
+    hasher.write_bytes(node_modules.await?.path.as_bytes());
+    // These files change when packages are installed, updated or removed.
+    for path in [project.join("package.json"), *lockfile] {
+        if let FileContent::Content(file) = &*path.read().await? {
+            hasher.write_bytes(path.await?.path.as_bytes());
+            hasher.write_ref(file.content());
+        }
+    }
+    let file = node_modules.join(&format!(
+        ".cache/turbopack/resolve/{}.json",
+        encode_hex(hasher.finish())
+    ));
+
+    // An unreadable digest is treated as a cache miss.
+    if let FileJsonContent::Content(packages) = &*file.read_json().await? {
+        if let Ok(packages) = serde_json::from_value(packages.clone()) {
+            return Ok(OptionPackageDigestVc::cell(Some(
+                PackageDigest { packages }.cell(),
+            )));
+        }
+    }
+    let packages = scan_packages(node_modules).await?;
+    let content = File::from(serde_json::to_string(&packages)?);
+    // A failed write only results in a cache miss next time.
+    if let Err(err) = file.write(FileContent::Content(content).cell()).await {
+        PackageCacheIssue {
+            path: file,
+            error_message: format!("{err:?}"),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+    Ok(OptionPackageDigestVc::cell(Some(
+        PackageDigest { packages }.cell(),
+    )))
+}
+
+async fn scan_packages(node_modules: FileSystemPathVc) -> Result<HashMap<String, JsonValue>> {
+    let mut package_paths = Vec::new();
+    let DirectoryContent::Entries(entries) = &*node_modules.read_dir().await? else {
+        return Ok(HashMap::new());
+    };
+    for (name, entry) in entries.iter() {
+        let (DirectoryEntry::Directory(path) | DirectoryEntry::Symlink(path)) = entry else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        if !name.starts_with('@') {
+            package_paths.push(*path);
+            continue;
+        }
+        if let DirectoryContent::Entries(entries) = &*path.read_dir().await? {
+            for entry in entries.values() {
+                if let DirectoryEntry::Directory(path) | DirectoryEntry::Symlink(path) = entry {
+                    package_paths.push(*path);
+                }
+            }
+        }
+    }
+
+    let packages = package_paths
+        .into_iter()
+        .map(read_resolve_fields)
+        .try_join()
+        .await?;
+    Ok(packages.into_iter().flatten().collect())
+}
+
+async fn read_resolve_fields(
+    package_path: FileSystemPathVc,
+) -> Result<Option<(String, JsonValue)>> {
+    let FileJsonContent::Content(package_json) =
+        &*package_path.join("package.json").read_json().await?
+    else {
+        return Ok(None);
+    };
+    let fields = RESOLVE_FIELDS
+        .iter()
+        .filter_map(|&field| Some((field.to_string(), package_json.get(field)?.clone())))
+        .collect::<Map<_, _>>();
+    Ok(Some((
+        package_path.await?.path.clone(),
+        JsonValue::Object(fields),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_fs::DiskFileSystemVc;
+    use turbo_tasks_memory::MemoryBackend;
+
+    use super::*;
+
+    /// Reads the version of `a` in a fresh session, like after a restart.
+    fn read_version(root: &Path) -> String {
+        let root = root.to_string_lossy().to_string();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async move {
+                    let fs = DiskFileSystemVc::new("test".to_string(), root);
+                    let package = fs.root().join("packages/app/node_modules/a");
+                    let FileJsonContent::Content(package_json) =
+                        &*read_package_json(package).await?
+                    else {
+                        panic!("package.json of a not found");
+                    };
+                    Ok(package_json["version"].as_str().unwrap().to_string())
+                })
+                .await
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn rescans_packages_when_the_lockfile_changes() {
+        crate::register();
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        // The lockfile is in the root of the workspace, not next to
+        // `node_modules`.
+        write("yarn.lock", "a@1.0.0:\n  version \"1.0.0\"\n");
+        write("packages/app/package.json", r#"{"name":"app"}"#);
+        write(
+            "packages/app/node_modules/a/package.json",
+            r#"{"name":"a","version":"1.0.0"}"#,
+        );
+
+        assert_eq!(read_version(dir.path()), "1.0.0");
+        let cache = dir
+            .path()
+            .join("packages/app/node_modules/.cache/turbopack/resolve");
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), 1);
+
+        // Editing a package by hand doesn't invalidate the digest.
+        write(
+            "packages/app/node_modules/a/package.json",
+            r#"{"name":"a","version":"2.0.0"}"#,
+        );
+        assert_eq!(read_version(dir.path()), "1.0.0");
+
+        // An install changes the lockfile, which leads to a rescan.
+        write("yarn.lock", "a@2.0.0:\n  version \"2.0.0\"\n");
+        assert_eq!(read_version(dir.path()), "2.0.0");
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn finds_node_modules_directory() {
+        assert_eq!(
+            node_modules_directory("node_modules/a"),
+            Some("node_modules")
+        );
+        assert_eq!(
+            node_modules_directory("x/node_modules/@scope/a"),
+            Some("x/node_modules")
+        );
+        assert_eq!(
+            node_modules_directory("x/node_modules/a/node_modules/b"),
+            Some("x/node_modules/a/node_modules")
+        );
+        assert_eq!(node_modules_directory("node_modules/a/lib"), None);
+        assert_eq!(node_modules_directory("node_modules/@scope"), None);
+        assert_eq!(node_modules_directory("src/a"), None);
+    }
+}
@@ -1,14 +1,27 @@
-use std::{io::Write, ops::Deref, sync::Arc};
+use std::{collections::HashSet, io::Write, ops::Deref, sync::Arc};
 
 use anyhow::Result;
 use indexmap::IndexMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use sourcemap::SourceMap as CrateMap;
+use sourcemap::{SourceMap as CrateMap, SourceMapBuilder};
 use turbo_tasks::TryJoinIterExt;
 use turbo_tasks_fs::rope::{Rope, RopeBuilder, RopeVc};
 
 use crate::source_pos::SourcePos;
 
+/// The prefix of the sources of code that is generated by turbopack, like the
+/// runtime.
+pub const GENERATED_SOURCE_PREFIX: &str = "/__turbopack__/";
+
+/// Whether devtools should skip the source by default when debugging, as it's
+/// third party code or code generated by turbopack.
+pub fn is_ignored_source(source: &str) -> bool {
+    source.starts_with(GENERATED_SOURCE_PREFIX)
+        || source.starts_with("node_modules/")
+        || source.starts_with("/node_modules/")
+        || source.contains("/node_modules/")
+}
+
 /// Allows callers to generate source maps.
 #[turbo_tasks::value_trait]
 pub trait GenerateSourceMap {
@@ -116,11 +129,7 @@ impl SourceMapVc {
     pub async fn to_rope(self) -> Result<RopeVc> {
         let this = self.await?;
         let rope = match &*this {
-            SourceMap::Regular(r) => {
-                let mut bytes = vec![];
-                r.0.to_writer(&mut bytes)?;
-                Rope::from(bytes)
-            }
+            SourceMap::Regular(r) => Rope::from(regular_map_to_bytes(&r.0)?),
 
             SourceMap::Sectioned(s) => {
                 if s.sections.len() == 1 {
@@ -172,6 +181,59 @@ impl SourceMapVc {
         Ok(rope.cell())
     }
 
+    /// Flattens a (possibly nested) sectioned source map into a single regular
+    /// source map. This is required for post-processing steps that need to
+    /// chain their own source map with the existing one.
+    #[turbo_tasks::function]
+    pub async fn flatten(self) -> Result<SourceMapVc> {
+        let this = self.await?;
+        let sectioned = match &*this {
+            SourceMap::Regular(_) => return Ok(self),
+            SourceMap::Sectioned(s) => s,
+        };
+
+        let sections = sectioned
+            .sections
+            .iter()
+            .map(|s| async move { Ok((s.offset, s.map.flatten().await?)) })
+            .try_join()
+            .await?;
+
+        let mut builder = SourceMapBuilder::new(None);
+        let mut sources_with_contents = HashSet::new();
+        for (offset, map) in sections {
+            let map: &CrateMap = match &*map {
+                SourceMap::Regular(map) => map,
+                SourceMap::Sectioned(_) => unreachable!("flatten always returns a regular map"),
+            };
+            for token in map.tokens() {
+                let dst_line = token.get_dst_line() + offset.line as u32;
+                // The section's column offset only applies to its first line.
+                let dst_col = if token.get_dst_line() == 0 {
+                    token.get_dst_col() + offset.column as u32
+                } else {
+                    token.get_dst_col()
+                };
+                let raw = builder.add(
+                    dst_line,
+                    dst_col,
+                    token.get_src_line(),
+                    token.get_src_col(),
+                    token.get_source(),
+                    token.get_name(),
+                );
+                if token.has_source() && sources_with_contents.insert(raw.src_id) {
+                    builder.set_source_contents(
+                        raw.src_id,
+                        map.get_source_contents(token.get_src_id()),
+                    );
+                }
+            }
+        }
+
+        Ok(SourceMapVc::new_regular(builder.into_sourcemap()))
+    }
+
     /// Traces a generated line/column into an mapping token representing either
     /// synthetic code or user-authored original code.
     #[turbo_tasks::function]
@@ -225,6 +287,65 @@ impl SourceMapVc {
     }
 }
 
+/// Stringifies a regular source map, with the ignore list of its sources.
+fn regular_map_to_bytes(map: &CrateMap) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    map.to_writer(&mut bytes)?;
+    // The sourcemap crate doesn't know about ignore lists, so they are added
+    // to the serialized map.
+    let ignore_list = map
+        .sources()
+        .enumerate()
+        .filter(|(_, source)| is_ignored_source(source))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if !ignore_list.is_empty() {
+        let mut map: serde_json::Value = serde_json::from_slice(&bytes)?;
+        map["ignoreList"] = ignore_list.clone().into();
+        // The name that Chrome used before the ignore list was standardized.
+        map["x_google_ignoreList"] = ignore_list.into();
+        bytes = serde_json::to_vec(&map)?;
+    }
+    Ok(bytes)
+}
+
+/// A source map of code that is generated by turbopack, which maps every line
+/// to the same line of a source under [GENERATED_SOURCE_PREFIX]. This includes
+/// the code in the sources of the map, and puts it on the ignore list.
+#[turbo_tasks::value]
+pub struct GeneratedCodeSourceMap {
+    name: String,
+    code: RopeVc,
+}
+
+#[turbo_tasks::value_impl]
+impl GeneratedCodeSourceMapVc {
+    #[turbo_tasks::function]
+    pub fn new(name: &str, code: RopeVc) -> Self {
+        GeneratedCodeSourceMap {
+            name: name.to_string(),
+            code,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for GeneratedCodeSourceMap {
+    #[turbo_tasks::function]
+    async fn generate_source_map(&self) -> Result<SourceMapVc> {
+        let code = self.code.await?;
+        let code = code.to_str()?;
+        let mut builder = SourceMapBuilder::new(None);
+        let source = builder.add_source(&format!("{GENERATED_SOURCE_PREFIX}{}", self.name));
+        builder.set_source_contents(source, Some(&code));
+        for line in 0..code.lines().count() as u32 {
+            builder.add_raw(line, 0, line, 0, Some(source), None);
+        }
+        Ok(SourceMapVc::new_regular(builder.into_sourcemap()))
+    }
+}
+
 /// A regular source map covers an entire file.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegularSourceMap(Arc<CrateMapWrapper>);
@@ -313,3 +434,44 @@ impl SourceMapSection {
         Self { offset, map }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sourcemap::SourceMapBuilder;
+
+    use super::{is_ignored_source, regular_map_to_bytes, GENERATED_SOURCE_PREFIX};
+
+    #[test]
+    fn ignores_third_party_and_generated_sources() {
+        assert!(is_ignored_source("node_modules/react/index.js"));
+        assert!(is_ignored_source("/node_modules/react/index.js"));
+        assert!(is_ignored_source(
+            "packages/app/node_modules/react/index.js"
+        ));
+        assert!(is_ignored_source(&format!(
+            "{GENERATED_SOURCE_PREFIX}runtime.js"
+        )));
+        assert!(!is_ignored_source("src/node_modules.js"));
+        assert!(!is_ignored_source("src/index.js"));
+    }
+
+    #[test]
+    fn adds_ignore_list() {
+        let mut builder = SourceMapBuilder::new(None);
+        let app = builder.add_source("src/index.js");
+        let library = builder.add_source("node_modules/react/index.js");
+        builder.add_raw(0, 0, 0, 0, Some(app), None);
+        builder.add_raw(1, 0, 0, 0, Some(library), None);
+        let bytes = regular_map_to_bytes(&builder.into_sourcemap()).unwrap();
+        let map: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(map["ignoreList"], serde_json::json!([1]));
+        assert_eq!(map["x_google_ignoreList"], serde_json::json!([1]));
+
+        let mut builder = SourceMapBuilder::new(None);
+        let app = builder.add_source("src/index.js");
+        builder.add_raw(0, 0, 0, 0, Some(app), None);
+        let bytes = regular_map_to_bytes(&builder.into_sourcemap()).unwrap();
+        let map: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(map.get("ignoreList").is_none());
+    }
+}
@@ -1,11 +1,7 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use turbo_tasks::{
-    debug::ValueDebugFormat,
-    primitives::{JsonValueVc, StringVc},
-    trace::TraceRawVcs,
-};
-use turbo_tasks_fs::{FileContent, FileContentReadRef, LinkType};
+use turbo_tasks::{debug::ValueDebugFormat, primitives::StringVc, trace::TraceRawVcs};
+use turbo_tasks_fs::{rope::RopeVc, FileContent, FileContentReadRef, LinkType};
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
 use crate::asset::{AssetContent, AssetContentReadRef, AssetContentVc};
@@ -164,9 +160,10 @@ pub struct TotalUpdate {
 pub struct PartialUpdate {
     /// The version this update will bring the object to.
     pub to: VersionVc,
-    /// The instructions to be passed to a remote system in order to update the
-    /// versioned object.
-    pub instruction: JsonValueVc,
+    /// The JSON encoded instructions to be passed to a remote system in order
+    /// to update the versioned object. It's a rope, so that instructions can
+    /// share unchanged parts, like the code of a module, with earlier ones.
+    pub instruction: RopeVc,
 }
 
 /// [`Version`] implementation that hashes a file at a given path and returns
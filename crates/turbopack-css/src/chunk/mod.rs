@@ -131,13 +131,20 @@ impl CssChunkContentVc {
             }
         }
 
+        let banner = this.context.chunk_banner(this.chunk_path).await?;
         let mut code = CodeBuilder::default();
+        if let Some(banner) = &banner.banner {
+            code.push_source(banner, None);
+        }
         writeln!(code, "/* chunk {} */", chunk_name.await?)?;
         for external_import in external_imports {
             writeln!(code, "@import {};", stringify_str(&external_import))?;
         }
 
         code.push_code(&body.build());
+        if let Some(footer) = &banner.footer {
+            code.push_source(footer, None);
+        }
 
         if code.has_source_map() {
             let chunk_path = this.chunk_path.await?;
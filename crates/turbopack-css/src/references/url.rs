@@ -98,6 +98,7 @@ impl CodeGenerateable for UrlAssetReference {
         let this = self_vc.await?;
         let chunk_path = context.chunk_path(this.origin.origin_path(), ".css");
         let context_path = chunk_path.parent().await?;
+        let base_path = context.base_path().await?;
 
         let mut visitors = Vec::new();
 
@@ -105,7 +106,7 @@ impl CodeGenerateable for UrlAssetReference {
             let path = asset.path().await?;
             let relative_path = context_path
                 .get_relative_path_to(&path)
-                .unwrap_or_else(|| format!("/{}", path.path));
+                .unwrap_or_else(|| format!("{base_path}/{}", path.path));
 
             visitors.push(
                 create_visitor!((&this.path.await?), visit_mut_url(u: &mut Url) {
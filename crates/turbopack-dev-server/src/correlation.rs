@@ -0,0 +1,84 @@
+//! Correlation ids tag the logs caused by a request, including the output of
+//! the Node.js processes rendering it, so that interleaved logs of concurrent
+//! requests can be told apart.
+
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use hyper::Request;
+
+/// The header that carries the correlation id. An id set by a proxy in front
+/// of the dev server is kept, and the id is sent back in the response.
+pub const CORRELATION_ID_HEADER: &str = "x-request-id";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The correlation id of a request. It's stored in the extensions of the
+/// [Request].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorrelationId(pub String);
+
+impl CorrelationId {
+    /// Takes the id from the [CORRELATION_ID_HEADER] of the request, or
+    /// creates a new one.
+    pub fn for_request<B>(request: &Request<B>) -> Self {
+        request
+            .headers()
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= 128)
+            .map(|id| CorrelationId(id.to_string()))
+            .unwrap_or_else(Self::new)
+    }
+
+    fn new() -> Self {
+        CorrelationId(format!("req-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed)))
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::Request;
+
+    use super::{CorrelationId, CORRELATION_ID_HEADER};
+
+    #[test]
+    fn keeps_correlation_id_of_request() {
+        let request = Request::builder()
+            .header(CORRELATION_ID_HEADER, "proxy-42")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            CorrelationId::for_request(&request),
+            CorrelationId("proxy-42".to_string())
+        );
+    }
+
+    #[test]
+    fn creates_correlation_ids() {
+        let empty = Request::builder()
+            .header(CORRELATION_ID_HEADER, "")
+            .body(())
+            .unwrap();
+        let too_long = Request::builder()
+            .header(CORRELATION_ID_HEADER, "a".repeat(129))
+            .body(())
+            .unwrap();
+        let missing = Request::builder().body(()).unwrap();
+        let ids = [&empty, &too_long, &missing].map(|request| {
+            let CorrelationId(id) = CorrelationId::for_request(request);
+            assert!(id.starts_with("req-"), "{id}");
+            id
+        });
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[1], ids[2]);
+    }
+}
@@ -1,14 +1,68 @@
 use anyhow::{anyhow, Context, Result};
+use indexmap::{IndexMap, IndexSet};
 use mime_guess::mime::TEXT_HTML_UTF_8;
-use turbo_tasks::{debug::ValueDebug, primitives::StringVc};
-use turbo_tasks_fs::{File, FileSystemPathVc};
-use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
+use turbo_tasks::{
+    debug::ValueDebug,
+    primitives::{BoolVc, StringVc},
+};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, DeterministicHash, Xxh3Hash64Hasher};
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
-    chunk::{ChunkGroupVc, ChunkReferenceVc},
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    chunk::{
+        dev::normalize_base_path, hinted_chunks, AsyncLoadingHint, ChunkGroupVc, ChunkReferenceVc,
+        RuntimeAssetReferenceVc,
+    },
     reference::AssetReferencesVc,
     version::{Update, UpdateVc, Version, VersionVc, VersionedContent, VersionedContentVc},
 };
+use turbopack_ecmascript::chunk::EcmascriptChunkVc;
+
+/// A nonce which the dev server replaces with a new nonce for every request,
+/// in the HTML of the page and in its headers, e.g. in a
+/// `Content-Security-Policy` header set by the content source.
+pub const REQUEST_NONCE_PLACEHOLDER: &str = "__TURBOPACK_REQUEST_NONCE__";
+
+/// The header which marks the responses whose [REQUEST_NONCE_PLACEHOLDER]s
+/// the dev server replaces. It's set for the pages of [DevHtmlAsset]s which
+/// use the placeholder, so that other HTML, e.g. served from the project as
+/// it is, is left untouched. The header isn't sent to the browser.
+pub const REQUEST_NONCE_HEADER: &str = "x-turbopack-request-nonce";
+
+/// How the scripts of the chunks are loaded by the page.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, DeterministicHash)]
+pub enum ScriptLoading {
+    /// Classic scripts, unless the chunks are ES modules.
+    #[default]
+    Blocking,
+    /// Classic scripts with the `defer` attribute. ES module chunks are
+    /// deferred anyway.
+    Defer,
+    /// Module scripts, i.e. `type="module"`.
+    Module,
+}
+
+/// Customizes the HTML page generated by a [DevHtmlAsset], beyond the chunks
+/// it includes.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+pub struct DevHtmlOptions {
+    /// Tags which are added to the `<head>`, before the stylesheets, e.g.
+    /// `<meta>` or `<title>` elements. They are inserted as they are.
+    pub head_tags: Vec<String>,
+    /// Attributes of the `<body>` element, as name and value.
+    pub body_attributes: Vec<(String, String)>,
+    /// A nonce for a Content Security Policy, which is added to all script
+    /// and stylesheet elements of the page. The runtime adds it to the chunks
+    /// it loads as well. [REQUEST_NONCE_PLACEHOLDER] gives every request a
+    /// nonce of its own.
+    pub nonce: Option<String>,
+    /// Code which is inlined into the page and runs before the runtime and
+    /// the chunks, e.g. to set up globals the application expects.
+    pub bootstrap_scripts: Vec<String>,
+    pub script_loading: ScriptLoading,
+}
 
 /// The HTML entry point of the dev server.
 ///
@@ -19,6 +73,9 @@ pub struct DevHtmlAsset {
     path: FileSystemPathVc,
     chunk_groups: Vec<ChunkGroupVc>,
     body: Option<String>,
+    /// The path the page and its chunks are served under, e.g. `/app`.
+    base_path: String,
+    options: DevHtmlOptions,
 }
 
 #[turbo_tasks::value_impl]
@@ -58,6 +115,8 @@ impl DevHtmlAssetVc {
             path,
             chunk_groups,
             body: None,
+            base_path: String::new(),
+            options: DevHtmlOptions::default(),
         }
         .cell()
     }
@@ -72,6 +131,8 @@ impl DevHtmlAssetVc {
             path,
             chunk_groups,
             body: Some(body),
+            base_path: String::new(),
+            options: DevHtmlOptions::default(),
         }
         .cell()
     }
@@ -92,6 +153,35 @@ impl DevHtmlAssetVc {
         html.body = Some(body);
         Ok(html.cell())
     }
+
+    /// Prefixes the URLs of the chunks with `base_path`, for pages that are
+    /// served under a subpath. It should match the base path of the chunking
+    /// contexts of the chunk groups.
+    #[turbo_tasks::function]
+    pub async fn with_base_path(self, base_path: &str) -> Result<Self> {
+        let mut html: DevHtmlAsset = self.await?.clone_value();
+        html.base_path = normalize_base_path(base_path);
+        Ok(html.cell())
+    }
+
+    /// Whether the page has a nonce of its own for every request, i.e. its
+    /// nonce is the [REQUEST_NONCE_PLACEHOLDER].
+    #[turbo_tasks::function]
+    pub async fn uses_request_nonce(self) -> Result<BoolVc> {
+        let html = self.await?;
+        Ok(BoolVc::cell(
+            html.options.nonce.as_deref() == Some(REQUEST_NONCE_PLACEHOLDER),
+        ))
+    }
+
+    /// Replaces the [DevHtmlOptions] of the page, e.g. to add head tags or a
+    /// CSP nonce.
+    #[turbo_tasks::function]
+    pub async fn with_options(self, options: DevHtmlOptionsVc) -> Result<Self> {
+        let mut html: DevHtmlAsset = self.await?.clone_value();
+        html.options = options.await?.clone_value();
+        Ok(html.cell())
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -100,67 +190,282 @@ impl DevHtmlAssetVc {
     async fn html_content(self) -> Result<DevHtmlAssetContentVc> {
         let this = self.await?;
         let context_path = this.path.parent().await?;
+        let base_path = &this.base_path;
 
         let mut chunk_paths = vec![];
+        let mut runtime_paths = IndexSet::new();
+        let mut inline_scripts = IndexMap::new();
+        let mut module_scripts = false;
+        let mut chunks = Vec::new();
         for chunk_group in &this.chunk_groups {
             for chunk in chunk_group.chunks().await?.iter() {
+                chunks.push(*chunk);
                 let chunk_path = &*chunk.path().await?;
                 if let Some(relative_path) = context_path.get_path_to(chunk_path) {
-                    chunk_paths.push(format!("/{relative_path}"));
+                    chunk_paths.push(format!("{base_path}/{relative_path}"));
+                }
+                if let Some(chunk) = EcmascriptChunkVc::resolve_from(chunk).await? {
+                    module_scripts |= *chunk.is_es_module().await?;
+                }
+                for reference in chunk.references().await?.iter() {
+                    if let Some(reference) =
+                        RuntimeAssetReferenceVc::resolve_from(reference).await?
+                    {
+                        let runtime = reference.runtime();
+                        let runtime_path = &*runtime.path().await?;
+                        if *reference.is_inline().await? {
+                            if !inline_scripts.contains_key(&runtime_path.path) {
+                                let url = context_path
+                                    .get_path_to(runtime_path)
+                                    .map(|relative_path| format!("{base_path}/{relative_path}"));
+                                let code = inline_script(runtime, url.as_deref()).await?;
+                                inline_scripts.insert(runtime_path.path.clone(), code);
+                            }
+                        } else if let Some(relative_path) = context_path.get_path_to(runtime_path) {
+                            runtime_paths.insert(format!("{base_path}/{relative_path}"));
+                        }
+                    }
+                }
+            }
+        }
+
+        // The runtime is loaded before the chunks which evaluate entries.
+        let chunk_paths: Vec<String> = runtime_paths.into_iter().chain(chunk_paths).collect();
+
+        let mut preload_paths = IndexSet::new();
+        let mut prefetch_paths = IndexSet::new();
+        for (asset, hint) in hinted_chunks(&chunks).await? {
+            let paths = match hint {
+                AsyncLoadingHint::None => continue,
+                AsyncLoadingHint::Prefetch => &mut prefetch_paths,
+                AsyncLoadingHint::Preload => &mut preload_paths,
+            };
+            let asset_path = &*asset.path().await?;
+            if let Some(relative_path) = context_path.get_path_to(asset_path) {
+                let relative_path = format!("{base_path}/{relative_path}");
+                if !chunk_paths.contains(&relative_path) {
+                    paths.insert(relative_path);
                 }
             }
         }
+        // Preloaded chunks don't need to be prefetched as well.
+        prefetch_paths.retain(|path| !preload_paths.contains(path));
+
+        Ok(DevHtmlAssetContentVc::new(
+            chunk_paths,
+            inline_scripts.into_values().collect(),
+            module_scripts,
+            preload_paths.into_iter().collect(),
+            prefetch_paths.into_iter().collect(),
+            this.body.clone(),
+            this.options.clone(),
+        ))
+    }
+}
+
+/// Reads the code of a script that is inlined into the HTML. `</script` is
+/// escaped, so it doesn't end the script element.
+///
+/// The source map of the script is referenced relative to the script's own
+/// path. An inline script has the URL of the page, so the script gets a
+/// `sourceURL` and an absolute `sourceMappingURL` from the `url` it would be
+/// served at.
+async fn inline_script(asset: AssetVc, url: Option<&str>) -> Result<String> {
+    let AssetContent::File(file) = &*asset.content().await? else {
+        return Err(anyhow!("inlined script {} is not a file", asset.path().await?.path));
+    };
+    let FileContent::Content(file) = &*file.await? else {
+        return Err(anyhow!("inlined script {} is not found", asset.path().await?.path));
+    };
+    let code = file.content().to_str()?;
+    let code = match url {
+        Some(url) => with_source_url(&code, url),
+        None => code.into_owned(),
+    };
+    Ok(escape_script(&code))
+}
+
+/// Points the `sourceURL` and the `sourceMappingURL` of inlined `code` to the
+/// `url` it would be served at.
+fn with_source_url(code: &str, url: &str) -> String {
+    match code.rsplit_once("\n//# sourceMappingURL=") {
+        Some((code, _)) => format!("{code}\n//# sourceURL={url}\n//# sourceMappingURL={url}.map"),
+        None => format!("{code}\n//# sourceURL={url}"),
+    }
+}
 
-        Ok(DevHtmlAssetContentVc::new(chunk_paths, this.body.clone()))
+/// The `<link>` tag which fetches a chunk ahead of time. ES module chunks are
+/// preloaded with `modulepreload`, so they are parsed and compiled as well.
+fn hint_tag(
+    path: &str,
+    hint: AsyncLoadingHint,
+    module_scripts: bool,
+    nonce: &str,
+) -> Option<String> {
+    let href = escape_attribute(path);
+    match hint {
+        AsyncLoadingHint::None => None,
+        AsyncLoadingHint::Prefetch => Some(format!("<link rel=\"prefetch\" href=\"{href}\">")),
+        AsyncLoadingHint::Preload if path.ends_with(".js") && module_scripts => Some(format!(
+            "<link rel=\"modulepreload\"{nonce} href=\"{href}\">"
+        )),
+        AsyncLoadingHint::Preload if path.ends_with(".js") => Some(format!(
+            "<link rel=\"preload\" as=\"script\"{nonce} href=\"{href}\">"
+        )),
+        AsyncLoadingHint::Preload if path.ends_with(".css") => Some(format!(
+            "<link rel=\"preload\" as=\"style\"{nonce} href=\"{href}\">"
+        )),
+        AsyncLoadingHint::Preload => None,
     }
 }
 
+/// Escapes `</script`, so the code doesn't end the script element it's
+/// inlined into.
+fn escape_script(code: &str) -> String {
+    code.replace("</script", "<\\/script")
+}
+
+/// Escapes a value for a double quoted attribute.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
 #[turbo_tasks::value]
 struct DevHtmlAssetContent {
     chunk_paths: Vec<String>,
+    /// Scripts which are inlined into the page before the chunks, e.g. the
+    /// runtime.
+    inline_scripts: Vec<String>,
+    /// Whether the scripts are ES modules.
+    module_scripts: bool,
+    /// Chunks of dynamically imported chunk groups which should be loaded
+    /// together with the page.
+    preload_paths: Vec<String>,
+    /// Chunks of dynamically imported chunk groups which are likely needed in
+    /// the future.
+    prefetch_paths: Vec<String>,
     body: Option<String>,
+    options: DevHtmlOptions,
 }
 
 impl DevHtmlAssetContentVc {
-    pub fn new(chunk_paths: Vec<String>, body: Option<String>) -> Self {
-        DevHtmlAssetContent { chunk_paths, body }.cell()
+    pub fn new(
+        chunk_paths: Vec<String>,
+        inline_scripts: Vec<String>,
+        module_scripts: bool,
+        preload_paths: Vec<String>,
+        prefetch_paths: Vec<String>,
+        body: Option<String>,
+        options: DevHtmlOptions,
+    ) -> Self {
+        DevHtmlAssetContent {
+            chunk_paths,
+            inline_scripts,
+            module_scripts,
+            preload_paths,
+            prefetch_paths,
+            body,
+            options,
+        }
+        .cell()
     }
 }
 
-#[turbo_tasks::value_impl]
-impl DevHtmlAssetContentVc {
-    #[turbo_tasks::function]
-    async fn content(self) -> Result<AssetContentVc> {
-        let this = self.await?;
-
+impl DevHtmlAssetContent {
+    fn html(&self) -> Result<String> {
         let mut scripts = Vec::new();
         let mut stylesheets = Vec::new();
 
-        for relative_path in &*this.chunk_paths {
+        let options = &self.options;
+        let nonce = match &options.nonce {
+            Some(nonce) => format!(" nonce=\"{}\"", escape_attribute(nonce)),
+            None => String::new(),
+        };
+        let module_scripts = self.module_scripts || options.script_loading == ScriptLoading::Module;
+        let script_type = if module_scripts {
+            " type=\"module\""
+        } else {
+            ""
+        };
+        // `defer` has no effect on inline scripts, so the bootstrap scripts and
+        // the runtime still run before the chunks.
+        let script_loading = if !module_scripts && options.script_loading == ScriptLoading::Defer {
+            " defer"
+        } else {
+            ""
+        };
+        for code in &options.bootstrap_scripts {
+            scripts.push(format!(
+                "<script{}{}>{}</script>",
+                script_type,
+                nonce,
+                escape_script(code)
+            ));
+        }
+        for code in &*self.inline_scripts {
+            scripts.push(format!("<script{}{}>{}</script>", script_type, nonce, code));
+        }
+
+        for relative_path in &*self.chunk_paths {
             if relative_path.ends_with(".js") {
-                scripts.push(format!("<script src=\"{}\"></script>", relative_path));
+                scripts.push(format!(
+                    "<script{}{}{} src=\"{}\"></script>",
+                    script_type, script_loading, nonce, relative_path
+                ));
             } else if relative_path.ends_with(".css") {
                 stylesheets.push(format!(
-                    "<link data-turbopack rel=\"stylesheet\" href=\"{}\">",
-                    relative_path
+                    "<link data-turbopack rel=\"stylesheet\"{} href=\"{}\">",
+                    nonce, relative_path
                 ));
             } else {
                 return Err(anyhow!("chunk with unknown asset type: {}", relative_path));
             }
         }
 
-        let body = match &this.body {
+        let hints = self
+            .preload_paths
+            .iter()
+            .map(|path| (path, AsyncLoadingHint::Preload))
+            .chain(
+                self.prefetch_paths
+                    .iter()
+                    .map(|path| (path, AsyncLoadingHint::Prefetch)),
+            )
+            .filter_map(|(path, hint)| hint_tag(path, hint, module_scripts, &nonce));
+
+        let body = match &self.body {
             Some(body) => body.as_str(),
             None => "",
         };
 
-        let html = format!(
-            "<!DOCTYPE html>\n<html>\n<head>\n{}\n</head>\n<body>\n{}\n{}\n</body>\n</html>",
-            stylesheets.join("\n"),
+        let body_attributes: String = options
+            .body_attributes
+            .iter()
+            .map(|(name, value)| format!(" {}=\"{}\"", name, escape_attribute(value)))
+            .collect();
+
+        let mut head = options.head_tags.clone();
+        head.extend(stylesheets);
+        head.extend(hints);
+
+        Ok(format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n{}\n</head>\n<body{}>\n{}\n{}\n</body>\n</html>",
+            head.join("\n"),
+            body_attributes,
             body,
             scripts.join("\n"),
-        );
+        ))
+    }
+}
 
+#[turbo_tasks::value_impl]
+impl DevHtmlAssetContentVc {
+    #[turbo_tasks::function]
+    async fn content(self) -> Result<AssetContentVc> {
+        let html = self.await?.html()?;
         Ok(File::from(html).with_content_type(TEXT_HTML_UTF_8).into())
     }
 
@@ -193,7 +498,13 @@ impl VersionedContent for DevHtmlAssetContent {
         let to = to_version.await?;
         let from = from_version.await?;
 
-        if to.content.chunk_paths == from.content.chunk_paths {
+        if to.content.chunk_paths == from.content.chunk_paths
+            && to.content.inline_scripts == from.content.inline_scripts
+            && to.content.module_scripts == from.content.module_scripts
+            && to.content.preload_paths == from.content.preload_paths
+            && to.content.prefetch_paths == from.content.prefetch_paths
+            && to.content.options == from.content.options
+        {
             return Ok(Update::None.into());
         }
 
@@ -219,11 +530,132 @@ impl Version for DevHtmlAssetVersion {
         for relative_path in &*self.content.chunk_paths {
             hasher.write_ref(relative_path);
         }
+        for code in &*self.content.inline_scripts {
+            hasher.write_ref(code);
+        }
+        hasher.write_value(self.content.module_scripts);
+        for relative_path in &*self.content.preload_paths {
+            hasher.write_ref(relative_path);
+        }
+        for relative_path in &*self.content.prefetch_paths {
+            hasher.write_ref(relative_path);
+        }
         if let Some(body) = &self.content.body {
             hasher.write_ref(body);
         }
+        let options = &self.content.options;
+        for tag in &options.head_tags {
+            hasher.write_ref(tag);
+        }
+        for (name, value) in &options.body_attributes {
+            hasher.write_ref(name);
+            hasher.write_ref(value);
+        }
+        if let Some(nonce) = &options.nonce {
+            hasher.write_ref(nonce);
+        }
+        for code in &options.bootstrap_scripts {
+            hasher.write_ref(code);
+        }
+        hasher.write_value(options.script_loading);
         let hash = hasher.finish();
         let hex_hash = encode_hex(hash);
         Ok(StringVc::cell(hex_hash))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use turbopack_core::chunk::AsyncLoadingHint;
+
+    use super::{hint_tag, with_source_url, DevHtmlAssetContent, DevHtmlOptions, ScriptLoading};
+
+    fn content(options: DevHtmlOptions) -> DevHtmlAssetContent {
+        DevHtmlAssetContent {
+            chunk_paths: vec!["/chunks/a.css".to_string(), "/chunks/a.js".to_string()],
+            inline_scripts: vec!["runtime();".to_string()],
+            module_scripts: false,
+            preload_paths: vec![],
+            prefetch_paths: vec![],
+            body: None,
+            options,
+        }
+    }
+
+    #[test]
+    fn hint_tags() {
+        let nonce = " nonce=\"abc\"";
+        assert_eq!(
+            hint_tag("/chunks/a.js", AsyncLoadingHint::Preload, false, nonce).unwrap(),
+            "<link rel=\"preload\" as=\"script\" nonce=\"abc\" href=\"/chunks/a.js\">"
+        );
+        assert_eq!(
+            hint_tag("/chunks/a.js", AsyncLoadingHint::Preload, true, "").unwrap(),
+            "<link rel=\"modulepreload\" href=\"/chunks/a.js\">"
+        );
+        assert_eq!(
+            hint_tag("/chunks/a.css", AsyncLoadingHint::Preload, true, "").unwrap(),
+            "<link rel=\"preload\" as=\"style\" href=\"/chunks/a.css\">"
+        );
+        assert_eq!(
+            hint_tag("/chunks/a.js", AsyncLoadingHint::Prefetch, true, nonce).unwrap(),
+            "<link rel=\"prefetch\" href=\"/chunks/a.js\">"
+        );
+        assert_eq!(
+            hint_tag("/chunks/\"a\".js", AsyncLoadingHint::Prefetch, false, "").unwrap(),
+            "<link rel=\"prefetch\" href=\"/chunks/&quot;a&quot;.js\">"
+        );
+        assert!(hint_tag("/chunks/a.js", AsyncLoadingHint::None, false, "").is_none());
+        assert!(hint_tag("/chunks/a.wasm", AsyncLoadingHint::Preload, false, "").is_none());
+    }
+
+    #[test]
+    fn inline_scripts_have_absolute_source_urls() {
+        assert_eq!(
+            with_source_url(
+                "runtime();\n\n//# sourceMappingURL=runtime.js.map",
+                "/_chunks/runtime.js"
+            ),
+            "runtime();\n\n//# sourceURL=/_chunks/runtime.js\n//# \
+             sourceMappingURL=/_chunks/runtime.js.map"
+        );
+        assert_eq!(
+            with_source_url("runtime();", "/_chunks/runtime.js"),
+            "runtime();\n//# sourceURL=/_chunks/runtime.js"
+        );
+    }
+
+    #[test]
+    fn html_with_options() {
+        let html = content(DevHtmlOptions {
+            head_tags: vec!["<title>App</title>".to_string()],
+            body_attributes: vec![("class".to_string(), "a \"b\"".to_string())],
+            nonce: Some("abc".to_string()),
+            bootstrap_scripts: vec!["window.x = \"</script>\";".to_string()],
+            script_loading: ScriptLoading::Defer,
+        })
+        .html()
+        .unwrap();
+        assert_eq!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<title>App</title>\n<link data-turbopack \
+             rel=\"stylesheet\" nonce=\"abc\" href=\"/chunks/a.css\">\n</head>\n<body class=\"a \
+             &quot;b&quot;\">\n\n<script nonce=\"abc\">window.x = \
+             \"<\\/script>\";</script>\n<script nonce=\"abc\">runtime();</script>\n<script defer \
+             nonce=\"abc\" src=\"/chunks/a.js\"></script>\n</body>\n</html>"
+        );
+    }
+
+    #[test]
+    fn html_with_module_scripts() {
+        let html = content(DevHtmlOptions {
+            script_loading: ScriptLoading::Module,
+            ..Default::default()
+        })
+        .html()
+        .unwrap();
+        assert!(html.contains("<script type=\"module\">runtime();</script>"));
+        // Module scripts are deferred anyway.
+        assert!(html.contains("<script type=\"module\" src=\"/chunks/a.js\"></script>"));
+    }
+}
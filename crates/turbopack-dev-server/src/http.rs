@@ -1,18 +1,38 @@
+use std::{net::SocketAddr, sync::Mutex};
+
 use anyhow::Result;
-use futures::{StreamExt, TryStreamExt};
-use hyper::{header::HeaderName, Request, Response};
+use futures::TryStreamExt;
+use hyper::{
+    header::{
+        HeaderName, HeaderValue, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER, TRAILER,
+        TRANSFER_ENCODING,
+    },
+    HeaderMap, Method, Request, Response,
+};
+use indexmap::IndexMap;
 use mime_guess::mime;
 use turbo_tasks::TransientInstance;
-use turbo_tasks_fs::{FileContent, FileContentReadRef};
+use turbo_tasks_fs::{rope::Rope, FileContent, FileContentReadRef};
 use turbopack_cli_utils::issue::ConsoleUiVc;
 use turbopack_core::{asset::AssetContent, version::VersionedContent};
 
-use crate::source::{
-    request::SourceRequest,
-    resolve::{resolve_source_request, ResolveSourceRequestResult},
-    Body, Bytes, ContentSourceVc, HeaderListReadRef, ProxyResultReadRef,
+use crate::{
+    correlation::CorrelationId,
+    handle_issues,
+    html::{REQUEST_NONCE_HEADER, REQUEST_NONCE_PLACEHOLDER},
+    source::{
+        error::ContentSourceError,
+        request::SourceRequest,
+        resolve::{resolve_source_request, ResolveSourceRequestResult},
+        trace::SOURCE_TRACE_HEADER,
+        Body, Bytes, ContentSourceVc, HeaderListReadRef, ProxyResultReadRef,
+    },
 };
 
+/// The size to which small sections of response bodies are merged, so they're
+/// written to the socket in fewer, larger writes.
+const RESPONSE_CHUNK_SIZE: usize = 64 * 1024;
+
 #[turbo_tasks::value(serialization = "none")]
 enum GetFromSourceResult {
     Static {
@@ -55,17 +75,155 @@ async fn get_from_source(
     )
 }
 
+/// The number of routes of which a [LastGoodBuild] keeps the content. Chunks
+/// and every query of a route count as routes of their own.
+const LAST_GOOD_BUILD_CAPACITY: usize = 1024;
+
+/// The last successful static content of the most recently served routes, to
+/// keep serving it while the source has fatal issues, e.g. because of a syntax
+/// error in a file which doesn't affect the route.
+#[derive(Default)]
+pub struct LastGoodBuild {
+    /// In order of use, the least recently used route first.
+    results: Mutex<IndexMap<String, GetFromSourceResultReadRef>>,
+}
+
+impl LastGoodBuild {
+    fn store(&self, path_and_query: &str, result: &GetFromSourceResultReadRef) {
+        let is_good = match &**result {
+            GetFromSourceResult::Static {
+                content,
+                status_code,
+                ..
+            } => *status_code < 400 && matches!(&**content, FileContent::Content(_)),
+            _ => false,
+        };
+        let mut results = self.results.lock().unwrap();
+        if is_good {
+            results.shift_remove(path_and_query);
+            results.insert(path_and_query.to_string(), result.clone());
+            if results.len() > LAST_GOOD_BUILD_CAPACITY {
+                results.shift_remove_index(0);
+            }
+        } else if is_removed(result) {
+            results.shift_remove(path_and_query);
+        }
+    }
+
+    /// Responds with the last successful content of the route, when there is
+    /// one. The response has a [ServedLastGoodBuild] extension.
+    fn respond(&self, uri: &hyper::Uri) -> Result<Option<Response<hyper::Body>>> {
+        let key = path_and_query(uri);
+        let result = {
+            let mut results = self.results.lock().unwrap();
+            let Some(result) = results.shift_remove(&key) else {
+                return Ok(None);
+            };
+            // Moves the route to the end, as the most recently used one.
+            results.insert(key, result.clone());
+            result
+        };
+        let mut response = result_to_response(uri.path(), &result)?;
+        response.extensions_mut().insert(ServedLastGoodBuild);
+        Ok(Some(response))
+    }
+}
+
+/// Whether the route doesn't exist anymore, e.g. because its page was deleted.
+/// The content of removed routes is not served from a [LastGoodBuild].
+fn is_removed(result: &GetFromSourceResult) -> bool {
+    match result {
+        GetFromSourceResult::NotFound => true,
+        GetFromSourceResult::Static {
+            content,
+            status_code,
+            ..
+        } => *status_code == 404 || matches!(&**content, FileContent::NotFound),
+        _ => false,
+    }
+}
+
+/// Marks responses with the content of a [LastGoodBuild].
+#[derive(Clone, Copy, Debug)]
+pub struct ServedLastGoodBuild;
+
+fn path_and_query(uri: &hyper::Uri) -> String {
+    uri.path_and_query().map_or_else(
+        || uri.path().to_string(),
+        |path_and_query| path_and_query.to_string(),
+    )
+}
+
 /// Processes an HTTP request within a given content source and returns the
-/// response.
+/// response. Successful static content is stored in the `last_good_build`.
+/// Request bodies larger than `max_request_body_size` are rejected.
 pub async fn process_request_with_content_source(
     source: ContentSourceVc,
     request: Request<hyper::Body>,
     console_ui: ConsoleUiVc,
+    last_good_build: Option<&LastGoodBuild>,
+    max_request_body_size: usize,
 ) -> Result<Response<hyper::Body>> {
     let original_path = request.uri().path().to_string();
-    let request = http_request_to_source_request(request).await?;
+    let original_path_and_query = path_and_query(request.uri());
+    let request = http_request_to_source_request(request, max_request_body_size).await?;
+    let trace = request.trace.clone();
+    let result = get_from_source(source, TransientInstance::new(request), console_ui)
+        .strongly_consistent()
+        .await?;
+    if let Some(last_good_build) = last_good_build {
+        last_good_build.store(&original_path_and_query, &result);
+    }
+    let mut response = result_to_response(&original_path, &result)?;
+    if let Some(trace) = trace {
+        let trace = serde_json::to_string(&*trace.lock().unwrap())?;
+        response.headers_mut().insert(
+            SOURCE_TRACE_HEADER,
+            HeaderValue::from_bytes(trace.as_bytes())?,
+        );
+    }
+    Ok(response)
+}
+
+/// Processes an HTTP request while the source has fatal issues. The route is
+/// resolved first, and fatal issues of its own are returned as an error, so
+/// broken routes still respond with the error. Other routes are served from
+/// the `last_good_build`, or with their new content when they weren't served
+/// successfully before. Removed routes are not found, and are dropped from the
+/// `last_good_build`.
+pub async fn process_request_with_last_good_build(
+    source: ContentSourceVc,
+    request: Request<hyper::Body>,
+    console_ui: ConsoleUiVc,
+    last_good_build: &LastGoodBuild,
+    max_request_body_size: usize,
+) -> Result<Response<hyper::Body>> {
+    let uri = request.uri().clone();
+    let original_path = uri.path().to_string();
+    let request = http_request_to_source_request(request, max_request_body_size).await?;
     let result = get_from_source(source, TransientInstance::new(request), console_ui);
-    match &*result.strongly_consistent().await? {
+    handle_issues(
+        result,
+        &original_path,
+        "get content from source",
+        console_ui,
+    )
+    .await?;
+    let result = result.strongly_consistent().await?;
+    if !is_removed(&result) {
+        if let Some(response) = last_good_build.respond(&uri)? {
+            return Ok(response);
+        }
+    }
+    last_good_build.store(&path_and_query(&uri), &result);
+    result_to_response(&original_path, &result)
+}
+
+fn result_to_response(
+    original_path: &str,
+    result: &GetFromSourceResult,
+) -> Result<Response<hyper::Body>> {
+    match result {
         GetFromSourceResult::Static {
             content,
             status_code,
@@ -90,7 +248,7 @@ pub async fn process_request_with_content_source(
                     );
                 } else if let hyper::header::Entry::Vacant(entry) = header_map.entry("content-type")
                 {
-                    let guess = mime_guess::from_path(&original_path).first_or_octet_stream();
+                    let guess = mime_guess::from_path(original_path).first_or_octet_stream();
                     // If a text type, application/javascript, or application/json was
                     // guessed, use a utf-8 charset as  we most likely generated it as
                     // such.
@@ -107,14 +265,12 @@ pub async fn process_request_with_content_source(
                     )?);
                 }
 
-                let content = file.content();
-                header_map.insert(
-                    "Content-Length",
-                    hyper::header::HeaderValue::try_from(content.len().to_string())?,
-                );
+                let mut content = file.content().clone();
+                if let Some(with_nonce) = insert_request_nonce(&content, header_map)? {
+                    content = with_nonce;
+                }
 
-                let bytes = content.read();
-                return Ok(response.body(hyper::Body::wrap_stream(bytes))?);
+                return rope_response(response, &content, HeaderMap::new());
             }
         }
         GetFromSourceResult::HttpProxy(proxy_result) => {
@@ -128,7 +284,15 @@ pub async fn process_request_with_content_source(
                 );
             }
 
-            return Ok(response.body(hyper::Body::wrap_stream(proxy_result.body.read()))?);
+            let mut trailers = HeaderMap::new();
+            for [name, value] in proxy_result.trailers.array_chunks() {
+                trailers.append(
+                    HeaderName::from_bytes(name.as_bytes())?,
+                    hyper::header::HeaderValue::from_str(value)?,
+                );
+            }
+
+            return rope_response(response, &proxy_result.body, trailers);
         }
         _ => {}
     }
@@ -136,18 +300,457 @@ pub async fn process_request_with_content_source(
     Ok(Response::builder().status(404).body(hyper::Body::empty())?)
 }
 
-async fn http_request_to_source_request(request: Request<hyper::Body>) -> Result<SourceRequest> {
+/// Builds a response which streams the sections of the `body` rope, without
+/// copying large sections.
+///
+/// The Content-Length is set from the length of the rope, unless the response
+/// is chunked. That's the case when there are `trailers`, which are sent after
+/// the body and announced in the Trailer header. HTTP/1.1 clients may not
+/// receive them, as hyper only sends trailers over HTTP/2.
+pub fn rope_response(
+    mut response: hyper::http::response::Builder,
+    body: &Rope,
+    trailers: HeaderMap,
+) -> Result<Response<hyper::Body>> {
+    let headers = response.headers_mut().expect("headers must be defined");
+    let reader = body.read().with_chunk_size(RESPONSE_CHUNK_SIZE);
+
+    if trailers.is_empty() {
+        if !headers.contains_key(TRANSFER_ENCODING) {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        }
+        return Ok(response.body(hyper::Body::wrap_stream(reader))?);
+    }
+
+    headers.remove(CONTENT_LENGTH);
+    for name in trailers.keys() {
+        headers.append(TRAILER, HeaderValue::from(name.clone()));
+    }
+    let (mut sender, hyper_body) = hyper::Body::channel();
+    tokio::spawn(async move {
+        for bytes in reader {
+            if sender.send_data(bytes).await.is_err() {
+                // The client disconnected.
+                return;
+            }
+        }
+        let _ = sender.send_trailers(trailers).await;
+    });
+    Ok(response.body(hyper_body)?)
+}
+
+/// How many seconds browsers should wait before retrying a request which
+/// exceeded the request deadline.
+const STILL_COMPILING_RETRY_AFTER: u64 = 2;
+
+/// The response to a request which exceeded the request deadline while its
+/// route is still compiling. The page reloads itself, so browsers retry the
+/// request like other clients do because of the Retry-After header.
+pub fn still_compiling_response() -> Result<Response<hyper::Body>, hyper::http::Error> {
+    Response::builder()
+        .status(503)
+        .header(RETRY_AFTER, STILL_COMPILING_RETRY_AFTER)
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(hyper::Body::from(format!(
+            r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta http-equiv="refresh" content="{STILL_COMPILING_RETRY_AFTER}">
+    <title>Still compiling</title>
+  </head>
+  <body>
+    <p>This page is still compiling. It will reload in a moment.</p>
+  </body>
+</html>
+"#
+        )))
+}
+
+/// Replaces the [REQUEST_NONCE_PLACEHOLDER] in a page generated by the dev
+/// server and in its headers with a random nonce. Returns None when the
+/// response isn't marked with the [REQUEST_NONCE_HEADER].
+fn insert_request_nonce(content: &Rope, headers: &mut HeaderMap) -> Result<Option<Rope>> {
+    if headers.remove(REQUEST_NONCE_HEADER).is_none() {
+        return Ok(None);
+    }
+    let html = content.to_str()?;
+    if !html.contains(REQUEST_NONCE_PLACEHOLDER) {
+        return Ok(None);
+    }
+    let nonce = format!("{:032x}", rand::random::<u128>());
+    for value in headers.values_mut() {
+        let replaced = match value.to_str() {
+            Ok(value) if value.contains(REQUEST_NONCE_PLACEHOLDER) => {
+                value.replace(REQUEST_NONCE_PLACEHOLDER, &nonce)
+            }
+            _ => continue,
+        };
+        *value = HeaderValue::try_from(replaced)?;
+    }
+    Ok(Some(Rope::from(
+        html.replace(REQUEST_NONCE_PLACEHOLDER, &nonce),
+    )))
+}
+
+/// The response to a request which failed with `error`. Failures caused by the
+/// request, see [ContentSourceError], are responded to with a matching status.
+pub fn error_response(error: &anyhow::Error) -> Result<Response<hyper::Body>, hyper::http::Error> {
+    let status = ContentSourceError::find(error).map_or(500, |error| error.status_code());
+    Response::builder()
+        .status(status)
+        .body(hyper::Body::from(format!("{:?}", error)))
+}
+
+/// Returns whether a request is a navigation of a browser to a page, as
+/// opposed to e.g. a request of a chunk, an API call or an HMR connection.
+/// Only navigations can be answered with a "still compiling" page.
+pub fn is_navigation(request: &Request<hyper::Body>) -> bool {
+    let is_get = request.method() == Method::GET || request.method() == Method::HEAD;
+    let accepts_html = request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.contains("text/html"));
+    is_get && accepts_html && !hyper_tungstenite::is_upgrade_request(request)
+}
+
+/// Reads the whole body of a request, so that the time it takes to upload it
+/// isn't counted towards the request deadline.
+pub async fn buffer_request_body(
+    request: Request<hyper::Body>,
+    max_body_size: usize,
+) -> Result<Request<hyper::Body>> {
     let (parts, body) = request.into_parts();
+    let bytes = read_body(&parts.headers, body, max_body_size).await?;
+    Ok(Request::from_parts(
+        parts,
+        hyper::Body::from(bytes.concat()),
+    ))
+}
 
-    let bytes: Vec<_> = body
-        .map(|bytes| bytes.map(Bytes::from))
-        .try_collect::<Vec<_>>()
-        .await?;
+/// Reads a body, rejecting it when it's larger than `max_body_size`.
+async fn read_body(
+    headers: &HeaderMap,
+    mut body: hyper::Body,
+    max_body_size: usize,
+) -> Result<Vec<hyper::body::Bytes>> {
+    let too_large = || ContentSourceError::RequestBodyTooLarge {
+        limit: max_body_size,
+    };
+    // Reject bodies which announce their size up front before reading them.
+    let content_length = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if content_length.map_or(false, |length| length > max_body_size) {
+        return Err(too_large().into());
+    }
+
+    let mut bytes = Vec::new();
+    let mut size = 0;
+    while let Some(chunk) = body.try_next().await? {
+        size += chunk.len();
+        if size > max_body_size {
+            return Err(too_large().into());
+        }
+        bytes.push(chunk);
+    }
+    Ok(bytes)
+}
+
+async fn http_request_to_source_request(
+    request: Request<hyper::Body>,
+    max_body_size: usize,
+) -> Result<SourceRequest> {
+    let (parts, body) = request.into_parts();
+    let bytes = read_body(&parts.headers, body, max_body_size).await?;
 
     Ok(SourceRequest {
         method: parts.method.to_string(),
         uri: parts.uri,
         headers: parts.headers,
-        body: Body::new(bytes),
+        body: Body::new(bytes.into_iter().map(Bytes::from).collect()),
+        remote_addr: parts.extensions.get::<SocketAddr>().copied(),
+        correlation_id: parts
+            .extensions
+            .get::<CorrelationId>()
+            .map(|id| id.0.clone()),
+        trace: parts
+            .headers
+            .contains_key(SOURCE_TRACE_HEADER)
+            .then(Default::default),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::body::HttpBody;
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_fs::{rope::RopeBuilder, File};
+    use turbo_tasks_memory::MemoryBackend;
+
+    use super::*;
+    use crate::source::HeaderListVc;
+
+    fn request(method: Method, accept: Option<&str>, body: &'static str) -> Request<hyper::Body> {
+        let mut builder = Request::builder().method(method).uri("/about");
+        if let Some(accept) = accept {
+            builder = builder.header(ACCEPT, accept);
+        }
+        builder.body(hyper::Body::from(body)).unwrap()
+    }
+
+    #[test]
+    fn only_page_navigations_are_navigations() {
+        let html = Some("text/html,application/xhtml+xml,*/*;q=0.8");
+        assert!(is_navigation(&request(Method::GET, html, "")));
+        assert!(!is_navigation(&request(Method::POST, html, "")));
+        assert!(!is_navigation(&request(Method::GET, Some("*/*"), "")));
+        assert!(!is_navigation(&request(Method::GET, None, "")));
+    }
+
+    #[tokio::test]
+    async fn buffers_request_bodies_up_to_the_limit() {
+        let buffered = buffer_request_body(request(Method::GET, None, "body"), 4)
+            .await
+            .unwrap();
+        let body = hyper::body::to_bytes(buffered.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"body");
+
+        let error = buffer_request_body(request(Method::GET, None, "body"), 3)
+            .await
+            .unwrap_err();
+        assert_eq!(ContentSourceError::find(&error).unwrap().status_code(), 413);
+    }
+
+    #[tokio::test]
+    async fn rejects_announced_body_sizes_over_the_limit() {
+        // The announced size is checked before the body is read.
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/about")
+            .header(CONTENT_LENGTH, "1024")
+            .body(hyper::Body::empty())
+            .unwrap();
+        let error = buffer_request_body(request, 1023).await.unwrap_err();
+        assert_eq!(error_response(&error).unwrap().status(), 413);
+    }
+
+    #[tokio::test]
+    async fn rejects_streamed_bodies_over_the_limit() {
+        let (mut sender, body) = hyper::Body::channel();
+        tokio::spawn(async move {
+            for _ in 0..4 {
+                if sender.send_data("chunk".into()).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/about")
+            .body(body)
+            .unwrap();
+        let error = buffer_request_body(request, 16).await.unwrap_err();
+        assert_eq!(error_response(&error).unwrap().status(), 413);
+    }
+
+    #[test]
+    fn responds_to_other_errors_with_500() {
+        let error = anyhow::anyhow!("render failed");
+        assert_eq!(error_response(&error).unwrap().status(), 500);
+    }
+
+    fn html_headers(marked: bool) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        headers.insert(
+            "content-security-policy",
+            HeaderValue::from_static("script-src 'nonce-__TURBOPACK_REQUEST_NONCE__'"),
+        );
+        if marked {
+            headers.insert(REQUEST_NONCE_HEADER, HeaderValue::from_static("1"));
+        }
+        headers
+    }
+
+    #[test]
+    fn inserts_request_nonce_into_marked_pages() {
+        let content = Rope::from(format!(
+            "<script nonce=\"{REQUEST_NONCE_PLACEHOLDER}\"></script>"
+        ));
+        let mut headers = html_headers(true);
+        let html = insert_request_nonce(&content, &mut headers)
+            .unwrap()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .into_owned();
+        let nonce = html
+            .strip_prefix("<script nonce=\"")
+            .and_then(|html| html.strip_suffix("\"></script>"))
+            .unwrap();
+        assert_eq!(nonce.len(), 32);
+        assert_eq!(
+            headers["content-security-policy"],
+            format!("script-src 'nonce-{nonce}'")
+        );
+        assert!(!headers.contains_key(REQUEST_NONCE_HEADER));
+
+        // Every request gets a nonce of its own
+        let other = insert_request_nonce(&content, &mut html_headers(true))
+            .unwrap()
+            .unwrap();
+        assert_ne!(other.to_str().unwrap(), html);
+    }
+
+    #[test]
+    fn leaves_other_html_untouched() {
+        let content = Rope::from(format!("<p>{REQUEST_NONCE_PLACEHOLDER}</p>"));
+        let mut headers = html_headers(false);
+        assert!(insert_request_nonce(&content, &mut headers)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            headers["content-security-policy"],
+            "script-src 'nonce-__TURBOPACK_REQUEST_NONCE__'"
+        );
+    }
+
+    /// Static content with the status code, read like the dev server reads
+    /// the result of a source.
+    async fn static_result(
+        content: FileContent,
+        status_code: u16,
+    ) -> Result<GetFromSourceResultReadRef> {
+        GetFromSourceResult::Static {
+            content: content.cell().await?,
+            status_code,
+            headers: HeaderListVc::empty().await?,
+        }
+        .cell()
+        .await
+    }
+
+    #[test]
+    fn serves_last_good_build() {
+        crate::register();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async {
+                    let last_good_build = LastGoodBuild::default();
+                    let about = request(Method::GET, None, "");
+                    assert!(last_good_build.respond(about.uri())?.is_none());
+
+                    let good = static_result(File::from("good").into(), 200).await?;
+                    last_good_build.store("/about", &good);
+                    // Errors don't replace the last good content.
+                    let error = static_result(File::from("error").into(), 500).await?;
+                    last_good_build.store("/about", &error);
+
+                    let response = last_good_build.respond(about.uri())?.unwrap();
+                    assert_eq!(response.status(), 200);
+                    assert!(response.extensions().get::<ServedLastGoodBuild>().is_some());
+                    let body = hyper::body::to_bytes(response.into_body()).await?;
+                    assert_eq!(&body[..], b"good");
+
+                    // The query is part of the route.
+                    let with_query = Request::get("/about?page=2")
+                        .body(hyper::Body::empty())
+                        .unwrap();
+                    assert!(last_good_build.respond(with_query.uri())?.is_none());
+
+                    // Removed routes are forgotten.
+                    let missing = static_result(FileContent::NotFound, 200).await?;
+                    last_good_build.store("/about", &missing);
+                    assert!(last_good_build.respond(about.uri())?.is_none());
+                    last_good_build.store("/about", &good);
+                    let not_found = GetFromSourceResult::NotFound.cell().await?;
+                    last_good_build.store("/about", &not_found);
+                    assert!(last_good_build.respond(about.uri())?.is_none());
+                    Ok(())
+                })
+                .await
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn keeps_most_recently_used_routes() {
+        crate::register();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async {
+                    let last_good_build = LastGoodBuild::default();
+                    let good = static_result(File::from("good").into(), 200).await?;
+                    for i in 0..LAST_GOOD_BUILD_CAPACITY {
+                        last_good_build.store(&format!("/chunk-{i}.js"), &good);
+                    }
+                    let first = "/chunk-0.js".parse::<hyper::Uri>()?;
+                    assert!(last_good_build.respond(&first)?.is_some());
+
+                    // The least recently used route makes room for the new one.
+                    last_good_build.store("/about", &good);
+                    assert_eq!(
+                        last_good_build.results.lock().unwrap().len(),
+                        LAST_GOOD_BUILD_CAPACITY
+                    );
+                    assert!(last_good_build.respond(&first)?.is_some());
+                    let second = "/chunk-1.js".parse::<hyper::Uri>()?;
+                    assert!(last_good_build.respond(&second)?.is_none());
+                    Ok(())
+                })
+                .await
+            })
+            .unwrap();
+    }
+
+    fn sectioned_rope() -> Rope {
+        let mut builder = RopeBuilder::from("head ");
+        builder += &Rope::from("shared ");
+        builder += "tail";
+        builder.build()
+    }
+
+    #[tokio::test]
+    async fn streams_rope_with_content_length() {
+        let response =
+            rope_response(Response::builder(), &sectioned_rope(), HeaderMap::new()).unwrap();
+        assert_eq!(response.headers()[CONTENT_LENGTH], "16");
+        assert!(!response.headers().contains_key(TRAILER));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"head shared tail");
+
+        // Chunked responses don't have a length.
+        let chunked = Response::builder().header(TRANSFER_ENCODING, "chunked");
+        let response = rope_response(chunked, &sectioned_rope(), HeaderMap::new()).unwrap();
+        assert!(!response.headers().contains_key(CONTENT_LENGTH));
+    }
+
+    #[tokio::test]
+    async fn sends_trailers_after_the_body() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("x-render-status", HeaderValue::from_static("500"));
+        let response = Response::builder().header(CONTENT_LENGTH, "16");
+        let response = rope_response(response, &sectioned_rope(), trailers).unwrap();
+        assert!(!response.headers().contains_key(CONTENT_LENGTH));
+        assert_eq!(response.headers()[TRAILER], "x-render-status");
+
+        let mut body = response.into_body();
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(data, b"head shared tail");
+        let trailers = body.trailers().await.unwrap().unwrap();
+        assert_eq!(trailers["x-render-status"], "500");
+    }
+}
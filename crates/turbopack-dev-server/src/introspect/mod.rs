@@ -1,17 +1,25 @@
 use std::{collections::HashSet, fmt::Display};
 
 use anyhow::Result;
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt};
+use serde::Deserialize;
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value};
 use turbo_tasks_fs::{json::parse_json_with_source_context, File, FileContent};
 use turbopack_core::{
     asset::AssetContent,
-    introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc},
+    introspect::{
+        reasons::introspectable_reasons,
+        search::{
+            resolve_address, search_introspectable, AddressStep, IntrospectableAddress,
+            IntrospectableMatch, IntrospectableMatchesVc, IntrospectableQuery,
+        },
+        Introspectable, IntrospectableChildrenVc, IntrospectableVc,
+    },
 };
 use turbopack_ecmascript::utils::FormatIter;
 
 use crate::source::{
-    ContentSource, ContentSourceContentVc, ContentSourceData, ContentSourceResultVc,
-    ContentSourceVc,
+    router::RouterContentSourceVc, ContentSource, ContentSourceContentVc, ContentSourceData,
+    ContentSourceDataVary, ContentSourceResult, ContentSourceResultVc, ContentSourceVc, NeededData,
 };
 
 #[turbo_tasks::value(shared)]
@@ -71,23 +79,247 @@ impl<T: Display> Display for HtmlStringEscaped<T> {
     }
 }
 
+#[derive(Deserialize)]
+struct ReasonsQuery {
+    target: String,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    #[serde(rename = "type")]
+    ty: Option<String>,
+    title: Option<String>,
+    path: Option<String>,
+}
+
+fn json_result(json: &serde_json::Value) -> Result<ContentSourceResultVc> {
+    Ok(ContentSourceResultVc::exact(
+        ContentSourceContentVc::static_content(
+            AssetContent::File(
+                FileContent::Content(
+                    File::from(serde_json::to_string_pretty(json)?)
+                        .with_content_type(mime::APPLICATION_JSON),
+                )
+                .cell(),
+            )
+            .cell()
+            .into(),
+        )
+        .into(),
+    ))
+}
+
+/// The url of the page of an address, relative to the introspection source.
+fn address_url(address: &IntrospectableAddress) -> Result<String> {
+    Ok(format!(
+        "at/{}",
+        urlencoding::encode(&serde_json::to_string(address)?)
+    ))
+}
+
+impl IntrospectionSourceVc {
+    async fn introspectable(self, path: &str) -> Result<IntrospectableVc> {
+        Ok(if path.is_empty() {
+            let roots = &self.await?.roots;
+            if roots.len() == 1 {
+                *roots.iter().next().unwrap()
+            } else {
+                self.as_introspectable()
+            }
+        } else {
+            parse_json_with_source_context(path)?
+        })
+    }
+
+    /// Responds to `reasons/<introspectable>?target=<title>` with JSON that
+    /// explains why the target is reachable from the introspectable, e.g. why
+    /// a module is in a chunk.
+    async fn reasons(self, path: &str, data: &ContentSourceData) -> Result<ContentSourceResultVc> {
+        let Some(raw_query) = &data.raw_query else {
+            return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self.into(),
+                path: format!("reasons/{path}"),
+                vary: ContentSourceDataVary {
+                    raw_query: true,
+                    ..Default::default()
+                },
+            })));
+        };
+        let Ok(query) = serde_qs::from_str::<ReasonsQuery>(raw_query) else {
+            return Ok(ContentSourceResultVc::not_found());
+        };
+        let introspectable = self.introspectable(path).await?;
+        let reasons = introspectable_reasons(introspectable, &query.target).await?;
+        json_result(&serde_json::json!({
+            "chain": reasons.chain,
+            "issuers": reasons.issuers,
+        }))
+    }
+
+    /// Responds to `search?type=<type>&title=<substring>&path=<served path>`
+    /// with JSON that lists the matching introspectables with their stable
+    /// addresses, e.g. to find the content source which serves `/api/foo`.
+    /// Every parameter is optional, and all given parameters need to match.
+    async fn search(self, data: &ContentSourceData) -> Result<ContentSourceResultVc> {
+        let Some(raw_query) = &data.raw_query else {
+            return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self.into(),
+                path: "search".to_string(),
+                vary: ContentSourceDataVary {
+                    raw_query: true,
+                    ..Default::default()
+                },
+            })));
+        };
+        let Ok(query) = serde_qs::from_str::<SearchQuery>(raw_query) else {
+            return Ok(ContentSourceResultVc::not_found());
+        };
+        let root = self.introspectable("").await?;
+        let introspectable_query = IntrospectableQuery {
+            ty: query.ty,
+            title: query.title,
+        };
+        let matches = if let Some(path) = &query.path {
+            let mut matches = Vec::new();
+            for m in introspectables_serving(root, path).await?.iter() {
+                let Some(introspectable) = resolve_address(root, &m.address).await? else {
+                    continue;
+                };
+                if introspectable_query.matches(introspectable).await? {
+                    matches.push(m.clone());
+                }
+            }
+            matches
+        } else {
+            search_introspectable(root, Value::new(introspectable_query))
+                .await?
+                .clone_value()
+        };
+        let matches = matches
+            .iter()
+            .map(|m| {
+                Ok(serde_json::json!({
+                    "type": m.ty,
+                    "title": m.title,
+                    "address": m.address,
+                    "url": address_url(&m.address)?,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        json_result(&serde_json::json!({ "matches": matches }))
+    }
+}
+
+/// Finds the content sources which serve `path`, e.g. `/api/foo`, by
+/// following the sources that respond to it from `root`, like a request
+/// would. Only the innermost sources are returned, e.g. the source of an API
+/// route instead of the sources that combine it with other routes.
+#[turbo_tasks::function]
+async fn introspectables_serving(
+    root: IntrospectableVc,
+    path: &str,
+) -> Result<IntrospectableMatchesVc> {
+    let path = path.trim_start_matches('/');
+    let root = root.resolve().await?;
+    let mut matches = Vec::new();
+    // The introspection source only groups several roots, it doesn't serve them.
+    let grouping = IntrospectionSourceVc::resolve_from(root).await?.is_some();
+    if !grouping && !serves(root, path).await? {
+        return Ok(IntrospectableMatchesVc::cell(matches));
+    }
+    let mut visited = HashSet::new();
+    let mut stack = vec![(root, path.to_string(), Vec::new())];
+    while let Some((introspectable, path, address)) = stack.pop() {
+        if !visited.insert(introspectable) {
+            continue;
+        }
+        let children = introspectable.children().await?;
+        // A router only passes a request to the first route that matches,
+        // without the prefix of the route, or to its fallback.
+        let candidates = if RouterContentSourceVc::resolve_from(introspectable)
+            .await?
+            .is_some()
+        {
+            let mut route = None;
+            let mut fallback = None;
+            for &(name, child) in children.iter() {
+                let name = name.await?;
+                if name.is_empty() {
+                    fallback.get_or_insert((name, child, path.clone()));
+                } else if let Some(rest) = path.strip_prefix(name.as_str()) {
+                    route.get_or_insert((name, child, rest.to_string()));
+                }
+            }
+            route.or(fallback).into_iter().collect::<Vec<_>>()
+        } else {
+            let mut candidates = Vec::new();
+            for &(name, child) in children.iter() {
+                candidates.push((name.await?, child, path.clone()));
+            }
+            candidates
+        };
+        let mut serving_children = Vec::new();
+        for (name, child, child_path) in candidates {
+            if serves(child, &child_path).await? {
+                let mut address = address.clone();
+                address.push(AddressStep {
+                    name: name.clone_value(),
+                    title: child.title().await?.clone_value(),
+                });
+                serving_children.push((child.resolve().await?, child_path, address));
+            }
+        }
+        if serving_children.is_empty() && !(grouping && introspectable == root) {
+            matches.push(IntrospectableMatch {
+                ty: introspectable.ty().await?.clone_value(),
+                title: introspectable.title().await?.clone_value(),
+                address,
+            });
+        }
+        stack.extend(serving_children.into_iter().rev());
+    }
+    Ok(IntrospectableMatchesVc::cell(matches))
+}
+
+/// Whether the introspectable is a content source which responds to `path`,
+/// or may respond to it depending on the request.
+async fn serves(introspectable: IntrospectableVc, path: &str) -> Result<bool> {
+    let Some(source) = ContentSourceVc::resolve_from(introspectable).await? else {
+        return Ok(false);
+    };
+    Ok(!matches!(
+        *source.get(path, Value::new(Default::default())).await?,
+        ContentSourceResult::NotFound
+    ))
+}
+
 #[turbo_tasks::value_impl]
 impl ContentSource for IntrospectionSource {
     #[turbo_tasks::function]
     async fn get(
         self_vc: IntrospectionSourceVc,
         path: &str,
-        _data: turbo_tasks::Value<ContentSourceData>,
+        data: Value<ContentSourceData>,
     ) -> Result<ContentSourceResultVc> {
-        let introspectable = if path.is_empty() {
-            let roots = &self_vc.await?.roots;
-            if roots.len() == 1 {
-                *roots.iter().next().unwrap()
-            } else {
-                self_vc.as_introspectable()
-            }
+        if let Some(path) = path.strip_prefix("reasons/") {
+            return self_vc.reasons(path, &data).await;
+        }
+        if path == "search" {
+            return self_vc.search(&data).await;
+        }
+        // Pages of stable addresses link to the children relative to the
+        // parent directory.
+        let (introspectable, link_prefix) = if let Some(address) = path.strip_prefix("at/") {
+            let root = self_vc.introspectable("").await?;
+            let Ok(address) = serde_json::from_str::<IntrospectableAddress>(address) else {
+                return Ok(ContentSourceResultVc::not_found());
+            };
+            let Some(introspectable) = resolve_address(root, &address).await? else {
+                return Ok(ContentSourceResultVc::not_found());
+            };
+            (introspectable, "../")
         } else {
-            parse_json_with_source_context(path)?
+            (self_vc.introspectable(path).await?, "./")
         };
         let ty = introspectable.ty().await?;
         let title = introspectable.title().await?;
@@ -102,7 +334,8 @@ impl ContentSource for IntrospectionSource {
                 let title = child.title().await?;
                 let path = serde_json::to_string(&child)?;
                 Ok(format!(
-                    "<li>{name} <!-- {title} --><a href=\"./{path}\">[{ty}] {title}</a></li>",
+                    "<li>{name} <!-- {title} --><a href=\"{link_prefix}{path}\">[{ty}] \
+                     {title}</a></li>",
                     name = HtmlEscaped(name),
                     title = HtmlEscaped(title),
                     path = HtmlStringEscaped(urlencoding::encode(&path)),
@@ -2,6 +2,7 @@
 #![feature(trait_alias)]
 #![feature(array_chunks)]
 
+pub mod correlation;
 pub mod html;
 mod http;
 pub mod introspect;
@@ -18,20 +19,30 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use hyper::{
-    server::{conn::AddrIncoming, Builder},
+    server::{
+        conn::{AddrIncoming, AddrStream},
+        Builder,
+    },
     service::{make_service_fn, service_fn},
     Request, Response, Server,
 };
+use tracing::Instrument;
 use turbo_tasks::{
     run_once, trace::TraceRawVcs, util::FormatDuration, RawVc, TransientValue, TurboTasksApi,
 };
 use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc};
+use turbopack_core::chunk::dev::normalize_base_path;
 
 use self::{
+    correlation::{CorrelationId, CORRELATION_ID_HEADER},
+    http::{LastGoodBuild, ServedLastGoodBuild},
     source::{ContentSourceResultVc, ContentSourceVc},
     update::UpdateServer,
 };
 
+/// The default of [DevServerBuilder::max_request_body_size].
+const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 100 * 1024 * 1024;
+
 pub trait SourceProvider: Send + Clone + 'static {
     /// must call a turbo-tasks function internally
     fn get_source(&self) -> ContentSourceVc;
@@ -56,6 +67,11 @@ pub struct DevServerBuilder {
     pub addr: SocketAddr,
     #[turbo_tasks(trace_ignore)]
     server: Builder<AddrIncoming>,
+    base_path: String,
+    serve_last_good_build: bool,
+    #[turbo_tasks(trace_ignore)]
+    request_deadline: Option<Duration>,
+    max_request_body_size: usize,
 }
 
 #[derive(TraceRawVcs)]
@@ -97,31 +113,131 @@ impl DevServer {
             .context("not able to get bound address")?;
 
         let server = Server::from_tcp(listener).context("Not able to start server")?;
-        Ok(DevServerBuilder { addr, server })
+        Ok(DevServerBuilder {
+            addr,
+            server,
+            base_path: String::new(),
+            serve_last_good_build: false,
+            request_deadline: None,
+            max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+        })
+    }
+}
+
+/// Strips the base path from the path of a request. Returns `None` when the
+/// path is outside of the base path.
+fn strip_base_path<'a>(base_path: &str, path: &'a str) -> Option<&'a str> {
+    if base_path.is_empty() {
+        return Some(path);
+    }
+    match path.strip_prefix(base_path)? {
+        "" => Some("/"),
+        path if path.starts_with('/') => Some(path),
+        _ => None,
     }
 }
 
 impl DevServerBuilder {
+    /// Serves everything under a subpath, e.g. `/app/`. The base path is
+    /// stripped from requests before they are passed to the content source,
+    /// and requests outside of it are not found. Chunking contexts and HTML
+    /// assets need the same base path to generate the URLs of chunks.
+    pub fn base_path(mut self, base_path: &str) -> Self {
+        self.base_path = normalize_base_path(base_path);
+        self
+    }
+
+    /// Keeps serving the last successful content of routes while the source
+    /// has fatal issues, e.g. because a file has a syntax error. Routes which
+    /// have fatal issues of their own still respond with the error, and routes
+    /// which weren't served before respond with their new content.
+    pub fn serve_last_good_build(mut self, serve_last_good_build: bool) -> Self {
+        self.serve_last_good_build = serve_last_good_build;
+        self
+    }
+
+    /// Responds with a "still compiling" page and `503 Service Unavailable`
+    /// to page navigations which take longer than the `deadline`, e.g. the
+    /// first compilation of a heavy route. The compilation continues in the
+    /// background, so retrying the request picks up where it left off. Other
+    /// requests, e.g. of chunks or APIs, are not limited.
+    pub fn request_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.request_deadline = deadline;
+        self
+    }
+
+    /// Responds with `413 Payload Too Large` to requests whose body exceeds
+    /// `size` bytes, instead of buffering it. Defaults to 100 MB.
+    pub fn max_request_body_size(mut self, size: usize) -> Self {
+        self.max_request_body_size = size;
+        self
+    }
+
     pub fn serve(
         self,
         turbo_tasks: Arc<dyn TurboTasksApi>,
         source_provider: impl SourceProvider + Clone + Send + Sync,
         console_ui: Arc<ConsoleUi>,
     ) -> DevServer {
-        let make_svc = make_service_fn(move |_| {
+        let base_path = self.base_path;
+        let request_deadline = self.request_deadline;
+        let max_request_body_size = self.max_request_body_size;
+        let last_good_build = self
+            .serve_last_good_build
+            .then(|| Arc::new(LastGoodBuild::default()));
+        let make_svc = make_service_fn(move |conn: &AddrStream| {
+            let base_path = base_path.clone();
+            let last_good_build = last_good_build.clone();
             let tt = turbo_tasks.clone();
             let source_provider = source_provider.clone();
             let console_ui = console_ui.clone();
+            let remote_addr = conn.remote_addr();
             async move {
-                let handler = move |request: Request<hyper::Body>| {
+                let handler = move |mut request: Request<hyper::Body>| {
+                    // Content sources can request the address of the client via
+                    // `ContentSourceDataVary::remote_ip`.
+                    request.extensions_mut().insert(remote_addr);
+                    let correlation_id = CorrelationId::for_request(&request);
+                    request.extensions_mut().insert(correlation_id.clone());
+                    let span = tracing::info_span!(
+                        "request",
+                        correlation_id = %correlation_id,
+                        method = %request.method(),
+                        path = request.uri().path(),
+                    );
                     let console_ui = console_ui.clone();
                     let start = Instant::now();
+                    let request_path = request.uri().path().to_string();
                     let tt = tt.clone();
                     let source_provider = source_provider.clone();
-                    let future = async move {
+                    let log_correlation_id = correlation_id.clone();
+                    let base_path = base_path.clone();
+                    let last_good_build = last_good_build.clone();
+                    // Only browsers navigating to a page understand the "still
+                    // compiling" page.
+                    let deadline = request_deadline.filter(|_| http::is_navigation(&request));
+                    let handle = move |mut request: Request<hyper::Body>| async move {
                         run_once(tt.clone(), async move {
                             let console_ui = (*console_ui).clone().cell();
 
+                            let Some(path) = strip_base_path(&base_path, request.uri().path())
+                                .map(|path| path.to_string())
+                            else {
+                                println!("[404] {} (outside of base path)", request.uri().path());
+                                return Ok(Response::builder()
+                                    .status(404)
+                                    .body(hyper::Body::empty())?);
+                            };
+                            if !base_path.is_empty() {
+                                let path_and_query = match request.uri().query() {
+                                    Some(query) => format!("{path}?{query}"),
+                                    None => path,
+                                };
+                                let mut parts = request.uri().clone().into_parts();
+                                parts.path_and_query = Some(path_and_query.parse()?);
+                                *request.uri_mut() = hyper::Uri::from_parts(parts)?;
+                            }
+
                             if hyper_tungstenite::is_upgrade_request(&request) {
                                 let uri = request.uri();
                                 let path = uri.path();
@@ -157,15 +273,49 @@ impl DevServerBuilder {
 
                             let uri = request.uri();
                             let path = uri.path().to_string();
+                            let correlation_id = log_correlation_id;
                             let source = source_provider.get_source();
-                            handle_issues(source, &path, "get source", console_ui).await?;
-                            let resolved_source = source.resolve_strongly_consistent().await?;
-                            let response = http::process_request_with_content_source(
-                                resolved_source,
-                                request,
-                                console_ui,
-                            )
-                            .await?;
+                            let source_issues =
+                                handle_issues(source, &path, "get source", console_ui).await;
+                            let mut response = match (source_issues, &last_good_build) {
+                                (Ok(()), _) => {
+                                    let resolved_source =
+                                        source.resolve_strongly_consistent().await?;
+                                    http::process_request_with_content_source(
+                                        resolved_source,
+                                        request,
+                                        console_ui,
+                                        last_good_build.as_deref(),
+                                        max_request_body_size,
+                                    )
+                                    .await?
+                                }
+                                (Err(err), None) => return Err(err),
+                                (Err(_), Some(last_good_build)) => {
+                                    let resolved_source =
+                                        source.resolve_strongly_consistent().await?;
+                                    let response = http::process_request_with_last_good_build(
+                                        resolved_source,
+                                        request,
+                                        console_ui,
+                                        last_good_build,
+                                        max_request_body_size,
+                                    )
+                                    .await?;
+                                    if response.extensions().get::<ServedLastGoodBuild>().is_some()
+                                    {
+                                        println!(
+                                            "[stale] {path} (serving the last good build) \
+                                             [{correlation_id}]"
+                                        );
+                                    }
+                                    response
+                                }
+                            };
+                            response.headers_mut().insert(
+                                CORRELATION_ID_HEADER,
+                                hyper::header::HeaderValue::try_from(&correlation_id.0)?,
+                            );
                             let status = response.status().as_u16();
                             let is_error = response.status().is_client_error()
                                 || response.status().is_server_error();
@@ -175,7 +325,7 @@ impl DevServerBuilder {
                                     && elapsed > Duration::from_secs(1))
                             {
                                 println!(
-                                    "[{status}] {path} ({duration})",
+                                    "[{status}] {path} ({duration}) [{correlation_id}]",
                                     duration = FormatDuration(elapsed)
                                 );
                             }
@@ -184,17 +334,44 @@ impl DevServerBuilder {
                         .await
                     };
                     async move {
-                        match future.await {
+                        let result = match deadline {
+                            Some(deadline) => {
+                                // The deadline starts after the body is uploaded, so that
+                                // it only covers compiling.
+                                match http::buffer_request_body(request, max_request_body_size)
+                                    .await
+                                {
+                                    Ok(request) => {
+                                        let future = handle(request).instrument(span);
+                                        match tokio::time::timeout(deadline, future).await {
+                                            Ok(result) => result,
+                                            Err(_) => {
+                                                println!(
+                                                    "[503] {request_path} (still compiling after \
+                                                     {}) [{correlation_id}]",
+                                                    FormatDuration(deadline)
+                                                );
+                                                return http::still_compiling_response();
+                                            }
+                                        }
+                                    }
+                                    Err(err) => Err(err),
+                                }
+                            }
+                            None => handle(request).instrument(span).await,
+                        };
+                        match result {
                             Ok(r) => Ok::<_, hyper::http::Error>(r),
                             Err(e) => {
+                                let response = http::error_response(&e)?;
                                 println!(
-                                    "[500] error: {:?} ({})",
+                                    "[{}] error: {:?} ({}) [{}]",
+                                    response.status().as_u16(),
                                     e,
-                                    FormatDuration(start.elapsed())
+                                    FormatDuration(start.elapsed()),
+                                    correlation_id
                                 );
-                                Ok(Response::builder()
-                                    .status(500)
-                                    .body(hyper::Body::from(format!("{:?}", e,)))?)
+                                Ok(response)
                             }
                         }
                     }
@@ -222,3 +399,18 @@ pub fn register() {
     turbopack_ecmascript::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::strip_base_path;
+
+    #[test]
+    fn strips_base_path() {
+        assert_eq!(strip_base_path("", "/index.js"), Some("/index.js"));
+        assert_eq!(strip_base_path("/app", "/app/index.js"), Some("/index.js"));
+        assert_eq!(strip_base_path("/app", "/app"), Some("/"));
+        assert_eq!(strip_base_path("/app", "/app/"), Some("/"));
+        assert_eq!(strip_base_path("/app", "/application"), None);
+        assert_eq!(strip_base_path("/app", "/index.js"), None);
+    }
+}
@@ -14,8 +14,9 @@ use turbopack_core::{
 
 use super::{
     ContentSource, ContentSourceContentVc, ContentSourceData, ContentSourceResultVc,
-    ContentSourceVc,
+    ContentSourceVc, HeaderListVc,
 };
+use crate::html::{DevHtmlAssetVc, REQUEST_NONCE_HEADER};
 
 #[turbo_tasks::value(transparent)]
 struct AssetsMap(HashMap<String, AssetVc>);
@@ -113,6 +114,16 @@ impl AssetGraphContentSourceVc {
                 }
             }
         }
+        if let Some(expanded) = &this.expanded {
+            // Assets which are no longer part of the graph, e.g. of a removed
+            // entry, are forgotten, so they can be released and are expanded
+            // lazily again when they come back.
+            expanded.update_conditionally(|expanded| {
+                let len = expanded.len();
+                expanded.retain(|asset| assets_set.contains(asset));
+                expanded.len() != len
+            });
+        }
         for (p_vc, asset) in assets {
             // For clippy -- This explicit deref is necessary
             let p = &*p_vc.await?;
@@ -149,6 +160,19 @@ impl ContentSource for AssetGraphContentSource {
                     expanded.update_conditionally(|expanded| expanded.insert(*asset));
                 }
             }
+            if let Some(html) = DevHtmlAssetVc::resolve_from(asset).await? {
+                if *html.uses_request_nonce().await? {
+                    let headers = vec![(REQUEST_NONCE_HEADER.to_string(), "1".to_string())];
+                    return Ok(ContentSourceResultVc::exact(
+                        ContentSourceContentVc::static_with_headers(
+                            asset.versioned_content(),
+                            200,
+                            HeaderListVc::cell(headers),
+                        )
+                        .into(),
+                    ));
+                }
+            }
             return Ok(ContentSourceResultVc::exact(
                 ContentSourceContentVc::static_content(asset.versioned_content()).into(),
             ));
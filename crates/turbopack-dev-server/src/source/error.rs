@@ -0,0 +1,193 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+use turbo_tasks::{primitives::StringVc, util::FormatBytes};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// The classes of failures of content sources which are caused by the request
+/// rather than by the code of the app. They are responded to with a matching
+/// HTTP status, instead of a generic `500 Internal Server Error`, and reported
+/// as issues, see [ContentSourceError::report].
+#[derive(Debug)]
+pub enum ContentSourceError {
+    /// The path doesn't match the params of the route which was asked for its
+    /// content.
+    NonMatchingPath { path: String },
+    /// The data about the request which the content source declared it needs
+    /// via [super::GetContentSourceContent::vary] wasn't provided.
+    MissingRequestData { path: String },
+    /// The body of the request exceeds the maximum request body size.
+    RequestBodyTooLarge { limit: usize },
+    /// Rendering the content failed.
+    RenderFailed { path: String, source: anyhow::Error },
+}
+
+impl ContentSourceError {
+    /// The HTTP status to respond with.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ContentSourceError::NonMatchingPath { .. } => 400,
+            ContentSourceError::RequestBodyTooLarge { .. } => 413,
+            ContentSourceError::MissingRequestData { .. }
+            | ContentSourceError::RenderFailed { .. } => 500,
+        }
+    }
+
+    /// How severe the error is when it's reported as an issue. Errors caused by
+    /// the client are only warnings.
+    pub fn severity(&self) -> IssueSeverity {
+        match self {
+            ContentSourceError::NonMatchingPath { .. }
+            | ContentSourceError::RequestBodyTooLarge { .. } => IssueSeverity::Warning,
+            ContentSourceError::MissingRequestData { .. } => IssueSeverity::Bug,
+            ContentSourceError::RenderFailed { .. } => IssueSeverity::Error,
+        }
+    }
+
+    /// Emits an issue about the error for the content at `context`, and
+    /// converts it into an error which is responded to with its status.
+    pub fn report(self, context: FileSystemPathVc) -> anyhow::Error {
+        let description = match &self {
+            ContentSourceError::RenderFailed { source, .. } => format!("{self}: {source:#}"),
+            _ => self.to_string(),
+        };
+        ContentSourceIssue {
+            context,
+            severity: self.severity().into(),
+            description: StringVc::cell(description),
+            status_code: self.status_code(),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+        self.into()
+    }
+
+    /// Finds the content source error which caused `error`. Errors pass
+    /// through the tasks which read the content, so it's usually not the
+    /// outermost error.
+    pub fn find(error: &anyhow::Error) -> Option<&ContentSourceError> {
+        error
+            .chain()
+            .find_map(|error| error.downcast_ref::<ContentSourceError>())
+    }
+}
+
+impl Display for ContentSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentSourceError::NonMatchingPath { path } => {
+                write!(f, "the path {path} doesn't match the route")
+            }
+            ContentSourceError::MissingRequestData { path } => {
+                write!(
+                    f,
+                    "the request to {path} is missing data the content source needs"
+                )
+            }
+            ContentSourceError::RequestBodyTooLarge { limit } => {
+                write!(
+                    f,
+                    "the request body exceeds the maximum size of {}",
+                    FormatBytes(*limit)
+                )
+            }
+            ContentSourceError::RenderFailed { path, .. } => {
+                write!(f, "rendering {path} failed")
+            }
+        }
+    }
+}
+
+impl Error for ContentSourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ContentSourceError::RenderFailed { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+/// An issue about a request which a content source failed to respond to, see
+/// [ContentSourceError::report].
+#[turbo_tasks::value(shared)]
+pub struct ContentSourceIssue {
+    pub context: FileSystemPathVc,
+    pub severity: IssueSeverityVc,
+    pub description: StringVc,
+    pub status_code: u16,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ContentSourceIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Responding to a request failed".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("request".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.description
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        StringVc::cell(format!("Responded with status {}", self.status_code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use turbo_tasks::util::SharedError;
+
+    use super::*;
+
+    #[test]
+    fn finds_error_through_tasks() {
+        let error = anyhow::Error::new(ContentSourceError::NonMatchingPath {
+            path: "about".to_string(),
+        })
+        .context("Execution of get failed");
+        // Reading the output of a failed task wraps its error.
+        let error = anyhow::Error::new(SharedError::new(error)).context("reading content failed");
+
+        let found = ContentSourceError::find(&error).unwrap();
+        assert_eq!(found.status_code(), 400);
+        assert!(ContentSourceError::find(&anyhow!("unrelated")).is_none());
+    }
+
+    #[test]
+    fn client_errors_are_warnings() {
+        let error = ContentSourceError::NonMatchingPath {
+            path: "about".to_string(),
+        };
+        assert_eq!(error.severity(), IssueSeverity::Warning);
+        let error = ContentSourceError::RequestBodyTooLarge { limit: 1024 };
+        assert_eq!(error.severity(), IssueSeverity::Warning);
+        let error = ContentSourceError::RenderFailed {
+            path: "about".to_string(),
+            source: anyhow!("boom"),
+        };
+        assert_eq!(error.status_code(), 500);
+        assert_eq!(error.severity(), IssueSeverity::Error);
+    }
+}
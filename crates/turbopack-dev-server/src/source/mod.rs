@@ -1,15 +1,18 @@
 pub mod asset_graph;
 pub mod combined;
 pub mod conditional;
+pub mod error;
 pub mod headers;
 pub mod lazy_instantiated;
 pub mod query;
 pub mod request;
-pub(crate) mod resolve;
+pub mod resolve;
 pub mod router;
 pub mod source_maps;
 pub mod specificity;
+pub mod split;
 pub mod static_assets;
+pub mod trace;
 
 use std::{collections::BTreeSet, sync::Arc};
 
@@ -30,6 +33,10 @@ pub struct ProxyResult {
     pub headers: Vec<String>,
     /// The body to return.
     pub body: Rope,
+    /// Trailers, which are sent after the body, arranged as contiguous (name,
+    /// value) pairs. E.g. the status of a streamed render which fails after
+    /// its headers were sent.
+    pub trailers: Vec<String>,
 }
 
 /// The return value of a content source when getting a path. A specificity is
@@ -225,6 +232,10 @@ pub struct ContentSourceData {
     pub body: Option<BodyVc>,
     /// See [ContentSourceDataVary::cache_buster].
     pub cache_buster: u64,
+    /// The IP address of the client, if requested and known.
+    pub remote_ip: Option<String>,
+    /// See [ContentSourceDataVary::correlation_id].
+    pub correlation_id: Option<String>,
 }
 
 /// A request body.
@@ -367,6 +378,11 @@ pub struct ContentSourceDataVary {
     /// This value will be different on every request, which ensures the
     /// content is never cached.
     pub cache_buster: bool,
+    pub remote_ip: bool,
+    /// When true, the correlation id of the request is added to the
+    /// [ContentSourceData], so that logs of the content can be attributed to
+    /// the request. Like `cache_buster`, it's different on every request.
+    pub correlation_id: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -383,12 +399,16 @@ impl ContentSourceDataVary {
             raw_headers,
             body,
             cache_buster,
+            remote_ip,
+            correlation_id,
             placeholder_for_future_extensions: _,
         } = self;
         *method = *method || other.method;
         *url = *url || other.url;
         *body = *body || other.body;
         *cache_buster = *cache_buster || other.cache_buster;
+        *remote_ip = *remote_ip || other.remote_ip;
+        *correlation_id = *correlation_id || other.correlation_id;
         *raw_query = *raw_query || other.raw_query;
         *raw_headers = *raw_headers || other.raw_headers;
         ContentSourceDataFilter::extend_options(query, &other.query);
@@ -408,6 +428,8 @@ impl ContentSourceDataVary {
             raw_headers,
             body,
             cache_buster,
+            remote_ip,
+            correlation_id,
             placeholder_for_future_extensions: _,
         } = self;
         if other.method && !method {
@@ -428,6 +450,12 @@ impl ContentSourceDataVary {
         if other.cache_buster && !cache_buster {
             return false;
         }
+        if other.remote_ip && !remote_ip {
+            return false;
+        }
+        if other.correlation_id && !correlation_id {
+            return false;
+        }
         if !ContentSourceDataFilter::fulfills(query, &other.query) {
             return false;
         }
@@ -499,6 +527,22 @@ pub struct Rewrite {
     /// not_ need to be the original content source. Having [None] source will
     /// restart the lookup process from the original ContentSource.
     pub source: Option<ContentSourceVc>,
+
+    /// Changes to the request, which are visible in the [ContentSourceData]
+    /// of the rest of the lookup.
+    pub overrides: Option<RequestOverridesVc>,
+}
+
+/// Changes that a [Rewrite] makes to the request in addition to its path and
+/// query, e.g. to pass information to the [ContentSource] it delegates to.
+#[derive(Debug, Default)]
+#[turbo_tasks::value(shared)]
+pub struct RequestOverrides {
+    /// Headers that replace the request headers of the same name.
+    pub headers: Vec<(String, String)>,
+    /// Query params that replace the params of the same name in the path and
+    /// query of the [Rewrite].
+    pub params: Vec<(String, String)>,
 }
 
 #[turbo_tasks::value_impl]
@@ -511,6 +555,25 @@ impl RewriteVc {
         Rewrite {
             path_and_query: path_query,
             source: Some(source),
+            overrides: None,
+        }
+        .cell()
+    }
+
+    /// Creates a new [RewriteVc] which starts lookup from the provided
+    /// [ContentSource] with a changed request. This allows sources to delegate
+    /// to other sources without another HTTP request.
+    #[turbo_tasks::function]
+    pub fn new_with_overrides(
+        path_query: String,
+        source: ContentSourceVc,
+        overrides: RequestOverridesVc,
+    ) -> RewriteVc {
+        debug_assert!(path_query.starts_with('/'));
+        Rewrite {
+            path_and_query: path_query,
+            source: Some(source),
+            overrides: Some(overrides),
         }
         .cell()
     }
@@ -522,6 +585,7 @@ impl RewriteVc {
         Rewrite {
             path_and_query: path_query,
             source: None,
+            overrides: None,
         }
         .cell()
     }
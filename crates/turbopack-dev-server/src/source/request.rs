@@ -1,6 +1,8 @@
+use std::net::SocketAddr;
+
 use hyper::{HeaderMap, Uri};
 
-use super::Body;
+use super::{trace::SourceTraceCollector, Body};
 
 /// A request to a content source.
 #[derive(Debug, Clone)]
@@ -13,4 +15,11 @@ pub struct SourceRequest {
     pub headers: HeaderMap<hyper::header::HeaderValue>,
     /// The body to send.
     pub body: Body,
+    /// The address of the client that sent the request, if known.
+    pub remote_addr: Option<SocketAddr>,
+    /// The id that logs caused by the request are tagged with.
+    pub correlation_id: Option<String>,
+    /// Collects the trace of resolving the request in the content sources,
+    /// when it's traced.
+    pub trace: Option<SourceTraceCollector>,
 }
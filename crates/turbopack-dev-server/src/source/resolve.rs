@@ -4,14 +4,18 @@ use std::{
 };
 
 use anyhow::{bail, Result};
-use hyper::Uri;
+use hyper::{
+    header::{HeaderName, HeaderValue as HttpHeaderValue},
+    Uri,
+};
 use turbo_tasks::{TransientInstance, Value};
 use turbopack_cli_utils::issue::ConsoleUiVc;
 
 use super::{
     headers::{HeaderValue, Headers},
-    query::Query,
+    query::{Query, QueryValue},
     request::SourceRequest,
+    trace::{record_rewrite, record_step},
     ContentSourceContent, ContentSourceDataVary, ContentSourceResult, ContentSourceVc,
     ProxyResultVc, StaticContentVc,
 };
@@ -44,6 +48,12 @@ pub async fn resolve_source_request(
     let original_path = request.uri.path().to_string();
     let mut current_asset_path = urlencoding::decode(&original_path[1..])?.into_owned();
     let mut request_overwrites = (*request).clone();
+    // The vary of `data`, for the trace.
+    let mut data_vary = ContentSourceDataVary::default();
+    if let Some(trace) = &request.trace {
+        // The resolution may run again, e.g. when a source was invalidated.
+        trace.lock().unwrap().clear();
+    }
     loop {
         let result = current_source.get(&current_asset_path, Value::new(data));
         handle_issues(
@@ -54,12 +64,24 @@ pub async fn resolve_source_request(
         )
         .await?;
 
-        match &*result.strongly_consistent().await? {
+        let result = result.strongly_consistent().await?;
+        if let Some(trace) = &request.trace {
+            record_step(
+                trace,
+                current_source,
+                &current_asset_path,
+                &data_vary,
+                &result,
+            )
+            .await?;
+        }
+        match &*result {
             ContentSourceResult::NotFound => break Ok(ResolveSourceRequestResult::NotFound.cell()),
             ContentSourceResult::NeedData(needed) => {
                 current_source = needed.source.resolve().await?;
                 current_asset_path = needed.path.clone();
                 data = request_to_data(&request_overwrites, &needed.vary).await?;
+                data_vary = needed.vary.clone();
             }
             ContentSourceResult::Result { get_content, .. } => {
                 let content_vary = get_content.vary().await?;
@@ -70,10 +92,28 @@ pub async fn resolve_source_request(
                         let rewrite = rewrite.await?;
                         // If a source isn't specified, we restart at the top.
                         let new_source = rewrite.source.unwrap_or(source);
-                        let new_uri = Uri::try_from(&rewrite.path_and_query)?;
+                        let mut new_uri = Uri::try_from(&rewrite.path_and_query)?;
+                        let overrides = match rewrite.overrides {
+                            Some(overrides) => Some(overrides.await?),
+                            None => None,
+                        };
+                        if let Some(overrides) = &overrides {
+                            new_uri = override_params(new_uri, &overrides.params)?;
+                        }
                         if new_source == current_source && new_uri == request_overwrites.uri {
                             bail!("rewrite loop detected: {}", new_uri);
                         }
+                        if let Some(trace) = &request.trace {
+                            record_rewrite(trace, &new_uri.to_string());
+                        }
+                        if let Some(overrides) = &overrides {
+                            for (name, value) in overrides.headers.iter() {
+                                request_overwrites.headers.insert(
+                                    HeaderName::try_from(name)?,
+                                    HttpHeaderValue::try_from(value)?,
+                                );
+                            }
+                        }
                         let new_asset_path =
                             urlencoding::decode(&new_uri.path()[1..])?.into_owned();
 
@@ -81,6 +121,7 @@ pub async fn resolve_source_request(
                         request_overwrites.uri = new_uri;
                         current_asset_path = new_asset_path;
                         data = ContentSourceData::default();
+                        data_vary = ContentSourceDataVary::default();
                     } // _ => ,
                     ContentSourceContent::NotFound => {
                         break Ok(ResolveSourceRequestResult::NotFound.cell())
@@ -97,7 +138,26 @@ pub async fn resolve_source_request(
     }
 }
 
-static CACHE_BUSTER: AtomicU64 = AtomicU64::new(0);
+/// Replaces the query params of `uri` that have the same name as one of
+/// `params`, and adds the others.
+fn override_params(uri: Uri, params: &[(String, String)]) -> Result<Uri> {
+    if params.is_empty() {
+        return Ok(uri);
+    }
+    let mut query: Query = serde_qs::from_str(uri.query().unwrap_or(""))?;
+    for (name, value) in params {
+        query.insert(name.clone(), QueryValue::String(value.clone()));
+    }
+    Ok(Uri::try_from(format!(
+        "{}?{}",
+        uri.path(),
+        serde_qs::to_string(&query)?
+    ))?)
+}
+
+// Starts at 1, so that sources can tell a requested `cache_buster` from a
+// missing one.
+static CACHE_BUSTER: AtomicU64 = AtomicU64::new(1);
 
 async fn request_to_data(
     request: &SourceRequest,
@@ -163,5 +223,43 @@ async fn request_to_data(
     if vary.cache_buster {
         data.cache_buster = CACHE_BUSTER.fetch_add(1, Ordering::SeqCst);
     }
+    if vary.remote_ip {
+        data.remote_ip = request.remote_addr.map(|addr| addr.ip().to_string());
+    }
+    if vary.correlation_id {
+        data.correlation_id = request.correlation_id.clone();
+    }
     Ok(data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_query_params() {
+        let uri = Uri::from_static("/page?lang=en&page=1");
+        let params = vec![
+            ("lang".to_string(), "fr".to_string()),
+            ("preview".to_string(), "true".to_string()),
+        ];
+        let uri = override_params(uri, &params).unwrap();
+        assert_eq!(uri.path(), "/page");
+        let query: Query = serde_qs::from_str(uri.query().unwrap()).unwrap();
+        assert_eq!(
+            query.get("lang"),
+            Some(&QueryValue::String("fr".to_string()))
+        );
+        assert_eq!(
+            query.get("page"),
+            Some(&QueryValue::String("1".to_string()))
+        );
+        assert_eq!(
+            query.get("preview"),
+            Some(&QueryValue::String("true".to_string()))
+        );
+
+        let uri = Uri::from_static("/page?lang=en");
+        assert_eq!(override_params(uri.clone(), &[]).unwrap(), uri);
+    }
+}
@@ -1,4 +1,7 @@
+use std::net::IpAddr;
+
 use anyhow::Result;
+use serde_json::Value as JsonValue;
 use turbo_tasks::{primitives::StringVc, Value};
 use turbo_tasks_fs::File;
 use turbopack_core::{
@@ -24,17 +27,58 @@ use crate::source::GetContentSourceContent;
 ///
 /// Optionally, if an `?id={ID}` query param is present, we will instead fetch
 /// an individual section from the asset via [GenerateSourceMap::by_section].
+///
+/// Which clients can see the source maps, and whether they include the
+/// sources, is configured with [SourceMapServingOptions].
 #[turbo_tasks::value(shared)]
 pub struct SourceMapContentSource {
     /// A wrapped content source from which we will fetch assets.
     asset_source: ContentSourceVc,
+    options: SourceMapServingOptionsVc,
+}
+
+/// Configures how source maps are served, e.g. for shared dev environments
+/// where the sources shouldn't be shipped to every client.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash)]
+pub struct SourceMapServingOptions {
+    /// Whether source maps are served at all.
+    pub enabled: bool,
+    /// Only serves source maps to clients connecting from the loopback
+    /// interface.
+    pub localhost_only: bool,
+    /// Whether the source maps include `sourcesContent`. Without it, stack
+    /// traces can still be symbolicated, but the sources aren't shipped.
+    pub sources_content: bool,
+}
+
+impl Default for SourceMapServingOptions {
+    fn default() -> Self {
+        SourceMapServingOptions {
+            enabled: true,
+            localhost_only: false,
+            sources_content: true,
+        }
+    }
 }
 
 #[turbo_tasks::value_impl]
 impl SourceMapContentSourceVc {
     #[turbo_tasks::function]
     pub fn new(asset_source: ContentSourceVc) -> SourceMapContentSourceVc {
-        SourceMapContentSource { asset_source }.cell()
+        Self::new_with_options(asset_source, SourceMapServingOptions::default().cell())
+    }
+
+    #[turbo_tasks::function]
+    pub fn new_with_options(
+        asset_source: ContentSourceVc,
+        options: SourceMapServingOptionsVc,
+    ) -> SourceMapContentSourceVc {
+        SourceMapContentSource {
+            asset_source,
+            options,
+        }
+        .cell()
     }
 }
 
@@ -51,6 +95,12 @@ impl ContentSource for SourceMapContentSource {
             _ => return Ok(ContentSourceResultVc::not_found()),
         };
 
+        let this = self_vc.await?;
+        let options = *this.options.await?;
+        if !options.enabled {
+            return Ok(ContentSourceResultVc::not_found());
+        }
+
         let query = match &data.query {
             Some(q) => q,
             None => {
@@ -59,18 +109,22 @@ impl ContentSource for SourceMapContentSource {
                     path: path.to_string(),
                     vary: ContentSourceDataVary {
                         query: Some(ContentSourceDataFilter::Subset(["id".to_string()].into())),
+                        remote_ip: options.localhost_only,
                         ..Default::default()
                     },
                 })))
             }
         };
 
+        if options.localhost_only && !is_loopback(data.remote_ip.as_deref()) {
+            return Ok(ContentSourceResultVc::not_found());
+        }
+
         let id = match query.get("id") {
             Some(QueryValue::String(s)) => Some(s),
             _ => None,
         };
 
-        let this = self_vc.await?;
         let result = this.asset_source.get(pathname, Default::default()).await?;
         let content = match &*result {
             ContentSourceResult::Result { get_content, .. } => {
@@ -98,14 +152,48 @@ impl ContentSource for SourceMapContentSource {
             gen.generate_source_map()
         };
         let content = sm.to_rope().await?;
+        let content = if options.sources_content {
+            File::from(content)
+        } else {
+            let mut map: JsonValue = serde_json::from_reader(content.read())?;
+            remove_sources_content(&mut map);
+            File::from(serde_json::to_string(&map)?)
+        };
 
-        let asset = AssetContentVc::from(File::from(content));
+        let asset = AssetContentVc::from(content);
         Ok(ContentSourceResultVc::exact(
             ContentSourceContentVc::static_content(asset.into()).into(),
         ))
     }
 }
 
+fn is_loopback(ip: Option<&str>) -> bool {
+    ip.and_then(|ip| ip.parse::<IpAddr>().ok())
+        .map_or(false, |ip| match ip {
+            IpAddr::V4(ip) => ip.is_loopback(),
+            // IPv4 clients of a dual stack socket are mapped to IPv6.
+            IpAddr::V6(ip) => {
+                ip.is_loopback() || ip.to_ipv4_mapped().map_or(false, |ip| ip.is_loopback())
+            }
+        })
+}
+
+/// Removes `sourcesContent` from a source map, including the maps of all
+/// sections of a sectioned source map.
+fn remove_sources_content(map: &mut JsonValue) {
+    let Some(map) = map.as_object_mut() else {
+        return;
+    };
+    map.remove("sourcesContent");
+    if let Some(JsonValue::Array(sections)) = map.get_mut("sections") {
+        for section in sections.iter_mut() {
+            if let Some(map) = section.get_mut("map") {
+                remove_sources_content(map);
+            }
+        }
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl Introspectable for SourceMapContentSource {
     #[turbo_tasks::function]
@@ -118,3 +206,51 @@ impl Introspectable for SourceMapContentSource {
         StringVc::cell("serves chunk and chunk item source maps".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn detects_loopback_clients() {
+        assert!(is_loopback(Some("127.0.0.1")));
+        assert!(is_loopback(Some("::1")));
+        assert!(is_loopback(Some("::ffff:127.0.0.1")));
+        assert!(!is_loopback(Some("192.168.0.2")));
+        assert!(!is_loopback(Some("::ffff:192.168.0.2")));
+        assert!(!is_loopback(Some("localhost")));
+        assert!(!is_loopback(None));
+    }
+
+    #[test]
+    fn removes_sources_content_of_sections() {
+        let mut map = json!({
+            "version": 3,
+            "sections": [
+                {
+                    "offset": { "line": 0, "column": 0 },
+                    "map": {
+                        "version": 3,
+                        "sources": ["a.js"],
+                        "sourcesContent": ["a"],
+                        "mappings": "AAAA",
+                    },
+                },
+            ],
+        });
+        remove_sources_content(&mut map);
+        assert!(map["sections"][0]["map"].get("sourcesContent").is_none());
+        assert_eq!(map["sections"][0]["map"]["sources"], json!(["a.js"]));
+
+        let mut map = json!({
+            "version": 3,
+            "sources": ["a.js"],
+            "sourcesContent": ["a"],
+            "mappings": "AAAA",
+        });
+        remove_sources_content(&mut map);
+        assert!(map.get("sourcesContent").is_none());
+    }
+}
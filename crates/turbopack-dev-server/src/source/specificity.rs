@@ -39,6 +39,36 @@ impl Ord for SpecificityElement {
     }
 }
 
+/// A dimension of specificity, which embedders define in addition to the
+/// elements of the URL, e.g. whether a match is specific to a locale or
+/// generic, or whether it comes from a file or from a convention.
+///
+/// Dimensions only decide between matches whose URLs are equally specific.
+/// Dimensions of higher priority are compared first, dimensions of the same
+/// priority are compared by name.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize, TraceRawVcs)]
+pub struct SpecificityDimension {
+    pub name: String,
+    pub priority: u32,
+}
+
+impl SpecificityDimension {
+    pub fn new(name: impl Into<String>, priority: u32) -> Self {
+        SpecificityDimension {
+            name: name.into(),
+            priority,
+        }
+    }
+
+    /// The order in which dimensions are compared, first dimension first.
+    fn compare_order(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+
 /// The specificity of a URL. Implements Ord to allow to compare two
 /// specificities. A match with higher specificity should be preferred.
 #[turbo_tasks::value(shared)]
@@ -46,6 +76,11 @@ impl Ord for SpecificityElement {
 pub struct Specificity {
     #[turbo_tasks(trace_ignore)]
     elements: Vec<SpecificityElement>,
+    /// How much less specific the match is in each dimension, in the order
+    /// the dimensions are compared. Dimensions with a penalty of 0 are left
+    /// out, so equal specificities have equal dimensions.
+    #[turbo_tasks(trace_ignore)]
+    dimensions: Vec<(SpecificityDimension, u32)>,
 }
 
 impl PartialOrd for Specificity {
@@ -67,7 +102,29 @@ impl Ord for Specificity {
                 // Having no [SpecificityElement] is more specific than having some.
                 (Some(_), None) => return Ordering::Less,
                 (None, Some(_)) => return Ordering::Greater,
+                (None, None) => break,
+            }
+        }
+        // A dimension which is missing on one side has a penalty of 0 there.
+        let mut a = self.dimensions.iter().peekable();
+        let mut b = other.dimensions.iter().peekable();
+        loop {
+            let (a_penalty, b_penalty) = match (a.peek(), b.peek()) {
+                (Some((a_dimension, _)), Some((b_dimension, _))) => {
+                    match a_dimension.compare_order(b_dimension) {
+                        Ordering::Less => (a.next().unwrap().1, 0),
+                        Ordering::Greater => (0, b.next().unwrap().1),
+                        Ordering::Equal => (a.next().unwrap().1, b.next().unwrap().1),
+                    }
+                }
+                (Some(_), None) => (a.next().unwrap().1, 0),
+                (None, Some(_)) => (0, b.next().unwrap().1),
                 (None, None) => return Ordering::Equal,
+            };
+            // A lower penalty is more specific.
+            match b_penalty.cmp(&a_penalty) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
             }
         }
     }
@@ -91,10 +148,37 @@ impl Specificity {
         new
     }
 
+    /// Makes the match less specific by `penalty` in `dimension`, e.g. a
+    /// generic match has a penalty in a `locale` dimension, so a match
+    /// specific to the locale of the request wins. Penalties in the same
+    /// dimension add up.
+    pub fn add_dimension(&mut self, dimension: SpecificityDimension, penalty: u32) {
+        if penalty == 0 {
+            return;
+        }
+        match self
+            .dimensions
+            .binary_search_by(|(existing, _)| existing.compare_order(&dimension))
+        {
+            Ok(index) => {
+                let existing = &mut self.dimensions[index].1;
+                *existing = existing.saturating_add(penalty);
+            }
+            Err(index) => self.dimensions.insert(index, (dimension, penalty)),
+        }
+    }
+
+    /// Clones the specificity and adds a penalty in a dimension to it.
+    pub fn with_dimension_penalty(&self, dimension: SpecificityDimension, penalty: u32) -> Self {
+        let mut new = self.clone();
+        new.add_dimension(dimension, penalty);
+        new
+    }
+
     /// Returns true if the specificity is exact. This means it's the maximum
     /// specificity and any loop could early exit.
     pub fn is_exact(&self) -> bool {
-        self.elements.is_empty()
+        self.elements.is_empty() && self.dimensions.is_empty()
     }
 
     /// The lowest possible specificity. Used when no match is found.
@@ -104,6 +188,7 @@ impl Specificity {
                 position: 0,
                 ty: SpecificityElementType::NotFound,
             }],
+            dimensions: Vec::new(),
         }
     }
 }
@@ -127,6 +212,9 @@ impl Display for Specificity {
         if pos == 0 {
             write!(f, "/static")?;
         }
+        for (dimension, penalty) in &self.dimensions {
+            write!(f, " {}-{}", dimension.name, penalty)?;
+        }
         Ok(())
     }
 }
@@ -142,10 +230,7 @@ impl SpecificityVc {
     /// The highest possible specificity. Used for exact matches.
     #[turbo_tasks::function]
     pub fn exact() -> Self {
-        Specificity {
-            elements: Vec::new(),
-        }
-        .cell()
+        Specificity::default().cell()
     }
 
     /// The specificity with an additional catch all at the specified position.
@@ -176,4 +261,75 @@ impl SpecificityVc {
             .with(position, SpecificityElementType::Fallback)
             .cell())
     }
+
+    /// The specificity with an additional penalty in an embedder defined
+    /// dimension, see [Specificity::add_dimension].
+    #[turbo_tasks::function]
+    pub async fn with_dimension_penalty(
+        self,
+        name: &str,
+        priority: u32,
+        penalty: u32,
+    ) -> Result<Self> {
+        Ok(self
+            .await?
+            .with_dimension_penalty(SpecificityDimension::new(name, priority), penalty)
+            .cell())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{Specificity, SpecificityDimension, SpecificityElementType};
+
+    fn locale() -> SpecificityDimension {
+        SpecificityDimension::new("locale", 1)
+    }
+
+    fn source() -> SpecificityDimension {
+        SpecificityDimension::new("source", 0)
+    }
+
+    #[test]
+    fn url_elements_decide_before_dimensions() {
+        let dynamic = Specificity::new().with(0, SpecificityElementType::DynamicSegment);
+        let generic = Specificity::new().with_dimension_penalty(locale(), 1);
+        assert_eq!(generic.cmp(&dynamic), Ordering::Greater);
+        assert!(!generic.is_exact());
+    }
+
+    #[test]
+    fn lower_penalty_is_more_specific() {
+        let localized = Specificity::new();
+        let generic = Specificity::new().with_dimension_penalty(locale(), 1);
+        let fallback = Specificity::new().with_dimension_penalty(locale(), 2);
+        assert_eq!(localized.cmp(&generic), Ordering::Greater);
+        assert_eq!(generic.cmp(&fallback), Ordering::Greater);
+        assert_eq!(
+            generic.cmp(&Specificity::new().with_dimension_penalty(locale(), 1)),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn dimensions_of_higher_priority_are_compared_first() {
+        let generic_file = Specificity::new().with_dimension_penalty(locale(), 1);
+        let localized_convention = Specificity::new().with_dimension_penalty(source(), 3);
+        assert_eq!(localized_convention.cmp(&generic_file), Ordering::Greater);
+        assert_eq!(generic_file.cmp(&localized_convention), Ordering::Less);
+    }
+
+    #[test]
+    fn shows_dimensions() {
+        let specificity = Specificity::new()
+            .with(1, SpecificityElementType::DynamicSegment)
+            .with_dimension_penalty(source(), 1)
+            .with_dimension_penalty(locale(), 2);
+        assert_eq!(
+            specificity.to_string(),
+            "/static/[dynamic] locale-2 source-1"
+        );
+    }
 }
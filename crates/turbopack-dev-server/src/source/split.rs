@@ -0,0 +1,283 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbopack_core::introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc};
+
+use super::{
+    headers::HeaderValue, ContentSource, ContentSourceData, ContentSourceDataFilter,
+    ContentSourceDataVary, ContentSourceResultVc, ContentSourceVc, ContentSourcesVc, NeededData,
+};
+
+/// Decides which requests a [SplitContentSource] sends to its alternate
+/// source.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Clone, Debug, Hash, PartialOrd, Ord)]
+pub enum SplitRule {
+    /// Sends this percentage of requests to the alternate source.
+    Percentage(u8),
+    /// Sends requests with this header to the alternate source. When a value
+    /// is given, the header must have this value.
+    Header { name: String, value: Option<String> },
+    /// Sends requests with this cookie to the alternate source. When a value
+    /// is given, the cookie must have this value.
+    Cookie { name: String, value: Option<String> },
+}
+
+impl SplitRule {
+    fn vary(&self) -> ContentSourceDataVary {
+        match self {
+            SplitRule::Percentage(_) => ContentSourceDataVary {
+                cache_buster: true,
+                ..Default::default()
+            },
+            SplitRule::Header { name, .. } => ContentSourceDataVary {
+                headers: Some(ContentSourceDataFilter::Subset(BTreeSet::from([
+                    name.to_lowercase()
+                ]))),
+                ..Default::default()
+            },
+            SplitRule::Cookie { .. } => ContentSourceDataVary {
+                headers: Some(ContentSourceDataFilter::Subset(BTreeSet::from([
+                    "cookie".to_string()
+                ]))),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Returns whether the request belongs to the alternate source, or `None`
+    /// when the data needed to decide is missing.
+    fn use_alternate(&self, data: &ContentSourceData) -> Option<bool> {
+        match self {
+            SplitRule::Percentage(percentage) => {
+                if data.cache_buster == 0 {
+                    return None;
+                }
+                // Spreads the requests evenly, e.g. for 25 every fourth request matches.
+                let percentage = u64::from(*percentage);
+                Some(data.cache_buster * percentage % 100 < percentage)
+            }
+            SplitRule::Header { name, value } => {
+                let header = data.headers.as_ref()?.get(&name.to_lowercase());
+                Some(match (header, value) {
+                    (None, _) => false,
+                    (Some(_), None) => true,
+                    (Some(header), Some(value)) => header_values(header).any(|v| v == value),
+                })
+            }
+            SplitRule::Cookie { name, value } => {
+                let headers = data.headers.as_ref()?;
+                Some(
+                    headers
+                        .get("cookie")
+                        .into_iter()
+                        .flat_map(header_values)
+                        .flat_map(|cookies| cookies.split(';'))
+                        .filter_map(|cookie| cookie.trim().split_once('='))
+                        .any(|(cookie_name, cookie_value)| {
+                            cookie_name == name
+                                && value.as_deref().map_or(true, |value| value == cookie_value)
+                        }),
+                )
+            }
+        }
+    }
+}
+
+fn header_values(value: &HeaderValue) -> impl Iterator<Item = &str> {
+    let values: Vec<&str> = match value {
+        HeaderValue::SingleString(s) => vec![s.as_str()],
+        HeaderValue::MultiStrings(v) => v.iter().map(|s| s.as_str()).collect(),
+        HeaderValue::SingleBytes(_) | HeaderValue::MultiBytes(_) => vec![],
+    };
+    values.into_iter()
+}
+
+/// Routes requests to one of two sources, e.g. to compare a rendered source
+/// against a static export of the same routes during development. The
+/// requests that match the [SplitRule] are served by the `alternate` source,
+/// all others by the `control` source.
+#[turbo_tasks::value(shared)]
+pub struct SplitContentSource {
+    pub control: ContentSourceVc,
+    pub alternate: ContentSourceVc,
+    pub rule: SplitRule,
+}
+
+#[turbo_tasks::value_impl]
+impl SplitContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        control: ContentSourceVc,
+        alternate: ContentSourceVc,
+        rule: Value<SplitRule>,
+    ) -> SplitContentSourceVc {
+        SplitContentSource {
+            control,
+            alternate,
+            rule: rule.into_value(),
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for SplitContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: SplitContentSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let this = self_vc.await?;
+        let Some(use_alternate) = this.rule.use_alternate(&data) else {
+            return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self_vc.into(),
+                path: path.to_string(),
+                vary: this.rule.vary(),
+            })));
+        };
+        let source = if use_alternate {
+            this.alternate
+        } else {
+            this.control
+        };
+        // The chosen source starts from scratch and requests the data it
+        // needs itself.
+        Ok(source.get(path, Value::new(ContentSourceData::default())))
+    }
+
+    #[turbo_tasks::function]
+    fn get_children(&self) -> ContentSourcesVc {
+        ContentSourcesVc::cell(vec![self.control, self.alternate])
+    }
+}
+
+#[turbo_tasks::function]
+fn introspectable_type() -> StringVc {
+    StringVc::cell("split content source".to_string())
+}
+
+#[turbo_tasks::function]
+fn control_key() -> StringVc {
+    StringVc::cell("control".to_string())
+}
+
+#[turbo_tasks::function]
+fn alternate_key() -> StringVc {
+    StringVc::cell("alternate".to_string())
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for SplitContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        introspectable_type()
+    }
+
+    #[turbo_tasks::function]
+    fn details(&self) -> StringVc {
+        StringVc::cell(format!("{:?}", self.rule))
+    }
+
+    #[turbo_tasks::function]
+    async fn children(&self) -> Result<IntrospectableChildrenVc> {
+        Ok(IntrospectableChildrenVc::cell(
+            [
+                IntrospectableVc::resolve_from(self.control)
+                    .await?
+                    .map(|i| (control_key(), i)),
+                IntrospectableVc::resolve_from(self.alternate)
+                    .await?
+                    .map(|i| (alternate_key(), i)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::headers::Headers;
+
+    fn data_with_headers(headers: &[(&str, &str)]) -> ContentSourceData {
+        let mut map = Headers::default();
+        for (name, value) in headers {
+            map.insert(
+                name.to_string(),
+                HeaderValue::SingleString(value.to_string()),
+            );
+        }
+        ContentSourceData {
+            headers: Some(map),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn splits_by_percentage() {
+        let rule = SplitRule::Percentage(25);
+        assert_eq!(rule.use_alternate(&ContentSourceData::default()), None);
+        let alternate = (1..=100)
+            .filter(|&cache_buster| {
+                rule.use_alternate(&ContentSourceData {
+                    cache_buster,
+                    ..Default::default()
+                })
+                .unwrap()
+            })
+            .count();
+        assert_eq!(alternate, 25);
+    }
+
+    #[test]
+    fn splits_by_header() {
+        let rule = SplitRule::Header {
+            name: "X-Variant".to_string(),
+            value: Some("b".to_string()),
+        };
+        assert_eq!(rule.use_alternate(&ContentSourceData::default()), None);
+        assert_eq!(
+            rule.use_alternate(&data_with_headers(&[("x-variant", "b")])),
+            Some(true)
+        );
+        assert_eq!(
+            rule.use_alternate(&data_with_headers(&[("x-variant", "a")])),
+            Some(false)
+        );
+        assert_eq!(rule.use_alternate(&data_with_headers(&[])), Some(false));
+
+        let rule = SplitRule::Header {
+            name: "x-variant".to_string(),
+            value: None,
+        };
+        assert_eq!(
+            rule.use_alternate(&data_with_headers(&[("x-variant", "a")])),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn splits_by_cookie() {
+        let rule = SplitRule::Cookie {
+            name: "variant".to_string(),
+            value: Some("b".to_string()),
+        };
+        assert_eq!(
+            rule.use_alternate(&data_with_headers(&[("cookie", "theme=dark; variant=b")])),
+            Some(true)
+        );
+        assert_eq!(
+            rule.use_alternate(&data_with_headers(&[("cookie", "variant=a")])),
+            Some(false)
+        );
+        assert_eq!(
+            rule.use_alternate(&data_with_headers(&[("cookie", "other_variant=b")])),
+            Some(false)
+        );
+    }
+}
@@ -1,6 +1,5 @@
-use std::collections::HashSet;
-
 use anyhow::Result;
+use indexmap::IndexSet;
 use turbo_tasks::{primitives::StringVc, Value};
 use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemEntryType, FileSystemPathVc};
 use turbopack_core::{
@@ -72,7 +71,7 @@ impl Introspectable for StaticAssetsContentSource {
     async fn children(&self) -> Result<IntrospectableChildrenVc> {
         let dir = self.dir.read_dir().await?;
         let children = match &*dir {
-            DirectoryContent::NotFound => HashSet::new(),
+            DirectoryContent::NotFound => IndexSet::new(),
             DirectoryContent::Entries(entries) => entries
                 .iter()
                 .map(|(name, entry)| {
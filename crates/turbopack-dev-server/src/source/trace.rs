@@ -0,0 +1,294 @@
+//! Opt-in traces of how a request is resolved in the content sources, for
+//! debugging why a source served a request, e.g. when two routes conflict.
+//!
+//! A request with the [SOURCE_TRACE_HEADER] gets the trace as JSON in the
+//! same header of the response.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use turbo_tasks::Value;
+use turbopack_core::introspect::IntrospectableVc;
+
+use super::{
+    combined::CombinedContentSourceVc, conditional::ConditionalContentSourceVc,
+    router::RouterContentSourceVc, ContentSource, ContentSourceData, ContentSourceDataVary,
+    ContentSourceResult, ContentSourceResultReadRef, ContentSourceVc,
+};
+
+/// The header which enables the trace of a request, and which carries the
+/// trace in the response.
+pub const SOURCE_TRACE_HEADER: &str = "x-turbopack-source-trace";
+
+/// How deep the sources consulted for a step are explained.
+const MAX_DEPTH: usize = 16;
+
+/// Collects the [SourceTraceStep]s of a request while it's resolved.
+pub type SourceTraceCollector = Arc<Mutex<Vec<SourceTraceStep>>>;
+
+/// A step of resolving a request: a source is asked for a path, with the data
+/// of the request it asked for in a previous step.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceTraceStep {
+    pub source: String,
+    pub path: String,
+    /// The data of the request the source was asked with.
+    pub data: Vec<&'static str>,
+    pub outcome: SourceTraceOutcome,
+    /// The path and query the content rewrote the request to, when it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rewrite: Option<String>,
+    /// The sources the source consulted, when it was asked without data.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub consulted: Vec<SourceTraceNode>,
+}
+
+/// A source consulted by the source of a [SourceTraceStep] or by another
+/// consulted source.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceTraceNode {
+    pub source: String,
+    pub path: String,
+    pub outcome: SourceTraceOutcome,
+    /// Whether the result of this source is the one the parent responded
+    /// with, e.g. because it's the most specific of a combined source.
+    pub selected: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub consulted: Vec<SourceTraceNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SourceTraceOutcome {
+    NotFound,
+    /// The source needs data of the request to respond.
+    NeedData {
+        vary: Vec<&'static str>,
+    },
+    /// The source responds with a result of the specificity.
+    Result {
+        specificity: String,
+    },
+}
+
+impl SourceTraceOutcome {
+    async fn from_result(result: &ContentSourceResult) -> Result<Self> {
+        Ok(match result {
+            ContentSourceResult::NotFound => SourceTraceOutcome::NotFound,
+            ContentSourceResult::NeedData(needed) => SourceTraceOutcome::NeedData {
+                vary: vary_names(&needed.vary),
+            },
+            ContentSourceResult::Result { specificity, .. } => SourceTraceOutcome::Result {
+                specificity: specificity.await?.to_string(),
+            },
+        })
+    }
+}
+
+/// The names of the data of a request which `vary` asks for.
+pub fn vary_names(vary: &ContentSourceDataVary) -> Vec<&'static str> {
+    let ContentSourceDataVary {
+        method,
+        url,
+        query,
+        raw_query,
+        headers,
+        raw_headers,
+        body,
+        cache_buster,
+        remote_ip,
+        correlation_id,
+        placeholder_for_future_extensions: _,
+    } = vary;
+    [
+        (*method, "method"),
+        (*url, "url"),
+        (query.is_some(), "query"),
+        (*raw_query, "rawQuery"),
+        (headers.is_some(), "headers"),
+        (*raw_headers, "rawHeaders"),
+        (*body, "body"),
+        (*cache_buster, "cacheBuster"),
+        (*remote_ip, "remoteIp"),
+        (*correlation_id, "correlationId"),
+    ]
+    .into_iter()
+    .filter_map(|(set, name)| set.then_some(name))
+    .collect()
+}
+
+/// Describes a source by its introspection type and title.
+pub async fn describe_source(source: ContentSourceVc) -> Result<String> {
+    Ok(match IntrospectableVc::resolve_from(source).await? {
+        Some(introspectable) => {
+            let ty = introspectable.ty().await?;
+            let title = introspectable.title().await?;
+            if title.is_empty() {
+                ty.to_string()
+            } else {
+                format!("{ty} ({title})")
+            }
+        }
+        None => "content source".to_string(),
+    })
+}
+
+/// Records a step of resolving a request. The sources which `source`
+/// consults are explained when it was asked without data, the same way it
+/// consults them.
+pub async fn record_step(
+    collector: &SourceTraceCollector,
+    source: ContentSourceVc,
+    path: &str,
+    data: &ContentSourceDataVary,
+    result: &ContentSourceResult,
+) -> Result<()> {
+    let data = vary_names(data);
+    let consulted = if data.is_empty() {
+        explain_consulted(source, path.to_string(), 0).await?
+    } else {
+        Vec::new()
+    };
+    let step = SourceTraceStep {
+        source: describe_source(source).await?,
+        path: path.to_string(),
+        data,
+        outcome: SourceTraceOutcome::from_result(result).await?,
+        rewrite: None,
+        consulted,
+    };
+    collector.lock().unwrap().push(step);
+    Ok(())
+}
+
+/// Sets the rewrite of the last recorded step.
+pub fn record_rewrite(collector: &SourceTraceCollector, path_and_query: &str) {
+    if let Some(step) = collector.lock().unwrap().last_mut() {
+        step.rewrite = Some(path_and_query.to_string());
+    }
+}
+
+async fn get_without_data(
+    source: ContentSourceVc,
+    path: &str,
+) -> Result<ContentSourceResultReadRef> {
+    source
+        .get(path, Value::new(ContentSourceData::default()))
+        .await
+}
+
+async fn node(
+    source: ContentSourceVc,
+    path: String,
+    result: &ContentSourceResult,
+    selected: bool,
+    depth: usize,
+) -> Result<SourceTraceNode> {
+    Ok(SourceTraceNode {
+        source: describe_source(source).await?,
+        outcome: SourceTraceOutcome::from_result(result).await?,
+        selected,
+        consulted: explain_consulted(source, path.clone(), depth + 1).await?,
+        path,
+    })
+}
+
+/// Explains the sources which the routing sources, i.e. routers, combined
+/// and conditional sources, consult for `path` without data.
+fn explain_consulted(
+    source: ContentSourceVc,
+    path: String,
+    depth: usize,
+) -> Pin<Box<dyn Future<Output = Result<Vec<SourceTraceNode>>> + Send>> {
+    Box::pin(async move {
+        if depth >= MAX_DEPTH {
+            return Ok(Vec::new());
+        }
+        let mut consulted = Vec::new();
+        if let Some(router) = RouterContentSourceVc::resolve_from(source).await? {
+            let router = router.await?;
+            let (route, route_path) = router
+                .routes
+                .iter()
+                .find_map(|(route, source)| {
+                    path.strip_prefix(route.as_str())
+                        .map(|rest| (*source, rest.to_string()))
+                })
+                .unwrap_or((router.fallback, path.clone()));
+            let result = get_without_data(route, &route_path).await?;
+            consulted.push(node(route, route_path, &result, true, depth).await?);
+        } else if let Some(combined) = CombinedContentSourceVc::resolve_from(source).await? {
+            // Like the combined source: the first result of the highest
+            // specificity wins, an exact result wins right away, and the source
+            // pauses at the first source which needs data.
+            let mut max = None;
+            for &inner in combined.await?.sources.iter() {
+                let result = get_without_data(inner, &path).await?;
+                let mut stop = false;
+                match &*result {
+                    ContentSourceResult::NotFound => {}
+                    ContentSourceResult::NeedData(_) => {
+                        max = Some((None, consulted.len()));
+                        stop = true;
+                    }
+                    ContentSourceResult::Result { specificity, .. } => {
+                        let specificity = specificity.await?;
+                        if specificity.is_exact() {
+                            max = Some((None, consulted.len()));
+                            stop = true;
+                        } else if !matches!(&max, Some((Some(max), _)) if *max >= specificity) {
+                            max = Some((Some(specificity), consulted.len()));
+                        }
+                    }
+                }
+                consulted.push(node(inner, path.clone(), &result, false, depth).await?);
+                if stop {
+                    break;
+                }
+            }
+            if let Some((_, index)) = max {
+                consulted[index].selected = true;
+            }
+        } else if ConditionalContentSourceVc::resolve_from(source)
+            .await?
+            .is_some()
+        {
+            // The activator is consulted first, the action only once the
+            // activator served a request.
+            for &inner in source.get_children().await?.iter() {
+                let result = get_without_data(inner, &path).await?;
+                consulted.push(node(inner, path.clone(), &result, false, depth).await?);
+            }
+        }
+        Ok(consulted)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::ContentSourceDataFilter;
+
+    #[test]
+    fn names_varying_data() {
+        assert!(vary_names(&ContentSourceDataVary::default()).is_empty());
+        let vary = ContentSourceDataVary {
+            method: true,
+            query: Some(ContentSourceDataFilter::All),
+            raw_headers: true,
+            correlation_id: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            vary_names(&vary),
+            ["method", "query", "rawHeaders", "correlationId"]
+        );
+    }
+}
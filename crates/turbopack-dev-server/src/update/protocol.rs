@@ -1,10 +1,13 @@
 use std::{collections::BTreeMap, ops::Deref, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use turbopack_cli_utils::issue::{format_issue, LogOptions};
+use serde_json::value::RawValue;
+use turbopack_cli_utils::{
+    diagnostics::DiagnosticsFormat,
+    issue::{format_issue, LogOptions},
+};
 use turbopack_core::{
-    issue::{IssueSeverity, PlainIssue},
+    issue::{code_frame::CodeFrame, IssueSeverity, PlainIssue},
     source_pos::SourcePos,
 };
 
@@ -26,6 +29,9 @@ pub enum ClientMessage {
         #[serde(flatten)]
         resource: ResourceIdentifier,
     },
+    /// Subscribes to [BuildActivityMessage]s, e.g. to show an activity
+    /// indicator while the dev server is building.
+    SubscribeBuildActivity,
 }
 
 #[derive(Serialize)]
@@ -58,7 +64,7 @@ impl<'a> ClientUpdateInstruction<'a> {
 
     pub fn partial(
         resource: &'a ResourceIdentifier,
-        instruction: &'a Value,
+        instruction: &'a RawValue,
         issues: &'a [Issue<'a>],
     ) -> Self {
         Self::new(
@@ -85,10 +91,30 @@ impl<'a> ClientUpdateInstruction<'a> {
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum ClientUpdateInstructionType<'a> {
     Restart,
-    Partial { instruction: &'a Value },
+    Partial { instruction: &'a RawValue },
     Issues,
 }
 
+/// A message about the activity of the build, which is sent to clients that
+/// subscribed with [ClientMessage::SubscribeBuildActivity]. It's derived from
+/// the number of tasks in progress, so it covers any work of the dev server,
+/// not only the work that results in updates.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BuildActivityMessage {
+    /// Tasks started after all work was done, e.g. because a file changed.
+    BuildingStarted,
+    /// The number of tasks in progress changed while building.
+    Building { remaining: usize },
+    /// All tasks are done.
+    BuildingFinished {
+        #[serde(rename = "durationMs")]
+        duration_ms: u64,
+        /// The number of issues of the resources the client subscribed to.
+        issues: usize,
+    },
+}
+
 #[derive(Serialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum ServerError {
@@ -106,6 +132,8 @@ pub struct IssueSource<'a> {
     pub asset: Asset<'a>,
     pub start: SourcePos,
     pub end: SourcePos,
+    /// The lines around the range, for rendering a code frame.
+    pub code_frame: Option<&'a CodeFrame>,
 }
 
 #[derive(Serialize)]
@@ -133,6 +161,7 @@ impl<'a> From<&'a PlainIssue> for Issue<'a> {
             },
             start: source.start,
             end: source.end,
+            code_frame: source.code_frame.as_deref(),
         });
 
         Issue {
@@ -153,6 +182,7 @@ impl<'a> From<&'a PlainIssue> for Issue<'a> {
                     show_all: true,
                     log_detail: true,
                     log_level: IssueSeverity::Info,
+                    diagnostics_format: DiagnosticsFormat::Human,
                 },
             ),
         }
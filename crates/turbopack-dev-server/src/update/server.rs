@@ -1,6 +1,8 @@
 use std::{
+    collections::HashMap,
     pin::Pin,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context as _, Error, Result};
@@ -8,15 +10,19 @@ use futures::{prelude::*, ready, stream::FusedStream, SinkExt};
 use hyper::{upgrade::Upgraded, HeaderMap, Uri};
 use hyper_tungstenite::{tungstenite::Message, HyperWebsocket, WebSocketStream};
 use pin_project_lite::pin_project;
-use tokio::select;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use tokio::{select, time::MissedTickBehavior};
 use tokio_stream::StreamMap;
-use turbo_tasks::{TransientInstance, TurboTasksApi};
+use turbo_tasks::{turbo_tasks, TransientInstance, TurboTasksApi};
 use turbo_tasks_fs::json::parse_json_with_source_context;
 use turbopack_cli_utils::issue::ConsoleUiVc;
 use turbopack_core::version::Update;
 
 use super::{
-    protocol::{ClientMessage, ClientUpdateInstruction, Issue, ResourceIdentifier},
+    protocol::{
+        BuildActivityMessage, ClientMessage, ClientUpdateInstruction, Issue, ResourceIdentifier,
+    },
     stream::UpdateStream,
 };
 use crate::{
@@ -25,6 +31,52 @@ use crate::{
     SourceProvider,
 };
 
+/// How often the number of tasks in progress is checked for
+/// [BuildActivityMessage]s.
+const BUILD_ACTIVITY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Derives the [BuildActivityMessage]s of a client from the number of tasks
+/// in progress, which is checked periodically.
+#[derive(Default)]
+struct BuildActivity {
+    /// When the current build started.
+    started: Option<Instant>,
+    /// The number of tasks in progress at the last check.
+    remaining: usize,
+}
+
+impl BuildActivity {
+    fn update(&mut self, in_progress: usize, issues: usize) -> Vec<BuildActivityMessage> {
+        let messages = match (self.started, in_progress) {
+            (None, 0) => vec![],
+            (None, _) => {
+                self.started = Some(Instant::now());
+                vec![
+                    BuildActivityMessage::BuildingStarted,
+                    BuildActivityMessage::Building {
+                        remaining: in_progress,
+                    },
+                ]
+            }
+            (Some(start), 0) => {
+                self.started = None;
+                vec![BuildActivityMessage::BuildingFinished {
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    issues,
+                }]
+            }
+            (Some(_), _) if in_progress != self.remaining => {
+                vec![BuildActivityMessage::Building {
+                    remaining: in_progress,
+                }]
+            }
+            (Some(_), _) => vec![],
+        };
+        self.remaining = in_progress;
+        messages
+    }
+}
+
 /// A server that listens for updates and sends them to connected clients.
 pub(crate) struct UpdateServer<P: SourceProvider> {
     source_provider: P,
@@ -54,6 +106,12 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
         let mut client: UpdateClient = ws.await?.into();
 
         let mut streams = StreamMap::new();
+        // The number of issues of the last update of every resource.
+        let mut issue_counts = HashMap::new();
+        // Once the client subscribed to the build activity.
+        let mut build_activity: Option<BuildActivity> = None;
+        let mut activity_interval = tokio::time::interval(BUILD_ACTIVITY_INTERVAL);
+        activity_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
         loop {
             select! {
@@ -78,6 +136,10 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
                         }
                         Some(ClientMessage::Unsubscribe { resource }) => {
                             streams.remove(&resource);
+                            issue_counts.remove(&resource);
+                        }
+                        Some(ClientMessage::SubscribeBuildActivity) => {
+                            build_activity.get_or_insert_with(Default::default);
                         }
                         None => {
                             // WebSocket was closed, stop sending updates
@@ -86,8 +148,18 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
                     }
                 }
                 Some((resource, update)) = streams.next() => {
+                    issue_counts.insert(resource.clone(), update.issues.len());
                     Self::send_update(&mut client, resource, &update).await?;
                 }
+                _ = activity_interval.tick(), if build_activity.is_some() => {
+                    let messages = build_activity.as_mut().unwrap().update(
+                        turbo_tasks().in_progress_count(),
+                        issue_counts.values().sum(),
+                    );
+                    for message in messages {
+                        client.send(message).await?;
+                    }
+                }
                 else => break
             }
         }
@@ -108,11 +180,12 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
 
         match &*update.update {
             Update::Partial(partial) => {
-                let partial_instruction = partial.instruction.await?;
+                let instruction = partial.instruction.await?;
+                let instruction = RawValue::from_string(instruction.to_str()?.into_owned())?;
                 client
                     .send(ClientUpdateInstruction::partial(
                         &resource,
-                        &partial_instruction,
+                        &instruction,
                         &issues,
                     ))
                     .await?;
@@ -150,6 +223,9 @@ fn resource_to_request(resource: &ResourceIdentifier) -> Result<SourceRequest> {
         headers,
         method: "GET".to_string(),
         body: Body::new(vec![]),
+        remote_addr: None,
+        correlation_id: None,
+        trace: None,
     })
 }
 
@@ -203,7 +279,7 @@ impl FusedStream for UpdateClient {
     }
 }
 
-impl<'a> Sink<ClientUpdateInstruction<'a>> for UpdateClient {
+impl<T: Serialize> Sink<T> for UpdateClient {
     type Error = Error;
 
     fn poll_ready(
@@ -216,10 +292,7 @@ impl<'a> Sink<ClientUpdateInstruction<'a>> for UpdateClient {
             .map(|res| res.context("polling WebSocket ready"))
     }
 
-    fn start_send(
-        self: Pin<&mut Self>,
-        item: ClientUpdateInstruction<'a>,
-    ) -> std::result::Result<(), Self::Error> {
+    fn start_send(self: Pin<&mut Self>, item: T) -> std::result::Result<(), Self::Error> {
         let msg = Message::text(serde_json::to_string(&item)?);
 
         self.project()
@@ -254,3 +327,44 @@ impl From<WebSocketStream<Upgraded>> for UpdateClient {
         Self { ws, ended: false }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reports_build_activity() {
+        let mut activity = BuildActivity::default();
+        let mut update = |in_progress, issues| {
+            activity
+                .update(in_progress, issues)
+                .iter()
+                .map(|message| serde_json::to_value(message).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert!(update(0, 0).is_empty());
+        assert_eq!(
+            update(10, 0),
+            [
+                json!({ "type": "buildingStarted" }),
+                json!({ "type": "building", "remaining": 10 }),
+            ]
+        );
+        assert_eq!(
+            update(4, 0),
+            [json!({ "type": "building", "remaining": 4 })]
+        );
+        // Only changes are reported.
+        assert!(update(4, 0).is_empty());
+        let finished = update(0, 2);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0]["type"], "buildingFinished");
+        assert_eq!(finished[0]["issues"], 2);
+        assert!(finished[0]["durationMs"].is_u64());
+        assert!(update(0, 2).is_empty());
+        // The next change starts a new build.
+        assert_eq!(update(1, 2)[0], json!({ "type": "buildingStarted" }));
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use criterion::{BatchSize, BenchmarkId, Criterion};
+use swc_core::{
+    common::{FileName, SourceMap, GLOBALS},
+    ecma::{
+        ast::{EsVersion, Module, Str},
+        parser::parse_file_as_module,
+        visit::{AstKindPath, AstParentKind, VisitMut, VisitMutAstPath, VisitMutWithPath},
+    },
+};
+use turbopack_ecmascript::{
+    code_gen::VisitorFactory,
+    path_visitor::{ApplyVisitors, AstPath},
+};
+
+/// A module with `statements` statements, each containing a few strings.
+fn large_module(statements: usize) -> Module {
+    let mut code = String::new();
+    for i in 0..statements {
+        code += &format!("foo(\"a{i}\", [\"b{i}\", {{ c: \"c{i}\" }}], (\"d\", \"e\"));\n");
+    }
+    let cm = SourceMap::default();
+    let fm = cm.new_source_file(FileName::Anon, code);
+    parse_file_as_module(
+        &fm,
+        Default::default(),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    )
+    .unwrap()
+}
+
+/// Collects the paths to all string literals, like code generation would
+/// collect the paths of the nodes it needs to replace.
+#[derive(Default)]
+struct CollectStrPaths(Vec<AstPath>);
+
+impl VisitMutAstPath for CollectStrPaths {
+    fn visit_mut_str(&mut self, _: &mut Str, ast_path: &mut AstKindPath<AstParentKind>) {
+        self.0.push(ast_path.to_vec());
+    }
+}
+
+struct StrVisitor;
+
+impl VisitorFactory for StrVisitor {
+    fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+        Box::new(StrVisitor)
+    }
+}
+
+impl VisitMut for StrVisitor {
+    fn visit_mut_str(&mut self, s: &mut Str) {
+        s.raw = None;
+    }
+}
+
+pub fn benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("path_visitor");
+    group.warm_up_time(Duration::from_secs(1));
+    group.measurement_time(Duration::from_secs(3));
+
+    GLOBALS.set(&Default::default(), || {
+        for statements in [100, 5_000] {
+            let mut module = large_module(statements);
+            let mut collect = CollectStrPaths::default();
+            module.visit_mut_with_path(&mut collect, &mut Default::default());
+            let paths = collect.0;
+
+            // Code generation usually touches a small part of the module.
+            for (name, step) in [("every string", 1), ("every 50th string", 50)] {
+                let visitors: Vec<(&AstPath, &dyn VisitorFactory)> = paths
+                    .iter()
+                    .step_by(step)
+                    .map(|path| (path, &StrVisitor as &dyn VisitorFactory))
+                    .collect();
+                group.bench_with_input(
+                    BenchmarkId::new(format!("apply {name}"), statements),
+                    &visitors,
+                    |b, visitors| {
+                        b.iter_batched(
+                            || module.clone(),
+                            |mut module| {
+                                module.visit_mut_with_path(
+                                    &mut ApplyVisitors::new(visitors.clone()),
+                                    &mut Default::default(),
+                                );
+                                module
+                            },
+                            BatchSize::LargeInput,
+                        )
+                    },
+                );
+            }
+        }
+    });
+
+    group.finish();
+}
@@ -0,0 +1,154 @@
+/** @typedef {import('../types').RefreshHelpers} RefreshHelpers */
+
+/**
+ * Sets up React Refresh for the turbopack runtime. This is the counterpart of
+ * the React Refresh transform, which registers components and hook signatures
+ * with the `register` and `signature` globals.
+ *
+ * The runtime calls `$RefreshInterceptModuleExecution$` around the execution
+ * of every module and uses `$RefreshHelpers$` to accept updates of modules
+ * that only export components.
+ *
+ * @param {any} RefreshRuntime the `react-refresh/runtime` module
+ * @param {{ register: string, signature: string }} options
+ */
+function setupReactRefresh(RefreshRuntime, { register, signature }) {
+  RefreshRuntime.injectIntoGlobalHook(globalThis);
+
+  // Modules executed outside of the turbopack runtime don't take part in
+  // React Refresh.
+  globalThis[register] = () => {};
+  globalThis[signature] = () => (type) => type;
+
+  globalThis.$RefreshInterceptModuleExecution$ = (moduleId) => {
+    const prevRegister = globalThis[register];
+    const prevSignature = globalThis[signature];
+    globalThis[register] = (type, id) => {
+      RefreshRuntime.register(type, `${moduleId} ${id}`);
+    };
+    globalThis[signature] = RefreshRuntime.createSignatureFunctionForTransform;
+    return () => {
+      globalThis[register] = prevRegister;
+      globalThis[signature] = prevSignature;
+    };
+  };
+
+  /**
+   * @param {any} moduleExports
+   * @param {(key: string, value: any) => void} callback
+   */
+  function forEachExport(moduleExports, callback) {
+    if (moduleExports == null || typeof moduleExports !== "object") {
+      return;
+    }
+    for (const key in moduleExports) {
+      if (key === "__esModule") {
+        continue;
+      }
+      const desc = Object.getOwnPropertyDescriptor(moduleExports, key);
+      if (desc && desc.get) {
+        // Don't invoke getters as they may have side effects.
+        continue;
+      }
+      callback(key, moduleExports[key]);
+    }
+  }
+
+  /**
+   * @param {any} moduleExports
+   * @returns {boolean}
+   */
+  function isReactRefreshBoundary(moduleExports) {
+    if (RefreshRuntime.isLikelyComponentType(moduleExports)) {
+      return true;
+    }
+    if (moduleExports == null || typeof moduleExports !== "object") {
+      // Exit if we can't iterate over exports.
+      return false;
+    }
+    let hasExports = false;
+    let areAllExportsComponents = true;
+    for (const key in moduleExports) {
+      hasExports = true;
+      if (key === "__esModule") {
+        continue;
+      }
+      const desc = Object.getOwnPropertyDescriptor(moduleExports, key);
+      if (desc && desc.get) {
+        // Don't invoke getters as they may have side effects.
+        return false;
+      }
+      if (!RefreshRuntime.isLikelyComponentType(moduleExports[key])) {
+        areAllExportsComponents = false;
+      }
+    }
+    return hasExports && areAllExportsComponents;
+  }
+
+  /**
+   * @param {any} moduleExports
+   * @returns {any[]}
+   */
+  function getRefreshBoundarySignature(moduleExports) {
+    const boundarySignature = [RefreshRuntime.getFamilyByType(moduleExports)];
+    forEachExport(moduleExports, (key, value) => {
+      boundarySignature.push(key);
+      boundarySignature.push(RefreshRuntime.getFamilyByType(value));
+    });
+    return boundarySignature;
+  }
+
+  /**
+   * @param {any} prevExports
+   * @param {any} nextExports
+   * @returns {boolean}
+   */
+  function shouldInvalidateReactRefreshBoundary(prevExports, nextExports) {
+    const prevSignature = getRefreshBoundarySignature(prevExports);
+    const nextSignature = getRefreshBoundarySignature(nextExports);
+    if (prevSignature.length !== nextSignature.length) {
+      return true;
+    }
+    for (let i = 0; i < nextSignature.length; i++) {
+      if (prevSignature[i] !== nextSignature[i]) {
+        return true;
+      }
+    }
+    return false;
+  }
+
+  /**
+   * @param {any} moduleExports
+   * @param {string} moduleId
+   */
+  function registerExportsForReactRefresh(moduleExports, moduleId) {
+    RefreshRuntime.register(moduleExports, `${moduleId} %exports%`);
+    forEachExport(moduleExports, (key, value) => {
+      RefreshRuntime.register(value, `${moduleId} %exports% ${key}`);
+    });
+  }
+
+  /** @type {ReturnType<typeof setTimeout> | undefined} */
+  let refreshTimeout;
+
+  // Multiple modules are usually updated at once, so the refresh is batched.
+  function scheduleUpdate() {
+    if (refreshTimeout !== undefined) {
+      return;
+    }
+    refreshTimeout = setTimeout(() => {
+      refreshTimeout = undefined;
+      RefreshRuntime.performReactRefresh();
+    }, 30);
+  }
+
+  /** @type {RefreshHelpers} */
+  const helpers = {
+    registerExportsForReactRefresh,
+    isReactRefreshBoundary,
+    shouldInvalidateReactRefreshBoundary,
+    getRefreshBoundarySignature,
+    scheduleUpdate,
+  };
+  globalThis.$RefreshHelpers$ = helpers;
+}
@@ -7,7 +7,8 @@ const BACKEND = {
       if (chunkPath.endsWith(".css")) {
         const link = document.createElement("link");
         link.rel = "stylesheet";
-        link.href = `/${chunkPath}`;
+        link.href = `${CHUNK_BASE_PATH}${chunkPath}`;
+        if (CSP_NONCE) link.nonce = CSP_NONCE;
         link.onerror = () => {
           reject();
         };
@@ -19,7 +20,8 @@ const BACKEND = {
         document.body.appendChild(link);
       } else if (chunkPath.endsWith(".js")) {
         const script = document.createElement("script");
-        script.src = `/${chunkPath}`;
+        script.src = `${CHUNK_BASE_PATH}${chunkPath}`;
+        if (CSP_NONCE) script.nonce = CSP_NONCE;
         // We'll only mark the chunk as loaded once the script has been executed,
         // which happens in `registerChunk`. Hence the absence of `resolve()` in
         // this branch.
@@ -33,5 +35,16 @@ const BACKEND = {
     });
   },
 
+  hintChunk(chunkPath, hint) {
+    const link = document.createElement("link");
+    link.rel = hint;
+    if (hint === "preload") {
+      link.as = chunkPath.endsWith(".css") ? "style" : "script";
+    }
+    link.href = `${CHUNK_BASE_PATH}${chunkPath}`;
+    if (CSP_NONCE) link.nonce = CSP_NONCE;
+    document.head.appendChild(link);
+  },
+
   restart: () => self.location.reload(),
 };
@@ -0,0 +1,80 @@
+/** @typedef {import('../types/backend').RuntimeBackend} RuntimeBackend */
+
+/** @type {RuntimeBackend} */
+const BACKEND = {
+  loadChunk(chunkPath, from) {
+    return new Promise((resolve, reject) => {
+      if (chunkPath.endsWith(".css")) {
+        if (typeof document === "undefined") {
+          // Stylesheets can only be attached in the DOM.
+          resolve();
+          return;
+        }
+        const link = document.createElement("link");
+        link.rel = "stylesheet";
+        link.href = `${CHUNK_BASE_PATH}${chunkPath}`;
+        if (CSP_NONCE) link.nonce = CSP_NONCE;
+        link.onerror = () => {
+          reject();
+        };
+        link.onload = () => {
+          // CSS chunks do not register themselves, and as such must be marked as
+          // loaded instantly.
+          resolve();
+        };
+        document.body.appendChild(link);
+      } else if (chunkPath.endsWith(".js") || chunkPath.endsWith(".mjs")) {
+        let url;
+        if (typeof document !== "undefined") {
+          url = `${CHUNK_BASE_PATH}${chunkPath}`;
+        } else {
+          const fromPath = getFirstModuleChunk(from);
+          if (fromPath == null) {
+            reject(
+              `Module ${from} that requested chunk ${chunkPath} has been removed`
+            );
+            return;
+          }
+          // Like in the Node.js backend, chunks are resolved relative to the
+          // chunk that requested them, which is next to the runtime.
+          const depth = fromPath.split("/").length - 1;
+          const root = new URL("../".repeat(depth), import.meta.url);
+          url = new URL(chunkPath, root).href;
+        }
+        // We'll only mark the chunk as loaded once the module has been
+        // evaluated, which happens in `registerChunk`. Hence the absence of
+        // `resolve()` in this branch.
+        import(url).catch(reject);
+      } else {
+        throw new Error(`can't infer type of chunk from path ${chunkPath}`);
+      }
+    });
+  },
+
+  hintChunk(chunkPath, hint) {
+    if (typeof document === "undefined") {
+      // There is nothing to fetch ahead of time outside of the DOM.
+      return;
+    }
+    const link = document.createElement("link");
+    if (chunkPath.endsWith(".css")) {
+      link.rel = hint;
+      if (hint === "preload") link.as = "style";
+    } else {
+      // Preloaded chunks are modules, which need to be fetched as such to be
+      // reused by `import()`.
+      link.rel = hint === "preload" ? "modulepreload" : hint;
+    }
+    link.href = `${CHUNK_BASE_PATH}${chunkPath}`;
+    if (CSP_NONCE) link.nonce = CSP_NONCE;
+    document.head.appendChild(link);
+  },
+
+  restart: () => {
+    if (typeof location !== "undefined") {
+      location.reload();
+    } else {
+      throw new Error("restart not implemented for ES modules in Node.js");
+    }
+  },
+};
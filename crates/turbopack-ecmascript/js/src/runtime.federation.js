@@ -0,0 +1,192 @@
+/**
+ * The runtime of module federation. It's only part of the runtime when the
+ * chunking context enables module federation, see `federation` in
+ * `runtime.js`.
+ */
+
+/** @typedef {import('../types/runtime').FederationState} FederationState */
+/** @typedef {import('../types/runtime').FederationContainer} FederationContainer */
+/** @typedef {import('../types/runtime').SharedConfig} SharedConfig */
+
+/**
+ * Creates the API which federated modules use as `__turbopack_federation__`.
+ */
+function createFederation() {
+  /**
+   * The state of module federation is shared by all containers on the page, so
+   * that they negotiate the shared packages with each other.
+   *
+   * @type {FederationState}
+   */
+  const federationState = (globalThis.__turbopack_federation_state__ ??= {
+    shareScope: {},
+    containers: {},
+  });
+
+  /**
+   * Loads the remote entry of a container and returns a module it exposes.
+   *
+   * @param {string} name
+   * @param {string} url
+   * @param {string} module
+   * @returns {Promise<any>}
+   */
+  function loadRemote(name, url, module) {
+    const containers = federationState.containers;
+    containers[name] ??= loadRemoteEntry(name, url);
+    return containers[name]
+      .then((container) => container.get(module))
+      .then((factory) => factory());
+  }
+
+  /**
+   * @param {string} name
+   * @param {string} url
+   * @returns {Promise<FederationContainer>}
+   */
+  function loadRemoteEntry(name, url) {
+    if (globalThis[name] != null) {
+      return Promise.resolve(globalThis[name]);
+    }
+    if (typeof document === "undefined") {
+      return Promise.reject(
+        new Error(`Failed to load remote container ${name}: not in a browser`)
+      );
+    }
+    return new Promise((resolve, reject) => {
+      const script = document.createElement("script");
+      script.src = url;
+      script.onload = () => {
+        if (globalThis[name] == null) {
+          reject(new Error(`Remote container ${name} was not found at ${url}`));
+        } else {
+          resolve(globalThis[name]);
+        }
+      };
+      script.onerror = () => {
+        reject(new Error(`Failed to load remote container ${name} from ${url}`));
+      };
+      document.head.appendChild(script);
+    });
+  }
+
+  /**
+   * Returns the best version of a shared package that is loaded on the page.
+   * The local package is registered as a candidate and is used when no loaded
+   * version satisfies the required version.
+   *
+   * @param {string} name
+   * @param {SharedConfig} config
+   * @param {any} local
+   * @returns {any}
+   */
+  function consumeShared(name, config, local) {
+    const versions = (federationState.shareScope[name] ??= {});
+    const localVersion = config.version ?? "0.0.0";
+    versions[localVersion] ??= { from: config.from, module: local };
+
+    const candidates = Object.keys(versions).sort(compareVersions).reverse();
+    const required = config.requiredVersion;
+    if (config.singleton) {
+      const version = candidates[0];
+      if (required != null && !satisfiesVersion(version, required)) {
+        console.warn(
+          `[federation] Shared singleton ${name}@${version} does not satisfy the required version ${required} of ${config.from}`
+        );
+      }
+      return versions[version].module;
+    }
+    const version = candidates.find(
+      (version) => required == null || satisfiesVersion(version, required)
+    );
+    return version != null ? versions[version].module : local;
+  }
+
+  /**
+   * @param {string} version
+   * @returns {number[]}
+   */
+  function parseVersion(version) {
+    return version
+      .replace(/^[^\d]*/, "")
+      .split(/[.+-]/)
+      .slice(0, 3)
+      .map((part) => parseInt(part, 10) || 0);
+  }
+
+  /**
+   * @param {string} a
+   * @param {string} b
+   * @returns {number}
+   */
+  function compareVersions(a, b) {
+    const left = parseVersion(a);
+    const right = parseVersion(b);
+    for (let i = 0; i < 3; i++) {
+      if (left[i] !== right[i]) return (left[i] ?? 0) - (right[i] ?? 0);
+    }
+    return 0;
+  }
+
+  /**
+   * Supports exact versions and the `^`, `~`, `>` and `>=` ranges.
+   *
+   * @param {string} version
+   * @param {string} range
+   * @returns {boolean}
+   */
+  function satisfiesVersion(version, range) {
+    range = range.trim();
+    if (range === "" || range === "*") return true;
+    const [major, minor, patch] = parseVersion(range);
+    const actual = parseVersion(version);
+    if (range.startsWith(">=")) return compareVersions(version, range) >= 0;
+    if (range.startsWith(">")) return compareVersions(version, range) > 0;
+    if (range.startsWith("^")) {
+      if (compareVersions(version, range) < 0) return false;
+      if (major !== 0) return actual[0] === major;
+      if (minor !== 0) return actual[0] === 0 && actual[1] === minor;
+      return actual[0] === 0 && actual[1] === 0 && actual[2] === patch;
+    }
+    if (range.startsWith("~")) {
+      return (
+        compareVersions(version, range) >= 0 &&
+        actual[0] === major &&
+        actual[1] === minor
+      );
+    }
+    return compareVersions(version, range) === 0;
+  }
+
+  /**
+   * Creates the container that exposes modules to other builds. The share scope
+   * is global, so initializing the container is a no-op.
+   *
+   * @param {string} name
+   * @param {Record<string, () => Promise<any>>} exposes
+   * @returns {FederationContainer}
+   */
+  function createContainer(name, exposes) {
+    const container = {
+      get(module) {
+        const load = exposes[module];
+        if (load == null) {
+          return Promise.reject(
+            new Error(`Module ${module} is not exposed by container ${name}`)
+          );
+        }
+        return load().then((namespace) => () => namespace);
+      },
+      init() {},
+    };
+    federationState.containers[name] = Promise.resolve(container);
+    globalThis[name] = container;
+    return container;
+  }
+
+  return {
+    loadRemote,
+    consumeShared,
+    createContainer,
+  };
+}
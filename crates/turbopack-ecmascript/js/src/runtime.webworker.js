@@ -0,0 +1,25 @@
+/** @typedef {import('../types/backend').RuntimeBackend} RuntimeBackend */
+
+/** @type {RuntimeBackend} */
+const BACKEND = {
+  loadChunk(chunkPath, _from) {
+    return new Promise((resolve, reject) => {
+      if (chunkPath.endsWith(".css")) {
+        // Web workers have no document to attach stylesheets to.
+        resolve();
+      } else if (chunkPath.endsWith(".js")) {
+        // `importScripts` executes the chunk synchronously. The chunk is only
+        // marked as loaded once it has been registered in `registerChunk`.
+        try {
+          importScripts(`${CHUNK_BASE_PATH}${chunkPath}`);
+        } catch (e) {
+          reject(e);
+        }
+      } else {
+        throw new Error(`can't infer type of chunk from path ${chunkPath}`);
+      }
+    });
+  },
+
+  restart: () => self.close(),
+};
@@ -4,4 +4,8 @@ export { RuntimeBackend } from "types";
 
 declare global {
   declare const getFirstModuleChunk: GetFirstModuleChunk;
+  /** The URL prefix of chunk paths, e.g. `/` or `/app/`. */
+  declare const CHUNK_BASE_PATH: string;
+  /** Whether the runtime must not use `eval`. */
+  declare const STRICT_CSP: boolean;
 }
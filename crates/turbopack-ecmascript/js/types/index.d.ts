@@ -1,6 +1,7 @@
 import { RefreshRuntimeGlobals } from "@next/react-refresh-utils/dist/runtime";
 import { ServerMessage } from "./protocol";
 import { Hot } from "./hot";
+import { FederationContainer, SharedConfig } from "./runtime";
 
 export type RefreshHelpers = RefreshRuntimeGlobals["$RefreshHelpers$"];
 
@@ -31,6 +32,8 @@ interface Module {
   children: ModuleId[];
   parents: ModuleId[];
   interopNamespace?: EsmInteropNamespace;
+  async?: Promise<void>;
+  waitingFor?: Set<Module>;
 }
 
 type ModuleCache = Record<ModuleId, Module>;
@@ -40,12 +43,25 @@ type CommonJsRequire = (moduleId: ModuleId) => Exports;
 export type EsmInteropNamespace = Record<string, any>;
 type EsmImport = (
   moduleId: ModuleId,
-  allowExportDefault: boolean
+  allowExportDefault?: boolean,
+  nodeInterop?: boolean
 ) => EsmInteropNamespace;
 type EsmExport = (exportGetters: Record<string, () => any>) => void;
 type ExportValue = (value: any) => void;
 
 type LoadChunk = (chunkPath: ChunkPath) => Promise<any> | undefined;
+type HintChunk = (chunkPath: ChunkPath, hint: "prefetch" | "preload") => void;
+type AsyncModule = (body: () => Promise<void>) => void;
+type WaitForModule = (moduleId: ModuleId) => Promise<void>;
+
+interface Federation {
+  loadRemote: (name: string, url: string, module: string) => Promise<any>;
+  consumeShared: (name: string, config: SharedConfig, local: any) => any;
+  createContainer: (
+    name: string,
+    exposes: Record<string, () => Promise<any>>
+  ) => FederationContainer;
+}
 
 interface TurbopackContext {
   e: Module["exports"];
@@ -56,6 +72,10 @@ interface TurbopackContext {
   m: Module;
   c: ModuleCache;
   l: LoadChunk;
+  h: HintChunk;
+  a: AsyncModule;
+  w: WaitForModule;
+  f: Federation | undefined;
   p: Partial<NodeJS.Process> & Pick<NodeJS.Process, "env">;
 }
 
@@ -78,6 +98,8 @@ interface Runtime {
 interface RuntimeBackend {
   loadChunk: (chunkPath: ChunkPath, from: ModuleId) => Promise<void>;
 
+  hintChunk?: (chunkPath: ChunkPath, hint: "prefetch" | "preload") => void;
+
   restart: () => void;
 }
 
@@ -104,6 +126,7 @@ declare global {
     | ChunkUpdateProvider
     | [ChunkPath, UpdateCallback][]
     | undefined;
+  var TURBOPACK_EXECUTED_MODULES: Set<ModuleId> | undefined;
 
   var $RefreshHelpers$: RefreshRuntimeGlobals["$RefreshHelpers$"];
   var $RefreshReg$: RefreshRuntimeGlobals["$RefreshReg$"];
@@ -47,7 +47,28 @@ export type ClientMessageUnsubscribe = {
   type: "unsubscribe";
 } & ResourceIdentifier;
 
-export type ClientMessage = ClientMessageSubscribe | ClientMessageUnsubscribe;
+export type ClientMessageSubscribeBuildActivity = {
+  type: "subscribeBuildActivity";
+};
+
+export type ClientMessage =
+  | ClientMessageSubscribe
+  | ClientMessageUnsubscribe
+  | ClientMessageSubscribeBuildActivity;
+
+export type BuildActivityMessage =
+  | {
+      type: "buildingStarted";
+    }
+  | {
+      type: "building";
+      remaining: number;
+    }
+  | {
+      type: "buildingFinished";
+      durationMs: number;
+      issues: number;
+    };
 
 export type IssueSeverity =
   | "bug"
@@ -68,10 +89,27 @@ export type SourcePos = {
   column: number;
 };
 
+export type CodeFrameLine = {
+  line: number;
+  before: string;
+  inside: string;
+  after: string;
+  is_start: boolean;
+  is_end: boolean;
+  highlighted: boolean;
+};
+
+export type CodeFrame = {
+  start: SourcePos;
+  end: SourcePos;
+  lines: CodeFrameLine[];
+};
+
 export type IssueSource = {
   asset: IssueAsset;
   start: SourcePos;
   end: SourcePos;
+  code_frame: CodeFrame | null;
 };
 
 export type Issue = {
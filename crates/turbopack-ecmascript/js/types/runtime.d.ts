@@ -22,3 +22,21 @@ export type ModuleEffect =
       moduleId: ModuleId;
       outdatedModules: Set<ModuleId>;
     };
+
+export interface FederationContainer {
+  get: (module: string) => Promise<() => any>;
+  init: () => void;
+}
+
+export interface SharedConfig {
+  version?: string;
+  requiredVersion?: string;
+  singleton: boolean;
+  from: string;
+}
+
+export interface FederationState {
+  /** The loaded versions of the shared packages by package name. */
+  shareScope: Record<string, Record<string, { from: string; module: any }>>;
+  containers: Record<string, Promise<FederationContainer>>;
+}
@@ -153,12 +153,73 @@ pub enum Effect {
         ast_path: Vec<AstParentKind>,
         span: Span,
     },
+    /// A call to `import.meta.glob(...)`.
+    ImportMetaGlob {
+        args: Vec<JsValue>,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
+    /// A call to `import.meta.resolve(...)` with a single argument.
+    ImportMetaResolve {
+        input: JsValue,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
+    /// A reference to `import.meta.env`.
+    ImportMetaEnv {
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
     /// A reference to `new URL(..., import.meta.url)`.
     Url {
         input: JsValue,
         ast_path: Vec<AstParentKind>,
         span: Span,
     },
+    /// A reference to `new URL(..., import.meta.url)` which is passed to a
+    /// worker constructor, e. g. `new Worker(new URL(..., import.meta.url))`.
+    /// The `ast_path` points to the `new URL(...)` expression. `module` is set
+    /// when the script is loaded as an ES module, i. e. for
+    /// `new Worker(..., { type: "module" })`.
+    Worker {
+        kind: WorkerKind,
+        module: bool,
+        input: JsValue,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
+    /// A call to `eval(...)` or `new Function(...)`, which evaluates code that
+    /// can't be analyzed.
+    Eval { kind: EvalKind, span: Span },
+}
+
+/// How code is evaluated at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalKind {
+    /// A direct `eval(...)` call, which has access to the local scope.
+    Direct,
+    /// `new Function(...)` or `Function(...)`, which only has access to the
+    /// global scope.
+    Function,
+}
+
+/// The kind of script a worker URL is loaded as.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum WorkerKind {
+    /// `new Worker(url)`
+    Worker,
+    /// `new SharedWorker(url)`
+    SharedWorker,
+}
+
+impl WorkerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkerKind::Worker => "Worker",
+            WorkerKind::SharedWorker => "SharedWorker",
+        }
+    }
 }
 
 impl Effect {
@@ -217,6 +278,26 @@ impl Effect {
                 ast_path: _,
                 span: _,
             } => {}
+            Effect::ImportMetaGlob {
+                args,
+                ast_path: _,
+                span: _,
+            } => {
+                for arg in args.iter_mut() {
+                    arg.normalize();
+                }
+            }
+            Effect::ImportMetaResolve {
+                input,
+                ast_path: _,
+                span: _,
+            } => {
+                input.normalize();
+            }
+            Effect::ImportMetaEnv {
+                ast_path: _,
+                span: _,
+            } => {}
             Effect::Url {
                 input,
                 ast_path: _,
@@ -224,6 +305,16 @@ impl Effect {
             } => {
                 input.normalize();
             }
+            Effect::Eval { kind: _, span: _ } => {}
+            Effect::Worker {
+                kind: _,
+                module: _,
+                input,
+                ast_path: _,
+                span: _,
+            } => {
+                input.normalize();
+            }
         }
     }
 }
@@ -299,6 +390,21 @@ impl EvalContext {
         }
     }
 
+    /// Matches `typeof define == "function"` with the operands in any order.
+    fn is_typeof_define_check(&self, left: &Expr, right: &Expr) -> bool {
+        let is_typeof_define = |e: &Expr| match unparen(e) {
+            Expr::Unary(UnaryExpr {
+                op: op!("typeof"),
+                arg: box Expr::Ident(i),
+                ..
+            }) => &*i.sym == "define" && is_unresolved(i, self.unresolved_mark),
+            _ => false,
+        };
+        let is_function = |e: &Expr| matches!(e, Expr::Lit(Lit::Str(s)) if &*s.value == "function");
+        (is_typeof_define(left) && is_function(right))
+            || (is_function(left) && is_typeof_define(right))
+    }
+
     fn eval_tpl(&self, e: &Tpl, raw: bool) -> JsValue {
         debug_assert!(e.quasis.len() == e.exprs.len() + 1);
 
@@ -385,6 +491,22 @@ impl EvalContext {
                 }
             }
 
+            // UMD wrappers check for an AMD loader with `typeof define === "function"`.
+            // AMD define() calls are converted to the internal module format, so the
+            // AMD branch is always taken.
+            Expr::Bin(BinExpr {
+                op: op @ (op!("==") | op!("===") | op!("!=") | op!("!==")),
+                left,
+                right,
+                ..
+            }) if self.is_typeof_define_check(left, right) => {
+                if matches!(op, op!("==") | op!("===")) {
+                    JsValue::Constant(ConstantValue::True)
+                } else {
+                    JsValue::Constant(ConstantValue::False)
+                }
+            }
+
             Expr::Bin(BinExpr {
                 op: op!("&&"),
                 left,
@@ -631,6 +753,72 @@ impl Analyzer<'_> {
         self.effects.push(effect);
     }
 
+    /// Returns how code is evaluated when the global `eval` or `Function` is
+    /// called.
+    fn eval_kind_of_callee(&self, callee: &Ident) -> Option<EvalKind> {
+        if !is_unresolved(callee, self.eval_context.unresolved_mark) {
+            return None;
+        }
+        match &*callee.sym {
+            "eval" => Some(EvalKind::Direct),
+            "Function" => Some(EvalKind::Function),
+            _ => None,
+        }
+    }
+
+    /// Checks if the `new URL(...)` expression at the end of `ast_path` is the
+    /// first argument of a worker constructor. Returns the kind of the worker
+    /// and whether its script is an ES module.
+    fn worker_kind_of_url_parent(
+        &self,
+        ast_path: &AstNodePath<AstParentNodeRef<'_>>,
+    ) -> Option<(WorkerKind, bool)> {
+        // The path ends with [..., <parent>.args[0], ExprOrSpread.expr, Expr::New]
+        let parent = ast_path.iter().rev().nth(2)?;
+        match parent {
+            AstParentNodeRef::NewExpr(
+                NewExpr {
+                    callee: box Expr::Ident(callee),
+                    args,
+                    ..
+                },
+                NewExprField::Args(0),
+            ) if is_unresolved(callee, self.eval_context.unresolved_mark) => {
+                let kind = match &*callee.sym {
+                    "Worker" => WorkerKind::Worker,
+                    "SharedWorker" => WorkerKind::SharedWorker,
+                    _ => return None,
+                };
+                let options = args.as_ref().and_then(|args| args.get(1));
+                Some((kind, self.is_module_worker_options(options)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks if the options passed to a worker constructor load the script as
+    /// an ES module, i. e. `{ type: "module" }`. Options which can't be
+    /// analyzed are assumed to do so.
+    fn is_module_worker_options(&self, options: Option<&ExprOrSpread>) -> bool {
+        let Some(ExprOrSpread { expr, spread: None }) = options else {
+            return options.is_some();
+        };
+        let JsValue::Object(_, parts) = self.eval_context.eval(expr) else {
+            return true;
+        };
+        let mut module = false;
+        for part in parts {
+            match part {
+                ObjectPart::KeyValue(key, value) if key.as_str() == Some("type") => {
+                    module = value.as_str() != Some("classic");
+                }
+                ObjectPart::KeyValue(..) => {}
+                ObjectPart::Spread(_) => module = true,
+            }
+        }
+        module
+    }
+
     fn check_iife<'ast: 'r, 'r>(
         &mut self,
         n: &'ast CallExpr,
@@ -956,13 +1144,69 @@ impl VisitAstPath for Analyzer<'_> {
     ) {
         // We handle `define(function (require) {})` here.
         if let Callee::Expr(callee) = &n.callee {
-            if n.args.len() == 1 {
-                if let Some(require_var_id) = extract_var_from_umd_factory(callee, &n.args) {
-                    self.add_value(
-                        require_var_id,
-                        JsValue::WellKnownFunction(WellKnownFunctionKind::Require),
-                    );
-                }
+            if let Some(require_var_id) = extract_var_from_umd_factory(callee, &n.args) {
+                self.add_value(
+                    require_var_id,
+                    JsValue::WellKnownFunction(WellKnownFunctionKind::Require),
+                );
+            }
+        }
+
+        if let Callee::Expr(box Expr::Ident(callee)) = &n.callee {
+            if let Some(kind) = self.eval_kind_of_callee(callee) {
+                self.add_effect(Effect::Eval {
+                    kind,
+                    span: n.span(),
+                });
+            }
+        }
+
+        // import.meta.glob("./dir/*.js") and import.meta.resolve("./module.js")
+        if let Callee::Expr(box Expr::Member(MemberExpr {
+            obj:
+                box Expr::MetaProp(MetaPropExpr {
+                    kind: MetaPropKind::ImportMeta,
+                    ..
+                }),
+            prop: MemberProp::Ident(prop),
+            ..
+        })) = &n.callee
+        {
+            if &*prop.sym == "glob" {
+                let args = n
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        if arg.spread.is_none() {
+                            self.eval_context.eval(&arg.expr)
+                        } else {
+                            JsValue::Unknown(None, "spread is not supported")
+                        }
+                    })
+                    .collect();
+                self.add_effect(Effect::ImportMetaGlob {
+                    args,
+                    ast_path: as_parent_path(ast_path),
+                    span: n.span(),
+                });
+                // The whole call is replaced, so `import.meta` itself must not
+                // be rewritten.
+                return;
+            }
+            if let ("resolve", [ExprOrSpread { spread: None, expr }]) = (&*prop.sym, &n.args[..]) {
+                let input = self.eval_context.eval(expr);
+                self.add_effect(Effect::ImportMetaResolve {
+                    input,
+                    ast_path: as_parent_path(ast_path),
+                    span: n.span(),
+                });
+                ast_path.with(
+                    AstParentNodeRef::CallExpr(n, CallExprField::Args(0)),
+                    |ast_path| {
+                        n.args[0].visit_with_path(self, ast_path);
+                    },
+                );
+                return;
             }
         }
 
@@ -1060,6 +1304,15 @@ impl VisitAstPath for Analyzer<'_> {
         new_expr: &'ast NewExpr,
         ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
     ) {
+        if let box Expr::Ident(ref callee) = &new_expr.callee {
+            if let Some(EvalKind::Function) = self.eval_kind_of_callee(callee) {
+                self.add_effect(Effect::Eval {
+                    kind: EvalKind::Function,
+                    span: new_expr.span(),
+                });
+            }
+        }
+
         // new URL("path", import.meta.url)
         if let box Expr::Ident(ref callee) = &new_expr.callee {
             if &*callee.sym == "URL" && is_unresolved(callee, self.eval_context.unresolved_mark) {
@@ -1076,11 +1329,26 @@ impl VisitAstPath for Analyzer<'_> {
                         }) = &*args[1].expr
                         {
                             if &*prop.sym == "url" {
-                                self.add_effect(Effect::Url {
-                                    input: self.eval_context.eval(&args[0].expr),
-                                    ast_path: as_parent_path(ast_path),
-                                    span: new_expr.span(),
-                                });
+                                let input = self.eval_context.eval(&args[0].expr);
+                                let ast_path_kinds = as_parent_path(ast_path);
+                                let span = new_expr.span();
+                                if let Some((kind, module)) =
+                                    self.worker_kind_of_url_parent(ast_path)
+                                {
+                                    self.add_effect(Effect::Worker {
+                                        kind,
+                                        module,
+                                        input,
+                                        ast_path: ast_path_kinds,
+                                        span,
+                                    });
+                                } else {
+                                    self.add_effect(Effect::Url {
+                                        input,
+                                        ast_path: ast_path_kinds,
+                                        span,
+                                    });
+                                }
                             }
                         }
                     }
@@ -1095,6 +1363,25 @@ impl VisitAstPath for Analyzer<'_> {
         member_expr: &'ast MemberExpr,
         ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
     ) {
+        // import.meta.env
+        if let MemberExpr {
+            obj:
+                box Expr::MetaProp(MetaPropExpr {
+                    kind: MetaPropKind::ImportMeta,
+                    ..
+                }),
+            prop: MemberProp::Ident(prop),
+            ..
+        } = member_expr
+        {
+            if &*prop.sym == "env" {
+                self.add_effect(Effect::ImportMetaEnv {
+                    ast_path: as_parent_path(ast_path),
+                    span: member_expr.span(),
+                });
+                return;
+            }
+        }
         self.check_member_expr_for_effects(member_expr, ast_path);
         member_expr.visit_children_with_path(self, ast_path);
     }
@@ -1641,15 +1928,42 @@ impl<'a> Analyzer<'a> {
 
 fn extract_var_from_umd_factory(callee: &Expr, args: &[ExprOrSpread]) -> Option<Id> {
     match unparen(callee) {
+        // define(function (require, exports, module) {})
+        // define(["dep", "require"], function (dep, require) {})
+        //
+        // Both forms may also pass an id as the first argument.
         Expr::Ident(Ident { sym, .. }) => {
             if &**sym == "define" {
-                if let Expr::Fn(FnExpr { function, .. }) = &*args[0].expr {
-                    let params = &*function.params;
-                    if params.len() == 1 {
-                        if let Pat::Ident(param) = &params[0].pat {
-                            if &*param.id.sym == "require" {
-                                return Some(param.to_id());
-                            }
+                let (deps, factory) = match args {
+                    [ExprOrSpread {
+                        spread: None,
+                        expr: box Expr::Array(deps),
+                    }, factory]
+                    | [_, ExprOrSpread {
+                        spread: None,
+                        expr: box Expr::Array(deps),
+                    }, factory] => (Some(deps), factory),
+                    [factory] | [_, factory] => (None, factory),
+                    _ => return None,
+                };
+                if let Expr::Fn(FnExpr { function, .. }) = unparen(&factory.expr) {
+                    let index = match deps {
+                        Some(deps) => deps.elems.iter().position(|elem| {
+                            matches!(
+                                elem,
+                                Some(ExprOrSpread {
+                                    spread: None,
+                                    expr: box Expr::Lit(Lit::Str(s)),
+                                }) if &*s.value == "require"
+                            )
+                        })?,
+                        // Without dependencies the factory is called with `require`, `exports`
+                        // and `module`.
+                        None => 0,
+                    };
+                    if let Some(Pat::Ident(param)) = function.params.get(index).map(|p| &p.pat) {
+                        if deps.is_some() || &*param.id.sym == "require" {
+                            return Some(param.to_id());
                         }
                     }
                 }
@@ -1664,7 +1978,7 @@ fn extract_var_from_umd_factory(callee: &Expr, args: &[ExprOrSpread]) -> Option<
         // treated as a well-known require.
         Expr::Fn(FnExpr { function, .. }) => {
             let params = &*function.params;
-            if params.len() == 1 {
+            if params.len() == 1 && args.len() == 1 {
                 if let Some(FnExpr { function, .. }) =
                     args.first().and_then(|arg| arg.expr.as_fn_expr())
                 {
@@ -1685,3 +1999,108 @@ fn extract_var_from_umd_factory(callee: &Expr, args: &[ExprOrSpread]) -> Option<
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{create_graph, Effect, EvalContext, EvalKind, VarGraph};
+    use crate::{
+        analyzer::{ConstantValue, JsValue, WellKnownFunctionKind},
+        test_utils::with_resolved_program,
+    };
+
+    fn graph(input: &str) -> VarGraph {
+        with_resolved_program(input, Default::default(), |program, unresolved_mark| {
+            create_graph(program, &EvalContext::new(program, unresolved_mark))
+        })
+    }
+
+    /// The value of the variable `name`.
+    fn value<'a>(graph: &'a VarGraph, name: &str) -> &'a JsValue {
+        graph
+            .values
+            .iter()
+            .find(|(id, _)| &*id.0 == name)
+            .map(|(_, value)| value)
+            .unwrap_or_else(|| panic!("{name} has no value"))
+    }
+
+    fn eval_kinds(input: &str) -> Vec<EvalKind> {
+        with_resolved_program(input, Default::default(), |program, unresolved_mark| {
+            let eval_context = EvalContext::new(program, unresolved_mark);
+            create_graph(program, &eval_context)
+                .effects
+                .into_iter()
+                .filter_map(|effect| match effect {
+                    Effect::Eval { kind, .. } => Some(kind),
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn detects_eval() {
+        assert_eq!(
+            eval_kinds(
+                "eval('1');\nnew Function('return 1');\nFunction('a', 'return \
+                 a');\nconsole.log('no eval');"
+            ),
+            vec![EvalKind::Direct, EvalKind::Function, EvalKind::Function]
+        );
+    }
+
+    #[test]
+    fn ignores_shadowed_function_and_member_calls() {
+        assert!(
+            eval_kinds("const Function = class {};\nnew Function();\nwindow.eval('1');").is_empty()
+        );
+    }
+
+    /// Parameters have the argument as an alternative value.
+    fn is_require(value: &JsValue) -> bool {
+        match value {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::Require) => true,
+            JsValue::Alternatives(_, values) => values.iter().any(is_require),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn takes_amd_branch_of_umd_wrappers() {
+        let graph = graph(
+            "const isAmd = typeof define === 'function';\nconst isNotAmd = 'function' != typeof \
+             define;",
+        );
+        assert!(matches!(
+            value(&graph, "isAmd"),
+            JsValue::Constant(ConstantValue::True)
+        ));
+        assert!(matches!(
+            value(&graph, "isNotAmd"),
+            JsValue::Constant(ConstantValue::False)
+        ));
+    }
+
+    #[test]
+    fn finds_require_in_amd_factories() {
+        for input in [
+            "define(function (req, exports, module) {});",
+            "define('id', function (require) {});",
+            "define(['dep', 'require'], function (dep, req) {});",
+            "define('id', ['require'], function (req) {});",
+        ] {
+            let graph = graph(input);
+            let is_require = graph
+                .values
+                .iter()
+                .any(|(id, value)| (&*id.0 == "req" || &*id.0 == "require") && is_require(value));
+            // Without dependencies, the first parameter is only require when
+            // it's named like it.
+            assert_eq!(
+                is_require,
+                !input.starts_with("define(function (req"),
+                "{input}"
+            );
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::BTreeMap, fmt::Display, mem::take};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    mem::take,
+};
 
 use indexmap::{IndexMap, IndexSet};
 use once_cell::sync::Lazy;
@@ -7,6 +11,7 @@ use swc_core::ecma::{
     atoms::{js_word, JsWord},
     visit::{Visit, VisitWith},
 };
+use turbopack_core::reference_type::{EcmaScriptModulesReferenceSubType, ImportWithType};
 
 use super::{JsValue, ModuleValue};
 use crate::utils::unparen;
@@ -25,6 +30,9 @@ static ANNOTATION_TRANSITION: Lazy<JsWord> = Lazy::new(|| "transition".into());
 /// Changes the chunking type for the annotated import
 static ANNOTATION_CHUNKING_TYPE: Lazy<JsWord> = Lazy::new(|| "chunking-type".into());
 
+/// The module type requested by the `type` import attribute
+static ANNOTATION_TYPE: Lazy<JsWord> = Lazy::new(|| "type".into());
+
 impl ImportAnnotations {
     fn insert(&mut self, key: JsWord, value: Option<JsWord>) {
         self.map.insert(key, value);
@@ -47,6 +55,29 @@ impl ImportAnnotations {
             .get(&ANNOTATION_CHUNKING_TYPE)
             .and_then(|w| w.as_ref().map(|w| &**w))
     }
+
+    /// Returns the content on the type annotation
+    pub fn module_type(&self) -> Option<&str> {
+        self.map
+            .get(&ANNOTATION_TYPE)
+            .and_then(|w| w.as_ref().map(|w| &**w))
+    }
+
+    /// Returns the reference sub type which routes the import to the module
+    /// type requested by the type annotation. Unknown types are ignored and
+    /// the module type is determined by the file as usual.
+    pub fn reference_sub_type(&self) -> EcmaScriptModulesReferenceSubType {
+        import_with_type_to_sub_type(self.module_type())
+    }
+}
+
+/// Maps the value of a `type` import attribute to a reference sub type.
+pub(crate) fn import_with_type_to_sub_type(ty: Option<&str>) -> EcmaScriptModulesReferenceSubType {
+    match ty {
+        Some("json") => EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Json),
+        Some("css") => EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Css),
+        _ => EcmaScriptModulesReferenceSubType::Undefined,
+    }
 }
 
 impl Display for ImportAnnotations {
@@ -99,6 +130,9 @@ pub(crate) struct ImportMap {
 
     /// True, when the module has exports
     has_exports: bool,
+
+    /// Identifiers that are exported with `export { ident }`
+    exported_idents: HashSet<Id>,
 }
 
 impl ImportMap {
@@ -138,6 +172,16 @@ impl ImportMap {
         None
     }
 
+    /// Returns all imported bindings with the index of their reference and
+    /// the imported export, which is None for namespace imports.
+    pub fn bindings(&self) -> impl Iterator<Item = (&Id, usize, Option<&str>)> {
+        self.imports
+            .iter()
+            .filter(|(_, (_, sym))| &**sym != "__turbopack_module_id__")
+            .map(|(id, (i, sym))| (id, *i, Some(&**sym)))
+            .chain(self.namespace_imports.iter().map(|(id, i)| (id, *i, None)))
+    }
+
     pub fn references(&self) -> impl Iterator<Item = (&JsWord, &ImportAnnotations)> {
         self.references.iter().map(|(m, a)| (m, a))
     }
@@ -146,6 +190,25 @@ impl ImportMap {
         self.reexports.iter().map(|(i, r)| (*i, r))
     }
 
+    /// Returns true when the reference at the index is only used by named
+    /// imports like `import { a, b as c } from "x"` and not by namespace
+    /// imports, re-exports or exports of the imported bindings.
+    pub fn has_only_named_imports(&self, reference: usize) -> bool {
+        let mut has_named_imports = false;
+        for (id, (i, sym)) in self.imports.iter() {
+            if *i != reference {
+                continue;
+            }
+            if self.exported_idents.contains(id) || &**sym == "__turbopack_module_id__" {
+                return false;
+            }
+            has_named_imports = true;
+        }
+        has_named_imports
+            && !self.namespace_imports.values().any(|i| *i == reference)
+            && !self.reexports.iter().any(|(i, _)| *i == reference)
+    }
+
     /// Analyze ES import
     pub(super) fn analyze(m: &Program) -> Self {
         let mut data = ImportMap::default();
@@ -175,6 +238,32 @@ impl<'a> Analyzer<'a> {
             i
         }
     }
+
+    /// Adds the import attributes of `import data from "./data.json" with {
+    /// type: "json" }` (or the older `assert { type: "json" }`) to the
+    /// annotations of the import.
+    fn add_import_attributes(&mut self, attributes: Option<&ObjectLit>) {
+        let Some(attributes) = attributes else {
+            return;
+        };
+        for prop in attributes.props.iter() {
+            let PropOrSpread::Prop(prop) = prop else {
+                continue;
+            };
+            let Prop::KeyValue(KeyValueProp { key, value }) = &**prop else {
+                continue;
+            };
+            let key = match key {
+                PropName::Ident(ident) => ident.sym.clone(),
+                PropName::Str(str) => str.value.clone(),
+                _ => continue,
+            };
+            if let Expr::Lit(Lit::Str(value)) = &**value {
+                self.current_annotations
+                    .insert(key, Some(value.value.clone()));
+            }
+        }
+    }
 }
 
 fn to_word(name: &ModuleExportName) -> JsWord {
@@ -220,6 +309,7 @@ impl Visit for Analyzer<'_> {
     }
 
     fn visit_import_decl(&mut self, import: &ImportDecl) {
+        self.add_import_attributes(import.asserts.as_deref());
         let i = self.ensure_reference(import.src.value.clone());
         for s in &import.specifiers {
             let (local, orig_sym) = match s {
@@ -242,13 +332,26 @@ impl Visit for Analyzer<'_> {
 
     fn visit_export_all(&mut self, export: &ExportAll) {
         self.data.has_exports = true;
+        self.add_import_attributes(export.asserts.as_deref());
         let i = self.ensure_reference(export.src.value.clone());
         self.data.reexports.push((i, Reexport::Star));
     }
 
     fn visit_named_export(&mut self, export: &NamedExport) {
         self.data.has_exports = true;
+        if export.src.is_none() {
+            for spec in export.specifiers.iter() {
+                if let ExportSpecifier::Named(ExportNamedSpecifier {
+                    orig: ModuleExportName::Ident(ident),
+                    ..
+                }) = spec
+                {
+                    self.data.exported_idents.insert(ident.to_id());
+                }
+            }
+        }
         if let Some(ref src) = export.src {
+            self.add_import_attributes(export.asserts.as_deref());
             let i = self.ensure_reference(src.value.clone());
             for spec in export.specifiers.iter() {
                 match spec {
@@ -303,3 +406,88 @@ fn orig_name(n: &ModuleExportName) -> JsWord {
         ModuleExportName::Str(v) => v.value.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::ecma::parser::{EsConfig, Syntax};
+    use turbopack_core::reference_type::{EcmaScriptModulesReferenceSubType, ImportWithType};
+
+    use super::ImportMap;
+    use crate::test_utils::with_program;
+
+    fn analyze(input: &str) -> ImportMap {
+        let syntax = Syntax::Es(EsConfig {
+            import_assertions: true,
+            ..Default::default()
+        });
+        with_program(input, syntax, ImportMap::analyze)
+    }
+
+    #[test]
+    fn only_named_imports() {
+        let map = analyze(
+            "import { a, b as c } from './named';\nimport * as ns from './namespace';\nimport { d \
+             } from './exported';\nexport { d };\nexport { e } from './reexported';\nimport { f } \
+             from './reexported';\nimport './side-effect';",
+        );
+        let index = |module: &str| map.references().position(|(m, _)| &**m == module).unwrap();
+        assert!(map.has_only_named_imports(index("./named")));
+        assert!(!map.has_only_named_imports(index("./namespace")));
+        assert!(!map.has_only_named_imports(index("./exported")));
+        assert!(!map.has_only_named_imports(index("./reexported")));
+        assert!(!map.has_only_named_imports(index("./side-effect")));
+    }
+
+    #[test]
+    fn import_attributes_select_module_type() {
+        let map = analyze(
+            "import data from './data.json' assert { type: 'json' };
+export { default as              styles } from './styles.css' assert { type: 'css' };
+import wasm from              './module.wasm' assert { type: 'webassembly' };
+import plain from './plain.json';",
+        );
+        let sub_types = map
+            .references()
+            .map(|(module, annotations)| (module.to_string(), annotations.reference_sub_type()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sub_types,
+            [
+                (
+                    "./data.json".to_string(),
+                    EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Json)
+                ),
+                (
+                    "./styles.css".to_string(),
+                    EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Css)
+                ),
+                (
+                    "./module.wasm".to_string(),
+                    EcmaScriptModulesReferenceSubType::Undefined
+                ),
+                (
+                    "./plain.json".to_string(),
+                    EcmaScriptModulesReferenceSubType::Undefined
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn bindings_of_imports() {
+        let map = analyze("import a, { b as c } from './a';\nimport * as ns from './ns';");
+        let mut bindings = map
+            .bindings()
+            .map(|(id, _, export)| (id.0.to_string(), export.map(str::to_string)))
+            .collect::<Vec<_>>();
+        bindings.sort();
+        assert_eq!(
+            bindings,
+            [
+                ("a".to_string(), Some("default".to_string())),
+                ("c".to_string(), Some("b".to_string())),
+                ("ns".to_string(), None),
+            ]
+        );
+    }
+}
@@ -1129,6 +1129,7 @@ impl JsValue {
                     ),
                     WellKnownFunctionKind::Require => ("require".to_string(), "The require method from CommonJS"),
                     WellKnownFunctionKind::RequireResolve => ("require.resolve".to_string(), "The require.resolve method from CommonJS"),
+                    WellKnownFunctionKind::RequireContext => ("require.context".to_string(), "The require.context method from webpack: https://webpack.js.org/guides/dependency-management/#requirecontext"),
                     WellKnownFunctionKind::Define => ("define".to_string(), "The define method from AMD"),
                     WellKnownFunctionKind::FsReadMethod(name) => (
                         format!("fs.{name}"),
@@ -2468,6 +2469,7 @@ pub enum WellKnownFunctionKind {
     Import,
     Require,
     RequireResolve,
+    RequireContext,
     Define,
     FsReadMethod(JsWord),
     PathToFileUrl,
@@ -2561,7 +2563,7 @@ mod tests {
     use std::{mem::take, path::PathBuf, time::Instant};
 
     use swc_core::{
-        common::Mark,
+        common::{FileName, Mark},
         ecma::{
             ast::EsVersion, parser::parse_file_as_program, transforms::base::resolver,
             visit::VisitMutWith,
@@ -2577,7 +2579,9 @@ mod tests {
     };
 
     use super::{
-        graph::{create_graph, ConditionalKind, Effect, EffectArg, EvalContext, VarGraph},
+        graph::{
+            create_graph, ConditionalKind, Effect, EffectArg, EvalContext, VarGraph, WorkerKind,
+        },
         linker::link,
         JsValue,
     };
@@ -2830,6 +2834,77 @@ mod tests {
         .unwrap();
     }
 
+    fn worker_effects(input: &str) -> Vec<(WorkerKind, bool)> {
+        run_test(false, |cm, _| {
+            let fm = cm.new_source_file(FileName::Anon, input.to_string());
+            let mut m = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap();
+            let unresolved_mark = Mark::new();
+            m.visit_mut_with(&mut resolver(unresolved_mark, Mark::new(), false));
+            let eval_context = EvalContext::new(&m, unresolved_mark);
+            Ok(create_graph(&m, &eval_context)
+                .effects
+                .into_iter()
+                .filter_map(|effect| match effect {
+                    Effect::Worker { kind, module, .. } => Some((kind, module)),
+                    _ => None,
+                })
+                .collect())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn classic_workers() {
+        assert_eq!(
+            worker_effects(
+                r#"
+                new Worker(new URL("./a.js", import.meta.url));
+                new SharedWorker(new URL("./b.js", import.meta.url), { name: "b" });
+                new Worker(new URL("./c.js", import.meta.url), { type: "classic" });
+                "#
+            ),
+            vec![
+                (WorkerKind::Worker, false),
+                (WorkerKind::SharedWorker, false),
+                (WorkerKind::Worker, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn module_workers() {
+        assert_eq!(
+            worker_effects(
+                r#"
+                new Worker(new URL("./a.js", import.meta.url), { type: "module" });
+                new SharedWorker(new URL("./b.js", import.meta.url), options);
+                "#
+            ),
+            vec![(WorkerKind::Worker, true), (WorkerKind::SharedWorker, true)]
+        );
+    }
+
+    #[test]
+    fn worklets_are_not_workers() {
+        // Worklet modules are referenced like any other `new URL(...)`.
+        assert_eq!(
+            worker_effects(
+                r#"
+                CSS.paintWorklet.addModule(new URL("./c.js", import.meta.url));
+                context.audioWorklet.addModule(new URL("./d.js", import.meta.url));
+                "#
+            ),
+            vec![]
+        );
+    }
+
     async fn resolve(var_graph: &VarGraph, val: JsValue) -> JsValue {
         turbo_tasks_testing::VcStorage::with(link(
             var_graph,
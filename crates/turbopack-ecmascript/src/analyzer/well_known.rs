@@ -362,9 +362,14 @@ pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) ->
         (WellKnownFunctionKind::Require, Some("resolve")) => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve)
         }
+        (WellKnownFunctionKind::Require, Some("context")) => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::RequireContext)
+        }
         (WellKnownFunctionKind::Require, Some("cache")) => {
             JsValue::WellKnownObject(WellKnownObjectKind::RequireCache)
         }
+        // UMD wrappers check `define.amd` before calling define()
+        (WellKnownFunctionKind::Define, Some("amd")) => JsValue::object(Vec::new()),
         (WellKnownFunctionKind::NodeStrongGlobalize, Some("SetRootDir")) => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::NodeStrongGlobalizeSetRootDir)
         }
@@ -457,8 +462,9 @@ pub fn fs_module_member(kind: WellKnownObjectKind, prop: JsValue) -> JsValue {
         match (kind, word) {
             (
                 ..,
-                "realpath" | "realpathSync" | "stat" | "statSync" | "existsSync"
-                | "createReadStream" | "exists" | "open" | "openSync" | "readFile" | "readFileSync",
+                "realpath" | "realpathSync" | "stat" | "statSync" | "lstat" | "lstatSync"
+                | "existsSync" | "access" | "accessSync" | "createReadStream" | "exists" | "open"
+                | "openSync" | "readFile" | "readFileSync",
             ) => {
                 return JsValue::WellKnownFunction(WellKnownFunctionKind::FsReadMethod(
                     word.into(),
@@ -0,0 +1,219 @@
+use anyhow::Result;
+use swc_core::{
+    base::SwcComments,
+    common::comments::{Comment, CommentKind, Comments},
+};
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc},
+    chunk::CommentsPolicy,
+    reference::{AssetReference, AssetReferenceVc},
+    resolve::{ResolveResult, ResolveResultVc},
+};
+
+use super::EcmascriptChunkVc;
+
+/// Legal comments are `/*! ... */` comments and comments that contain
+/// `@license` or `@preserve`. They are usually required to be kept by the
+/// license of the code.
+pub(crate) fn is_legal_comment(comment: &Comment) -> bool {
+    comment.text.starts_with('!')
+        || comment.text.contains("@license")
+        || comment.text.contains("@preserve")
+}
+
+/// Returns the comments that are emitted with the code for the given policy.
+///
+/// The comments are always copied, as emitting code takes the comments out of
+/// the map.
+pub(crate) fn emitted_comments(
+    comments: &SwcComments,
+    policy: CommentsPolicy,
+) -> Option<SwcComments> {
+    match policy {
+        CommentsPolicy::None | CommentsPolicy::ExtractLegal => None,
+        CommentsPolicy::Legal => Some(filter_comments(comments, is_legal_comment)),
+        CommentsPolicy::All => Some(filter_comments(comments, |_| true)),
+    }
+}
+
+/// Returns the legal comments as they are written in the code, ordered by
+/// their position.
+pub(crate) fn legal_comments(comments: &SwcComments) -> Vec<String> {
+    let mut result = Vec::new();
+    for entry in comments.leading.iter().chain(comments.trailing.iter()) {
+        for comment in entry.value().iter().filter(|c| is_legal_comment(c)) {
+            result.push((comment.span.lo, comment_to_string(comment)));
+        }
+    }
+    result.sort();
+    result.into_iter().map(|(_, text)| text).collect()
+}
+
+fn comment_to_string(comment: &Comment) -> String {
+    match comment.kind {
+        CommentKind::Line => format!("//{}", comment.text),
+        CommentKind::Block => format!("/*{}*/", comment.text),
+    }
+}
+
+fn filter_comments(comments: &SwcComments, filter: impl Fn(&Comment) -> bool) -> SwcComments {
+    let result = SwcComments::default();
+    for entry in comments.leading.iter() {
+        let kept: Vec<_> = entry
+            .value()
+            .iter()
+            .filter(|c| filter(c))
+            .cloned()
+            .collect();
+        if !kept.is_empty() {
+            result.add_leading_comments(*entry.key(), kept);
+        }
+    }
+    for entry in comments.trailing.iter() {
+        let kept: Vec<_> = entry
+            .value()
+            .iter()
+            .filter(|c| filter(c))
+            .cloned()
+            .collect();
+        if !kept.is_empty() {
+            result.add_trailing_comments(*entry.key(), kept);
+        }
+    }
+    result
+}
+
+/// The legal comments of the modules of an ecmascript chunk, which are
+/// extracted into a `.LICENSE.txt` file next to the chunk.
+#[turbo_tasks::value]
+pub struct EcmascriptChunkLicenseAsset {
+    chunk: EcmascriptChunkVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptChunkLicenseAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.chunk.path().append(".LICENSE.txt")
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let comments = self.chunk.chunk_content().legal_comments().await?;
+        let mut content = comments.join("\n\n");
+        content.push('\n');
+        Ok(File::from(content).into())
+    }
+}
+
+/// A reference to a [`EcmascriptChunkLicenseAsset`], so that it is emitted
+/// together with the chunk.
+#[turbo_tasks::value]
+pub struct EcmascriptChunkLicenseAssetReference {
+    chunk: EcmascriptChunkVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkLicenseAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(chunk: EcmascriptChunkVc) -> Self {
+        EcmascriptChunkLicenseAssetReference { chunk }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for EcmascriptChunkLicenseAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        let asset = EcmascriptChunkLicenseAsset { chunk: self.chunk }
+            .cell()
+            .into();
+        ResolveResult::asset(asset).cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for EcmascriptChunkLicenseAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "license comments of {}",
+            self.chunk.path().to_string().await?
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        base::SwcComments,
+        common::{FileName, SourceMap},
+        ecma::parser::parse_file_as_program,
+    };
+    use turbopack_core::chunk::CommentsPolicy;
+
+    use super::{emitted_comments, legal_comments};
+
+    const INPUT: &str = r#"/*! Copyright */
+// a plain comment
+export const a = 1; // @license MIT
+/** @preserve kept */
+export const b = 2; /* another plain comment */
+"#;
+
+    fn parse_comments(input: &str) -> SwcComments {
+        let cm = SourceMap::default();
+        let fm = cm.new_source_file(FileName::Anon, input.to_string());
+        let comments = SwcComments::default();
+        parse_file_as_program(
+            &fm,
+            Default::default(),
+            Default::default(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .unwrap();
+        comments
+    }
+
+    fn comment_texts(comments: &SwcComments) -> Vec<String> {
+        let mut texts: Vec<_> = comments
+            .leading
+            .iter()
+            .chain(comments.trailing.iter())
+            .flat_map(|entry| entry.value().clone())
+            .map(|comment| (comment.span.lo, comment.text.to_string()))
+            .collect();
+        texts.sort();
+        texts.into_iter().map(|(_, text)| text).collect()
+    }
+
+    #[test]
+    fn collects_legal_comments_in_order() {
+        assert_eq!(
+            legal_comments(&parse_comments(INPUT)),
+            vec![
+                "/*! Copyright */",
+                "// @license MIT",
+                "/** @preserve kept */"
+            ]
+        );
+    }
+
+    #[test]
+    fn emits_comments_by_policy() {
+        let comments = parse_comments(INPUT);
+        assert!(emitted_comments(&comments, CommentsPolicy::None).is_none());
+        assert!(emitted_comments(&comments, CommentsPolicy::ExtractLegal).is_none());
+
+        let legal = emitted_comments(&comments, CommentsPolicy::Legal).unwrap();
+        assert_eq!(
+            comment_texts(&legal),
+            vec!["! Copyright ", " @license MIT", "* @preserve kept "]
+        );
+        let all = emitted_comments(&comments, CommentsPolicy::All).unwrap();
+        assert_eq!(comment_texts(&all).len(), 5);
+    }
+}
@@ -7,19 +7,23 @@ use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{
-        ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
-        ChunkableAssetVc, ChunkingContext, ChunkingContextVc, ChunksVc,
+        hinted_chunks, AsyncLoadingHint, ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc,
+        ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext, ChunkingContextVc, ChunksVc,
+        CommonJsInterop,
     },
-    reference::AssetReferencesVc,
+    reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
+    resolve::{ResolveResult, ResolveResultVc},
 };
 
 use crate::{
     chunk::{
         EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
-        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
-        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+        EcmascriptChunkItemOptions, EcmascriptChunkItemVc, EcmascriptChunkPlaceable,
+        EcmascriptChunkPlaceableVc, EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
     },
+    references::esm::is_async_module,
     utils::{stringify_module_id, stringify_str},
+    EcmascriptModuleAssetVc,
 };
 
 /// The manifest loader item is shipped in the same chunk that uses the dynamic
@@ -115,20 +119,53 @@ impl EcmascriptChunkItem for ManifestLoaderItem {
             .await?
             .ok_or_else(|| anyhow!("asset is not placeable in ecmascript chunk"))?;
         let dynamic_id = &*placeable.as_chunk_item(self.context).id().await?;
+        let import_args = match *self.context.commonjs_interop().await? {
+            CommonJsInterop::Babel => "",
+            CommonJsInterop::Node => ", false, true",
+        };
+
+        // Async modules need to finish their evaluation before the import resolves.
+        // With lazy compilation the module graph of the imported module is not
+        // known at this point, so it's checked at runtime.
+        let is_async = if *self.context.lazy_compilation().await? {
+            true
+        } else if let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+            *is_async_module(module).await?
+        } else {
+            false
+        };
 
         // TODO: a dedent macro with expression interpolation would be awesome.
-        write!(
-            code,
-            "
+        if is_async {
+            write!(
+                code,
+                "
 __turbopack_export_value__((__turbopack_import__) => {{
     return __turbopack_load__({chunk_server_path}).then(() => {{
         return __turbopack_require__({item_id});
-    }}).then(() => __turbopack_import__({dynamic_id}));
+    }}).then(() => {{
+        const namespace = __turbopack_import__({dynamic_id}{import_args});
+        return Promise.resolve(__turbopack_cache__[{dynamic_id}].async).then(() => namespace);
+    }});
 }});",
-            chunk_server_path = stringify_str(chunk_server_path),
-            item_id = stringify_module_id(item_id),
-            dynamic_id = stringify_module_id(dynamic_id),
-        )?;
+                chunk_server_path = stringify_str(chunk_server_path),
+                item_id = stringify_module_id(item_id),
+                dynamic_id = stringify_module_id(dynamic_id),
+            )?;
+        } else {
+            write!(
+                code,
+                "
+__turbopack_export_value__((__turbopack_import__) => {{
+    return __turbopack_load__({chunk_server_path}).then(() => {{
+        return __turbopack_require__({item_id});
+    }}).then(() => __turbopack_import__({dynamic_id}{import_args}));
+}});",
+                chunk_server_path = stringify_str(chunk_server_path),
+                item_id = stringify_module_id(item_id),
+                dynamic_id = stringify_module_id(dynamic_id),
+            )?;
+        }
 
         Ok(EcmascriptChunkItemContent {
             inner_code: code.into(),
@@ -251,6 +288,7 @@ impl EcmascriptChunkItem for ManifestChunkItem {
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
         let chunks = self.manifest.chunks().await?;
+        let output_root = self.context.output_root().await?;
 
         let mut chunk_server_paths = IndexSet::new();
         for chunk in chunks.iter() {
@@ -259,7 +297,6 @@ impl EcmascriptChunkItem for ManifestChunkItem {
             // item is one of several that are contained in that chunk file.
             let chunk_path = &*chunk.path().await?;
             // The pathname is the file path necessary to load the chunk from the server.
-            let output_root = self.context.output_root().await?;
             let chunk_server_path = if let Some(path) = output_root.get_path_to(chunk_path) {
                 path
             } else {
@@ -272,11 +309,35 @@ impl EcmascriptChunkItem for ManifestChunkItem {
             chunk_server_paths.insert(chunk_server_path.to_string());
         }
 
+        // The chunks of dynamic imports with prefetch or preload hints in the
+        // loaded chunks are fetched ahead of time, like the hints of a page.
+        let mut hints = Vec::new();
+        for (asset, hint) in hinted_chunks(&chunks).await? {
+            let hint = match hint {
+                AsyncLoadingHint::None => continue,
+                AsyncLoadingHint::Prefetch => "prefetch",
+                AsyncLoadingHint::Preload => "preload",
+            };
+            if let Some(path) = output_root.get_path_to(&*asset.path().await?) {
+                if !chunk_server_paths.contains(path) {
+                    hints.push((path.to_string(), hint));
+                }
+            }
+        }
+
         let mut code = b"const chunks = [\n".to_vec();
         for pathname in chunk_server_paths {
             writeln!(code, "    {},", stringify_str(&pathname))?;
         }
         writeln!(code, "];")?;
+        for (pathname, hint) in hints.iter() {
+            writeln!(
+                code,
+                "__turbopack_hint__({}, {});",
+                stringify_str(pathname),
+                stringify_str(hint)
+            )?;
+        }
 
         // TODO: a dedent macro would be awesome.
         write!(
@@ -287,6 +348,10 @@ __turbopack_export_value__(Promise.all(chunks.map(__turbopack_load__)));"
 
         Ok(EcmascriptChunkItemContent {
             inner_code: code.into(),
+            options: EcmascriptChunkItemOptions {
+                hints: !hints.is_empty(),
+                ..Default::default()
+            },
             ..Default::default()
         }
         .into())
@@ -297,6 +362,12 @@ __turbopack_export_value__(Promise.all(chunks.map(__turbopack_load__)));"
 impl ChunkItem for ManifestChunkItem {
     #[turbo_tasks::function]
     async fn references(&self) -> Result<AssetReferencesVc> {
+        if *self.context.lazy_compilation().await? {
+            return Ok(AssetReferencesVc::cell(vec![
+                LazyManifestChunksReferenceVc::new(self.manifest).into(),
+            ]));
+        }
+
         let chunks = self.manifest.chunks();
 
         Ok(AssetReferencesVc::cell(
@@ -310,3 +381,45 @@ impl ChunkItem for ManifestChunkItem {
         ))
     }
 }
+
+/// References the chunks that are loaded by a manifest chunk. The reference is
+/// not chunkable, so the chunks are only computed when the reference is
+/// resolved, e. g. when the manifest chunk is served for the first time.
+#[turbo_tasks::value]
+struct LazyManifestChunksReference {
+    manifest: ManifestChunkAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl LazyManifestChunksReferenceVc {
+    #[turbo_tasks::function]
+    fn new(manifest: ManifestChunkAssetVc) -> Self {
+        Self::cell(LazyManifestChunksReference { manifest })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for LazyManifestChunksReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        let chunks = self
+            .manifest
+            .chunks()
+            .await?
+            .iter()
+            .map(|chunk| chunk.as_asset())
+            .collect();
+        Ok(ResolveResult::assets(chunks).into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for LazyManifestChunksReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "lazy chunks of {}",
+            self.manifest.path().to_string().await?
+        )))
+    }
+}
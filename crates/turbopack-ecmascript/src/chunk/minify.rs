@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use swc_core::{
+    base::SwcComments,
+    common::{comments::Comments, input::StringInput, FileName, Globals, Mark, SourceMap, GLOBALS},
+    ecma::{
+        ast::{EsVersion, Program},
+        codegen::{text_writer::JsWriter, Emitter},
+        minifier::{
+            optimize,
+            option::{
+                terser::TerserCompressorOptions, ExtraOptions, MangleOptions,
+                MinifyOptions as SwcOptions,
+            },
+        },
+        parser::{lexer::Lexer, EsConfig, Parser, Syntax},
+        transforms::base::{fixer::fixer, resolver},
+        visit::FoldWith,
+    },
+};
+use turbo_tasks::Value;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    chunk::{CommentsPolicy, MinifyOptions, MinifyOptionsVc},
+    code_builder::{CodeBuilder, CodeVc},
+    source_map::GenerateSourceMap,
+};
+
+use super::comments::emitted_comments;
+use crate::parse::ParseResultSourceMap;
+
+/// Minifies the code of a chunk. The source map of the minified code is
+/// chained with the source map of the original code, so it still points to
+/// the original sources of the chunk items. Comments that are kept by the
+/// comments policy survive the minification.
+#[turbo_tasks::function]
+pub async fn minify(
+    path: FileSystemPathVc,
+    code: CodeVc,
+    options: MinifyOptionsVc,
+    comments_policy: Value<CommentsPolicy>,
+) -> Result<CodeVc> {
+    let path = path.await?;
+    let options = options.await?;
+    let original_source_map = code.generate_source_map();
+    let code = code.await?;
+    let source = code.source_code().to_str()?.into_owned();
+
+    let cm = Arc::new(SourceMap::default());
+    let fm = cm.new_source_file(FileName::Custom(path.path.clone()), source);
+
+    // Legal comments that are extracted from the chunk items are referenced
+    // by a legal comment in the chunk, which needs to be kept too.
+    let comments_policy = match comments_policy.into_value() {
+        CommentsPolicy::ExtractLegal => CommentsPolicy::Legal,
+        policy => policy,
+    };
+    let comments = SwcComments::default();
+
+    let (minified, mappings) = GLOBALS.set(&Globals::new(), || {
+        let lexer = Lexer::new(
+            Syntax::Es(EsConfig::default()),
+            EsVersion::latest(),
+            StringInput::from(&*fm),
+            Some(&comments),
+        );
+        let mut parser = Parser::new_from(lexer);
+        let program = match parser.parse_program() {
+            Ok(program) => program,
+            Err(err) => bail!(
+                "failed to parse the chunk {} for minification: {}",
+                path.path,
+                err.kind().msg()
+            ),
+        };
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        let program = program.fold_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        let program = optimize(
+            program,
+            cm.clone(),
+            Some(&comments),
+            None,
+            &swc_options(&options, cm.clone()),
+            &ExtraOptions {
+                unresolved_mark,
+                top_level_mark,
+            },
+        );
+        let program: Program = program.fold_with(&mut fixer(None));
+
+        let comments = emitted_comments(&comments, comments_policy);
+        let mut bytes = vec![];
+        let mut mappings = vec![];
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: comments.as_ref().map(|c| c as &dyn Comments),
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, Some(&mut mappings)),
+        };
+        emitter
+            .emit_program(&program)
+            .context("failed to emit the minified chunk")?;
+
+        Ok((bytes, mappings))
+    })?;
+
+    let source_map = ParseResultSourceMap::new(cm, mappings)
+        .with_original_source_map(original_source_map)
+        .cell();
+    let mut builder = CodeBuilder::default();
+    builder.push_source(&minified.into(), Some(source_map.as_generate_source_map()));
+    Ok(builder.build().cell())
+}
+
+/// The options of the swc minifier. Compression uses the defaults of terser,
+/// as the defaults of [swc_core::ecma::minifier::option::CompressOptions] turn
+/// most of the transforms off.
+fn swc_options(options: &MinifyOptions, cm: Arc<SourceMap>) -> SwcOptions {
+    SwcOptions {
+        compress: (options.compress_passes > 0).then(|| {
+            TerserCompressorOptions {
+                passes: options.compress_passes,
+                ..Default::default()
+            }
+            .into_config(cm)
+        }),
+        mangle: options.mangle.then(|| MangleOptions {
+            reserved: options
+                .reserved_names
+                .iter()
+                .map(|name| name.as_str().into())
+                .collect(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compresses_with_terser_defaults() {
+        let options = swc_options(&MinifyOptions::default(), Default::default());
+        let compress = options.compress.unwrap();
+        assert_eq!(compress.passes, 1);
+        assert!(compress.dead_code);
+        assert!(compress.unused);
+        assert!(options.mangle.is_some());
+
+        let options = swc_options(
+            &MinifyOptions {
+                mangle: false,
+                compress_passes: 0,
+                reserved_names: Vec::new(),
+            },
+            Default::default(),
+        );
+        assert!(options.compress.is_none());
+        assert!(options.mangle.is_none());
+    }
+}
@@ -1,20 +1,23 @@
+pub mod comments;
 pub mod loader;
+pub(crate) mod minify;
 pub(crate) mod optimize;
+pub mod runtime;
 pub mod source_map;
 
 use std::{fmt::Write, io::Write as _, slice::Iter};
 
 use anyhow::{anyhow, bail, Result};
 use indexmap::{IndexMap, IndexSet};
-use indoc::indoc;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
-    primitives::{JsonValueVc, StringReadRef, StringVc, StringsVc, UsizeVc},
+    primitives::{BoolVc, StringReadRef, StringVc, StringsVc, UsizeVc},
     trace::TraceRawVcs,
-    TryJoinIterExt, ValueToString, ValueToStringVc,
+    TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
 use turbo_tasks_fs::{
-    embed_file, rope::Rope, File, FileContent, FileSystemPathOptionVc, FileSystemPathVc,
+    rope::{Rope, RopeBuilder, RopeReadRef, RopeTemplate, RopeVc, TemplatePart::*},
+    to_sys_path, File, FileSystemPathOptionVc, FileSystemPathVc,
 };
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64, DeterministicHasher, Xxh3Hash64Hasher};
 use turbopack_core::{
@@ -22,9 +25,12 @@ use turbopack_core::{
     chunk::{
         chunk_content, chunk_content_split,
         optimize::{ChunkOptimizerVc, OptimizableChunk, OptimizableChunkVc},
-        Chunk, ChunkContentResult, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkItemVc,
-        ChunkReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
-        ChunkingContextVc, FromChunkableAsset, ModuleId, ModuleIdReadRef, ModuleIdVc, ModuleIdsVc,
+        profile::UsageFilterVc,
+        AsyncLoadingHint, Chunk, ChunkBannerVc, ChunkContentResult, ChunkGroupReferenceVc,
+        ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetVc, ChunkingContext, ChunkingContextVc, CommentsPolicy, FromChunkableAsset,
+        ModuleId, ModuleIdReadRef, ModuleIdVc, ModuleIdsVc, OptionMinifyOptionsVc, OutputFormat,
+        RuntimeAssetReferenceVc, RuntimeChunking,
     },
     code_builder::{Code, CodeBuilder, CodeReadRef, CodeVc},
     environment::{ChunkLoading, EnvironmentVc},
@@ -42,8 +48,11 @@ use turbopack_core::{
 };
 
 use self::{
+    comments::EcmascriptChunkLicenseAssetReferenceVc,
     loader::{ManifestChunkAssetVc, ManifestLoaderItemVc},
+    minify::minify,
     optimize::EcmascriptChunkOptimizerVc,
+    runtime::{runtime_code, EcmascriptRuntimeAssetVc},
     source_map::EcmascriptChunkSourceMapAssetReferenceVc,
 };
 use crate::{
@@ -58,6 +67,8 @@ pub struct EcmascriptChunk {
     main_entries: EcmascriptChunkPlaceablesVc,
     omit_entries: Option<EcmascriptChunkPlaceablesVc>,
     evaluate: Option<EcmascriptChunkEvaluateVc>,
+    /// Only the modules which match the filter are part of the chunk.
+    usage: Option<UsageFilterVc>,
 }
 
 #[turbo_tasks::value_impl]
@@ -68,12 +79,14 @@ impl EcmascriptChunkVc {
         main_entries: EcmascriptChunkPlaceablesVc,
         omit_entries: Option<EcmascriptChunkPlaceablesVc>,
         evaluate: Option<EcmascriptChunkEvaluateVc>,
+        usage: Option<UsageFilterVc>,
     ) -> Self {
         EcmascriptChunk {
             context,
             main_entries,
             omit_entries,
             evaluate,
+            usage,
         }
         .cell()
     }
@@ -85,6 +98,7 @@ impl EcmascriptChunkVc {
             EcmascriptChunkPlaceablesVc::cell(vec![main_entry]),
             None,
             None,
+            None,
         )
     }
 
@@ -111,6 +125,7 @@ impl EcmascriptChunkVc {
                 }
                 .cell(),
             ),
+            None,
         ))
     }
 
@@ -147,8 +162,8 @@ impl EcmascriptChunkVc {
         let a = left.await?;
         let b = right.await?;
 
-        let a = ecmascript_chunk_content(a.context, a.main_entries, a.omit_entries);
-        let b = ecmascript_chunk_content(b.context, b.main_entries, b.omit_entries);
+        let a = ecmascript_chunk_content(a.context, a.main_entries, a.omit_entries, a.usage);
+        let b = ecmascript_chunk_content(b.context, b.main_entries, b.omit_entries, b.usage);
 
         let a = a.await?.chunk_items.to_set();
         let b = b.await?.chunk_items.to_set();
@@ -240,6 +255,7 @@ pub struct EcmascriptChunkContentResult {
     pub chunk_items: EcmascriptChunkItemsVc,
     pub chunks: Vec<ChunkVc>,
     pub async_chunk_groups: Vec<ChunkGroupVc>,
+    pub hinted_chunk_groups: Vec<(ChunkGroupVc, AsyncLoadingHint)>,
     pub external_asset_references: Vec<AssetReferenceVc>,
 }
 
@@ -249,6 +265,35 @@ impl EcmascriptChunkContentResultVc {
     fn filter(self, _other: EcmascriptChunkContentResultVc) -> EcmascriptChunkContentResultVc {
         todo!()
     }
+
+    /// Keeps the chunk items of the modules which match the filter.
+    #[turbo_tasks::function]
+    async fn filter_by_usage(self, usage: UsageFilterVc) -> Result<EcmascriptChunkContentResultVc> {
+        let this = self.await?;
+        let usage = usage.await?;
+        let profile = usage.profile.await?;
+        let mut chunk_items = Vec::new();
+        for chunk in this.chunk_items.await?.iter() {
+            for &chunk_item in chunk.await?.iter() {
+                if usage
+                    .usage
+                    .includes(&profile, &chunk_item.id().to_string().await?)
+                {
+                    chunk_items.push(chunk_item);
+                }
+            }
+        }
+        Ok(EcmascriptChunkContentResult {
+            chunk_items: EcmascriptChunkItemsVc::cell(EcmascriptChunkItems::make_chunks(
+                &chunk_items,
+            )),
+            chunks: this.chunks.clone(),
+            async_chunk_groups: this.async_chunk_groups.clone(),
+            hinted_chunk_groups: this.hinted_chunk_groups.clone(),
+            external_asset_references: this.external_asset_references.clone(),
+        }
+        .cell())
+    }
 }
 
 impl From<ChunkContentResult<EcmascriptChunkItemVc>> for EcmascriptChunkContentResult {
@@ -258,22 +303,76 @@ impl From<ChunkContentResult<EcmascriptChunkItemVc>> for EcmascriptChunkContentR
                 .cell(),
             chunks: from.chunks,
             async_chunk_groups: from.async_chunk_groups,
+            hinted_chunk_groups: from.hinted_chunk_groups,
             external_asset_references: from.external_asset_references,
         }
     }
 }
 
+/// Whether the chunks of the context are ES modules. They are only used when
+/// the chunks are loaded in the DOM or in Node.js.
+#[turbo_tasks::function]
+pub(crate) async fn uses_esm_output(context: ChunkingContextVc) -> Result<BoolVc> {
+    let esm = matches!(*context.output_format().await?, OutputFormat::EsModule)
+        && matches!(
+            *context.environment().chunk_loading().await?,
+            ChunkLoading::Dom | ChunkLoading::NodeJs
+        );
+    Ok(BoolVc::cell(esm))
+}
+
+/// The path from `from` to `to` on disk, with `/` as separator. `None` when one
+/// of the paths isn't on disk.
+async fn relative_sys_path(from: FileSystemPathVc, to: FileSystemPathVc) -> Result<Option<String>> {
+    let (Some(from), Some(to)) = (to_sys_path(from).await?, to_sys_path(to).await?) else {
+        return Ok(None);
+    };
+    Ok(pathdiff::diff_paths(to, from).map(|path| {
+        let path = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.is_empty() {
+            ".".to_string()
+        } else {
+            path
+        }
+    }))
+}
+
+/// The extension of the JavaScript chunks of the context. Node.js only treats
+/// `.mjs` files as ES modules without a `package.json` next to them.
+pub(crate) async fn chunk_extension(context: ChunkingContextVc) -> Result<&'static str> {
+    Ok(
+        if *uses_esm_output(context).await?
+            && matches!(
+                *context.environment().chunk_loading().await?,
+                ChunkLoading::NodeJs
+            )
+        {
+            ".mjs"
+        } else {
+            ".js"
+        },
+    )
+}
+
 #[turbo_tasks::function]
 fn ecmascript_chunk_content(
     context: ChunkingContextVc,
     main_entries: EcmascriptChunkPlaceablesVc,
     omit_entries: Option<EcmascriptChunkPlaceablesVc>,
+    usage: Option<UsageFilterVc>,
 ) -> EcmascriptChunkContentResultVc {
     let mut chunk_content = ecmascript_chunk_content_internal(context, main_entries);
     if let Some(omit_entries) = omit_entries {
         let omit_chunk_content = ecmascript_chunk_content_internal(context, omit_entries);
         chunk_content = chunk_content.filter(omit_chunk_content);
     }
+    if let Some(usage) = usage {
+        chunk_content = chunk_content.filter_by_usage(usage);
+    }
     chunk_content
 }
 
@@ -296,6 +395,7 @@ async fn ecmascript_chunk_content_internal(
     let mut all_chunk_items = IndexSet::<EcmascriptChunkItemVc>::new();
     let mut all_chunks = IndexSet::<ChunkVc>::new();
     let mut all_async_chunk_groups = IndexSet::<ChunkGroupVc>::new();
+    let mut all_hinted_chunk_groups = IndexSet::<(ChunkGroupVc, AsyncLoadingHint)>::new();
     let mut all_external_asset_references = IndexSet::<AssetReferenceVc>::new();
 
     for content in contents {
@@ -303,6 +403,7 @@ async fn ecmascript_chunk_content_internal(
             chunk_items,
             chunks,
             async_chunk_groups,
+            hinted_chunk_groups,
             external_asset_references,
         } = &*content.await?;
         for chunk in chunk_items.await?.iter() {
@@ -310,6 +411,7 @@ async fn ecmascript_chunk_content_internal(
         }
         all_chunks.extend(chunks.iter().copied());
         all_async_chunk_groups.extend(async_chunk_groups.iter().copied());
+        all_hinted_chunk_groups.extend(hinted_chunk_groups.iter().copied());
         all_external_asset_references.extend(external_asset_references.iter().copied());
     }
 
@@ -319,6 +421,7 @@ async fn ecmascript_chunk_content_internal(
         chunk_items: EcmascriptChunkItemsVc::cell(chunk_items),
         chunks: all_chunks.into_iter().collect(),
         async_chunk_groups: all_async_chunk_groups.into_iter().collect(),
+        hinted_chunk_groups: all_hinted_chunk_groups.into_iter().collect(),
         external_asset_references: all_external_asset_references.into_iter().collect(),
     }
     .cell())
@@ -341,6 +444,35 @@ async fn ecmascript_chunk_content_single_entry(
     ))
 }
 
+/// Computes the project root of the modules of a chunk that is loaded as an
+/// ES module, from the path of the root relative to the chunk.
+static ESM_PROJECT_ROOT: RopeTemplate = RopeTemplate::new(&[
+    Static(
+        "import { fileURLToPath as __turbopack_file_url_to_path__ } from \
+         \"node:url\";\nglobalThis.__turbopack_project_root__ ??= \
+         __turbopack_file_url_to_path__(new URL(",
+    ),
+    Dynamic("root"),
+    Static(", import.meta.url));\n"),
+]);
+
+/// Computes the project root of the modules of a CommonJS chunk.
+static CJS_PROJECT_ROOT: RopeTemplate = RopeTemplate::new(&[
+    Static("globalThis.__turbopack_project_root__ ??= require(\"path\").join(__dirname, "),
+    Dynamic("root"),
+    Static(");\n"),
+]);
+
+/// A runnable that instantiates the entries of an evaluated chunk, once all
+/// the chunks they depend on (`condition`) have been registered.
+static EVALUATE_RUNNABLE: RopeTemplate = RopeTemplate::new(&[
+    Static(", ({ loadedChunks, instantiateRuntimeModule }) => {\n    if(!(true"),
+    Dynamic("condition"),
+    Static(")) return true;\n    "),
+    Dynamic("entries"),
+    Static("\n}"),
+]);
+
 #[turbo_tasks::value(serialization = "none")]
 pub struct EcmascriptChunkContent {
     module_factories: EcmascriptChunkContentEntriesSnapshotReadRef,
@@ -348,6 +480,23 @@ pub struct EcmascriptChunkContent {
     output_root: FileSystemPathVc,
     evaluate: Option<EcmascriptChunkContentEvaluateVc>,
     environment: EnvironmentVc,
+    minify_options: OptionMinifyOptionsVc,
+    comments_policy: CommentsPolicy,
+    banner: ChunkBannerVc,
+    runtime_chunking: RuntimeChunking,
+    /// Whether the chunk is an ES module.
+    esm: bool,
+    /// The separate runtime, which is imported by ES module chunks that
+    /// evaluate entries.
+    runtime_path: Option<FileSystemPathVc>,
+    /// The project root when the chunk is executed by Node.js on a server.
+    node_server_project_root: Option<FileSystemPathVc>,
+    /// The path the output root is served under.
+    base_path: String,
+    /// Whether the runtime avoids `eval`.
+    strict_csp: bool,
+    /// Whether the runtime includes the module federation runtime.
+    federation: bool,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -443,21 +592,40 @@ impl EcmascriptChunkContentVc {
         context: ChunkingContextVc,
         main_entries: EcmascriptChunkPlaceablesVc,
         omit_entries: Option<EcmascriptChunkPlaceablesVc>,
+        usage: Option<UsageFilterVc>,
         chunk_path: FileSystemPathVc,
         evaluate: Option<EcmascriptChunkContentEvaluateVc>,
     ) -> Result<Self> {
         // TODO(alexkirsz) All of this should be done in a transition, otherwise we run
         // the risks of values not being strongly consistent with each other.
-        let chunk_content = ecmascript_chunk_content(context, main_entries, omit_entries);
+        let chunk_content = ecmascript_chunk_content(context, main_entries, omit_entries, usage);
         let chunk_content = chunk_content.await?;
         let module_factories = chunk_content.chunk_items.to_entry_snapshot().await?;
         let output_root = context.output_root();
+        let runtime_chunking = *context.runtime_chunking().await?;
+        let esm = *uses_esm_output(context).await?;
+        let runtime_path = match runtime_chunking {
+            RuntimeChunking::Separate if esm && evaluate.is_some() => {
+                Some(EcmascriptRuntimeAssetVc::new(context, chunk_path.parent()).path())
+            }
+            _ => None,
+        };
         Ok(EcmascriptChunkContent {
             module_factories,
             chunk_path,
             output_root,
             evaluate,
             environment: context.environment(),
+            minify_options: context.minify_options(),
+            comments_policy: *context.comments_policy().await?,
+            banner: context.chunk_banner(chunk_path),
+            runtime_chunking,
+            esm,
+            runtime_path,
+            node_server_project_root: *context.node_server_project_root().await?,
+            base_path: context.base_path().await?.clone(),
+            strict_csp: *context.strict_csp().await?,
+            federation: *context.federation().await?,
         }
         .cell())
     }
@@ -491,7 +659,18 @@ impl EcmascriptChunkContentEntryVc {
     #[turbo_tasks::function]
     async fn new(chunk_item: EcmascriptChunkItemVc) -> Result<Self> {
         let content = chunk_item.content();
-        let factory = match module_factory(content).resolve().await {
+        let source_path = match *chunk_item
+            .chunking_context()
+            .node_server_project_root()
+            .await?
+        {
+            Some(project_root) => project_root
+                .await?
+                .get_path_to(&*chunk_item.related_path().await?)
+                .map(|path| path.to_string()),
+            None => None,
+        };
+        let factory = match module_factory(content, source_path).resolve().await {
             Ok(factory) => factory,
             Err(error) => {
                 let id = chunk_item.id().to_string().await;
@@ -532,7 +711,10 @@ impl EcmascriptChunkContentEntryVc {
 }
 
 #[turbo_tasks::function]
-async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc> {
+async fn module_factory(
+    content: EcmascriptChunkItemContentVc,
+    source_path: Option<String>,
+) -> Result<CodeVc> {
     let content = content.await?;
     let mut args = vec![
         "r: __turbopack_require__",
@@ -545,41 +727,106 @@ async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc>
         "j: __turbopack_cjs__",
         "p: process",
         "g: global",
-        // HACK
-        "__dirname",
     ];
+    if source_path.is_none() {
+        // HACK
+        args.push("__dirname");
+    }
     if content.options.module {
         args.push("m: module");
     }
     if content.options.exports {
         args.push("e: exports");
     }
+    if content.options.async_module {
+        args.push("a: __turbopack_async_module__");
+        args.push("w: __turbopack_wait__");
+    }
+    if content.options.federation {
+        args.push("f: __turbopack_federation__");
+    }
+    if content.options.hints {
+        args.push("h: __turbopack_hint__");
+    }
     let mut code = CodeBuilder::default();
     let args = FormatIter(|| args.iter().copied().intersperse(", "));
-    if content.options.this {
-        write!(code, "(function({{ {} }}) {{ !function() {{\n\n", args,)?;
-    } else {
-        write!(code, "(({{ {} }}) => (() => {{\n\n", args,)?;
+    let params = match &source_path {
+        // Modules executed by Node.js on a server see the paths of their source
+        // files, which are relative to the project root.
+        Some(path) => format!(
+            "{{ {args} }}, __filename = \
+             __turbopack_external_require__(\"path\").join(globalThis.__turbopack_project_root__, \
+             {}), __dirname = __turbopack_external_require__(\"path\").dirname(__filename)",
+            stringify_str(path)
+        ),
+        None => format!("{{ {args} }}"),
+    };
+    // Modules using top-level await are evaluated in an async function, which
+    // is passed to the runtime so that importers can wait for it.
+    match (content.options.this, content.options.async_module) {
+        (true, false) => write!(code, "(function({}) {{ !function() {{\n\n", params)?,
+        (false, false) => write!(code, "(({}) => (() => {{\n\n", params)?,
+        (true, true) => write!(
+            code,
+            "(function({}) {{ __turbopack_async_module__(async function() {{\n\n",
+            params
+        )?,
+        (false, true) => write!(
+            code,
+            "(({}) => __turbopack_async_module__(async () => {{\n\n",
+            params
+        )?,
     }
 
     let source_map = content.source_map.map(|sm| sm.as_generate_source_map());
     code.push_source(&content.inner_code, source_map);
-    if content.options.this {
-        code += "\n}.call(this) })";
-    } else {
-        code += "\n})())";
+    match (content.options.this, content.options.async_module) {
+        (true, false) => code += "\n}.call(this) })",
+        (false, false) => code += "\n})())",
+        (true, true) => code += "\n}) })",
+        (false, true) => code += "\n}))",
     }
     Ok(code.build().cell())
 }
 
-#[derive(Serialize)]
-#[serde(tag = "type")]
 struct EcmascriptChunkUpdate<'a> {
-    added: IndexMap<&'a ModuleId, HmrUpdateEntry<'a>>,
-    modified: IndexMap<&'a ModuleId, HmrUpdateEntry<'a>>,
+    added: IndexMap<&'a ModuleId, RopeReadRef>,
+    modified: IndexMap<&'a ModuleId, RopeReadRef>,
     deleted: IndexSet<&'a ModuleId>,
 }
 
+impl EcmascriptChunkUpdate<'_> {
+    /// Serializes the update to JSON. The serialized entries are shared, not
+    /// copied.
+    fn to_rope(&self) -> Result<Rope> {
+        let mut rope = RopeBuilder::from(r#"{"type":"EcmascriptChunkUpdate","added":"#);
+        write_update_entries(&mut rope, &self.added)?;
+        rope += r#","modified":"#;
+        write_update_entries(&mut rope, &self.modified)?;
+        rope += r#","deleted":"#;
+        serde_json::to_writer(&mut rope, &self.deleted)?;
+        rope += "}";
+        Ok(rope.build())
+    }
+}
+
+fn write_update_entries(
+    rope: &mut RopeBuilder,
+    entries: &IndexMap<&ModuleId, RopeReadRef>,
+) -> Result<()> {
+    *rope += "{";
+    for (i, (id, entry)) in entries.iter().enumerate() {
+        if i > 0 {
+            *rope += ",";
+        }
+        serde_json::to_writer(&mut *rope, &id.to_string())?;
+        *rope += ":";
+        *rope += &**entry;
+    }
+    *rope += "}";
+    Ok(())
+}
+
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkContentVc {
     #[turbo_tasks::function]
@@ -611,7 +858,55 @@ impl EcmascriptChunkContentVc {
             );
         };
         let mut code = CodeBuilder::default();
-        code += "(self.TURBOPACK = self.TURBOPACK || []).push([";
+        if let CommentsPolicy::ExtractLegal = this.comments_policy {
+            if !self.legal_comments().await?.is_empty() {
+                writeln!(
+                    code,
+                    "/*! For license information please see {}.LICENSE.txt */",
+                    chunk_path.file_name()
+                )?;
+            }
+        }
+        if this.esm {
+            if let Some(evaluate) = &this.evaluate {
+                // Importing the runtime and the other chunks of the chunk group makes sure
+                // that they are registered before the entries are evaluated.
+                let mut imports = Vec::new();
+                imports.extend(this.runtime_path);
+                for path in evaluate.await?.chunks_server_paths.await?.iter() {
+                    imports.push(this.output_root.join(path));
+                }
+                let chunk_dir = this.chunk_path.parent().await?;
+                for path in imports {
+                    if let Some(specifier) = chunk_dir.get_relative_path_to(&*path.await?) {
+                        writeln!(code, "import {};", stringify_str(&specifier))?;
+                    }
+                }
+                code += "let __turbopack_exports__;\n";
+            }
+        }
+        if let Some(project_root) = this.node_server_project_root {
+            // The modules compute the paths of their source files from the project root,
+            // which is located relative to the chunk.
+            if let Some(root) = relative_sys_path(this.chunk_path.parent(), project_root).await? {
+                if this.esm {
+                    code.push_template(
+                        &ESM_PROJECT_ROOT,
+                        &[("root", stringify_str(&format!("{root}/")).as_str().into())],
+                    )?;
+                } else {
+                    code.push_template(
+                        &CJS_PROJECT_ROOT,
+                        &[("root", stringify_str(&root).as_str().into())],
+                    )?;
+                }
+            }
+        }
+        if this.esm {
+            code += "(globalThis.TURBOPACK = globalThis.TURBOPACK || []).push([";
+        } else {
+            code += "(self.TURBOPACK = self.TURBOPACK || []).push([";
+        }
 
         writeln!(code, "{}, {{", stringify_str(chunk_server_path))?;
         for entry in &this.module_factories {
@@ -631,12 +926,21 @@ impl EcmascriptChunkContentVc {
                 .collect::<Vec<_>>()
                 .join("");
             let entries_ids = &*evaluate.entry_modules_ids.await?;
+            let esm = this.esm;
             let entries_instantiations = entries_ids
                 .iter()
-                .map(|id| async move {
+                .enumerate()
+                .map(|(i, id)| async move {
                     let id = id.await?;
                     let id = stringify_module_id(&id);
-                    Ok(format!(r#"instantiateRuntimeModule({id});"#)) as Result<_>
+                    // An ES module chunk exports the exports of its last entry.
+                    Ok(if esm && i == entries_ids.len() - 1 {
+                        format!(
+                            r#"__turbopack_exports__ = instantiateRuntimeModule({id}).exports;"#
+                        )
+                    } else {
+                        format!(r#"instantiateRuntimeModule({id});"#)
+                    }) as Result<_>
                 })
                 .try_join()
                 .await?
@@ -647,59 +951,104 @@ impl EcmascriptChunkContentVc {
             // depend on have not yet been registered.
             // The runnable will run every time a new chunk is `.push`ed to TURBOPACK, until
             // all dependent chunks have been evaluated.
-            write!(
-                code,
-                ", ({{ loadedChunks, instantiateRuntimeModule }}) => {{
-    if(!(true{condition})) return true;
-    {entries_instantiations}
-}}"
+            code.push_template(
+                &EVALUATE_RUNNABLE,
+                &[
+                    ("condition", condition.as_str().into()),
+                    ("entries", entries_instantiations.as_str().into()),
+                ],
             )?;
         }
         code += "]);\n";
+        if this.esm && this.evaluate.is_some() {
+            code += "export { __turbopack_exports__ as default };\n";
+        }
         if this.evaluate.is_some() {
-            // When a chunk is executed, it will either register itself with the current
-            // instance of the runtime, or it will push itself onto the list of pending
-            // chunks (`self.TURBOPACK`).
-            //
-            // When the runtime executes, it will pick up and register all pending chunks,
-            // and replace the list of pending chunks with itself so later chunks can
-            // register directly with it.
-            code += indoc! { r#"
-                (() => {
-                if (!Array.isArray(globalThis.TURBOPACK)) {
-                    return;
-                }
-            "# };
-
-            let specific_runtime_code = match *this.environment.chunk_loading().await? {
-                ChunkLoading::None => embed_file!("js/src/runtime.none.js").await?,
-                ChunkLoading::NodeJs => embed_file!("js/src/runtime.nodejs.js").await?,
-                ChunkLoading::Dom => embed_file!("js/src/runtime.dom.js").await?,
-            };
-
-            match &*specific_runtime_code {
-                FileContent::NotFound => return Err(anyhow!("specific runtime code is not found")),
-                FileContent::Content(file) => code.push_source(file.content(), None),
-            };
+            // A web worker is started from a single script, so it always embeds the
+            // runtime.
+            let embed_runtime = matches!(this.runtime_chunking, RuntimeChunking::Embedded)
+                || matches!(
+                    *this.environment.chunk_loading().await?,
+                    ChunkLoading::WebWorker
+                );
+            if embed_runtime {
+                // When a chunk is executed, it will either register itself with the
+                // current instance of the runtime, or it will push itself onto the list
+                // of pending chunks (`self.TURBOPACK`).
+                code.push_code(
+                    &*runtime_code(
+                        this.environment,
+                        this.esm,
+                        &this.base_path,
+                        this.strict_csp,
+                        this.federation,
+                    )
+                    .await?,
+                );
+            }
+        }
 
-            let shared_runtime_code = embed_file!("js/src/runtime.js").await?;
+        if let (Some(evaluate), ChunkLoading::WebWorker) =
+            (&this.evaluate, &*this.environment.chunk_loading().await?)
+        {
+            // A web worker is started from a single script, so it has to load the other
+            // chunks of its chunk group itself.
+            let chunks_urls = evaluate
+                .await?
+                .chunks_server_paths
+                .await?
+                .iter()
+                .filter(|path| path.ends_with(".js"))
+                .map(|path| stringify_str(&format!("{}/{path}", this.base_path)))
+                .collect::<Vec<_>>();
+            if !chunks_urls.is_empty() {
+                writeln!(code, "importScripts({});", chunks_urls.join(", "))?;
+            }
+        }
 
-            match &*shared_runtime_code {
-                FileContent::NotFound => return Err(anyhow!("shared runtime code is not found")),
-                FileContent::Content(file) => code.push_source(file.content(), None),
-            };
+        let code = code.build().cell();
+        let code = if let Some(options) = &*this.minify_options.await? {
+            minify(
+                this.chunk_path,
+                code,
+                options.clone().cell(),
+                Value::new(this.comments_policy),
+            )
+        } else {
+            code
+        };
 
-            code += indoc! { r#"
-                })();
-            "# };
+        let code = code.await?;
+        let banner = this.banner.await?;
+        let mut code_with_url = CodeBuilder::default();
+        if let Some(banner) = &banner.banner {
+            code_with_url.push_source(banner, None);
+        }
+        code_with_url.push_code(&code);
+        if let Some(footer) = &banner.footer {
+            code_with_url.push_source(footer, None);
         }
-
         if code.has_source_map() {
             let filename = chunk_path.file_name();
-            write!(code, "\n\n//# sourceMappingURL={}.map", filename)?;
+            write!(code_with_url, "\n\n//# sourceMappingURL={}.map", filename)?;
         }
 
-        Ok(code.build().cell())
+        Ok(code_with_url.build().cell())
+    }
+
+    /// The legal comments of all chunk items, which are extracted into a
+    /// `.LICENSE.txt` file with [CommentsPolicy::ExtractLegal].
+    #[turbo_tasks::function]
+    async fn legal_comments(self) -> Result<StringsVc> {
+        let this = self.await?;
+        let mut comments = IndexSet::new();
+        for entry in &this.module_factories {
+            // Errors are already reported when generating the code of the chunk item.
+            if let Ok(content) = entry.chunk_item.content().await {
+                comments.extend(content.legal_comments.iter().cloned());
+            }
+        }
+        Ok(StringsVc::cell(comments.into_iter().collect()))
     }
 
     #[turbo_tasks::function]
@@ -764,7 +1113,7 @@ impl VersionedContent for EcmascriptChunkContent {
             let id = &**id;
             if let Some(entry) = module_factories.remove(id) {
                 if entry.hash != *hash {
-                    modified.insert(id, HmrUpdateEntry::new(entry, chunk_path));
+                    modified.insert(id, hmr_update_entry(entry, chunk_path).await?);
                 }
             } else {
                 deleted.insert(id);
@@ -773,7 +1122,7 @@ impl VersionedContent for EcmascriptChunkContent {
 
         // Remaining entries are added
         for (id, entry) in module_factories {
-            added.insert(id, HmrUpdateEntry::new(entry, chunk_path));
+            added.insert(id, hmr_update_entry(entry, chunk_path).await?);
         }
 
         let update = if added.is_empty() && modified.is_empty() && deleted.is_empty() {
@@ -787,7 +1136,7 @@ impl VersionedContent for EcmascriptChunkContent {
 
             Update::Partial(PartialUpdate {
                 to: to_version.into(),
-                instruction: JsonValueVc::cell(serde_json::to_value(&chunk_update)?),
+                instruction: chunk_update.to_rope()?.cell(),
             })
         };
 
@@ -826,23 +1175,33 @@ struct HmrUpdateEntry<'a> {
     map: Option<String>,
 }
 
-impl<'a> HmrUpdateEntry<'a> {
-    fn new(entry: &'a EcmascriptChunkContentEntry, chunk_path: &str) -> Self {
-        /// serde_qs can't serialize a lone enum when it's [serde::untagged].
-        #[derive(Serialize)]
-        struct Id<'a> {
-            id: &'a ModuleId,
-        }
-        let id = serde_qs::to_string(&Id { id: &entry.id }).unwrap();
-        HmrUpdateEntry {
-            code: entry.source_code(),
-            url: format!("/{}?{}", chunk_path, &id),
-            map: entry
-                .code
-                .has_source_map()
-                .then(|| format!("/__turbopack_sourcemap__/{}.map?{}", chunk_path, &id)),
-        }
+async fn hmr_update_entry(
+    entry: &EcmascriptChunkContentEntry,
+    chunk_path: &str,
+) -> Result<RopeReadRef> {
+    /// serde_qs can't serialize a lone enum when it's [serde::untagged].
+    #[derive(Serialize)]
+    struct Id<'a> {
+        id: &'a ModuleId,
     }
+    let id = serde_qs::to_string(&Id { id: &entry.id }).unwrap();
+    serialize_hmr_update_entry(entry.code_vc, chunk_path, &id).await
+}
+
+/// Serializes an added or modified module of an update. The result is cached
+/// until the code of the module changes, so that updates don't serialize
+/// modules again that they have in common.
+#[turbo_tasks::function]
+async fn serialize_hmr_update_entry(code: CodeVc, chunk_path: &str, id: &str) -> Result<RopeVc> {
+    let code = code.await?;
+    let entry = HmrUpdateEntry {
+        code: code.source_code(),
+        url: format!("/{}?{}", chunk_path, id),
+        map: code
+            .has_source_map()
+            .then(|| format!("/__turbopack_sourcemap__/{}.map?{}", chunk_path, id)),
+    };
+    Ok(Rope::from(serde_json::to_vec(&entry)?).cell())
 }
 
 #[turbo_tasks::value(serialization = "none")]
@@ -924,10 +1283,16 @@ impl ValueToString for EcmascriptChunk {
         let entry_strs = || entry_strings.iter().map(|s| s.as_str()).intersperse(" + ");
         let omit_entry_strings = entries_to_string(self.omit_entries).await?;
         let omit_entry_strs = || omit_entry_strings.iter().flat_map(|s| [" - ", s.as_str()]);
+        let usage = if let Some(usage) = self.usage {
+            format!(" ({})", usage.await?.usage)
+        } else {
+            String::new()
+        };
         Ok(StringVc::cell(format!(
-            "chunk {}{}{}",
+            "chunk {}{}{}{}",
             FormatIter(entry_strs),
             FormatIter(omit_entry_strs),
+            usage,
             suffix
         )))
     }
@@ -935,6 +1300,13 @@ impl ValueToString for EcmascriptChunk {
 
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkVc {
+    /// Whether the chunk is an ES module, which needs to be loaded with
+    /// `<script type="module">`.
+    #[turbo_tasks::function]
+    pub async fn is_es_module(self) -> Result<BoolVc> {
+        Ok(uses_esm_output(self.await?.context))
+    }
+
     #[turbo_tasks::function]
     async fn chunk_content_result(self) -> Result<EcmascriptChunkContentResultVc> {
         let this = self.await?;
@@ -942,6 +1314,7 @@ impl EcmascriptChunkVc {
             this.context,
             this.main_entries,
             this.omit_entries,
+            this.usage,
         ))
     }
 
@@ -972,6 +1345,7 @@ impl EcmascriptChunkVc {
             this.context,
             this.main_entries,
             this.omit_entries,
+            this.usage,
             chunk_path,
             evaluate,
         );
@@ -1041,6 +1415,10 @@ impl Asset for EcmascriptChunk {
             }
             need_hash = true;
         }
+        if let Some(usage) = this.usage {
+            hasher.write_value(usage.await?.usage.to_string());
+            need_hash = true;
+        }
 
         if need_hash {
             let hash = hasher.finish();
@@ -1049,7 +1427,9 @@ impl Asset for EcmascriptChunk {
             path = path.append_to_stem(&format!("_{}", truncated_hash))
         }
 
-        Ok(this.context.chunk_path(path, ".js"))
+        Ok(this
+            .context
+            .chunk_path(path, chunk_extension(this.context).await?))
     }
 
     #[turbo_tasks::function]
@@ -1060,8 +1440,13 @@ impl Asset for EcmascriptChunk {
     #[turbo_tasks::function]
     async fn references(self_vc: EcmascriptChunkVc) -> Result<AssetReferencesVc> {
         let this = self_vc.await?;
-        let content =
-            ecmascript_chunk_content(this.context, this.main_entries, this.omit_entries).await?;
+        let content = ecmascript_chunk_content(
+            this.context,
+            this.main_entries,
+            this.omit_entries,
+            this.usage,
+        )
+        .await?;
         let mut references = Vec::new();
         for r in content.external_asset_references.iter() {
             references.push(*r);
@@ -1072,7 +1457,33 @@ impl Asset for EcmascriptChunk {
         for chunk_group in content.async_chunk_groups.iter() {
             references.push(ChunkGroupReferenceVc::new(*chunk_group).into());
         }
+        for &(chunk_group, hint) in content.hinted_chunk_groups.iter() {
+            references
+                .push(ChunkGroupReferenceVc::new_with_hint(chunk_group, Value::new(hint)).into());
+        }
         references.push(EcmascriptChunkSourceMapAssetReferenceVc::new(self_vc).into());
+        if let CommentsPolicy::ExtractLegal = *this.context.comments_policy().await? {
+            if !self_vc.chunk_content().legal_comments().await?.is_empty() {
+                references.push(EcmascriptChunkLicenseAssetReferenceVc::new(self_vc).into());
+            }
+        }
+        if this.evaluate.is_some() {
+            let inline = match *this.context.runtime_chunking().await? {
+                RuntimeChunking::Embedded => None,
+                RuntimeChunking::Separate => Some(false),
+                RuntimeChunking::Inline => Some(true),
+            };
+            // A web worker always embeds the runtime, see
+            // `EcmascriptChunkContentVc::code`.
+            let is_web_worker = matches!(
+                *this.context.environment().chunk_loading().await?,
+                ChunkLoading::WebWorker
+            );
+            if let (Some(inline), false) = (inline, is_web_worker) {
+                let runtime = EcmascriptRuntimeAssetVc::new(this.context, self_vc.path().parent());
+                references.push(RuntimeAssetReferenceVc::new(runtime.into(), inline).into());
+            }
+        }
 
         Ok(AssetReferencesVc::cell(references))
     }
@@ -1110,8 +1521,13 @@ impl Introspectable for EcmascriptChunk {
         let content = content_to_details(self_vc.content());
         let mut details = String::new();
         let this = self_vc.await?;
-        let chunk_content =
-            ecmascript_chunk_content(this.context, this.main_entries, this.omit_entries).await?;
+        let chunk_content = ecmascript_chunk_content(
+            this.context,
+            this.main_entries,
+            this.omit_entries,
+            this.usage,
+        )
+        .await?;
         let chunk_items = chunk_content.chunk_items.await?;
         details += "Chunk items:\n\n";
         for chunk in chunk_items.iter() {
@@ -1209,6 +1625,9 @@ pub struct EcmascriptChunkItemContent {
     pub inner_code: Rope,
     pub source_map: Option<ParseResultSourceMapVc>,
     pub options: EcmascriptChunkItemOptions,
+    /// Legal comments that are removed from the code, to be extracted into a
+    /// `.LICENSE.txt` file.
+    pub legal_comments: Vec<String>,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1217,6 +1636,13 @@ pub struct EcmascriptChunkItemOptions {
     pub module: bool,
     pub exports: bool,
     pub this: bool,
+    /// Whether the module uses top-level await or imports a module that does.
+    pub async_module: bool,
+    /// Whether the module uses the module federation runtime.
+    pub federation: bool,
+    /// Whether the module fetches chunks ahead of time, see
+    /// [turbopack_core::chunk::AsyncLoadingHint].
+    pub hints: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1309,3 +1735,58 @@ impl EcmascriptChunkItemsVc {
 
 #[turbo_tasks::value(transparent)]
 pub struct EcmascriptChunkItemsSet(IndexSet<EcmascriptChunkItemVc>);
+
+#[cfg(test)]
+mod tests {
+    use indexmap::{IndexMap, IndexSet};
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_fs::rope::Rope;
+    use turbo_tasks_memory::MemoryBackend;
+    use turbopack_core::chunk::ModuleId;
+
+    use super::EcmascriptChunkUpdate;
+
+    #[test]
+    fn serializes_update_from_serialized_entries() {
+        crate::register();
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async {
+                    let added_id = ModuleId::String("./added.js".to_string());
+                    let modified_id = ModuleId::Number(1);
+                    let other_id = ModuleId::Number(2);
+                    let deleted_id = ModuleId::String("./deleted.js".to_string());
+                    let added = Rope::from(r#"{"code":"added"}"#).cell().await?;
+                    let modified = Rope::from(r#"{"code":"modified"}"#).cell().await?;
+                    let update = EcmascriptChunkUpdate {
+                        added: IndexMap::from([(&added_id, added)]),
+                        modified: IndexMap::from([
+                            (&modified_id, modified.clone()),
+                            (&other_id, modified),
+                        ]),
+                        deleted: IndexSet::from([&deleted_id]),
+                    };
+                    let json: serde_json::Value =
+                        serde_json::from_str(&update.to_rope()?.to_str()?)?;
+                    assert_eq!(
+                        json,
+                        serde_json::json!({
+                            "type": "EcmascriptChunkUpdate",
+                            "added": { "./added.js": { "code": "added" } },
+                            "modified": {
+                                "1": { "code": "modified" },
+                                "2": { "code": "modified" },
+                            },
+                            "deleted": ["./deleted.js"],
+                        })
+                    );
+                    Ok(())
+                })
+                .await
+            })
+            .unwrap();
+    }
+}
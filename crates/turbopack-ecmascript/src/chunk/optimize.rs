@@ -8,7 +8,8 @@ use turbo_tasks::TryJoinIterExt;
 use turbo_tasks_fs::FileSystemPathOptionVc;
 use turbopack_core::chunk::{
     optimize::{optimize_by_common_parent, ChunkOptimizer, ChunkOptimizerVc},
-    ChunkGroupVc, ChunkVc, ChunkingContextVc, ChunksVc,
+    profile::{ModuleUsage, ModuleUsagesVc, UsageFilter, UsageProfileVc},
+    ChunkGroupVc, ChunkVc, ChunkingContext, ChunkingContextVc, ChunksVc,
 };
 
 use super::{EcmascriptChunkPlaceablesVc, EcmascriptChunkVc};
@@ -29,8 +30,9 @@ impl EcmascriptChunkOptimizerVc {
 impl ChunkOptimizer for EcmascriptChunkOptimizer {
     #[turbo_tasks::function]
     async fn optimize(&self, chunks: ChunksVc, chunk_group: ChunkGroupVc) -> Result<ChunksVc> {
+        let context = self.0;
         optimize_by_common_parent(chunks, get_common_parent, |local, children| {
-            optimize_ecmascript(local, children, chunk_group)
+            optimize_ecmascript(local, children, chunk_group, context)
         })
         .await
     }
@@ -64,11 +66,28 @@ async fn merge_chunks(
         .flat_map(|e| e.iter().copied())
         .collect::<IndexSet<_>>();
     let evaluate = chunks.iter().find_map(|e| e.evaluate);
+    // Parts of chunks with different usages are merged into a chunk with all of
+    // their modules.
+    let mut usage = chunks[0].usage;
+    if let Some(first_usage) = usage {
+        let first_usage = first_usage.await?;
+        for chunk in chunks[1..].iter() {
+            let same = match chunk.usage {
+                Some(other) => *other.await? == *first_usage,
+                None => false,
+            };
+            if !same {
+                usage = None;
+                break;
+            }
+        }
+    }
     Ok(EcmascriptChunkVc::new_normalized(
         first.await?.context,
         EcmascriptChunkPlaceablesVc::cell(main_entries.into_iter().collect()),
         None,
         evaluate,
+        usage,
     ))
 }
 
@@ -89,6 +108,10 @@ const LOCAL_CHUNK_MERGE_THRESHOLD: usize = 20;
 const TOTAL_CHUNK_MERGE_THRESHOLD: usize = 20;
 /// Max number of chunk items per chunk to merge.
 const MAX_CHUNK_ITEMS_PER_CHUNK: usize = 3000;
+/// Max number of sets of routes a chunk is split by. Chunks whose modules were
+/// executed on more sets of routes are split into executed and not executed
+/// modules only.
+const MAX_USAGE_PARTS_PER_CHUNK: usize = 8;
 
 /// Merge chunks with high duplication between them.
 async fn merge_duplicated_and_contained(
@@ -372,12 +395,95 @@ async fn merge_by_size(
     Ok(merged)
 }
 
+/// The usages of the modules of a chunk by which it's split.
+#[turbo_tasks::function]
+async fn module_usages(
+    chunk: EcmascriptChunkVc,
+    profile: UsageProfileVc,
+) -> Result<ModuleUsagesVc> {
+    let chunk_items = chunk
+        .chunk_content_result()
+        .await?
+        .chunk_items
+        .to_set()
+        .await?;
+    let ids = chunk_items
+        .iter()
+        .map(|chunk_item| chunk_item.id().to_string())
+        .try_join()
+        .await?;
+    Ok(ModuleUsagesVc::cell(profile.await?.split(
+        ids.iter().map(|id| id.as_str()),
+        MAX_USAGE_PARTS_PER_CHUNK,
+    )))
+}
+
+/// Splits the chunks into parts by the usage of their modules according to the
+/// usage profile, and merges the parts with the same usage. Modules which were
+/// executed on the same routes are placed together, followed by the modules
+/// which were not executed.
+async fn split_by_usage(
+    chunks: Vec<(EcmascriptChunkVc, Option<ChunksVc>)>,
+    profile: UsageProfileVc,
+) -> Result<Vec<(EcmascriptChunkVc, Option<ChunksVc>)>> {
+    let mut hot = IndexMap::<ModuleUsage, Vec<EcmascriptChunkVc>>::new();
+    let mut cold = Vec::new();
+    let mut add = |chunk: EcmascriptChunkVc, source: Option<ChunksVc>, usage: ModuleUsage| {
+        if usage == ModuleUsage::NotExecuted {
+            cold.push((chunk, source));
+        } else {
+            hot.entry(usage).or_default().push(chunk);
+        }
+    };
+    for (chunk, source) in chunks {
+        let this = chunk.await?;
+        // Children have been split already.
+        if let Some(usage) = this.usage {
+            add(chunk, source, usage.await?.usage.clone());
+            continue;
+        }
+        let usages = module_usages(chunk, profile).await?;
+        if usages
+            .iter()
+            .all(|usage| *usage == ModuleUsage::NotExecuted)
+        {
+            add(chunk, source, ModuleUsage::NotExecuted);
+            continue;
+        }
+        for (i, usage) in usages.iter().enumerate() {
+            let part = EcmascriptChunkVc::new_normalized(
+                this.context,
+                this.main_entries,
+                this.omit_entries,
+                // The chunk group is evaluated once all its chunks are loaded, so one
+                // of the parts evaluates it.
+                if i == 0 { this.evaluate } else { None },
+                Some(
+                    UsageFilter {
+                        profile,
+                        usage: usage.clone(),
+                    }
+                    .cell(),
+                ),
+            );
+            add(part, source, usage.clone());
+        }
+    }
+    let mut split = Vec::new();
+    for (_, parts) in hot {
+        split.extend(merge_by_size(parts).await?.into_iter().map(|c| (c, None)));
+    }
+    split.append(&mut cold);
+    Ok(split)
+}
+
 /// Chunk optimization for ecmascript chunks.
 #[turbo_tasks::function]
 async fn optimize_ecmascript(
     local: Option<ChunksVc>,
     children: Vec<ChunksVc>,
     chunk_group: ChunkGroupVc,
+    context: ChunkingContextVc,
 ) -> Result<ChunksVc> {
     let mut chunks = Vec::<(EcmascriptChunkVc, Option<ChunksVc>)>::new();
     // TODO optimize
@@ -403,6 +509,7 @@ async fn optimize_ecmascript(
                         }
                         .cell(),
                     ),
+                    content.usage,
                 )
             }
         }
@@ -434,6 +541,12 @@ async fn optimize_ecmascript(
     // threshold for that.)
     // TODO implement that
 
+    // Modules which are executed on the same routes are placed together, apart
+    // from the modules which are not executed.
+    if let Some(profile) = *context.usage_profile().await? {
+        chunks = split_by_usage(chunks, profile).await?;
+    }
+
     // When there are too many chunks, try hard to reduce the number of chunks to
     // limit the request count.
     if chunks.len() > TOTAL_CHUNK_MERGE_THRESHOLD {
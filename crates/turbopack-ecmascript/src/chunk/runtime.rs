@@ -0,0 +1,235 @@
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
+use indoc::indoc;
+use turbo_tasks::Value;
+use turbo_tasks_fs::{
+    embed_file,
+    rope::{RopeBuilder, RopeTemplate, RopeVc, TemplatePart::*},
+    File, FileContent, FileSystemPathVc,
+};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{ChunkingContext, ChunkingContextVc},
+    code_builder::{Code, CodeBuilder, CodeVc},
+    environment::{ChunkLoading, EnvironmentVc},
+    reference::AssetReferencesVc,
+    source_map::{GenerateSourceMap, GenerateSourceMapVc, GeneratedCodeSourceMapVc, SourceMapVc},
+};
+
+use super::{
+    chunk_extension, minify::minify, source_map::EcmascriptRuntimeSourceMapAssetReferenceVc,
+    uses_esm_output,
+};
+use crate::utils::stringify_str;
+
+/// Starts the runtime, unless it has been loaded already.
+static RUNTIME_PRELUDE: RopeTemplate = RopeTemplate::new(&[
+    Static(
+        "(() => {\nif (!Array.isArray(globalThis.TURBOPACK)) {\n    return;\n}\nconst \
+         CHUNK_BASE_PATH = ",
+    ),
+    Dynamic("base_path"),
+    Static(";\nconst STRICT_CSP = "),
+    Dynamic("strict_csp"),
+    Static(";\n"),
+]);
+
+/// The code of the runtime for the chunk loading of the environment.
+///
+/// When the runtime executes, it will pick up and register all pending chunks
+/// (`self.TURBOPACK`), and replace the list of pending chunks with itself so
+/// later chunks can register directly with it. So it doesn't matter whether
+/// the runtime is loaded before or after the chunks.
+///
+/// When the chunks are ES modules, they are loaded with `import()`.
+///
+/// Chunks are requested from `{base_path}/{chunk path}`.
+///
+/// With `strict_csp`, hot module replacement doesn't use `eval`.
+///
+/// The module federation runtime is only included with `federation`.
+#[turbo_tasks::function]
+pub(super) async fn runtime_code(
+    environment: EnvironmentVc,
+    esm: bool,
+    base_path: &str,
+    strict_csp: bool,
+    federation: bool,
+) -> Result<CodeVc> {
+    let chunk_loading = *environment.chunk_loading().await?;
+    let mut code = CodeBuilder::default();
+    if esm && matches!(chunk_loading, ChunkLoading::NodeJs) {
+        // External modules are loaded with `require`, which doesn't exist in ES
+        // modules.
+        code += indoc! { r#"
+            import { createRequire as __turbopack_create_require__ } from "node:module";
+            const require = __turbopack_create_require__(import.meta.url);
+        "# };
+    }
+    code.push_template(
+        &RUNTIME_PRELUDE,
+        &[
+            (
+                "base_path",
+                stringify_str(&format!("{base_path}/")).as_str().into(),
+            ),
+            (
+                "strict_csp",
+                if strict_csp { "true" } else { "false" }.into(),
+            ),
+        ],
+    )?;
+
+    let (specific_runtime_name, specific_runtime_code) = match chunk_loading {
+        ChunkLoading::NodeJs | ChunkLoading::Dom if esm => (
+            "runtime.esm.js",
+            embed_file!("js/src/runtime.esm.js").await?,
+        ),
+        ChunkLoading::None => (
+            "runtime.none.js",
+            embed_file!("js/src/runtime.none.js").await?,
+        ),
+        ChunkLoading::NodeJs => (
+            "runtime.nodejs.js",
+            embed_file!("js/src/runtime.nodejs.js").await?,
+        ),
+        ChunkLoading::Dom => (
+            "runtime.dom.js",
+            embed_file!("js/src/runtime.dom.js").await?,
+        ),
+        ChunkLoading::WebWorker => (
+            "runtime.webworker.js",
+            embed_file!("js/src/runtime.webworker.js").await?,
+        ),
+    };
+
+    match &*specific_runtime_code {
+        FileContent::NotFound => return Err(anyhow!("specific runtime code is not found")),
+        FileContent::Content(file) => push_runtime_source(&mut code, specific_runtime_name, file),
+    };
+
+    if federation {
+        match &*embed_file!("js/src/runtime.federation.js").await? {
+            FileContent::NotFound => return Err(anyhow!("federation runtime code is not found")),
+            FileContent::Content(file) => {
+                push_runtime_source(&mut code, "runtime.federation.js", file)
+            }
+        };
+    }
+
+    let shared_runtime_code = embed_file!("js/src/runtime.js").await?;
+
+    match &*shared_runtime_code {
+        FileContent::NotFound => return Err(anyhow!("shared runtime code is not found")),
+        FileContent::Content(file) => push_runtime_source(&mut code, "runtime.js", file),
+    };
+
+    code += indoc! { r#"
+        })();
+    "# };
+    Ok(code.build().cell())
+}
+
+/// Pushes runtime code with a source map that maps it to itself, so devtools
+/// can skip it with the ignore list.
+fn push_runtime_source(code: &mut CodeBuilder, name: &str, file: &File) {
+    let source_map = GeneratedCodeSourceMapVc::new(name, RopeVc::cell(file.content().clone()));
+    code.push_source(file.content(), Some(source_map.into()));
+}
+
+/// The runtime of a chunking context as a chunk of its own, which is used when
+/// the runtime is not embedded in the evaluated chunks. The name of the chunk
+/// contains the hash of its content, so it can be cached for a long time.
+///
+/// It has a source map of its own, so stack traces from the runtime, e.g. of
+/// chunk loading errors, point to the runtime sources.
+#[turbo_tasks::value]
+pub struct EcmascriptRuntimeAsset {
+    context: ChunkingContextVc,
+    directory: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptRuntimeAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(context: ChunkingContextVc, directory: FileSystemPathVc) -> Self {
+        Self::cell(EcmascriptRuntimeAsset { context, directory })
+    }
+
+    #[turbo_tasks::function]
+    async fn code(self) -> Result<CodeVc> {
+        let this = self.await?;
+        let mut code = CodeBuilder::default();
+        // The runtime can be loaded before any chunk was pushed onto the list of
+        // pending chunks.
+        code += "globalThis.TURBOPACK = globalThis.TURBOPACK || [];\n";
+        let esm = *uses_esm_output(this.context).await?;
+        code.push_code(
+            &*runtime_code(
+                this.context.environment(),
+                esm,
+                &this.context.base_path().await?,
+                *this.context.strict_csp().await?,
+                *this.context.federation().await?,
+            )
+            .await?,
+        );
+        let code = code.build().cell();
+        Ok(
+            if let Some(options) = &*this.context.minify_options().await? {
+                minify(
+                    this.directory.join("turbopack-runtime.js"),
+                    code,
+                    options.clone().cell(),
+                    Value::new(*this.context.comments_policy().await?),
+                )
+            } else {
+                code
+            },
+        )
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptRuntimeAsset {
+    #[turbo_tasks::function]
+    async fn path(self_vc: EcmascriptRuntimeAssetVc) -> Result<FileSystemPathVc> {
+        let this = self_vc.await?;
+        let code = self_vc.code().await?;
+        let hash = encode_hex(hash_xxh3_hash64(code.source_code()));
+        let extension = chunk_extension(this.context).await?;
+        Ok(this
+            .directory
+            .join(&format!("turbopack-runtime-{}{}", &hash[..8], extension)))
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: EcmascriptRuntimeAssetVc) -> Result<AssetContentVc> {
+        let code = self_vc.code().await?;
+        let mut content = RopeBuilder::default();
+        content += code.source_code();
+        if code.has_source_map() {
+            let path = self_vc.path().await?;
+            write!(content, "\n\n//# sourceMappingURL={}.map", path.file_name())?;
+        }
+        Ok(File::from(content.build()).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(self_vc: EcmascriptRuntimeAssetVc) -> AssetReferencesVc {
+        AssetReferencesVc::cell(vec![EcmascriptRuntimeSourceMapAssetReferenceVc::new(
+            self_vc,
+        )
+        .into()])
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for EcmascriptRuntimeAsset {
+    #[turbo_tasks::function]
+    fn generate_source_map(self_vc: EcmascriptRuntimeAssetVc) -> SourceMapVc {
+        self_vc.code().generate_source_map()
+    }
+}
@@ -8,7 +8,7 @@ use turbopack_core::{
     source_map::GenerateSourceMap,
 };
 
-use super::EcmascriptChunkVc;
+use super::{runtime::EcmascriptRuntimeAssetVc, EcmascriptChunkVc};
 
 /// Represents the source map of an ecmascript chunk.
 #[turbo_tasks::value]
@@ -81,3 +81,61 @@ impl ValueToString for EcmascriptChunkSourceMapAssetReference {
         )))
     }
 }
+
+/// Represents the source map of a separate runtime chunk.
+#[turbo_tasks::value]
+pub struct EcmascriptRuntimeSourceMapAsset {
+    runtime: EcmascriptRuntimeAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptRuntimeSourceMapAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.runtime.path().append(".map")
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let sm = self.runtime.generate_source_map().to_rope().await?;
+        Ok(File::from(sm).into())
+    }
+}
+
+/// A reference to a [`EcmascriptRuntimeSourceMapAsset`].
+#[turbo_tasks::value]
+pub struct EcmascriptRuntimeSourceMapAssetReference {
+    runtime: EcmascriptRuntimeAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptRuntimeSourceMapAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(runtime: EcmascriptRuntimeAssetVc) -> Self {
+        EcmascriptRuntimeSourceMapAssetReference { runtime }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for EcmascriptRuntimeSourceMapAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        let asset = EcmascriptRuntimeSourceMapAsset {
+            runtime: self.runtime,
+        }
+        .cell()
+        .into();
+        ResolveResult::asset(asset).cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for EcmascriptRuntimeSourceMapAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "source maps for {}",
+            self.runtime.path().to_string().await?
+        )))
+    }
+}
@@ -13,13 +13,20 @@ pub mod chunk_group_files_asset;
 pub mod code_gen;
 mod errors;
 pub mod magic_identifier;
+pub mod native_module;
 pub mod parse;
-mod path_visitor;
+mod parse_cache;
+pub mod path_visitor;
+pub mod react_refresh;
 pub(crate) mod references;
 pub mod resolve;
+pub mod service_worker;
 pub(crate) mod special_cases;
+#[cfg(test)]
+mod test_utils;
 pub(crate) mod transform;
 pub mod typescript;
+pub mod unused;
 pub mod utils;
 pub mod webpack;
 
@@ -35,20 +42,34 @@ pub use parse::{ParseResultSourceMap, ParseResultSourceMapVc};
 use path_visitor::ApplyVisitors;
 use references::AnalyzeEcmascriptModuleResult;
 use swc_core::{
-    common::GLOBALS,
+    common::{comments::Comments, Mark, GLOBALS},
     ecma::{
+        ast::{Ident, Program},
         codegen::{text_writer::JsWriter, Emitter},
-        visit::{VisitMutWith, VisitMutWithPath},
+        visit::{Visit, VisitMutWith, VisitMutWithPath, VisitWith},
     },
 };
+#[cfg(feature = "swc_wasm_plugins")]
+pub use transform::create_wasm_transform_plugin;
 pub use transform::{
-    EcmascriptInputTransform, EcmascriptInputTransformsVc, NextJsPageExportFilter,
+    CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc, CoreJsPolyfills,
+    CustomTransformer, DecoratorsKind, DecoratorsOptions, DecoratorsOptionsVc,
+    EcmascriptInputTransform, EcmascriptInputTransformsVc, FeatureFlags, FeatureFlagsVc,
+    NextJsPageExportFilter, ReactRefreshOptions, ReactRefreshOptionsVc, TransformContext,
+    TransformPlugin, TransformPluginIssue, TransformPluginIssueVc, TransformPluginOrder,
+    TransformPluginVc,
+};
+use turbo_tasks::{
+    primitives::{StringVc, StringsVc},
+    TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
-    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    chunk::{
+        ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, CommentsPolicy,
+    },
     context::AssetContextVc,
     environment::EnvironmentVc,
     reference::AssetReferencesVc,
@@ -61,6 +82,7 @@ use turbopack_core::{
 pub use self::references::AnalyzeEcmascriptModuleResultVc;
 use self::{
     chunk::{
+        comments::{emitted_comments, legal_comments},
         EcmascriptChunkItemContent, EcmascriptChunkItemContentVc, EcmascriptChunkItemOptions,
         EcmascriptExportsVc,
     },
@@ -69,7 +91,17 @@ use self::{
 use crate::{
     chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
     code_gen::CodeGenerateable,
-    references::analyze_ecmascript_module,
+    references::{
+        analyze_ecmascript_module,
+        cjs::CjsAssetReferenceVc,
+        esm::{
+            base::{ReferencedAsset, ReferencedAssetVc},
+            is_async_module, module_export_usage, EsmExportsVc,
+        },
+    },
+    resolve::cjs_resolve,
+    transform::{downlevel_program, swc_helper_requests, SwcHelperImports},
+    utils::module_id_to_lit,
 };
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -85,6 +117,17 @@ pub enum EcmascriptModuleAssetType {
     TypescriptDeclaration,
 }
 
+/// Options of an ecmascript module that affect its analysis and code
+/// generation.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Hash, Debug, Default, Copy, Clone)]
+pub struct EcmascriptOptions {
+    /// Keeps direct `eval` calls working for modules that need them, by
+    /// declaring the imported bindings under their original names, instead
+    /// of reporting a warning.
+    pub allow_eval: bool,
+}
+
 #[turbo_tasks::value(transparent)]
 pub struct InnerAssets(HashMap<String, AssetVc>);
 
@@ -95,6 +138,7 @@ pub struct EcmascriptModuleAsset {
     pub context: AssetContextVc,
     pub ty: EcmascriptModuleAssetType,
     pub transforms: EcmascriptInputTransformsVc,
+    pub options: EcmascriptOptions,
     pub environment: EnvironmentVc,
     pub inner_assets: Option<InnerAssetsVc>,
 }
@@ -107,6 +151,7 @@ impl EcmascriptModuleAssetVc {
         context: AssetContextVc,
         ty: Value<EcmascriptModuleAssetType>,
         transforms: EcmascriptInputTransformsVc,
+        options: Value<EcmascriptOptions>,
         environment: EnvironmentVc,
     ) -> Self {
         Self::cell(EcmascriptModuleAsset {
@@ -114,6 +159,7 @@ impl EcmascriptModuleAssetVc {
             context,
             ty: ty.into_value(),
             transforms,
+            options: options.into_value(),
             environment,
             inner_assets: None,
         })
@@ -125,6 +171,7 @@ impl EcmascriptModuleAssetVc {
         context: AssetContextVc,
         ty: Value<EcmascriptModuleAssetType>,
         transforms: EcmascriptInputTransformsVc,
+        options: Value<EcmascriptOptions>,
         environment: EnvironmentVc,
         inner_assets: InnerAssetsVc,
     ) -> Self {
@@ -133,6 +180,7 @@ impl EcmascriptModuleAssetVc {
             context,
             ty: ty.into_value(),
             transforms,
+            options: options.into_value(),
             environment,
             inner_assets: Some(inner_assets),
         })
@@ -155,6 +203,7 @@ impl EcmascriptModuleAssetVc {
             self.as_resolve_origin(),
             Value::new(this.ty),
             this.transforms,
+            Value::new(this.options),
             this.environment,
         ))
     }
@@ -261,8 +310,21 @@ impl ValueToString for ModuleChunkItem {
 #[turbo_tasks::value_impl]
 impl ChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
-    fn references(&self) -> AssetReferencesVc {
-        self.module.references()
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        if !*self.context.downlevel_syntax().await? {
+            return Ok(self.module.references());
+        }
+        let mut references = self.module.references().await?.clone_value();
+        let origin = self.module.as_resolve_origin();
+        for request in swc_helper_requests_of(self.module, self.context.environment())
+            .await?
+            .iter()
+        {
+            references.push(
+                CjsAssetReferenceVc::new(origin, RequestVc::parse_string(request.clone())).into(),
+            );
+        }
+        Ok(AssetReferencesVc::cell(references))
     }
 }
 
@@ -286,6 +348,8 @@ impl EcmascriptChunkItem for ModuleChunkItem {
             ..
         } = &*self.module.analyze().await?;
         let context = self.context;
+        let export_usage = module_export_usage(self.module.into(), context);
+        let async_module = *is_async_module(self.module).await?;
         let mut code_gens = Vec::new();
         for r in references.await?.iter() {
             if let Some(code_gen) = CodeGenerateableVc::resolve_from(r).await? {
@@ -294,7 +358,11 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         }
         for c in code_generation.await?.iter() {
             let c = c.resolve().await?;
-            code_gens.push(c.code_generation(context));
+            if let Some(esm_exports) = EsmExportsVc::resolve_from(c).await? {
+                code_gens.push(esm_exports.code_generation_with_usage(context, export_usage));
+            } else {
+                code_gens.push(c.code_generation(context));
+            }
         }
         // need to keep that around to allow references into that
         let code_gens = code_gens.into_iter().try_join().await?;
@@ -312,6 +380,34 @@ impl EcmascriptChunkItem for ModuleChunkItem {
             }
         }
 
+        let downlevel_versions = if *context.downlevel_syntax().await? {
+            Some(*context.environment().runtime_versions().await?)
+        } else {
+            None
+        };
+
+        let mut swc_helpers = HashMap::new();
+        if downlevel_versions.is_some() {
+            let origin = self.module.as_resolve_origin();
+            for request in swc_helper_requests_of(self.module, context.environment())
+                .await?
+                .iter()
+            {
+                let request_vc = RequestVc::parse_string(request.clone());
+                if let ReferencedAsset::Some(helper) = &*ReferencedAssetVc::from_resolve_result(
+                    cjs_resolve(origin, request_vc),
+                    request_vc,
+                )
+                .await?
+                {
+                    let id = helper.as_chunk_item(context).id().await?;
+                    swc_helpers.insert(request.clone(), module_id_to_lit(&id));
+                }
+            }
+        }
+
+        let comments_policy = *context.comments_policy().await?;
+
         let module = self.module.await?;
         let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
 
@@ -320,10 +416,12 @@ impl EcmascriptChunkItem for ModuleChunkItem {
             source_map,
             globals,
             eval_context,
-            ..
+            comments,
+            original_source_map,
         } = &*parsed
         {
             let mut program = program.clone();
+            let federation = uses_federation_runtime(&program, eval_context.unresolved_mark);
 
             GLOBALS.set(globals, || {
                 if !visitors.is_empty() {
@@ -335,6 +433,15 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                 for visitor in root_visitors {
                     program.visit_mut_with(&mut visitor.create());
                 }
+                if let Some(versions) = downlevel_versions {
+                    downlevel_program(
+                        &mut program,
+                        versions,
+                        comments,
+                        eval_context.unresolved_mark,
+                    );
+                    program.visit_mut_with(&mut SwcHelperImports { ids: &swc_helpers });
+                }
                 program.visit_mut_with(&mut swc_core::ecma::transforms::base::hygiene::hygiene());
                 program.visit_mut_with(&mut swc_core::ecma::transforms::base::fixer::fixer(None));
             });
@@ -345,24 +452,31 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
             let mut srcmap = vec![];
 
+            let emitted_comments = emitted_comments(comments, comments_policy);
             let mut emitter = Emitter {
                 cfg: swc_core::ecma::codegen::Config {
                     ..Default::default()
                 },
                 cm: source_map.clone(),
-                comments: None,
+                comments: emitted_comments.as_ref().map(|c| c as &dyn Comments),
                 wr: JsWriter::new(source_map.clone(), "\n", &mut bytes, Some(&mut srcmap)),
             };
 
             emitter.emit_program(&program)?;
 
-            let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap).cell();
+            let mut srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap);
+            if let Some(original_source_map) = *original_source_map {
+                srcmap = srcmap.with_original_source_map(original_source_map);
+            }
+            let srcmap = srcmap.cell();
 
             Ok(EcmascriptChunkItemContent {
                 inner_code: bytes.into(),
                 source_map: Some(srcmap),
                 options: if eval_context.is_esm() {
                     EcmascriptChunkItemOptions {
+                        async_module,
+                        federation,
                         ..Default::default()
                     }
                 } else {
@@ -371,9 +485,15 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                         module: true,
                         exports: true,
                         this: true,
+                        async_module,
+                        federation,
                         ..Default::default()
                     }
                 },
+                legal_comments: match comments_policy {
+                    CommentsPolicy::ExtractLegal => legal_comments(comments),
+                    _ => Vec::new(),
+                },
                 ..Default::default()
             }
             .into())
@@ -392,6 +512,65 @@ impl EcmascriptChunkItem for ModuleChunkItem {
     }
 }
 
+/// The requests of the `@swc/helpers` modules which the module imports when
+/// its syntax is downleveled for `environment`.
+#[turbo_tasks::function]
+async fn swc_helper_requests_of(
+    module: EcmascriptModuleAssetVc,
+    environment: EnvironmentVc,
+) -> Result<StringsVc> {
+    let versions = *environment.runtime_versions().await?;
+    let module = module.await?;
+    let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
+    let ParseResult::Ok {
+        program,
+        globals,
+        eval_context,
+        ..
+    } = &*parsed else {
+        return Ok(StringsVc::empty());
+    };
+    let mut program = program.clone();
+    let requests = GLOBALS.set(globals, || {
+        // The comments of the parse result are shared, so the comments added by the
+        // downleveling must not end up in them
+        downlevel_program(
+            &mut program,
+            versions,
+            &Default::default(),
+            eval_context.unresolved_mark,
+        );
+        swc_helper_requests(&program)
+    });
+    Ok(StringsVc::cell(requests))
+}
+
+/// Checks whether the module references the module federation runtime, i. e.
+/// the unresolved `__turbopack_federation__` identifier.
+fn uses_federation_runtime(program: &Program, unresolved_mark: Mark) -> bool {
+    struct Visitor {
+        unresolved_mark: Mark,
+        found: bool,
+    }
+
+    impl Visit for Visitor {
+        fn visit_ident(&mut self, ident: &Ident) {
+            if &*ident.sym == "__turbopack_federation__"
+                && ident.span.ctxt.outer() == self.unresolved_mark
+            {
+                self.found = true;
+            }
+        }
+    }
+
+    let mut visitor = Visitor {
+        unresolved_mark,
+        found: false,
+    };
+    program.visit_with(&mut visitor);
+    visitor.found
+}
+
 pub fn register() {
     turbo_tasks::register();
     turbo_tasks_fs::register();
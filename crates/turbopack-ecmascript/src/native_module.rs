@@ -0,0 +1,180 @@
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{
+        ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc,
+    },
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
+    reference::{AssetReferencesVc, SingleAssetReferenceVc},
+};
+
+use crate::{
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::stringify_str,
+};
+
+/// A native addon, i. e. a `.node` file, which is required by a module.
+///
+/// The binary can't be bundled, so it's kept external: the module requires
+/// the file from the project at runtime, and the file is referenced, so it's
+/// traced like other files the server needs. Native addons can only be loaded
+/// by Node.js next to the project, see
+/// [ChunkingContext::node_server_project_root]. In other environments, an
+/// issue is emitted and the module throws when it's required.
+#[turbo_tasks::value]
+pub struct NodeNativeModuleAsset {
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NodeNativeModuleAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc) -> Self {
+        Self::cell(NodeNativeModuleAsset { source })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for NodeNativeModuleAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        Ok(AssetReferencesVc::cell(vec![SingleAssetReferenceVc::new(
+            self.source,
+            StringVc::cell(format!(
+                "native addon {}",
+                self.source.path().to_string().await?
+            )),
+        )
+        .into()]))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for NodeNativeModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: NodeNativeModuleAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.as_ecmascript_chunk_placeable()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for NodeNativeModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(
+        self_vc: NodeNativeModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
+        NodeNativeChunkItemVc::cell(NodeNativeChunkItem {
+            module: self_vc,
+            context,
+        })
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.cell()
+    }
+}
+
+#[turbo_tasks::value]
+struct NodeNativeChunkItem {
+    module: NodeNativeModuleAssetVc,
+    context: ChunkingContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for NodeNativeChunkItem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{} (native addon)",
+            self.module.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for NodeNativeChunkItem {
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.module.references()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for NodeNativeChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn related_path(&self) -> FileSystemPathVc {
+        self.module.path()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let path = self.module.path();
+        let path_value = path.await?;
+        let relative_path = match *self.context.node_server_project_root().await? {
+            Some(project_root) => project_root
+                .await?
+                .get_path_to(&path_value)
+                .map(str::to_string),
+            None => None,
+        };
+        let inner_code = if let Some(relative_path) = relative_path {
+            format!(
+                "__turbopack_export_value__(__turbopack_external_require__(\
+                 __turbopack_external_require__(\"path\").join(globalThis.\
+                 __turbopack_project_root__, {})));",
+                stringify_str(&relative_path)
+            )
+        } else {
+            CodeGenerationIssue {
+                severity: IssueSeverity::Error.into(),
+                title: StringVc::cell("native addons can't be bundled".to_string()),
+                message: StringVc::cell(format!(
+                    "the native addon {} can only be loaded by Node.js on a server next to the \
+                     project",
+                    path.to_string().await?
+                )),
+                path,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            format!(
+                "throw new Error({});",
+                stringify_str(&format!(
+                    "Native addon {} can't be loaded in this environment",
+                    path_value.path
+                ))
+            )
+        };
+        Ok(EcmascriptChunkItemContent {
+            inner_code: inner_code.into(),
+            ..Default::default()
+        }
+        .into())
+    }
+}
@@ -1,6 +1,7 @@
 use std::{future::Future, sync::Arc};
 
 use anyhow::{anyhow, Context, Result};
+use sourcemap::{DecodedMap, SourceMap as CrateMap};
 use swc_core::{
     base::SwcComments,
     common::{
@@ -31,7 +32,8 @@ use turbopack_swc_utils::emitter::IssueEmitter;
 use super::EcmascriptModuleAssetType;
 use crate::{
     analyzer::graph::EvalContext,
-    transform::{EcmascriptInputTransformsVc, TransformContext},
+    parse_cache::ParseCache,
+    transform::{order_transforms, EcmascriptInputTransformsVc, TransformContext},
     utils::WrapFuture,
     EcmascriptInputTransform,
 };
@@ -50,6 +52,8 @@ pub enum ParseResult {
         globals: Globals,
         #[turbo_tasks(debug_ignore, trace_ignore)]
         source_map: Arc<SourceMap>,
+        /// A source map of the parsed code, when it's not the original code.
+        original_source_map: Option<SourceMapVc>,
     },
     Unparseable,
     NotFound,
@@ -64,6 +68,32 @@ impl PartialEq for ParseResult {
     }
 }
 
+/// A source file parsed without any transforms or resolving applied. It only
+/// depends on the source, so it is shared between all contexts which include
+/// the same file, e.g. the client and the server.
+#[turbo_tasks::value(shared, serialization = "none", eq = "manual")]
+#[allow(clippy::large_enum_variant)]
+enum ParsedSource {
+    Ok {
+        #[turbo_tasks(trace_ignore)]
+        program: Program,
+        #[turbo_tasks(debug_ignore, trace_ignore)]
+        comments: SwcComments,
+        #[turbo_tasks(debug_ignore, trace_ignore)]
+        source_map: Arc<SourceMap>,
+    },
+    Unparseable,
+}
+
+impl PartialEq for ParsedSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Ok { .. }, Self::Ok { .. }) => false,
+            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+        }
+    }
+}
+
 #[turbo_tasks::value(shared, serialization = "none", eq = "manual")]
 pub struct ParseResultSourceMap {
     /// Confusingly, SWC's SourceMap is not a mapping of transformed locations
@@ -76,11 +106,18 @@ pub struct ParseResultSourceMap {
     /// SourceMap.
     #[turbo_tasks(debug_ignore, trace_ignore)]
     mappings: Vec<(BytePos, LineCol)>,
+
+    /// A source map of the parsed code itself. When present, the generated
+    /// source map points to the sources of this map instead of the parsed
+    /// code.
+    original_source_map: Option<SourceMapVc>,
 }
 
 impl PartialEq for ParseResultSourceMap {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.source_map, &other.source_map) && self.mappings == other.mappings
+        Arc::ptr_eq(&self.source_map, &other.source_map)
+            && self.mappings == other.mappings
+            && self.original_source_map == other.original_source_map
     }
 }
 
@@ -89,20 +126,35 @@ impl ParseResultSourceMap {
         ParseResultSourceMap {
             source_map,
             mappings,
+            original_source_map: None,
         }
     }
+
+    /// Chains the generated source map with the source map of the parsed code.
+    pub fn with_original_source_map(mut self, original_source_map: SourceMapVc) -> Self {
+        self.original_source_map = Some(original_source_map);
+        self
+    }
 }
 
 #[turbo_tasks::value_impl]
 impl GenerateSourceMap for ParseResultSourceMap {
     #[turbo_tasks::function]
-    fn generate_source_map(&self) -> SourceMapVc {
+    async fn generate_source_map(&self) -> Result<SourceMapVc> {
+        let original_source_map = match self.original_source_map {
+            Some(map) => Some(map.flatten().await?),
+            None => None,
+        };
+        let original_source_map: Option<&CrateMap> = match original_source_map.as_deref() {
+            Some(turbopack_core::source_map::SourceMap::Regular(map)) => Some(map),
+            _ => None,
+        };
         let map = self.source_map.build_source_map_with_config(
             &self.mappings,
-            None,
+            original_source_map,
             InlineSourcesContentConfig {},
         );
-        SourceMapVc::new_regular(map)
+        Ok(SourceMapVc::new_regular(map))
     }
 }
 
@@ -141,17 +193,73 @@ pub async fn parse(
             FileContent::Content(file) => match file.content().to_str() {
                 Ok(string) => {
                     let transforms = &*transforms.await?;
-                    match parse_content(
-                        string.into_owned(),
+                    let cache = ParseCache::new(source.path(), ty, transforms, &string).await?;
+                    if let Some(cache) = &cache {
+                        if let Some((code, original_source_map)) = cache.read().await {
+                            // The cached code has been transformed already.
+                            return parse_content(
+                                code,
+                                fs_path,
+                                file_path_hash,
+                                source,
+                                ty,
+                                &[],
+                                Some(original_source_map),
+                            )
+                            .await;
+                        }
+                    }
+                    let input_source_map = read_input_source_map(source.path(), &string).await?;
+                    let parsed = parse_source(source, Value::new(ty)).await?;
+                    let ParsedSource::Ok {
+                        program,
+                        comments,
+                        source_map,
+                    } = &*parsed
+                    else {
+                        return Ok(ParseResult::Unparseable.cell());
+                    };
+                    // The transforms modify the program and the comments, so they work on
+                    // copies of the shared parse result.
+                    match transform_program(
+                        program.clone(),
+                        copy_comments(comments),
+                        source_map.clone(),
                         fs_path,
                         file_path_hash,
                         source,
                         ty,
                         transforms,
+                        input_source_map,
                     )
                     .await
                     {
-                        Ok(result) => result,
+                        Ok(result) => {
+                            if let Some(cache) = cache {
+                                if let ParseResult::Ok {
+                                    program,
+                                    comments,
+                                    globals,
+                                    source_map,
+                                    original_source_map,
+                                    ..
+                                } = &*result.await?
+                                {
+                                    // A failed write only results in a cache miss next
+                                    // time.
+                                    let _ = cache
+                                        .write(
+                                            program,
+                                            comments,
+                                            globals,
+                                            source_map,
+                                            *original_source_map,
+                                        )
+                                        .await;
+                                }
+                            }
+                            result
+                        }
                         Err(e) => {
                             return Err(e).context(anyhow!(
                                 "Transforming and/or parsing of {} failed",
@@ -168,6 +276,46 @@ pub async fn parse(
     })
 }
 
+/// Parses the content of the source. Parsing doesn't depend on the transforms
+/// of a context, so this task is shared by all contexts which include the
+/// source.
+#[turbo_tasks::function]
+async fn parse_source(
+    source: AssetVc,
+    ty: Value<EcmascriptModuleAssetType>,
+) -> Result<ParsedSourceVc> {
+    let content = source.content();
+    let fs_path = &*source.path().await?;
+    let AssetContent::File(file) = &*content.await? else {
+        return Ok(ParsedSource::Unparseable.cell());
+    };
+    let FileContent::Content(file) = &*file.await? else {
+        return Ok(ParsedSource::Unparseable.cell());
+    };
+    let Ok(string) = file.content().to_str() else {
+        return Ok(ParsedSource::Unparseable.cell());
+    };
+    let source_map: Arc<SourceMap> = Default::default();
+    let handler = parse_handler(source, &source_map);
+    Ok(
+        match parse_program(
+            string.into_owned(),
+            fs_path,
+            ty.into_value(),
+            &source_map,
+            &handler,
+        ) {
+            Some((program, comments)) => ParsedSource::Ok {
+                program,
+                comments,
+                source_map,
+            }
+            .cell(),
+            None => ParsedSource::Unparseable.cell(),
+        },
+    )
+}
+
 async fn parse_content(
     string: String,
     fs_path: &FileSystemPath,
@@ -175,9 +323,30 @@ async fn parse_content(
     source: AssetVc,
     ty: EcmascriptModuleAssetType,
     transforms: &[EcmascriptInputTransform],
+    original_source_map: Option<SourceMapVc>,
 ) -> Result<ParseResultVc> {
     let source_map: Arc<SourceMap> = Default::default();
-    let handler = Handler::with_emitter(
+    let handler = parse_handler(source, &source_map);
+    let Some((program, comments)) = parse_program(string, fs_path, ty, &source_map, &handler)
+    else {
+        return Ok(ParseResult::Unparseable.cell());
+    };
+    transform_program(
+        program,
+        comments,
+        source_map,
+        fs_path,
+        file_path_hash,
+        source,
+        ty,
+        transforms,
+        original_source_map,
+    )
+    .await
+}
+
+fn parse_handler(source: AssetVc, source_map: &Arc<SourceMap>) -> Handler {
+    Handler::with_emitter(
         true,
         false,
         box IssueEmitter {
@@ -185,7 +354,91 @@ async fn parse_content(
             source_map: source_map.clone(),
             title: Some("Parsing ecmascript source code failed".to_string()),
         },
-    );
+    )
+}
+
+/// Parses the code into a program without resolving it. Errors are reported
+/// to the `handler`, and `None` is returned when there are any.
+fn parse_program(
+    string: String,
+    fs_path: &FileSystemPath,
+    ty: EcmascriptModuleAssetType,
+    source_map: &Arc<SourceMap>,
+    handler: &Handler,
+) -> Option<(Program, SwcComments)> {
+    GLOBALS.set(&Globals::new(), || {
+        let file_name = FileName::Custom(fs_path.path.clone());
+        let fm = source_map.new_source_file(file_name, string);
+
+        let comments = SwcComments::default();
+
+        let lexer = Lexer::new(
+            match ty {
+                EcmascriptModuleAssetType::Ecmascript => Syntax::Es(EsConfig {
+                    jsx: true,
+                    fn_bind: true,
+                    decorators: true,
+                    decorators_before_export: true,
+                    export_default_from: true,
+                    import_assertions: true,
+                    allow_super_outside_method: true,
+                    allow_return_outside_function: true,
+                }),
+                EcmascriptModuleAssetType::Typescript
+                | EcmascriptModuleAssetType::TypescriptWithTypes => Syntax::Typescript(TsConfig {
+                    decorators: true,
+                    dts: false,
+                    no_early_errors: true,
+                    tsx: true,
+                }),
+                EcmascriptModuleAssetType::TypescriptDeclaration => Syntax::Typescript(TsConfig {
+                    decorators: true,
+                    dts: true,
+                    no_early_errors: true,
+                    tsx: true,
+                }),
+            },
+            EsVersion::latest(),
+            StringInput::from(&*fm),
+            Some(&comments),
+        );
+
+        let mut parser = Parser::new_from(lexer);
+
+        let mut has_errors = false;
+        for e in parser.take_errors() {
+            e.into_diagnostic(handler).emit();
+            has_errors = true
+        }
+
+        if has_errors {
+            return None;
+        }
+
+        match parser.parse_program() {
+            Ok(program) => Some((program, comments)),
+            Err(e) => {
+                e.into_diagnostic(handler).emit();
+                None
+            }
+        }
+    })
+}
+
+/// Resolves the program and applies the transforms of the context to it.
+#[allow(clippy::too_many_arguments)]
+async fn transform_program(
+    mut program: Program,
+    comments: SwcComments,
+    source_map: Arc<SourceMap>,
+    fs_path: &FileSystemPath,
+    file_path_hash: u128,
+    source: AssetVc,
+    ty: EcmascriptModuleAssetType,
+    transforms: &[EcmascriptInputTransform],
+    original_source_map: Option<SourceMapVc>,
+) -> Result<ParseResultVc> {
+    let handler = parse_handler(source, &source_map);
     let globals = Globals::new();
     let globals_ref = &globals;
     let helpers = GLOBALS.set(globals_ref, || Helpers::new(true));
@@ -196,68 +449,6 @@ async fn parse_content(
             })
         },
         async {
-            let file_name = FileName::Custom(fs_path.path.clone());
-            let fm = source_map.new_source_file(file_name.clone(), string);
-
-            let comments = SwcComments::default();
-
-            let mut parsed_program = {
-                let lexer = Lexer::new(
-                    match ty {
-                        EcmascriptModuleAssetType::Ecmascript => Syntax::Es(EsConfig {
-                            jsx: true,
-                            fn_bind: true,
-                            decorators: true,
-                            decorators_before_export: true,
-                            export_default_from: true,
-                            import_assertions: true,
-                            allow_super_outside_method: true,
-                            allow_return_outside_function: true,
-                        }),
-                        EcmascriptModuleAssetType::Typescript
-                        | EcmascriptModuleAssetType::TypescriptWithTypes => {
-                            Syntax::Typescript(TsConfig {
-                                decorators: true,
-                                dts: false,
-                                no_early_errors: true,
-                                tsx: true,
-                            })
-                        }
-                        EcmascriptModuleAssetType::TypescriptDeclaration => {
-                            Syntax::Typescript(TsConfig {
-                                decorators: true,
-                                dts: true,
-                                no_early_errors: true,
-                                tsx: true,
-                            })
-                        }
-                    },
-                    EsVersion::latest(),
-                    StringInput::from(&*fm),
-                    Some(&comments),
-                );
-
-                let mut parser = Parser::new_from(lexer);
-
-                let mut has_errors = false;
-                for e in parser.take_errors() {
-                    e.into_diagnostic(&handler).emit();
-                    has_errors = true
-                }
-
-                if has_errors {
-                    return Ok(ParseResult::Unparseable);
-                }
-
-                match parser.parse_program() {
-                    Ok(parsed_program) => parsed_program,
-                    Err(e) => {
-                        e.into_diagnostic(&handler).emit();
-                        return Ok(ParseResult::Unparseable);
-                    }
-                }
-            };
-
             let unresolved_mark = Mark::new();
             let top_level_mark = Mark::new();
 
@@ -267,7 +458,7 @@ async fn parse_content(
                     | EcmascriptModuleAssetType::TypescriptWithTypes
                     | EcmascriptModuleAssetType::TypescriptDeclaration
             );
-            parsed_program.visit_mut_with(&mut resolver(
+            program.visit_mut_with(&mut resolver(
                 unresolved_mark,
                 top_level_mark,
                 is_typescript,
@@ -281,21 +472,23 @@ async fn parse_content(
                 file_path_str: &fs_path.path,
                 file_name_str: fs_path.file_name(),
                 file_name_hash: file_path_hash,
+                file_path: source.path(),
             };
-            for transform in transforms.iter() {
-                transform.apply(&mut parsed_program, &context).await?;
+            for transform in order_transforms(transforms).await? {
+                transform.apply(&mut program, &context).await?;
             }
 
-            let eval_context = EvalContext::new(&parsed_program, unresolved_mark);
+            let eval_context = EvalContext::new(&program, unresolved_mark);
 
             Ok::<ParseResult, anyhow::Error>(ParseResult::Ok {
-                program: parsed_program,
+                program,
                 comments,
                 eval_context,
                 // Temporary globals as the current one can't be moved yet, since they are
                 // borrowed
                 globals: Globals::new(),
                 source_map,
+                original_source_map,
             })
         },
     )
@@ -310,6 +503,52 @@ async fn parse_content(
     Ok(result.cell())
 }
 
+/// Copies the comments into new storage. Cloning [SwcComments] shares the
+/// storage.
+fn copy_comments(comments: &SwcComments) -> SwcComments {
+    let copy = SwcComments::default();
+    for entry in comments.leading.iter() {
+        copy.leading.insert(*entry.key(), entry.value().clone());
+    }
+    for entry in comments.trailing.iter() {
+        copy.trailing.insert(*entry.key(), entry.value().clone());
+    }
+    copy
+}
+
+/// Loads the source map of the parsed code, e. g. of compiled code in
+/// `node_modules`, so that generated source maps point to the original
+/// sources. It's referenced by a `//# sourceMappingURL=` comment, either as
+/// inline data url or as file, or is placed next to the parsed file.
+async fn read_input_source_map(
+    path: FileSystemPathVc,
+    content: &str,
+) -> Result<Option<SourceMapVc>> {
+    let map_path = match sourcemap::locate_sourcemap_reference_slice(content.as_bytes()) {
+        Ok(Some(reference)) => {
+            if reference.get_url().starts_with("data:") {
+                return Ok(match reference.get_embedded_sourcemap() {
+                    Ok(Some(DecodedMap::Regular(map))) => Some(SourceMapVc::new_regular(map)),
+                    _ => None,
+                });
+            }
+            match *path.parent().try_join(reference.get_url()).await? {
+                Some(map_path) => map_path,
+                None => return Ok(None),
+            }
+        }
+        _ => path.append(".map"),
+    };
+    let FileContent::Content(file) = &*map_path.read().await? else {
+        return Ok(None);
+    };
+    // Invalid source maps are ignored, the generated source map points to the
+    // parsed code then.
+    Ok(CrateMap::from_reader(file.content().read())
+        .ok()
+        .map(SourceMapVc::new_regular))
+}
+
 #[turbo_tasks::function]
 async fn hash_file_path(file_path_vc: FileSystemPathVc) -> Result<U64Vc> {
     let file_path = &*file_path_vc.await?;
@@ -317,3 +556,209 @@ async fn hash_file_path(file_path_vc: FileSystemPathVc) -> Result<U64Vc> {
     hasher.write_bytes(file_path.file_name().as_bytes());
     Ok(U64Vc::cell(hasher.finish()))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+
+    use anyhow::{anyhow, Result};
+    use swc_core::ecma::{
+        ast::{Program, Str},
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    };
+    use turbo_tasks::{TurboTasks, Value};
+    use turbo_tasks_fs::{DiskFileSystemVc, File};
+    use turbo_tasks_memory::MemoryBackend;
+    use turbopack_core::{
+        asset::{AssetContent, AssetVc},
+        issue::IssueVc,
+        virtual_asset::VirtualAssetVc,
+    };
+
+    use super::{parse, ParseResult};
+    use crate::{
+        transform::{CompileTimeDefines, EcmascriptInputTransformsVc},
+        CustomTransformer, EcmascriptInputTransform, EcmascriptModuleAssetType, TransformContext,
+        TransformPluginOrder, TransformPluginVc,
+    };
+
+    #[derive(Default)]
+    struct Strings(Vec<String>);
+
+    impl Visit for Strings {
+        fn visit_str(&mut self, s: &Str) {
+            self.0.push(s.value.to_string());
+        }
+    }
+
+    /// A plugin which maps every string literal with `map`.
+    #[derive(Debug)]
+    struct MapStrings(fn(&str) -> String);
+
+    impl CustomTransformer for MapStrings {
+        fn name(&self) -> &str {
+            "map-strings"
+        }
+
+        fn transform(&self, program: &mut Program, _context: &TransformContext<'_>) -> Result<()> {
+            program.visit_mut_with(&mut StrMapper(self.0));
+            Ok(())
+        }
+    }
+
+    struct StrMapper(fn(&str) -> String);
+
+    impl VisitMut for StrMapper {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            s.value = (self.0)(&s.value).into();
+            s.raw = None;
+        }
+    }
+
+    #[derive(Debug)]
+    struct Failing;
+
+    impl CustomTransformer for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn transform(&self, _program: &mut Program, _context: &TransformContext<'_>) -> Result<()> {
+            Err(anyhow!("unsupported syntax"))
+        }
+    }
+
+    fn plugin(
+        transformer: impl CustomTransformer + 'static,
+        order: TransformPluginOrder,
+    ) -> EcmascriptInputTransform {
+        EcmascriptInputTransform::Plugin(TransformPluginVc::new(Box::new(transformer), order))
+    }
+
+    /// A source which exports `process.env.VALUE` and a string literal.
+    fn shared_source() -> AssetVc {
+        let fs = DiskFileSystemVc::new(
+            "project".to_string(),
+            env!("CARGO_MANIFEST_DIR").to_string(),
+        );
+        VirtualAssetVc::new(
+            fs.root().join("shared.js"),
+            AssetContent::File(
+                File::from("export const value = [process.env.VALUE, \"shared\"];").into(),
+            )
+            .cell(),
+        )
+        .into()
+    }
+
+    /// The string literals of `source` after parsing it with `value` defined
+    /// as `process.env.VALUE`.
+    async fn strings(source: AssetVc, value: Option<&str>) -> Result<Vec<String>> {
+        let transforms = match value {
+            Some(value) => {
+                let mut defines = CompileTimeDefines::default();
+                defines.insert("process.env.VALUE", value);
+                vec![EcmascriptInputTransform::Defines(defines.cell())]
+            }
+            None => vec![],
+        };
+        transformed_strings(source, transforms).await
+    }
+
+    /// The string literals of `source` after parsing it with `transforms`.
+    async fn transformed_strings(
+        source: AssetVc,
+        transforms: Vec<EcmascriptInputTransform>,
+    ) -> Result<Vec<String>> {
+        let result = parse(
+            source,
+            Value::new(EcmascriptModuleAssetType::Ecmascript),
+            EcmascriptInputTransformsVc::cell(transforms),
+        )
+        .await?;
+        let ParseResult::Ok { program, .. } = &*result else {
+            panic!("the source is parseable");
+        };
+        let mut strings = Strings::default();
+        program.visit_with(&mut strings);
+        Ok(strings.0)
+    }
+
+    /// Runs `test` in a new turbo tasks instance.
+    fn run<F: Future<Output = Result<()>> + Send + 'static>(test: impl FnOnce() -> F) {
+        crate::register();
+        let future = test();
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(future).await
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn transforms_work_on_copies_of_the_parsed_source() {
+        run(|| async {
+            let source = shared_source();
+            // Each context transforms the same parsed source, one must not see
+            // the transforms of the other.
+            assert_eq!(strings(source, Some("client")).await?, ["client", "shared"]);
+            assert_eq!(strings(source, Some("server")).await?, ["server", "shared"]);
+            assert_eq!(strings(source, None).await?, ["shared"]);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn plugins_run_in_their_order() {
+        run(|| async {
+            let mut defines = CompileTimeDefines::default();
+            defines.insert("process.env.VALUE", "client");
+            let transforms = vec![
+                plugin(
+                    MapStrings(|s| format!("{s}-late")),
+                    TransformPluginOrder::Late,
+                ),
+                EcmascriptInputTransform::Defines(defines.cell()),
+                // Sees the program before `process.env.VALUE` is replaced.
+                plugin(
+                    MapStrings(|s| s.replace("shared", "early")),
+                    TransformPluginOrder::Early,
+                ),
+            ];
+            assert_eq!(
+                transformed_strings(shared_source(), transforms).await?,
+                ["client-late", "early-late"]
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn reports_plugin_errors_as_issues() {
+        run(|| async {
+            let transforms = vec![plugin(Failing, TransformPluginOrder::Normal)];
+            let result = parse(
+                shared_source(),
+                Value::new(EcmascriptModuleAssetType::Ecmascript),
+                EcmascriptInputTransformsVc::cell(transforms),
+            );
+            // The program is used as it is.
+            assert!(matches!(&*result.await?, ParseResult::Ok { .. }));
+            let issues = IssueVc::peek_issues_with_path(result)
+                .await?
+                .await?
+                .get_plain_issues()
+                .await?;
+            assert_eq!(issues.len(), 1, "{issues:#?}");
+            assert_eq!(
+                issues[0].title,
+                "Transform plugin failing reported a problem"
+            );
+            assert!(issues[0].description.contains("unsupported syntax"));
+            Ok(())
+        });
+    }
+}
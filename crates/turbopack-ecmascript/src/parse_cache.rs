@@ -0,0 +1,322 @@
+use std::{fmt::Write as _, path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sourcemap::SourceMap as CrateMap;
+use swc_core::{
+    base::SwcComments,
+    common::{BytePos, Globals, LineCol, SourceMap, GLOBALS},
+    ecma::{
+        ast::Program,
+        codegen::{text_writer::JsWriter, Emitter},
+        transforms::base::{fixer::fixer, hygiene::hygiene},
+        visit::VisitMutWith,
+    },
+};
+use turbo_tasks_fs::{to_sys_path, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, DeterministicHasher, Xxh3Hash64Hasher};
+use turbopack_core::source_map::{GenerateSourceMap, SourceMapVc};
+
+use crate::{parse::ParseResultSourceMap, EcmascriptInputTransform, EcmascriptModuleAssetType};
+
+/// Needs to be bumped when the output of the transforms changes for the same
+/// input, e. g. when updating swc.
+const CACHE_VERSION: u32 = 2;
+
+/// A cache for the transformed code of files in `node_modules`, which is
+/// stored in `node_modules/.cache/turbopack/parse` and survives restarts.
+///
+/// Entries are keyed by the hash of the file content, path, module type and
+/// transforms. On a hit the transformed code is parsed again, but the
+/// transforms, which are the expensive part, are skipped. A source map of the
+/// transformed code is stored with it, so that source maps still point to the
+/// original sources.
+pub(crate) struct ParseCache {
+    file: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ParseCacheEntry {
+    code: String,
+    map: String,
+}
+
+impl ParseCache {
+    /// Returns the cache entry for the file, or None when the file can't be
+    /// cached.
+    pub async fn new(
+        path: FileSystemPathVc,
+        ty: EcmascriptModuleAssetType,
+        transforms: &[EcmascriptInputTransform],
+        content: &str,
+    ) -> Result<Option<Self>> {
+        let fs_path = &*path.await?;
+        let Some(directory) = cache_directory(&fs_path.path) else {
+            return Ok(None);
+        };
+        let Some(transforms_key) = transforms_cache_key(transforms).await? else {
+            return Ok(None);
+        };
+        let Some(directory) = to_sys_path(path.root().join(&directory)).await? else {
+            return Ok(None);
+        };
+
+        let mut hasher = Xxh3Hash64Hasher::new();
+        hasher.write_bytes(&CACHE_VERSION.to_le_bytes());
+        hasher.write_bytes(fs_path.path.as_bytes());
+        hasher.write_bytes(format!("{ty:?}").as_bytes());
+        hasher.write_bytes(transforms_key.as_bytes());
+        hasher.write_bytes(content.as_bytes());
+        let file = directory.join(format!("{}.json", encode_hex(hasher.finish())));
+
+        Ok(Some(ParseCache { file }))
+    }
+
+    /// Reads the transformed code and its source map. Any error is treated as
+    /// a cache miss.
+    pub async fn read(&self) -> Option<(String, SourceMapVc)> {
+        let bytes = tokio::fs::read(&self.file).await.ok()?;
+        let entry: ParseCacheEntry = serde_json::from_slice(&bytes).ok()?;
+        let map = CrateMap::from_slice(entry.map.as_bytes()).ok()?;
+        Some((entry.code, SourceMapVc::new_regular(map)))
+    }
+
+    /// Stores the transformed program.
+    pub async fn write(
+        &self,
+        program: &Program,
+        comments: &SwcComments,
+        globals: &Globals,
+        source_map: &Arc<SourceMap>,
+        original_source_map: Option<SourceMapVc>,
+    ) -> Result<()> {
+        let (code, mappings) = emit(program, comments, globals, source_map)?;
+
+        let mut map = ParseResultSourceMap::new(source_map.clone(), mappings);
+        if let Some(original_source_map) = original_source_map {
+            map = map.with_original_source_map(original_source_map);
+        }
+        let map = map.cell().generate_source_map().to_rope().await?;
+        let entry = ParseCacheEntry {
+            code: String::from_utf8(code)?,
+            map: map.to_str()?.into_owned(),
+        };
+
+        if let Some(directory) = self.file.parent() {
+            tokio::fs::create_dir_all(directory).await?;
+        }
+        // Write to a temporary file first, so that concurrent readers never see
+        // a partial entry.
+        let temp_file = self.file.with_extension("json.tmp");
+        tokio::fs::write(&temp_file, serde_json::to_vec(&entry)?).await?;
+        tokio::fs::rename(&temp_file, &self.file).await?;
+        Ok(())
+    }
+}
+
+/// Emits the transformed program and its mappings. Like the code of chunks,
+/// the program is made hygienic first: identifiers which transforms add, e.g.
+/// for React Refresh, may have the same name as other bindings, and would
+/// refer to them once the code is parsed again. The fixer adds the parentheses
+/// which transforms may have left out.
+fn emit(
+    program: &Program,
+    comments: &SwcComments,
+    globals: &Globals,
+    source_map: &Arc<SourceMap>,
+) -> Result<(Vec<u8>, Vec<(BytePos, LineCol)>)> {
+    let mut program = program.clone();
+    GLOBALS.set(globals, || {
+        program.visit_mut_with(&mut hygiene());
+        program.visit_mut_with(&mut fixer(None));
+    });
+
+    let mut code = Vec::new();
+    let mut mappings = Vec::new();
+    let mut emitter = Emitter {
+        cfg: Default::default(),
+        cm: source_map.clone(),
+        // Comments are kept, as they may contain annotations and magic comments
+        // for the analysis.
+        comments: Some(comments),
+        wr: JsWriter::new(source_map.clone(), "\n", &mut code, Some(&mut mappings)),
+    };
+    emitter.emit_program(&program)?;
+    Ok((code, mappings))
+}
+
+/// Only files in `node_modules` are cached, as they rarely change. The cache
+/// is placed in the outermost `node_modules` directory.
+fn cache_directory(path: &str) -> Option<String> {
+    let prefix = if path.starts_with("node_modules/") {
+        ""
+    } else {
+        let i = path.find("/node_modules/")?;
+        &path[..=i]
+    };
+    Some(format!("{prefix}node_modules/.cache/turbopack/parse"))
+}
+
+/// Returns a key that identifies the transforms across restarts, or None when
+/// a transform can't be identified that way.
+async fn transforms_cache_key(transforms: &[EcmascriptInputTransform]) -> Result<Option<String>> {
+    let mut key = String::new();
+    for transform in transforms {
+        match transform {
+            EcmascriptInputTransform::ClientDirective(transition_name) => {
+                write!(key, "ClientDirective({:?})", &*transition_name.await?)?
+            }
+            EcmascriptInputTransform::Decorators(options) => {
+                write!(key, "Decorators({:?})", &*options.await?)?
+            }
+            EcmascriptInputTransform::Defines(defines) => {
+                write!(key, "Defines({:?})", &*defines.await?)?
+            }
+            EcmascriptInputTransform::FeatureFlags(flags) => {
+                write!(key, "FeatureFlags({:?})", &*flags.await?)?
+            }
+            EcmascriptInputTransform::NextJsFont(font_loaders) => {
+                write!(key, "NextJsFont({:?})", &*font_loaders.await?)?
+            }
+            EcmascriptInputTransform::Plugin(plugin) => {
+                let plugin = plugin.await?;
+                let transformer = plugin.transformer();
+                let Some(cache_key) = transformer.cache_key() else {
+                    return Ok(None);
+                };
+                write!(key, "Plugin({}, {cache_key:?})", transformer.name())?
+            }
+            EcmascriptInputTransform::React {
+                refresh: Some(refresh),
+            } => write!(key, "React({:?})", &*refresh.await?)?,
+            EcmascriptInputTransform::NextJsDynamic {
+                pages_dir: Some(_), ..
+            }
+            | EcmascriptInputTransform::PresetEnv { .. } => return Ok(None),
+            // The remaining transforms don't reference other values.
+            _ => write!(key, "{transform:?}")?,
+        }
+        key.push(';');
+    }
+    Ok(Some(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        base::SwcComments,
+        common::{FileName, Globals, Mark, SourceMap, GLOBALS},
+        ecma::{
+            ast::{EsVersion, Module, Program},
+            parser::parse_file_as_module,
+            transforms::base::{fixer::paren_remover, resolver},
+            visit::VisitMutWith,
+        },
+    };
+
+    use super::{cache_directory, emit, transforms_cache_key};
+    use crate::EcmascriptInputTransform;
+
+    fn parse(source_map: &Arc<SourceMap>, code: &str) -> Module {
+        let file = source_map.new_source_file(FileName::Anon, code.to_string());
+        parse_file_as_module(
+            &file,
+            Default::default(),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn emitted_code_keeps_bindings_apart() {
+        let globals = Globals::new();
+        let source_map: Arc<SourceMap> = Default::default();
+        let program = GLOBALS.set(&globals, || {
+            let unresolved_mark = Mark::new();
+            let mut module = parse(
+                &source_map,
+                "const _c = 'user';\nconsole.log(_c);\n(a, b).c;",
+            );
+            module.visit_mut_with(&mut resolver(unresolved_mark, Mark::new(), false));
+            // Transforms can leave out the parentheses which the code needs.
+            module.visit_mut_with(&mut paren_remover(None));
+            // A binding with the same name, which a transform added.
+            let mut added = parse(&source_map, "const _c = 'added';");
+            added.visit_mut_with(&mut resolver(unresolved_mark, Mark::new(), false));
+            module.body.extend(added.body);
+            Program::Module(module)
+        });
+
+        let (code, _) = emit(&program, &SwcComments::default(), &globals, &source_map).unwrap();
+        let code = String::from_utf8(code).unwrap();
+
+        // Parsed again, as on a warm start, the code behaves the same.
+        GLOBALS.set(&Globals::new(), || parse(&Default::default(), &code));
+        let binding = |value: &str| {
+            code.lines()
+                .find(|line| line.starts_with("const ") && line.contains(value))
+                .and_then(|line| line.split(' ').nth(1))
+                .unwrap()
+                .to_string()
+        };
+        let user = binding("user");
+        assert_ne!(user, binding("added"), "{code}");
+        assert!(code.contains(&format!("console.log({user});")), "{code}");
+        assert!(code.contains("(a, b).c;"), "{code}");
+    }
+
+    fn cache_key(transforms: &[EcmascriptInputTransform]) -> Option<String> {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(transforms_cache_key(transforms))
+            .unwrap()
+    }
+
+    #[test]
+    fn cache_directory_in_outermost_node_modules() {
+        assert_eq!(cache_directory("src/index.js"), None);
+        assert_eq!(
+            cache_directory("node_modules/react/index.js").as_deref(),
+            Some("node_modules/.cache/turbopack/parse")
+        );
+        assert_eq!(
+            cache_directory("app/node_modules/a/node_modules/b/index.js").as_deref(),
+            Some("app/node_modules/.cache/turbopack/parse")
+        );
+    }
+
+    #[test]
+    fn transforms_cache_key_depends_on_order() {
+        let transforms = [
+            EcmascriptInputTransform::TypeScript,
+            EcmascriptInputTransform::React { refresh: None },
+            EcmascriptInputTransform::CommonJs,
+        ];
+        let key = cache_key(&transforms).unwrap();
+        assert_eq!(
+            key, "TypeScript;React { refresh: None };CommonJs;",
+            "the key needs to be the same across restarts"
+        );
+
+        let mut reversed = transforms;
+        reversed.reverse();
+        assert_ne!(cache_key(&reversed).unwrap(), key);
+        assert_eq!(cache_key(&[]).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn next_dynamic_without_pages_dir_is_cached() {
+        let transforms = [EcmascriptInputTransform::NextJsDynamic {
+            is_development: true,
+            is_server: false,
+            is_server_components: false,
+            pages_dir: None,
+        }];
+        assert!(cache_key(&transforms).is_some());
+    }
+}
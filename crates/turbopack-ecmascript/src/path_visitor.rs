@@ -137,6 +137,7 @@ impl VisitMutAstPath for ApplyVisitors<'_, '_> {
 mod tests {
     use std::sync::Arc;
 
+    use proptest::{prelude::*, sample::Index};
     use swc_core::{
         common::{errors::HANDLER, FileName, Mark, SourceFile, SourceMap},
         ecma::{
@@ -144,12 +145,15 @@ mod tests {
             codegen::{text_writer::JsWriter, Emitter},
             parser::parse_file_as_module,
             transforms::base::resolver,
-            visit::{fields::*, AstParentKind, VisitMut, VisitMutWith, VisitMutWithPath},
+            visit::{
+                fields::*, AstKindPath, AstParentKind, VisitMut, VisitMutAstPath, VisitMutWith,
+                VisitMutWithPath,
+            },
         },
         testing::run_test,
     };
 
-    use super::{ApplyVisitors, VisitorFactory};
+    use super::{find_range, ApplyVisitors, AstPath, VisitorFactory};
 
     fn parse(fm: &SourceFile) -> Module {
         let mut m = parse_file_as_module(
@@ -282,4 +286,220 @@ mod tests {
         })
         .unwrap();
     }
+
+    /// Kinds that are likely to collide, so ranges of multiple visitors are
+    /// generated.
+    fn kinds() -> Vec<AstParentKind> {
+        vec![
+            AstParentKind::Module(ModuleField::Body(0)),
+            AstParentKind::Module(ModuleField::Body(1)),
+            AstParentKind::ModuleItem(ModuleItemField::Stmt),
+            AstParentKind::Stmt(StmtField::Expr),
+            AstParentKind::Expr(ExprField::Paren),
+            AstParentKind::Expr(ExprField::Seq),
+            AstParentKind::Expr(ExprField::Lit),
+            AstParentKind::SeqExpr(SeqExprField::Exprs(0)),
+            AstParentKind::SeqExpr(SeqExprField::Exprs(1)),
+            AstParentKind::Lit(LitField::Str),
+        ]
+    }
+
+    fn kind() -> impl Strategy<Value = AstParentKind> {
+        prop::sample::select(kinds())
+    }
+
+    proptest! {
+        /// `find_range` must select exactly the visitors a linear scan selects.
+        #[test]
+        fn find_range_matches_linear_scan(
+            prefix in prop::collection::vec(kind(), 0..3),
+            suffixes in prop::collection::vec(prop::collection::vec(kind(), 1..4), 1..12),
+            kind in kind(),
+        ) {
+            // All visitors share the path up to `index`, like in `ApplyVisitors`.
+            let index = prefix.len();
+            let mut paths: Vec<AstPath> = suffixes
+                .into_iter()
+                .map(|suffix| prefix.iter().copied().chain(suffix).collect())
+                .collect();
+            paths.sort();
+            let factory = replacer("a", "b");
+            let visitors: Vec<(&AstPath, &dyn VisitorFactory)> =
+                paths.iter().map(|path| (path, &factory as _)).collect();
+
+            let expected: Vec<&AstPath> = visitors
+                .iter()
+                .filter(|(path, _)| path[index] == kind)
+                .map(|(path, _)| *path)
+                .collect();
+            let actual: Vec<&AstPath> = find_range(&visitors, &kind, index)
+                .map(|range| range.iter().map(|(path, _)| *path).collect())
+                .unwrap_or_default();
+            prop_assert_eq!(actual, expected);
+        }
+    }
+
+    /// A small subset of expressions, which nest the node types that
+    /// `ApplyVisitors` intercepts.
+    #[derive(Debug, Clone)]
+    enum TestExpr {
+        Str(&'static str),
+        Array(Vec<TestExpr>),
+        Seq(Vec<TestExpr>),
+        Call(Vec<TestExpr>),
+        Object(Vec<TestExpr>),
+    }
+
+    impl TestExpr {
+        fn to_js(&self) -> String {
+            fn join(items: &[TestExpr]) -> String {
+                items
+                    .iter()
+                    .map(|e| e.to_js())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+            match self {
+                TestExpr::Str(s) => format!("'{s}'"),
+                TestExpr::Array(items) => format!("[{}]", join(items)),
+                TestExpr::Seq(items) => format!("({})", join(items)),
+                TestExpr::Call(items) => format!("f({})", join(items)),
+                TestExpr::Object(items) => format!(
+                    "({{ {} }})",
+                    items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, e)| format!("k{i}: {}", e.to_js()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+    }
+
+    fn test_expr() -> impl Strategy<Value = TestExpr> {
+        let leaf = prop::sample::select(vec!["a", "b", "c"]).prop_map(TestExpr::Str);
+        leaf.prop_recursive(4, 32, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(TestExpr::Array),
+                prop::collection::vec(inner.clone(), 2..4).prop_map(TestExpr::Seq),
+                prop::collection::vec(inner.clone(), 0..3).prop_map(TestExpr::Call),
+                prop::collection::vec(inner, 0..3).prop_map(TestExpr::Object),
+            ]
+        })
+    }
+
+    /// Appends a marker to every string it visits, so the order in which
+    /// visitors are applied is visible in the output.
+    struct Marker(usize);
+
+    impl VisitorFactory for Marker {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box &*self
+        }
+    }
+
+    impl VisitMut for &'_ Marker {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            s.value = format!("{}#{}", s.value, self.0).into();
+            s.raw = None;
+        }
+    }
+
+    /// Records the paths to all nodes that `ApplyVisitors` intercepts.
+    #[derive(Default)]
+    struct CollectPaths(Vec<AstPath>);
+
+    /// Applies the visitors by comparing the full path of every node with the
+    /// path of every visitor.
+    struct LinearVisitors<'a>(&'a [(&'a AstPath, &'a dyn VisitorFactory)]);
+
+    macro_rules! collect_method {
+        ($name:ident, $T:ty) => {
+            fn $name(&mut self, n: &mut $T, ast_path: &mut AstKindPath<AstParentKind>) {
+                self.0.push(ast_path.to_vec());
+                n.visit_mut_children_with_path(self, ast_path);
+            }
+        };
+    }
+
+    macro_rules! linear_method {
+        ($name:ident, $T:ty) => {
+            fn $name(&mut self, n: &mut $T, ast_path: &mut AstKindPath<AstParentKind>) {
+                n.visit_mut_children_with_path(self, ast_path);
+                for (path, visitor) in self.0.iter() {
+                    if ast_path[..] == path[..] {
+                        n.visit_mut_with(&mut visitor.create());
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! impl_intercepted {
+        ($ty:ty, $method:ident) => {
+            impl VisitMutAstPath for $ty {
+                $method!(visit_mut_prop, Prop);
+                $method!(visit_mut_expr, Expr);
+                $method!(visit_mut_pat, Pat);
+                $method!(visit_mut_stmt, Stmt);
+                $method!(visit_mut_module_decl, ModuleDecl);
+                $method!(visit_mut_module_item, ModuleItem);
+                $method!(visit_mut_call_expr, CallExpr);
+                $method!(visit_mut_lit, Lit);
+                $method!(visit_mut_str, Str);
+            }
+        };
+    }
+
+    impl_intercepted!(CollectPaths, collect_method);
+    impl_intercepted!(LinearVisitors<'_>, linear_method);
+
+    proptest! {
+        /// `ApplyVisitors` must have the same effect as comparing every node
+        /// with every visitor. Visitors are picked from the paths of existing
+        /// nodes, and may be truncated to apply to an ancestor.
+        #[test]
+        fn apply_visitors_matches_linear_scan(
+            expr in test_expr(),
+            picks in prop::collection::vec((any::<Index>(), any::<Index>()), 1..8),
+        ) {
+            run_test(false, |cm, _handler| {
+                let fm = cm.new_source_file(FileName::Anon, format!("{};", expr.to_js()));
+                let m = parse(&fm);
+
+                let mut collected = CollectPaths::default();
+                m.clone()
+                    .visit_mut_with_path(&mut collected, &mut Default::default());
+                let paths: Vec<AstPath> = picks
+                    .iter()
+                    .map(|(path, len)| {
+                        let path = path.get(&collected.0);
+                        path[..len.index(path.len()) + 1].to_vec()
+                    })
+                    .collect();
+                let markers: Vec<Marker> = (0..paths.len()).map(Marker).collect();
+                let visitors: Vec<(&AstPath, &dyn VisitorFactory)> = paths
+                    .iter()
+                    .zip(markers.iter())
+                    .map(|(path, marker)| (path, marker as _))
+                    .collect();
+
+                let mut expected = m.clone();
+                expected.visit_mut_with_path(
+                    &mut LinearVisitors(&visitors),
+                    &mut Default::default(),
+                );
+                let mut actual = m;
+                actual.visit_mut_with_path(
+                    &mut ApplyVisitors::new(visitors.clone()),
+                    &mut Default::default(),
+                );
+
+                assert_eq!(to_js(&actual, &cm), to_js(&expected, &cm));
+                Ok(())
+            })
+            .unwrap();
+        }
+    }
 }
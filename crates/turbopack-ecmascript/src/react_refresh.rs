@@ -0,0 +1,43 @@
+//! Runtime support for React Refresh, for embedders that don't bring their own
+//! React Refresh runtime.
+
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
+use turbo_tasks_fs::{embed_file, rope::RopeBuilder, File, FileContent, FileSystemPathVc};
+use turbopack_core::{asset::AssetVc, virtual_asset::VirtualAssetVc};
+
+use crate::{utils::stringify_str, ReactRefreshOptionsVc};
+
+/// A module that sets up React Refresh with the globals configured in
+/// `options`, and hooks it into the hot module replacement of the runtime. It
+/// needs to be evaluated before any module that is transformed with React
+/// Refresh, e.g. as the first runtime entry.
+///
+/// `react-refresh/runtime` is resolved from `project_path`.
+#[turbo_tasks::function]
+pub async fn react_refresh_runtime_entry(
+    project_path: FileSystemPathVc,
+    options: ReactRefreshOptionsVc,
+) -> Result<AssetVc> {
+    let options = options.await?;
+    let setup = embed_file!("js/src/react-refresh.js").await?;
+    let FileContent::Content(setup) = &*setup else {
+        return Err(anyhow!("React Refresh setup code is not found"));
+    };
+
+    let mut code = RopeBuilder::from("import RefreshRuntime from \"react-refresh/runtime\";\n\n");
+    code += setup.content();
+    writeln!(
+        code,
+        "\nsetupReactRefresh(RefreshRuntime, {{ register: {}, signature: {} }});",
+        stringify_str(&options.register_function),
+        stringify_str(&options.signature_function),
+    )?;
+
+    Ok(VirtualAssetVc::new(
+        project_path.join("__turbopack__/react-refresh.js"),
+        File::from(code.build()).into(),
+    )
+    .into())
+}
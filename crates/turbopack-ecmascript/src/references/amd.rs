@@ -224,7 +224,7 @@ fn transform_amd_factory(
             });
             *callee = Callee::Expr(quote_expr!(
                 "($f1, r = typeof $f2 !== \"function\" ? $f3 : $call_f) => r !== undefined && \
-                 __turbopack_export_value(r)",
+                 __turbopack_export_value__(r)",
                 f1 = f.clone(),
                 f2 = f.clone(),
                 f3 = f,
@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::BoolVc, ValueToString};
+use turbopack_core::asset::Asset;
+
+use super::{usage::referenced_placeable, EsmAssetReferenceVc};
+use crate::EcmascriptModuleAssetVc;
+
+#[turbo_tasks::value(transparent)]
+struct ImportedModules(Vec<EcmascriptModuleAssetVc>);
+
+/// The modules which are imported statically by a module. Only static imports
+/// wait for the imported module, `require()` and `import()` don't make the
+/// importer async.
+#[turbo_tasks::function]
+async fn imported_modules(module: EcmascriptModuleAssetVc) -> Result<ImportedModulesVc> {
+    let mut modules = Vec::new();
+    for reference in module.analyze().await?.references.await?.iter() {
+        let Some(reference) = EsmAssetReferenceVc::resolve_from(reference).await? else {
+            continue;
+        };
+        let Some(placeable) = referenced_placeable(reference).await? else {
+            continue;
+        };
+        if let Some(imported) = EcmascriptModuleAssetVc::resolve_from(placeable).await? {
+            modules.push(imported);
+        }
+    }
+    Ok(ImportedModulesVc::cell(modules))
+}
+
+/// Checks whether a module is an async module, i. e. it uses top-level await
+/// or (transitively) imports a module that does. Async modules are evaluated
+/// asynchronously and their importers need to wait for them.
+///
+/// The result of each imported module is computed by its own task, so it's
+/// shared by all of its importers. To keep import cycles from leading to
+/// cyclic task dependencies, a module only depends on the results of modules
+/// whose path sorts before its own. The imports of the other modules are
+/// walked by the task itself.
+#[turbo_tasks::function]
+pub async fn is_async_module(module: EcmascriptModuleAssetVc) -> Result<BoolVc> {
+    let path = module.path().to_string().await?;
+    let mut queue = vec![module];
+    let mut visited = HashSet::new();
+    while let Some(current) = queue.pop() {
+        if !visited.insert(current) {
+            continue;
+        }
+        if current.analyze().await?.has_top_level_await {
+            return Ok(BoolVc::cell(true));
+        }
+        for &imported in imported_modules(current).await?.iter() {
+            if visited.contains(&imported) {
+                continue;
+            }
+            if *imported.path().to_string().await? < *path {
+                if *is_async_module(imported).await? {
+                    return Ok(BoolVc::cell(true));
+                }
+                visited.insert(imported);
+            } else {
+                queue.push(imported);
+            }
+        }
+    }
+    Ok(BoolVc::cell(false))
+}
@@ -5,15 +5,21 @@ use swc_core::{
     ecma::ast::{Expr, ExprStmt, Ident, Lit, Module, ModuleItem, Program, Script, Stmt},
     quote,
 };
-use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    Value, ValueToString, ValueToStringVc,
+};
 use turbopack_core::{
     asset::Asset,
     chunk::{
-        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
-        ChunkingTypeOptionVc, ModuleId,
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContext, ChunkingContextVc,
+        ChunkingType, ChunkingTypeOptionVc, CommonJsInterop, ModuleId,
     },
     reference::{AssetReference, AssetReferenceVc},
-    resolve::{origin::ResolveOriginVc, parse::RequestVc, PrimaryResolveResult, ResolveResultVc},
+    resolve::{
+        origin::ResolveOriginVc, parse::RequestVc, PrimaryResolveResult, ResolveResult,
+        ResolveResultVc,
+    },
 };
 
 use crate::{
@@ -21,8 +27,16 @@ use crate::{
     chunk::{EcmascriptChunkItem, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor, magic_identifier,
-    references::util::{request_to_string, throw_module_not_found_expr},
+    references::{
+        esm::{
+            async_module::is_async_module,
+            reexports::{follow_reexports, FollowedExportVc},
+            usage::{is_marked_side_effect_free, is_unused_side_effect_free_import},
+        },
+        util::{request_to_string, throw_module_not_found_expr},
+    },
     resolve::esm_resolve,
+    EcmascriptModuleAssetVc,
 };
 
 #[turbo_tasks::value]
@@ -95,6 +109,14 @@ pub struct EsmAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub annotations: ImportAnnotations,
+    /// The export that is imported through this reference. When set, the
+    /// reference points to the module that defines the export, skipping
+    /// re-exports of side effect free modules like barrel files.
+    pub export_name: Option<String>,
+    /// The module is only imported with named imports, which use separate
+    /// references with an `export_name`. The module itself only needs to be
+    /// imported when it has side effects.
+    pub only_named_imports: bool,
 }
 
 impl EsmAssetReference {
@@ -111,23 +133,84 @@ impl EsmAssetReference {
 impl EsmAssetReferenceVc {
     #[turbo_tasks::function]
     pub(super) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
+        if let Some(followed) = self.followed_export().await? {
+            return Ok(ReferencedAsset::Some(followed.await?.module).cell());
+        }
+        Ok(self.get_requested_asset())
+    }
+
+    /// The asset that the request resolves to, before following re-exports.
+    #[turbo_tasks::function]
+    async fn get_requested_asset(self) -> Result<ReferencedAssetVc> {
         let this = self.await?;
         Ok(ReferencedAssetVc::from_resolve_result(
-            esm_resolve(this.get_origin(), this.request),
+            esm_resolve(
+                this.get_origin(),
+                this.request,
+                Value::new(this.annotations.reference_sub_type()),
+            ),
             this.request,
         ))
     }
 
+    /// A reference is redundant when another reference of the same import
+    /// already covers it: the module reference of named imports when the
+    /// re-exports of the module are followed, and the references of the
+    /// single exports when they aren't.
+    #[turbo_tasks::function]
+    async fn is_redundant(self) -> Result<BoolVc> {
+        let this = self.await?;
+        if this.export_name.is_none() && !this.only_named_imports {
+            return Ok(BoolVc::cell(false));
+        }
+        let follows_reexports = match &*self.get_requested_asset().await? {
+            ReferencedAsset::Some(asset) => *is_marked_side_effect_free(asset.path()).await?,
+            _ => false,
+        };
+        Ok(BoolVc::cell(follows_reexports == this.only_named_imports))
+    }
+
     #[turbo_tasks::function]
     pub fn new(
         origin: ResolveOriginVc,
         request: RequestVc,
         annotations: Value<ImportAnnotations>,
+        only_named_imports: bool,
     ) -> Self {
         Self::cell(EsmAssetReference {
             origin,
             request,
             annotations: annotations.into_value(),
+            export_name: None,
+            only_named_imports,
+        })
+    }
+
+    /// A reference for a single named import of this reference.
+    #[turbo_tasks::function]
+    pub async fn with_export_name(self, export_name: String) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(EsmAssetReference {
+            origin: this.origin,
+            request: this.request,
+            annotations: this.annotations.clone(),
+            export_name: Some(export_name),
+            only_named_imports: false,
+        }))
+    }
+}
+
+impl EsmAssetReferenceVc {
+    /// Returns the module and name of the imported export for references with
+    /// an `export_name`.
+    pub(crate) async fn followed_export(self) -> Result<Option<FollowedExportVc>> {
+        let this = self.await?;
+        let Some(export_name) = &this.export_name else {
+            return Ok(None);
+        };
+        Ok(match &*self.get_requested_asset().await? {
+            ReferencedAsset::Some(asset) => Some(follow_reexports(*asset, export_name.clone())),
+            _ => None,
         })
     }
 }
@@ -135,8 +218,16 @@ impl EsmAssetReferenceVc {
 #[turbo_tasks::value_impl]
 impl AssetReference for EsmAssetReference {
     #[turbo_tasks::function]
-    fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.get_origin(), self.request)
+    async fn resolve_reference(self_vc: EsmAssetReferenceVc) -> Result<ResolveResultVc> {
+        if let Some(followed) = self_vc.followed_export().await? {
+            return Ok(ResolveResult::asset(followed.await?.module.into()).into());
+        }
+        let this = self_vc.await?;
+        Ok(esm_resolve(
+            this.get_origin(),
+            this.request,
+            Value::new(this.annotations.reference_sub_type()),
+        ))
     }
 }
 
@@ -144,26 +235,47 @@ impl AssetReference for EsmAssetReference {
 impl ValueToString for EsmAssetReference {
     #[turbo_tasks::function]
     async fn to_string(&self) -> Result<StringVc> {
-        Ok(StringVc::cell(format!(
-            "import {} {}",
-            self.request.to_string().await?,
-            self.annotations
-        )))
+        Ok(StringVc::cell(
+            if let Some(export_name) = &self.export_name {
+                format!(
+                    "import {{ {} }} from {} {}",
+                    export_name,
+                    self.request.to_string().await?,
+                    self.annotations
+                )
+            } else {
+                format!(
+                    "import {} {}",
+                    self.request.to_string().await?,
+                    self.annotations
+                )
+            },
+        ))
     }
 }
 
 #[turbo_tasks::value_impl]
 impl ChunkableAssetReference for EsmAssetReference {
     #[turbo_tasks::function]
-    fn chunking_type(&self, _context: ChunkingContextVc) -> Result<ChunkingTypeOptionVc> {
+    async fn chunking_type(
+        self_vc: EsmAssetReferenceVc,
+        context: ChunkingContextVc,
+    ) -> Result<ChunkingTypeOptionVc> {
+        let this = self_vc.await?;
         Ok(ChunkingTypeOptionVc::cell(
-            if let Some(chunking_type) = self.annotations.chunking_type() {
+            if let Some(chunking_type) = this.annotations.chunking_type() {
                 match chunking_type {
                     "separate" => Some(ChunkingType::Separate),
                     "parallel" => Some(ChunkingType::Parallel),
                     "none" => None,
                     _ => return Err(anyhow!("unknown chunking_type: {}", chunking_type)),
                 }
+            } else if *self_vc.is_redundant().await? {
+                None
+            } else if *is_unused_side_effect_free_import(self_vc, context).await? {
+                // When tree shaking, side effect free modules don't need to be included
+                // when none of their exports are used.
+                None
             } else {
                 Some(ChunkingType::default())
             },
@@ -180,6 +292,10 @@ impl CodeGenerateable for EsmAssetReference {
     ) -> Result<CodeGenerationVc> {
         let mut visitors = Vec::new();
 
+        if *self_vc.is_redundant().await? {
+            return Ok(CodeGeneration { visitors }.into());
+        }
+
         let chunking_type = self_vc.chunking_type(context).await?;
         let resolved = self_vc.resolve_reference().await?;
 
@@ -207,16 +323,43 @@ impl CodeGenerateable for EsmAssetReference {
                 match &*referenced_asset {
                     ReferencedAsset::Some(asset) => {
                         let id = asset.as_chunk_item(context).id().await?;
+                        let node_interop =
+                            matches!(*context.commonjs_interop().await?, CommonJsInterop::Node);
+                        // Async modules need to finish their evaluation before the
+                        // importer can continue.
+                        let is_async = if let Some(module) =
+                            EcmascriptModuleAssetVc::resolve_from(asset).await?
+                        {
+                            *is_async_module(module).await?
+                        } else {
+                            false
+                        };
                         visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
-                            let stmt = quote!(
-                                "var $name = __turbopack_import__($id);" as Stmt,
-                                name = Ident::new(ident.clone().into(), DUMMY_SP),
-                                id: Expr = Expr::Lit(match &*id {
-                                    ModuleId::String(s) => s.clone().into(),
-                                    ModuleId::Number(n) => (*n as f64).into(),
-                                })
-                            );
+                            let id = Expr::Lit(match &*id {
+                                ModuleId::String(s) => s.clone().into(),
+                                ModuleId::Number(n) => (*n as f64).into(),
+                            });
+                            let name = Ident::new(ident.clone().into(), DUMMY_SP);
+                            let stmt = if node_interop {
+                                quote!(
+                                    "var $name = __turbopack_import__($id, false, true);" as Stmt,
+                                    name = name,
+                                    id: Expr = id.clone()
+                                )
+                            } else {
+                                quote!(
+                                    "var $name = __turbopack_import__($id);" as Stmt,
+                                    name = name,
+                                    id: Expr = id.clone()
+                                )
+                            };
                             insert_hoisted_stmt(program, stmt);
+                            if is_async {
+                                insert_hoisted_stmt(program, quote!(
+                                    "await __turbopack_wait__($id);" as Stmt,
+                                    id: Expr = id
+                                ));
+                            }
                         }));
                     }
                     ReferencedAsset::OriginalReferenceTypeExternal(request) => {
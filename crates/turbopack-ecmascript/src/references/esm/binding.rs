@@ -3,15 +3,16 @@ use swc_core::{
     common::DUMMY_SP,
     ecma::{
         ast::{
-            ComputedPropName, Expr, Ident, KeyValueProp, Lit, MemberExpr, MemberProp, Prop,
-            PropName, Str,
+            ComputedPropName, Expr, Ident, KeyValueProp, Lit, MemberExpr, MemberProp, Program,
+            Prop, PropName, Stmt, Str,
         },
         visit::fields::{ExprField, PropField},
     },
+    quote,
 };
 use turbopack_core::chunk::ChunkingContextVc;
 
-use super::EsmAssetReferenceVc;
+use super::{base::insert_hoisted_stmt, EsmAssetReferenceVc};
 use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
@@ -54,27 +55,9 @@ impl CodeGenerateable for EsmBinding {
         let mut visitors = Vec::new();
         let imported_module = this.reference.get_referenced_asset();
 
-        fn make_expr(imported_module: &str, export: Option<&str>) -> Expr {
-            if let Some(export) = export {
-                Expr::Member(MemberExpr {
-                    span: DUMMY_SP,
-                    obj: box Expr::Ident(Ident::new(imported_module.into(), DUMMY_SP)),
-                    prop: MemberProp::Computed(ComputedPropName {
-                        span: DUMMY_SP,
-                        expr: box Expr::Lit(Lit::Str(Str {
-                            span: DUMMY_SP,
-                            value: export.into(),
-                            raw: None,
-                        })),
-                    }),
-                })
-            } else {
-                Expr::Ident(Ident::new(imported_module.into(), DUMMY_SP))
-            }
-        }
-
         let mut ast_path = this.ast_path.await?.clone_value();
         let imported_module = imported_module.await?.get_ident().await?;
+        let export = followed_export_name(this.reference, &this.export).await?;
 
         loop {
             match ast_path.last() {
@@ -83,7 +66,7 @@ impl CodeGenerateable for EsmBinding {
                     visitors.push(
                         create_visitor!(exact ast_path, visit_mut_expr(expr: &mut Expr) {
                             if let Some(ident) = imported_module.as_deref() {
-                              *expr = make_expr(ident, this.export.as_deref());
+                              *expr = make_expr(ident, export.as_deref());
                             }
                             // If there's no identifier for the imported module,
                             // resolution failed and will insert code that throws
@@ -99,7 +82,7 @@ impl CodeGenerateable for EsmBinding {
                             if let Prop::Shorthand(ident) = prop {
                                 // TODO: Merge with the above condition when https://rust-lang.github.io/rfcs/2497-if-let-chains.html lands.
                                 if let Some(imported_ident) = imported_module.as_deref() {
-                                    *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box make_expr(imported_ident, this.export.as_deref())});
+                                    *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box make_expr(imported_ident, export.as_deref())});
                                 }
                             }
                         }),
@@ -116,3 +99,78 @@ impl CodeGenerateable for EsmBinding {
         Ok(CodeGeneration { visitors }.into())
     }
 }
+
+fn make_expr(imported_module: &str, export: Option<&str>) -> Expr {
+    if let Some(export) = export {
+        Expr::Member(MemberExpr {
+            span: DUMMY_SP,
+            obj: box Expr::Ident(Ident::new(imported_module.into(), DUMMY_SP)),
+            prop: MemberProp::Computed(ComputedPropName {
+                span: DUMMY_SP,
+                expr: box Expr::Lit(Lit::Str(Str {
+                    span: DUMMY_SP,
+                    value: export.into(),
+                    raw: None,
+                })),
+            }),
+        })
+    } else {
+        Expr::Ident(Ident::new(imported_module.into(), DUMMY_SP))
+    }
+}
+
+/// The export might have a different name in the module that defines it.
+async fn followed_export_name(
+    reference: EsmAssetReferenceVc,
+    export: &Option<String>,
+) -> Result<Option<String>> {
+    Ok(if let Some(followed) = reference.followed_export().await? {
+        Some(followed.await?.export_name.clone())
+    } else {
+        export.clone()
+    })
+}
+
+/// Declares the imported bindings of a module under their original names, so
+/// that code evaluated by a direct `eval` can access them. The values are read
+/// when the module is evaluated, so they are not live bindings.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct EsmEvalBindings {
+    /// The local name, reference and imported export of each binding.
+    pub bindings: Vec<(String, EsmAssetReferenceVc, Option<String>)>,
+}
+
+impl EsmEvalBindingsVc {
+    pub fn new(bindings: Vec<(String, EsmAssetReferenceVc, Option<String>)>) -> Self {
+        EsmEvalBindings { bindings }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for EsmEvalBindings {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let mut stmts = Vec::new();
+        for (name, reference, export) in self.bindings.iter() {
+            let imported_module = reference.get_referenced_asset().await?;
+            let Some(imported_module) = imported_module.get_ident().await? else {
+                continue;
+            };
+            let export = followed_export_name(*reference, export).await?;
+            stmts.push(quote!(
+                "var $name = $value;" as Stmt,
+                name = Ident::new(name.as_str().into(), DUMMY_SP),
+                value: Expr = make_expr(&imported_module, export.as_deref())
+            ));
+        }
+
+        let visitors = vec![create_visitor!(visit_mut_program(program: &mut Program) {
+            for stmt in stmts.iter() {
+                insert_hoisted_stmt(program, stmt.clone());
+            }
+        })];
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
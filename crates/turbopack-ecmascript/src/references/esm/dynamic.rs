@@ -1,42 +1,171 @@
 use anyhow::Result;
 use swc_core::{
+    base::SwcComments,
+    common::{comments::CommentKind, BytePos, Span},
     ecma::ast::{Callee, ExprOrSpread},
     quote_expr,
 };
 use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
     chunk::{
-        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
-        ChunkingTypeOptionVc,
+        AsyncLoadingHint, AsyncLoadingHintVc, ChunkableAssetReference, ChunkableAssetReferenceVc,
+        ChunkingContext, ChunkingContextVc, ChunkingType, ChunkingTypeOptionVc,
     },
     reference::{AssetReference, AssetReferenceVc},
+    reference_type::EcmaScriptModulesReferenceSubType,
     resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
 };
 
-use super::super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::EsmAsync};
+use super::super::pattern_mapping::{
+    PatternMapping, PatternMappingVc,
+    ResolveType::{Cjs, EsmAsync},
+};
 use crate::{
+    analyzer::{JsValue, ObjectPart},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
     references::AstPathVc,
     resolve::esm_resolve,
 };
 
+/// How the module of an `import()` expression is loaded, given by a
+/// `webpackMode` magic comment.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum DynamicImportMode {
+    /// The module is placed in a separate chunk group that is loaded on
+    /// demand.
+    #[default]
+    Lazy,
+    /// The module is placed in the referencing chunk group, no additional
+    /// chunks are loaded. The expression still returns a promise.
+    Eager,
+}
+
+/// Options of an `import()` expression, which are given by magic comments
+/// like `import(/* webpackChunkName: "chart", webpackPrefetch: true */
+/// "./chart")` and by import attributes like `import("./data.json", { with:
+/// { type: "json" } })`.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Hash, PartialOrd, Ord)]
+pub struct DynamicImportOptions {
+    pub hint: AsyncLoadingHint,
+    pub mode: DynamicImportMode,
+    pub chunk_name: Option<String>,
+    /// `webpackIgnore: true` opts out of bundling, the `import()` is kept as
+    /// is and evaluated by the runtime.
+    pub ignore: bool,
+    pub reference_sub_type: EcmaScriptModulesReferenceSubType,
+}
+
+/// The magic comments of all `import()` expressions in a module.
+#[derive(Default)]
+pub(crate) struct DynamicImportHints {
+    comments: Vec<(BytePos, String)>,
+}
+
+impl DynamicImportHints {
+    pub fn from_comments(comments: &SwcComments) -> Self {
+        let mut result = Vec::new();
+        for entry in comments.leading.iter() {
+            for comment in entry.value().iter() {
+                if let CommentKind::Block = comment.kind {
+                    if comment.text.contains("webpack") || comment.text.contains("turbopack") {
+                        result.push((*entry.key(), comment.text.to_string()));
+                    }
+                }
+            }
+        }
+        result.sort_by_key(|(pos, _)| *pos);
+        Self { comments: result }
+    }
+
+    /// Returns the options of the `import()` call at `span`. Magic comments
+    /// are attached to the arguments, so they are located inside of the call.
+    pub fn get(&self, span: Span) -> DynamicImportOptions {
+        let start = self.comments.partition_point(|(pos, _)| *pos <= span.lo);
+        let mut options = DynamicImportOptions::default();
+        for (_, text) in self.comments[start..]
+            .iter()
+            .take_while(|(pos, _)| *pos < span.hi)
+        {
+            parse_magic_comment(text, &mut options);
+        }
+        options
+    }
+}
+
+fn parse_magic_comment(text: &str, options: &mut DynamicImportOptions) {
+    for part in text.split(',') {
+        let Some((key, value)) = part.split_once(':') else {
+            continue;
+        };
+        let value = value
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'' || c == '`');
+        match key.trim() {
+            "webpackPrefetch" | "turbopackPrefetch" if value == "true" => {
+                options.hint = options.hint.max(AsyncLoadingHint::Prefetch);
+            }
+            "webpackPreload" | "turbopackPreload" if value == "true" => {
+                options.hint = options.hint.max(AsyncLoadingHint::Preload);
+            }
+            "webpackChunkName" | "turbopackChunkName" if !value.is_empty() => {
+                options.chunk_name = Some(value.to_string());
+            }
+            "webpackMode" | "turbopackMode" => match value {
+                "eager" => options.mode = DynamicImportMode::Eager,
+                "lazy" | "lazy-once" => options.mode = DynamicImportMode::Lazy,
+                _ => {}
+            },
+            "webpackIgnore" | "turbopackIgnore" => options.ignore = value == "true",
+            _ => {}
+        }
+    }
+}
+
+/// Returns the `type` import attribute of the options argument of
+/// `import("./data.json", { with: { type: "json" } })`.
+pub(crate) fn import_attributes_type(options: &JsValue) -> Option<&str> {
+    let JsValue::Object(_, parts) = options else {
+        return None;
+    };
+    parts.iter().find_map(|part| match part {
+        ObjectPart::KeyValue(key, JsValue::Object(_, attributes))
+            if matches!(key.as_str(), Some("with" | "assert")) =>
+        {
+            attributes.iter().find_map(|attribute| match attribute {
+                ObjectPart::KeyValue(key, value) if key.as_str() == Some("type") => value.as_str(),
+                _ => None,
+            })
+        }
+        _ => None,
+    })
+}
+
 #[turbo_tasks::value]
 #[derive(Hash, Debug)]
 pub struct EsmAsyncAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub path: AstPathVc,
+    pub options: DynamicImportOptions,
 }
 
 #[turbo_tasks::value_impl]
 impl EsmAsyncAssetReferenceVc {
     #[turbo_tasks::function]
-    pub fn new(origin: ResolveOriginVc, request: RequestVc, path: AstPathVc) -> Self {
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        path: AstPathVc,
+        options: Value<DynamicImportOptions>,
+    ) -> Self {
         Self::cell(EsmAsyncAssetReference {
             origin,
             request,
             path,
+            options: options.into_value(),
         })
     }
 }
@@ -45,7 +174,11 @@ impl EsmAsyncAssetReferenceVc {
 impl AssetReference for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.origin, self.request)
+        esm_resolve(
+            self.origin,
+            self.request,
+            Value::new(self.options.reference_sub_type.clone()),
+        )
     }
 }
 
@@ -53,18 +186,30 @@ impl AssetReference for EsmAsyncAssetReference {
 impl ValueToString for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     async fn to_string(&self) -> Result<StringVc> {
-        Ok(StringVc::cell(format!(
-            "dynamic import {}",
-            self.request.to_string().await?,
-        )))
+        let request = self.request.to_string().await?;
+        Ok(StringVc::cell(match &self.options.chunk_name {
+            Some(chunk_name) => format!("dynamic import {request} (chunk {chunk_name})"),
+            None => format!("dynamic import {request}"),
+        }))
     }
 }
 
 #[turbo_tasks::value_impl]
 impl ChunkableAssetReference for EsmAsyncAssetReference {
     #[turbo_tasks::function]
-    fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
-        ChunkingTypeOptionVc::cell(Some(ChunkingType::SeparateAsync))
+    async fn chunking_type(&self, context: ChunkingContextVc) -> Result<ChunkingTypeOptionVc> {
+        // A single file can't load other chunks, so the import is eager.
+        let eager = self.options.mode == DynamicImportMode::Eager || *context.single_file().await?;
+        Ok(ChunkingTypeOptionVc::cell(Some(if eager {
+            ChunkingType::PlacedOrParallel
+        } else {
+            ChunkingType::SeparateAsync
+        })))
+    }
+
+    #[turbo_tasks::function]
+    fn async_loading_hint(&self) -> AsyncLoadingHintVc {
+        self.options.hint.cell()
     }
 }
 
@@ -72,12 +217,17 @@ impl ChunkableAssetReference for EsmAsyncAssetReference {
 impl CodeGenerateable for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let eager = self.options.mode == DynamicImportMode::Eager || *context.single_file().await?;
         let pm = PatternMappingVc::resolve_request(
             self.request,
             self.origin,
             context,
-            esm_resolve(self.origin, self.request),
-            Value::new(EsmAsync),
+            esm_resolve(
+                self.origin,
+                self.request,
+                Value::new(self.options.reference_sub_type.clone()),
+            ),
+            Value::new(if eager { Cjs } else { EsmAsync }),
         )
         .await?;
 
@@ -117,7 +267,30 @@ impl CodeGenerateable for EsmAsyncAssetReference {
                     Some(ExprOrSpread { expr, spread: None }) => pm.apply(*expr),
                     _ => pm.create(),
                 };
-                if pm.is_internal_import() {
+                if pm.is_internal_import() && eager {
+                    // The module is already available, but the result still
+                    // needs to be a promise.
+                    call_expr.callee = Callee::Expr(quote_expr!("Promise.resolve().then"));
+                    call_expr.args = vec![
+                        ExprOrSpread {
+                            spread: None,
+                            expr: quote_expr!("() => __turbopack_import__($arg)", arg: Expr = expr),
+                        },
+                    ];
+                } else if let PatternMapping::Map(_) = &*pm {
+                    // The module id is looked up at runtime, a request which
+                    // doesn't match any of the modules rejects the promise.
+                    call_expr.callee = Callee::Expr(quote_expr!("Promise.resolve().then"));
+                    call_expr.args = vec![
+                        ExprOrSpread {
+                            spread: None,
+                            expr: quote_expr!(
+                                "() => __turbopack_require__($arg)(__turbopack_import__)",
+                                arg: Expr = expr
+                            ),
+                        },
+                    ];
+                } else if pm.is_internal_import() {
                     call_expr.callee = Callee::Expr(quote_expr!(
                             "__turbopack_require__($arg)",
                             arg: Expr = expr
@@ -140,3 +313,102 @@ impl CodeGenerateable for EsmAsyncAssetReference {
         .into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::common::{
+        comments::{Comment, Comments},
+        DUMMY_SP,
+    };
+
+    use super::*;
+
+    fn parse(text: &str) -> DynamicImportOptions {
+        let mut options = DynamicImportOptions::default();
+        parse_magic_comment(text, &mut options);
+        options
+    }
+
+    #[test]
+    fn parses_magic_comments() {
+        let options = parse(r#" webpackChunkName: "chart", webpackPrefetch: true "#);
+        assert_eq!(options.chunk_name.as_deref(), Some("chart"));
+        assert_eq!(options.hint, AsyncLoadingHint::Prefetch);
+        assert_eq!(options.mode, DynamicImportMode::Lazy);
+
+        let options = parse(" turbopackPreload: true, turbopackMode: 'eager' ");
+        assert_eq!(options.hint, AsyncLoadingHint::Preload);
+        assert_eq!(options.mode, DynamicImportMode::Eager);
+
+        assert!(parse(" webpackIgnore: true ").ignore);
+        assert_eq!(
+            parse(" webpackPrefetch: false ").hint,
+            AsyncLoadingHint::None
+        );
+        assert_eq!(parse(" webpackChunkName: '' ").chunk_name, None);
+    }
+
+    #[test]
+    fn parses_chunk_name_mode_and_ignore() {
+        assert_eq!(
+            parse(" webpackChunkName: `vendors` ").chunk_name.as_deref(),
+            Some("vendors")
+        );
+        assert_eq!(
+            parse(" webpackMode: \"lazy-once\" ").mode,
+            DynamicImportMode::Lazy
+        );
+        // Unsupported modes like `weak` keep the default.
+        assert_eq!(parse(" webpackMode: 'weak' ").mode, DynamicImportMode::Lazy);
+
+        let mut options = parse(" webpackIgnore: true ");
+        parse_magic_comment(" turbopackIgnore: false ", &mut options);
+        assert!(!options.ignore);
+    }
+
+    #[test]
+    fn keeps_strongest_hint() {
+        let mut options = parse(" webpackPreload: true ");
+        parse_magic_comment(" webpackPrefetch: true ", &mut options);
+        assert_eq!(options.hint, AsyncLoadingHint::Preload);
+    }
+
+    #[test]
+    fn reads_type_import_attribute() {
+        let options = |key: &str| {
+            JsValue::object(vec![ObjectPart::KeyValue(
+                key.into(),
+                JsValue::object(vec![ObjectPart::KeyValue("type".into(), "json".into())]),
+            )])
+        };
+        assert_eq!(import_attributes_type(&options("with")), Some("json"));
+        assert_eq!(import_attributes_type(&options("assert")), Some("json"));
+        assert_eq!(import_attributes_type(&options("other")), None);
+        assert_eq!(import_attributes_type(&"json".into()), None);
+    }
+
+    #[test]
+    fn gets_comments_inside_of_the_call() {
+        let comments = SwcComments::default();
+        let comment = |text: &str| Comment {
+            kind: CommentKind::Block,
+            span: DUMMY_SP,
+            text: text.into(),
+        };
+        comments.add_leading(BytePos(15), comment(" webpackChunkName: \"a\" "));
+        comments.add_leading(BytePos(45), comment(" webpackPrefetch: true "));
+        comments.add_leading(BytePos(50), comment(" unrelated "));
+        let hints = DynamicImportHints::from_comments(&comments);
+
+        let span = |lo, hi| Span::new(BytePos(lo), BytePos(hi), Default::default());
+        let first = hints.get(span(10, 30));
+        assert_eq!(first.chunk_name.as_deref(), Some("a"));
+        assert_eq!(first.hint, AsyncLoadingHint::None);
+
+        let second = hints.get(span(40, 60));
+        assert_eq!(second.chunk_name, None);
+        assert_eq!(second.hint, AsyncLoadingHint::Prefetch);
+
+        assert_eq!(hints.get(span(60, 70)), DynamicImportOptions::default());
+    }
+}
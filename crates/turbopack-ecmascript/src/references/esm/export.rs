@@ -20,7 +20,11 @@ use turbopack_core::{
     issue::{analyze::AnalyzeIssue, IssueSeverity},
 };
 
-use super::{base::ReferencedAsset, EsmAssetReferenceVc};
+use super::{
+    base::ReferencedAsset,
+    usage::{remove_unused_declarations, ExportUsageVc},
+    EsmAssetReferenceVc,
+};
 use crate::{
     chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
@@ -37,13 +41,16 @@ pub enum EsmExport {
 }
 
 #[turbo_tasks::value]
-struct ExpandResults {
-    star_exports: Vec<String>,
-    has_cjs_exports: bool,
+pub(crate) struct ExpandResults {
+    pub star_exports: Vec<String>,
+    pub has_cjs_exports: bool,
 }
 
+/// Collects the names exported by the `export *` of a module, transitively.
 #[turbo_tasks::function]
-async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<ExpandResultsVc> {
+pub(crate) async fn expand_star_exports(
+    root_asset: EcmascriptChunkPlaceableVc,
+) -> Result<ExpandResultsVc> {
     let mut set = HashSet::new();
     let mut has_cjs_exports = false;
     let mut checked_assets = HashSet::new();
@@ -135,11 +142,24 @@ pub struct EsmExports {
 #[turbo_tasks::value_impl]
 impl CodeGenerateable for EsmExports {
     #[turbo_tasks::function]
-    async fn code_generation(
-        self_vc: EsmExportsVc,
+    fn code_generation(self_vc: EsmExportsVc, context: ChunkingContextVc) -> CodeGenerationVc {
+        self_vc.code_generation_with_usage(context, ExportUsageVc::all())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EsmExportsVc {
+    /// Generates the exports object, leaving out exports that are not used.
+    /// Declarations that are only needed by unused exports are removed too.
+    #[turbo_tasks::function]
+    pub async fn code_generation_with_usage(
+        self,
         _context: ChunkingContextVc,
+        usage: ExportUsageVc,
     ) -> Result<CodeGenerationVc> {
-        let this = self_vc.await?;
+        let this = self.await?;
+        let usage = usage.await?;
+        let mut unused_locals = HashSet::new();
         let mut visitors = Vec::new();
 
         let mut all_exports: BTreeMap<Cow<str>, Cow<EsmExport>> = this
@@ -174,6 +194,12 @@ impl CodeGenerateable for EsmExports {
             }
         }
         for (exported, local) in all_exports.into_iter() {
+            if !usage.is_used(&exported) {
+                if let EsmExport::LocalBinding(name) = local.as_ref() {
+                    unused_locals.insert(name.clone());
+                }
+                continue;
+            }
             let expr = match local.as_ref() {
                 EsmExport::Error => Some(quote!(
                     "(() => { throw new Error(\"Failed binding. See build errors!\"); })" as Expr,
@@ -251,6 +277,9 @@ impl CodeGenerateable for EsmExports {
             if let Some(cjs_stmt) = cjs_stmt.clone() {
                 insert_hoisted_stmt(program, cjs_stmt);
             }
+            if !unused_locals.is_empty() {
+                remove_unused_declarations(program, &unused_locals);
+            }
         }));
 
         Ok(CodeGeneration { visitors }.into())
@@ -1,18 +1,28 @@
 use std::borrow::Cow;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use swc_core::{
     common::DUMMY_SP,
-    ecma::ast::{Expr, Ident},
-    quote,
+    ecma::ast::{Callee, Expr, Ident},
+    quote, quote_expr,
 };
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
-use turbopack_core::chunk::ChunkingContextVc;
+use turbopack_core::{
+    asset::Asset,
+    chunk::ChunkingContextVc,
+    environment::Rendering,
+    reference::{AssetReference, AssetReferenceVc},
+    reference_type::EcmaScriptModulesReferenceSubType,
+    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+};
 
 use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor, magic_identifier,
     references::{as_abs_path, esm::base::insert_hoisted_stmt, AstPathVc},
+    resolve::esm_resolve,
+    utils::object_lit,
 };
 
 /// Responsible for initializing the `import.meta` object binding, so that it
@@ -37,22 +47,14 @@ impl ImportMetaBindingVc {
 #[turbo_tasks::value_impl]
 impl CodeGenerateable for ImportMetaBinding {
     #[turbo_tasks::function]
-    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
-        let path = as_abs_path(self.path).await?.as_str().map_or_else(
-            || {
-                quote!(
-                    "(() => { throw new Error('could not convert import.meta.url to filepath') })()"
-                        as Expr
-                )
-            },
-            |path| format!("file://{}", encode_path(path)).into(),
-        );
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let url = module_url(self.path, context).await?;
 
         let visitor = create_visitor!(visit_mut_program(program: &mut Program) {
             let meta = quote!(
-                "const $name = { url: $path };" as Stmt,
+                "const $name = { url: $url };" as Stmt,
                 name = meta_ident(),
-                path: Expr = path.clone(),
+                url: Expr = url.clone(),
             );
             insert_hoisted_stmt(program, meta);
         });
@@ -64,6 +66,44 @@ impl CodeGenerateable for ImportMetaBinding {
     }
 }
 
+/// Returns the URL of a module as seen from `import.meta`. In the browser
+/// modules are only served as part of chunks, so this is the URL of the chunk
+/// the module would be the entry of. Everywhere else it's the `file://` URL of
+/// the file.
+async fn module_url(path: FileSystemPathVc, context: ChunkingContextVc) -> Result<Expr> {
+    Ok(match *context.environment().rendering().await? {
+        Rendering::Client => {
+            let chunk_path = context.chunk_path(path, ".js");
+            let output_root = context.output_root().await?;
+            let Some(chunk_server_path) = output_root.get_path_to(&*chunk_path.await?) else {
+                bail!(
+                    "chunk path {} is not in output root {}",
+                    chunk_path.to_string().await?,
+                    context.output_root().to_string().await?
+                );
+            };
+            quote!(
+                "location.origin + $path" as Expr,
+                path: Expr = format!(
+                    "{}/{}",
+                    context.base_path().await?,
+                    encode_path(chunk_server_path)
+                )
+                .into(),
+            )
+        }
+        Rendering::None | Rendering::Server(_) => as_abs_path(path).await?.as_str().map_or_else(
+            || {
+                quote!(
+                    "(() => { throw new Error('could not convert import.meta.url to filepath') })()"
+                        as Expr
+                )
+            },
+            |path| format!("file://{}", encode_path(path)).into(),
+        ),
+    })
+}
+
 /// Handles rewriting `import.meta` references into the injected binding created
 /// by ImportMetaBindi ImportMetaBinding.
 ///
@@ -99,6 +139,133 @@ impl CodeGenerateable for ImportMetaRef {
     }
 }
 
+/// Replaces `import.meta.resolve(...)` calls with the URL of the resolved
+/// module. When the request can't be resolved statically, it's resolved
+/// relative to `import.meta.url` at runtime.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ImportMetaResolveAssetReference {
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    ast_path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMetaResolveAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(origin: ResolveOriginVc, request: RequestVc, ast_path: AstPathVc) -> Self {
+        ImportMetaResolveAssetReference {
+            origin,
+            request,
+            ast_path,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for ImportMetaResolveAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        esm_resolve(
+            self.origin,
+            self.request,
+            Value::new(EcmaScriptModulesReferenceSubType::Undefined),
+        )
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ImportMetaResolveAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "import.meta.resolve {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ImportMetaResolveAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(
+        self_vc: ImportMetaResolveAssetReferenceVc,
+        context: ChunkingContextVc,
+    ) -> Result<CodeGenerationVc> {
+        let this = self_vc.await?;
+        let url = if this.request.await?.request().is_some() {
+            match &*self_vc.resolve_reference().first_asset().await? {
+                Some(asset) => Some(module_url(asset.path(), context).await?),
+                None => None,
+            }
+        } else {
+            None
+        };
+
+        let ast_path = &this.ast_path.await?;
+        let visitor = create_visitor!(ast_path, visit_mut_expr(expr: &mut Expr) {
+            if let Expr::Call(call_expr) = expr {
+                if let Some(url) = &url {
+                    *expr = url.clone();
+                } else {
+                    // The arguments are kept, as they might be rewritten by other code
+                    // generations.
+                    call_expr.callee = Callee::Expr(quote_expr!(
+                        "(specifier) => new URL(specifier, $meta.url).href",
+                        meta = meta_ident(),
+                    ));
+                }
+            }
+        });
+
+        Ok(CodeGeneration {
+            visitors: vec![visitor],
+        }
+        .into())
+    }
+}
+
+/// Replaces `import.meta.env` with the variables of the chunking context.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct ImportMetaEnvRef {
+    ast_path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMetaEnvRefVc {
+    #[turbo_tasks::function]
+    pub fn new(ast_path: AstPathVc) -> Self {
+        ImportMetaEnvRef { ast_path }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ImportMetaEnvRef {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let env = object_lit(
+            context
+                .import_meta_env()
+                .await?
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone().into()))
+                .collect(),
+        );
+
+        let ast_path = &self.ast_path.await?;
+        let visitor = create_visitor!(ast_path, visit_mut_expr(expr: &mut Expr) {
+            *expr = env.clone();
+        });
+
+        Ok(CodeGeneration {
+            visitors: vec![visitor],
+        }
+        .into())
+    }
+}
+
 /// URL encodes special chars that would appear in the "pathname" portion.
 /// https://github.com/nodejs/node/blob/3bed5f11e039153eff5cbfd9513b8f55fd53fc43/lib/internal/url.js#L1513-L1526
 fn encode_path(path: &'_ str) -> Cow<'_, str> {
@@ -1,3 +1,4 @@
+pub(crate) mod async_module;
 pub(crate) mod base;
 pub(crate) mod binding;
 pub(crate) mod dynamic;
@@ -5,14 +6,28 @@ pub(crate) mod export;
 pub(crate) mod meta;
 pub(crate) mod module_id;
 pub(crate) mod module_item;
+pub(crate) mod reexports;
 pub(crate) mod url;
+pub(crate) mod usage;
 
 pub use self::{
+    async_module::is_async_module,
     base::{EsmAssetReference, EsmAssetReferenceVc},
-    binding::{EsmBinding, EsmBindingVc},
-    dynamic::{EsmAsyncAssetReference, EsmAsyncAssetReferenceVc},
+    binding::{EsmBinding, EsmBindingVc, EsmEvalBindings, EsmEvalBindingsVc},
+    dynamic::{
+        DynamicImportMode, DynamicImportOptions, EsmAsyncAssetReference, EsmAsyncAssetReferenceVc,
+    },
     export::{EsmExports, EsmExportsVc},
-    meta::{ImportMetaBinding, ImportMetaBindingVc, ImportMetaRef, ImportMetaRefVc},
+    meta::{
+        ImportMetaBinding, ImportMetaBindingVc, ImportMetaEnvRef, ImportMetaEnvRefVc,
+        ImportMetaRef, ImportMetaRefVc, ImportMetaResolveAssetReference,
+        ImportMetaResolveAssetReferenceVc,
+    },
     module_item::{EsmModuleItem, EsmModuleItemVc},
+    reexports::{follow_reexports, FollowedExport, FollowedExportVc},
     url::{UrlAssetReference, UrlAssetReferenceVc},
+    usage::{
+        is_marked_side_effect_free, module_export_usage, EsmImportUsages, EsmImportUsagesVc,
+        ExportUsage, ExportUsageGraph, ExportUsageGraphVc, ExportUsageVc,
+    },
 };
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use turbopack_core::asset::Asset;
+
+use super::{
+    export::{EsmExport, EsmExports},
+    usage::{is_marked_side_effect_free, referenced_placeable},
+};
+use crate::chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptExports};
+
+/// The module that an export is imported from after following re-exports,
+/// and the name of the export in that module.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct FollowedExport {
+    pub module: EcmascriptChunkPlaceableVc,
+    pub export_name: String,
+}
+
+/// Follows the re-exports of an export to the module that defines it, e. g.
+/// through the `index.js` barrel file of a package. Only re-exports of side
+/// effect free modules are followed, as modules with side effects need to be
+/// evaluated anyway. When the export can't be followed statically, the
+/// original module is returned.
+#[turbo_tasks::function]
+pub async fn follow_reexports(
+    module: EcmascriptChunkPlaceableVc,
+    export_name: String,
+) -> Result<FollowedExportVc> {
+    let unchanged = || -> Result<FollowedExportVc> {
+        Ok(FollowedExport {
+            module,
+            export_name: export_name.clone(),
+        }
+        .cell())
+    };
+
+    // Candidates that might export the name. There is more than one when a
+    // module contains multiple `export * from "..."` statements.
+    let mut candidates = vec![(module, export_name.clone())];
+    let mut visited = HashSet::new();
+    while let Some((current, name)) = candidates.pop() {
+        if !visited.insert((current, name.clone())) {
+            continue;
+        }
+        let EcmascriptExports::EsmExports(exports) = &*current.get_exports().await? else {
+            // CommonJS and other dynamic exports can't be followed.
+            return unchanged();
+        };
+        let exports = exports.await?;
+        let side_effect_free = *is_marked_side_effect_free(current.path()).await?;
+        match exports.exports.get(&name) {
+            Some(EsmExport::ImportedBinding(reference, imported)) if side_effect_free => {
+                let Some(target) = referenced_placeable(*reference).await? else {
+                    return Ok(FollowedExport {
+                        module: current,
+                        export_name: name,
+                    }
+                    .cell());
+                };
+                // The module that exports the name has been found, other
+                // candidates don't need to be checked anymore.
+                candidates.clear();
+                candidates.push((target, imported.clone()));
+            }
+            Some(_) => {
+                return Ok(FollowedExport {
+                    module: current,
+                    export_name: name,
+                }
+                .cell());
+            }
+            None => {
+                if name == "default" || exports.star_exports.is_empty() {
+                    // Not exported by this candidate.
+                    continue;
+                }
+                if !side_effect_free {
+                    return unchanged();
+                }
+                for reference in exports.star_exports.iter().rev() {
+                    let Some(target) = referenced_placeable(*reference).await? else {
+                        return unchanged();
+                    };
+                    candidates.push((target, name.clone()));
+                }
+            }
+        }
+    }
+
+    unchanged()
+}
@@ -0,0 +1,532 @@
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::Result;
+use indexmap::{map::Entry, IndexMap};
+use swc_core::ecma::{
+    ast::{Decl, Expr, Ident, ModuleItem, Pat, Program, Stmt, VarDeclarator},
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc, StringsVc},
+    ValueToString,
+};
+use turbo_tasks_fs::{glob::Glob, FileJsonContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetsVc},
+    chunk::{ChunkingContext, ChunkingContextVc},
+    reference::all_assets,
+    resolve::{find_context_file, FindContextFileResult},
+};
+
+use super::{
+    base::ReferencedAsset,
+    export::{EsmExport, EsmExports},
+    EsmAssetReferenceVc,
+};
+use crate::{
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
+    EcmascriptModuleAssetVc,
+};
+
+/// The exports of a module that are used by other modules.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default)]
+pub enum ExportUsage {
+    /// No exports are used, the module is only imported for its side effects.
+    #[default]
+    None,
+    /// Only the listed exports are used.
+    Names(BTreeSet<String>),
+    /// The exports are accessed in a way that can't be statically analyzed,
+    /// e. g. via a namespace object, so all of them are used.
+    All,
+}
+
+impl ExportUsage {
+    /// Marks an export as used. `None` marks all exports as used. Returns
+    /// true when the usage has changed.
+    pub fn add(&mut self, export: Option<&str>) -> bool {
+        match (&mut *self, export) {
+            (ExportUsage::All, _) => false,
+            (_, None) => {
+                *self = ExportUsage::All;
+                true
+            }
+            (ExportUsage::None, Some(export)) => {
+                *self = ExportUsage::Names(BTreeSet::from([export.to_string()]));
+                true
+            }
+            (ExportUsage::Names(names), Some(export)) => names.insert(export.to_string()),
+        }
+    }
+
+    pub fn is_used(&self, export: &str) -> bool {
+        match self {
+            ExportUsage::None => false,
+            ExportUsage::Names(names) => names.contains(export),
+            ExportUsage::All => true,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, ExportUsage::None)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ExportUsageVc {
+    #[turbo_tasks::function]
+    pub fn all() -> Self {
+        ExportUsage::All.cell()
+    }
+}
+
+/// For every ESM import of a module, the exports of the imported module that
+/// are accessed.
+#[turbo_tasks::value(transparent)]
+pub struct EsmImportUsages(IndexMap<EsmAssetReferenceVc, ExportUsage>);
+
+#[turbo_tasks::value_impl]
+impl EsmImportUsagesVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        EsmImportUsagesVc::cell(IndexMap::new())
+    }
+}
+
+/// The export usage of all modules reachable from the tree shaking roots of a
+/// chunking context.
+#[turbo_tasks::value]
+pub struct ExportUsageGraph {
+    usages: IndexMap<EcmascriptChunkPlaceableVc, ExportUsage>,
+    /// Why an export is retained, keyed by export name. `*` is used when all
+    /// exports are retained.
+    reasons: IndexMap<EcmascriptChunkPlaceableVc, IndexMap<String, String>>,
+}
+
+struct ExportUsageGraphBuilder {
+    usages: IndexMap<EcmascriptChunkPlaceableVc, ExportUsage>,
+    reasons: IndexMap<EcmascriptChunkPlaceableVc, IndexMap<String, String>>,
+    queue: Vec<(EcmascriptChunkPlaceableVc, Option<String>)>,
+}
+
+impl ExportUsageGraphBuilder {
+    fn touch(&mut self, module: EcmascriptChunkPlaceableVc) {
+        self.usages.entry(module).or_default();
+    }
+
+    fn add(&mut self, module: EcmascriptChunkPlaceableVc, export: Option<&str>, reason: &str) {
+        if self.usages.entry(module).or_default().add(export) {
+            if let Entry::Vacant(entry) = self
+                .reasons
+                .entry(module)
+                .or_default()
+                .entry(export.unwrap_or("*").to_string())
+            {
+                entry.insert(reason.to_string());
+            }
+            self.queue.push((module, export.map(|s| s.to_string())));
+        }
+    }
+
+    fn add_usage(&mut self, module: EcmascriptChunkPlaceableVc, usage: &ExportUsage, reason: &str) {
+        match usage {
+            ExportUsage::None => self.touch(module),
+            ExportUsage::Names(names) => {
+                for name in names {
+                    self.add(module, Some(name), reason);
+                }
+            }
+            ExportUsage::All => self.add(module, None, reason),
+        }
+    }
+
+    /// Follows re-exports of newly used exports until a fixpoint is reached.
+    async fn propagate(&mut self) -> Result<()> {
+        while let Some((module, export)) = self.queue.pop() {
+            let EcmascriptExports::EsmExports(exports) = &*module.get_exports().await? else {
+                continue;
+            };
+            let EsmExports {
+                exports,
+                star_exports,
+            } = &*exports.await?;
+            let path = module.path().to_string().await?;
+            let reason = format!("re-exported by {}", path);
+
+            let targets = match &export {
+                Some(export) => exports.get(export).into_iter().collect::<Vec<_>>(),
+                None => exports.values().collect(),
+            };
+            for target in targets {
+                match target {
+                    EsmExport::ImportedBinding(reference, name) => {
+                        if let Some(asset) = referenced_placeable(*reference).await? {
+                            self.add(asset, Some(name), &reason);
+                        }
+                    }
+                    EsmExport::ImportedNamespace(reference) => {
+                        if let Some(asset) = referenced_placeable(*reference).await? {
+                            self.add(asset, None, &reason);
+                        }
+                    }
+                    EsmExport::LocalBinding(_) | EsmExport::Error => {}
+                }
+            }
+
+            let star_export = match &export {
+                Some(export) if !exports.contains_key(export) => Some(Some(export.as_str())),
+                None => Some(None),
+                Some(_) => None,
+            };
+            if let Some(export) = star_export {
+                for reference in star_exports.iter() {
+                    if let Some(asset) = referenced_placeable(*reference).await? {
+                        self.add(asset, export, &reason);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) async fn referenced_placeable(
+    reference: EsmAssetReferenceVc,
+) -> Result<Option<EcmascriptChunkPlaceableVc>> {
+    Ok(
+        if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+            Some(*asset)
+        } else {
+            None
+        },
+    )
+}
+
+#[turbo_tasks::value_impl]
+impl ExportUsageGraphVc {
+    /// Computes which exports are used by walking the module graph from the
+    /// given roots. Roots are considered to use all of their exports.
+    #[turbo_tasks::function]
+    pub async fn new(roots: AssetsVc) -> Result<Self> {
+        let mut builder = ExportUsageGraphBuilder {
+            usages: IndexMap::new(),
+            reasons: IndexMap::new(),
+            queue: Vec::new(),
+        };
+        let mut visited = HashSet::new();
+
+        for root in roots.await?.iter() {
+            if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(root).await? {
+                builder.add(placeable, None, "entry module");
+            }
+
+            for asset in all_assets(*root).await?.iter() {
+                if !visited.insert(*asset) {
+                    continue;
+                }
+                let path = asset.path().to_string().await?;
+                let import_usages =
+                    if let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+                        Some(module.analyze().await?.import_usages.await?)
+                    } else {
+                        None
+                    };
+
+                for reference in asset.references().await?.iter() {
+                    let esm_usage = if let (Some(import_usages), Some(esm_reference)) = (
+                        &import_usages,
+                        EsmAssetReferenceVc::resolve_from(reference).await?,
+                    ) {
+                        import_usages
+                            .get(&esm_reference)
+                            .map(|usage| (esm_reference, usage))
+                    } else {
+                        None
+                    };
+                    if let Some((esm_reference, usage)) = esm_usage {
+                        let reason = format!("imported by {}", path);
+                        if let Some(followed) = esm_reference.followed_export().await? {
+                            // A single named import, the export might have a different name in
+                            // the module that defines it.
+                            let followed = followed.await?;
+                            builder.add(followed.module, Some(&followed.export_name), &reason);
+                            continue;
+                        }
+                        for target in reference.resolve_reference().primary_assets().await?.iter() {
+                            if let Some(placeable) =
+                                EcmascriptChunkPlaceableVc::resolve_from(target).await?
+                            {
+                                builder.add_usage(placeable, usage, &reason);
+                            }
+                        }
+                    } else {
+                        let reason =
+                            format!("referenced by {} in {}", reference.to_string().await?, path);
+                        for target in reference.resolve_reference().primary_assets().await?.iter() {
+                            if let Some(placeable) =
+                                EcmascriptChunkPlaceableVc::resolve_from(target).await?
+                            {
+                                builder.add(placeable, None, &reason);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        builder.propagate().await?;
+
+        Ok(ExportUsageGraph {
+            usages: builder.usages,
+            reasons: builder.reasons,
+        }
+        .cell())
+    }
+
+    /// Returns the used exports of a module. Modules that are not part of the
+    /// graph are considered to use all exports.
+    #[turbo_tasks::function]
+    pub async fn usage(self, module: EcmascriptChunkPlaceableVc) -> Result<ExportUsageVc> {
+        let this = self.await?;
+        Ok(this
+            .usages
+            .get(&module)
+            .cloned()
+            .unwrap_or(ExportUsage::All)
+            .cell())
+    }
+
+    /// Explains why an export of a module is retained.
+    #[turbo_tasks::function]
+    pub async fn explain(
+        self,
+        module: EcmascriptChunkPlaceableVc,
+        export: &str,
+    ) -> Result<StringVc> {
+        let this = self.await?;
+        let path = module.path().to_string().await?;
+        let Some(usage) = this.usages.get(&module) else {
+            return Ok(StringVc::cell(format!(
+                "{} is not part of the tree shaken module graph, all exports are retained",
+                path
+            )));
+        };
+        let reasons = this.reasons.get(&module);
+        let explanation = if !usage.is_used(export) {
+            format!(
+                "export {} of {} is not used and will be removed",
+                export, path
+            )
+        } else if let Some(reason) = reasons.and_then(|r| r.get(export)) {
+            format!(
+                "export {} of {} is retained, it is {}",
+                export, path, reason
+            )
+        } else if let Some(reason) = reasons.and_then(|r| r.get("*")) {
+            format!(
+                "export {} of {} is retained, all exports are used as it is {}",
+                export, path, reason
+            )
+        } else {
+            format!("export {} of {} is retained", export, path)
+        };
+        Ok(StringVc::cell(explanation))
+    }
+}
+
+/// Returns the used exports of a module within the given chunking context. When
+/// tree shaking is disabled for the context, all exports are used.
+#[turbo_tasks::function]
+pub async fn module_export_usage(
+    module: EcmascriptChunkPlaceableVc,
+    context: ChunkingContextVc,
+) -> Result<ExportUsageVc> {
+    let roots = context.tree_shaking_roots();
+    if roots.await?.is_empty() {
+        return Ok(ExportUsageVc::all());
+    }
+    Ok(ExportUsageGraphVc::new(roots).usage(module))
+}
+
+/// Checks if the `sideEffects` field of the closest package.json marks the
+/// file as side effect free.
+#[turbo_tasks::function]
+pub async fn is_marked_side_effect_free(path: FileSystemPathVc) -> Result<BoolVc> {
+    let FindContextFileResult::Found(package_json, _) =
+        &*find_context_file(path.parent(), package_json()).await?
+    else {
+        return Ok(BoolVc::cell(false));
+    };
+    let FileJsonContent::Content(content) = &*package_json.read_json().await? else {
+        return Ok(BoolVc::cell(false));
+    };
+    let side_effect_free = match content.get("sideEffects") {
+        Some(serde_json::Value::Bool(side_effects)) => !side_effects,
+        Some(serde_json::Value::Array(globs)) => {
+            let package_dir = package_json.parent().await?;
+            let path = path.await?;
+            match package_dir.get_path_to(&path) {
+                Some(relative) => !globs.iter().filter_map(|g| g.as_str()).any(|glob| {
+                    // Patterns without a slash match the file name anywhere, like in
+                    // webpack.
+                    let glob = glob.trim_start_matches("./");
+                    let glob = if glob.contains('/') {
+                        glob.to_string()
+                    } else {
+                        format!("**/{glob}")
+                    };
+                    Glob::parse(&glob)
+                        .map(|glob| glob.execute(relative))
+                        .unwrap_or(true)
+                }),
+                None => false,
+            }
+        }
+        _ => false,
+    };
+    Ok(BoolVc::cell(side_effect_free))
+}
+
+#[turbo_tasks::function]
+fn package_json() -> StringsVc {
+    StringsVc::cell(vec!["package.json".to_string()])
+}
+
+/// Returns true when the import of the referenced module can be skipped
+/// entirely, because none of its exports are used and it is marked as side
+/// effect free.
+#[turbo_tasks::function]
+pub async fn is_unused_side_effect_free_import(
+    reference: EsmAssetReferenceVc,
+    context: ChunkingContextVc,
+) -> Result<BoolVc> {
+    if context.tree_shaking_roots().await?.is_empty() {
+        return Ok(BoolVc::cell(false));
+    }
+    let Some(asset) = referenced_placeable(reference).await? else {
+        return Ok(BoolVc::cell(false));
+    };
+    if !*is_marked_side_effect_free(asset.path()).await? {
+        return Ok(BoolVc::cell(false));
+    }
+    Ok(BoolVc::cell(
+        module_export_usage(asset, context).await?.is_none(),
+    ))
+}
+
+/// Removes top-level declarations of unused exports from a module, together
+/// with declarations that were only referenced by them. Only declarations
+/// without side effects are removed.
+pub(crate) fn remove_unused_declarations(program: &mut Program, unused: &HashSet<String>) {
+    let initial_counts = count_references(program);
+    loop {
+        let counts = count_references(program);
+        let is_removable = |name: &str| {
+            // The declaration itself is the only remaining reference
+            counts.get(name).copied().unwrap_or(0) <= 1
+                && (unused.contains(name) || initial_counts.get(name).copied().unwrap_or(0) > 1)
+        };
+        let mut removed = false;
+        let mut retain = |stmt: &Stmt| {
+            let remove = match stmt {
+                Stmt::Decl(Decl::Fn(f)) => is_removable(&f.ident.sym),
+                Stmt::Decl(Decl::Class(c)) => {
+                    is_removable(&c.ident.sym) && c.class.decorators.is_empty()
+                }
+                Stmt::Decl(Decl::Var(v)) => v.decls.iter().all(|d| match d {
+                    VarDeclarator {
+                        name: Pat::Ident(ident),
+                        init,
+                        ..
+                    } => is_removable(&ident.id.sym) && init.as_deref().map_or(true, is_pure),
+                    _ => false,
+                }),
+                _ => false,
+            };
+            removed |= remove;
+            !remove
+        };
+        match program {
+            Program::Module(module) => module.body.retain(|item| match item {
+                ModuleItem::Stmt(stmt) => retain(stmt),
+                ModuleItem::ModuleDecl(_) => true,
+            }),
+            Program::Script(script) => script.body.retain(retain),
+        }
+        if !removed {
+            break;
+        }
+    }
+}
+
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) | Expr::Fn(_) | Expr::Arrow(_) | Expr::Ident(_) => true,
+        Expr::Array(array) => array.elems.iter().all(|e| {
+            e.as_ref()
+                .map_or(true, |e| e.spread.is_none() && is_pure(&e.expr))
+        }),
+        Expr::Paren(paren) => is_pure(&paren.expr),
+        _ => false,
+    }
+}
+
+fn count_references(program: &Program) -> IndexMap<String, usize> {
+    struct Counter(IndexMap<String, usize>);
+
+    impl Visit for Counter {
+        fn visit_ident(&mut self, ident: &Ident) {
+            *self.0.entry(ident.sym.to_string()).or_default() += 1;
+        }
+    }
+
+    let mut counter = Counter(IndexMap::new());
+    program.visit_with(&mut counter);
+    counter.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::transform_program;
+
+    fn remove(input: &str, unused: &[&str]) -> String {
+        let unused = unused.iter().map(|s| s.to_string()).collect();
+        transform_program(input, |program, _| {
+            remove_unused_declarations(program, &unused);
+        })
+    }
+
+    #[test]
+    fn export_usage() {
+        let mut usage = ExportUsage::None;
+        assert!(usage.add(Some("a")));
+        assert!(!usage.add(Some("a")));
+        assert!(usage.is_used("a"));
+        assert!(!usage.is_used("b"));
+        assert!(usage.add(None));
+        assert!(usage.is_used("b"));
+        assert!(!usage.add(Some("c")));
+    }
+
+    #[test]
+    fn removes_unused_declarations() {
+        assert_eq!(
+            remove(
+                "function helper() {}\nfunction unused() { helper(); }\nfunction used() \
+                 {}\nused();",
+                &["unused"]
+            ),
+            "function used() {}\nused();\n"
+        );
+    }
+
+    #[test]
+    fn keeps_impure_declarations() {
+        assert_eq!(
+            remove("const unused = sideEffect();", &["unused"]),
+            "const unused = sideEffect();\n"
+        );
+    }
+}
@@ -2,15 +2,17 @@ pub mod amd;
 pub mod cjs;
 pub mod constant_condition;
 pub mod esm;
+pub mod module_context;
 pub mod node;
 pub mod pattern_mapping;
 pub mod raw;
 pub mod typescript;
 pub mod unreachable;
 pub mod util;
+pub mod worker;
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{hash_map::Entry, BTreeMap, HashMap},
     future::Future,
     mem::take,
     pin::Pin,
@@ -19,6 +21,7 @@ use std::{
 
 use anyhow::Result;
 use constant_condition::{ConstantConditionValue, ConstantConditionVc};
+use indexmap::IndexMap;
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
 use regex::Regex;
@@ -34,11 +37,12 @@ use swc_core::{
         visit::{AstParentKind, AstParentNodeRef, VisitAstPath, VisitWithPath},
     },
 };
-use turbo_tasks::{TryJoinIterExt, Value};
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value};
+use turbo_tasks_fs::{glob::GlobVc, FileSystemPathVc};
 use turbopack_core::{
     asset::{Asset, AssetVc},
     environment::EnvironmentVc,
+    issue::{analyze::AnalyzeIssue, IssueSeverity},
     reference::{AssetReferenceVc, AssetReferencesVc, SourceMapReferenceVc},
     reference_type::{CommonJsReferenceSubType, ReferenceType},
     resolve::{
@@ -60,19 +64,27 @@ use self::{
     },
     cjs::CjsAssetReferenceVc,
     esm::{
-        export::EsmExport, EsmAssetReferenceVc, EsmAsyncAssetReferenceVc, EsmExports,
-        EsmModuleItemVc, ImportMetaBindingVc, ImportMetaRefVc, UrlAssetReferenceVc,
+        export::EsmExport, EsmAssetReferenceVc, EsmAsyncAssetReferenceVc, EsmEvalBindingsVc,
+        EsmExports, EsmImportUsagesVc, EsmModuleItemVc, ExportUsage, ImportMetaBindingVc,
+        ImportMetaEnvRefVc, ImportMetaRefVc, ImportMetaResolveAssetReferenceVc,
+        UrlAssetReferenceVc,
+    },
+    module_context::{
+        glob_context_map, parse_import_meta_glob, parse_require_context, require_context_map,
+        ModuleContextAssetReferenceVc, ModuleContextKind,
     },
     node::{DirAssetReferenceVc, PackageJsonReferenceVc},
     raw::SourceAssetReferenceVc,
     typescript::{
-        TsConfigReferenceVc, TsReferencePathAssetReferenceVc, TsReferenceTypeAssetReferenceVc,
+        referenced_ts_exports, TsConfigReferenceVc, TsConstEnumMember,
+        TsReferencePathAssetReferenceVc, TsReferenceTypeAssetReferenceVc,
     },
+    worker::WorkerAssetReferenceVc,
 };
 use super::{
     analyzer::{
         builtin::replace_builtin,
-        graph::{create_graph, Effect},
+        graph::{create_graph, Effect, EvalKind},
         linker::link,
         well_known::replace_well_known,
         ConstantValue, FreeVarKind, JsValue, ObjectPart, WellKnownFunctionKind,
@@ -87,13 +99,13 @@ use super::{
         parse::{webpack_runtime, WebpackRuntime, WebpackRuntimeVc},
         WebpackChunkAssetReference, WebpackEntryAssetReference, WebpackRuntimeAssetReference,
     },
-    EcmascriptModuleAssetType,
+    EcmascriptModuleAssetType, EcmascriptOptions,
 };
 use crate::{
     analyzer::{
         builtin::early_replace_builtin,
         graph::{ConditionalKind, EffectArg, EvalContext},
-        imports::Reexport,
+        imports::{import_with_type_to_sub_type, Reexport},
         ModuleValue,
     },
     chunk::{EcmascriptExports, EcmascriptExportsVc},
@@ -103,7 +115,11 @@ use crate::{
         cjs::{
             CjsRequireAssetReferenceVc, CjsRequireCacheAccess, CjsRequireResolveAssetReferenceVc,
         },
-        esm::{module_id::EsmModuleIdAssetReferenceVc, EsmBindingVc, EsmExportsVc},
+        esm::{
+            dynamic::{import_attributes_type, DynamicImportHints},
+            module_id::EsmModuleIdAssetReferenceVc,
+            EsmBindingVc, EsmExportsVc,
+        },
     },
     typescript::resolve::tsconfig,
     EcmascriptInputTransformsVc,
@@ -114,6 +130,10 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub references: AssetReferencesVc,
     pub code_generation: CodeGenerateablesVc,
     pub exports: EcmascriptExportsVc,
+    /// The exports accessed through each ESM import of the module.
+    pub import_usages: EsmImportUsagesVc,
+    /// Whether the module uses top-level await.
+    pub has_top_level_await: bool,
 }
 
 /// A temporary analysis result builder to pass around, to be turned into an
@@ -122,6 +142,8 @@ pub(crate) struct AnalyzeEcmascriptModuleResultBuilder {
     references: Vec<AssetReferenceVc>,
     code_gens: Vec<CodeGenerateableVc>,
     exports: EcmascriptExports,
+    import_usages: IndexMap<EsmAssetReferenceVc, ExportUsage>,
+    has_top_level_await: bool,
 }
 
 impl AnalyzeEcmascriptModuleResultBuilder {
@@ -130,6 +152,8 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             references: Vec::new(),
             code_gens: Vec::new(),
             exports: EcmascriptExports::None,
+            import_usages: IndexMap::new(),
+            has_top_level_await: false,
         }
     }
 
@@ -149,11 +173,22 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.code_gens.push(code_gen.into());
     }
 
+    /// Records an access to an export of an ESM import. `None` records an
+    /// access to the namespace object, which uses all exports.
+    pub fn add_import_usage(&mut self, reference: EsmAssetReferenceVc, export: Option<&str>) {
+        self.import_usages.entry(reference).or_default().add(export);
+    }
+
     /// Sets the analysis result ES export.
     pub fn set_exports(&mut self, exports: EcmascriptExports) {
         self.exports = exports;
     }
 
+    /// Marks the module as using top-level await.
+    pub fn set_has_top_level_await(&mut self, has_top_level_await: bool) {
+        self.has_top_level_await = has_top_level_await;
+    }
+
     /// Builds the final analysis result. Resolves internal Vcs for performance
     /// in using them.
     pub async fn build(mut self) -> Result<AnalyzeEcmascriptModuleResultVc> {
@@ -168,6 +203,8 @@ impl AnalyzeEcmascriptModuleResultBuilder {
                 references: AssetReferencesVc::cell(self.references),
                 code_generation: CodeGenerateablesVc::cell(self.code_gens),
                 exports: self.exports.into(),
+                import_usages: EsmImportUsagesVc::cell(self.import_usages),
+                has_top_level_await: self.has_top_level_await,
             },
         ))
     }
@@ -185,6 +222,7 @@ pub(crate) async fn analyze_ecmascript_module(
     origin: ResolveOriginVc,
     ty: Value<EcmascriptModuleAssetType>,
     transforms: EcmascriptInputTransformsVc,
+    options: Value<EcmascriptOptions>,
     environment: EnvironmentVc,
 ) -> Result<AnalyzeEcmascriptModuleResultVc> {
     let mut analysis = AnalyzeEcmascriptModuleResultBuilder::new();
@@ -197,6 +235,12 @@ pub(crate) async fn analyze_ecmascript_module(
         EcmascriptModuleAssetType::Typescript | EcmascriptModuleAssetType::Ecmascript => false,
     };
 
+    // Is this a typescript file that can import `const enum`s?
+    let is_typescript = matches!(
+        &*ty,
+        EcmascriptModuleAssetType::Typescript | EcmascriptModuleAssetType::TypescriptWithTypes
+    );
+
     let parsed = parse(source, ty, transforms);
 
     match &*find_context_file(path.parent(), package_json()).await? {
@@ -297,19 +341,26 @@ pub(crate) async fn analyze_ecmascript_module(
                 GLOBALS.set(globals, || create_graph(program, eval_context))
             });
 
-            for (src, annotations) in eval_context.imports.references() {
+            for (i, (src, annotations)) in eval_context.imports.references().enumerate() {
+                // Named imports are resolved through separate references, which follow
+                // re-exports to the module that defines the export.
+                let only_named_imports = annotations.chunking_type().is_none()
+                    && eval_context.imports.has_only_named_imports(i);
                 let r = EsmAssetReferenceVc::new(
                     origin,
                     RequestVc::parse(Value::new(src.to_string().into())),
                     Value::new(annotations.clone()),
+                    only_named_imports,
                 );
                 import_references.push(r);
             }
+            let mut binding_references = HashMap::new();
             for r in import_references.iter_mut() {
                 // Resolving these references here avoids many resolve wrapper tasks when
                 // passing that to other turbo tasks functions later.
                 *r = r.resolve().await?;
                 analysis.add_reference(*r);
+                analysis.import_usages.entry(*r).or_default();
             }
 
             let (
@@ -407,6 +458,40 @@ pub(crate) async fn analyze_ecmascript_module(
                 }
             }
 
+            if is_typescript {
+                for (name, export) in esm_exports.iter() {
+                    let EsmExport::ImportedBinding(reference, imported) = export else {
+                        continue;
+                    };
+                    if !referenced_ts_exports(*reference)
+                        .await?
+                        .types
+                        .contains(imported)
+                    {
+                        continue;
+                    }
+                    AnalyzeIssue {
+                        code: Some(
+                            errors::failed_to_analyse::ecmascript::TYPE_REEXPORT.to_string(),
+                        ),
+                        category: StringVc::cell("analyze".to_string()),
+                        message: StringVc::cell(format!(
+                            "`{imported}` is only a type, but it's exported as the value \
+                             `{name}`. Types are removed when modules are compiled file by file \
+                             (`isolatedModules`), so the export is missing at runtime.\nUse \
+                             `export type {{ ... }}` or `export {{ type {name} }}` instead."
+                        )),
+                        path: source.path(),
+                        severity: IssueSeverity::Warning.into(),
+                        source: None,
+                        title: StringVc::cell("type exported as a value".to_string()),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+            }
+
             let exports = if !esm_exports.is_empty() || !esm_star_exports.is_empty() {
                 let esm_exports: EsmExportsVc = EsmExports {
                     exports: esm_exports,
@@ -422,6 +507,7 @@ pub(crate) async fn analyze_ecmascript_module(
             };
 
             analysis.set_exports(exports);
+            analysis.set_has_top_level_await(has_top_level_await(program));
 
             fn handle_call_boxed<
                 'a,
@@ -441,6 +527,7 @@ pub(crate) async fn analyze_ecmascript_module(
                 add_effects: &'a G,
                 analysis: &'a mut AnalyzeEcmascriptModuleResultBuilder,
                 environment: EnvironmentVc,
+                import_hints: &'a DynamicImportHints,
             ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
                 Box::pin(handle_call(
                     handler,
@@ -455,6 +542,7 @@ pub(crate) async fn analyze_ecmascript_module(
                     add_effects,
                     analysis,
                     environment,
+                    import_hints,
                 ))
             }
 
@@ -475,6 +563,7 @@ pub(crate) async fn analyze_ecmascript_module(
                 add_effects: &G,
                 analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
                 environment: EnvironmentVc,
+                import_hints: &DynamicImportHints,
             ) -> Result<()> {
                 fn explain_args(args: &[JsValue]) -> (String, String) {
                     JsValue::explain_args(args, 10, 2)
@@ -516,13 +605,24 @@ pub(crate) async fn analyze_ecmascript_module(
                                 add_effects,
                                 analysis,
                                 environment,
+                                import_hints,
                             )
                             .await?;
                         }
                     }
                     JsValue::WellKnownFunction(WellKnownFunctionKind::Import) => {
+                        let mut options = import_hints.get(span);
+                        if options.ignore {
+                            // Kept as a runtime `import()`, see `webpackIgnore`.
+                            return Ok(());
+                        }
                         let args = linked_args(args).await?;
-                        if args.len() == 1 {
+                        if args.len() == 1 || args.len() == 2 {
+                            if let Some(import_options) = args.get(1) {
+                                options.reference_sub_type = import_with_type_to_sub_type(
+                                    import_attributes_type(import_options),
+                                );
+                            }
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
                                 let (args, hints) = explain_args(&args);
@@ -539,6 +639,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                 origin,
                                 RequestVc::parse(Value::new(pat)),
                                 AstPathVc::cell(ast_path.to_vec()),
+                                Value::new(options),
                             ));
                             return Ok(());
                         }
@@ -552,8 +653,18 @@ pub(crate) async fn analyze_ecmascript_module(
                         )
                     }
                     JsValue::WellKnownFunction(WellKnownFunctionKind::Require) => {
+                        let mut options = import_hints.get(span);
+                        if options.ignore {
+                            // Kept as a runtime `import()`, see `webpackIgnore`.
+                            return Ok(());
+                        }
                         let args = linked_args(args).await?;
-                        if args.len() == 1 {
+                        if args.len() == 1 || args.len() == 2 {
+                            if let Some(import_options) = args.get(1) {
+                                options.reference_sub_type = import_with_type_to_sub_type(
+                                    import_attributes_type(import_options),
+                                );
+                            }
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
                                 let (args, hints) = explain_args(&args);
@@ -593,8 +704,18 @@ pub(crate) async fn analyze_ecmascript_module(
                     }
 
                     JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve) => {
+                        let mut options = import_hints.get(span);
+                        if options.ignore {
+                            // Kept as a runtime `import()`, see `webpackIgnore`.
+                            return Ok(());
+                        }
                         let args = linked_args(args).await?;
-                        if args.len() == 1 {
+                        if args.len() == 1 || args.len() == 2 {
+                            if let Some(import_options) = args.get(1) {
+                                options.reference_sub_type = import_with_type_to_sub_type(
+                                    import_attributes_type(import_options),
+                                );
+                            }
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
                                 let (args, hints) = explain_args(&args);
@@ -626,20 +747,60 @@ pub(crate) async fn analyze_ecmascript_module(
                         )
                     }
 
+                    JsValue::WellKnownFunction(WellKnownFunctionKind::RequireContext) => {
+                        let args = linked_args(args).await?;
+                        let reason = match parse_require_context(&args) {
+                            Ok(options) => {
+                                let dir = source.path().parent().try_join(&options.dir).await?;
+                                if let Some(dir) = *dir {
+                                    analysis.add_reference(ModuleContextAssetReferenceVc::new(
+                                        origin,
+                                        require_context_map(dir, options.recursive, options.filter),
+                                        Value::new(ModuleContextKind::RequireContext {
+                                            lazy: options.lazy,
+                                        }),
+                                        options.dir,
+                                        AstPathVc::cell(ast_path.to_vec()),
+                                    ));
+                                    return Ok(());
+                                }
+                                "the directory is outside of the filesystem"
+                            }
+                            Err(reason) => reason,
+                        };
+                        let (args, hints) = explain_args(&args);
+                        handler.span_warn_with_code(
+                            span,
+                            &format!(
+                                "require.context({args}) is not statically analyse-able: \
+                                 {reason}{hints}",
+                            ),
+                            DiagnosticId::Error(
+                                errors::failed_to_analyse::ecmascript::REQUIRE_CONTEXT.to_string(),
+                            ),
+                        )
+                    }
+
                     JsValue::WellKnownFunction(WellKnownFunctionKind::FsReadMethod(name)) => {
                         let args = linked_args(args).await?;
                         if !args.is_empty() {
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
+                                // Without a constant part, the pattern would
+                                // match every file, so nothing is traced.
                                 let (args, hints) = explain_args(&args);
                                 handler.span_warn_with_code(
                                     span,
-                                    &format!("fs.{name}({args}) is very dynamic{hints}",),
+                                    &format!(
+                                        "fs.{name}({args}) is very dynamic, so the files it \
+                                         accesses can't be traced{hints}",
+                                    ),
                                     DiagnosticId::Lint(
                                         errors::failed_to_analyse::ecmascript::FS_METHOD
                                             .to_string(),
                                     ),
-                                )
+                                );
+                                return Ok(());
                             }
                             analysis.add_reference(SourceAssetReferenceVc::new(source, pat.into()));
                             return Ok(());
@@ -671,11 +832,15 @@ pub(crate) async fn analyze_ecmascript_module(
                             let (args, hints) = explain_args(&args);
                             handler.span_warn_with_code(
                                 span,
-                                &format!("path.resolve({args}) is very dynamic{hints}",),
+                                &format!(
+                                    "path.resolve({args}) is very dynamic, so the files it refers \
+                                     to can't be traced{hints}",
+                                ),
                                 DiagnosticId::Lint(
                                     errors::failed_to_analyse::ecmascript::PATH_METHOD.to_string(),
                                 ),
-                            )
+                            );
+                            return Ok(());
                         }
                         analysis.add_reference(SourceAssetReferenceVc::new(source, pat.into()));
                         return Ok(());
@@ -693,11 +858,15 @@ pub(crate) async fn analyze_ecmascript_module(
                             let (args, hints) = explain_args(&args);
                             handler.span_warn_with_code(
                                 span,
-                                &format!("path.join({args}) is very dynamic{hints}",),
+                                &format!(
+                                    "path.join({args}) is very dynamic, so the files it refers to \
+                                     can't be traced{hints}",
+                                ),
                                 DiagnosticId::Lint(
                                     errors::failed_to_analyse::ecmascript::PATH_METHOD.to_string(),
                                 ),
-                            )
+                            );
+                            return Ok(());
                         }
                         analysis.add_reference(DirAssetReferenceVc::new(source, pat.into()));
                         return Ok(());
@@ -1087,6 +1256,56 @@ pub(crate) async fn analyze_ecmascript_module(
                 Ok(())
             }
 
+            /// Inlines the member of an imported `const enum`. Returns true
+            /// when the object is a `const enum`.
+            async fn handle_ts_const_enum_member(
+                ast_path: &[AstParentKind],
+                span: Span,
+                reference: EsmAssetReferenceVc,
+                export: &str,
+                prop: &JsValue,
+                handler: &Handler,
+                analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
+            ) -> Result<bool> {
+                let ts_exports = referenced_ts_exports(reference).await?;
+                let Some(members) = ts_exports.const_enums.get(export) else {
+                    return Ok(false);
+                };
+                let message = match prop.as_str() {
+                    Some(prop) => match members.get(prop) {
+                        Some(value) => {
+                            analysis.add_code_gen(
+                                TsConstEnumMember {
+                                    value: value.clone(),
+                                    path: AstPathVc::cell(ast_path.to_vec()),
+                                }
+                                .cell(),
+                            );
+                            return Ok(true);
+                        }
+                        None => format!(
+                            "`{export}.{prop}` is not a constant member of the const enum \
+                             `{export}`. Const enums don't exist at runtime, so only members with \
+                             constant values can be used from other modules."
+                        ),
+                    },
+                    None => format!(
+                        "The const enum `{export}` is accessed with a dynamic key. Const enums \
+                         don't exist at runtime when modules are compiled file by file \
+                         (`isolatedModules`), so only members accessed by name can be inlined. \
+                         Use a regular enum instead."
+                    ),
+                };
+                handler.span_warn_with_code(
+                    span,
+                    &message,
+                    DiagnosticId::Lint(
+                        errors::failed_to_analyse::ecmascript::CONST_ENUM.to_string(),
+                    ),
+                );
+                Ok(true)
+            }
+
             let effects = take(&mut var_graph.effects);
 
             enum Action {
@@ -1106,10 +1325,13 @@ pub(crate) async fn analyze_ecmascript_module(
                 .get_mut()
                 .extend(effects.into_iter().map(Action::Effect).rev());
 
+            let import_hints = DynamicImportHints::from_comments(comments);
+
             let linker = |value| value_visitor(source, origin, value, environment);
             // There can be many references to import.meta, but only the first should hoist
             // the object allocation.
             let mut first_import_meta = true;
+            let mut has_direct_eval = false;
 
             while let Some(action) = queue_stack.get_mut().pop() {
                 match action {
@@ -1262,6 +1484,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                     &add_effects,
                                     &mut analysis,
                                     environment,
+                                    &import_hints,
                                 )
                                 .await?;
                             }
@@ -1321,6 +1544,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                     &add_effects,
                                     &mut analysis,
                                     environment,
+                                    &import_hints,
                                 )
                                 .await?;
                             }
@@ -1328,8 +1552,42 @@ pub(crate) async fn analyze_ecmascript_module(
                                 obj,
                                 prop,
                                 ast_path,
-                                span: _,
+                                span,
                             } => {
+                                if is_typescript {
+                                    if let JsValue::Member(
+                                        _,
+                                        box JsValue::Module(ModuleValue {
+                                            module,
+                                            annotations,
+                                        }),
+                                        box export,
+                                    ) = &obj
+                                    {
+                                        let reference = eval_context
+                                            .imports
+                                            .references()
+                                            .position(|(m, a)| m == module && a == annotations)
+                                            .and_then(|i| import_references.get(i));
+                                        if let (Some(reference), Some(export)) =
+                                            (reference, export.as_str())
+                                        {
+                                            if handle_ts_const_enum_member(
+                                                &ast_path,
+                                                span,
+                                                *reference,
+                                                export,
+                                                &prop,
+                                                &handler,
+                                                &mut analysis,
+                                            )
+                                            .await?
+                                            {
+                                                continue;
+                                            }
+                                        }
+                                    }
+                                }
                                 let obj = link_value(obj).await?;
                                 let prop = link_value(prop).await?;
 
@@ -1348,8 +1606,16 @@ pub(crate) async fn analyze_ecmascript_module(
                                             AstPathVc::cell(ast_path),
                                         ))
                                     } else {
-                                        analysis.add_code_gen(EsmBindingVc::new(
+                                        let r = binding_reference(
                                             *r,
+                                            esm_reference_index,
+                                            export.as_deref(),
+                                            &mut binding_references,
+                                            &mut analysis,
+                                        )
+                                        .await?;
+                                        analysis.add_code_gen(EsmBindingVc::new(
+                                            r,
                                             export,
                                             AstPathVc::cell(ast_path),
                                         ));
@@ -1365,6 +1631,83 @@ pub(crate) async fn analyze_ecmascript_module(
                                 analysis
                                     .add_code_gen(ImportMetaRefVc::new(AstPathVc::cell(ast_path)));
                             }
+                            Effect::ImportMetaResolve {
+                                input,
+                                ast_path,
+                                span: _,
+                            } => {
+                                // Requests that can't be resolved statically are resolved
+                                // relative to `import.meta.url` at runtime.
+                                if first_import_meta {
+                                    first_import_meta = false;
+                                    analysis.add_code_gen(ImportMetaBindingVc::new(source.path()));
+                                }
+                                let input = link_value(input).await?;
+                                analysis.add_reference(ImportMetaResolveAssetReferenceVc::new(
+                                    origin,
+                                    RequestVc::parse(Value::new(js_value_to_pattern(&input))),
+                                    AstPathVc::cell(ast_path),
+                                ));
+                            }
+                            Effect::ImportMetaEnv { ast_path, span: _ } => {
+                                analysis.add_code_gen(ImportMetaEnvRefVc::new(AstPathVc::cell(
+                                    ast_path,
+                                )));
+                            }
+                            Effect::ImportMetaGlob {
+                                args,
+                                ast_path,
+                                span,
+                            } => {
+                                let mut linked = Vec::with_capacity(args.len());
+                                for arg in args {
+                                    linked.push(link_value(arg).await?);
+                                }
+                                let reason = match parse_import_meta_glob(&linked) {
+                                    Ok(options) => {
+                                        let dir = source
+                                            .path()
+                                            .parent()
+                                            .try_join(&options.prefix)
+                                            .await?;
+                                        if let Some(dir) = *dir {
+                                            let request =
+                                                format!("{}{}", options.prefix, options.glob);
+                                            analysis.add_reference(
+                                                ModuleContextAssetReferenceVc::new(
+                                                    origin,
+                                                    glob_context_map(
+                                                        dir,
+                                                        options.prefix,
+                                                        GlobVc::new(&options.glob),
+                                                    ),
+                                                    Value::new(ModuleContextKind::ImportMetaGlob {
+                                                        eager: options.eager,
+                                                        import: options.import,
+                                                    }),
+                                                    request,
+                                                    AstPathVc::cell(ast_path),
+                                                ),
+                                            );
+                                            continue;
+                                        }
+                                        "the pattern points outside of the filesystem"
+                                    }
+                                    Err(reason) => reason,
+                                };
+                                let (args, hints) = JsValue::explain_args(&linked, 10, 2);
+                                handler.span_warn_with_code(
+                                    span,
+                                    &format!(
+                                        "import.meta.glob({args}) is not statically analyse-able: \
+                                         {reason}{hints}",
+                                    ),
+                                    DiagnosticId::Error(
+                                        errors::failed_to_analyse::ecmascript::IMPORT_META_GLOB
+                                            .to_string(),
+                                    ),
+                                )
+                            }
                             Effect::Url {
                                 input,
                                 ast_path,
@@ -1388,10 +1731,112 @@ pub(crate) async fn analyze_ecmascript_module(
                                     AstPathVc::cell(ast_path),
                                 ));
                             }
+                            Effect::Worker {
+                                kind,
+                                module,
+                                input,
+                                ast_path,
+                                span,
+                            } => {
+                                let pat = js_value_to_pattern(&input);
+                                if module {
+                                    // The worker runtime loads chunks with `importScripts`, which
+                                    // isn't available to ES module scripts. Reference the script
+                                    // like any other `new URL(...)` until there's a runtime for
+                                    // them.
+                                    handler.span_warn_with_code(
+                                        span,
+                                        &format!(
+                                            "{}(new URL({input}, import.meta.url)) loads an ES \
+                                             module, which can't be bundled as a worker yet",
+                                            kind.as_str()
+                                        ),
+                                        DiagnosticId::Lint(
+                                            errors::failed_to_analyse::ecmascript::NEW_WORKER
+                                                .to_string(),
+                                        ),
+                                    );
+                                    analysis.add_reference(UrlAssetReferenceVc::new(
+                                        origin,
+                                        RequestVc::parse(Value::new(pat)),
+                                        environment.rendering(),
+                                        AstPathVc::cell(ast_path),
+                                    ));
+                                    continue;
+                                }
+                                if !pat.has_constant_parts() {
+                                    handler.span_warn_with_code(
+                                        span,
+                                        &format!(
+                                            "new {}(new URL({input}, import.meta.url)) is very \
+                                             dynamic",
+                                            kind.as_str()
+                                        ),
+                                        DiagnosticId::Lint(
+                                            errors::failed_to_analyse::ecmascript::NEW_WORKER
+                                                .to_string(),
+                                        ),
+                                    )
+                                }
+                                analysis.add_reference(WorkerAssetReferenceVc::new(
+                                    origin,
+                                    RequestVc::parse(Value::new(pat)),
+                                    Value::new(kind),
+                                    environment.rendering(),
+                                    AstPathVc::cell(ast_path),
+                                ));
+                            }
+                            Effect::Eval { kind, span } => {
+                                if options.allow_eval {
+                                    has_direct_eval |= kind == EvalKind::Direct;
+                                    continue;
+                                }
+                                let message = match kind {
+                                    EvalKind::Direct => {
+                                        "eval() is used in this module. The evaluated code can't \
+                                         access the imports of the module, as they are renamed \
+                                         when bundling. Enable `allow_eval` for this module if it \
+                                         needs them."
+                                    }
+                                    EvalKind::Function => {
+                                        "new Function() is used in this module. The created \
+                                         function only has access to global variables, not to the \
+                                         imports and bindings of the module."
+                                    }
+                                };
+                                handler.span_warn_with_code(
+                                    span,
+                                    message,
+                                    DiagnosticId::Lint(
+                                        errors::failed_to_analyse::ecmascript::EVAL.to_string(),
+                                    ),
+                                )
+                            }
                         }
                     }
                 }
             }
+
+            if has_direct_eval {
+                // Code evaluated by a direct eval refers to the imports by their
+                // original names, so they are declared in the module.
+                let mut bindings = Vec::new();
+                for (id, esm_reference_index, export) in eval_context.imports.bindings() {
+                    let Some(r) = import_references.get(esm_reference_index) else {
+                        continue;
+                    };
+                    let r = binding_reference(
+                        *r,
+                        esm_reference_index,
+                        export,
+                        &mut binding_references,
+                        &mut analysis,
+                    )
+                    .await?;
+                    bindings.push((id.0.to_string(), r, export.map(|e| e.to_string())));
+                }
+                analysis.add_code_gen(EsmEvalBindingsVc::new(bindings));
+            }
         }
         ParseResult::Unparseable | ParseResult::NotFound => {}
     };
@@ -1399,6 +1844,36 @@ pub(crate) async fn analyze_ecmascript_module(
     analysis.build().await
 }
 
+/// Returns the reference that is used for an imported binding. Named imports
+/// get a reference for each imported export, so that re-exports can be
+/// followed.
+async fn binding_reference(
+    reference: EsmAssetReferenceVc,
+    esm_reference_index: usize,
+    export: Option<&str>,
+    binding_references: &mut HashMap<(usize, String), EsmAssetReferenceVc>,
+    analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
+) -> Result<EsmAssetReferenceVc> {
+    let r = match export {
+        Some(export) if reference.await?.only_named_imports => {
+            match binding_references.entry((esm_reference_index, export.to_string())) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    let binding_reference = reference
+                        .with_export_name(export.to_string())
+                        .resolve()
+                        .await?;
+                    analysis.add_reference(binding_reference);
+                    *entry.insert(binding_reference)
+                }
+            }
+        }
+        _ => reference,
+    };
+    analysis.add_import_usage(r, export);
+    Ok(r)
+}
+
 fn analyze_amd_define(
     analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
     origin: ResolveOriginVc,
@@ -1504,14 +1979,8 @@ fn analyze_amd_define_with_deps(
                 "exports" => {
                     requests.push(AmdDefineDependencyElement::Exports);
                 }
+                // `require()` calls in the factory are analyzed like CommonJS requires.
                 "require" => {
-                    handler.span_warn_with_code(
-                        span,
-                        "using \"require\" as dependency in an AMD define() is not yet supported",
-                        DiagnosticId::Error(
-                            errors::failed_to_analyse::ecmascript::AMD_DEFINE.to_string(),
-                        ),
-                    );
                     requests.push(AmdDefineDependencyElement::Require);
                 }
                 "module" => {
@@ -2175,3 +2644,53 @@ fn has_cjs_export(p: &Program) -> bool {
     p.visit_with(&mut v);
     v.found
 }
+
+/// Checks whether the module uses `await` or `for await` outside of any
+/// function, which makes it an async module.
+fn has_top_level_await(p: &Program) -> bool {
+    use swc_core::ecma::visit::{Visit, VisitWith};
+
+    let Program::Module(m) = p else {
+        // Scripts can't use top-level await
+        return false;
+    };
+
+    struct Visitor {
+        found: bool,
+    }
+
+    impl Visit for Visitor {
+        fn visit_await_expr(&mut self, _: &AwaitExpr) {
+            self.found = true;
+        }
+
+        fn visit_for_of_stmt(&mut self, n: &ForOfStmt) {
+            if n.is_await {
+                self.found = true;
+                return;
+            }
+            n.visit_children_with(self);
+        }
+
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+        fn visit_class(&mut self, n: &Class) {
+            // Computed keys and extended classes are evaluated in the
+            // module scope
+            n.super_class.visit_with(self);
+            for member in n.body.iter() {
+                match member {
+                    ClassMember::Method(method) => method.key.visit_with(self),
+                    ClassMember::ClassProp(prop) => prop.key.visit_with(self),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut v = Visitor { found: false };
+    m.visit_with(&mut v);
+    v.found
+}
@@ -0,0 +1,540 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use regex::Regex;
+use swc_core::{ecma::ast::Expr, quote};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{glob::GlobVc, DirectoryContent, DirectoryEntry, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    chunk::{
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, ChunkingType, ChunkingTypeOptionVc, FromChunkableAsset,
+    },
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
+    reference::{AssetReference, AssetReferenceVc},
+    reference_type::{CommonJsReferenceSubType, EcmaScriptModulesReferenceSubType, ReferenceType},
+    resolve::{
+        origin::{ResolveOrigin, ResolveOriginVc},
+        ResolveResult, ResolveResultVc,
+    },
+    source_asset::SourceAssetVc,
+};
+
+use crate::{
+    analyzer::{ConstantValue, JsValue, ObjectPart},
+    chunk::{EcmascriptChunkItem, EcmascriptChunkItemVc},
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::AstPathVc,
+    utils::{module_id_to_lit, object_lit},
+};
+
+/// The files matched by a `require.context()` or `import.meta.glob()` call,
+/// keyed by the request that is used to access them at runtime (e. g.
+/// `./components/button.js`).
+#[turbo_tasks::value(transparent)]
+pub struct ModuleContextMap(BTreeMap<String, FileSystemPathVc>);
+
+/// Reads the files of a `require.context()` call. Reading the directories
+/// makes sure the map is invalidated when matching files are added or removed.
+#[turbo_tasks::function]
+pub(crate) async fn require_context_map(
+    dir: FileSystemPathVc,
+    recursive: bool,
+    filter: String,
+) -> Result<ModuleContextMapVc> {
+    let filter = Regex::new(&filter)?;
+    let mut map = BTreeMap::new();
+    let mut queue = vec![(dir, "./".to_string())];
+    while let Some((dir, prefix)) = queue.pop() {
+        let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+            continue;
+        };
+        for (name, entry) in entries.iter() {
+            match entry {
+                DirectoryEntry::File(path) => {
+                    let key = format!("{prefix}{name}");
+                    if filter.is_match(&key) {
+                        map.insert(key, *path);
+                    }
+                }
+                DirectoryEntry::Directory(path) if recursive => {
+                    queue.push((*path, format!("{prefix}{name}/")));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(ModuleContextMapVc::cell(map))
+}
+
+/// Reads the files matching an `import.meta.glob()` pattern in `dir`. `prefix`
+/// is the relative part of the pattern that was stripped to get to `dir`.
+#[turbo_tasks::function]
+pub(crate) async fn glob_context_map(
+    dir: FileSystemPathVc,
+    prefix: String,
+    glob: GlobVc,
+) -> Result<ModuleContextMapVc> {
+    let mut map = BTreeMap::new();
+    for (path, entry) in dir.read_glob(glob, false).all_matches().await? {
+        if let DirectoryEntry::File(file) = entry {
+            map.insert(format!("{prefix}{path}"), file);
+        }
+    }
+    Ok(ModuleContextMapVc::cell(map))
+}
+
+/// The options of a `require.context(directory, recursive, filter, mode)`
+/// call.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct RequireContextOptions {
+    pub dir: String,
+    pub recursive: bool,
+    /// The filter regular expression, converted to the syntax of the `regex`
+    /// crate.
+    pub filter: String,
+    pub lazy: bool,
+}
+
+/// Parses the arguments of a `require.context()` call. Returns the reason why
+/// the call can't be analysed on failure.
+pub(crate) fn parse_require_context(
+    args: &[JsValue],
+) -> Result<RequireContextOptions, &'static str> {
+    let Some(dir) = args.first().and_then(|dir| dir.as_str()) else {
+        return Err("the directory must be a constant string");
+    };
+    let recursive = match args.get(1) {
+        None => true,
+        Some(recursive) => recursive
+            .is_truthy()
+            .ok_or("the recursive flag must be a constant")?,
+    };
+    let filter = match args.get(2) {
+        None => r"^\./.*$".to_string(),
+        Some(JsValue::Constant(ConstantValue::Regex(exp, flags))) => {
+            let mut filter = String::new();
+            for flag in flags.chars() {
+                match flag {
+                    'i' | 'm' | 's' => filter.push_str(&format!("(?{flag})")),
+                    // Only test once per key, so global and sticky matching
+                    // make no difference.
+                    'g' | 'y' | 'u' => {}
+                    _ => return Err("the filter uses unsupported regular expression flags"),
+                }
+            }
+            filter.push_str(exp);
+            if Regex::new(&filter).is_err() {
+                return Err("the filter uses unsupported regular expression syntax");
+            }
+            filter
+        }
+        Some(_) => return Err("the filter must be a regular expression literal"),
+    };
+    let lazy = match args.get(3).map(|mode| mode.as_str()) {
+        None | Some(Some("sync")) => false,
+        Some(Some("lazy")) => true,
+        Some(_) => return Err("only the \"sync\" and \"lazy\" modes are supported"),
+    };
+    Ok(RequireContextOptions {
+        dir: dir.to_string(),
+        recursive,
+        filter,
+        lazy,
+    })
+}
+
+/// The options of an `import.meta.glob(pattern, { eager, import })` call.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ImportMetaGlobOptions {
+    /// The leading relative segments of the pattern, e. g. `./` or `../../`.
+    pub prefix: String,
+    /// The remaining glob, which is matched relative to `prefix`.
+    pub glob: String,
+    pub eager: bool,
+    pub import: Option<String>,
+}
+
+/// Parses the arguments of an `import.meta.glob()` call. Returns the reason
+/// why the call can't be analysed on failure.
+pub(crate) fn parse_import_meta_glob(
+    args: &[JsValue],
+) -> Result<ImportMetaGlobOptions, &'static str> {
+    let Some(pattern) = args.first().and_then(|pattern| pattern.as_str()) else {
+        return Err("the pattern must be a constant string");
+    };
+    let mut glob = pattern;
+    let mut prefix_len = 0;
+    loop {
+        if let Some(rest) = glob.strip_prefix("./") {
+            glob = rest;
+            prefix_len += 2;
+        } else if let Some(rest) = glob.strip_prefix("../") {
+            glob = rest;
+            prefix_len += 3;
+        } else {
+            break;
+        }
+    }
+    if prefix_len == 0 {
+        return Err("the pattern must start with ./ or ../");
+    }
+
+    let mut eager = false;
+    let mut import = None;
+    match args.get(1) {
+        None => {}
+        Some(JsValue::Object(_, parts)) => {
+            for part in parts {
+                let ObjectPart::KeyValue(key, value) = part else {
+                    return Err("the options must not contain spread properties");
+                };
+                match key.as_str() {
+                    Some("eager") => {
+                        eager = value
+                            .is_truthy()
+                            .ok_or("the eager option must be a constant")?;
+                    }
+                    Some("import") => {
+                        import = Some(
+                            value
+                                .as_str()
+                                .ok_or("the import option must be a constant string")?
+                                .to_string(),
+                        );
+                    }
+                    _ => return Err("only the eager and import options are supported"),
+                }
+            }
+        }
+        Some(_) => return Err("the options must be an object literal"),
+    }
+    Ok(ImportMetaGlobOptions {
+        prefix: pattern[..prefix_len].to_string(),
+        glob: glob.to_string(),
+        eager,
+        import,
+    })
+}
+
+/// The kind of call that created a module context.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum ModuleContextKind {
+    /// `require.context()`, which evaluates to a function that requires a key
+    /// of the context.
+    RequireContext { lazy: bool },
+    /// `import.meta.glob()`, which evaluates to an object mapping keys to the
+    /// imported modules (when eager) or to functions importing them.
+    ImportMetaGlob { eager: bool, import: Option<String> },
+}
+
+impl ModuleContextKind {
+    fn is_lazy(&self) -> bool {
+        match self {
+            ModuleContextKind::RequireContext { lazy } => *lazy,
+            ModuleContextKind::ImportMetaGlob { eager, .. } => !eager,
+        }
+    }
+}
+
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ModuleContextAssetReference {
+    pub origin: ResolveOriginVc,
+    pub map: ModuleContextMapVc,
+    pub kind: ModuleContextKind,
+    /// The directory or pattern of the call, used for display purposes.
+    pub request: String,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleContextAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        origin: ResolveOriginVc,
+        map: ModuleContextMapVc,
+        kind: Value<ModuleContextKind>,
+        request: String,
+        path: AstPathVc,
+    ) -> Self {
+        Self::cell(ModuleContextAssetReference {
+            origin,
+            map,
+            kind: kind.into_value(),
+            request,
+            path,
+        })
+    }
+}
+
+impl ModuleContextAssetReference {
+    /// Processes the matched files into modules, keyed by their request.
+    async fn modules(&self) -> Result<Vec<(String, AssetVc)>> {
+        let reference_type = Value::new(match self.kind {
+            ModuleContextKind::RequireContext { .. } => {
+                ReferenceType::CommonJs(CommonJsReferenceSubType::Undefined)
+            }
+            ModuleContextKind::ImportMetaGlob { .. } => {
+                ReferenceType::EcmaScriptModules(EcmaScriptModulesReferenceSubType::Undefined)
+            }
+        });
+        let context = self.origin.context();
+        Ok(self
+            .map
+            .await?
+            .iter()
+            .map(|(key, path)| {
+                (
+                    key.clone(),
+                    context.process(SourceAssetVc::new(*path).into(), reference_type.clone()),
+                )
+            })
+            .collect())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for ModuleContextAssetReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        let assets = self
+            .modules()
+            .await?
+            .into_iter()
+            .map(|(_, asset)| asset)
+            .collect();
+        Ok(ResolveResult::assets(assets).cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ModuleContextAssetReference {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        let name = match self.kind {
+            ModuleContextKind::RequireContext { .. } => "require.context",
+            ModuleContextKind::ImportMetaGlob { .. } => "import.meta.glob",
+        };
+        StringVc::cell(format!("{name} {}", self.request))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for ModuleContextAssetReference {
+    #[turbo_tasks::function]
+    async fn chunking_type(&self, context: ChunkingContextVc) -> Result<ChunkingTypeOptionVc> {
+        let lazy = self.kind.is_lazy() && !*context.single_file().await?;
+        Ok(ChunkingTypeOptionVc::cell(Some(if lazy {
+            ChunkingType::SeparateAsync
+        } else {
+            ChunkingType::default()
+        })))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ModuleContextAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        // In a single file, the modules are placed in the chunk, but lazy
+        // modules are still loaded with a promise.
+        let single_file = *context.single_file().await?;
+        let mut entries = Vec::new();
+        for (key, asset) in self.modules().await? {
+            let Some(chunk_item) = EcmascriptChunkItemVc::from_asset(context, asset).await? else {
+                CodeGenerationIssue {
+                    severity: IssueSeverity::Error.into(),
+                    title: StringVc::cell("non-ecmascript placeable asset".to_string()),
+                    message: StringVc::cell(format!(
+                        "asset {} matched by {} is not placeable in ESM chunks, so it can't be \
+                         imported",
+                        asset.path().to_string().await?,
+                        self.request
+                    )),
+                    path: self.origin.origin_path(),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                continue;
+            };
+            let id = module_id_to_lit(&*chunk_item.id().await?);
+            let load = if self.kind.is_lazy() && single_file {
+                quote!(
+                    "() => Promise.resolve().then(() => __turbopack_import__($id))" as Expr,
+                    id: Expr = id.clone()
+                )
+            } else if self.kind.is_lazy() {
+                let Some(chunkable) = ChunkableAssetVc::resolve_from(asset).await? else {
+                    continue;
+                };
+                let Some((loader, _)) =
+                    EcmascriptChunkItemVc::from_async_asset(context, chunkable).await?
+                else {
+                    continue;
+                };
+                quote!(
+                    "() => __turbopack_require__($loader)(__turbopack_import__)" as Expr,
+                    loader: Expr = module_id_to_lit(&*loader.id().await?)
+                )
+            } else {
+                match self.kind {
+                    ModuleContextKind::RequireContext { .. } => quote!(
+                        "() => __turbopack_require__($id)" as Expr,
+                        id: Expr = id.clone()
+                    ),
+                    ModuleContextKind::ImportMetaGlob { .. } => {
+                        quote!("__turbopack_import__($id)" as Expr, id: Expr = id.clone())
+                    }
+                }
+            };
+            let value = match &self.kind {
+                ModuleContextKind::RequireContext { .. } => {
+                    object_lit(vec![("id".to_string(), id), ("module".to_string(), load)])
+                }
+                ModuleContextKind::ImportMetaGlob { eager, import } => match import {
+                    Some(import) if *eager => quote!(
+                        "$module[$import]" as Expr,
+                        module: Expr = load,
+                        import: Expr = import.clone().into()
+                    ),
+                    Some(import) => quote!(
+                        "() => $load().then((module) => module[$import])" as Expr,
+                        load: Expr = load,
+                        import: Expr = import.clone().into()
+                    ),
+                    None => load,
+                },
+            };
+            entries.push((key, value));
+        }
+
+        let object = object_lit(entries);
+        let replacement = match self.kind {
+            ModuleContextKind::RequireContext { .. } => quote!(
+                "((map) => {
+                    function resolve(key) {
+                        if (!Object.prototype.hasOwnProperty.call(map, key)) {
+                            const error = new Error(\"Cannot find module '\" + key + \"'\");
+                            error.code = \"MODULE_NOT_FOUND\";
+                            throw error;
+                        }
+                        return map[key].id;
+                    }
+                    function context(key) {
+                        resolve(key);
+                        return map[key].module();
+                    }
+                    context.keys = () => Object.keys(map);
+                    context.resolve = resolve;
+                    return context;
+                })($entries)" as Expr,
+                entries: Expr = object
+            ),
+            ModuleContextKind::ImportMetaGlob { .. } => object,
+        };
+
+        let path = &self.path.await?;
+        let visitor = create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+            // The reference is only created for call expressions. Due to
+            // eventual consistency the path might match something else, but
+            // that will be recomputed anyway.
+            if let Expr::Call(_) = expr {
+                *expr = replacement.clone();
+            }
+        });
+
+        Ok(CodeGeneration {
+            visitors: vec![visitor],
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(exp: &str, flags: &str) -> JsValue {
+        ConstantValue::Regex(exp.into(), flags.into()).into()
+    }
+
+    #[test]
+    fn require_context_defaults() {
+        assert_eq!(
+            parse_require_context(&["./components".into()]),
+            Ok(RequireContextOptions {
+                dir: "./components".to_string(),
+                recursive: true,
+                filter: r"^\./.*$".to_string(),
+                lazy: false,
+            })
+        );
+    }
+
+    #[test]
+    fn require_context_filter_and_mode() {
+        assert_eq!(
+            parse_require_context(&[
+                "./icons".into(),
+                ConstantValue::False.into(),
+                regex(r"\.svg$", "i"),
+                "lazy".into(),
+            ]),
+            Ok(RequireContextOptions {
+                dir: "./icons".to_string(),
+                recursive: false,
+                filter: r"(?i)\.svg$".to_string(),
+                lazy: true,
+            })
+        );
+        assert!(parse_require_context(&[
+            "./icons".into(),
+            ConstantValue::True.into(),
+            regex(r"(?<=a)b", ""),
+        ])
+        .is_err());
+        assert!(parse_require_context(&[
+            "./icons".into(),
+            ConstantValue::True.into(),
+            regex(r"\.svg$", ""),
+            "weak".into(),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn import_meta_glob_prefix() {
+        assert_eq!(
+            parse_import_meta_glob(&["../../pages/**/*.js".into()]),
+            Ok(ImportMetaGlobOptions {
+                prefix: "../../".to_string(),
+                glob: "pages/**/*.js".to_string(),
+                eager: false,
+                import: None,
+            })
+        );
+        assert!(parse_import_meta_glob(&["/src/*.js".into()]).is_err());
+    }
+
+    #[test]
+    fn import_meta_glob_options() {
+        let options = JsValue::object(vec![
+            ObjectPart::KeyValue("eager".into(), ConstantValue::True.into()),
+            ObjectPart::KeyValue("import".into(), "default".into()),
+        ]);
+        assert_eq!(
+            parse_import_meta_glob(&["./routes/*.js".into(), options]),
+            Ok(ImportMetaGlobOptions {
+                prefix: "./".to_string(),
+                glob: "routes/*.js".to_string(),
+                eager: true,
+                import: Some("default".to_string()),
+            })
+        );
+    }
+}
@@ -1,12 +1,13 @@
 use anyhow::Result;
 use indexmap::IndexMap;
 use swc_core::{
-    ecma::ast::{Expr, Lit},
-    quote,
+    common::DUMMY_SP,
+    ecma::ast::{Expr, KeyValueProp, Lit, ObjectLit, Prop, PropName, PropOrSpread},
+    quote, quote_expr,
 };
 use turbo_tasks::{debug::ValueDebug, primitives::StringVc, Value, ValueToString};
 use turbopack_core::{
-    asset::Asset,
+    asset::{Asset, AssetVc},
     chunk::{ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset, ModuleId},
     issue::{code_gen::CodeGenerationIssue, IssueSeverity},
     resolve::{
@@ -99,9 +100,27 @@ impl PatternMapping {
     pub fn apply(&self, key_expr: Expr) -> Expr {
         match self {
             PatternMapping::OriginalReferenceExternal => key_expr,
+            PatternMapping::Map(map) => {
+                let props = map
+                    .iter()
+                    .map(|(key, module_id)| {
+                        PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                            key: PropName::Str(key.as_str().into()),
+                            value: box module_id_to_lit(module_id),
+                        }))
+                    })
+                    .collect();
+                *quote_expr!(
+                    "$map[$key]",
+                    map: Expr = Expr::Object(ObjectLit {
+                        span: DUMMY_SP,
+                        props,
+                    }),
+                    key: Expr = key_expr
+                )
+            }
             _ => self.create(),
         }
-        // TODO handle PatternMapping::Map
     }
 }
 
@@ -119,6 +138,11 @@ impl PatternMappingVc {
         resolve_type: Value<ResolveType>,
     ) -> Result<PatternMappingVc> {
         let result = resolve_result.await?;
+        if result.primary.len() > 1 {
+            return Ok(resolve_map(origin, context, &result.primary, *resolve_type)
+                .await?
+                .cell());
+        }
         let asset = match result.primary.first() {
             None => {
                 return Ok(PatternMapping::Unresolveable(
@@ -155,22 +179,8 @@ impl PatternMappingVc {
             }
         };
 
-        if let Some(chunkable) = ChunkableAssetVc::resolve_from(asset).await? {
-            if *resolve_type == ResolveType::EsmAsync {
-                if let Some((loader, _)) =
-                    EcmascriptChunkItemVc::from_async_asset(context, chunkable).await?
-                {
-                    return Ok(PatternMappingVc::cell(PatternMapping::Single(
-                        loader.id().await?.clone_value(),
-                    )));
-                }
-            } else if let Some(chunk_item) =
-                EcmascriptChunkItemVc::from_asset(context, asset).await?
-            {
-                return Ok(PatternMappingVc::cell(PatternMapping::Single(
-                    chunk_item.id().await?.clone_value(),
-                )));
-            }
+        if let Some(module_id) = module_id(context, asset, *resolve_type).await? {
+            return Ok(PatternMappingVc::cell(PatternMapping::Single(module_id)));
         }
         CodeGenerationIssue {
             severity: IssueSeverity::Bug.into(),
@@ -187,3 +197,89 @@ impl PatternMappingVc {
         Ok(PatternMappingVc::cell(PatternMapping::Invalid))
     }
 }
+
+/// The id of the module which is required for `asset`. That's the loader of
+/// the chunk group of `asset` for `import()`.
+async fn module_id(
+    context: ChunkingContextVc,
+    asset: AssetVc,
+    resolve_type: ResolveType,
+) -> Result<Option<ModuleId>> {
+    if let Some(chunkable) = ChunkableAssetVc::resolve_from(asset).await? {
+        if resolve_type == ResolveType::EsmAsync {
+            if let Some((loader, _)) =
+                EcmascriptChunkItemVc::from_async_asset(context, chunkable).await?
+            {
+                return Ok(Some(loader.id().await?.clone_value()));
+            }
+        } else if let Some(chunk_item) = EcmascriptChunkItemVc::from_asset(context, asset).await? {
+            return Ok(Some(chunk_item.id().await?.clone_value()));
+        }
+    }
+    Ok(None)
+}
+
+/// Maps the requests which a variable request like `import(`./locale/${lang}`)`
+/// can evaluate to onto the module ids of the assets it resolves to. The keys
+/// are the paths of the assets relative to the requesting module, with and
+/// without extension.
+async fn resolve_map(
+    origin: ResolveOriginVc,
+    context: ChunkingContextVc,
+    primary: &[PrimaryResolveResult],
+    resolve_type: ResolveType,
+) -> Result<PatternMapping> {
+    let origin_dir = origin.origin_path().parent().await?;
+    let mut map = IndexMap::new();
+    let mut shorthands = Vec::new();
+    for result in primary {
+        let PrimaryResolveResult::Asset(asset) = result else {
+            continue;
+        };
+        let Some(mut key) = origin_dir.get_relative_path_to(&*asset.path().await?) else {
+            continue;
+        };
+        if !key.starts_with('.') {
+            key = format!("./{key}");
+        }
+        let Some(module_id) = module_id(context, *asset, resolve_type).await? else {
+            continue;
+        };
+        if let Some(shorthand) = strip_extension(&key) {
+            shorthands.push((shorthand.to_string(), module_id.clone()));
+        }
+        map.insert(key, module_id);
+    }
+    // Full paths take precedence over requests without extension.
+    for (shorthand, module_id) in shorthands {
+        map.entry(shorthand).or_insert(module_id);
+    }
+    Ok(PatternMapping::Map(map))
+}
+
+/// Strips the extension of the file name of `path`, e.g. `./locale/en.js`
+/// becomes `./locale/en`. Returns `None` when there is no extension.
+fn strip_extension(path: &str) -> Option<&str> {
+    let file_name_start = path.rfind('/').map_or(0, |i| i + 1);
+    match path[file_name_start..].rfind('.') {
+        // Dotfiles like `.env` don't have an extension.
+        Some(0) | None => None,
+        Some(i) => Some(&path[..file_name_start + i]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_extension;
+
+    #[test]
+    fn strips_extension_of_file_name() {
+        assert_eq!(strip_extension("./locale/en.js"), Some("./locale/en"));
+        assert_eq!(
+            strip_extension("./locale/en.min.js"),
+            Some("./locale/en.min")
+        );
+        assert_eq!(strip_extension("../dir.v2/en"), None);
+        assert_eq!(strip_extension("./locale/.env"), None);
+    }
+}
@@ -1,7 +1,12 @@
 use anyhow::Result;
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{Expr, Lit, Number, Str, UnaryExpr, UnaryOp},
+};
 use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
+    chunk::ChunkingContextVc,
     context::AssetContext,
     reference::{AssetReference, AssetReferenceVc},
     reference_type::{ReferenceType, TypeScriptReferenceSubType},
@@ -14,7 +19,20 @@ use turbopack_core::{
     source_asset::SourceAssetVc,
 };
 
-use crate::typescript::{resolve::type_resolve, TsConfigModuleAssetVc};
+use super::{
+    esm::{base::ReferencedAsset, EsmAssetReferenceVc},
+    AstPathVc,
+};
+use crate::{
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    typescript::{
+        exports::{ts_exports, ConstEnumMember, TsExportsVc},
+        resolve::type_resolve,
+        TsConfigModuleAssetVc,
+    },
+    EcmascriptModuleAssetVc,
+};
 
 #[turbo_tasks::value]
 #[derive(Hash, Clone, Debug)]
@@ -146,3 +164,70 @@ impl ValueToString for TsReferenceTypeAssetReference {
         )))
     }
 }
+
+/// The type-only exports of the TypeScript module that is imported by the
+/// reference.
+#[turbo_tasks::function]
+pub async fn referenced_ts_exports(reference: EsmAssetReferenceVc) -> Result<TsExportsVc> {
+    let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? else {
+        return Ok(TsExportsVc::empty());
+    };
+    Ok(
+        if let Some(module) = EcmascriptModuleAssetVc::resolve_from(*asset).await? {
+            ts_exports(module)
+        } else {
+            TsExportsVc::empty()
+        },
+    )
+}
+
+/// Inlines the value of a member of an imported `const enum`, as the enum
+/// doesn't exist at runtime.
+#[turbo_tasks::value]
+pub struct TsConstEnumMember {
+    pub value: ConstEnumMember,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for TsConstEnumMember {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let value = match &self.value {
+            ConstEnumMember::Number(value) => {
+                let value: f64 = value.parse()?;
+                let number = Expr::Lit(Lit::Num(Number {
+                    span: DUMMY_SP,
+                    value: value.abs(),
+                    raw: None,
+                }));
+                if value < 0.0 {
+                    Expr::Unary(UnaryExpr {
+                        span: DUMMY_SP,
+                        op: UnaryOp::Minus,
+                        arg: box number,
+                    })
+                } else {
+                    number
+                }
+            }
+            ConstEnumMember::String(value) => Expr::Lit(Lit::Str(Str {
+                span: DUMMY_SP,
+                value: value.as_str().into(),
+                raw: None,
+            })),
+        };
+        let visitors = [
+            create_visitor!(&self.path.await?, visit_mut_expr(expr: &mut Expr) {
+                if let Expr::Member(_) = expr {
+                    *expr = value.clone();
+                } else {
+                    unreachable!("`TsConstEnumMember` is only created from `MemberExpr`");
+                }
+            }),
+        ]
+        .into();
+
+        Ok(CodeGeneration { visitors }.cell())
+    }
+}
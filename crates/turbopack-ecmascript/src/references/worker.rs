@@ -0,0 +1,344 @@
+use anyhow::{bail, Result};
+use swc_core::{
+    ecma::ast::{Expr, ExprOrSpread, NewExpr},
+    quote,
+};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{
+        ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, ChunkingType, ChunkingTypeOptionVc, ChunksVc,
+    },
+    environment::{Rendering, RenderingVc},
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
+    reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
+    reference_type::EcmaScriptModulesReferenceSubType,
+    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+};
+
+use crate::{
+    analyzer::graph::WorkerKind,
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::{
+        esm::base::{ReferencedAsset, ReferencedAssetVc},
+        AstPathVc,
+    },
+    resolve::esm_resolve,
+    utils::{module_id_to_lit, stringify_str},
+};
+
+/// Worker Asset References are injected during code analysis when we find a
+/// `new URL("path", import.meta.url)` that is passed to `new Worker(...)` or
+/// `new SharedWorker(...)`.
+///
+/// The referenced module becomes the entry of a separate chunk group, which is
+/// chunked for a web worker environment. The `new URL(...)` expression is
+/// rewritten to point to the entry chunk of that chunk group. The chunks are
+/// loaded with `importScripts`, so module workers are not referenced this
+/// way.
+#[turbo_tasks::value]
+pub struct WorkerAssetReference {
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    kind: WorkerKind,
+    rendering: RenderingVc,
+    ast_path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl WorkerAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        kind: Value<WorkerKind>,
+        rendering: RenderingVc,
+        ast_path: AstPathVc,
+    ) -> Self {
+        WorkerAssetReference {
+            origin,
+            request,
+            kind: kind.into_value(),
+            rendering,
+            ast_path,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for WorkerAssetReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        let result = esm_resolve(
+            self.origin,
+            self.request,
+            Value::new(EcmaScriptModulesReferenceSubType::Undefined),
+        )
+        .await?;
+        Ok(result
+            .map(
+                |asset| async move {
+                    Ok(
+                        if let Some(placeable) =
+                            EcmascriptChunkPlaceableVc::resolve_from(asset).await?
+                        {
+                            WorkerLoaderAssetVc::new(placeable).into()
+                        } else {
+                            asset
+                        },
+                    )
+                },
+                |reference| async move { Ok(reference) },
+            )
+            .await?
+            .into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for WorkerAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{}(new URL({}))",
+            self.kind.as_str(),
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for WorkerAssetReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
+        ChunkingTypeOptionVc::cell(Some(ChunkingType::PlacedOrParallel))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for WorkerAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(
+        self_vc: WorkerAssetReferenceVc,
+        context: ChunkingContextVc,
+    ) -> Result<CodeGenerationVc> {
+        let this = self_vc.await?;
+        let mut visitors = vec![];
+
+        let referenced_asset =
+            ReferencedAssetVc::from_resolve_result(self_vc.resolve_reference(), this.request)
+                .await?;
+
+        // Worker scripts are served by the dev server, so the URL base needs to be
+        // rewritten the same way as for `new URL(...)`.
+        let rewrite = match &*this.rendering.await? {
+            Rendering::None => {
+                CodeGenerationIssue {
+                    severity: IssueSeverity::Error.into(),
+                    title: StringVc::cell(format!(
+                        "{}(new URL(…)) not implemented for this environment",
+                        this.kind.as_str()
+                    )),
+                    message: StringVc::cell(
+                        "Workers are only currently supported for rendering environments like \
+                         Client-Side or Server-Side Rendering."
+                            .to_string(),
+                    ),
+                    path: this.origin.origin_path(),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                None
+            }
+            Rendering::Client => Some(quote!("location.origin" as Expr)),
+            Rendering::Server(server_addr) => {
+                let location = server_addr.await?.to_string()?;
+                Some(location.into())
+            }
+        };
+
+        if let ReferencedAsset::Some(asset) = &*referenced_asset {
+            // The worker loader chunk item exports the path of the worker's entry chunk.
+            let id = asset.as_chunk_item(context).id().await?;
+            let ast_path = this.ast_path.await?;
+
+            visitors.push(
+                create_visitor!(ast_path, visit_mut_expr(new_expr: &mut Expr) {
+                    if let Expr::New(NewExpr { args: Some(args), .. }) = new_expr {
+                        if let Some(ExprOrSpread { box expr, spread: None }) = args.get_mut(0) {
+                            *expr = quote!(
+                                "__turbopack_require__($id)" as Expr,
+                                id: Expr = module_id_to_lit(&id),
+                            );
+                        }
+
+                        if let Some(rewrite) = &rewrite {
+                            if let Some(ExprOrSpread { box expr, spread: None }) = args.get_mut(1) {
+                                *expr = rewrite.clone();
+                            }
+                        }
+                    }
+                }),
+            );
+        }
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+/// An asset that exports the URL of the entry chunk of a web worker. The worker
+/// module is placed into its own chunk group, which uses a chunking context for
+/// the web worker environment.
+#[turbo_tasks::value]
+pub struct WorkerLoaderAsset {
+    asset: EcmascriptChunkPlaceableVc,
+}
+
+#[turbo_tasks::value_impl]
+impl WorkerLoaderAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(asset: EcmascriptChunkPlaceableVc) -> Self {
+        Self::cell(WorkerLoaderAsset { asset })
+    }
+
+    #[turbo_tasks::function]
+    async fn entry_chunk(self, context: ChunkingContextVc) -> Result<ChunkVc> {
+        let worker_context = context.with_environment(context.environment().web_worker());
+        Ok(EcmascriptChunkVc::new_evaluate(worker_context, self.await?.asset, None).into())
+    }
+
+    #[turbo_tasks::function]
+    fn chunks(self, context: ChunkingContextVc) -> ChunksVc {
+        ChunkGroupVc::from_chunk(self.entry_chunk(context)).chunks()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for WorkerLoaderAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.asset.path().join("worker-loader.js")
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        unimplemented!()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for WorkerLoaderAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: WorkerLoaderAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.as_ecmascript_chunk_placeable()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for WorkerLoaderAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(
+        self_vc: WorkerLoaderAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
+        WorkerLoaderChunkItem {
+            context,
+            inner: self_vc,
+        }
+        .cell()
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.cell()
+    }
+}
+
+#[turbo_tasks::value]
+struct WorkerLoaderChunkItem {
+    context: ChunkingContextVc,
+    inner: WorkerLoaderAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for WorkerLoaderChunkItem {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        self.inner.path().to_string()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for WorkerLoaderChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn related_path(&self) -> FileSystemPathVc {
+        self.inner.path()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let entry_chunk = self.inner.entry_chunk(self.context);
+        let chunk_path = &*entry_chunk.path().await?;
+        let output_root = self.context.output_root().await?;
+        let base_path = self.context.base_path().await?;
+        let chunk_server_path = if let Some(path) = output_root.get_path_to(chunk_path) {
+            path
+        } else {
+            bail!(
+                "chunk path {} is not in output root {}",
+                entry_chunk.path().to_string().await?,
+                self.context.output_root().to_string().await?
+            );
+        };
+
+        Ok(EcmascriptChunkItemContent {
+            inner_code: format!(
+                "__turbopack_export_value__({});\n",
+                stringify_str(&format!("{base_path}/{chunk_server_path}"))
+            )
+            .into(),
+            ..Default::default()
+        }
+        .cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for WorkerLoaderChunkItem {
+    #[turbo_tasks::function]
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        let chunks = self.inner.chunks(self.context);
+
+        Ok(AssetReferencesVc::cell(
+            chunks
+                .await?
+                .iter()
+                .copied()
+                .map(ChunkReferenceVc::new)
+                .map(Into::into)
+                .collect(),
+        ))
+    }
+}
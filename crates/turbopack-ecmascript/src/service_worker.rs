@@ -0,0 +1,202 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::json;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc, AssetsVc},
+    chunk::{ChunkGroupVc, ChunkVc, ChunkingContext, ChunkingContextVc},
+    reference::{all_assets, AssetReferencesVc},
+};
+
+use crate::chunk::{EcmascriptChunkPlaceableVc, EcmascriptChunkVc};
+
+/// The expression which is replaced with the precache manifest in the code of
+/// a service worker, like `self.__WB_MANIFEST` of workbox. The manifest is a
+/// list of `{ url, revision }` objects.
+pub const PRECACHE_MANIFEST_PLACEHOLDER: &str = "self.__TURBOPACK_PRECACHE_MANIFEST__";
+
+/// The script of a service worker, e.g. `/sw.js`.
+///
+/// The entry module is chunked for a service worker environment. The script
+/// contains the code of all of its chunks, so it can be served at the root of
+/// the app, which limits the scope of the service worker, without serving its
+/// chunks there. That includes the chunks of `import()`s, as service workers
+/// can't load scripts once they are installed.
+///
+/// The precache manifest lists the assets referenced by the `precached`
+/// assets, e.g. the HTML page of the app, with a hash of their content as
+/// revision. It replaces [PRECACHE_MANIFEST_PLACEHOLDER] in the code of the
+/// chunks. A changed asset changes the script, so the browser installs the new
+/// service worker.
+#[turbo_tasks::value]
+pub struct ServiceWorkerAsset {
+    path: FileSystemPathVc,
+    context: ChunkingContextVc,
+    entry: EcmascriptChunkPlaceableVc,
+    precached: AssetsVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ServiceWorkerAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        context: ChunkingContextVc,
+        entry: EcmascriptChunkPlaceableVc,
+        precached: AssetsVc,
+    ) -> Self {
+        ServiceWorkerAsset {
+            path,
+            context,
+            entry,
+            precached,
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn entry_chunk(self) -> Result<ChunkVc> {
+        let this = self.await?;
+        let context = this
+            .context
+            .with_environment(this.context.environment().service_worker());
+        Ok(EcmascriptChunkVc::new_evaluate(context, this.entry, None).into())
+    }
+
+    /// The JavaScript chunks of the service worker in the order they are
+    /// evaluated. The chunks of `import()`s come first, they are only
+    /// registered until they are imported.
+    #[turbo_tasks::function]
+    async fn chunks(self) -> Result<AssetsVc> {
+        let entry_chunk = self.entry_chunk();
+        let mut initial_chunks = Vec::new();
+        for &chunk in ChunkGroupVc::from_chunk(entry_chunk).chunks().await?.iter() {
+            let chunk: AssetVc = chunk.into();
+            initial_chunks.push(chunk.resolve().await?);
+        }
+        let mut chunks = Vec::new();
+        for &asset in all_assets(entry_chunk.into()).await?.iter() {
+            let asset = asset.resolve().await?;
+            if asset.path().await?.path.ends_with(".js") && !initial_chunks.contains(&asset) {
+                chunks.push(asset);
+            }
+        }
+        chunks.extend(initial_chunks);
+        Ok(AssetsVc::cell(chunks))
+    }
+
+    /// The precache manifest as JSON.
+    #[turbo_tasks::function]
+    async fn precache_manifest(self) -> Result<StringVc> {
+        let this = self.await?;
+        let output_root = this.context.output_root().await?;
+        let base_path = this.context.base_path().await?;
+        let mut entries = IndexMap::new();
+        for &root in this.precached.await?.iter() {
+            for &asset in all_assets(root).await?.iter() {
+                let path = asset.path().await?;
+                let Some(relative_path) = output_root.get_path_to(&path) else {
+                    continue;
+                };
+                // Source maps are only requested by devtools.
+                if relative_path.ends_with(".map") {
+                    continue;
+                }
+                let url = format!("{base_path}/{relative_path}");
+                if entries.contains_key(&url) {
+                    continue;
+                }
+                let AssetContent::File(file) = &*asset.content().await? else {
+                    continue;
+                };
+                if let FileContent::Content(file) = &*file.await? {
+                    entries.insert(url, encode_hex(hash_xxh3_hash64(file.content())));
+                }
+            }
+        }
+        let manifest = entries
+            .into_iter()
+            .map(|(url, revision)| json!({ "url": url, "revision": revision }))
+            .collect::<Vec<_>>();
+        Ok(StringVc::cell(serde_json::to_string(&manifest)?))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for ServiceWorkerAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: ServiceWorkerAssetVc) -> Result<AssetContentVc> {
+        let mut chunks = Vec::new();
+        for &chunk in self_vc.chunks().await?.iter() {
+            let AssetContent::File(file) = &*chunk.content().await? else {
+                continue;
+            };
+            if let FileContent::Content(file) = &*file.await? {
+                chunks.push(file.content().to_str()?.into_owned());
+            }
+        }
+        let manifest = self_vc.precache_manifest().await?;
+        Ok(File::from(service_worker_code(&chunks, &manifest)).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        // The code of the chunks is part of the script.
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Concatenates the code of the chunks of a service worker and inserts the
+/// precache manifest. Source map comments are removed, as the source maps
+/// don't match the concatenated code.
+fn service_worker_code(chunks: &[String], manifest: &str) -> String {
+    let mut code = String::new();
+    for chunk in chunks {
+        for line in chunk.lines() {
+            if line.starts_with("//# sourceMappingURL=") {
+                continue;
+            }
+            code.push_str(&line.replace(PRECACHE_MANIFEST_PLACEHOLDER, manifest));
+            code.push('\n');
+        }
+    }
+    code
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ServiceWorkerAsset {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "service worker {}",
+            self.path.to_string().await?
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::service_worker_code;
+
+    #[test]
+    fn inserts_precache_manifest() {
+        let chunks = vec![
+            "(self.TURBOPACK = self.TURBOPACK || []).push([\"a.js\", {}]);\n//# \
+             sourceMappingURL=a.js.map"
+                .to_string(),
+            "precacheAndRoute(self.__TURBOPACK_PRECACHE_MANIFEST__);\n".to_string(),
+        ];
+        assert_eq!(
+            service_worker_code(&chunks, r#"[{"url":"/","revision":"1"}]"#),
+            "(self.TURBOPACK = self.TURBOPACK || []).push([\"a.js\", \
+             {}]);\nprecacheAndRoute([{\"url\":\"/\",\"revision\":\"1\"}]);\n"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use swc_core::{
+    common::{FileName, Globals, Mark, SourceMap, GLOBALS},
+    ecma::{
+        ast::Program,
+        codegen::{text_writer::JsWriter, Emitter},
+        parser::{parse_file_as_program, EsConfig, Syntax},
+        transforms::base::resolver,
+        visit::VisitMutWith,
+    },
+};
+
+/// Parses `input` and resolves its identifiers. Returns the mark of unresolved
+/// identifiers. Needs to be called with [GLOBALS] set.
+fn parse(cm: &Arc<SourceMap>, input: &str, syntax: Syntax) -> (Program, Mark) {
+    let fm = cm.new_source_file(FileName::Anon, input.to_string());
+    let mut program =
+        parse_file_as_program(&fm, syntax, Default::default(), None, &mut vec![]).unwrap();
+    let unresolved_mark = Mark::new();
+    program.visit_mut_with(&mut resolver(unresolved_mark, Mark::new(), false));
+    (program, unresolved_mark)
+}
+
+/// Parses `input` with `syntax`, resolves its identifiers and passes it to
+/// `f`, e.g. to analyze it.
+pub(crate) fn with_program<R>(input: &str, syntax: Syntax, f: impl FnOnce(&Program) -> R) -> R {
+    with_resolved_program(input, syntax, |program, _| f(program))
+}
+
+/// Like [with_program], but `f` also gets the mark of unresolved identifiers.
+pub(crate) fn with_resolved_program<R>(
+    input: &str,
+    syntax: Syntax,
+    f: impl FnOnce(&Program, Mark) -> R,
+) -> R {
+    GLOBALS.set(&Globals::new(), || {
+        let cm = Arc::new(SourceMap::default());
+        let (program, unresolved_mark) = parse(&cm, input, syntax);
+        f(&program, unresolved_mark)
+    })
+}
+
+/// Parses `input`, resolves its identifiers, applies `transform` to it and
+/// emits the result. `transform` gets the mark of unresolved identifiers.
+pub(crate) fn transform_program(input: &str, transform: impl FnOnce(&mut Program, Mark)) -> String {
+    transform_program_with_syntax(input, Syntax::Es(EsConfig::default()), transform)
+}
+
+/// Like [transform_program], for inputs with a different syntax, e.g.
+/// TypeScript.
+pub(crate) fn transform_program_with_syntax(
+    input: &str,
+    syntax: Syntax,
+    transform: impl FnOnce(&mut Program, Mark),
+) -> String {
+    GLOBALS.set(&Globals::new(), || {
+        let cm = Arc::new(SourceMap::default());
+        let (mut program, unresolved_mark) = parse(&cm, input, syntax);
+        transform(&mut program, unresolved_mark);
+
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: Default::default(),
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm, "\n", &mut bytes, None),
+        };
+        emitter.emit_program(&program).unwrap();
+        String::from_utf8(bytes).unwrap()
+    })
+}
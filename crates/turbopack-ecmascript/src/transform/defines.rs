@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::{
+        ast::{
+            AssignExpr, BinExpr, BinaryOp, Bool, Expr, Lit, MemberExpr, MemberProp, Pat, PatOrExpr,
+            Str, UnaryExpr, UnaryOp, UpdateExpr,
+        },
+        visit::{VisitMut, VisitMutWith},
+    },
+};
+
+/// A constant value that an expression is replaced with at compile time.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum CompileTimeDefineValue {
+    Bool(bool),
+    String(String),
+    Undefined,
+}
+
+impl From<bool> for CompileTimeDefineValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<String> for CompileTimeDefineValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for CompileTimeDefineValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl CompileTimeDefineValue {
+    fn to_expr(&self) -> Expr {
+        match self {
+            CompileTimeDefineValue::Bool(value) => Expr::Lit(Lit::Bool(Bool {
+                span: DUMMY_SP,
+                value: *value,
+            })),
+            CompileTimeDefineValue::String(value) => Expr::Lit(Lit::Str(Str {
+                span: DUMMY_SP,
+                value: value.as_str().into(),
+                raw: None,
+            })),
+            CompileTimeDefineValue::Undefined => Expr::Unary(UnaryExpr {
+                span: DUMMY_SP,
+                op: UnaryOp::Void,
+                arg: box Expr::Lit(Lit::Num(0.0.into())),
+            }),
+        }
+    }
+}
+
+/// A map from member expression paths (e.g. `["process", "env", "NODE_ENV"]`)
+/// to the constant value they should be replaced with.
+#[turbo_tasks::value(transparent, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord, Default)]
+pub struct CompileTimeDefines(pub BTreeMap<Vec<String>, CompileTimeDefineValue>);
+
+impl CompileTimeDefines {
+    /// Adds a define for a dotted path like `process.env.NODE_ENV`.
+    pub fn insert(&mut self, path: &str, value: impl Into<CompileTimeDefineValue>) {
+        self.0.insert(
+            path.split('.').map(|s| s.to_string()).collect(),
+            value.into(),
+        );
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CompileTimeDefinesVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        Self::cell(Default::default())
+    }
+
+    /// Defines `process.env.NODE_ENV` as the given value.
+    #[turbo_tasks::function]
+    pub fn node_env(node_env: &str) -> Self {
+        let mut defines = CompileTimeDefines::default();
+        defines.insert("process.env.NODE_ENV", node_env);
+        Self::cell(defines)
+    }
+}
+
+/// Replaces free variables and member expressions on free variables with
+/// their defined constant value. Comparisons between literals that result
+/// from the replacement are folded, so that the analyzer can detect constant
+/// conditions like `process.env.NODE_ENV !== "production"` and skip the dead
+/// branch.
+pub(crate) struct DefineReplacer<'a> {
+    defines: &'a CompileTimeDefines,
+    unresolved_mark: Mark,
+}
+
+impl<'a> DefineReplacer<'a> {
+    pub fn new(defines: &'a CompileTimeDefines, unresolved_mark: Mark) -> Self {
+        Self {
+            defines,
+            unresolved_mark,
+        }
+    }
+
+    /// Returns the path of the expression if it's a (possibly nested) member
+    /// expression on an unresolved identifier.
+    fn expr_path(&self, expr: &Expr, path: &mut Vec<String>) -> bool {
+        match expr {
+            Expr::Ident(ident) => {
+                if ident.span.ctxt.outer() != self.unresolved_mark {
+                    return false;
+                }
+                path.push(ident.sym.to_string());
+                true
+            }
+            Expr::Member(MemberExpr { obj, prop, .. }) => {
+                if !self.expr_path(obj, path) {
+                    return false;
+                }
+                match prop {
+                    MemberProp::Ident(ident) => path.push(ident.sym.to_string()),
+                    MemberProp::Computed(computed) => match &*computed.expr {
+                        Expr::Lit(Lit::Str(str)) => path.push(str.value.to_string()),
+                        _ => return false,
+                    },
+                    MemberProp::PrivateName(_) => return false,
+                }
+                true
+            }
+            Expr::Paren(paren) => self.expr_path(&paren.expr, path),
+            _ => false,
+        }
+    }
+
+    fn lookup(&self, expr: &Expr) -> Option<&CompileTimeDefineValue> {
+        if !matches!(expr, Expr::Ident(_) | Expr::Member(_)) {
+            return None;
+        }
+        let mut path = Vec::new();
+        if !self.expr_path(expr, &mut path) {
+            return None;
+        }
+        self.defines.0.get(&path)
+    }
+
+    /// Assignment targets like `process.env.FOO = 1` must not be replaced,
+    /// but the expressions they contain are read, like the key of
+    /// `cache[process.env.NODE_ENV] = 1`.
+    fn visit_mut_target(&mut self, target: &mut Expr) {
+        match target {
+            Expr::Paren(paren) => self.visit_mut_target(&mut paren.expr),
+            _ => target.visit_mut_children_with(self),
+        }
+    }
+}
+
+/// Compares two literals. Returns None if the result is unknown.
+fn lit_strict_eq(left: &Lit, right: &Lit) -> Option<bool> {
+    Some(match (left, right) {
+        (Lit::Str(l), Lit::Str(r)) => l.value == r.value,
+        (Lit::Bool(l), Lit::Bool(r)) => l.value == r.value,
+        (Lit::Num(l), Lit::Num(r)) => l.value == r.value,
+        (Lit::Null(_), Lit::Null(_)) => true,
+        (Lit::Str(_) | Lit::Bool(_) | Lit::Num(_) | Lit::Null(_), _) => match right {
+            Lit::Str(_) | Lit::Bool(_) | Lit::Num(_) | Lit::Null(_) => false,
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+fn fold_comparison(expr: &BinExpr) -> Option<bool> {
+    let (Expr::Lit(left), Expr::Lit(right)) = (&*expr.left, &*expr.right) else {
+        return None;
+    };
+    let same_type = std::mem::discriminant(left) == std::mem::discriminant(right);
+    match expr.op {
+        BinaryOp::EqEqEq => lit_strict_eq(left, right),
+        BinaryOp::NotEqEq => lit_strict_eq(left, right).map(|eq| !eq),
+        // Loose equality coerces between types, only fold when no coercion
+        // happens.
+        BinaryOp::EqEq if same_type => lit_strict_eq(left, right),
+        BinaryOp::NotEq if same_type => lit_strict_eq(left, right).map(|eq| !eq),
+        _ => None,
+    }
+}
+
+impl VisitMut for DefineReplacer<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Some(value) = self.lookup(expr) {
+            *expr = value.to_expr();
+            return;
+        }
+
+        expr.visit_mut_children_with(self);
+
+        if let Expr::Bin(bin) = expr {
+            if let Some(value) = fold_comparison(bin) {
+                *expr = Expr::Lit(Lit::Bool(Bool {
+                    span: bin.span,
+                    value,
+                }));
+            }
+        }
+    }
+
+    fn visit_mut_assign_expr(&mut self, expr: &mut AssignExpr) {
+        match &mut expr.left {
+            PatOrExpr::Expr(target) => self.visit_mut_target(target),
+            PatOrExpr::Pat(target) => target.visit_mut_with(self),
+        }
+        expr.right.visit_mut_with(self);
+    }
+
+    fn visit_mut_pat(&mut self, pat: &mut Pat) {
+        // Expressions in patterns are the targets of destructuring assignments
+        // like `[process.env.FOO] = values`.
+        match pat {
+            Pat::Expr(target) => self.visit_mut_target(target),
+            _ => pat.visit_mut_children_with(self),
+        }
+    }
+
+    fn visit_mut_update_expr(&mut self, expr: &mut UpdateExpr) {
+        self.visit_mut_target(&mut expr.arg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::transform_program;
+
+    fn transform(input: &str) -> String {
+        let mut defines = CompileTimeDefines::default();
+        defines.insert("process.env.NODE_ENV", "development");
+        defines.insert("__DEV__", true);
+        defines.insert("MISSING", CompileTimeDefineValue::Undefined);
+
+        transform_program(input, |program, unresolved_mark| {
+            program.visit_mut_with(&mut DefineReplacer::new(&defines, unresolved_mark));
+        })
+    }
+    #[test]
+    fn replaces_member_expressions() {
+        assert_eq!(
+            transform("console.log(process.env.NODE_ENV, process.env['NODE_ENV']);"),
+            "console.log(\"development\", \"development\");\n"
+        );
+        assert_eq!(transform("x(__DEV__, MISSING);"), "x(true, void 0);\n");
+    }
+
+    #[test]
+    fn folds_comparisons() {
+        assert_eq!(
+            transform("if (process.env.NODE_ENV !== 'production') a();"),
+            "if (true) a();\n"
+        );
+        assert_eq!(
+            transform("if (process.env.NODE_ENV === 'production') a();"),
+            "if (false) a();\n"
+        );
+    }
+
+    #[test]
+    fn skips_shadowed_and_assigned() {
+        assert_eq!(
+            transform("function f(process) { return process.env.NODE_ENV; }"),
+            "function f(process) {\n    return process.env.NODE_ENV;\n}\n"
+        );
+        assert_eq!(
+            transform("process.env.NODE_ENV = 'test';"),
+            "process.env.NODE_ENV = 'test';\n"
+        );
+        assert_eq!(
+            transform("process.env.NODE_ENV++;"),
+            "process.env.NODE_ENV++;\n"
+        );
+        assert_eq!(
+            transform("[process.env.NODE_ENV] = values;"),
+            "[process.env.NODE_ENV] = values;\n"
+        );
+    }
+
+    #[test]
+    fn replaces_in_assignment_targets() {
+        assert_eq!(
+            transform("cache[process.env.NODE_ENV] = value;"),
+            "cache[\"development\"] = value;\n"
+        );
+        assert_eq!(transform("cache[__DEV__]++;"), "cache[true]++;\n");
+        assert_eq!(
+            transform("({ a = __DEV__ } = value);"),
+            "({ a = true } = value);\n"
+        );
+    }
+}
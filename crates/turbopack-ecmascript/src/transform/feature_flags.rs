@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashSet};
+
+use swc_core::{
+    common::{util::take::Take, Mark},
+    ecma::{
+        ast::{
+            ArrowExpr, BinExpr, BinaryOp, Bool, Class, CondExpr, Expr, Function, Id, Ident, IfStmt,
+            ImportDecl, ImportSpecifier, Lit, Module, ModuleDecl, ModuleItem, Program, Stmt,
+            UnaryExpr, UnaryOp, VarDecl, VarDeclKind,
+        },
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    },
+};
+
+use super::defines::{CompileTimeDefines, DefineReplacer};
+
+/// Named boolean flags, e.g. `__FEATURE_NEW_CHECKOUT__` or
+/// `features.newCheckout`. Like defines, a flag is referenced by a free
+/// variable or a member expression on one.
+#[turbo_tasks::value(transparent, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord, Default)]
+pub struct FeatureFlags(pub BTreeMap<String, bool>);
+
+#[turbo_tasks::value_impl]
+impl FeatureFlagsVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        Self::cell(Default::default())
+    }
+}
+
+/// Replaces the feature flags with their values and removes the branches that
+/// are dead because of them. Imports that were only used in the removed
+/// branches are removed as well, so the modules of disabled features are not
+/// part of the module graph at all.
+pub(crate) fn strip_feature_flags(
+    program: &mut Program,
+    flags: &FeatureFlags,
+    unresolved_mark: Mark,
+) {
+    let mut defines = CompileTimeDefines::default();
+    for (name, value) in flags.0.iter() {
+        defines.insert(name, *value);
+    }
+
+    let used_before = used_idents(program);
+    program.visit_mut_with(&mut DefineReplacer::new(&defines, unresolved_mark));
+    program.visit_mut_with(&mut DeadBranchRemover);
+    let used_after = used_idents(program);
+
+    if let Program::Module(module) = program {
+        remove_unused_imports(module, |id| {
+            used_before.contains(id) && !used_after.contains(id)
+        });
+    }
+}
+
+fn as_bool(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Lit(Lit::Bool(Bool { value, .. })) => Some(*value),
+        Expr::Paren(paren) => as_bool(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Removes the branches of conditions on boolean literals.
+struct DeadBranchRemover;
+
+impl VisitMut for DeadBranchRemover {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        expr.visit_mut_children_with(self);
+
+        let replacement = match expr {
+            Expr::Unary(UnaryExpr {
+                op: UnaryOp::Bang,
+                arg,
+                span,
+            }) => as_bool(arg).map(|value| {
+                Expr::Lit(Lit::Bool(Bool {
+                    span: *span,
+                    value: !value,
+                }))
+            }),
+            Expr::Bin(BinExpr {
+                op: BinaryOp::LogicalAnd,
+                left,
+                right,
+                ..
+            }) => as_bool(left).map(|value| if value { right.take() } else { left.take() }),
+            Expr::Bin(BinExpr {
+                op: BinaryOp::LogicalOr,
+                left,
+                right,
+                ..
+            }) => as_bool(left).map(|value| if value { left.take() } else { right.take() }),
+            Expr::Cond(CondExpr {
+                test, cons, alt, ..
+            }) => as_bool(test).map(|value| if value { cons.take() } else { alt.take() }),
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            *expr = replacement;
+        }
+    }
+
+    fn visit_mut_stmt(&mut self, stmt: &mut Stmt) {
+        stmt.visit_mut_children_with(self);
+
+        let Stmt::If(IfStmt { test, cons, alt, .. }) = stmt else {
+            return;
+        };
+        let Some(value) = as_bool(test) else {
+            return;
+        };
+        // `var` declarations are hoisted out of the branch, so a branch that
+        // declares them has to stay.
+        let dead = if value { alt.as_deref() } else { Some(&**cons) };
+        if dead.map_or(false, declares_var) {
+            return;
+        }
+        *stmt = if value {
+            *cons.take()
+        } else {
+            alt.take().map_or_else(Stmt::dummy, |alt| *alt)
+        };
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.visit_mut_children_with(self);
+        stmts.retain(|stmt| !matches!(stmt, Stmt::Empty(_)));
+    }
+
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.visit_mut_children_with(self);
+        items.retain(|item| !matches!(item, ModuleItem::Stmt(Stmt::Empty(_))));
+    }
+}
+
+fn declares_var(stmt: &Stmt) -> bool {
+    struct VarFinder(bool);
+
+    impl Visit for VarFinder {
+        fn visit_var_decl(&mut self, decl: &VarDecl) {
+            if decl.kind == VarDeclKind::Var {
+                self.0 = true;
+            }
+        }
+
+        // Declarations in nested functions don't leave them.
+        fn visit_function(&mut self, _: &Function) {}
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+        fn visit_class(&mut self, _: &Class) {}
+    }
+
+    let mut finder = VarFinder(false);
+    stmt.visit_with(&mut finder);
+    finder.0
+}
+
+/// Collects the identifiers that are referenced outside of import
+/// declarations.
+fn used_idents(program: &Program) -> HashSet<Id> {
+    struct Collector(HashSet<Id>);
+
+    impl Visit for Collector {
+        fn visit_ident(&mut self, ident: &Ident) {
+            self.0.insert(ident.to_id());
+        }
+
+        fn visit_import_decl(&mut self, _: &ImportDecl) {}
+    }
+
+    let mut collector = Collector(HashSet::new());
+    program.visit_with(&mut collector);
+    collector.0
+}
+
+fn remove_unused_imports(module: &mut Module, is_unused: impl Fn(&Id) -> bool) {
+    module.body.retain_mut(|item| {
+        let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+            return true;
+        };
+        if import.specifiers.is_empty() {
+            // Side effect imports don't have bindings which could be unused.
+            return true;
+        }
+        import.specifiers.retain(|specifier| {
+            let local = match specifier {
+                ImportSpecifier::Named(specifier) => &specifier.local,
+                ImportSpecifier::Default(specifier) => &specifier.local,
+                ImportSpecifier::Namespace(specifier) => &specifier.local,
+            };
+            !is_unused(&local.to_id())
+        });
+        !import.specifiers.is_empty()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::transform_program;
+
+    fn transform(input: &str) -> String {
+        let flags = FeatureFlags(BTreeMap::from([
+            ("__NEW_CHECKOUT__".to_string(), false),
+            ("features.search".to_string(), true),
+        ]));
+
+        transform_program(input, |program, unresolved_mark| {
+            strip_feature_flags(program, &flags, unresolved_mark);
+        })
+    }
+
+    #[test]
+    fn removes_dead_branches() {
+        assert_eq!(
+            transform("if (__NEW_CHECKOUT__) { a(); } else { b(); }"),
+            "{\n    b();\n}\n"
+        );
+        assert_eq!(
+            transform("x = features.search ? a : b; y = !__NEW_CHECKOUT__ && c;"),
+            "x = a;\ny = c;\n"
+        );
+    }
+
+    #[test]
+    fn removes_imports_of_dead_branches() {
+        assert_eq!(
+            transform(
+                "import { Checkout, other } from './checkout';\nimport unused from \
+                 './unused';\nimport './side-effect';\nif (__NEW_CHECKOUT__) { render(Checkout); \
+                 }\nother();"
+            ),
+            "import { other } from './checkout';\nimport unused from './unused';\nimport \
+             './side-effect';\nother();\n"
+        );
+    }
+
+    #[test]
+    fn keeps_hoisted_vars() {
+        assert_eq!(
+            transform("if (__NEW_CHECKOUT__) { var a = 1; }"),
+            "if (false) {\n    var a = 1;\n}\n"
+        );
+    }
+}
@@ -1,24 +1,39 @@
+mod defines;
+mod feature_flags;
+mod plugin;
 mod server_to_client_proxy;
+#[cfg(feature = "swc_wasm_plugins")]
+mod wasm_plugin;
 
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, path::Path, str::FromStr, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use next_transform_dynamic::{next_dynamic, NextDynamicMode};
 use next_transform_strip_page_exports::{next_transform_strip_page_exports, ExportFilter};
 use serde::{Deserialize, Serialize};
 use swc_core::{
     base::SwcComments,
-    common::{chain, util::take::Take, FileName, Mark, SourceMap},
+    common::{chain, util::take::Take, FileName, Mark, SourceMap, DUMMY_SP},
     ecma::{
-        ast::{Module, ModuleItem, Program},
+        ast::{
+            CallExpr, Callee, Decl, Expr, ExprOrSpread, Ident, ImportDecl, ImportSpecifier, Lit,
+            Module, ModuleDecl, ModuleExportName, ModuleItem, Pat, Program, Stmt, VarDecl,
+            VarDeclKind, VarDeclarator,
+        },
         atoms::JsWord,
-        preset_env::{self, Targets},
+        preset_env::{self, Targets, Version, Versions},
         transforms::{
-            base::{feature::FeatureFlag, helpers::inject_helpers, resolver, Assumptions},
+            base::{
+                feature::FeatureFlag,
+                helpers::{inject_helpers, Helpers, HELPERS},
+                resolver, Assumptions,
+            },
+            proposal::decorators::{self, decorators},
             react::react,
         },
-        visit::{FoldWith, VisitMutWith},
+        visit::{FoldWith, Visit, VisitMut, VisitMutWith, VisitWith},
     },
+    quote,
 };
 use turbo_tasks::{
     primitives::{StringVc, StringsVc},
@@ -27,7 +42,22 @@ use turbo_tasks::{
 use turbo_tasks_fs::{json::parse_json_with_source_context, FileSystemPathVc};
 use turbopack_core::environment::EnvironmentVc;
 
-use self::server_to_client_proxy::{create_proxy_module, is_client_module};
+pub(crate) use self::plugin::order_transforms;
+#[cfg(feature = "swc_wasm_plugins")]
+pub use self::wasm_plugin::create_wasm_transform_plugin;
+use self::{
+    defines::DefineReplacer,
+    feature_flags::strip_feature_flags,
+    server_to_client_proxy::{create_proxy_module, is_client_module},
+};
+pub use self::{
+    defines::{CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc},
+    feature_flags::{FeatureFlags, FeatureFlagsVc},
+    plugin::{
+        CustomTransformer, TransformPlugin, TransformPluginIssue, TransformPluginIssueVc,
+        TransformPluginOrder, TransformPluginVc,
+    },
+};
 
 #[derive(
     Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize, TraceRawVcs,
@@ -49,13 +79,128 @@ impl From<NextJsPageExportFilter> for ExportFilter {
     }
 }
 
+/// The `core-js` polyfills that are injected for modern built-ins (e.g.
+/// `Array.prototype.includes`) when the target environment lacks them.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+)]
+pub enum CoreJsPolyfills {
+    /// No polyfills are injected.
+    #[default]
+    None,
+    /// Polyfills are imported by each module for the built-ins it uses.
+    Usage,
+    /// `import "core-js"` in the entry is replaced with the polyfills
+    /// required by the target environment.
+    Entry,
+}
+
+/// The decorators proposal that is implemented by the decorators transform.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+)]
+pub enum DecoratorsKind {
+    /// TypeScript's `experimentalDecorators`.
+    Legacy,
+    /// The TC39 decorators proposal.
+    #[default]
+    Tc39,
+}
+
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct DecoratorsOptions {
+    pub kind: DecoratorsKind,
+    /// Emits `design:type`, `design:paramtypes` and `design:returntype`
+    /// metadata for decorated members, like TypeScript's
+    /// `emitDecoratorMetadata`.
+    pub emit_decorators_metadata: bool,
+    pub use_define_for_class_fields: bool,
+}
+
+/// Configures the React Refresh transform, which registers components and
+/// the signatures of their hooks, so they can be hot reloaded.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub struct ReactRefreshOptions {
+    /// The global function that components are registered with.
+    pub register_function: String,
+    /// The global function that creates the hook signatures of components.
+    pub signature_function: String,
+    /// Emits the full hook signatures instead of their hashes.
+    pub emit_full_signatures: bool,
+}
+
+impl Default for ReactRefreshOptions {
+    fn default() -> Self {
+        ReactRefreshOptions {
+            register_function: "$RefreshReg$".to_string(),
+            signature_function: "$RefreshSig$".to_string(),
+            emit_full_signatures: false,
+        }
+    }
+}
+
+impl DecoratorsOptions {
+    fn swc_config(&self) -> decorators::Config {
+        decorators::Config {
+            legacy: matches!(self.kind, DecoratorsKind::Legacy),
+            emit_metadata: self.emit_decorators_metadata,
+            use_define_for_class_fields: self.use_define_for_class_fields,
+        }
+    }
+}
+
+impl ReactRefreshOptions {
+    fn swc_config(&self) -> swc_core::ecma::transforms::react::RefreshOptions {
+        swc_core::ecma::transforms::react::RefreshOptions {
+            refresh_reg: self.register_function.clone(),
+            refresh_sig: self.signature_function.clone(),
+            emit_full_signatures: self.emit_full_signatures,
+        }
+    }
+}
+
+/// The version of `core-js` that polyfills are imported from.
+const CORE_JS_VERSION: &str = "3.26.0";
+
 #[turbo_tasks::value(serialization = "auto_for_input")]
 #[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
 pub enum EcmascriptInputTransform {
     ClientDirective(StringVc),
     CommonJs,
-    Custom,
+    /// Transforms decorators. Needs to run before types are stripped to be
+    /// able to emit decorator metadata.
+    Decorators(DecoratorsOptionsVc),
+    /// Replaces free variables and member expressions like
+    /// `process.env.NODE_ENV` with constant values.
+    Defines(CompileTimeDefinesVc),
     Emotion,
+    /// Replaces named boolean flags with their values and removes the dead
+    /// branches, including the imports only they used.
+    FeatureFlags(FeatureFlagsVc),
     /// This enables a Next.js transform which will eliminate some exports
     /// from a page file, as well as any imports exclusively used by these
     /// exports.
@@ -70,10 +215,19 @@ pub enum EcmascriptInputTransform {
         pages_dir: Option<FileSystemPathVc>,
     },
     NextJsFont(StringsVc),
-    PresetEnv(EnvironmentVc),
+    /// A transform that isn't built in, see [CustomTransformer].
+    Plugin(TransformPluginVc),
+    /// Downlevels syntax to the runtime versions of the environment. The
+    /// swc helpers needed by the downleveled syntax are imported from
+    /// `@swc/helpers`, so they are shared between modules.
+    PresetEnv {
+        env: EnvironmentVc,
+        polyfills: CoreJsPolyfills,
+    },
     React {
+        /// Enables React Refresh when set.
         #[serde(default)]
-        refresh: bool,
+        refresh: Option<ReactRefreshOptionsVc>,
     },
     StyledComponents,
     StyledJsx,
@@ -102,13 +256,12 @@ pub struct TransformContext<'a> {
     pub file_path_str: &'a str,
     pub file_name_str: &'a str,
     pub file_name_hash: u128,
+    pub file_path: FileSystemPathVc,
 }
 
 impl EcmascriptInputTransform {
-    pub async fn apply(
-        &self,
-        program: &mut Program,
-        &TransformContext {
+    pub async fn apply(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()> {
+        let &TransformContext {
             comments,
             source_map,
             top_level_mark,
@@ -116,8 +269,8 @@ impl EcmascriptInputTransform {
             file_path_str,
             file_name_str,
             file_name_hash,
-        }: &TransformContext<'_>,
-    ) -> Result<()> {
+            file_path: _,
+        } = context;
         match *self {
             EcmascriptInputTransform::React { refresh } => {
                 program.visit_mut_with(&mut react(
@@ -126,12 +279,9 @@ impl EcmascriptInputTransform {
                     swc_core::ecma::transforms::react::Options {
                         runtime: Some(swc_core::ecma::transforms::react::Runtime::Automatic),
                         development: Some(true),
-                        refresh: if refresh {
-                            Some(swc_core::ecma::transforms::react::RefreshOptions {
-                                ..Default::default()
-                            })
-                        } else {
-                            None
+                        refresh: match refresh {
+                            Some(options) => Some(options.await?.swc_config()),
+                            None => None,
                         },
                         ..Default::default()
                     },
@@ -152,6 +302,19 @@ impl EcmascriptInputTransform {
                     Some(comments.clone()),
                 ));
             }
+            EcmascriptInputTransform::Decorators(options) => {
+                let options = options.await?;
+                let p = std::mem::replace(program, Program::Module(Module::dummy()));
+                *program = p.fold_with(&mut decorators(options.swc_config()));
+            }
+            EcmascriptInputTransform::Defines(defines) => {
+                let defines = defines.await?;
+                program.visit_mut_with(&mut DefineReplacer::new(&defines, unresolved_mark));
+            }
+            EcmascriptInputTransform::FeatureFlags(flags) => {
+                let flags = flags.await?;
+                strip_feature_flags(program, &flags, unresolved_mark);
+            }
             EcmascriptInputTransform::Emotion => {
                 let p = std::mem::replace(program, Program::Module(Module::dummy()));
                 *program = p.fold_with(&mut swc_emotion::emotion(
@@ -161,11 +324,25 @@ impl EcmascriptInputTransform {
                     comments.clone(),
                 ))
             }
-            EcmascriptInputTransform::PresetEnv(env) => {
+            EcmascriptInputTransform::PresetEnv { env, polyfills } => {
                 let versions = env.runtime_versions().await?;
+                let mode = match polyfills {
+                    CoreJsPolyfills::None => None,
+                    CoreJsPolyfills::Usage => Some(preset_env::Mode::Usage),
+                    CoreJsPolyfills::Entry => Some(preset_env::Mode::Entry),
+                };
+                let core_js = if mode.is_some() {
+                    Some(
+                        Version::from_str(CORE_JS_VERSION)
+                            .map_err(|_| anyhow!("core-js version parse error"))?,
+                    )
+                } else {
+                    None
+                };
                 let config = swc_core::ecma::preset_env::Config {
                     targets: Some(Targets::Versions(*versions)),
-                    mode: None, // Don't insert core-js polyfills
+                    mode,
+                    core_js,
                     ..Default::default()
                 };
 
@@ -255,12 +432,176 @@ impl EcmascriptInputTransform {
 
                 program.visit_mut_with(&mut next_font);
             }
-            EcmascriptInputTransform::Custom => todo!(),
+            EcmascriptInputTransform::Plugin(plugin) => {
+                plugin.await?.apply(program, context);
+            }
         }
         Ok(())
     }
 }
 
+/// Downlevels the syntax of generated module code to the given runtime
+/// versions. This runs after the analysis, so the analysis only sees the
+/// original syntax. The swc helpers are imported from `@swc/helpers`, so all
+/// modules share them. As the analysis doesn't see these imports, the chunk
+/// item references the modules of [swc_helper_requests] itself, and the
+/// imports are replaced with [SwcHelperImports].
+pub(crate) fn downlevel_program(
+    program: &mut Program,
+    versions: Versions,
+    comments: &SwcComments,
+    unresolved_mark: Mark,
+) {
+    let config = preset_env::Config {
+        targets: Some(Targets::Versions(versions)),
+        ..Default::default()
+    };
+    let helpers = Helpers::new(true);
+    HELPERS.set(&helpers, || {
+        let taken = std::mem::replace(program, Program::Module(Module::dummy()));
+        *program = taken.fold_with(&mut chain!(
+            preset_env::preset_env(
+                Mark::new(),
+                Some(comments.clone()),
+                config,
+                Assumptions::default(),
+                &mut FeatureFlag::empty(),
+            ),
+            inject_helpers(unresolved_mark),
+        ));
+    });
+}
+
+const SWC_HELPERS: &str = "@swc/helpers/";
+
+/// Returns the `@swc/helpers` request of a `require("@swc/helpers/...")`
+/// call, as emitted for helpers in scripts.
+fn swc_helper_require(call: &CallExpr) -> Option<&JsWord> {
+    match call {
+        CallExpr {
+            callee: Callee::Expr(box Expr::Ident(ident)),
+            args,
+            ..
+        } if &*ident.sym == "require" => match args.first() {
+            Some(ExprOrSpread {
+                spread: None,
+                expr: box Expr::Lit(Lit::Str(request)),
+            }) if request.value.starts_with(SWC_HELPERS) => Some(&request.value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the requests of the `@swc/helpers` modules which a program that
+/// went through [downlevel_program] imports.
+pub(crate) fn swc_helper_requests(program: &Program) -> Vec<String> {
+    struct Visitor(Vec<String>);
+
+    impl Visit for Visitor {
+        fn visit_import_decl(&mut self, import: &ImportDecl) {
+            if import.src.value.starts_with(SWC_HELPERS) {
+                self.0.push(import.src.value.to_string());
+            }
+        }
+
+        fn visit_call_expr(&mut self, call: &CallExpr) {
+            if let Some(request) = swc_helper_require(call) {
+                self.0.push(request.to_string());
+            }
+            call.visit_children_with(self);
+        }
+    }
+
+    let mut visitor = Visitor(Vec::new());
+    program.visit_with(&mut visitor);
+    let mut requests = visitor.0;
+    requests.sort();
+    requests.dedup();
+    requests
+}
+
+/// Replaces the imports of `@swc/helpers` modules with the modules of the
+/// chunk items which `ids` maps the requests to.
+pub(crate) struct SwcHelperImports<'a> {
+    pub ids: &'a HashMap<String, Expr>,
+}
+
+impl VisitMut for SwcHelperImports<'_> {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        for item in items.iter_mut() {
+            let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+                continue;
+            };
+            let Some(id) = self.ids.get(&*import.src.value) else {
+                continue;
+            };
+            let module = quote!("__turbopack_import__($id)" as Expr, id: Expr = id.clone());
+            let decls = import
+                .specifiers
+                .iter()
+                .map(|specifier| {
+                    let (local, init) = match specifier {
+                        ImportSpecifier::Default(specifier) => (
+                            specifier.local.clone(),
+                            quote!("$module.default" as Expr, module: Expr = module.clone()),
+                        ),
+                        ImportSpecifier::Named(specifier) => {
+                            let imported = match &specifier.imported {
+                                Some(ModuleExportName::Ident(ident)) => ident.sym.to_string(),
+                                Some(ModuleExportName::Str(name)) => name.value.to_string(),
+                                None => specifier.local.sym.to_string(),
+                            };
+                            (
+                                specifier.local.clone(),
+                                quote!(
+                                    "$module[$imported]" as Expr,
+                                    module: Expr = module.clone(),
+                                    imported: Expr = imported.into()
+                                ),
+                            )
+                        }
+                        ImportSpecifier::Namespace(specifier) => {
+                            (specifier.local.clone(), module.clone())
+                        }
+                    };
+                    VarDeclarator {
+                        span: DUMMY_SP,
+                        name: Pat::Ident(local.into()),
+                        init: Some(box init),
+                        definite: false,
+                    }
+                })
+                .collect();
+            *item = ModuleItem::Stmt(Stmt::Decl(Decl::Var(box VarDecl {
+                span: import.span,
+                kind: VarDeclKind::Var,
+                declare: false,
+                decls,
+            })));
+        }
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        if let Some(id) = swc_helper_require(call).and_then(|request| self.ids.get(&**request)) {
+            *call = CallExpr {
+                span: call.span,
+                callee: Callee::Expr(box Expr::Ident(Ident::new(
+                    "__turbopack_require__".into(),
+                    DUMMY_SP,
+                ))),
+                args: vec![ExprOrSpread {
+                    spread: None,
+                    expr: box id.clone(),
+                }],
+                type_args: None,
+            };
+        }
+        call.visit_mut_children_with(self);
+    }
+}
+
 fn unwrap_module_program(program: &mut Program) -> Program {
     match program {
         Program::Module(module) => Program::Module(module.take()),
@@ -275,3 +616,90 @@ fn unwrap_module_program(program: &mut Program) -> Program {
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::ecma::parser::{Syntax, TsConfig};
+
+    use super::*;
+    use crate::test_utils::{transform_program, transform_program_with_syntax};
+
+    fn transform_decorators(input: &str, options: DecoratorsOptions) -> String {
+        let syntax = Syntax::Typescript(TsConfig {
+            decorators: true,
+            ..Default::default()
+        });
+        transform_program_with_syntax(input, syntax, |program, _| {
+            HELPERS.set(&Helpers::new(false), || {
+                let p = std::mem::replace(program, Program::Module(Module::dummy()));
+                *program = p.fold_with(&mut decorators(options.swc_config()));
+            });
+        })
+    }
+
+    /// Components without hooks don't need the source map, which is used to
+    /// compute the signatures of hooks.
+    fn transform_react_refresh(input: &str, options: ReactRefreshOptions) -> String {
+        transform_program(input, |program, _| {
+            program.visit_mut_with(&mut react(
+                Default::default(),
+                None::<SwcComments>,
+                swc_core::ecma::transforms::react::Options {
+                    development: Some(true),
+                    refresh: Some(options.swc_config()),
+                    ..Default::default()
+                },
+                Mark::new(),
+            ));
+        })
+    }
+
+    #[test]
+    fn transforms_legacy_decorators_with_metadata() {
+        let input = "@injectable() class Service { constructor(name: string) {} }";
+        let output = transform_decorators(
+            input,
+            DecoratorsOptions {
+                kind: DecoratorsKind::Legacy,
+                emit_decorators_metadata: true,
+                use_define_for_class_fields: false,
+            },
+        );
+        assert!(!output.contains('@'), "{output}");
+        assert!(output.contains("design:paramtypes"), "{output}");
+
+        let output = transform_decorators(
+            input,
+            DecoratorsOptions {
+                kind: DecoratorsKind::Legacy,
+                ..Default::default()
+            },
+        );
+        assert!(!output.contains("design:paramtypes"), "{output}");
+    }
+
+    #[test]
+    fn transforms_tc39_decorators() {
+        let output = transform_decorators("@sealed class Config {}", Default::default());
+        assert!(!output.contains('@'), "{output}");
+        assert!(output.contains("sealed"), "{output}");
+    }
+
+    #[test]
+    fn registers_components_with_react_refresh_globals() {
+        let input = "export function App() {\n  return null;\n}";
+        let output = transform_react_refresh(input, ReactRefreshOptions::default());
+        assert!(output.contains("$RefreshReg$(_c, \"App\")"), "{output}");
+
+        let output = transform_react_refresh(
+            input,
+            ReactRefreshOptions {
+                register_function: "__register__".to_string(),
+                signature_function: "__signature__".to_string(),
+                emit_full_signatures: false,
+            },
+        );
+        assert!(output.contains("__register__(_c, \"App\")"), "{output}");
+        assert!(!output.contains("$RefreshReg$"), "{output}");
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Debug;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use swc_core::ecma::ast::Program;
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+use super::{EcmascriptInputTransform, TransformContext};
+
+/// A transform that isn't built into turbopack, e.g. a port of a Babel plugin
+/// like styled-components or relay. It's added to modules with
+/// [EcmascriptInputTransform::Plugin], usually by a module rule that adds
+/// ecmascript transforms to the modules it matches.
+pub trait CustomTransformer: Debug + Send + Sync {
+    /// The name of the plugin, which is shown in issues.
+    fn name(&self) -> &str;
+
+    /// Identifies the plugin and its configuration in the persistent parse
+    /// cache. Modules transformed by plugins without a key are not cached.
+    fn cache_key(&self) -> Option<String> {
+        None
+    }
+
+    /// Transforms the program. Problems that the plugin can recover from can
+    /// be emitted as [TransformPluginIssue]s. An error is reported as an
+    /// issue as well, and the program is used as it is, so the plugin
+    /// shouldn't return an error after it has changed the program.
+    fn transform(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()>;
+}
+
+/// When a plugin runs, relative to the other transforms of a module.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+)]
+pub enum TransformPluginOrder {
+    /// Before all other transforms, so the plugin sees the program as it's
+    /// written, including types and JSX.
+    Early,
+    /// In the order of the transforms. Module rules add their transforms
+    /// after the built-in ones.
+    #[default]
+    Normal,
+    /// After all other transforms.
+    Late,
+}
+
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+pub struct TransformPlugin {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    transformer: Box<dyn CustomTransformer>,
+    order: TransformPluginOrder,
+}
+
+impl TransformPluginVc {
+    pub fn new(transformer: Box<dyn CustomTransformer>, order: TransformPluginOrder) -> Self {
+        TransformPlugin { transformer, order }.cell()
+    }
+}
+
+impl TransformPlugin {
+    pub fn transformer(&self) -> &dyn CustomTransformer {
+        &*self.transformer
+    }
+
+    pub(super) fn apply(&self, program: &mut Program, context: &TransformContext<'_>) {
+        if let Err(err) = self.transformer.transform(program, context) {
+            TransformPluginIssue {
+                file_path: context.file_path,
+                plugin: self.transformer.name().to_string(),
+                severity: IssueSeverity::Error.into(),
+                message: StringVc::cell(format!("{err:?}")),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+}
+
+/// Sorts the transforms by the [TransformPluginOrder] of the plugins among
+/// them. Transforms with the same order keep their relative order.
+pub(crate) async fn order_transforms(
+    transforms: &[EcmascriptInputTransform],
+) -> Result<Vec<&EcmascriptInputTransform>> {
+    let mut ordered = Vec::with_capacity(transforms.len());
+    for transform in transforms {
+        let order = match transform {
+            EcmascriptInputTransform::Plugin(plugin) => plugin.await?.order,
+            _ => TransformPluginOrder::Normal,
+        };
+        ordered.push((order, transform));
+    }
+    ordered.sort_by_key(|(order, _)| *order);
+    Ok(ordered
+        .into_iter()
+        .map(|(_, transform)| transform)
+        .collect())
+}
+
+/// An issue reported by a [CustomTransformer].
+#[turbo_tasks::value(shared)]
+pub struct TransformPluginIssue {
+    pub file_path: FileSystemPathVc,
+    pub plugin: String,
+    pub severity: IssueSeverityVc,
+    pub message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for TransformPluginIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("transform".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Transform plugin {} reported a problem",
+            self.plugin
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.file_path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
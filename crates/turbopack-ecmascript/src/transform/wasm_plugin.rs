@@ -0,0 +1,234 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value as JsonValue;
+use swc_core::{
+    common::{
+        plugin::{
+            metadata::TransformPluginMetadataContext,
+            serialized::{PluginSerializedBytes, VersionedSerializable},
+        },
+        util::take::Take,
+    },
+    ecma::ast::{Module, Program},
+    plugin_runner::{
+        cache::{init_plugin_module_cache_once, PLUGIN_MODULE_CACHE},
+        create_plugin_transform_executor,
+    },
+};
+use turbo_tasks_fs::{to_sys_path, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
+
+use super::{
+    plugin::{CustomTransformer, TransformPluginOrder, TransformPluginVc},
+    TransformContext,
+};
+
+/// Runs a swc plugin that is compiled to WASM, so that the existing swc
+/// plugins of the ecosystem can be used as transform plugins.
+///
+/// The compiled WASM modules are cached in memory and in
+/// `node_modules/.cache/turbopack/swc-plugins` of the project, so a plugin is
+/// only compiled once. Comments are not passed to the plugin.
+#[derive(Debug)]
+struct WasmPluginTransformer {
+    name: String,
+    path: PathBuf,
+    /// The configuration that is passed to the plugin, like the second
+    /// element of the plugin entry in `.swcrc`.
+    config: JsonValue,
+    /// The mode the plugin runs in, `development` or `production`.
+    env_name: String,
+    /// Changes when the WASM module changes.
+    content_hash: String,
+}
+
+impl CustomTransformer for WasmPluginTransformer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "{}:{}:{}",
+            self.content_hash, self.env_name, self.config
+        ))
+    }
+
+    fn transform(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()> {
+        let metadata = Arc::new(TransformPluginMetadataContext::new(
+            Some(context.file_path_str.to_string()),
+            self.env_name.clone(),
+            None,
+        ));
+        let original = PluginSerializedBytes::try_serialize(&VersionedSerializable::new(
+            std::mem::replace(program, Program::Module(Module::dummy())),
+        ))
+        .context("serializing the program for the plugin")?;
+        let result = create_plugin_transform_executor(
+            &self.path,
+            &PLUGIN_MODULE_CACHE,
+            context.source_map,
+            &metadata,
+            Some(self.config.clone()),
+        )
+        .and_then(|mut executor| executor.transform(&original, context.unresolved_mark, false));
+        // The program is restored when the plugin failed.
+        let serialized = match &result {
+            Ok(serialized) => serialized,
+            Err(_) => &original,
+        };
+        *program = serialized
+            .deserialize::<Program>()
+            .context("deserializing the program returned by the plugin")?
+            .into_inner();
+        result.map(|_| ()).map_err(|err| anyhow!(err))
+    }
+}
+
+/// Creates a transform plugin for the swc plugin at `path`, which needs to be
+/// a `.wasm` file on disk. `env_name` is passed to the plugin as the mode of
+/// the build, `development` or `production`. `project_path` is the directory
+/// the compiled plugins are cached in.
+pub async fn create_wasm_transform_plugin(
+    path: FileSystemPathVc,
+    config: JsonValue,
+    env_name: &str,
+    order: TransformPluginOrder,
+    project_path: FileSystemPathVc,
+) -> Result<TransformPluginVc> {
+    let path_value = path.await?;
+    let Some(sys_path) = to_sys_path(path).await? else {
+        bail!("swc plugin {} needs to be on disk", path_value.path);
+    };
+    let FileContent::Content(file) = &*path.read().await? else {
+        bail!("swc plugin {} doesn't exist", path_value.path);
+    };
+    let cache_root = to_sys_path(project_path.join("node_modules/.cache/turbopack/swc-plugins"))
+        .await?
+        .map(|path| path.to_string_lossy().into_owned());
+    init_plugin_module_cache_once(&cache_root);
+
+    let name = path_value.file_name().trim_end_matches(".wasm").to_string();
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_ref(file.content());
+    Ok(TransformPluginVc::new(
+        Box::new(WasmPluginTransformer {
+            name,
+            path: sys_path,
+            config,
+            env_name: env_name.to_string(),
+            content_hash: encode_hex(hasher.finish()),
+        }),
+        order,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use swc_core::ecma::{
+        ast::Str,
+        visit::{Visit, VisitWith},
+    };
+    use turbo_tasks::{TurboTasks, Value};
+    use turbo_tasks_fs::{DiskFileSystemVc, File};
+    use turbo_tasks_memory::MemoryBackend;
+    use turbopack_core::{asset::AssetContent, issue::IssueVc, virtual_asset::VirtualAssetVc};
+
+    use super::*;
+    use crate::{
+        parse::{parse, ParseResult},
+        EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+    };
+
+    #[derive(Default)]
+    struct Strings(Vec<String>);
+
+    impl Visit for Strings {
+        fn visit_str(&mut self, s: &Str) {
+            self.0.push(s.value.to_string());
+        }
+    }
+
+    #[test]
+    fn keeps_program_when_plugin_fails() {
+        crate::register();
+        let dir = std::env::temp_dir().join("turbopack-swc-wasm-plugins");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("invalid.wasm"), "not a wasm module").unwrap();
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                tt.run_once(async move {
+                    let fs = DiskFileSystemVc::new(
+                        "plugins".to_string(),
+                        dir.to_string_lossy().into_owned(),
+                    );
+                    let root = fs.root();
+
+                    let missing = create_wasm_transform_plugin(
+                        root.join("missing.wasm"),
+                        json!({}),
+                        "development",
+                        TransformPluginOrder::Normal,
+                        root,
+                    )
+                    .await;
+                    assert!(missing.unwrap_err().to_string().contains("doesn't exist"));
+
+                    let plugin = create_wasm_transform_plugin(
+                        root.join("invalid.wasm"),
+                        json!({ "option": true }),
+                        "production",
+                        TransformPluginOrder::Normal,
+                        root,
+                    )
+                    .await?;
+                    let plugin_value = plugin.await?;
+                    let transformer = plugin_value.transformer();
+                    assert_eq!(transformer.name(), "invalid");
+                    // Changes with the mode and the configuration of the plugin.
+                    assert!(transformer
+                        .cache_key()
+                        .unwrap()
+                        .ends_with(r#":production:{"option":true}"#));
+
+                    let source = VirtualAssetVc::new(
+                        root.join("index.js"),
+                        AssetContent::File(File::from("export const value = \"value\";").into())
+                            .cell(),
+                    );
+                    let result = parse(
+                        source.into(),
+                        Value::new(EcmascriptModuleAssetType::Ecmascript),
+                        EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::Plugin(
+                            plugin,
+                        )]),
+                    );
+                    let ParseResult::Ok { program, .. } = &*result.await? else {
+                        panic!("the source is parseable");
+                    };
+                    let mut strings = Strings::default();
+                    program.visit_with(&mut strings);
+                    assert_eq!(strings.0, ["value"]);
+
+                    let issues = IssueVc::peek_issues_with_path(result)
+                        .await?
+                        .await?
+                        .get_plain_issues()
+                        .await?;
+                    assert_eq!(issues.len(), 1, "{issues:#?}");
+                    assert_eq!(
+                        issues[0].title,
+                        "Transform plugin invalid reported a problem"
+                    );
+                    Ok(())
+                })
+                .await
+            })
+            .unwrap();
+    }
+}
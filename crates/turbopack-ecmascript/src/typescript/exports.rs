@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use swc_core::ecma::ast::{
+    BinExpr, BinaryOp, Decl, ExportSpecifier, Expr, Lit, MemberExpr, MemberProp, Module,
+    ModuleDecl, ModuleExportName, ModuleItem, Program, Stmt, TsEnumDecl, TsEnumMemberId, UnaryExpr,
+    UnaryOp,
+};
+use turbo_tasks::{trace::TraceRawVcs, Value};
+use turbopack_core::asset::AssetVc;
+
+use crate::{
+    parse::{parse, ParseResult},
+    EcmascriptInputTransformsVc, EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+};
+
+/// The value of a member of a `const enum`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub enum ConstEnumMember {
+    /// A finite number, formatted like JavaScript formats numbers.
+    Number(String),
+    String(String),
+}
+
+/// The exports of a TypeScript module that don't exist at runtime once the
+/// types are stripped. Importers need to know about them to compile file by
+/// file, like TypeScript's `isolatedModules`.
+#[turbo_tasks::value]
+#[derive(Debug, Default)]
+pub struct TsExports {
+    /// Exported `const enum`s with the values of their members. Members with
+    /// values that can't be computed are missing.
+    pub const_enums: IndexMap<String, IndexMap<String, ConstEnumMember>>,
+    /// Exports that are only types, like interfaces and type aliases.
+    pub types: IndexSet<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl TsExportsVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        TsExports::default().cell()
+    }
+}
+
+/// Collects the type-only exports of a module. The module is parsed without
+/// transforms, as stripping the types removes them.
+#[turbo_tasks::function]
+pub async fn ts_exports(module: EcmascriptModuleAssetVc) -> Result<TsExportsVc> {
+    let module = module.await?;
+    Ok(source_ts_exports(module.source, Value::new(module.ty)))
+}
+
+/// The type-only exports only depend on the source, so they are shared between
+/// the contexts which include the same module, e.g. the client and the server.
+#[turbo_tasks::function]
+async fn source_ts_exports(
+    source: AssetVc,
+    ty: Value<EcmascriptModuleAssetType>,
+) -> Result<TsExportsVc> {
+    let ty = match ty.into_value() {
+        ty @ (EcmascriptModuleAssetType::Typescript
+        | EcmascriptModuleAssetType::TypescriptWithTypes) => ty,
+        EcmascriptModuleAssetType::Ecmascript
+        | EcmascriptModuleAssetType::TypescriptDeclaration => {
+            return Ok(TsExportsVc::empty());
+        }
+    };
+    let parsed = parse(
+        source,
+        Value::new(ty),
+        EcmascriptInputTransformsVc::cell(Vec::new()),
+    )
+    .await?;
+    let ParseResult::Ok { program: Program::Module(program), .. } = &*parsed else {
+        return Ok(TsExportsVc::empty());
+    };
+    Ok(collect_ts_exports(program).cell())
+}
+
+fn collect_ts_exports(program: &Module) -> TsExports {
+    let mut exports = TsExports::default();
+    let mut local_const_enums = IndexMap::new();
+    let mut local_types = HashSet::new();
+    let mut local_values = HashSet::new();
+    let mut exported_locals = Vec::new();
+    for item in program.body.iter() {
+        let (decl, exported) = match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => (&export.decl, true),
+            ModuleItem::Stmt(Stmt::Decl(decl)) => (decl, false),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export))
+                if export.src.is_none() && !export.type_only =>
+            {
+                for specifier in export.specifiers.iter() {
+                    if let ExportSpecifier::Named(specifier) = specifier {
+                        if specifier.is_type_only {
+                            continue;
+                        }
+                        let exported = specifier.exported.as_ref().unwrap_or(&specifier.orig);
+                        if let (ModuleExportName::Ident(orig), ModuleExportName::Ident(exported)) =
+                            (&specifier.orig, exported)
+                        {
+                            exported_locals.push((orig.sym.to_string(), exported.sym.to_string()));
+                        }
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        match decl {
+            Decl::TsEnum(decl) if decl.is_const => {
+                let name = decl.id.sym.to_string();
+                let members = const_enum_members(decl);
+                if exported {
+                    exports.const_enums.insert(name, members);
+                } else {
+                    local_const_enums.insert(name, members);
+                }
+            }
+            Decl::TsInterface(box decl) => {
+                let name = decl.id.sym.to_string();
+                if exported {
+                    exports.types.insert(name);
+                } else {
+                    local_types.insert(name);
+                }
+            }
+            Decl::TsTypeAlias(box decl) => {
+                let name = decl.id.sym.to_string();
+                if exported {
+                    exports.types.insert(name);
+                } else {
+                    local_types.insert(name);
+                }
+            }
+            Decl::Class(decl) if !decl.declare => {
+                local_values.insert(decl.ident.sym.to_string());
+            }
+            Decl::Fn(decl) if !decl.declare => {
+                local_values.insert(decl.ident.sym.to_string());
+            }
+            Decl::Var(decl) if !decl.declare => {
+                for declarator in decl.decls.iter() {
+                    if let Some(ident) = declarator.name.as_ident() {
+                        local_values.insert(ident.id.sym.to_string());
+                    }
+                }
+            }
+            Decl::TsEnum(decl) if !decl.declare => {
+                local_values.insert(decl.id.sym.to_string());
+            }
+            Decl::TsModule(box decl) if !decl.declare => {
+                if let Some(ident) = decl.id.as_ident() {
+                    local_values.insert(ident.sym.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    for (orig, exported) in exported_locals {
+        if let Some(members) = local_const_enums.get(&orig) {
+            exports.const_enums.insert(exported, members.clone());
+        } else if local_types.contains(&orig) && !local_values.contains(&orig) {
+            exports.types.insert(exported);
+        }
+    }
+    exports
+}
+
+#[derive(Clone)]
+enum ConstValue {
+    Number(f64),
+    String(String),
+}
+
+/// Computes the values of the members of a `const enum`, following the rules
+/// of TypeScript's constant enum expressions.
+fn const_enum_members(decl: &TsEnumDecl) -> IndexMap<String, ConstEnumMember> {
+    let mut values: IndexMap<String, ConstValue> = IndexMap::new();
+    let mut next = Some(0.0);
+    for member in decl.members.iter() {
+        let name = match &member.id {
+            TsEnumMemberId::Ident(ident) => ident.sym.to_string(),
+            TsEnumMemberId::Str(str) => str.value.to_string(),
+        };
+        let value = match &member.init {
+            Some(init) => eval_const_enum_expr(init, &decl.id.sym, &values),
+            None => next.map(ConstValue::Number),
+        };
+        next = match &value {
+            Some(ConstValue::Number(value)) => Some(value + 1.0),
+            _ => None,
+        };
+        if let Some(value) = value {
+            values.insert(name, value);
+        }
+    }
+    values
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let member = match value {
+                ConstValue::Number(value) if value.is_finite() => {
+                    ConstEnumMember::Number(format_number(value))
+                }
+                ConstValue::Number(_) => return None,
+                ConstValue::String(value) => ConstEnumMember::String(value),
+            };
+            Some((name, member))
+        })
+        .collect()
+}
+
+fn eval_const_enum_expr(
+    expr: &Expr,
+    enum_name: &str,
+    values: &IndexMap<String, ConstValue>,
+) -> Option<ConstValue> {
+    Some(match expr {
+        Expr::Lit(Lit::Num(num)) => ConstValue::Number(num.value),
+        Expr::Lit(Lit::Str(str)) => ConstValue::String(str.value.to_string()),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => {
+            ConstValue::String(tpl.quasis.first()?.cooked.as_ref()?.to_string())
+        }
+        Expr::Paren(paren) => eval_const_enum_expr(&paren.expr, enum_name, values)?,
+        Expr::Ident(ident) => values.get(&*ident.sym)?.clone(),
+        Expr::Member(MemberExpr {
+            obj: box Expr::Ident(obj),
+            prop: MemberProp::Ident(prop),
+            ..
+        }) if &*obj.sym == enum_name => values.get(&*prop.sym)?.clone(),
+        Expr::Unary(UnaryExpr { op, arg, .. }) => {
+            let ConstValue::Number(value) = eval_const_enum_expr(arg, enum_name, values)? else {
+                return None;
+            };
+            ConstValue::Number(match op {
+                UnaryOp::Minus => -value,
+                UnaryOp::Plus => value,
+                UnaryOp::Tilde => !to_int32(value) as f64,
+                _ => return None,
+            })
+        }
+        Expr::Bin(BinExpr {
+            op, left, right, ..
+        }) => {
+            let left = eval_const_enum_expr(left, enum_name, values)?;
+            let right = eval_const_enum_expr(right, enum_name, values)?;
+            match (left, right) {
+                (ConstValue::Number(left), ConstValue::Number(right)) => {
+                    ConstValue::Number(match op {
+                        BinaryOp::Add => left + right,
+                        BinaryOp::Sub => left - right,
+                        BinaryOp::Mul => left * right,
+                        BinaryOp::Div => left / right,
+                        BinaryOp::Mod => left % right,
+                        BinaryOp::Exp => left.powf(right),
+                        BinaryOp::BitOr => (to_int32(left) | to_int32(right)) as f64,
+                        BinaryOp::BitAnd => (to_int32(left) & to_int32(right)) as f64,
+                        BinaryOp::BitXor => (to_int32(left) ^ to_int32(right)) as f64,
+                        BinaryOp::LShift => {
+                            to_int32(left).wrapping_shl(to_int32(right) as u32 & 31) as f64
+                        }
+                        BinaryOp::RShift => {
+                            (to_int32(left) >> (to_int32(right) as u32 & 31)) as f64
+                        }
+                        BinaryOp::ZeroFillRShift => {
+                            ((to_int32(left) as u32) >> (to_int32(right) as u32 & 31)) as f64
+                        }
+                        _ => return None,
+                    })
+                }
+                (ConstValue::String(left), ConstValue::String(right)) if *op == BinaryOp::Add => {
+                    ConstValue::String(left + &right)
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Converts a number to a 32 bit integer like the bitwise operators of
+/// JavaScript do.
+fn to_int32(value: f64) -> i32 {
+    if !value.is_finite() {
+        return 0;
+    }
+    value.trunc().rem_euclid(4294967296.0) as u32 as i32
+}
+
+fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        // Both `0` and `-0` are formatted as `0`
+        "0".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use swc_core::ecma::{
+        ast::Program,
+        parser::{Syntax, TsConfig},
+    };
+
+    use super::{collect_ts_exports, ConstEnumMember, TsExports};
+    use crate::test_utils::with_program;
+
+    fn analyze(input: &str) -> TsExports {
+        with_program(input, Syntax::Typescript(TsConfig::default()), |program| {
+            let Program::Module(module) = program else {
+                panic!("the input is not a module");
+            };
+            collect_ts_exports(module)
+        })
+    }
+
+    fn numbers(members: &[(&str, &str)]) -> IndexMap<String, ConstEnumMember> {
+        members
+            .iter()
+            .map(|(name, value)| (name.to_string(), ConstEnumMember::Number(value.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn computes_const_enum_members() {
+        let exports = analyze(
+            "export const enum Flags { None, A = 1 << 0, B = 1 << 1, AB = A | B, Neg = -B, Next }
+export const enum Names { A = 'a', B = `b`, AB = A + Names.B, Dynamic = 'x'.length }
+export enum Regular { A }",
+        );
+        assert_eq!(
+            exports.const_enums["Flags"],
+            numbers(&[
+                ("None", "0"),
+                ("A", "1"),
+                ("B", "2"),
+                ("AB", "3"),
+                ("Neg", "-2"),
+                ("Next", "-1"),
+            ])
+        );
+        let names = &exports.const_enums["Names"];
+        assert_eq!(names["AB"], ConstEnumMember::String("ab".to_string()));
+        assert_eq!(names["B"], ConstEnumMember::String("b".to_string()));
+        assert!(!names.contains_key("Dynamic"));
+        assert!(!exports.const_enums.contains_key("Regular"));
+    }
+
+    #[test]
+    fn collects_type_exports() {
+        let exports = analyze(
+            "export interface Props {}
+type Alias = string;
+interface Merged {}
+const Merged = 1;
+const enum Local { A = 2 }
+export { Alias, Alias as Renamed, Merged, Local as Exported };
+export type { Props as TypeOnly };",
+        );
+        assert_eq!(
+            exports.types.iter().collect::<Vec<_>>(),
+            ["Props", "Alias", "Renamed"]
+        );
+        assert_eq!(exports.const_enums["Exported"], numbers(&[("A", "2")]));
+    }
+}
@@ -1,4 +1,5 @@
 use turbo_tasks::{ValueToString, ValueToStringVc};
+pub mod exports;
 pub mod resolve;
 
 use anyhow::Result;
@@ -30,6 +30,8 @@ use turbopack_core::{
     source_asset::SourceAssetVc,
 };
 
+use crate::transform::{DecoratorsKind, DecoratorsOptions, DecoratorsOptionsVc};
+
 #[turbo_tasks::value(shared)]
 pub struct TsConfigIssue {
     pub severity: IssueSeverityVc,
@@ -225,6 +227,45 @@ pub async fn tsconfig_resolve_options(
     .cell())
 }
 
+/// Returns the decorators options of a tsconfig.json. TypeScript's legacy
+/// decorators are enabled by `compilerOptions.experimentalDecorators`,
+/// otherwise the TC39 proposal is used.
+#[turbo_tasks::function]
+pub async fn tsconfig_decorators_options(
+    tsconfig: FileSystemPathVc,
+) -> Result<DecoratorsOptionsVc> {
+    let configs = read_tsconfigs(
+        tsconfig.read(),
+        SourceAssetVc::new(tsconfig).into(),
+        node_cjs_resolve_options(tsconfig.root()),
+    )
+    .await?;
+
+    let experimental_decorators = read_from_tsconfigs(&configs, |json, _| {
+        json["compilerOptions"]["experimentalDecorators"].as_bool()
+    })
+    .await?;
+    let emit_decorators_metadata = read_from_tsconfigs(&configs, |json, _| {
+        json["compilerOptions"]["emitDecoratorMetadata"].as_bool()
+    })
+    .await?;
+    let use_define_for_class_fields = read_from_tsconfigs(&configs, |json, _| {
+        json["compilerOptions"]["useDefineForClassFields"].as_bool()
+    })
+    .await?;
+
+    Ok(DecoratorsOptions {
+        kind: if experimental_decorators.unwrap_or(false) {
+            DecoratorsKind::Legacy
+        } else {
+            DecoratorsKind::Tc39
+        },
+        emit_decorators_metadata: emit_decorators_metadata.unwrap_or(false),
+        use_define_for_class_fields: use_define_for_class_fields.unwrap_or(false),
+    }
+    .cell())
+}
+
 #[turbo_tasks::function]
 pub fn tsconfig() -> StringsVc {
     StringsVc::cell(vec![
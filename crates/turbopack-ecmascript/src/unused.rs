@@ -0,0 +1,229 @@
+//! An opt-in analysis of unused code in a project. It reports exports that are
+//! not imported by any module, imports of exports that don't exist and modules
+//! that are not reachable from any entry, as issues.
+
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetsVc},
+    issue::{analyze::AnalyzeIssue, IssueSeverity},
+    reference::all_assets,
+};
+
+use crate::{
+    chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptExports},
+    references::esm::{
+        base::ReferencedAsset, export::expand_star_exports, ExportUsage, ExportUsageGraphVc,
+    },
+    EcmascriptModuleAssetVc,
+};
+
+/// Configures which unused code is reported and with which severity. `None`
+/// disables the respective issues.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct UnusedCodeOptions {
+    /// Exports that are not imported by any module.
+    pub unused_exports: Option<IssueSeverity>,
+    /// Imports of named exports that the imported module doesn't have.
+    pub missing_exports: Option<IssueSeverity>,
+    /// Source files in the project directory that are not reachable from any
+    /// entry.
+    pub unreachable_modules: Option<IssueSeverity>,
+}
+
+/// The extensions of the source files that are expected to be reachable from
+/// an entry.
+const MODULE_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"];
+
+/// Emits issues about unused code in the module graph of the entries. Only
+/// modules in the project directory outside of `node_modules` are reported.
+#[turbo_tasks::function]
+pub async fn emit_unused_code_issues(
+    entries: AssetsVc,
+    project_dir: FileSystemPathVc,
+    options: UnusedCodeOptionsVc,
+) -> Result<CompletionVc> {
+    let options = *options.await?;
+    let project_dir_value = project_dir.await?;
+    let usage_graph = ExportUsageGraphVc::new(entries);
+
+    let mut visited = HashSet::new();
+    let mut reachable = HashSet::new();
+    for entry in entries.await?.iter() {
+        for asset in all_assets(*entry).await?.iter() {
+            if !visited.insert(*asset) {
+                continue;
+            }
+            let path = asset.path();
+            let path_value = path.await?;
+            if !path_value.is_inside(&project_dir_value) || is_in_node_modules(&path_value.path) {
+                continue;
+            }
+            reachable.insert(path_value.path.clone());
+
+            let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? else {
+                continue;
+            };
+            if let Some(severity) = options.unused_exports {
+                emit_unused_exports(module, usage_graph, severity).await?;
+            }
+            if let Some(severity) = options.missing_exports {
+                emit_missing_exports(module, severity).await?;
+            }
+        }
+    }
+
+    if let Some(severity) = options.unreachable_modules {
+        let mut queue = vec![project_dir];
+        while let Some(dir) = queue.pop() {
+            let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+                continue;
+            };
+            for (name, entry) in entries.iter() {
+                match entry {
+                    DirectoryEntry::Directory(path)
+                        if name != "node_modules" && !name.starts_with('.') =>
+                    {
+                        queue.push(*path);
+                    }
+                    DirectoryEntry::File(path) if is_module_file(name) => {
+                        if reachable.contains(&path.await?.path) {
+                            continue;
+                        }
+                        emit_issue(
+                            *path,
+                            severity,
+                            "module is unreachable",
+                            format!(
+                                "{} is not imported by any entry, directly or indirectly.",
+                                path.to_string().await?
+                            ),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(CompletionVc::new())
+}
+
+async fn emit_unused_exports(
+    module: EcmascriptModuleAssetVc,
+    usage_graph: ExportUsageGraphVc,
+    severity: IssueSeverity,
+) -> Result<()> {
+    let EcmascriptExports::EsmExports(exports) = &*module.get_exports().await? else {
+        return Ok(());
+    };
+    let usage = usage_graph.usage(module.into()).await?;
+    if let ExportUsage::All = &*usage {
+        return Ok(());
+    }
+    for name in exports.await?.exports.keys() {
+        if usage.is_used(name) {
+            continue;
+        }
+        emit_issue(
+            module.path(),
+            severity,
+            "unused export",
+            format!("The export `{name}` is not imported by any module."),
+        );
+    }
+    Ok(())
+}
+
+async fn emit_missing_exports(
+    module: EcmascriptModuleAssetVc,
+    severity: IssueSeverity,
+) -> Result<()> {
+    let analysis = module.analyze().await?;
+    for (reference, usage) in analysis.import_usages.await?.iter() {
+        let ExportUsage::Names(names) = usage else {
+            continue;
+        };
+        let ReferencedAsset::Some(imported) = &*reference.get_referenced_asset().await? else {
+            continue;
+        };
+        let Some(available) = export_names(*imported).await? else {
+            continue;
+        };
+        for name in names.iter() {
+            if available.contains(name) {
+                continue;
+            }
+            let list = if available.is_empty() {
+                "It has no exports.".to_string()
+            } else {
+                format!(
+                    "It exports {}.",
+                    available
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            emit_issue(
+                module.path(),
+                severity,
+                "imported export doesn't exist",
+                format!(
+                    "`{name}` is imported from {}, but the module doesn't export it. {list}",
+                    imported.path().to_string().await?
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The names exported by a module, including the names of `export *`. `None`
+/// when the exports can't be statically known.
+async fn export_names(module: EcmascriptChunkPlaceableVc) -> Result<Option<BTreeSet<String>>> {
+    let EcmascriptExports::EsmExports(exports) = &*module.get_exports().await? else {
+        return Ok(None);
+    };
+    let mut names: BTreeSet<String> = exports.await?.exports.keys().cloned().collect();
+    let expanded = expand_star_exports(module).await?;
+    if expanded.has_cjs_exports {
+        return Ok(None);
+    }
+    names.extend(expanded.star_exports.iter().cloned());
+    Ok(Some(names))
+}
+
+fn emit_issue(path: FileSystemPathVc, severity: IssueSeverity, title: &str, message: String) {
+    AnalyzeIssue {
+        code: None,
+        category: StringVc::cell("unused code".to_string()),
+        message: StringVc::cell(message),
+        path,
+        severity: severity.into(),
+        source: None,
+        title: StringVc::cell(title.to_string()),
+    }
+    .cell()
+    .as_issue()
+    .emit();
+}
+
+fn is_in_node_modules(path: &str) -> bool {
+    path.split('/').any(|segment| segment == "node_modules")
+}
+
+fn is_module_file(name: &str) -> bool {
+    if name.ends_with(".d.ts") || name.ends_with(".d.mts") || name.ends_with(".d.cts") {
+        return false;
+    }
+    matches!(
+        name.rsplit_once('.'),
+        Some((_, extension)) if MODULE_EXTENSIONS.contains(&extension)
+    )
+}
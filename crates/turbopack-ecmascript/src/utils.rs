@@ -7,7 +7,7 @@ use std::{
 use pin_project_lite::pin_project;
 use swc_core::{
     common::DUMMY_SP,
-    ecma::ast::{Expr, Lit, Str},
+    ecma::ast::{Expr, KeyValueProp, Lit, ObjectLit, Prop, PropName, PropOrSpread, Str},
 };
 use turbopack_core::{chunk::ModuleId, resolve::pattern::Pattern};
 
@@ -64,6 +64,22 @@ pub fn module_id_to_lit(module_id: &ModuleId) -> Expr {
     })
 }
 
+/// Creates an object literal with string keys.
+pub fn object_lit(entries: Vec<(String, Expr)>) -> Expr {
+    Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props: entries
+            .into_iter()
+            .map(|(key, value)| {
+                PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                    key: PropName::Str(key.as_str().into()),
+                    value: box value,
+                }))
+            })
+            .collect(),
+    })
+}
+
 pub fn stringify_module_id(id: &ModuleId) -> String {
     match id {
         ModuleId::Number(n) => stringify_number(*n),
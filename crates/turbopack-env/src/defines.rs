@@ -0,0 +1,23 @@
+use anyhow::Result;
+use turbo_tasks::TryJoinIterExt;
+use turbo_tasks_env::EnvVc;
+use turbopack_ecmascript::{CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc};
+
+/// Defines `process.env.KEY` for every key in `keys`, as its value in `env` or
+/// `undefined` when it's not set.
+///
+/// Keys are read one by one, so the defines only change when one of `keys`
+/// changes, and not when other variables in a dotenv file are edited.
+#[turbo_tasks::function]
+pub async fn process_env_defines(env: EnvVc, keys: Vec<String>) -> Result<CompileTimeDefinesVc> {
+    let values = keys.iter().map(|key| env.read(key)).try_join().await?;
+    let mut defines = CompileTimeDefines::default();
+    for (key, value) in keys.iter().zip(values) {
+        let value = match &*value {
+            Some(value) => CompileTimeDefineValue::String(value.clone()),
+            None => CompileTimeDefineValue::Undefined,
+        };
+        defines.insert(&format!("process.env.{key}"), value);
+    }
+    Ok(defines.cell())
+}
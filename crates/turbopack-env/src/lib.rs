@@ -13,11 +13,13 @@
 #![feature(min_specialization)]
 
 mod asset;
+mod defines;
 mod embeddable;
 mod issue;
 mod try_env;
 
 pub use asset::{ProcessEnvAsset, ProcessEnvAssetVc};
+pub use defines::process_env_defines;
 pub use embeddable::EmbeddableProcessEnvVc;
 pub use issue::{ProcessEnvIssue, ProcessEnvIssueVc};
 pub use try_env::TryDotenvProcessEnvVc;
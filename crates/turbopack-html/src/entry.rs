@@ -0,0 +1,325 @@
+use std::{collections::HashSet, fmt::Write, ops::Range};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{File, FileSystemPath, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{
+        ChunkGroupVc, ChunkReferenceVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, ChunksVc, RuntimeAssetReferenceVc,
+    },
+    reference::{AssetReference, AssetReferencesVc, SingleAssetReferenceVc},
+};
+use turbopack_css::embed::{CssEmbed, CssEmbeddable, CssEmbeddableVc};
+use turbopack_ecmascript::{
+    chunk::{EcmascriptChunkPlaceablesVc, EcmascriptChunkVc},
+    EcmascriptModuleAssetVc,
+};
+
+use crate::{parse::HtmlReference, HtmlModuleAssetVc, HtmlReferenceKind};
+
+/// The page of an HTML entry. It's the HTML of the [HtmlModuleAsset] with the
+/// URLs of the emitted assets.
+///
+/// A `<script>` is replaced with the scripts of the chunks of its entry, and
+/// a stylesheet `<link>` with the stylesheets of its chunks. Stylesheets keep
+/// the other attributes of the original element, scripts are loaded as
+/// classic scripts or ES modules as their chunks need. The `src` and `href` of
+/// images and icons are replaced with the URLs of the emitted files. Elements
+/// whose reference can't be resolved are kept as they are.
+///
+/// [HtmlModuleAsset]: crate::HtmlModuleAsset
+#[turbo_tasks::value]
+pub struct HtmlEntryAsset {
+    path: FileSystemPathVc,
+    module: HtmlModuleAssetVc,
+    context: ChunkingContextVc,
+    runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+}
+
+/// What the reference of an element was emitted as.
+#[turbo_tasks::value]
+enum ReferencedOutput {
+    /// The chunks of a script or stylesheet.
+    Chunks(ChunksVc),
+    /// A file referred to by its URL, e.g. an image.
+    Asset(AssetVc),
+    /// The reference can't be resolved or emitted.
+    None,
+}
+
+#[turbo_tasks::value(transparent)]
+struct ReferencedOutputs(Vec<ReferencedOutputVc>);
+
+#[turbo_tasks::value_impl]
+impl HtmlEntryAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        module: HtmlModuleAssetVc,
+        context: ChunkingContextVc,
+        runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+    ) -> Self {
+        HtmlEntryAsset {
+            path,
+            module,
+            context,
+            runtime_entries,
+        }
+        .cell()
+    }
+
+    /// The outputs of the references of the document, in the same order.
+    #[turbo_tasks::function]
+    async fn referenced_outputs(self) -> Result<ReferencedOutputsVc> {
+        let this = self.await?;
+        let parsed = this.module.parse().await?;
+        let references = this.module.html_references().await?;
+        // The runtime entries are evaluated once, with the first script.
+        let mut runtime_entries = this.runtime_entries;
+        let mut outputs = Vec::with_capacity(references.len());
+        for (reference, asset_reference) in parsed.references.iter().zip(references.iter()) {
+            let asset = asset_reference
+                .resolve_reference()
+                .primary_assets()
+                .await?
+                .first()
+                .copied();
+            let Some(asset) = asset else {
+                outputs.push(ReferencedOutput::None.cell());
+                continue;
+            };
+            let output = match reference.kind {
+                HtmlReferenceKind::Script | HtmlReferenceKind::Stylesheet => {
+                    let chunk = if let Some(ecmascript) =
+                        EcmascriptModuleAssetVc::resolve_from(asset).await?
+                    {
+                        Some(ecmascript.as_evaluated_chunk(this.context, runtime_entries.take()))
+                    } else {
+                        ChunkableAssetVc::resolve_from(asset)
+                            .await?
+                            .map(|chunkable| chunkable.as_chunk(this.context))
+                    };
+                    match chunk {
+                        Some(chunk) => {
+                            ReferencedOutput::Chunks(ChunkGroupVc::from_chunk(chunk).chunks())
+                        }
+                        None => ReferencedOutput::None,
+                    }
+                }
+                HtmlReferenceKind::Url => match CssEmbeddableVc::resolve_from(asset).await? {
+                    Some(embeddable) => ReferencedOutput::Asset(
+                        embeddable.as_css_embed(this.context).embeddable_asset(),
+                    ),
+                    None => ReferencedOutput::None,
+                },
+            };
+            outputs.push(output.cell());
+        }
+        Ok(ReferencedOutputsVc::cell(outputs))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for HtmlEntryAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: HtmlEntryAssetVc) -> Result<AssetContentVc> {
+        let this = self_vc.await?;
+        let parsed = this.module.parse().await?;
+        let outputs = self_vc.referenced_outputs().await?;
+        let output_root = this.context.output_root().await?;
+        let base_path = this.context.base_path().await?;
+        let urls = Urls {
+            output_root: &output_root,
+            base_path: &base_path,
+        };
+
+        let html = &parsed.html;
+        let mut result = String::with_capacity(html.len());
+        let mut pos = 0;
+        // Chunks and runtimes which are shared by entries are only loaded once.
+        let mut loaded = HashSet::new();
+        for (reference, &output) in parsed.references.iter().zip(outputs.iter()) {
+            let (range, replacement) = match &*output.await? {
+                ReferencedOutput::None => continue,
+                ReferencedOutput::Asset(asset) => {
+                    let Some(url) = urls.get(&*asset.path().await?) else {
+                        continue;
+                    };
+                    (reference.value.clone(), quote_attribute(&url))
+                }
+                ReferencedOutput::Chunks(chunks) => (
+                    reference.element.clone(),
+                    chunk_tags(html, reference, *chunks, &urls, &mut loaded).await?,
+                ),
+            };
+            result.push_str(&html[pos..range.start]);
+            result.push_str(&replacement);
+            pos = range.end;
+        }
+        result.push_str(&html[pos..]);
+        Ok(File::from(result).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn references(self_vc: HtmlEntryAssetVc) -> Result<AssetReferencesVc> {
+        let mut references = Vec::new();
+        for &output in self_vc.referenced_outputs().await?.iter() {
+            match &*output.await? {
+                ReferencedOutput::Chunks(chunks) => {
+                    for &chunk in (*chunks).await?.iter() {
+                        references.push(ChunkReferenceVc::new(chunk).into());
+                    }
+                }
+                ReferencedOutput::Asset(asset) => references.push(
+                    SingleAssetReferenceVc::new(
+                        *asset,
+                        StringVc::cell(format!("html(url) {}", asset.path().await?)),
+                    )
+                    .into(),
+                ),
+                ReferencedOutput::None => {}
+            }
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for HtmlEntryAsset {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "html entry {}",
+            self.path.to_string().await?
+        )))
+    }
+}
+
+/// The URLs of the assets in the output root.
+struct Urls<'a> {
+    output_root: &'a FileSystemPath,
+    base_path: &'a str,
+}
+
+impl Urls<'_> {
+    fn get(&self, path: &FileSystemPath) -> Option<String> {
+        let relative_path = self.output_root.get_path_to(path)?;
+        Some(format!("{}/{relative_path}", self.base_path))
+    }
+}
+
+/// A tag which loads the chunk at `url`.
+fn script_tag(url: &str, is_es_module: bool) -> String {
+    let ty = if is_es_module { " type=\"module\"" } else { "" };
+    format!("<script{ty} src={}></script>", quote_attribute(url))
+}
+
+/// The tags which replace the element of a script or stylesheet: the
+/// runtimes the chunks need, followed by the chunks. CSS chunks of a
+/// stylesheet reuse its start tag with the URL of the chunk. Scripts get fresh
+/// tags, as the attributes of the original element, like `type="module"`,
+/// don't apply to every chunk.
+async fn chunk_tags(
+    html: &str,
+    reference: &HtmlReference,
+    chunks: ChunksVc,
+    urls: &Urls<'_>,
+    loaded: &mut HashSet<String>,
+) -> Result<String> {
+    let start_tag = &html[reference.start_tag.clone()];
+    let value = reference.value.start - reference.start_tag.start
+        ..reference.value.end - reference.start_tag.start;
+    let with_url = |url: &str| replace_range(start_tag, value.clone(), &quote_attribute(url));
+
+    let mut runtime_tags = String::new();
+    let mut tags = String::new();
+    for &chunk in chunks.await?.iter() {
+        let Some(url) = urls.get(&*chunk.path().await?) else {
+            continue;
+        };
+        for reference in chunk.references().await?.iter() {
+            let Some(runtime) = RuntimeAssetReferenceVc::resolve_from(reference).await? else {
+                continue;
+            };
+            let Some(runtime_url) = urls.get(&*runtime.runtime().path().await?) else {
+                continue;
+            };
+            if loaded.insert(runtime_url.clone()) {
+                write!(
+                    runtime_tags,
+                    "<script src={}></script>",
+                    quote_attribute(&runtime_url)
+                )?;
+            }
+        }
+        if !loaded.insert(url.clone()) {
+            continue;
+        }
+        if url.ends_with(".css") {
+            if reference.kind == HtmlReferenceKind::Stylesheet {
+                tags.push_str(&with_url(&url));
+            } else {
+                write!(
+                    tags,
+                    "<link rel=\"stylesheet\" href={}>",
+                    quote_attribute(&url)
+                )?;
+            }
+        } else if url.ends_with(".js") {
+            let is_es_module = match EcmascriptChunkVc::resolve_from(chunk).await? {
+                Some(chunk) => *chunk.is_es_module().await?,
+                None => false,
+            };
+            tags.push_str(&script_tag(&url, is_es_module));
+        }
+    }
+    Ok(runtime_tags + &tags)
+}
+
+fn replace_range(string: &str, range: Range<usize>, replacement: &str) -> String {
+    let mut result = String::with_capacity(string.len() + replacement.len());
+    result.push_str(&string[..range.start]);
+    result.push_str(replacement);
+    result.push_str(&string[range.end..]);
+    result
+}
+
+/// Quotes a value for an attribute.
+fn quote_attribute(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('&', "&amp;")
+            .replace('"', "&quot;")
+            .replace('<', "&lt;")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote_attribute, script_tag};
+
+    #[test]
+    fn script_tags_only_mark_es_modules() {
+        assert_eq!(
+            script_tag("/_next/chunk.js", false),
+            r#"<script src="/_next/chunk.js"></script>"#
+        );
+        assert_eq!(
+            script_tag("/_next/chunk.js", true),
+            r#"<script type="module" src="/_next/chunk.js"></script>"#
+        );
+    }
+
+    #[test]
+    fn quotes_attributes() {
+        assert_eq!(quote_attribute(r#"a"b&c<d"#), r#""a&quot;b&amp;c&lt;d""#);
+    }
+}
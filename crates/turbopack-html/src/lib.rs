@@ -0,0 +1,250 @@
+//! HTML entry support for turbopack.
+//!
+//! An HTML module refers to the scripts, stylesheets, images and icons of a
+//! page. When it's used as an entry, its scripts and stylesheets are chunked,
+//! the other files are emitted, and the HTML is emitted with the URLs of the
+//! emitted assets. That allows building plain HTML projects without a
+//! framework.
+
+#![feature(min_specialization)]
+
+mod entry;
+mod parse;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    chunk::ChunkingContextVc,
+    context::AssetContextVc,
+    reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
+    reference_type::{EntryReferenceSubType, ReferenceType, UrlReferenceSubType},
+    resolve::{
+        find_context_file, handle_resolve_error,
+        origin::{PlainResolveOriginVc, ResolveOrigin, ResolveOriginVc},
+        package_json,
+        parse::RequestVc,
+        FindContextFileResult, ResolveResultVc,
+    },
+};
+use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, resolve::url_resolve};
+
+use self::parse::{find_references, HtmlReference};
+pub use self::{
+    entry::{HtmlEntryAsset, HtmlEntryAssetVc},
+    parse::HtmlReferenceKind,
+};
+
+/// An HTML document in the module graph. Its references are the local files
+/// referred to by its elements.
+#[turbo_tasks::value]
+#[derive(Clone)]
+pub struct HtmlModuleAsset {
+    pub source: AssetVc,
+    pub context: AssetContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl HtmlModuleAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc, context: AssetContextVc) -> Self {
+        Self::cell(HtmlModuleAsset { source, context })
+    }
+
+    /// The page at `path` which loads the chunks of the scripts and
+    /// stylesheets of this document. The `runtime_entries` are evaluated
+    /// before the first script.
+    #[turbo_tasks::function]
+    pub fn as_entry_asset(
+        self,
+        context: ChunkingContextVc,
+        path: FileSystemPathVc,
+        runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+    ) -> HtmlEntryAssetVc {
+        HtmlEntryAssetVc::new(path, self, context, runtime_entries)
+    }
+
+    #[turbo_tasks::function]
+    pub(crate) async fn parse(self) -> Result<ParsedHtmlVc> {
+        let this = self.await?;
+        let html = match &*this.source.content().await? {
+            AssetContent::File(file) => match &*file.await? {
+                FileContent::Content(file) => file.content().to_str()?.into_owned(),
+                FileContent::NotFound => String::new(),
+            },
+            AssetContent::Redirect { .. } => String::new(),
+        };
+        let references = find_references(&html);
+        Ok(ParsedHtml { html, references }.cell())
+    }
+
+    /// The asset references of [ParsedHtml::references], in the same order.
+    #[turbo_tasks::function]
+    pub(crate) async fn html_references(self) -> Result<HtmlAssetReferencesVc> {
+        let origin = self.as_resolve_origin();
+        let mut references = Vec::new();
+        for reference in self.parse().await?.references.iter() {
+            let (origin, request) = match reference.request.trim_start().strip_prefix('/') {
+                // `/src/main.ts` is served from the root of the project.
+                Some(path) => (self.project_root_origin(), format!("./{path}")),
+                None => (origin, reference.request.clone()),
+            };
+            references.push(HtmlAssetReferenceVc::new(
+                origin,
+                RequestVc::parse(Value::new(request.into())),
+                reference.kind,
+            ));
+        }
+        Ok(HtmlAssetReferencesVc::cell(references))
+    }
+
+    /// The origin of requests which start at the root of the project, i. e.
+    /// the directory of the closest `package.json`, or the directory of the
+    /// document when there's none.
+    #[turbo_tasks::function]
+    async fn project_root_origin(self) -> Result<ResolveOriginVc> {
+        let this = self.await?;
+        let path = this.source.path();
+        Ok(
+            match &*find_context_file(path.parent(), package_json()).await? {
+                FindContextFileResult::Found(package_json, _) => {
+                    PlainResolveOriginVc::new(this.context, *package_json).into()
+                }
+                FindContextFileResult::NotFound(_) => self.as_resolve_origin(),
+            },
+        )
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for HtmlModuleAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    async fn references(self_vc: HtmlModuleAssetVc) -> Result<AssetReferencesVc> {
+        Ok(AssetReferencesVc::cell(
+            self_vc
+                .html_references()
+                .await?
+                .iter()
+                .map(|&reference| reference.into())
+                .collect(),
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ResolveOrigin for HtmlModuleAsset {
+    #[turbo_tasks::function]
+    fn origin_path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> AssetContextVc {
+        self.context
+    }
+}
+
+#[turbo_tasks::value(serialization = "none")]
+pub(crate) struct ParsedHtml {
+    pub html: String,
+    #[turbo_tasks(trace_ignore)]
+    pub references: Vec<HtmlReference>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub(crate) struct HtmlAssetReferences(Vec<HtmlAssetReferenceVc>);
+
+/// A reference from an element of an HTML document, e.g. the `src` of a
+/// `<script>`.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct HtmlAssetReference {
+    pub origin: ResolveOriginVc,
+    pub request: RequestVc,
+    pub kind: HtmlReferenceKind,
+}
+
+#[turbo_tasks::value_impl]
+impl HtmlAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(origin: ResolveOriginVc, request: RequestVc, kind: HtmlReferenceKind) -> Self {
+        Self::cell(HtmlAssetReference {
+            origin,
+            request,
+            kind,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for HtmlAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        match self.kind {
+            HtmlReferenceKind::Script | HtmlReferenceKind::Stylesheet => {
+                entry_resolve(self.origin, self.request)
+            }
+            HtmlReferenceKind::Url => url_resolve(
+                self.origin,
+                self.request,
+                Value::new(UrlReferenceSubType::HtmlUrl),
+            ),
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for HtmlAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        let kind = match self.kind {
+            HtmlReferenceKind::Script => "script",
+            HtmlReferenceKind::Stylesheet => "stylesheet",
+            HtmlReferenceKind::Url => "url",
+        };
+        Ok(StringVc::cell(format!(
+            "html {kind} {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+/// Resolves a script or stylesheet of a page as an entry. Like URLs in HTML,
+/// `main.js` is relative to the document, but it falls back to a module
+/// request when there's no such file.
+#[turbo_tasks::function]
+async fn entry_resolve(origin: ResolveOriginVc, request: RequestVc) -> Result<ResolveResultVc> {
+    let ty = Value::new(ReferenceType::Entry(EntryReferenceSubType::Web));
+    let options = origin.resolve_options(ty.clone());
+    let rel_request = request.as_relative();
+    let rel_result = origin.resolve_asset(rel_request, options, ty.clone());
+    let result = if *rel_result.is_unresolveable().await? && rel_request.resolve().await? != request
+    {
+        origin
+            .resolve_asset(request, options, ty.clone())
+            .add_references(rel_result.await?.get_references().clone())
+    } else {
+        rel_result
+    };
+    handle_resolve_error(result, ty, origin, request, options).await
+}
+
+pub fn register() {
+    turbo_tasks::register();
+    turbo_tasks_fs::register();
+    turbopack_core::register();
+    turbopack_css::register();
+    turbopack_ecmascript::register();
+    include!(concat!(env!("OUT_DIR"), "/register.rs"));
+}
@@ -0,0 +1,339 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use turbo_tasks::trace::TraceRawVcs;
+
+/// What an element of an HTML document refers to.
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize, TraceRawVcs,
+)]
+pub enum HtmlReferenceKind {
+    /// `<script src="...">`, which is chunked as an entry of the page.
+    Script,
+    /// `<link rel="stylesheet" href="...">`, which is chunked as an entry of
+    /// the page.
+    Stylesheet,
+    /// `<img src="...">` and `<link href="...">` of icons and manifests, which
+    /// are emitted as they are.
+    Url,
+}
+
+/// A reference from an element of an HTML document to another file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HtmlReference {
+    pub kind: HtmlReferenceKind,
+    /// The value of the attribute, with character references decoded.
+    pub request: String,
+    /// The byte range of the element. For scripts, it includes the content and
+    /// the end tag.
+    pub element: Range<usize>,
+    /// The byte range of the start tag.
+    pub start_tag: Range<usize>,
+    /// The byte range of the attribute value, including its quotes.
+    pub value: Range<usize>,
+}
+
+/// The `rel` values of `<link>` elements whose `href` is emitted as a URL.
+const URL_LINK_RELS: [&str; 4] = ["icon", "apple-touch-icon", "mask-icon", "manifest"];
+
+/// Elements whose content is text, which must not be scanned for tags.
+const RAW_TEXT_ELEMENTS: [&str; 5] = ["script", "style", "textarea", "title", "noscript"];
+
+struct Attribute {
+    name: String,
+    /// The decoded value and the byte range of the raw value, including its
+    /// quotes.
+    value: Option<(String, Range<usize>)>,
+}
+
+/// Finds the references to local files in an HTML document, in document
+/// order.
+///
+/// This is a tolerant scanner for the start tags of an HTML document, not a
+/// spec compliant parser. It skips comments and the content of raw text
+/// elements like `<script>`, which is enough to find the references of
+/// typical entry pages. References to URLs with a scheme, like `https:` or
+/// `data:`, and to other hosts, like `//cdn.example.com/`, are left to the
+/// browser.
+pub fn find_references(html: &str) -> Vec<HtmlReference> {
+    let bytes = html.as_bytes();
+    let mut references = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = html[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &html[start..];
+        if rest.starts_with("<!--") {
+            pos = match rest[4..].find("-->") {
+                Some(end) => start + 4 + end + 3,
+                None => html.len(),
+            };
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") || rest.starts_with("</") {
+            pos = match rest.find('>') {
+                Some(end) => start + end + 1,
+                None => html.len(),
+            };
+            continue;
+        }
+
+        let name_start = start + 1;
+        let mut name_end = name_start;
+        while name_end < bytes.len()
+            && (bytes[name_end].is_ascii_alphanumeric() || bytes[name_end] == b'-')
+        {
+            name_end += 1;
+        }
+        if name_end == name_start {
+            pos = name_start;
+            continue;
+        }
+        let name = html[name_start..name_end].to_ascii_lowercase();
+
+        let Some((attributes, tag_end)) = parse_attributes(html, name_end) else {
+            // An unterminated start tag ends the document.
+            break;
+        };
+
+        let mut element_end = tag_end;
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            element_end = find_end_tag(html, tag_end, &name).unwrap_or(html.len());
+        }
+        pos = element_end;
+
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.name == name)
+                .and_then(|attribute| attribute.value.as_ref())
+        };
+        let (kind, value) = match name.as_str() {
+            "script" => (HtmlReferenceKind::Script, attribute("src")),
+            "img" => (HtmlReferenceKind::Url, attribute("src")),
+            "link" => {
+                let rel = attribute("rel")
+                    .map(|(rel, _)| rel.to_ascii_lowercase())
+                    .unwrap_or_default();
+                let mut rels = rel.split_ascii_whitespace();
+                if rels.clone().any(|rel| rel == "stylesheet") {
+                    (HtmlReferenceKind::Stylesheet, attribute("href"))
+                } else if rels.any(|rel| URL_LINK_RELS.contains(&rel)) {
+                    (HtmlReferenceKind::Url, attribute("href"))
+                } else {
+                    continue;
+                }
+            }
+            _ => continue,
+        };
+        let Some((request, value)) = value else {
+            continue;
+        };
+        if !is_local_request(request) {
+            continue;
+        }
+        references.push(HtmlReference {
+            kind,
+            request: request.clone(),
+            element: start..element_end,
+            start_tag: start..tag_end,
+            value: value.clone(),
+        });
+    }
+    references
+}
+
+/// Parses the attributes of a start tag, starting after the tag name. Returns
+/// the attributes and the end of the start tag, or None when the tag isn't
+/// terminated.
+fn parse_attributes(html: &str, mut pos: usize) -> Option<(Vec<Attribute>, usize)> {
+    let bytes = html.as_bytes();
+    let mut attributes = Vec::new();
+    loop {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'/') {
+            pos += 1;
+        }
+        if *bytes.get(pos)? == b'>' {
+            return Some((attributes, pos + 1));
+        }
+
+        let name_start = pos;
+        while pos < bytes.len()
+            && !bytes[pos].is_ascii_whitespace()
+            && !matches!(bytes[pos], b'=' | b'>' | b'/')
+        {
+            pos += 1;
+        }
+        let name = html[name_start..pos].to_ascii_lowercase();
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if bytes.get(pos) != Some(&b'=') {
+            attributes.push(Attribute { name, value: None });
+            continue;
+        }
+        pos += 1;
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+
+        let value_start = pos;
+        let value = match bytes.get(pos)? {
+            quote @ (b'"' | b'\'') => {
+                let end = pos + 1 + html[pos + 1..].find(*quote as char)?;
+                pos = end + 1;
+                &html[value_start + 1..end]
+            }
+            _ => {
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'>' {
+                    pos += 1;
+                }
+                &html[value_start..pos]
+            }
+        };
+        attributes.push(Attribute {
+            name,
+            value: Some((decode_character_references(value), value_start..pos)),
+        });
+    }
+}
+
+/// Returns the end of the end tag of a raw text element, whose content starts
+/// at `pos`.
+fn find_end_tag(html: &str, pos: usize, name: &str) -> Option<usize> {
+    let lowercase = html[pos..].to_ascii_lowercase();
+    let end_tag = format!("</{name}");
+    let mut search = 0;
+    while let Some(offset) = lowercase[search..].find(&end_tag) {
+        let end_tag_start = search + offset;
+        let after = end_tag_start + end_tag.len();
+        // `</scripts` doesn't end a script.
+        match lowercase.as_bytes().get(after) {
+            Some(b) if b.is_ascii_whitespace() || matches!(b, b'>' | b'/') => {
+                let end = after + lowercase[after..].find('>')? + 1;
+                return Some(pos + end);
+            }
+            _ => search = after,
+        }
+    }
+    None
+}
+
+/// Decodes the character references which are common in URLs.
+fn decode_character_references(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Whether the request refers to a file of the project, i. e. it's a URL
+/// without a scheme or host. Paths like `/src/main.ts` start at the root of the
+/// project.
+fn is_local_request(request: &str) -> bool {
+    let request = request.trim();
+    if request.is_empty() || request.starts_with(['#', '?']) || request.starts_with("//") {
+        return false;
+    }
+    match request.find(':') {
+        Some(colon) => {
+            // A colon after a slash, query or fragment is part of the path.
+            request[..colon].contains(['/', '?', '#'])
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_references, is_local_request, HtmlReferenceKind};
+
+    fn references(html: &str) -> Vec<(HtmlReferenceKind, &str, &str)> {
+        find_references(html)
+            .into_iter()
+            .map(|reference| {
+                (
+                    reference.kind,
+                    &html[reference.element.clone()],
+                    &html[reference.value],
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_references() {
+        let html = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <link rel="icon" href="./favicon.ico">
+    <link rel="preconnect" href="https://example.com">
+    <LINK REL="Stylesheet" HREF='style.css' media=print>
+    <script>document.write('<script src="inline.js"></script>')</script>
+  </head>
+  <body>
+    <!-- <img src="commented.png"> -->
+    <img alt="logo" src=logo.png />
+    <script type="module" src="./src/index.ts" async></script >
+  </body>
+</html>"#;
+        assert_eq!(
+            references(html),
+            vec![
+                (
+                    HtmlReferenceKind::Url,
+                    r#"<link rel="icon" href="./favicon.ico">"#,
+                    r#""./favicon.ico""#
+                ),
+                (
+                    HtmlReferenceKind::Stylesheet,
+                    r#"<LINK REL="Stylesheet" HREF='style.css' media=print>"#,
+                    "'style.css'"
+                ),
+                (
+                    HtmlReferenceKind::Url,
+                    r#"<img alt="logo" src=logo.png />"#,
+                    "logo.png"
+                ),
+                (
+                    HtmlReferenceKind::Script,
+                    r#"<script type="module" src="./src/index.ts" async></script >"#,
+                    r#""./src/index.ts""#
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_requests() {
+        let references = find_references(r#"<img src="a&amp;b.png">"#);
+        assert_eq!(references[0].request, "a&b.png");
+    }
+
+    #[test]
+    fn stops_at_unterminated_tags() {
+        assert_eq!(
+            references(r#"<img src="a.png"><img src="b.png"#),
+            vec![(HtmlReferenceKind::Url, r#"<img src="a.png">"#, r#""a.png""#)]
+        );
+    }
+
+    #[test]
+    fn detects_local_requests() {
+        assert!(is_local_request("./a.js"));
+        assert!(is_local_request("../a.js"));
+        assert!(is_local_request("a.js"));
+        assert!(is_local_request("a/b:c.js"));
+        assert!(is_local_request("/a.js"));
+        assert!(is_local_request("/src/main.ts"));
+        assert!(!is_local_request("//cdn.example.com/a.js"));
+        assert!(!is_local_request("https://cdn.example.com/a.js"));
+        assert!(!is_local_request("data:image/png;base64,AAAA"));
+        assert!(!is_local_request("#icon"));
+        assert!(!is_local_request(""));
+    }
+}
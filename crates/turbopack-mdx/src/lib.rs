@@ -62,6 +62,7 @@ async fn into_ecmascript_module_asset(
         this.context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         this.transforms,
+        Value::new(Default::default()),
         this.context.environment(),
     ))
 }
@@ -0,0 +1,14 @@
+// @ts-ignore
+import importedConfig from "CONFIG";
+
+const loadConfig = async () => {
+  let config = importedConfig;
+  if (typeof config === "function") {
+    config = await config({ env: "development" });
+  }
+  // Functions and other values which can't be serialized are left out, so
+  // they're reported as missing fields instead of failing the evaluation.
+  return JSON.parse(JSON.stringify(config ?? {}));
+};
+
+export { loadConfig as default };
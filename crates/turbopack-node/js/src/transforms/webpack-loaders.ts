@@ -11,6 +11,14 @@ import {
   resolve as pathResolve,
 } from "path";
 
+type EmittedAsset = {
+  file: string;
+  content: string;
+  // Binary content is base64 encoded.
+  encoding?: "base64";
+  sourceMap?: string;
+};
+
 type LoaderConfig =
   | string
   | {
@@ -43,6 +51,8 @@ const transform = (
     const resource = pathResolve(contextDir, name);
     const resourceDir = dirname(resource);
 
+    const assets: EmittedAsset[] = [];
+
     const loadersWithOptions = loaders.map((loader) =>
       typeof loader === "string" ? { loader, options: {} } : loader
     );
@@ -58,6 +68,25 @@ const transform = (
               ? entry.options
               : {};
           },
+          emitFile(
+            file: string,
+            content: string | Buffer,
+            sourceMap?: string | object
+          ) {
+            assets.push({
+              file,
+              ...(typeof content === "string"
+                ? { content }
+                : {
+                    content: content.toString("base64"),
+                    encoding: "base64" as const,
+                  }),
+              sourceMap:
+                sourceMap == null || typeof sourceMap === "string"
+                  ? sourceMap
+                  : JSON.stringify(sourceMap),
+            });
+          },
         },
         loaders: loadersWithOptions.map((loader) => ({
           loader: __turbopack_external_require__.resolve(loader.loader, {
@@ -87,7 +116,13 @@ const transform = (
         }
         if (!result.result) return reject(new Error("No result from loaders"));
         const [source, map] = result.result;
-        resolve({ source, map });
+        resolve({
+          source: source.toString(),
+          // Loaders may return the source map as an object or a string.
+          map:
+            map == null || typeof map === "string" ? map : JSON.stringify(map),
+          assets,
+        });
       }
     );
   });
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use turbo_tasks::{primitives::JsonValueVc, Value};
+use turbo_tasks_fs::{json::parse_json_rope_with_source_context, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetVc},
+    config::{
+        find_config_file, load_json_config, parse_config_value, TurbopackConfig, TurbopackConfigVc,
+    },
+    context::{AssetContext, AssetContextVc},
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    resolve::FindContextFileResult,
+    source_asset::SourceAssetVc,
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc, InnerAssetsVc,
+};
+
+use crate::{
+    embed_js::embed_file,
+    evaluate::{evaluate, JavaScriptValue},
+    execution_context::{ExecutionContext, ExecutionContextVc},
+};
+
+#[turbo_tasks::function]
+fn config_loader(context: AssetContextVc, config_path: FileSystemPathVc) -> AssetVc {
+    let config_asset = context.process(
+        SourceAssetVc::new(config_path).into(),
+        Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+    );
+
+    EcmascriptModuleAssetVc::new_with_inner_assets(
+        VirtualAssetVc::new(
+            config_path.join("load-config.js"),
+            AssetContent::File(embed_file("config/turbopack.ts")).cell(),
+        )
+        .into(),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
+        context.environment(),
+        InnerAssetsVc::cell(HashMap::from([("CONFIG".to_string(), config_asset)])),
+    )
+    .into()
+}
+
+/// Loads the `turbopack.config.{json,js,ts}` of the project in `context_path`
+/// or one of its parents. JavaScript and TypeScript configs are evaluated in
+/// the `evaluate_context`. The default config is used when there is none.
+#[turbo_tasks::function]
+pub async fn load_config(
+    context_path: FileSystemPathVc,
+    evaluate_context: AssetContextVc,
+    execution_context: ExecutionContextVc,
+) -> Result<TurbopackConfigVc> {
+    let FindContextFileResult::Found(config_path, _) = *find_config_file(context_path).await? else {
+        return Ok(TurbopackConfig::default().cell());
+    };
+    if config_path.await?.extension() == Some("json") {
+        return Ok(load_json_config(config_path));
+    }
+
+    let ExecutionContext {
+        project_root,
+        intermediate_output_path,
+        ..
+    } = *execution_context.await?;
+    // The config is a runtime entry to get it watched.
+    let config_chunk = EcmascriptModuleAssetVc::new(
+        SourceAssetVc::new(config_path).into(),
+        evaluate_context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
+        evaluate_context.environment(),
+    )
+    .as_ecmascript_chunk_placeable();
+    let config_value = evaluate(
+        project_root,
+        config_loader(evaluate_context, config_path),
+        project_root,
+        config_path,
+        evaluate_context,
+        intermediate_output_path,
+        Some(EcmascriptChunkPlaceablesVc::cell(vec![config_chunk])),
+        vec![],
+        /* debug */ false,
+    )
+    .await?;
+    match &*config_value {
+        JavaScriptValue::Value(val) => {
+            let value: serde_json::Value = parse_json_rope_with_source_context(val)?;
+            Ok(parse_config_value(config_path, JsonValueVc::cell(value)))
+        }
+        // An error happened, which has already been converted into an issue.
+        JavaScriptValue::Error => Ok(TurbopackConfig::default().cell()),
+        JavaScriptValue::Stream(_) => {
+            bail!("the config of a project can't be a stream")
+        }
+    }
+}
@@ -65,6 +65,7 @@ pub async fn get_evaluate_pool(
         context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
         context.environment(),
     )
     .as_asset();
@@ -90,6 +91,7 @@ pub async fn get_evaluate_pool(
         context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
         context.environment(),
         InnerAssetsVc::cell(HashMap::from([
             ("INNER".to_string(), module_asset),
@@ -5,6 +5,9 @@ use turbo_tasks_fs::FileSystemPathVc;
 pub struct ExecutionContext {
     pub project_root: FileSystemPathVc,
     pub intermediate_output_path: FileSystemPathVc,
+    /// The largest message a Node.js process may send, e.g. the output of a
+    /// render. Defaults to [crate::DEFAULT_MAX_MESSAGE_SIZE].
+    pub max_message_size: Option<usize>,
 }
 
 #[turbo_tasks::value_impl]
@@ -14,6 +17,7 @@ impl ExecutionContextVc {
         ExecutionContext {
             project_root,
             intermediate_output_path,
+            max_message_size: None,
         }
         .cell()
     }
@@ -21,9 +25,22 @@ impl ExecutionContextVc {
     #[turbo_tasks::function]
     pub async fn join(self, name: &str) -> Result<Self> {
         let this = self.await?;
-        Ok(ExecutionContextVc::new(
-            this.project_root,
-            this.intermediate_output_path.join(name),
-        ))
+        Ok(ExecutionContext {
+            project_root: this.project_root,
+            intermediate_output_path: this.intermediate_output_path.join(name),
+            max_message_size: this.max_message_size,
+        }
+        .cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn with_max_message_size(self, max_message_size: usize) -> Result<Self> {
+        let this = self.await?;
+        Ok(ExecutionContext {
+            project_root: this.project_root,
+            intermediate_output_path: this.intermediate_output_path,
+            max_message_size: Some(max_message_size),
+        }
+        .cell())
     }
 }
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use turbo_tasks::primitives::{BoolVc, OptionStringVc};
+
+use crate::route_matcher::{ParamsVc, RequiredQueryVc, RouteMatcher, RouteMatcherVc};
+
+/// The locales of an internationalized app.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct I18nConfig {
+    /// The supported locales, e.g. `en-US` or `fr`.
+    pub locales: Vec<String>,
+    /// The locale of paths without a locale prefix, when the client doesn't
+    /// accept any of the supported locales.
+    pub default_locale: String,
+}
+
+impl I18nConfig {
+    /// Splits a path into its locale prefix, if it has a prefix of a supported
+    /// locale, and the rest of the path.
+    pub fn strip_locale<'a>(&'a self, path: &'a str) -> (Option<&'a str>, &'a str) {
+        let (first, rest) = path.split_once('/').unwrap_or((path, ""));
+        match self
+            .locales
+            .iter()
+            .find(|locale| locale.eq_ignore_ascii_case(first))
+        {
+            Some(locale) => (Some(locale), rest),
+            None => (None, path),
+        }
+    }
+
+    /// Picks the supported locale that fits an `Accept-Language` header best,
+    /// falling back to the default locale.
+    pub fn negotiate(&self, accept_language: &str) -> &str {
+        let mut accepted = accept_language
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = parts.next()?.trim();
+                if tag.is_empty() {
+                    return None;
+                }
+                let quality = parts
+                    .find_map(|part| part.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+                Some((tag, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect::<Vec<_>>();
+        // Stable, so tags with the same quality keep the order of the header.
+        accepted.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        for (tag, _) in accepted {
+            if tag == "*" {
+                break;
+            }
+            if let Some(locale) = self
+                .locales
+                .iter()
+                .find(|locale| locale.eq_ignore_ascii_case(tag))
+            {
+                return locale;
+            }
+            // `en-US` is served by `en`, and `en` by the first `en-*` locale.
+            let language = language_of(tag);
+            if let Some(locale) = self
+                .locales
+                .iter()
+                .find(|locale| language_of(locale).eq_ignore_ascii_case(language))
+            {
+                return locale;
+            }
+        }
+        &self.default_locale
+    }
+}
+
+fn language_of(tag: &str) -> &str {
+    tag.split_once('-').map_or(tag, |(language, _)| language)
+}
+
+/// Matches paths with an optional locale prefix, e.g. `fr/about` or `about`,
+/// by matching the rest of the path against another [RouteMatcher]. Paths
+/// without a prefix are served in the locale negotiated from the
+/// `Accept-Language` header.
+#[turbo_tasks::value]
+pub struct LocaleRouteMatcher {
+    inner: RouteMatcherVc,
+    config: I18nConfigVc,
+}
+
+#[turbo_tasks::value_impl]
+impl LocaleRouteMatcherVc {
+    #[turbo_tasks::function]
+    pub fn new(inner: RouteMatcherVc, config: I18nConfigVc) -> Self {
+        LocaleRouteMatcher { inner, config }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl RouteMatcher for LocaleRouteMatcher {
+    #[turbo_tasks::function]
+    async fn matches(&self, path: &str) -> Result<BoolVc> {
+        let config = self.config.await?;
+        Ok(self.inner.matches(config.strip_locale(path).1))
+    }
+
+    #[turbo_tasks::function]
+    async fn params(&self, path: &str) -> Result<ParamsVc> {
+        let config = self.config.await?;
+        Ok(self.inner.params(config.strip_locale(path).1))
+    }
+
+    #[turbo_tasks::function]
+    fn required_query(&self) -> RequiredQueryVc {
+        self.inner.required_query()
+    }
+
+    #[turbo_tasks::function]
+    async fn locale(&self, path: &str, accept_language: &str) -> Result<OptionStringVc> {
+        let config = self.config.await?;
+        let locale = match config.strip_locale(path).0 {
+            Some(locale) => locale,
+            None => config.negotiate(accept_language),
+        };
+        Ok(OptionStringVc::cell(Some(locale.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> I18nConfig {
+        I18nConfig {
+            locales: vec!["en-US".to_string(), "fr".to_string(), "de-DE".to_string()],
+            default_locale: "en-US".to_string(),
+        }
+    }
+
+    #[test]
+    fn strips_supported_locale_prefixes() {
+        let config = config();
+        assert_eq!(config.strip_locale("fr/about"), (Some("fr"), "about"));
+        assert_eq!(config.strip_locale("EN-us/about"), (Some("en-US"), "about"));
+        assert_eq!(config.strip_locale("fr"), (Some("fr"), ""));
+        assert_eq!(config.strip_locale("es/about"), (None, "es/about"));
+        assert_eq!(config.strip_locale("about"), (None, "about"));
+    }
+
+    #[test]
+    fn negotiates_locale_from_accept_language() {
+        let config = config();
+        assert_eq!(config.negotiate(""), "en-US");
+        assert_eq!(config.negotiate("fr"), "fr");
+        assert_eq!(config.negotiate("es, fr;q=0.5, de-DE;q=0.8"), "de-DE");
+        // Languages match locales of any region, and the other way around.
+        assert_eq!(config.negotiate("fr-CA"), "fr");
+        assert_eq!(config.negotiate("de"), "de-DE");
+        // Tags with the same quality keep the order of the header.
+        assert_eq!(config.negotiate("fr;q=0.5, de;q=0.5"), "fr");
+        assert_eq!(config.negotiate("fr;q=0, es"), "en-US");
+        assert_eq!(config.negotiate("*, fr"), "en-US");
+    }
+}
@@ -5,6 +5,7 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::Write as _,
     path::PathBuf,
+    sync::{atomic::AtomicUsize, Arc},
 };
 
 use anyhow::{anyhow, bail, Result};
@@ -26,6 +27,7 @@ use turbopack_core::{
 };
 use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc};
 
+pub use self::pool::DEFAULT_MAX_MESSAGE_SIZE;
 use self::{
     bootstrap::NodeJsBootstrapAsset,
     pool::{NodeJsPool, NodeJsPoolVc},
@@ -33,14 +35,18 @@ use self::{
 use crate::source_map::{SourceMapTraceVc, StackFrame, TraceResult};
 
 pub mod bootstrap;
+pub mod config;
 mod embed_js;
 pub mod evaluate;
 pub mod execution_context;
+pub mod i18n;
+pub mod native_dependencies;
 mod node_entry;
 mod pool;
 pub mod render;
 pub mod route_matcher;
 pub mod source_map;
+pub mod standalone;
 pub mod transforms;
 
 #[turbo_tasks::function]
@@ -178,12 +184,38 @@ async fn separate_assets(
     .cell())
 }
 
+/// The part of the renderer pool of an entrypoint that is kept when the pool
+/// is replaced.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+struct RendererPoolHistory {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    peak_processes: Arc<AtomicUsize>,
+}
+
+/// Doesn't read anything, so it's only executed once per entrypoint.
+#[turbo_tasks::function]
+fn renderer_pool_history(_intermediate_output_path: FileSystemPathVc) -> RendererPoolHistoryVc {
+    RendererPoolHistory {
+        peak_processes: Arc::new(AtomicUsize::new(0)),
+    }
+    .cell()
+}
+
 /// Creates a node.js renderer pool for an entrypoint.
+///
+/// When the intermediate asset changes, a new pool is created, which starts as
+/// many processes as the previous pool needed. New operations use the new pool,
+/// while running operations finish on the processes of the previous pool,
+/// which are stopped afterwards.
+///
+/// Operations fail when a process sends a message larger than
+/// `max_message_size`, which defaults to [DEFAULT_MAX_MESSAGE_SIZE].
 #[turbo_tasks::function]
 pub async fn get_renderer_pool(
     intermediate_asset: AssetVc,
     intermediate_output_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
+    max_message_size: Option<usize>,
     debug: bool,
 ) -> Result<NodeJsPoolVc> {
     // Emit a basic package.json that sets the type of the package to commonjs.
@@ -209,7 +241,11 @@ pub async fn get_renderer_pool(
 
     if let (Some(cwd), Some(entrypoint)) = (to_sys_path(cwd).await?, to_sys_path(entrypoint).await?)
     {
-        let pool = NodeJsPool::new(cwd, entrypoint, HashMap::new(), 4, debug);
+        let history = renderer_pool_history(intermediate_output_path).await?;
+        let pool = NodeJsPool::new(cwd, entrypoint, HashMap::new(), 4, debug)
+            .with_peak_processes(history.peak_processes.clone())
+            .with_max_message_size(max_message_size.unwrap_or(DEFAULT_MAX_MESSAGE_SIZE));
+        pool.warm_up().await?;
         Ok(pool.cell())
     } else {
         Err(anyhow!("can only render from a disk filesystem"))
@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde_json::json;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    reference::all_assets,
+};
+
+/// A JSON manifest of the native files a server entry needs at runtime, so
+/// deployment tooling knows which files to ship next to it:
+///
+/// ```json
+/// {
+///   "files": ["node_modules/sharp/build/Release/sharp.node"],
+///   "packages": ["node_modules/sharp"]
+/// }
+/// ```
+///
+/// `files` are the `.node` files in the graph of the entry, e.g. of native
+/// addons required by modules or of packages with native addons, which are
+/// external. `packages` are the packages in `node_modules` they belong to.
+/// The paths are relative to `project_root`.
+#[turbo_tasks::value]
+pub struct NativeDependenciesManifestAsset {
+    path: FileSystemPathVc,
+    entry: AssetVc,
+    project_root: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NativeDependenciesManifestAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(path: FileSystemPathVc, entry: AssetVc, project_root: FileSystemPathVc) -> Self {
+        NativeDependenciesManifestAsset {
+            path,
+            entry,
+            project_root,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for NativeDependenciesManifestAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let project_root = self.project_root.await?;
+        let mut files = BTreeSet::new();
+        let mut packages = BTreeSet::new();
+        for &asset in all_assets(self.entry).await?.iter() {
+            let path = asset.path().await?;
+            if path.extension() != Some("node") {
+                continue;
+            }
+            let Some(relative_path) = project_root.get_path_to(&path) else {
+                continue;
+            };
+            if let Some(package) = package_dir(relative_path) {
+                packages.insert(package.to_string());
+            }
+            files.insert(relative_path.to_string());
+        }
+        let manifest = json!({
+            "files": files,
+            "packages": packages,
+        });
+        Ok(File::from(serde_json::to_string_pretty(&manifest)?).into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for NativeDependenciesManifestAsset {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "native dependencies manifest {}",
+            self.path.to_string().await?
+        )))
+    }
+}
+
+/// The directory of the package in `node_modules` a file belongs to, e.g.
+/// `node_modules/@scope/name` for
+/// `node_modules/@scope/name/build/Release/addon.node`.
+fn package_dir(path: &str) -> Option<&str> {
+    let start = path.rfind("node_modules/")? + "node_modules/".len();
+    let mut segments = path[start..].split('/');
+    let mut end = start + segments.next()?.len();
+    if path[start..].starts_with('@') {
+        end += 1 + segments.next()?.len();
+    }
+    // The file is inside of the package directory.
+    segments.next()?;
+    Some(&path[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::package_dir;
+
+    #[test]
+    fn finds_package_dirs() {
+        assert_eq!(
+            package_dir("node_modules/sharp/build/Release/sharp.node"),
+            Some("node_modules/sharp")
+        );
+        assert_eq!(
+            package_dir("node_modules/@scope/name/addon.node"),
+            Some("node_modules/@scope/name")
+        );
+        // Nested packages belong to the innermost `node_modules`.
+        assert_eq!(
+            package_dir("node_modules/a/node_modules/b/b.node"),
+            Some("node_modules/a/node_modules/b")
+        );
+        assert_eq!(package_dir("lib/addon.node"), None);
+        assert_eq!(package_dir("node_modules/addon.node"), None);
+        assert_eq!(package_dir("node_modules/@scope/addon.node"), None);
+    }
+}
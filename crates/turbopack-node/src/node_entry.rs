@@ -11,6 +11,9 @@ pub struct NodeRenderingEntry {
     pub chunking_context: ChunkingContextVc,
     pub intermediate_output_path: FileSystemPathVc,
     pub output_root: FileSystemPathVc,
+    /// The largest message the renderer may send, see
+    /// [crate::execution_context::ExecutionContext::max_message_size].
+    pub max_message_size: Option<usize>,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -4,11 +4,15 @@ use std::{
     mem::take,
     path::{Path, PathBuf},
     process::{ExitStatus, Stdio},
-    sync::{Arc, Mutex},
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use anyhow::{bail, Context, Result};
+use futures::future::try_join_all;
 use indexmap::IndexSet;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::{
@@ -22,6 +26,10 @@ use tokio::{
     sync::{OwnedSemaphorePermit, Semaphore},
     time::sleep,
 };
+use turbo_tasks::util::FormatBytes;
+
+/// The default of [NodeJsPool::with_max_message_size].
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 256 * 1024 * 1024;
 
 enum NodeJsPoolProcess {
     Spawned(SpawnedNodeJsPoolProcess),
@@ -39,18 +47,24 @@ struct SpawnedNodeJsPoolProcess {
 struct RunningNodeJsPoolProcess {
     child: Option<Child>,
     connection: TcpStream,
+    correlation_id: CorrelationIdSlot,
 }
 
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
 
 type SharedOutputSet = Arc<Mutex<IndexSet<(Arc<[u8]>, u32)>>>;
 
+/// The correlation id of the request the process is currently working on.
+type CorrelationIdSlot = Arc<Mutex<Option<String>>>;
+
 /// Pipes the `stream` from `final_stream`, but uses `shared` to deduplicate
 /// lines that has beem emitted by other `handle_output_stream` instances with
-/// the same `shared` before.
+/// the same `shared` before. Lines are prefixed with the correlation id in
+/// `correlation_id`, if any.
 async fn handle_output_stream(
     stream: impl AsyncRead + Unpin,
     shared: SharedOutputSet,
+    correlation_id: CorrelationIdSlot,
     mut final_stream: impl AsyncWrite + Unpin,
 ) {
     let mut buffered = BufReader::new(stream);
@@ -76,7 +90,20 @@ async fn handle_output_stream(
             let mut shared = shared.lock().unwrap();
             shared.insert((line.clone(), occurance_number))
         };
-        if new_line && final_stream.write(&line).await.is_err() {
+        if !new_line {
+            continue;
+        }
+        let prefix = correlation_id
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|id| format!("[{id}] "));
+        if let Some(prefix) = prefix {
+            if final_stream.write_all(prefix.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+        if final_stream.write(&line).await.is_err() {
             // Whatever happened with stdout/stderr, we can't write to it anymore.
             break;
         }
@@ -185,20 +212,24 @@ impl NodeJsPoolProcess {
                     },
                 };
 
+                let correlation_id = CorrelationIdSlot::default();
                 tokio::spawn(handle_output_stream(
                     child.stdout.take().unwrap(),
                     shared_stdout,
+                    correlation_id.clone(),
                     stdout(),
                 ));
                 tokio::spawn(handle_output_stream(
                     child.stderr.take().unwrap(),
                     shared_stderr,
+                    correlation_id.clone(),
                     stderr(),
                 ));
 
                 RunningNodeJsPoolProcess {
                     child: Some(child),
                     connection,
+                    correlation_id,
                 }
             }
             NodeJsPoolProcess::Running(running) => running,
@@ -207,14 +238,23 @@ impl NodeJsPoolProcess {
 }
 
 impl RunningNodeJsPoolProcess {
-    async fn recv(&mut self) -> Result<Vec<u8>> {
-        let packet_len = self
+    async fn recv(&mut self, max_len: usize) -> Result<Vec<u8>> {
+        let packet_len: usize = self
             .connection
             .read_u32()
             .await
             .context("reading packet length")?
             .try_into()
             .context("storing packet length")?;
+        if packet_len > max_len {
+            // The packet isn't read, so the process can't be reused.
+            bail!(
+                "the Node.js process sent a message of {}, which exceeds the maximum message size \
+                 of {}",
+                FormatBytes(packet_len),
+                FormatBytes(max_len)
+            );
+        }
         let mut packet_data = vec![0; packet_len];
         self.connection
             .read_exact(&mut packet_data)
@@ -250,6 +290,10 @@ impl RunningNodeJsPoolProcess {
 ///
 /// The worker will *not* use the env of the parent process by default. All env
 /// vars need to be provided to make the execution as pure as possible.
+///
+/// When the pool is dropped, e. g. because it was replaced by a pool for
+/// changed code, idle processes are stopped right away, while busy processes
+/// finish their current operation first.
 #[turbo_tasks::value(into = "new", cell = "new", serialization = "none", eq = "manual")]
 pub struct NodeJsPool {
     cwd: PathBuf,
@@ -259,11 +303,22 @@ pub struct NodeJsPool {
     processes: Arc<Mutex<Vec<NodeJsPoolProcess>>>,
     #[turbo_tasks(trace_ignore, debug_ignore)]
     semaphore: Arc<Semaphore>,
+    concurrency: usize,
+    /// The most processes the pool had at once, idle or busy. It can be
+    /// shared with the pools that replace this one, see
+    /// [NodeJsPool::with_peak_processes].
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    peak_processes: Arc<AtomicUsize>,
+    /// Set when the pool is dropped. Processes of a retired pool are stopped
+    /// instead of being reused.
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    retired: Arc<AtomicBool>,
     #[turbo_tasks(trace_ignore, debug_ignore)]
     shared_stdout: SharedOutputSet,
     #[turbo_tasks(trace_ignore, debug_ignore)]
     shared_stderr: SharedOutputSet,
     debug: bool,
+    max_message_size: usize,
 }
 
 impl NodeJsPool {
@@ -276,60 +331,152 @@ impl NodeJsPool {
         concurrency: usize,
         debug: bool,
     ) -> Self {
+        let concurrency = if debug { 1 } else { concurrency };
         Self {
             cwd,
             entrypoint,
             env,
             processes: Arc::new(Mutex::new(Vec::new())),
-            semaphore: Arc::new(Semaphore::new(if debug { 1 } else { concurrency })),
+            semaphore: Arc::new(Semaphore::new(concurrency)),
+            concurrency,
+            peak_processes: Arc::new(AtomicUsize::new(0)),
+            retired: Arc::new(AtomicBool::new(false)),
             shared_stdout: Arc::new(Mutex::new(IndexSet::new())),
             shared_stderr: Arc::new(Mutex::new(IndexSet::new())),
             debug,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
         }
     }
 
-    async fn acquire_process(&self) -> Result<(NodeJsPoolProcess, OwnedSemaphorePermit)> {
+    /// Limits the size of the messages which the processes send, e.g. the
+    /// output of a render. An operation which receives a larger message fails,
+    /// instead of buffering runaway output.
+    pub(super) fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Shares the peak number of processes with other pools, usually the
+    /// previous pools of the same entrypoint, so that [NodeJsPool::warm_up]
+    /// can start as many processes as they needed.
+    pub(super) fn with_peak_processes(mut self, peak_processes: Arc<AtomicUsize>) -> Self {
+        self.peak_processes = peak_processes;
+        self
+    }
+
+    /// Starts as many processes as the peak number of processes, so that
+    /// operations don't have to wait for Node.js to start. When a pool
+    /// replaces one for changed code, this avoids cold starting every process
+    /// under load.
+    pub(super) async fn warm_up(&self) -> Result<()> {
+        if self.debug {
+            // The process would wait for the debugger to attach.
+            return Ok(());
+        }
+        let count = self
+            .peak_processes
+            .load(Ordering::Relaxed)
+            .min(self.concurrency);
+        let processes = try_join_all((0..count).map(|_| self.spawn_process()))
+            .await
+            .context("warming up processes")?;
+        self.processes.lock().unwrap().extend(processes);
+        Ok(())
+    }
+
+    async fn spawn_process(&self) -> Result<NodeJsPoolProcess> {
+        NodeJsPoolProcess::new(
+            self.cwd.as_path(),
+            &self.env,
+            self.entrypoint.as_path(),
+            self.shared_stdout.clone(),
+            self.shared_stderr.clone(),
+            self.debug,
+        )
+        .await
+        .context("creating new process")
+    }
+
+    async fn acquire_process(
+        &self,
+        timings: &mut OperationTimings,
+    ) -> Result<(NodeJsPoolProcess, OwnedSemaphorePermit)> {
+        let start = Instant::now();
         let permit = self.semaphore.clone().acquire_owned().await?;
+        timings.queue = start.elapsed();
 
-        let popped = {
+        let (popped, idle) = {
             let mut processes = self.processes.lock().unwrap();
-            processes.pop()
+            (processes.pop(), processes.len())
         };
+        let busy = self.concurrency - self.semaphore.available_permits();
+        self.peak_processes
+            .fetch_max(idle + busy, Ordering::Relaxed);
         let process = match popped {
             Some(process) => process,
-            None => NodeJsPoolProcess::new(
-                self.cwd.as_path(),
-                &self.env,
-                self.entrypoint.as_path(),
-                self.shared_stdout.clone(),
-                self.shared_stderr.clone(),
-                self.debug,
-            )
-            .await
-            .context("creating new process")?,
+            None => {
+                let start = Instant::now();
+                let process = self.spawn_process().await?;
+                timings.spawn = start.elapsed();
+                process
+            }
         };
         Ok((process, permit))
     }
 
     pub async fn operation(&self) -> Result<NodeJsOperation> {
-        let (process, permit) = self.acquire_process().await?;
+        let mut timings = OperationTimings::default();
+        let (process, permit) = self.acquire_process(&mut timings).await?;
+
+        let start = Instant::now();
+        let process = process.run().await?;
+        timings.chunk_load = start.elapsed();
 
         Ok(NodeJsOperation {
-            process: Some(process.run().await?),
+            process: Some(process),
             permit,
             processes: self.processes.clone(),
+            retired: self.retired.clone(),
             allow_process_reuse: true,
+            max_message_size: self.max_message_size,
+            timings,
         })
     }
 }
 
+/// How long the phases of a [NodeJsOperation] took so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OperationTimings {
+    /// Waiting for a process of the pool to become available.
+    pub queue: Duration,
+    /// Spawning a new process. Zero when an idle process was reused.
+    pub spawn: Duration,
+    /// Waiting for the process to connect, which includes loading the chunks
+    /// of the entrypoint. Zero when a connected process was reused.
+    pub chunk_load: Duration,
+    /// Deserializing the messages received from the process.
+    pub deserialization: Duration,
+}
+
+impl Drop for NodeJsPool {
+    fn drop(&mut self) {
+        self.retired.store(true, Ordering::Release);
+        // Busy processes are not in the list. They are stopped when their
+        // operation is done.
+        self.processes.lock().unwrap().clear();
+    }
+}
+
 pub struct NodeJsOperation {
     process: Option<RunningNodeJsPoolProcess>,
     // This is used for drop
     #[allow(dead_code)]
     permit: OwnedSemaphorePermit,
     processes: Arc<Mutex<Vec<NodeJsPoolProcess>>>,
+    retired: Arc<AtomicBool>,
     allow_process_reuse: bool,
+    max_message_size: usize,
+    timings: OperationTimings,
 }
 
 impl NodeJsOperation {
@@ -357,12 +504,19 @@ impl NodeJsOperation {
     where
         M: DeserializeOwned,
     {
+        let max_message_size = self.max_message_size;
         let message = self
-            .with_process(
-                |process| async move { process.recv().await.context("receiving message") },
-            )
+            .with_process(|process| async move {
+                process
+                    .recv(max_message_size)
+                    .await
+                    .context("receiving message")
+            })
             .await?;
-        serde_json::from_slice(&message).context("deserializing message")
+        let start = Instant::now();
+        let message = serde_json::from_slice(&message).context("deserializing message");
+        self.timings.deserialization += start.elapsed();
+        message
     }
 
     pub async fn send<M>(&mut self, message: M) -> Result<()>
@@ -395,15 +549,28 @@ impl NodeJsOperation {
         Ok(status)
     }
 
+    pub fn timings(&self) -> OperationTimings {
+        self.timings
+    }
+
     pub fn disallow_reuse(&mut self) {
         self.allow_process_reuse = false;
     }
+
+    /// Prefixes the output of the process with `correlation_id` until the
+    /// operation ends, so that it can be attributed to a request.
+    pub fn set_correlation_id(&mut self, correlation_id: Option<String>) {
+        if let Some(process) = &self.process {
+            *process.correlation_id.lock().unwrap() = correlation_id;
+        }
+    }
 }
 
 impl Drop for NodeJsOperation {
     fn drop(&mut self) {
-        if self.allow_process_reuse {
+        if self.allow_process_reuse && !self.retired.load(Ordering::Acquire) {
             if let Some(process) = self.process.take() {
+                *process.correlation_id.lock().unwrap() = None;
                 self.processes
                     .lock()
                     .unwrap()
@@ -412,3 +579,92 @@ impl Drop for NodeJsOperation {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn prefixes_output_with_correlation_id() {
+        let shared = SharedOutputSet::default();
+        let correlation_id = CorrelationIdSlot::new(Mutex::new(Some("req-1".to_string())));
+        let mut output = Vec::new();
+        handle_output_stream(
+            &b"rendering\ndone\n"[..],
+            shared.clone(),
+            correlation_id.clone(),
+            &mut output,
+        )
+        .await;
+        assert_eq!(output, b"[req-1] rendering\n[req-1] done\n");
+
+        // Lines which another process printed already are skipped, and output
+        // outside of an operation isn't prefixed.
+        *correlation_id.lock().unwrap() = None;
+        let mut output = Vec::new();
+        handle_output_stream(
+            &b"rendering\nidle\n"[..],
+            shared,
+            correlation_id,
+            &mut output,
+        )
+        .await;
+        assert_eq!(output, b"idle\n");
+    }
+
+    /// A pool of processes which connect and then idle until they are killed.
+    /// `name` keeps the entrypoints of tests apart, which run in parallel.
+    fn idle_pool(name: &str, concurrency: usize) -> NodeJsPool {
+        let entrypoint = std::env::temp_dir().join(format!("turbopack-node-{name}.js"));
+        std::fs::write(
+            &entrypoint,
+            "require('net').connect(Number(process.argv[2]), '127.0.0.1'); setInterval(() => {}, \
+             1000);",
+        )
+        .unwrap();
+        NodeJsPool::new(
+            std::env::temp_dir(),
+            entrypoint,
+            HashMap::new(),
+            concurrency,
+            false,
+        )
+    }
+
+    #[tokio::test]
+    async fn warms_up_peak_processes_of_previous_pool() {
+        let pool = idle_pool("warm-up", 4);
+        let first = pool.operation().await.unwrap();
+        let second = pool.operation().await.unwrap();
+        drop(first);
+        drop(second);
+        assert_eq!(pool.peak_processes.load(Ordering::Relaxed), 2);
+        assert_eq!(pool.processes.lock().unwrap().len(), 2);
+
+        let next = idle_pool("warm-up", 4).with_peak_processes(pool.peak_processes.clone());
+        next.warm_up().await.unwrap();
+        assert_eq!(next.processes.lock().unwrap().len(), 2);
+
+        // No more processes than the concurrency allows are started.
+        let limited = idle_pool("warm-up", 1).with_peak_processes(pool.peak_processes.clone());
+        limited.warm_up().await.unwrap();
+        assert_eq!(limited.processes.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_processes_of_retired_pool() {
+        let pool = idle_pool("retired", 4);
+        let busy = pool.operation().await.unwrap();
+        let idle = pool.operation().await.unwrap();
+        drop(idle);
+        let processes = pool.processes.clone();
+        assert_eq!(processes.lock().unwrap().len(), 1);
+
+        // Idle processes are stopped right away, busy ones when their operation
+        // is done.
+        drop(pool);
+        assert!(processes.lock().unwrap().is_empty());
+        drop(busy);
+        assert!(processes.lock().unwrap().is_empty());
+    }
+}
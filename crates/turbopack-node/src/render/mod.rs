@@ -1,23 +1,93 @@
+use std::{fmt::Write, time::Duration};
+
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-use crate::{ResponseHeaders, StructuredError};
+use crate::{pool::OperationTimings, route_matcher::Param, ResponseHeaders, StructuredError};
 
 pub mod issue;
 pub mod node_api_source;
 pub mod render_proxy;
 pub mod render_static;
 pub mod rendered_source;
+mod retry;
 
 #[turbo_tasks::value(shared)]
 #[serde(rename_all = "camelCase")]
 pub struct RenderData {
-    params: IndexMap<String, String>,
+    params: IndexMap<String, Param>,
     method: String,
     url: String,
     raw_query: String,
     raw_headers: Vec<(String, String)>,
     path: String,
+    correlation_id: Option<String>,
+    locale: Option<String>,
+}
+
+/// How long the phases of a render took, to tell the overhead of turbopack
+/// from slow user code.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderTimings {
+    /// Waiting for a Node.js process to become available.
+    pub queue: Duration,
+    /// Spawning a new Node.js process.
+    pub spawn: Duration,
+    /// Loading the chunks of the entry in a new Node.js process.
+    pub chunk_load: Duration,
+    /// Rendering in user code, until the response was received.
+    pub render: Duration,
+    /// Deserializing the response.
+    pub serialization: Duration,
+    /// The whole render, including compiling the entry.
+    pub total: Duration,
+}
+
+impl RenderTimings {
+    fn new(operation: OperationTimings, render: Duration, total: Duration) -> Self {
+        RenderTimings {
+            queue: operation.queue,
+            spawn: operation.spawn,
+            chunk_load: operation.chunk_load,
+            render,
+            serialization: operation.deserialization,
+            total,
+        }
+    }
+
+    fn phases(&self) -> [(&'static str, Duration); 6] {
+        [
+            ("queue", self.queue),
+            ("spawn", self.spawn),
+            ("chunk-load", self.chunk_load),
+            ("render", self.render),
+            ("serialization", self.serialization),
+            ("total", self.total),
+        ]
+    }
+
+    /// Formats the timings as the value of a `Server-Timing` header, which
+    /// browsers show in their devtools.
+    pub fn to_server_timing(&self) -> String {
+        let mut value = String::new();
+        for (name, duration) in self.phases() {
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+            write!(value, "{name};dur={:.1}", duration.as_secs_f64() * 1000.0).unwrap();
+        }
+        value
+    }
+}
+
+impl std::fmt::Display for RenderTimings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, duration) in self.phases() {
+            writeln!(f, "{name}: {duration:?}")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize)]
@@ -53,6 +123,45 @@ enum RenderStaticIncomingMessage {
     },
     Rewrite {
         path: String,
+        /// Request headers to change for the rewritten request.
+        #[serde(default)]
+        headers: Vec<(String, String)>,
     },
     Error(StructuredError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timings() -> RenderTimings {
+        RenderTimings::new(
+            OperationTimings {
+                queue: Duration::from_millis(1),
+                spawn: Duration::from_millis(20),
+                chunk_load: Duration::from_micros(30_400),
+                deserialization: Duration::from_micros(500),
+            },
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+        )
+    }
+
+    #[test]
+    fn formats_server_timing_header() {
+        assert_eq!(
+            timings().to_server_timing(),
+            "queue;dur=1.0, spawn;dur=20.0, chunk-load;dur=30.4, render;dur=100.0, \
+             serialization;dur=0.5, total;dur=200.0"
+        );
+    }
+
+    #[test]
+    fn displays_phases() {
+        assert_eq!(
+            timings().to_string(),
+            "queue: 1ms\nspawn: 20ms\nchunk-load: 30.4ms\nrender: 100ms\nserialization: \
+             500µs\ntotal: 200ms\n"
+        );
+    }
+}
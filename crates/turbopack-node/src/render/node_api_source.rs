@@ -1,15 +1,15 @@
-use std::collections::HashSet;
-
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use indexmap::IndexSet;
 use turbo_tasks::{primitives::StringVc, Value};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::introspect::{
     asset::IntrospectableAssetVc, Introspectable, IntrospectableChildrenVc, IntrospectableVc,
 };
 use turbopack_dev_server::source::{
-    specificity::SpecificityVc, ContentSource, ContentSourceContent, ContentSourceContentVc,
-    ContentSourceData, ContentSourceDataVary, ContentSourceDataVaryVc, ContentSourceResult,
-    ContentSourceResultVc, ContentSourceVc, GetContentSourceContent, GetContentSourceContentVc,
+    error::ContentSourceError, specificity::SpecificityVc, ContentSource, ContentSourceContent,
+    ContentSourceContentVc, ContentSourceData, ContentSourceDataVary, ContentSourceDataVaryVc,
+    ContentSourceResult, ContentSourceResultVc, ContentSourceVc, GetContentSourceContent,
+    GetContentSourceContentVc, NeededData,
 };
 use turbopack_ecmascript::chunk::EcmascriptChunkPlaceablesVc;
 
@@ -17,7 +17,7 @@ use super::{render_proxy::render_proxy, RenderData};
 use crate::{
     get_intermediate_asset,
     node_entry::{NodeEntry, NodeEntryVc},
-    route_matcher::{RouteMatcher, RouteMatcherVc},
+    route_matcher::{accept_language, match_query, QueryMatch, RouteMatcher, RouteMatcherVc},
 };
 
 /// Creates a [NodeApiContentSource].
@@ -72,10 +72,21 @@ impl ContentSource for NodeApiContentSource {
     async fn get(
         self_vc: NodeApiContentSourceVc,
         path: &str,
-        _data: turbo_tasks::Value<ContentSourceData>,
+        data: turbo_tasks::Value<ContentSourceData>,
     ) -> Result<ContentSourceResultVc> {
         let this = self_vc.await?;
         if *this.route_match.matches(path).await? {
+            match match_query(this.route_match, &data).await? {
+                QueryMatch::Match => {}
+                QueryMatch::NoMatch => return Ok(ContentSourceResultVc::not_found()),
+                QueryMatch::NeedQuery(vary) => {
+                    return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary,
+                    })));
+                }
+            }
             return Ok(ContentSourceResult::Result {
                 specificity: this.specificity,
                 get_content: NodeApiGetContentResult {
@@ -106,6 +117,7 @@ impl GetContentSourceContent for NodeApiGetContentResult {
             url: true,
             raw_headers: true,
             raw_query: true,
+            correlation_id: true,
             body: true,
             cache_buster: true,
             ..Default::default()
@@ -116,7 +128,10 @@ impl GetContentSourceContent for NodeApiGetContentResult {
     async fn get(&self, data: Value<ContentSourceData>) -> Result<ContentSourceContentVc> {
         let this = self.source.await?;
         let Some(params) = &*this.route_match.params(&self.path).await? else {
-            return Err(anyhow!("Non matching path provided"));
+            return Err(ContentSourceError::NonMatchingPath {
+                path: self.path.clone(),
+            }
+            .report(this.server_root.join(&self.path)));
         };
         let ContentSourceData {
             method: Some(method),
@@ -126,8 +141,16 @@ impl GetContentSourceContent for NodeApiGetContentResult {
             body: Some(body),
             ..
         } = &*data else {
-            return Err(anyhow!("Missing request data"));
+            return Err(ContentSourceError::MissingRequestData {
+                path: self.path.clone(),
+            }
+            .report(this.server_root.join(&self.path)));
         };
+        let locale = this
+            .route_match
+            .locale(&self.path, accept_language(raw_headers))
+            .await?
+            .clone();
         let entry = this.entry.entry(data.clone()).await?;
         Ok(ContentSourceContent::HttpProxy(render_proxy(
             this.server_root.join(&self.path),
@@ -136,12 +159,15 @@ impl GetContentSourceContent for NodeApiGetContentResult {
             entry.chunking_context,
             entry.intermediate_output_path,
             entry.output_root,
+            entry.max_message_size,
             RenderData {
                 params: params.clone(),
                 method: method.clone(),
                 url: url.clone(),
                 raw_query: raw_query.clone(),
                 raw_headers: raw_headers.clone(),
+                correlation_id: data.correlation_id.clone(),
+                locale,
                 path: format!("/{}", self.path),
             }
             .cell(),
@@ -178,7 +204,7 @@ impl Introspectable for NodeApiContentSource {
 
     #[turbo_tasks::function]
     async fn children(&self) -> Result<IntrospectableChildrenVc> {
-        let mut set = HashSet::new();
+        let mut set = IndexSet::new();
         for &entry in self.entry.entries().await?.iter() {
             let entry = entry.await?;
             set.insert((
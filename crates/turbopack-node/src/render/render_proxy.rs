@@ -6,8 +6,8 @@ use turbopack_dev_server::source::{BodyVc, ProxyResult, ProxyResultVc};
 use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc};
 
 use super::{
-    issue::RenderingIssue, RenderDataVc, RenderProxyIncomingMessage, RenderProxyOutgoingMessage,
-    ResponseHeaders,
+    issue::RenderingIssue, retry::RenderRetry, RenderDataVc, RenderProxyIncomingMessage,
+    RenderProxyOutgoingMessage, ResponseHeaders,
 };
 use crate::{get_intermediate_asset, get_renderer_pool, pool::NodeJsOperation, trace_stack};
 
@@ -20,6 +20,7 @@ pub async fn render_proxy(
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
+    max_message_size: Option<usize>,
     data: RenderDataVc,
     body: BodyVc,
 ) -> Result<ProxyResultVc> {
@@ -31,27 +32,37 @@ pub async fn render_proxy(
         intermediate_asset,
         intermediate_output_path,
         output_root,
+        max_message_size,
         /* debug */ false,
     );
     let pool = renderer_pool.await?;
-    let mut operation = match pool.operation().await {
-        Ok(operation) => operation,
-        Err(err) => {
-            return proxy_error(path, err, None).await;
-        }
-    };
+    let mut retry = RenderRetry::new(&data.await?.method);
+    loop {
+        let mut operation = match pool.operation().await {
+            Ok(operation) => operation,
+            Err(err) => {
+                return proxy_error(path, err, None).await;
+            }
+        };
 
-    match run_proxy_operation(
-        &mut operation,
-        data,
-        body,
-        intermediate_asset,
-        intermediate_output_path,
-    )
-    .await
-    {
-        Ok(proxy_result) => Ok(proxy_result.cell()),
-        Err(err) => Ok(proxy_error(path, err, Some(operation)).await?),
+        match run_proxy_operation(
+            &mut operation,
+            data,
+            body,
+            intermediate_asset,
+            intermediate_output_path,
+        )
+        .await
+        {
+            Ok(proxy_result) => return Ok(proxy_result.cell()),
+            Err(err) => {
+                // The process is dropped, so the retry uses a fresh one.
+                operation.disallow_reuse();
+                if let Err(err) = retry.retry(err).await {
+                    return proxy_error(path, err, Some(operation)).await;
+                }
+            }
+        }
     }
 }
 
@@ -63,6 +74,7 @@ async fn run_proxy_operation(
     intermediate_output_path: FileSystemPathVc,
 ) -> Result<ProxyResult> {
     let data = data.await?;
+    operation.set_correlation_id(data.correlation_id.clone());
     // First, send the render data.
     operation
         .send(RenderProxyOutgoingMessage::Headers { data: &data })
@@ -106,6 +118,7 @@ async fn run_proxy_operation(
         status,
         headers,
         body: body.into(),
+        trailers: vec![],
     })
 }
 
@@ -147,6 +160,7 @@ async fn proxy_error(
             "text/html; charset=utf-8".to_string(),
         ],
         body: body.into(),
+        trailers: vec![],
     }
     .cell())
 }
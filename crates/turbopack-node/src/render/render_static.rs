@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use anyhow::{bail, Context, Result};
 use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
@@ -7,12 +9,13 @@ use turbopack_core::{
 };
 use turbopack_dev_server::{
     html::DevHtmlAssetVc,
-    source::{HeaderListVc, RewriteVc},
+    source::{HeaderListVc, RequestOverrides, Rewrite, RewriteVc},
 };
 use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc};
 
 use super::{
-    issue::RenderingIssue, RenderDataVc, RenderStaticIncomingMessage, RenderStaticOutgoingMessage,
+    issue::RenderingIssue, retry::RenderRetry, RenderDataVc, RenderStaticIncomingMessage,
+    RenderStaticOutgoingMessage, RenderTimings,
 };
 use crate::{get_intermediate_asset, get_renderer_pool, pool::NodeJsOperation, trace_stack};
 
@@ -22,6 +25,8 @@ pub enum StaticResult {
         content: AssetContentVc,
         status_code: u16,
         headers: HeaderListVc,
+        /// Only known for successful renders.
+        timings: Option<RenderTimings>,
     },
     Rewrite(RewriteVc),
 }
@@ -34,6 +39,7 @@ impl StaticResultVc {
             content,
             status_code,
             headers,
+            timings: None,
         }
         .cell()
     }
@@ -54,8 +60,10 @@ pub async fn render_static(
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
+    max_message_size: Option<usize>,
     data: RenderDataVc,
 ) -> Result<StaticResultVc> {
+    let start = Instant::now();
     let intermediate_asset = get_intermediate_asset(
         module.as_evaluated_chunk(chunking_context, Some(runtime_entries)),
         intermediate_output_path,
@@ -64,39 +72,48 @@ pub async fn render_static(
         intermediate_asset,
         intermediate_output_path,
         output_root,
+        max_message_size,
         /* debug */ false,
     );
     // Read this strongly consistent, since we don't want to run inconsistent
     // node.js code.
     let pool = renderer_pool.strongly_consistent().await?;
-    let mut operation = match pool.operation().await {
-        Ok(operation) => operation,
-        Err(err) => {
-            return Ok(StaticResultVc::content(
-                static_error(path, err, None, fallback_page).await?,
-                500,
-                HeaderListVc::empty(),
-            ))
-        }
-    };
+    let mut retry = RenderRetry::new(&data.await?.method);
+    loop {
+        let mut operation = match pool.operation().await {
+            Ok(operation) => operation,
+            Err(err) => {
+                return Ok(StaticResultVc::content(
+                    static_error(path, err, None, fallback_page).await?,
+                    500,
+                    HeaderListVc::empty(),
+                ))
+            }
+        };
 
-    Ok(
         match run_static_operation(
             &mut operation,
             data,
             intermediate_asset,
             intermediate_output_path,
+            start,
         )
         .await
         {
-            Ok(result) => result,
-            Err(err) => StaticResultVc::content(
-                static_error(path, err, Some(operation), fallback_page).await?,
-                500,
-                HeaderListVc::empty(),
-            ),
-        },
-    )
+            Ok(result) => return Ok(result),
+            Err(err) => {
+                // The process is dropped, so the retry uses a fresh one.
+                operation.disallow_reuse();
+                if let Err(err) = retry.retry(err).await {
+                    return Ok(StaticResultVc::content(
+                        static_error(path, err, Some(operation), fallback_page).await?,
+                        500,
+                        HeaderListVc::empty(),
+                    ));
+                }
+            }
+        }
+    }
 }
 
 async fn run_static_operation(
@@ -104,9 +121,12 @@ async fn run_static_operation(
     data: RenderDataVc,
     intermediate_asset: AssetVc,
     intermediate_output_path: FileSystemPathVc,
+    start: Instant,
 ) -> Result<StaticResultVc> {
     let data = data.await?;
+    operation.set_correlation_id(data.correlation_id.clone());
 
+    let render_start = Instant::now();
     operation
         .send(RenderStaticOutgoingMessage::Headers { data: &data })
         .await
@@ -117,18 +137,45 @@ async fn run_static_operation(
             .await
             .context("receiving from node.js process")?
         {
-            RenderStaticIncomingMessage::Rewrite { path } => {
-                StaticResultVc::rewrite(RewriteVc::new_path_query(path))
+            RenderStaticIncomingMessage::Rewrite { path, headers } => {
+                if headers.is_empty() {
+                    StaticResultVc::rewrite(RewriteVc::new_path_query(path))
+                } else {
+                    StaticResultVc::rewrite(
+                        Rewrite {
+                            path_and_query: path,
+                            source: None,
+                            overrides: Some(
+                                RequestOverrides {
+                                    headers,
+                                    params: Vec::new(),
+                                }
+                                .cell(),
+                            ),
+                        }
+                        .cell(),
+                    )
+                }
             }
             RenderStaticIncomingMessage::Response {
                 status_code,
-                headers,
+                mut headers,
                 body,
-            } => StaticResultVc::content(
-                FileContent::Content(File::from(body)).into(),
-                status_code,
-                HeaderListVc::cell(headers),
-            ),
+            } => {
+                let operation_timings = operation.timings();
+                let render = render_start
+                    .elapsed()
+                    .saturating_sub(operation_timings.deserialization);
+                let timings = RenderTimings::new(operation_timings, render, start.elapsed());
+                headers.push(("Server-Timing".to_string(), timings.to_server_timing()));
+                StaticResult::Content {
+                    content: FileContent::Content(File::from(body)).into(),
+                    status_code,
+                    headers: HeaderListVc::cell(headers),
+                    timings: Some(timings),
+                }
+                .cell()
+            }
             RenderStaticIncomingMessage::Error(error) => {
                 bail!(trace_stack(error, intermediate_asset, intermediate_output_path).await?)
             }
@@ -1,8 +1,8 @@
-use std::collections::HashSet;
+use std::fmt::Write;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use indexmap::IndexSet;
-use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks::{primitives::StringVc, State, Value};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::{Asset, AssetsSetVc},
@@ -17,23 +17,24 @@ use turbopack_dev_server::{
     source::{
         asset_graph::AssetGraphContentSourceVc,
         conditional::ConditionalContentSourceVc,
+        error::ContentSourceError,
         lazy_instantiated::{GetContentSource, GetContentSourceVc, LazyInstantiatedContentSource},
         specificity::SpecificityVc,
         ContentSource, ContentSourceContent, ContentSourceContentVc, ContentSourceData,
         ContentSourceDataVary, ContentSourceDataVaryVc, ContentSourceResult, ContentSourceResultVc,
-        ContentSourceVc, GetContentSourceContent, GetContentSourceContentVc,
+        ContentSourceVc, GetContentSourceContent, GetContentSourceContentVc, NeededData,
     },
 };
 use turbopack_ecmascript::chunk::EcmascriptChunkPlaceablesVc;
 
 use super::{
     render_static::{render_static, StaticResult},
-    RenderData,
+    RenderData, RenderTimings,
 };
 use crate::{
     external_asset_entrypoints, get_intermediate_asset,
     node_entry::{NodeEntry, NodeEntryVc},
-    route_matcher::{RouteMatcher, RouteMatcherVc},
+    route_matcher::{accept_language, match_query, QueryMatch, RouteMatcher, RouteMatcherVc},
 };
 
 /// Creates a content source that renders something in Node.js with the passed
@@ -60,6 +61,7 @@ pub fn create_node_rendered_source(
         entry,
         runtime_entries,
         fallback_page,
+        last_render_timings: State::new(None),
     }
     .cell();
     ConditionalContentSourceVc::new(
@@ -74,7 +76,7 @@ pub fn create_node_rendered_source(
 }
 
 /// see [create_node_rendered_source]
-#[turbo_tasks::value]
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
 pub struct NodeRenderContentSource {
     specificity: SpecificityVc,
     server_root: FileSystemPathVc,
@@ -83,6 +85,8 @@ pub struct NodeRenderContentSource {
     entry: NodeEntryVc,
     runtime_entries: EcmascriptChunkPlaceablesVc,
     fallback_page: DevHtmlAssetVc,
+    /// Shown in the introspection of the source.
+    last_render_timings: State<Option<RenderTimings>>,
 }
 
 #[turbo_tasks::value_impl]
@@ -144,10 +148,21 @@ impl ContentSource for NodeRenderContentSource {
     async fn get(
         self_vc: NodeRenderContentSourceVc,
         path: &str,
-        _data: turbo_tasks::Value<ContentSourceData>,
+        data: turbo_tasks::Value<ContentSourceData>,
     ) -> Result<ContentSourceResultVc> {
         let this = self_vc.await?;
         if *this.route_match.matches(path).await? {
+            match match_query(this.route_match, &data).await? {
+                QueryMatch::Match => {}
+                QueryMatch::NoMatch => return Ok(ContentSourceResultVc::not_found()),
+                QueryMatch::NeedQuery(vary) => {
+                    return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary,
+                    })));
+                }
+            }
             return Ok(ContentSourceResult::Result {
                 specificity: this.specificity,
                 get_content: NodeRenderGetContentResult {
@@ -178,6 +193,7 @@ impl GetContentSourceContent for NodeRenderGetContentResult {
             url: true,
             raw_headers: true,
             raw_query: true,
+            correlation_id: true,
             ..Default::default()
         }
         .cell()
@@ -187,7 +203,10 @@ impl GetContentSourceContent for NodeRenderGetContentResult {
     async fn get(&self, data: Value<ContentSourceData>) -> Result<ContentSourceContentVc> {
         let this = self.source.await?;
         let Some(params) = &*this.route_match.params(&self.path).await? else {
-            return Err(anyhow!("Non matching path provided"));
+            return Err(ContentSourceError::NonMatchingPath {
+                path: self.path.clone(),
+            }
+            .report(this.server_root.join(&self.path)));
         };
         let ContentSourceData {
             method: Some(method),
@@ -196,8 +215,16 @@ impl GetContentSourceContent for NodeRenderGetContentResult {
             raw_query: Some(raw_query),
             ..
         } = &*data else {
-            return Err(anyhow!("Missing request data"));
+            return Err(ContentSourceError::MissingRequestData {
+                path: self.path.clone(),
+            }
+            .report(this.server_root.join(&self.path)));
         };
+        let locale = this
+            .route_match
+            .locale(&self.path, accept_language(raw_headers))
+            .await?
+            .clone();
         let entry = this.entry.entry(data.clone()).await?;
         let result = render_static(
             this.server_root.join(&self.path),
@@ -207,22 +234,38 @@ impl GetContentSourceContent for NodeRenderGetContentResult {
             entry.chunking_context,
             entry.intermediate_output_path,
             entry.output_root,
+            entry.max_message_size,
             RenderData {
                 params: params.clone(),
                 method: method.clone(),
                 url: url.clone(),
                 raw_query: raw_query.clone(),
                 raw_headers: raw_headers.clone(),
+                correlation_id: data.correlation_id.clone(),
+                locale,
                 path: format!("/{}", this.pathname.await?),
             }
             .cell(),
         );
-        Ok(match *result.await? {
+        let result = result.await.map_err(|source| {
+            ContentSourceError::RenderFailed {
+                path: self.path.clone(),
+                source,
+            }
+            .report(this.server_root.join(&self.path))
+        })?;
+        Ok(match *result {
             StaticResult::Content {
                 content,
                 status_code,
                 headers,
-            } => ContentSourceContentVc::static_with_headers(content.into(), status_code, headers),
+                timings,
+            } => {
+                if timings.is_some() {
+                    this.last_render_timings.set(timings);
+                }
+                ContentSourceContentVc::static_with_headers(content.into(), status_code, headers)
+            }
             StaticResult::Rewrite(rewrite) => ContentSourceContent::Rewrite(rewrite).cell(),
         })
     }
@@ -247,15 +290,16 @@ impl Introspectable for NodeRenderContentSource {
 
     #[turbo_tasks::function]
     async fn details(&self) -> Result<StringVc> {
-        Ok(StringVc::cell(format!(
-            "Specificity: {}",
-            self.specificity.await?
-        )))
+        let mut details = format!("Specificity: {}", self.specificity.await?);
+        if let Some(timings) = &*self.last_render_timings.get() {
+            write!(details, "\n\nLast render:\n{timings}")?;
+        }
+        Ok(StringVc::cell(details))
     }
 
     #[turbo_tasks::function]
     async fn children(&self) -> Result<IntrospectableChildrenVc> {
-        let mut set = HashSet::new();
+        let mut set = IndexSet::new();
         for &entry in self.entry.entries().await?.iter() {
             let entry = entry.await?;
             set.insert((
@@ -0,0 +1,143 @@
+use std::{io::ErrorKind, time::Duration};
+
+use anyhow::Error;
+use tokio::time::sleep;
+
+/// How often a render is retried at most.
+const MAX_RETRIES: usize = 1;
+/// The delay before the first retry. It's doubled for every further retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+/// The total time all retries of a render may wait.
+const BACKOFF_BUDGET: Duration = Duration::from_secs(1);
+
+/// Returns whether a render failed because the connection to the Node.js
+/// process broke, e.g. because it crashed or closed the IPC channel before it
+/// responded. Such failures are likely to go away on a fresh process, unlike
+/// errors thrown by the rendered code.
+fn is_transient(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<std::io::Error>().map_or(false, |err| {
+            matches!(
+                err.kind(),
+                ErrorKind::UnexpectedEof
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+            )
+        })
+    })
+}
+
+/// Returns whether a request with `method` can be sent again without changing
+/// its effect. The process may have handled a request before the connection
+/// broke, so other requests must not be rendered twice.
+fn is_idempotent(method: &str) -> bool {
+    matches!(
+        method.to_ascii_uppercase().as_str(),
+        "GET" | "HEAD" | "OPTIONS"
+    )
+}
+
+/// Decides whether a failed render is retried on a fresh process, with
+/// exponential backoff.
+pub(super) struct RenderRetry {
+    idempotent: bool,
+    failed_attempts: Vec<String>,
+    waited: Duration,
+}
+
+impl RenderRetry {
+    /// Only renders of idempotent requests are retried.
+    pub(super) fn new(method: &str) -> Self {
+        Self {
+            idempotent: is_idempotent(method),
+            failed_attempts: Vec::new(),
+            waited: Duration::ZERO,
+        }
+    }
+
+    /// Waits for the backoff and returns `Ok` when the render should be
+    /// retried. Otherwise returns the error, which includes the errors of
+    /// earlier attempts, so that they are reported as a single issue.
+    pub(super) async fn retry(&mut self, error: Error) -> Result<(), Error> {
+        let backoff = INITIAL_BACKOFF * 2u32.pow(self.failed_attempts.len() as u32);
+        if !self.idempotent
+            || !is_transient(&error)
+            || self.failed_attempts.len() >= MAX_RETRIES
+            || self.waited + backoff > BACKOFF_BUDGET
+        {
+            return Err(self.aggregate(error));
+        }
+        self.failed_attempts.push(format!("{error:?}"));
+        sleep(backoff).await;
+        self.waited += backoff;
+        Ok(())
+    }
+
+    fn aggregate(&self, error: Error) -> Error {
+        if self.failed_attempts.is_empty() {
+            return error;
+        }
+        let mut message = format!(
+            "rendering failed on {} attempts, earlier attempts failed with:",
+            self.failed_attempts.len() + 1
+        );
+        for failed_attempt in &self.failed_attempts {
+            message.push_str("\n\n");
+            message.push_str(failed_attempt);
+        }
+        error.context(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn broken_pipe() -> Error {
+        Error::new(io::Error::from(ErrorKind::BrokenPipe)).context("sending the render data")
+    }
+
+    #[test]
+    fn detects_transient_errors() {
+        assert!(is_transient(&broken_pipe()));
+        assert!(is_transient(&Error::new(io::Error::from(
+            ErrorKind::UnexpectedEof
+        ))));
+        assert!(!is_transient(&Error::new(io::Error::from(
+            ErrorKind::NotFound
+        ))));
+        assert!(!is_transient(&anyhow!(
+            "ReferenceError: window is not defined"
+        )));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_once() {
+        let mut retry = RenderRetry::new("GET");
+        assert!(retry.retry(broken_pipe()).await.is_ok());
+        let error = retry.retry(broken_pipe()).await.unwrap_err();
+        assert!(error.to_string().contains("on 2 attempts"));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_errors() {
+        let mut retry = RenderRetry::new("GET");
+        assert!(retry.retry(anyhow!("thrown by the page")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_non_idempotent_requests() {
+        for method in ["POST", "PUT", "PATCH", "DELETE"] {
+            let mut retry = RenderRetry::new(method);
+            let error = retry.retry(broken_pipe()).await.unwrap_err();
+            // Not aggregated, as there was a single attempt.
+            assert_eq!(error.to_string(), "sending the render data");
+        }
+        assert!(RenderRetry::new("head").retry(broken_pipe()).await.is_ok());
+    }
+}
@@ -1,8 +1,54 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
 use indexmap::IndexMap;
-use turbo_tasks::primitives::BoolVc;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{
+    primitives::{BoolVc, OptionStringVc},
+    trace::TraceRawVcs,
+};
+use turbopack_dev_server::source::{
+    query::{Query, QueryValue},
+    ContentSourceData, ContentSourceDataFilter, ContentSourceDataVary,
+};
+
+/// The value of a param extracted from a path.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, TraceRawVcs, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Param {
+    /// A dynamic segment, e.g. `[id]`.
+    Single(String),
+    /// The segments of a catch all, e.g. `[...slug]` or `[[...slug]]`.
+    Multi(Vec<String>),
+}
+
+impl From<String> for Param {
+    fn from(value: String) -> Self {
+        Param::Single(value)
+    }
+}
+
+impl From<&str> for Param {
+    fn from(value: &str) -> Self {
+        Param::Single(value.to_string())
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct Params(Option<IndexMap<String, Param>>);
 
+/// The query params that a route requires, with the value each param must
+/// have, or `None` when any value is accepted.
 #[turbo_tasks::value(transparent)]
-pub struct Params(Option<IndexMap<String, String>>);
+pub struct RequiredQuery(IndexMap<String, Option<String>>);
+
+#[turbo_tasks::value_impl]
+impl RequiredQueryVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        RequiredQueryVc::cell(IndexMap::new())
+    }
+}
 
 /// Extracts parameters from a URL path.
 #[turbo_tasks::value_trait]
@@ -12,4 +58,178 @@ pub trait RouteMatcher {
 
     /// Returns the parameters extracted from the given path.
     fn params(&self, path: &str) -> ParamsVc;
+
+    /// Returns the query params that a request must have to match the route,
+    /// in addition to the path. Nothing is required by default.
+    fn required_query(&self) -> RequiredQueryVc {
+        RequiredQueryVc::empty()
+    }
+
+    /// Returns the locale the given path is served in, given the
+    /// `Accept-Language` header of the request (empty when missing). Routes
+    /// aren't localized by default.
+    fn locale(&self, _path: &str, _accept_language: &str) -> OptionStringVc {
+        OptionStringVc::cell(None)
+    }
+}
+
+/// Returns the value of the `Accept-Language` header, or an empty string when
+/// the request has none.
+pub fn accept_language(raw_headers: &[(String, String)]) -> &str {
+    raw_headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("accept-language"))
+        .map_or("", |(_, value)| value.as_str())
+}
+
+/// The result of [match_query].
+pub enum QueryMatch {
+    Match,
+    NoMatch,
+    /// The query of the request is needed to decide. It must be requested
+    /// with this vary.
+    NeedQuery(ContentSourceDataVary),
+}
+
+/// Checks the query of a request against the [RouteMatcher::required_query]
+/// of a route.
+pub async fn match_query(
+    route_match: RouteMatcherVc,
+    data: &ContentSourceData,
+) -> Result<QueryMatch> {
+    let required = route_match.required_query().await?;
+    if required.is_empty() {
+        return Ok(QueryMatch::Match);
+    }
+    let Some(query) = &data.query else {
+        return Ok(QueryMatch::NeedQuery(ContentSourceDataVary {
+            query: Some(ContentSourceDataFilter::Subset(
+                required.keys().cloned().collect::<BTreeSet<_>>(),
+            )),
+            ..Default::default()
+        }));
+    };
+    let matches = query_matches(&required, query);
+    Ok(if matches {
+        QueryMatch::Match
+    } else {
+        QueryMatch::NoMatch
+    })
+}
+
+/// Whether `query` has every param of `required`, with the required value if
+/// there is one. An array param matches when any of its values does.
+fn query_matches(required: &IndexMap<String, Option<String>>, query: &Query) -> bool {
+    required.iter().all(|(name, value)| match query.get(name) {
+        None => false,
+        Some(_) if value.is_none() => true,
+        Some(QueryValue::String(actual)) => Some(actual) == value.as_ref(),
+        Some(QueryValue::Array(values)) => values.iter().any(
+            |actual| matches!(actual, QueryValue::String(actual) if Some(actual) == value.as_ref()),
+        ),
+        Some(_) => false,
+    })
+}
+
+/// Constrains the matches of another [RouteMatcher] to requests with some
+/// query params.
+#[turbo_tasks::value]
+pub struct QueryRouteMatcher {
+    inner: RouteMatcherVc,
+    required_query: RequiredQueryVc,
+}
+
+#[turbo_tasks::value_impl]
+impl QueryRouteMatcherVc {
+    #[turbo_tasks::function]
+    pub fn new(inner: RouteMatcherVc, required_query: RequiredQueryVc) -> Self {
+        QueryRouteMatcher {
+            inner,
+            required_query,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl RouteMatcher for QueryRouteMatcher {
+    #[turbo_tasks::function]
+    fn matches(&self, path: &str) -> BoolVc {
+        self.inner.matches(path)
+    }
+
+    #[turbo_tasks::function]
+    fn params(&self, path: &str) -> ParamsVc {
+        self.inner.params(path)
+    }
+
+    #[turbo_tasks::function]
+    fn required_query(&self) -> RequiredQueryVc {
+        self.required_query
+    }
+
+    #[turbo_tasks::function]
+    fn locale(&self, path: &str, accept_language: &str) -> OptionStringVc {
+        self.inner.locale(path, accept_language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(params: &[(&str, QueryValue)]) -> Query {
+        let mut query = Query::default();
+        for (name, value) in params {
+            query.insert(name.to_string(), value.clone());
+        }
+        query
+    }
+
+    fn string(value: &str) -> QueryValue {
+        QueryValue::String(value.to_string())
+    }
+
+    #[test]
+    fn reads_accept_language_header() {
+        let headers = vec![
+            ("host".to_string(), "localhost".to_string()),
+            ("Accept-Language".to_string(), "fr".to_string()),
+        ];
+        assert_eq!(accept_language(&headers), "fr");
+        assert_eq!(accept_language(&headers[..1]), "");
+    }
+
+    #[test]
+    fn matches_required_query_params() {
+        let required = IndexMap::from([
+            ("preview".to_string(), None),
+            ("lang".to_string(), Some("en".to_string())),
+        ]);
+        assert!(query_matches(
+            &required,
+            &query(&[("preview", string("")), ("lang", string("en"))])
+        ));
+        // Any value of an array can match.
+        assert!(query_matches(
+            &required,
+            &query(&[
+                ("preview", string("1")),
+                ("lang", QueryValue::Array(vec![string("de"), string("en")])),
+            ])
+        ));
+        assert!(!query_matches(
+            &required,
+            &query(&[("preview", string("")), ("lang", string("de"))])
+        ));
+        assert!(!query_matches(&required, &query(&[("lang", string("en"))])));
+        assert!(!query_matches(
+            &required,
+            &query(&[
+                ("preview", string("")),
+                ("lang", QueryValue::Nested(query(&[])))
+            ])
+        ));
+        assert!(query_matches(&IndexMap::new(), &query(&[])));
+    }
 }
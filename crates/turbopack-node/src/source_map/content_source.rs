@@ -2,7 +2,7 @@ use anyhow::Result;
 use turbo_tasks::{primitives::StringVc, Value};
 use turbopack_core::{
     introspect::{Introspectable, IntrospectableVc},
-    source_map::{GenerateSourceMap, GenerateSourceMapVc},
+    source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapVc},
 };
 use turbopack_dev_server::source::{
     ContentSource, ContentSourceContent, ContentSourceContentVc, ContentSourceData,
@@ -59,46 +59,9 @@ impl ContentSource for NextSourceMapTraceContentSource {
             _ => return Ok(ContentSourceResultVc::not_found()),
         };
 
-        // The file is some percent encoded `http://localhost:3000/_next/foo/bar.js`
-        let file = match Url::parse(&frame.file) {
-            Ok(u) => u,
-            _ => return Ok(ContentSourceResultVc::not_found()),
-        };
-
-        let path = match file.path().strip_prefix('/') {
-            Some(p) => p,
-            _ => return Ok(ContentSourceResultVc::not_found()),
-        };
-        let id = file
-            .query_pairs()
-            .find_map(|(k, v)| if k == "id" { Some(v) } else { None });
-
         let this = self_vc.await?;
-        let result = this.asset_source.get(path, Default::default()).await?;
-        let content = match &*result {
-            ContentSourceResult::Result { get_content, .. } => {
-                get_content.get(Default::default()).await?
-            }
-            _ => return Ok(ContentSourceResultVc::not_found()),
-        };
-        let file = match &*content {
-            ContentSourceContent::Static(static_content) => static_content.await?.content,
-            _ => return Ok(ContentSourceResultVc::not_found()),
-        };
-
-        let gen = match GenerateSourceMapVc::resolve_from(file).await? {
-            Some(f) => f,
-            _ => return Ok(ContentSourceResultVc::not_found()),
-        };
-
-        let sm = if let Some(id) = id {
-            let section = gen.by_section(&id).await?;
-            match &*section {
-                Some(sm) => *sm,
-                None => return Ok(ContentSourceResultVc::not_found()),
-            }
-        } else {
-            gen.generate_source_map()
+        let Some(sm) = source_map_for_url(this.asset_source, &frame.file).await? else {
+            return Ok(ContentSourceResultVc::not_found());
         };
 
         let traced = SourceMapTraceVc::new(sm, line, column, frame.name);
@@ -113,6 +76,51 @@ impl ContentSource for NextSourceMapTraceContentSource {
     }
 }
 
+/// Finds the source map of the asset which is served at the `url` of a stack
+/// frame, e.g. `http://localhost:3000/_next/foo/bar.js`. An `id` query param
+/// selects a section of the source map.
+pub(super) async fn source_map_for_url(
+    asset_source: ContentSourceVc,
+    url: &str,
+) -> Result<Option<SourceMapVc>> {
+    // The file is some percent encoded `http://localhost:3000/_next/foo/bar.js`
+    let file = match Url::parse(url) {
+        Ok(u) => u,
+        _ => return Ok(None),
+    };
+
+    let path = match file.path().strip_prefix('/') {
+        Some(p) => p,
+        _ => return Ok(None),
+    };
+    let id = file
+        .query_pairs()
+        .find_map(|(k, v)| if k == "id" { Some(v) } else { None });
+
+    let result = asset_source.get(path, Default::default()).await?;
+    let content = match &*result {
+        ContentSourceResult::Result { get_content, .. } => {
+            get_content.get(Default::default()).await?
+        }
+        _ => return Ok(None),
+    };
+    let file = match &*content {
+        ContentSourceContent::Static(static_content) => static_content.await?.content,
+        _ => return Ok(None),
+    };
+
+    let gen = match GenerateSourceMapVc::resolve_from(file).await? {
+        Some(f) => f,
+        _ => return Ok(None),
+    };
+
+    Ok(if let Some(id) = id {
+        *gen.by_section(&id).await?
+    } else {
+        Some(gen.generate_source_map())
+    })
+}
+
 #[turbo_tasks::value_impl]
 impl Introspectable for NextSourceMapTraceContentSource {
     #[turbo_tasks::function]
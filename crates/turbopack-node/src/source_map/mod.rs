@@ -1,7 +1,9 @@
 pub mod content_source;
+pub mod stack_trace_source;
 pub mod trace;
 
 pub use content_source::{NextSourceMapTraceContentSource, NextSourceMapTraceContentSourceVc};
+pub use stack_trace_source::{StackTraceContentSource, StackTraceContentSourceVc};
 pub use trace::{
     SourceMapTrace, SourceMapTraceVc, StackFrame, StackFrameVc, TraceResult, TraceResultVc,
 };
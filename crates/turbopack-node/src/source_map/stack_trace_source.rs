@@ -0,0 +1,129 @@
+use anyhow::Result;
+use mime::APPLICATION_JSON;
+use serde::Deserialize;
+use serde_json::json;
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value};
+use turbo_tasks_fs::File;
+use turbopack_core::{
+    asset::AssetContentVc,
+    introspect::{Introspectable, IntrospectableVc},
+};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceContentVc, ContentSourceData, ContentSourceDataVary,
+    ContentSourceResultVc, ContentSourceVc, ContentSourcesVc, NeededData,
+};
+
+use super::{content_source::source_map_for_url, SourceMapTraceVc, StackFrame, TraceResult};
+
+/// Symbolicates whole stack traces at once with the source maps the dev server
+/// has in memory, so clients don't need to download and parse the source maps
+/// themselves.
+///
+/// Accepts a `POST` with a JSON body of `{ "frames": [StackFrame] }` and
+/// responds with `{ "frames": [StackFrame | null] }`, where every frame is
+/// traced to its original position, or is `null` when it can't be traced.
+#[turbo_tasks::value(shared)]
+pub struct StackTraceContentSource {
+    asset_source: ContentSourceVc,
+}
+
+#[turbo_tasks::value_impl]
+impl StackTraceContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(asset_source: ContentSourceVc) -> StackTraceContentSourceVc {
+        StackTraceContentSource { asset_source }.cell()
+    }
+}
+
+#[derive(Deserialize)]
+struct StackTraceRequest {
+    frames: Vec<StackFrame>,
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for StackTraceContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: StackTraceContentSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let (Some(method), Some(body)) = (&data.method, data.body) else {
+            return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self_vc.into(),
+                path: path.to_string(),
+                vary: ContentSourceDataVary {
+                    method: true,
+                    body: true,
+                    ..Default::default()
+                },
+            })));
+        };
+        if method != "POST" {
+            return Ok(ContentSourceResultVc::not_found());
+        }
+
+        let body = body.await?;
+        let bytes = body
+            .chunks()
+            .flat_map(|chunk| chunk.as_bytes().iter().copied())
+            .collect::<Vec<u8>>();
+        let Ok(request) = serde_json::from_slice::<StackTraceRequest>(&bytes) else {
+            return Ok(ContentSourceResultVc::not_found());
+        };
+
+        let this = self_vc.await?;
+        let frames = request
+            .frames
+            .into_iter()
+            .map(|frame| trace_frame(this.asset_source, frame))
+            .try_join()
+            .await?;
+
+        let file =
+            File::from(json!({ "frames": frames }).to_string()).with_content_type(APPLICATION_JSON);
+        Ok(ContentSourceResultVc::exact(
+            ContentSourceContentVc::static_content(AssetContentVc::from(file).into()).into(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn get_children(&self) -> ContentSourcesVc {
+        ContentSourcesVc::cell(vec![self.asset_source])
+    }
+}
+
+/// Traces a frame to its original position, if possible.
+async fn trace_frame(
+    asset_source: ContentSourceVc,
+    frame: StackFrame,
+) -> Result<Option<StackFrame>> {
+    let Some((line, column)) = frame.get_pos() else {
+        return Ok(None);
+    };
+    let Some(sm) = source_map_for_url(asset_source, &frame.file).await? else {
+        return Ok(None);
+    };
+    let trace = SourceMapTraceVc::new(sm, line, column, frame.name)
+        .trace()
+        .await?;
+    Ok(match &*trace {
+        TraceResult::Found(frame) => Some(frame.clone()),
+        TraceResult::NotFound => None,
+    })
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for StackTraceContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("stack trace content source".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn details(&self) -> StringVc {
+        StringVc::cell(
+            "symbolicates error stack traces to their original source locations".to_string(),
+        )
+    }
+}
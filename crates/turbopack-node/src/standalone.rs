@@ -0,0 +1,147 @@
+use anyhow::{bail, Result};
+use serde_json::json;
+use turbo_tasks::{TryJoinIterExt, ValueToString};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc, AssetsVc},
+    reference::all_assets,
+    virtual_asset::VirtualAssetVc,
+};
+
+use crate::{native_dependencies::NativeDependenciesManifestAssetVc, separate_assets};
+
+/// The name of the blob which `node --experimental-sea-config` writes for a
+/// standalone output with [StandaloneOptions::sea].
+pub const SEA_BLOB_NAME: &str = "sea-prep.blob";
+
+/// Options of [standalone_assets].
+#[turbo_tasks::value(shared)]
+#[derive(Default, Clone, Debug)]
+pub struct StandaloneOptions {
+    /// Also emits a `sea-config.json`, which turns the entry into a Node.js
+    /// single executable application blob with
+    /// `node --experimental-sea-config sea-config.json`. A single executable
+    /// application can only require builtin modules, so the entry needs to be
+    /// chunked into a single file without externals, see
+    /// [ChunkingContext::single_file].
+    ///
+    /// [ChunkingContext::single_file]: turbopack_core::chunk::ChunkingContext::single_file
+    pub sea: bool,
+}
+
+/// The assets of a deployable server artifact for a node.js entry, which
+/// runs with `node .` in `output_path` without the project.
+///
+/// The artifact has the layout of the project, so the modules find the files
+/// they access relative to `__dirname` like in the project. The assets are:
+/// * the bootstrap script of the entry and its chunks, which are emitted into
+///   `output_root`, which needs to be inside of `project_root`,
+/// * the files the chunks refer to outside of `output_root`, like files read
+///   with `fs.readFile`, traced files of packages in `node_modules` and the
+///   `.node` binaries of native addons,
+/// * a minimal `package.json`, which makes the entry a CommonJS module,
+/// * a `native-dependencies.json`, which lists the native files of the
+///   artifact, see [crate::native_dependencies],
+/// * and, with [StandaloneOptions::sea], a `sea-config.json`.
+///
+/// Packages which the resolve options leave as externals without resolving
+/// them are not traced. They need to be installed next to the artifact.
+///
+/// The returned assets have no references, and can be emitted with
+/// `turbopack::build::emit_build`.
+#[turbo_tasks::function]
+pub async fn standalone_assets(
+    intermediate_asset: AssetVc,
+    output_root: FileSystemPathVc,
+    project_root: FileSystemPathVc,
+    output_path: FileSystemPathVc,
+    options: StandaloneOptionsVc,
+) -> Result<AssetsVc> {
+    let options = options.await?;
+    let separated = separate_assets(intermediate_asset, output_root)
+        .strongly_consistent()
+        .await?;
+    let mut assets = Vec::new();
+
+    let project_root_value = project_root.await?;
+    if !output_root.await?.is_inside(&project_root_value) {
+        bail!(
+            "the output root {} is not in the project root {}",
+            output_root.to_string().await?,
+            project_root.to_string().await?
+        );
+    }
+    let Some(main) = project_root_value
+        .get_path_to(&*intermediate_asset.path().await?)
+        .map(str::to_string)
+    else {
+        bail!(
+            "the entry {} is not in the project root {}",
+            intermediate_asset.path().to_string().await?,
+            project_root.to_string().await?
+        );
+    };
+    for &asset in separated.internal_assets.await?.iter() {
+        assets.push(rebased_asset(asset, project_root, output_path));
+    }
+
+    let externals = separated
+        .external_asset_entrypoints
+        .await?
+        .iter()
+        .map(|&asset| all_assets(asset))
+        .try_join()
+        .await?;
+    for &asset in externals.iter().flat_map(|assets| assets.iter()) {
+        // Files outside of the project, e.g. of a global package cache, are
+        // expected to be available where the artifact is deployed.
+        if asset.path().await?.is_inside(&project_root_value) {
+            assets.push(rebased_asset(asset, project_root, output_path));
+        }
+    }
+
+    let package_json = json!({
+        "private": true,
+        "type": "commonjs",
+        "main": main,
+    });
+    assets.push(json_asset(output_path.join("package.json"), &package_json)?);
+    assets.push(
+        NativeDependenciesManifestAssetVc::new(
+            output_path.join("native-dependencies.json"),
+            intermediate_asset,
+            project_root,
+        )
+        .into(),
+    );
+    if options.sea {
+        let sea_config = json!({
+            "main": main,
+            "output": SEA_BLOB_NAME,
+            "disableExperimentalSEAWarning": true,
+        });
+        assets.push(json_asset(
+            output_path.join("sea-config.json"),
+            &sea_config,
+        )?);
+    }
+
+    Ok(AssetsVc::cell(assets))
+}
+
+fn rebased_asset(
+    asset: AssetVc,
+    old_base: FileSystemPathVc,
+    new_base: FileSystemPathVc,
+) -> AssetVc {
+    VirtualAssetVc::new(
+        FileSystemPathVc::rebase(asset.path(), old_base, new_base),
+        asset.content(),
+    )
+    .into()
+}
+
+fn json_asset(path: FileSystemPathVc, value: &serde_json::Value) -> Result<AssetVc> {
+    let content = FileContent::Content(File::from(serde_json::to_string_pretty(value)?));
+    Ok(VirtualAssetVc::new(path, content.into()).into())
+}
@@ -143,6 +143,7 @@ async fn extra_configs(
                         context,
                         Value::new(EcmascriptModuleAssetType::Ecmascript),
                         EcmascriptInputTransformsVc::cell(vec![]),
+                        Value::new(Default::default()),
                         context.environment(),
                     )
                     .as_ecmascript_chunk_placeable()
@@ -174,6 +175,7 @@ fn postcss_executor(context: AssetContextVc, postcss_config_path: FileSystemPath
         context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
         context.environment(),
         InnerAssetsVc::cell(HashMap::from([("CONFIG".to_string(), config_asset)])),
     )
@@ -196,6 +198,7 @@ impl PostCssTransformedAssetVc {
         let ExecutionContext {
             project_root,
             intermediate_output_path,
+            ..
         } = *this.execution_context.await?;
         let source_content = this.source.content();
         let AssetContent::File(file) = *source_content.await? else {
@@ -242,7 +245,7 @@ impl PostCssTransformedAssetVc {
             .context("Unable to deserializate response from PostCSS transform operation")?;
         // TODO handle SourceMap
         let file = File::from(processed_css.css);
-        let assets = emitted_assets_to_virtual_assets(processed_css.assets);
+        let assets = emitted_assets_to_virtual_assets(processed_css.assets)?;
         let content = AssetContent::File(FileContent::Content(file).cell()).cell();
         Ok(ProcessPostCssResult { content, assets }.cell())
     }
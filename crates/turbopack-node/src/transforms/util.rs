@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use turbo_tasks_fs::{File, FileContent, FileSystem};
@@ -12,29 +13,83 @@ use turbopack_core::{
 pub struct EmittedAsset {
     file: String,
     content: String,
+    /// The encoding of binary content, e.g. images emitted by a loader.
+    #[serde(default)]
+    encoding: Option<EmittedAssetEncoding>,
     source_map: Option<JsonValue>,
 }
 
-pub fn emitted_assets_to_virtual_assets(assets: Option<Vec<EmittedAsset>>) -> Vec<VirtualAssetVc> {
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum EmittedAssetEncoding {
+    Base64,
+}
+
+impl EmittedAsset {
+    /// Decodes the content of the asset.
+    fn file(&self) -> Result<File> {
+        Ok(match self.encoding {
+            None => File::from(self.content.as_str()),
+            Some(EmittedAssetEncoding::Base64) => File::from(
+                base64::decode(&self.content)
+                    .with_context(|| format!("invalid base64 content of {}", self.file))?,
+            ),
+        })
+    }
+}
+
+pub fn emitted_assets_to_virtual_assets(
+    assets: Option<Vec<EmittedAsset>>,
+) -> Result<Vec<VirtualAssetVc>> {
     assets
         .into_iter()
         .flatten()
-        .map(
-            |EmittedAsset {
-                 file,
-                 content,
-                 source_map,
-             }| (file, (content, source_map)),
-        )
+        .map(|asset| (asset.file.clone(), asset))
         // Sort it to make it determinstic
         .collect::<BTreeMap<_, _>>()
         .into_iter()
-        .map(|(file, (content, _source_map))| {
+        .map(|(file, asset)| {
             // TODO handle SourceMap
-            VirtualAssetVc::new(
+            Ok(VirtualAssetVc::new(
                 ServerFileSystemVc::new().root().join(&file),
-                AssetContent::File(FileContent::Content(File::from(content)).cell()).cell(),
-            )
+                AssetContent::File(FileContent::Content(asset.file()?).cell()).cell(),
+            ))
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::EmittedAsset;
+
+    #[test]
+    fn decodes_binary_content() {
+        let assets: Vec<EmittedAsset> = serde_json::from_str(
+            r#"[
+                { "file": "style.css", "content": "a {}" },
+                { "file": "image.png", "content": "iVBORw0KGgo=", "encoding": "base64" }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            assets[0].file().unwrap().content().to_str().unwrap(),
+            "a {}"
+        );
+        assert_eq!(
+            assets[1]
+                .file()
+                .unwrap()
+                .content()
+                .read()
+                .flatten()
+                .collect::<Vec<_>>(),
+            b"\x89PNG\r\n\x1a\n"
+        );
+
+        let invalid: EmittedAsset = serde_json::from_str(
+            r#"{ "file": "image.png", "content": "not base64!", "encoding": "base64" }"#,
+        )
+        .unwrap();
+        assert!(invalid.file().is_err());
+    }
+}
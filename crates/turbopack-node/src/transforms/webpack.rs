@@ -125,11 +125,27 @@ fn webpack_loaders_executor(project_root: FileSystemPathVc, context: AssetContex
         context,
         Value::new(EcmascriptModuleAssetType::Typescript),
         EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+        Value::new(Default::default()),
         context.environment(),
     )
     .into()
 }
 
+/// Appends the source map the loaders returned as an inline source map
+/// reference, so that it's used as the input source map when the processed
+/// source is parsed, and mappings point to the original file.
+fn with_inline_source_map(mut source: String, map: Option<String>) -> String {
+    if let Some(map) = map {
+        if !source.ends_with('\n') {
+            source.push('\n');
+        }
+        source.push_str("//# sourceMappingURL=data:application/json;base64,");
+        source.push_str(&base64::encode(map));
+        source.push('\n');
+    }
+    source
+}
+
 #[turbo_tasks::value_impl]
 impl WebpackLoadersProcessedAssetVc {
     #[turbo_tasks::function]
@@ -139,6 +155,7 @@ impl WebpackLoadersProcessedAssetVc {
         let ExecutionContext {
             project_root,
             intermediate_output_path,
+            ..
         } = *this.execution_context.await?;
         let source_content = this.source.content();
         let AssetContent::File(file) = *source_content.await? else {
@@ -182,10 +199,32 @@ impl WebpackLoadersProcessedAssetVc {
         };
         let processed: WebpackLoadersProcessingResult = parse_json_rope_with_source_context(val)
             .context("Unable to deserializate response from webpack loaders transform operation")?;
-        // TODO handle SourceMap
-        let file = File::from(processed.source);
-        let assets = emitted_assets_to_virtual_assets(processed.assets);
+        let file = File::from(with_inline_source_map(processed.source, processed.map));
+        let assets = emitted_assets_to_virtual_assets(processed.assets)?;
         let content = AssetContent::File(FileContent::Content(file).cell()).cell();
         Ok(ProcessWebpackLoadersResult { content, assets }.cell())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::with_inline_source_map;
+
+    #[test]
+    fn appends_inline_source_map() {
+        let map = r#"{"version":3,"sources":["index.ts"],"mappings":"AAAA"}"#;
+        let source = with_inline_source_map("export {};".to_string(), Some(map.to_string()));
+        let (code, reference) = source.trim_end().rsplit_once('\n').unwrap();
+        assert_eq!(code, "export {};");
+        let encoded = reference
+            .strip_prefix("//# sourceMappingURL=data:application/json;base64,")
+            .unwrap();
+        assert_eq!(base64::decode(encoded).unwrap(), map.as_bytes());
+
+        // A source without a map is kept as it is.
+        assert_eq!(
+            with_inline_source_map("export {};\n".to_string(), None),
+            "export {};\n"
+        );
+    }
+}
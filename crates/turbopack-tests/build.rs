@@ -6,4 +6,10 @@ fn main() {
     // Unfortunately, we can't have the build.rs file operate differently on
     // each file, so the entire turbopack crate needs to be rebuilt.
     rerun_if_glob("tests/snapshot/*/*", "tests/snapshot");
+    rerun_if_glob("tests/content_source/*", "tests/content_source");
+    rerun_if_glob("tests/build/*", "tests/build");
+    rerun_if_glob("tests/chunking/*", "tests/chunking");
+    rerun_if_glob("tests/unused/*", "tests/unused");
+    rerun_if_glob("tests/project/*", "tests/project");
+    rerun_if_glob("tests/introspect/*", "tests/introspect");
 }
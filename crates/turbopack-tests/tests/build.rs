@@ -0,0 +1,228 @@
+#![cfg(test)]
+
+//! Tests for the production build driver. The fixture in `tests/build` is
+//! built into `target/turbopack-tests/build`, copies of it are built in
+//! `target/turbopack-tests/roots`, and assets are emitted into
+//! `target/turbopack-tests/emit`.
+
+mod util;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileContent, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    build::{emit_build, run_build, BuildOptions, BuildReport},
+    module_options::ModuleOptionsContext,
+    project::{ProjectOptions, ProjectOptionsVc},
+    resolve_options_context::ResolveOptionsContext,
+};
+use turbopack_core::{
+    asset::{AssetVc, AssetsVc},
+    chunk::dev::DevChunkingContextVc,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    virtual_asset::VirtualAssetVc,
+};
+use util::WORKSPACE_ROOT;
+
+util::register!("build");
+
+const OUTPUT_PATH: &str = "target/turbopack-tests/build";
+
+fn project_options() -> ProjectOptionsVc {
+    project_options_in(
+        WORKSPACE_ROOT.clone(),
+        "crates/turbopack-tests/tests/build",
+        OUTPUT_PATH,
+    )
+}
+
+/// The options of a project at `project` in a file system at `root`, which is
+/// built into `output`.
+fn project_options_in(root: String, project: &str, output: &str) -> ProjectOptionsVc {
+    let project_fs = DiskFileSystemVc::new("project".to_string(), root);
+    let project_path = project_fs.root().join(project);
+    let output_path = project_fs.root().join(output);
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: "Chrome 102".to_string(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    );
+    ProjectOptions {
+        project_path,
+        output_path,
+        entries: vec!["./input/index.js".to_string()],
+        environment,
+        module_options_context: ModuleOptionsContext::default().cell(),
+        resolve_options_context: ResolveOptionsContext::default().cell(),
+        chunking_context: DevChunkingContextVc::builder(
+            project_path,
+            output_path,
+            output_path.join("chunks"),
+            output_path.join("assets"),
+            environment,
+        )
+        .build(),
+        tree_shaking: false,
+        minify: None,
+        federation: None,
+    }
+    .cell()
+}
+
+#[test]
+fn builds_for_production() {
+    util::run(run());
+}
+
+async fn run() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut reports = Vec::new();
+    run_build(
+        tt,
+        BuildOptions::default(),
+        project_options,
+        |report: BuildReport| reports.push(report),
+    )
+    .await?;
+
+    assert_eq!(reports.len(), 1, "a build without watching runs once");
+    let result = reports.remove(0).result?;
+    assert!(
+        result.issues.is_empty(),
+        "the build reported {} issues",
+        result.issues.len()
+    );
+    let entrypoint = &result.entrypoints[0];
+    assert_eq!(entrypoint.request, "./input/index.js");
+
+    let output_path = PathBuf::from(WORKSPACE_ROOT.as_str()).join(OUTPUT_PATH);
+    let mut code = String::new();
+    for chunk in &entrypoint.chunks {
+        assert!(
+            result.assets.iter().any(|asset| &asset.path == chunk),
+            "{chunk} is not an emitted asset"
+        );
+        code += &fs::read_to_string(output_path.join(chunk))?;
+    }
+    // Unused exports are removed and the code is minified, even though the
+    // options of the project don't enable it.
+    assert!(code.contains("used export"));
+    assert!(!code.contains("unused export"));
+    assert!(!code.contains("return \"used export\";"));
+
+    Ok(())
+}
+
+/// Outputs are named and hashed by paths relative to the root of the file
+/// system, so the same project builds to the same outputs in any directory,
+/// e. g. on another machine.
+#[test]
+fn outputs_are_independent_of_the_project_root() {
+    let roots = [
+        "target/turbopack-tests/roots/a",
+        "target/turbopack-tests/roots/b/nested",
+    ]
+    .map(|root| PathBuf::from(WORKSPACE_ROOT.as_str()).join(root));
+    let outputs = roots.map(|root| util::run(build_in(&root)));
+    assert!(!outputs[0].is_empty());
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+/// Builds a copy of the fixture in `root`, and returns the paths and contents
+/// of the outputs.
+async fn build_in(root: &Path) -> Result<Vec<(String, String)>> {
+    register();
+
+    let _ = fs::remove_dir_all(root);
+    let input = root.join("app/input");
+    fs::create_dir_all(&input)?;
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/build/input");
+    for entry in fs::read_dir(fixture)? {
+        let entry = entry?;
+        fs::copy(entry.path(), input.join(entry.file_name()))?;
+    }
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut reports = Vec::new();
+    let root_path = root.to_str().unwrap().to_string();
+    run_build(
+        tt,
+        BuildOptions::default(),
+        move || project_options_in(root_path.clone(), "app", "out"),
+        |report: BuildReport| reports.push(report),
+    )
+    .await?;
+    let result = reports.remove(0).result?;
+    result
+        .assets
+        .iter()
+        .map(|asset| {
+            let content = fs::read_to_string(root.join("out").join(&asset.path))?;
+            Ok((asset.path.clone(), content))
+        })
+        .collect()
+}
+
+const EMIT_OUTPUT_PATH: &str = "target/turbopack-tests/emit";
+
+fn output_asset(output_dir: FileSystemPathVc, path: &str) -> AssetVc {
+    VirtualAssetVc::new(
+        output_dir.join(path),
+        FileContent::Content(File::from(path)).into(),
+    )
+    .into()
+}
+
+#[test]
+fn deletes_stale_outputs() {
+    util::run(run_stale_outputs());
+}
+
+async fn run_stale_outputs() -> Result<()> {
+    register();
+
+    let output_path = PathBuf::from(WORKSPACE_ROOT.as_str()).join(EMIT_OUTPUT_PATH);
+    let _ = fs::remove_dir_all(&output_path);
+    fs::create_dir_all(&output_path)?;
+    fs::write(output_path.join("other.js"), "other")?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let project_fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let output_dir = project_fs.root().join(EMIT_OUTPUT_PATH);
+        let emit = |paths: &[&str]| {
+            let assets = paths
+                .iter()
+                .map(|path| output_asset(output_dir, path))
+                .collect();
+            emit_build(AssetsVc::cell(assets), output_dir).strongly_consistent()
+        };
+
+        emit(&["main.js", "removed/chunk.js"]).await?;
+        assert_eq!(fs::read_to_string(output_path.join("main.js"))?, "main.js");
+        assert!(output_path.join("removed/chunk.js").exists());
+
+        emit(&["main.js"]).await?;
+        assert!(output_path.join("main.js").exists());
+        assert!(!output_path.join("removed/chunk.js").exists());
+        // Files which the build didn't write are left alone.
+        assert!(output_path.join("other.js").exists());
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,374 @@
+#![cfg(test)]
+
+//! Tests for the options of the chunking context. The fixtures in
+//! `tests/chunking/*/input` are chunked in memory, nothing is written to disk.
+
+mod util;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    future::Future,
+};
+
+use anyhow::Result;
+use turbo_tasks::{TurboTasks, Value, ValueToString};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    chunk::{
+        dev::{DevChunkingContextBuilder, DevChunkingContextVc},
+        ChunkBannerRule, OutputFormat, RuntimeChunking,
+    },
+    context::AssetContext,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use util::WORKSPACE_ROOT;
+
+util::register!("chunking");
+
+/// The chunks of a fixture and everything they reference.
+struct Output {
+    /// The code of the text assets, by their path relative to the fixture.
+    assets: BTreeMap<String, String>,
+    /// The descriptions of the references between the assets.
+    references: Vec<String>,
+}
+
+impl Output {
+    /// The code of all assets whose path ends with `suffix`.
+    fn code(&self, suffix: &str) -> String {
+        self.assets
+            .iter()
+            .filter(|(path, _)| path.ends_with(suffix))
+            .map(|(_, code)| code.as_str())
+            .collect()
+    }
+}
+
+/// The directory of the fixture `tests/chunking/{name}`.
+fn fixture_path(name: &str) -> FileSystemPathVc {
+    let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+    fs.root()
+        .join(&format!("crates/turbopack-tests/tests/chunking/{name}"))
+}
+
+/// Chunks `tests/chunking/{name}/input/index.js` with the chunking context
+/// that `configure` builds, and walks the chunks and their references.
+async fn chunk_fixture(
+    name: &str,
+    configure: impl FnOnce(DevChunkingContextBuilder) -> DevChunkingContextBuilder,
+) -> Result<Output> {
+    let path = fixture_path(name);
+    let root = path.root();
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: "Chrome 102".to_string(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    );
+    let context = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        environment,
+        ModuleOptionsContext::default().cell(),
+        ResolveOptionsContext {
+            enable_node_modules: true,
+            ..Default::default()
+        }
+        .cell(),
+    );
+    let chunking_context = configure(DevChunkingContextVc::builder(
+        root,
+        path,
+        path.join("output"),
+        path.join("static"),
+        environment,
+    ))
+    .build();
+
+    let module = context.process(
+        SourceAssetVc::new(path.join("input/index.js")).into(),
+        Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+    );
+    let module = EcmascriptModuleAssetVc::resolve_from(module)
+        .await?
+        .expect("the entry is an ecmascript module");
+    let chunk = module.as_evaluated_chunk(chunking_context, None);
+
+    let path_value = path.await?;
+    let mut output = Output {
+        assets: BTreeMap::new(),
+        references: Vec::new(),
+    };
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([chunk.as_asset()]);
+    while let Some(asset) = queue.pop_front() {
+        let asset_path = asset.path().await?;
+        if !seen.insert(asset_path.path.clone()) {
+            continue;
+        }
+        let Some(relative_path) = path_value.get_path_to(&asset_path) else {
+            // Source assets of the fixture and of node_modules.
+            continue;
+        };
+        if let AssetContent::File(file) = &*asset.content().await? {
+            if let FileContent::Content(file) = &*file.await? {
+                if let Ok(code) = file.content().to_str() {
+                    output
+                        .assets
+                        .insert(relative_path.to_string(), code.to_string());
+                }
+            }
+        }
+        for reference in asset.references().await?.iter() {
+            output
+                .references
+                .push(reference.to_string().await?.clone_value());
+            queue.extend(
+                reference
+                    .resolve_reference()
+                    .primary_assets()
+                    .await?
+                    .iter()
+                    .copied(),
+            );
+        }
+    }
+    Ok(output)
+}
+
+/// Runs `test` in a new turbo tasks instance.
+fn run<F: Future<Output = Result<()>> + Send + 'static>(test: impl FnOnce() -> F) {
+    register();
+    let future = test();
+    util::run(async {
+        let tt = TurboTasks::new(MemoryBackend::default());
+        tt.run_once(future).await
+    });
+}
+
+#[test]
+fn lazy_compilation() {
+    run(|| async {
+        let is_lazy = |output: &Output| {
+            output
+                .references
+                .iter()
+                .any(|reference| reference.starts_with("lazy chunks of"))
+        };
+
+        let output = chunk_fixture("lazy_compilation", |builder| builder).await?;
+        assert!(!is_lazy(&output));
+
+        let output =
+            chunk_fixture("lazy_compilation", |builder| builder.lazy_compilation()).await?;
+        assert!(is_lazy(&output));
+        // The chunks of the import are still found when they are requested.
+        assert!(output.code(".js").contains("\"lazy\""));
+        Ok(())
+    });
+}
+
+#[test]
+fn input_source_map() {
+    run(|| async {
+        let output = chunk_fixture("input_source_map", |builder| builder).await?;
+        let source_maps = output.code(".js.map");
+        // Both the source map next to the file and the inline one are chained
+        // into the source map of the chunk.
+        assert!(source_maps.contains("original.ts"));
+        assert!(source_maps.contains("export const answer: number = 42;"));
+        assert!(source_maps.contains("inline-original.ts"));
+        Ok(())
+    });
+}
+
+#[test]
+fn chunk_banner() {
+    run(|| async {
+        let output = chunk_fixture("banner", |builder| {
+            builder
+                .chunk_banner(ChunkBannerRule {
+                    pattern: None,
+                    banner: Some("/* all chunks */".to_string()),
+                    footer: None,
+                })
+                .chunk_banner(ChunkBannerRule {
+                    pattern: Some("**/*.css".to_string()),
+                    banner: Some("/* css chunks */".to_string()),
+                    footer: None,
+                })
+                .chunk_banner(ChunkBannerRule {
+                    pattern: Some("**/*.js".to_string()),
+                    banner: Some("/* js chunks */".to_string()),
+                    footer: Some("/* end of js chunks */".to_string()),
+                })
+        })
+        .await?;
+        let mut chunks = output
+            .assets
+            .iter()
+            .filter(|(path, _)| path.ends_with(".js"))
+            .peekable();
+        assert!(chunks.peek().is_some());
+        for (path, code) in chunks {
+            // Banners are added in the order of the rules.
+            assert!(
+                code.starts_with("/* all chunks */\n/* js chunks */\n"),
+                "{path} doesn't start with the banners"
+            );
+            assert!(!code.contains("/* css chunks */"));
+            assert!(code.contains("\n/* end of js chunks */"));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn runtime_chunking() {
+    run(|| async {
+        let runtime_references = |output: &Output| {
+            output
+                .references
+                .iter()
+                .filter(|reference| reference.starts_with("runtime "))
+                .count()
+        };
+        let has_embedded_runtime = |output: &Output| {
+            output.assets.iter().any(|(path, code)| {
+                !path.contains("turbopack-runtime-") && code.contains("CHUNK_BASE_PATH")
+            })
+        };
+
+        let output = chunk_fixture("runtime", |builder| builder).await?;
+        assert_eq!(runtime_references(&output), 0);
+        assert!(has_embedded_runtime(&output));
+
+        for runtime_chunking in [RuntimeChunking::Separate, RuntimeChunking::Inline] {
+            let output = chunk_fixture("runtime", move |builder| {
+                builder.runtime_chunking(runtime_chunking)
+            })
+            .await?;
+            assert_eq!(runtime_references(&output), 1, "{runtime_chunking:?}");
+            assert!(!has_embedded_runtime(&output), "{runtime_chunking:?}");
+            // The runtime is still emitted, for consumers that don't inline it.
+            assert!(output.code(".js").contains("CHUNK_BASE_PATH"));
+            // The runtime chunk has a source map of its own.
+            let (path, code) = output
+                .assets
+                .iter()
+                .find(|(path, _)| path.contains("turbopack-runtime-") && path.ends_with(".js"))
+                .unwrap();
+            let file_name = path.rsplit('/').next().unwrap();
+            assert!(code.ends_with(&format!("//# sourceMappingURL={file_name}.map")));
+            assert!(output
+                .code(&format!("{file_name}.map"))
+                .contains("/__turbopack__/runtime.js"));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn es_module_output_format() {
+    run(|| async {
+        let output = chunk_fixture("es_module", |builder| builder).await?;
+        let code = output.code(".js");
+        assert!(code.contains("(self.TURBOPACK = self.TURBOPACK || []).push("));
+        assert!(!code.contains("export {"));
+
+        let output = chunk_fixture("es_module", |builder| {
+            builder.output_format(OutputFormat::EsModule)
+        })
+        .await?;
+        let code = output.code(".js");
+        assert!(code.contains("(globalThis.TURBOPACK = globalThis.TURBOPACK || []).push("));
+        // The chunk exports the exports of its entry.
+        assert!(code.contains("export { __turbopack_exports__ as default };"));
+
+        // The chunk imports the separate runtime, so it's loaded first.
+        let output = chunk_fixture("es_module", |builder| {
+            builder
+                .output_format(OutputFormat::EsModule)
+                .runtime_chunking(RuntimeChunking::Separate)
+        })
+        .await?;
+        assert!(output
+            .code(".js")
+            .lines()
+            .any(|line| line.starts_with("import \"./turbopack-runtime-")));
+        Ok(())
+    });
+}
+
+#[test]
+fn node_server_source_paths() {
+    run(|| async {
+        let output = chunk_fixture("node_server", |builder| {
+            builder.node_server(fixture_path("node_server"))
+        })
+        .await?;
+        let code = output.code(".js");
+        // Modules see the paths of their source files, relative to the
+        // project root, which is located relative to the chunk.
+        assert!(code.contains(
+            "__filename = \
+             __turbopack_external_require__(\"path\").join(globalThis.__turbopack_project_root__, \
+             \"input/index.js\")"
+        ));
+        assert!(code.contains(
+            "globalThis.__turbopack_project_root__ ??= require(\"path\").join(__dirname, \"..\");"
+        ));
+
+        let output = chunk_fixture("node_server", |builder| builder).await?;
+        assert!(!output.code(".js").contains("__turbopack_project_root__"));
+        Ok(())
+    });
+}
+
+#[test]
+fn single_file() {
+    run(|| async {
+        let js_chunks = |output: &Output| {
+            output
+                .assets
+                .keys()
+                .filter(|path| path.ends_with(".js"))
+                .count()
+        };
+
+        // By default, the import is loaded from a chunk of its own.
+        let output = chunk_fixture("single_file", |builder| builder).await?;
+        assert!(js_chunks(&output) > 1);
+
+        let output = chunk_fixture("single_file", |builder| {
+            builder
+                .single_file()
+                .runtime_chunking(RuntimeChunking::Separate)
+        })
+        .await?;
+        assert_eq!(js_chunks(&output), 1);
+        let code = output.code(".js");
+        // The import and the runtime are part of the chunk.
+        assert!(code.contains("\"lazy\""));
+        assert!(code.contains("CHUNK_BASE_PATH"));
+        assert!(!output
+            .references
+            .iter()
+            .any(|reference| reference.starts_with("runtime ")));
+        Ok(())
+    });
+}
@@ -0,0 +1,4 @@
+import { answer } from "./compiled.js";
+import { inline } from "./inline.js";
+
+console.log(answer, inline);
@@ -0,0 +1,2 @@
+export const inline = "inline";
+//# sourceMappingURL=data:application/json;base64,eyJ2ZXJzaW9uIjogMywgImZpbGUiOiAiaW5saW5lLmpzIiwgInNvdXJjZXMiOiBbImlubGluZS1vcmlnaW5hbC50cyJdLCAic291cmNlc0NvbnRlbnQiOiBbImV4cG9ydCBjb25zdCBpbmxpbmU6IHN0cmluZyA9IFwiaW5saW5lXCI7XG4iXSwgIm5hbWVzIjogW10sICJtYXBwaW5ncyI6ICJBQUFBIn0=
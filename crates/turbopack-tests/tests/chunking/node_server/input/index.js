@@ -0,0 +1 @@
+console.log(__filename, __dirname);
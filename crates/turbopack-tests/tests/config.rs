@@ -0,0 +1,86 @@
+#![cfg(test)]
+
+//! Tests for loading the project config and applying it to the resolve
+//! options. The configs are built in memory, nothing is read from disk.
+
+mod util;
+
+use anyhow::Result;
+use serde_json::json;
+use turbo_tasks::{primitives::JsonValueVc, TurboTasks};
+use turbo_tasks_fs::DiskFileSystemVc;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::resolve_options_context::ResolveOptionsContextVc;
+use turbopack_core::config::{parse_config_value, ResolveConfig};
+use util::WORKSPACE_ROOT;
+
+util::register!("config");
+
+#[test]
+fn keeps_last_valid_config() {
+    util::run(run_last_valid_config());
+}
+
+async fn run_last_valid_config() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let path = fs.root().join("app/turbopack.config.js");
+        let valid = JsonValueVc::cell(json!({ "resolve": { "alias": { "lodash": "lodash-es" } } }));
+        let invalid = JsonValueVc::cell(json!({ "resolv": {} }));
+
+        let config = parse_config_value(path, valid).await?;
+        assert_eq!(config.resolve.alias["lodash"], "lodash-es");
+        // While the config is invalid, e.g. while it's edited, the last valid
+        // config is used.
+        let config = parse_config_value(path, invalid).await?;
+        assert_eq!(config.resolve.alias["lodash"], "lodash-es");
+        // Without a valid config, the default config is used.
+        let other_path = fs.root().join("other/turbopack.config.js");
+        let config = parse_config_value(other_path, invalid).await?;
+        assert!(config.resolve.alias.is_empty());
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn applies_resolve_config() {
+    util::run(run_resolve_config());
+}
+
+async fn run_resolve_config() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let config: ResolveConfig = serde_json::from_str(
+            r#"{
+                "alias": { "lodash": "lodash-es", "@/*": "./src/*" },
+                "conditions": ["react-native"],
+                "platformSuffixes": [".ios"],
+                "assets": { "og-image": "./public/og.png" }
+            }"#,
+        )?;
+        let config = config.cell();
+
+        // The aliases keep the order of the config.
+        assert_eq!(*config.alias_keys().await?, ["lodash", "@/*"]);
+        assert_eq!(config.alias("@/*").await?.as_deref(), Some("./src/*"));
+        assert_eq!(config.alias("react").await?.as_deref(), None);
+
+        let context = ResolveOptionsContextVc::default()
+            .with_config(config, fs.root().join("app"))
+            .await?;
+        assert_eq!(context.custom_conditions, ["react-native"]);
+        assert_eq!(context.platform_suffixes, [".ios"]);
+        assert!(context.import_map.is_some());
+        let manifest = context.asset_manifest.unwrap().await?;
+        assert_eq!(manifest.assets["og-image"], "./public/og.png");
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,666 @@
+#![cfg(test)]
+
+//! Snapshot tests for content sources. Each fixture in
+//! `tests/content_source/*/` serves the files in `input/public` and renders
+//! `input/render.js` in Node.js for the `/render` route. The requests in
+//! `requests.json` are resolved like the dev server does, and the status,
+//! headers and body of each response are compared with `snapshot.txt`.
+
+mod util;
+
+use std::{collections::HashMap, env, fmt::Write, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use hyper::{HeaderMap, Uri};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+use similar::TextDiff;
+use test_generator::test_resources;
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    NothingVc, TransientInstance, TurboTasks, Value,
+};
+use turbo_tasks_fs::{
+    json::parse_json_with_source_context,
+    rope::{Rope, RopeVc},
+    util::sys_to_unix,
+    DiskFileSystemVc, File, FileContent, FileSystemPathVc,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::{
+        chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+        EcmascriptModuleAssetVc, EcmascriptOptions,
+    },
+    module_options::ModuleOptionsContext,
+    resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_cli_utils::{
+    diagnostics::DiagnosticsFormat,
+    issue::{ConsoleUi, ConsoleUiVc, LogOptions},
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc},
+    chunk::{dev::DevChunkingContextVc, ChunkingContextVc},
+    context::AssetContextVc,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    issue::IssueSeverity,
+    source_asset::SourceAssetVc,
+    source_map::{GenerateSourceMap, GeneratedCodeSourceMapVc, SourceMapVc},
+    version::VersionedContent,
+};
+use turbopack_dev_server::{
+    html::DevHtmlAssetVc,
+    source::{
+        asset_graph::AssetGraphContentSourceVc,
+        combined::CombinedContentSourceVc,
+        headers::HeaderValue,
+        query::QueryValue,
+        request::SourceRequest,
+        resolve::{resolve_source_request, ResolveSourceRequestResult},
+        specificity::SpecificityVc,
+        static_assets::StaticAssetsContentSourceVc,
+        trace::SourceTraceCollector,
+        Body, ContentSource, ContentSourceContent, ContentSourceContentVc, ContentSourceData,
+        ContentSourceDataFilter, ContentSourceDataVary, ContentSourceResultVc, ContentSourceVc,
+        NeededData, NoContentSourceVc, RequestOverrides, RewriteVc,
+    },
+};
+use turbopack_node::{
+    render::rendered_source::create_node_rendered_source,
+    route_matcher::{ParamsVc, RouteMatcher, RouteMatcherVc},
+    source_map::StackTraceContentSourceVc,
+    NodeEntry, NodeEntryVc, NodeRenderingEntry, NodeRenderingEntryVc,
+};
+use util::WORKSPACE_ROOT;
+
+util::register!(
+    "content_source",
+    turbopack_dev_server::register,
+    turbopack_node::register,
+    turbopack_cli_utils::register
+);
+
+// Updates the existing snapshots with the actual responses of this run.
+// `UPDATE=1 cargo test -p turbopack-tests -- content_source`
+static UPDATE: Lazy<bool> = Lazy::new(|| env::var("UPDATE").unwrap_or_default() == "1");
+
+#[derive(Debug, Deserialize)]
+struct TestRequest {
+    #[serde(default = "default_method")]
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+#[test_resources("crates/turbopack-tests/tests/content_source/*/")]
+fn test(resource: &'static str) {
+    util::run(run(resource));
+}
+
+async fn run(resource: &'static str) -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let task = tt.spawn_once_task(async move {
+        run_test(resource).await?;
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await?;
+
+    Ok(())
+}
+
+async fn run_test(resource: &str) -> Result<()> {
+    let test_path = PathBuf::from(WORKSPACE_ROOT.as_str()).join(resource);
+    let requests: Vec<TestRequest> =
+        parse_json_with_source_context(&fs::read_to_string(test_path.join("requests.json"))?)?;
+
+    let project_fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+    let project_root = project_fs.root();
+    let path = project_root.join(&sys_to_unix(resource));
+    let name = test_path.file_name().unwrap().to_str().unwrap();
+    let source = fixture_source(project_root, path, name);
+
+    let console_ui = console_ui();
+
+    let mut actual = String::new();
+    for request in requests {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &request.headers {
+            headers.insert(
+                hyper::header::HeaderName::try_from(name.as_str())?,
+                value.parse()?,
+            );
+        }
+        let source_request = SourceRequest {
+            method: request.method.clone(),
+            uri: Uri::try_from(request.path.as_str())?,
+            headers,
+            body: Body::new(Vec::new()),
+            remote_addr: None,
+            correlation_id: None,
+            trace: None,
+        };
+        let result =
+            resolve_source_request(source, TransientInstance::new(source_request), console_ui);
+        writeln!(actual, "# {} {}", request.method, request.path)?;
+        write_response(&mut actual, &*result.strongly_consistent().await?).await?;
+        writeln!(actual)?;
+    }
+
+    diff(&test_path.join("snapshot.txt"), actual.trim_end())
+}
+
+fn console_ui() -> ConsoleUiVc {
+    ConsoleUi::new(LogOptions {
+        current_dir: PathBuf::from(WORKSPACE_ROOT.as_str()),
+        show_all: true,
+        log_detail: true,
+        log_level: IssueSeverity::Error,
+        diagnostics_format: DiagnosticsFormat::Human,
+    })
+    .cell()
+}
+
+/// The content sources under test: static files and a Node.js rendered route.
+fn fixture_source(
+    project_root: FileSystemPathVc,
+    path: FileSystemPathVc,
+    name: &str,
+) -> ContentSourceVc {
+    let env = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::NodeJsLambda(
+            NodeJsEnvironment::default().into(),
+        )),
+        Value::new(EnvironmentIntention::ServerRendering),
+    );
+    let context: AssetContextVc = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        env,
+        ModuleOptionsContext::default().cell(),
+        ResolveOptionsContext {
+            enable_node_modules: true,
+            enable_node_native_modules: true,
+            ..Default::default()
+        }
+        .cell(),
+    )
+    .into();
+
+    let intermediate_output_path = project_root
+        .join("target/turbopack-tests/content_source")
+        .join(name);
+    let chunking_context = DevChunkingContextVc::builder(
+        project_root,
+        intermediate_output_path,
+        intermediate_output_path.join("chunks"),
+        intermediate_output_path.join("assets"),
+        env,
+    )
+    .build();
+
+    let entry = FixtureEntry {
+        module: EcmascriptModuleAssetVc::new(
+            SourceAssetVc::new(path.join("input/render.js")).into(),
+            context,
+            Value::new(EcmascriptModuleAssetType::Ecmascript),
+            EcmascriptInputTransformsVc::cell(vec![]),
+            Value::new(EcmascriptOptions::default()),
+            env,
+        ),
+        chunking_context,
+        intermediate_output_path,
+    }
+    .cell();
+
+    let server_root = project_root.join("server");
+    let rendered = create_node_rendered_source(
+        SpecificityVc::exact(),
+        server_root,
+        PrefixMatcherVc::new(StringVc::cell("render".to_string())).into(),
+        StringVc::cell("/render".to_string()),
+        entry.into(),
+        EcmascriptChunkPlaceablesVc::cell(vec![]),
+        DevHtmlAssetVc::new(server_root.join("fallback.html"), vec![]),
+    );
+    let public = StaticAssetsContentSourceVc::new(String::new(), path.join("input/public")).into();
+    CombinedContentSourceVc::new(vec![public, rendered]).into()
+}
+
+async fn write_response(out: &mut String, result: &ResolveSourceRequestResult) -> Result<()> {
+    match result {
+        ResolveSourceRequestResult::NotFound => writeln!(out, "not found")?,
+        ResolveSourceRequestResult::Static(content) => {
+            let content = content.await?;
+            writeln!(out, "status: {}", content.status_code)?;
+            for (name, value) in content.headers.await?.iter() {
+                writeln!(out, "{name}: {value}")?;
+            }
+            writeln!(out)?;
+            match &*content.content.content().await? {
+                AssetContent::File(file) => match &*file.await? {
+                    FileContent::Content(file) => {
+                        writeln!(out, "{}", file.content().to_str()?.trim_end())?
+                    }
+                    FileContent::NotFound => writeln!(out, "(file not found)")?,
+                },
+                AssetContent::Redirect { target, link_type } => {
+                    writeln!(out, "(redirect to {target} with {link_type:?})")?
+                }
+            }
+        }
+        ResolveSourceRequestResult::HttpProxy(proxy) => {
+            let proxy = proxy.await?;
+            writeln!(out, "status: {}", proxy.status)?;
+            for pair in proxy.headers.chunks(2) {
+                writeln!(out, "{}: {}", pair[0], pair[1])?;
+            }
+            writeln!(out)?;
+            writeln!(out, "{}", proxy.body.to_str()?.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+fn diff(path: &PathBuf, actual: &str) -> Result<()> {
+    let expected = fs::read_to_string(path).ok();
+    if expected.as_deref().map(str::trim_end) == Some(actual) {
+        return Ok(());
+    }
+    if *UPDATE {
+        fs::write(path, format!("{actual}\n"))
+            .with_context(|| format!("writing {}", path.display()))?;
+        println!("updated contents of {}", path.display());
+        return Ok(());
+    }
+    let expected = expected.unwrap_or_default();
+    eprintln!(
+        "{}",
+        TextDiff::from_lines(expected.trim_end(), actual)
+            .unified_diff()
+            .context_radius(3)
+            .header("expected", "actual")
+    );
+    bail!("{} did not match", path.display())
+}
+
+#[turbo_tasks::value(shared)]
+struct FixtureEntry {
+    module: EcmascriptModuleAssetVc,
+    chunking_context: ChunkingContextVc,
+    intermediate_output_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NodeEntry for FixtureEntry {
+    #[turbo_tasks::function]
+    fn entry(&self, _data: Value<ContentSourceData>) -> NodeRenderingEntryVc {
+        NodeRenderingEntry {
+            module: self.module,
+            chunking_context: self.chunking_context,
+            intermediate_output_path: self.intermediate_output_path,
+            output_root: self.intermediate_output_path,
+            max_message_size: None,
+        }
+        .cell()
+    }
+}
+
+/// Matches paths below a prefix, e.g. `render` and `render/about`.
+#[turbo_tasks::value]
+struct PrefixMatcher {
+    prefix: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl PrefixMatcherVc {
+    #[turbo_tasks::function]
+    fn new(prefix: StringVc) -> Self {
+        Self::cell(PrefixMatcher { prefix })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl RouteMatcher for PrefixMatcher {
+    #[turbo_tasks::function]
+    async fn matches(&self, path: &str) -> Result<BoolVc> {
+        let prefix = self.prefix.await?;
+        Ok(BoolVc::cell(
+            path == *prefix || path.starts_with(&format!("{prefix}/")),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    async fn params(self_vc: PrefixMatcherVc, path: &str) -> Result<ParamsVc> {
+        Ok(ParamsVc::cell(if *self_vc.matches(path).await? {
+            Some(Default::default())
+        } else {
+            None
+        }))
+    }
+}
+
+#[test]
+fn symbolicates_stack_traces() {
+    util::run(run_stack_traces());
+}
+
+async fn run_stack_traces() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let task = tt.spawn_once_task(async move {
+        let project_fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let root = project_fs.root();
+        let asset = MappedAssetVc::new(root.join("chunks/compiled.js"));
+        let source: ContentSourceVc = StackTraceContentSourceVc::new(
+            AssetGraphContentSourceVc::new_eager(root, asset.into()).into(),
+        )
+        .into();
+
+        let body = json!({
+            "frames": [
+                {
+                    "file": "http://localhost:3000/chunks/compiled.js",
+                    "lineNumber": 2,
+                    "column": 1,
+                    "methodName": "render",
+                },
+                {
+                    "file": "http://localhost:3000/chunks/missing.js",
+                    "lineNumber": 1,
+                    "column": 1,
+                },
+            ],
+        });
+        let response = stack_trace_request(source, "POST", body.to_string()).await?;
+        let response: serde_json::Value = serde_json::from_str(&response.unwrap())?;
+        // Frames which can't be traced are `null`, so the frames stay in order.
+        assert_eq!(
+            response,
+            json!({
+                "frames": [
+                    {
+                        "file": "/__turbopack__/compiled.js",
+                        "lineNumber": 2,
+                        "column": 1,
+                        "methodName": "render",
+                    },
+                    null,
+                ],
+            })
+        );
+
+        assert!(stack_trace_request(source, "GET", String::new())
+            .await?
+            .is_none());
+        assert!(stack_trace_request(source, "POST", "not json".to_string())
+            .await?
+            .is_none());
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await?;
+
+    Ok(())
+}
+
+/// Sends a request to the stack trace endpoint. Returns the body of the
+/// response, or `None` when it's not found.
+async fn stack_trace_request(
+    source: ContentSourceVc,
+    method: &str,
+    body: String,
+) -> Result<Option<String>> {
+    let request = SourceRequest {
+        method: method.to_string(),
+        uri: Uri::from_static("/__stack_frames"),
+        headers: HeaderMap::new(),
+        body: Body::new(vec![hyper::body::Bytes::from(body).into()]),
+        remote_addr: None,
+        correlation_id: None,
+        trace: None,
+    };
+    static_response(source, request).await
+}
+
+/// Resolves `request` like the dev server does. Returns the body of the
+/// response, or `None` when it's not static content.
+async fn static_response(
+    source: ContentSourceVc,
+    request: SourceRequest,
+) -> Result<Option<String>> {
+    let result = resolve_source_request(source, TransientInstance::new(request), console_ui());
+    let ResolveSourceRequestResult::Static(content) = &*result.strongly_consistent().await? else {
+        return Ok(None);
+    };
+    let AssetContent::File(file) = &*content.await?.content.content().await? else {
+        bail!("the response is not a file");
+    };
+    let FileContent::Content(file) = &*file.await? else {
+        bail!("the response is not found");
+    };
+    Ok(Some(file.content().to_str()?.to_string()))
+}
+
+const MAPPED_CODE: &str = "function render() {\n  throw new Error();\n}\n";
+
+/// A compiled asset whose source map maps every line to the same line of
+/// `/__turbopack__/compiled.js`.
+#[turbo_tasks::value]
+struct MappedAsset {
+    path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl MappedAssetVc {
+    #[turbo_tasks::function]
+    fn new(path: FileSystemPathVc) -> Self {
+        Self::cell(MappedAsset { path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for MappedAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        File::from(MAPPED_CODE).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for MappedAsset {
+    #[turbo_tasks::function]
+    fn generate_source_map(&self) -> SourceMapVc {
+        GeneratedCodeSourceMapVc::new("compiled.js", RopeVc::cell(Rope::from(MAPPED_CODE)))
+            .generate_source_map()
+    }
+}
+
+#[test]
+fn rewrites_with_request_overrides() {
+    util::run(run_request_overrides());
+}
+
+async fn run_request_overrides() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let task = tt.spawn_once_task(async move {
+        let source: ContentSourceVc = RewritingSourceVc::new(EchoSourceVc::new().into()).into();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-locale", "en".parse()?);
+        let request = SourceRequest {
+            method: "GET".to_string(),
+            uri: Uri::from_static("/page"),
+            headers,
+            body: Body::new(Vec::new()),
+            remote_addr: None,
+            correlation_id: None,
+            trace: None,
+        };
+        // The overrides replace the params and headers of the same name.
+        assert_eq!(
+            static_response(source, request).await?.as_deref(),
+            Some("echo lang=fr page=1 x-locale=fr")
+        );
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await?;
+
+    Ok(())
+}
+
+#[test]
+fn traces_resolving_requests() {
+    util::run(run_trace());
+}
+
+async fn run_trace() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let task = tt.spawn_once_task(async move {
+        let source: ContentSourceVc = CombinedContentSourceVc::new(vec![
+            NoContentSourceVc::new().into(),
+            RewritingSourceVc::new(EchoSourceVc::new().into()).into(),
+        ])
+        .into();
+        let trace = SourceTraceCollector::default();
+        let request = SourceRequest {
+            method: "GET".to_string(),
+            uri: Uri::from_static("/page"),
+            headers: HeaderMap::new(),
+            body: Body::new(Vec::new()),
+            remote_addr: None,
+            correlation_id: None,
+            trace: Some(trace.clone()),
+        };
+        assert!(static_response(source, request).await?.is_some());
+
+        let trace = serde_json::to_value(&*trace.lock().unwrap())?;
+        let steps = trace.as_array().unwrap();
+        assert_eq!(steps.len(), 3, "{trace:#}");
+        // The combined source is explained with the sources it consulted.
+        assert_eq!(steps[0]["source"], "combined content source");
+        assert_eq!(steps[0]["path"], "page");
+        assert_eq!(steps[0]["outcome"]["type"], "result");
+        assert_eq!(steps[0]["rewrite"], "/echo?lang=fr&page=1");
+        let consulted = steps[0]["consulted"].as_array().unwrap();
+        assert_eq!(consulted.len(), 2, "{trace:#}");
+        assert_eq!(consulted[0]["outcome"]["type"], "notFound");
+        assert_eq!(consulted[0]["selected"], false);
+        assert_eq!(consulted[1]["outcome"]["type"], "result");
+        assert_eq!(consulted[1]["selected"], true);
+        // The echo source asks for data, and is asked again with it.
+        assert_eq!(steps[1]["path"], "echo");
+        assert_eq!(
+            steps[1]["outcome"],
+            json!({ "type": "needData", "vary": ["query", "headers"] })
+        );
+        assert_eq!(steps[2]["data"], json!(["query", "headers"]));
+        assert_eq!(steps[2]["outcome"]["type"], "result");
+        assert!(steps[2].get("consulted").is_none());
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await?;
+
+    Ok(())
+}
+
+/// Rewrites every request to `/echo?lang=en&page=1` of `echo`, with the `lang`
+/// param and the `x-locale` header overridden.
+#[turbo_tasks::value(shared)]
+struct RewritingSource {
+    echo: ContentSourceVc,
+}
+
+#[turbo_tasks::value_impl]
+impl RewritingSourceVc {
+    #[turbo_tasks::function]
+    fn new(echo: ContentSourceVc) -> Self {
+        Self::cell(RewritingSource { echo })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for RewritingSource {
+    #[turbo_tasks::function]
+    fn get(&self, _path: &str, _data: Value<ContentSourceData>) -> ContentSourceResultVc {
+        let overrides = RequestOverrides {
+            headers: vec![("x-locale".to_string(), "fr".to_string())],
+            params: vec![("lang".to_string(), "fr".to_string())],
+        };
+        let rewrite = RewriteVc::new_with_overrides(
+            "/echo?lang=en&page=1".to_string(),
+            self.echo,
+            overrides.cell(),
+        );
+        ContentSourceResultVc::exact(ContentSourceContent::Rewrite(rewrite).cell().into())
+    }
+}
+
+/// Responds with the path, the `lang` and `page` params and the `x-locale`
+/// header of the request.
+#[turbo_tasks::value(shared)]
+struct EchoSource;
+
+#[turbo_tasks::value_impl]
+impl EchoSourceVc {
+    #[turbo_tasks::function]
+    fn new() -> Self {
+        Self::cell(EchoSource)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for EchoSource {
+    #[turbo_tasks::function]
+    fn get(
+        self_vc: EchoSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> ContentSourceResultVc {
+        let (Some(query), Some(headers)) = (&data.query, &data.headers) else {
+            return ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self_vc.into(),
+                path: path.to_string(),
+                vary: ContentSourceDataVary {
+                    query: Some(ContentSourceDataFilter::All),
+                    headers: Some(ContentSourceDataFilter::All),
+                    ..Default::default()
+                },
+            }));
+        };
+        let param = |name: &str| match query.get(name) {
+            Some(QueryValue::String(value)) => value.as_str(),
+            _ => "",
+        };
+        let locale = match headers.get("x-locale") {
+            Some(HeaderValue::SingleString(value)) => value.as_str(),
+            _ => "",
+        };
+        let body = format!(
+            "{path} lang={} page={} x-locale={locale}",
+            param("lang"),
+            param("page")
+        );
+        ContentSourceResultVc::exact(
+            ContentSourceContentVc::static_content(AssetContentVc::from(File::from(body)).into())
+                .into(),
+        )
+    }
+}
@@ -0,0 +1,43 @@
+// Renders the request with the IPC protocol of turbopack-node: packets are a
+// 4-byte big-endian length followed by JSON.
+import { createConnection } from "node:net";
+
+const socket = createConnection(parseInt(process.argv[2], 10), "127.0.0.1");
+
+function send(message) {
+  const packet = Buffer.from(JSON.stringify(message), "utf8");
+  const length = Buffer.alloc(4);
+  length.writeUInt32BE(packet.length);
+  socket.write(length);
+  socket.write(packet);
+}
+
+function handle(message) {
+  if (message.type !== "headers") {
+    return;
+  }
+  const { method, url, params } = message.data;
+  send({
+    type: "response",
+    statusCode: 200,
+    headers: [
+      ["content-type", "text/html; charset=utf-8"],
+      ["x-rendered-by", "fixture"],
+    ],
+    body: `<h1>${method} ${url}</h1>\n<pre>${JSON.stringify(params)}</pre>`,
+  });
+}
+
+let buffer = Buffer.alloc(0);
+socket.on("data", (chunk) => {
+  buffer = Buffer.concat([buffer, chunk]);
+  while (buffer.length >= 4) {
+    const length = buffer.readUInt32BE(0);
+    if (buffer.length < 4 + length) {
+      break;
+    }
+    const packet = buffer.subarray(4, 4 + length);
+    buffer = buffer.subarray(4 + length);
+    handle(JSON.parse(packet.toString("utf8")));
+  }
+});
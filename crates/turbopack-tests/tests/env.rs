@@ -0,0 +1,87 @@
+#![cfg(test)]
+
+//! Tests for reading env variables through [EnvVc], on top of custom values.
+
+mod util;
+
+use anyhow::Result;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_env::{CommandLineProcessEnvVc, CustomProcessEnvVc, EnvMapVc, EnvVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::ecmascript::CompileTimeDefineValue;
+use turbopack_env::process_env_defines;
+
+util::register!("env", turbopack_env::register);
+
+/// The env of the process with the custom `values`.
+fn env(values: &[(&str, &str)]) -> EnvVc {
+    EnvVc::new(
+        CustomProcessEnvVc::new(
+            CommandLineProcessEnvVc::new().into(),
+            EnvMapVc::cell(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            ),
+        )
+        .into(),
+    )
+}
+
+#[test]
+fn reads_env() {
+    util::run(run());
+}
+
+async fn run() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let env = env(&[
+            ("TURBOPACK_TEST_API_URL", "https://example.com"),
+            ("TURBOPACK_TEST_API_KEY", "secret"),
+            ("TURBOPACK_TEST_MODE", "test"),
+        ]);
+
+        assert_eq!(
+            env.read("turbopack_test_mode").await?.as_deref(),
+            Some("test")
+        );
+        assert_eq!(env.read("TURBOPACK_TEST_MISSING").await?.as_deref(), None);
+
+        let api = env.read_prefix("turbopack_test_api_").await?;
+        let mut keys = api.keys().map(|key| key.as_str()).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["TURBOPACK_TEST_API_KEY", "TURBOPACK_TEST_API_URL"]);
+
+        let defines = process_env_defines(
+            env,
+            vec![
+                "TURBOPACK_TEST_API_URL".to_string(),
+                "TURBOPACK_TEST_MISSING".to_string(),
+            ],
+        )
+        .await?;
+        let defines = defines
+            .iter()
+            .map(|(path, value)| (path.join("."), value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            defines,
+            [
+                (
+                    "process.env.TURBOPACK_TEST_API_URL".to_string(),
+                    CompileTimeDefineValue::String("https://example.com".to_string())
+                ),
+                (
+                    "process.env.TURBOPACK_TEST_MISSING".to_string(),
+                    CompileTimeDefineValue::Undefined
+                ),
+            ]
+        );
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,276 @@
+#![cfg(test)]
+
+//! Tests for the reasons, the search and the addresses of introspectables, on
+//! graphs of test introspectables, and for the order of the children of
+//! assets, on the modules in `tests/introspect`.
+
+mod util;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, TurboTasks, Value};
+use turbo_tasks_fs::DiskFileSystemVc;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext, resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc, ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::Asset,
+    context::AssetContext,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    introspect::{
+        asset::children_from_asset_references,
+        reasons::{introspectable_reasons, ReasonStep},
+        search::{
+            resolve_address, search_introspectable, AddressStep, IntrospectableQuery,
+            MAX_SEARCH_MATCHES,
+        },
+        Introspectable, IntrospectableChildrenVc, IntrospectableVc,
+    },
+    reference::all_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use util::WORKSPACE_ROOT;
+
+util::register!("introspect");
+
+#[turbo_tasks::value]
+struct TestIntrospectable {
+    title: String,
+    children: Vec<(String, IntrospectableVc)>,
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for TestIntrospectable {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("test".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(self.title.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn children(&self) -> IntrospectableChildrenVc {
+        IntrospectableChildrenVc::cell(
+            self.children
+                .iter()
+                .map(|(via, child)| (StringVc::cell(via.clone()), *child))
+                .collect(),
+        )
+    }
+}
+
+fn node(title: &str, children: Vec<(&str, IntrospectableVc)>) -> IntrospectableVc {
+    TestIntrospectable {
+        title: title.to_string(),
+        children: children
+            .into_iter()
+            .map(|(via, child)| (via.to_string(), child))
+            .collect(),
+    }
+    .cell()
+    .into()
+}
+
+fn reason_step(title: &str, via: &str) -> ReasonStep {
+    ReasonStep {
+        ty: "test".to_string(),
+        title: title.to_string(),
+        via: via.to_string(),
+    }
+}
+
+#[test]
+fn explains_reachable_modules() {
+    util::run(run());
+}
+
+async fn run() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let target = node("target.js", vec![]);
+        let a = node("a.js", vec![("reference", target)]);
+        let b = node("b.js", vec![("reference", target)]);
+        let index = node("index.js", vec![("reference", a), ("reference", b)]);
+        let chunk = node("chunk", vec![("entry module", index)]);
+
+        let reasons = introspectable_reasons(chunk, "target.js").await?;
+        // The shortest chain from the root, and every direct issuer.
+        assert_eq!(
+            reasons.chain,
+            [
+                reason_step("chunk", ""),
+                reason_step("index.js", "entry module"),
+                reason_step("a.js", "reference"),
+                reason_step("target.js", "reference"),
+            ]
+        );
+        assert_eq!(
+            reasons.issuers,
+            [
+                reason_step("a.js", "reference"),
+                reason_step("b.js", "reference")
+            ]
+        );
+
+        let reasons = introspectable_reasons(chunk, "missing.js").await?;
+        assert!(reasons.chain.is_empty());
+        assert!(reasons.issuers.is_empty());
+        Ok(())
+    })
+    .await
+}
+
+fn address_step(name: &str, title: &str) -> AddressStep {
+    AddressStep {
+        name: name.to_string(),
+        title: title.to_string(),
+    }
+}
+
+#[test]
+fn searches_introspectables() {
+    util::run(run_search());
+}
+
+async fn run_search() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let target = node("target.js", vec![]);
+        let a = node("a.js", vec![("reference", target)]);
+        let b = node("b.js", vec![("reference", target)]);
+        let index = node("index.js", vec![("reference", a), ("reference", b)]);
+        let chunk = node("chunk", vec![("entry module", index)]);
+
+        let query = |ty: Option<&str>, title: Option<&str>| {
+            Value::new(IntrospectableQuery {
+                ty: ty.map(str::to_string),
+                title: title.map(str::to_string),
+            })
+        };
+        let matches = search_introspectable(chunk, query(None, Some("target"))).await?;
+        // The shortest address of every match.
+        assert_eq!(matches.len(), 1);
+        let address = matches[0].address.clone();
+        assert_eq!(
+            address,
+            [
+                address_step("entry module", "index.js"),
+                address_step("reference", "a.js"),
+                address_step("reference", "target.js"),
+            ]
+        );
+        let resolved = resolve_address(chunk, &address).await?.unwrap();
+        assert_eq!(*resolved.title().await?, "target.js");
+        assert_eq!(
+            *resolve_address(chunk, &[]).await?.unwrap().title().await?,
+            "chunk"
+        );
+        assert!(
+            resolve_address(chunk, &[address_step("reference", "index.js")])
+                .await?
+                .is_none()
+        );
+
+        // Closest to the root first, every introspectable once.
+        let matches = search_introspectable(chunk, query(Some("test"), None)).await?;
+        assert_eq!(
+            matches.iter().map(|m| m.title.as_str()).collect::<Vec<_>>(),
+            ["chunk", "index.js", "a.js", "b.js", "target.js"]
+        );
+        // All parameters need to match.
+        assert!(
+            search_introspectable(chunk, query(Some("test"), Some("c.js")))
+                .await?
+                .is_empty()
+        );
+        assert!(
+            search_introspectable(chunk, query(Some("asset"), Some("a.js")))
+                .await?
+                .is_empty()
+        );
+
+        let modules = (0..MAX_SEARCH_MATCHES + 10)
+            .map(|i| node(&format!("{i}.js"), vec![]))
+            .collect::<Vec<_>>();
+        let chunk = node(
+            "chunk",
+            modules.iter().map(|&module| ("module", module)).collect(),
+        );
+        let matches = search_introspectable(chunk, query(None, Some(".js"))).await?;
+        assert_eq!(matches.len(), MAX_SEARCH_MATCHES);
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn lists_assets_in_reference_order() {
+    util::run(run_reference_order());
+}
+
+async fn run_reference_order() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let dir = fs.root().join("crates/turbopack-tests/tests/introspect");
+        let environment = EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::Browser(
+                BrowserEnvironment {
+                    dom: true,
+                    web_worker: false,
+                    service_worker: false,
+                    browserslist_query: "Chrome 102".to_string(),
+                }
+                .into(),
+            )),
+            Value::new(EnvironmentIntention::Client),
+        );
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            environment,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext::default().cell(),
+        );
+        let entry = context.process(
+            SourceAssetVc::new(dir.join("index.js")).into(),
+            Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+        );
+
+        let dir_value = dir.await?;
+        let mut assets = Vec::new();
+        for asset in all_assets(entry).await?.iter() {
+            if let Some(path) = dir_value.get_path_to(&*asset.path().await?) {
+                assets.push(path.to_string());
+            }
+        }
+        // In the order they are found, breadth first.
+        assert_eq!(assets, ["index.js", "c.js", "a.js", "b.js"]);
+
+        let mut children = Vec::new();
+        for (_, child) in children_from_asset_references(entry.references())
+            .await?
+            .iter()
+        {
+            let title = child.title().await?;
+            if let Some((_, name)) = title.rsplit_once('/') {
+                children.push(name.to_string());
+            }
+        }
+        assert_eq!(children, ["c.js", "a.js", "b.js"]);
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,3 @@
+import "./c.js";
+import "./a.js";
+import "./b.js";
@@ -0,0 +1,117 @@
+#![cfg(test)]
+
+//! Tests for the project API. The project in `tests/project` is built into
+//! `target/turbopack-tests/project`.
+
+mod util;
+
+use anyhow::Result;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::DiskFileSystemVc;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext,
+    project::{ProjectOptions, ProjectVc},
+    resolve_options_context::ResolveOptionsContext,
+};
+use turbopack_core::{
+    chunk::dev::DevChunkingContextVc,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+};
+use util::WORKSPACE_ROOT;
+
+util::register!("project");
+
+/// The project in `tests/project`, with its single entry.
+fn project() -> ProjectVc {
+    let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+    let root = fs.root();
+    let project_path = root.join("crates/turbopack-tests/tests/project");
+    let output_path = root.join("target/turbopack-tests/project");
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::NodeJsLambda(
+            NodeJsEnvironment::default().into(),
+        )),
+        Value::new(EnvironmentIntention::ServerRendering),
+    );
+    let chunking_context = DevChunkingContextVc::builder(
+        root,
+        output_path,
+        output_path.join("chunks"),
+        output_path.join("assets"),
+        environment,
+    )
+    .build();
+    ProjectVc::new(
+        ProjectOptions {
+            project_path,
+            output_path,
+            entries: vec!["./src/index.js".to_string()],
+            environment,
+            module_options_context: ModuleOptionsContext::default().cell(),
+            resolve_options_context: ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+            chunking_context,
+            tree_shaking: false,
+            minify: None,
+            federation: None,
+        }
+        .cell(),
+    )
+}
+
+#[test]
+fn builds_project() {
+    util::run(run());
+}
+
+async fn run() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let project = project();
+
+        let entrypoints = project.entrypoints().await?;
+        let [entrypoint] = &entrypoints.entrypoints[..] else {
+            panic!("the project has one entry: {entrypoints:#?}");
+        };
+        assert_eq!(entrypoint.request, "./src/index.js");
+        assert!(!entrypoint.chunks.is_empty());
+        // The paths are relative to the output path.
+        assert!(entrypoint
+            .chunks
+            .iter()
+            .all(|chunk| chunk.starts_with("chunks/") && chunk.ends_with(".js")));
+
+        let build = project.build().await?;
+        assert_eq!(build.entrypoints, entrypoints.entrypoints);
+        let paths = build
+            .assets
+            .iter()
+            .map(|asset| asset.path.as_str())
+            .collect::<Vec<_>>();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(paths, sorted);
+        for chunk in &entrypoint.chunks {
+            let asset = build
+                .assets
+                .iter()
+                .find(|asset| &asset.path == chunk)
+                .unwrap_or_else(|| panic!("{chunk} is emitted"));
+            assert!(asset.size > 0);
+        }
+
+        assert_eq!(
+            &*project.explain_export("./src/utils.js", "unused").await?,
+            "tree shaking is disabled, all exports are retained"
+        );
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,458 @@
+#![cfg(test)]
+
+//! Tests for the resolve options of a [ResolveOptionsContext]. The requests
+//! are resolved in `tests/resolve`.
+
+mod util;
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::Regex, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    asset_manifest::{AssetManifest, ASSET_MANIFEST_REQUEST},
+    module_options::ModuleOptionsContext,
+    module_replacement::{
+        ModuleReplacement, ModuleReplacementCondition, ModuleReplacementRule,
+        ModuleReplacementRulesVc,
+    },
+    resolve::resolve_options,
+    resolve_options_context::{NodeBuiltinsFallback, NodeBuiltinsOptions, ResolveOptionsContext},
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    context::AssetContext,
+    environment::{
+        BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment,
+        NodeJsEnvironment,
+    },
+    issue::IssueVc,
+    reference::all_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    resolve::{options::ForbiddenImport, parse::RequestVc, resolve, PrimaryResolveResult},
+    source_asset::SourceAssetVc,
+};
+use util::WORKSPACE_ROOT;
+
+util::register!("resolve");
+
+/// How `request` resolves with `node_builtins`: the path of the asset
+/// relative to `tests/resolve`, `empty` or `unresolveable`.
+async fn resolve_builtin(request: &str, node_builtins: NodeBuiltinsOptions) -> Result<String> {
+    let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+    let context = fs.root().join("crates/turbopack-tests/tests/resolve");
+    let options = resolve_options(
+        context,
+        ResolveOptionsContext {
+            enable_node_modules: true,
+            node_builtins,
+            ..Default::default()
+        }
+        .cell(),
+    );
+    let result = resolve(
+        context,
+        RequestVc::parse(Value::new(request.to_string().into())),
+        options,
+    )
+    .await?;
+    Ok(match result.primary.first() {
+        Some(PrimaryResolveResult::Asset(asset)) => context
+            .await?
+            .get_path_to(&*asset.path().await?)
+            .unwrap()
+            .to_string(),
+        Some(PrimaryResolveResult::Empty) => "empty".to_string(),
+        _ => "unresolveable".to_string(),
+    })
+}
+
+/// How `request` resolves with external packages: the path of the asset
+/// relative to `tests/resolve`, `external` or `unresolveable`. The project is
+/// the root of the file system, as Node.js resolves externals from there.
+async fn resolve_external(request: &str, bundled_packages: &[&str]) -> Result<String> {
+    let fs = DiskFileSystemVc::new(
+        "project".to_string(),
+        format!("{}/crates/turbopack-tests/tests/resolve", *WORKSPACE_ROOT),
+    );
+    let context = fs.root();
+    let options = resolve_options(
+        context,
+        ResolveOptionsContext {
+            enable_node_modules: true,
+            enable_external_packages: true,
+            bundled_packages: bundled_packages.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+        .cell(),
+    );
+    let result = resolve(
+        context,
+        RequestVc::parse(Value::new(request.to_string().into())),
+        options,
+    )
+    .await?;
+    Ok(match result.primary.first() {
+        Some(PrimaryResolveResult::Asset(asset)) => asset.path().await?.path.clone(),
+        Some(PrimaryResolveResult::OriginalReferenceExternal) => "external".to_string(),
+        _ => "unresolveable".to_string(),
+    })
+}
+
+#[test]
+fn resolves_external_packages() {
+    util::run(run_external_packages());
+}
+
+async fn run_external_packages() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        assert_eq!(resolve_external("cjs-package", &[]).await?, "external");
+        // ES modules can't be loaded with `require()`, so they are bundled.
+        assert_eq!(
+            resolve_external("esm-package", &[]).await?,
+            "node_modules/esm-package/index.js"
+        );
+        assert_eq!(
+            resolve_external("transpiled-package", &["transpiled-package"]).await?,
+            "node_modules/transpiled-package/index.js"
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn resolves_node_builtins() {
+    util::run(run_node_builtins());
+}
+
+async fn run_node_builtins() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let polyfills = NodeBuiltinsOptions {
+            polyfills: BTreeMap::from([("node:buffer".to_string(), "buffer/".to_string())]),
+            fallback: NodeBuiltinsFallback::Empty,
+        };
+        for request in ["buffer", "node:buffer"] {
+            assert_eq!(
+                resolve_builtin(request, polyfills.clone()).await?,
+                "node_modules/buffer/index.js",
+                "{request} resolves to the polyfill"
+            );
+        }
+        // Builtins without a polyfill are resolved by the fallback import map.
+        assert_eq!(resolve_builtin("fs", polyfills).await?, "empty");
+
+        let errors = NodeBuiltinsOptions::default();
+        assert_eq!(
+            resolve_builtin("fs", errors.clone()).await?,
+            "unresolveable"
+        );
+        // A package with the name of a builtin takes its place, also for
+        // requests with the `node:` prefix.
+        for request in ["buffer", "node:buffer"] {
+            assert_eq!(
+                resolve_builtin(request, errors.clone()).await?,
+                "node_modules/buffer/index.js",
+                "{request} resolves to the package"
+            );
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn replaces_modules() {
+    util::run(run_module_replacements());
+}
+
+async fn run_module_replacements() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let context = fs.root().join("crates/turbopack-tests/tests/resolve");
+        let regex = |regex: &str| Regex(regex::Regex::new(regex).unwrap());
+        let rules = ModuleReplacementRulesVc::cell(vec![
+            ModuleReplacementRule::new(
+                ModuleReplacementCondition::Request(regex("/api$")),
+                ModuleReplacement::Path(context.join("replacement/api.mock.js")),
+            ),
+            ModuleReplacementRule::new(
+                ModuleReplacementCondition::Not(Box::new(
+                    ModuleReplacementCondition::ResolvedPath(regex(r"label\.js$")),
+                )),
+                ModuleReplacement::StemSuffix(".ios".to_string()),
+            ),
+        ]);
+        let options = resolve_options(
+            context,
+            ResolveOptionsContext {
+                module_replacements: Some(rules),
+                ..Default::default()
+            }
+            .cell(),
+        );
+        for (request, expected) in [
+            ("./replacement/api", "replacement/api.mock.js"),
+            ("./replacement/button", "replacement/button.ios.js"),
+            // Excluded from the `.ios` rule.
+            ("./replacement/label", "replacement/label.js"),
+            // The `.ios` file doesn't exist, so the module is kept.
+            ("./forbidden", "forbidden.js"),
+        ] {
+            let result = resolve(
+                context,
+                RequestVc::parse(Value::new(request.to_string().into())),
+                options,
+            )
+            .await?;
+            let Some(PrimaryResolveResult::Asset(asset)) = result.primary.first() else {
+                panic!("{request} doesn't resolve to an asset");
+            };
+            assert_eq!(
+                context.await?.get_path_to(&*asset.path().await?),
+                Some(expected),
+                "{request}"
+            );
+        }
+        Ok(())
+    })
+    .await
+}
+
+fn browser_environment() -> EnvironmentVc {
+    EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: "Chrome 102".to_string(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    )
+}
+
+/// How `./platform/{request}` resolves in `environment` with
+/// `platform_suffixes`: the path of the asset relative to `tests/resolve`.
+async fn resolve_platform(
+    request: &str,
+    environment: Option<EnvironmentVc>,
+    platform_suffixes: &[&str],
+) -> Result<String> {
+    let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+    let context = fs.root().join("crates/turbopack-tests/tests/resolve");
+    let options = resolve_options(
+        context,
+        ResolveOptionsContext {
+            emulate_environment: environment,
+            platform_suffixes: platform_suffixes.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+        .cell(),
+    );
+    let result = resolve(
+        context,
+        RequestVc::parse(Value::new(format!("./platform/{request}").into())),
+        options,
+    )
+    .await?;
+    let Some(PrimaryResolveResult::Asset(asset)) = result.primary.first() else {
+        panic!("{request} doesn't resolve to an asset");
+    };
+    Ok(context
+        .await?
+        .get_path_to(&*asset.path().await?)
+        .unwrap()
+        .to_string())
+}
+
+#[test]
+fn resolves_platform_specific_files() {
+    util::run(run_platform_specific_files());
+}
+
+async fn run_platform_specific_files() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let browser = Some(browser_environment());
+        // Earlier suffixes take precedence.
+        assert_eq!(
+            resolve_platform("button", browser, &[]).await?,
+            "platform/button.browser.js"
+        );
+        assert_eq!(
+            resolve_platform("card", browser, &[]).await?,
+            "platform/card.web.js"
+        );
+        assert_eq!(
+            resolve_platform("link", browser, &[]).await?,
+            "platform/link.js"
+        );
+
+        // Configured suffixes replace the ones of the environment.
+        assert_eq!(
+            resolve_platform("link", browser, &[".android", ".ios"]).await?,
+            "platform/link.android.js"
+        );
+        assert_eq!(
+            resolve_platform("button", browser, &[".ios"]).await?,
+            "platform/button.js"
+        );
+        assert_eq!(
+            resolve_platform("link", None, &[".ios"]).await?,
+            "platform/link.ios.js"
+        );
+
+        // Node.js doesn't resolve platform specific files.
+        let node = EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::NodeJsLambda(
+                NodeJsEnvironment::default().into(),
+            )),
+            Value::new(EnvironmentIntention::Api),
+        );
+        assert_eq!(
+            resolve_platform("button", Some(node), &[]).await?,
+            "platform/button.js"
+        );
+        assert_eq!(
+            resolve_platform("button", None, &[]).await?,
+            "platform/button.js"
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn resolves_asset_manifest() {
+    util::run(run_asset_manifest());
+}
+
+async fn run_asset_manifest() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let context = fs.root().join("crates/turbopack-tests/tests/resolve");
+        let resolve_manifest = |asset_manifest| async move {
+            let options = resolve_options(
+                context,
+                ResolveOptionsContext {
+                    asset_manifest,
+                    ..Default::default()
+                }
+                .cell(),
+            );
+            resolve(
+                context,
+                RequestVc::parse(Value::new(ASSET_MANIFEST_REQUEST.to_string().into())),
+                options,
+            )
+            .await
+        };
+
+        let manifest = AssetManifest {
+            project_path: context,
+            assets: BTreeMap::from([
+                ("og-image".to_string(), "./public/og.png".to_string()),
+                ("wasm".to_string(), "./lib/module.wasm".to_string()),
+            ]),
+        };
+        let result = resolve_manifest(Some(manifest.cell())).await?;
+        let Some(PrimaryResolveResult::Asset(module)) = result.primary.first() else {
+            panic!("the manifest doesn't resolve to an asset");
+        };
+        let AssetContent::File(file) = &*module.content().await? else {
+            panic!("the manifest is not a file");
+        };
+        let FileContent::Content(file) = &*file.await? else {
+            panic!("the manifest has no content");
+        };
+        // The module requires the assets, so it exports their URLs.
+        assert_eq!(
+            file.content().to_str()?,
+            "__turbopack_export_value__({\n  \"og-image\": require(\"./public/og.png\"),\n  \
+             \"wasm\": require(\"./lib/module.wasm\"),\n});\n"
+        );
+
+        let result = resolve_manifest(None).await?;
+        assert!(!matches!(
+            result.primary.first(),
+            Some(PrimaryResolveResult::Asset(_))
+        ));
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn reports_forbidden_imports() {
+    util::run(run_forbidden_imports());
+}
+
+async fn run_forbidden_imports() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let dir = fs.root().join("crates/turbopack-tests/tests/resolve");
+        let environment = browser_environment();
+        let reason = "server-only can't be imported on the client";
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            environment,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                forbidden_imports: vec![ForbiddenImport {
+                    module: "server-only".to_string(),
+                    reason: reason.to_string(),
+                }],
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let entry = context.process(
+            SourceAssetVc::new(dir.join("forbidden.js")).into(),
+            Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+        );
+        let assets = all_assets(entry);
+        assets.await?;
+
+        let issues = IssueVc::peek_issues_with_path(assets)
+            .await?
+            .await?
+            .get_plain_issues()
+            .await?;
+        let forbidden = issues
+            .iter()
+            .filter(|issue| issue.description == reason)
+            .collect::<Vec<_>>();
+        // Paths inside of the module are forbidden too, other modules are not.
+        assert_eq!(forbidden.len(), 2, "{issues:#?}");
+        assert!(forbidden
+            .iter()
+            .all(|issue| issue.context.ends_with("forbidden.js")));
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,3 @@
+import "server-only";
+import "server-only/index.js";
+import "cjs-package";
@@ -0,0 +1 @@
+export default "button.browser.js";
@@ -0,0 +1 @@
+export default "button.js";
@@ -0,0 +1 @@
+export default "button.web.js";
@@ -0,0 +1 @@
+export default "link.ios.js";
@@ -16,6 +16,8 @@ __TURBOPACK__imported__module__$5b$project$5d2f$crates$2f$turbopack$2d$tests$2f$
 if (!Array.isArray(globalThis.TURBOPACK)) {
     return;
 }
+const CHUNK_BASE_PATH = "/";
+const STRICT_CSP = false;
 /** @typedef {import('../types/backend').RuntimeBackend} RuntimeBackend */
 
 /** @type {RuntimeBackend} */
@@ -25,7 +27,8 @@ const BACKEND = {
       if (chunkPath.endsWith(".css")) {
         const link = document.createElement("link");
         link.rel = "stylesheet";
-        link.href = `/${chunkPath}`;
+        link.href = `${CHUNK_BASE_PATH}${chunkPath}`;
+        if (CSP_NONCE) link.nonce = CSP_NONCE;
         link.onerror = () => {
           reject();
         };
@@ -37,7 +40,8 @@ const BACKEND = {
         document.body.appendChild(link);
       } else if (chunkPath.endsWith(".js")) {
         const script = document.createElement("script");
-        script.src = `/${chunkPath}`;
+        script.src = `${CHUNK_BASE_PATH}${chunkPath}`;
+        if (CSP_NONCE) script.nonce = CSP_NONCE;
         // We'll only mark the chunk as loaded once the script has been executed,
         // which happens in `registerChunk`. Hence the absence of `resolve()` in
         // this branch.
@@ -51,6 +55,17 @@ const BACKEND = {
     });
   },
 
+  hintChunk(chunkPath, hint) {
+    const link = document.createElement("link");
+    link.rel = hint;
+    if (hint === "preload") {
+      link.as = chunkPath.endsWith(".css") ? "style" : "script";
+    }
+    link.href = `${CHUNK_BASE_PATH}${chunkPath}`;
+    if (CSP_NONCE) link.nonce = CSP_NONCE;
+    document.head.appendChild(link);
+  },
+
   restart: () => self.location.reload(),
 };
 /* eslint-disable @next/next/no-assign-module-variable */
@@ -81,6 +96,19 @@ const BACKEND = {
 /** @typedef {import('../types/runtime').Loader} Loader */
 /** @typedef {import('../types/runtime').ModuleEffect} ModuleEffect */
 
+/**
+ * The nonce of the script that started the runtime. It's added to the scripts
+ * and stylesheets the runtime adds to the page, so they are allowed by a
+ * Content Security Policy with nonces.
+ *
+ * @type {string | undefined}
+ */
+const CSP_NONCE =
+  typeof document !== "undefined"
+    ? (document.currentScript ?? document.querySelector("script[nonce]"))
+        ?.nonce || undefined
+    : undefined;
+
 /** @type {Array<Runnable>} */
 let runnable = [];
 /** @type {Object.<ModuleId, ModuleFactory>} */
@@ -112,12 +140,26 @@ const moduleHotData = new Map();
  * @type {Map<Module, HotState>}
  */
 const moduleHotState = new Map();
+/**
+ * Contains the modules that are ES modules compiled by turbopack, as opposed
+ * to CommonJS modules that only set `__esModule`.
+ *
+ * @type {WeakSet<Module>}
+ */
+const esmModules = new WeakSet();
 /**
  * Module IDs that are instantiated as part of the runtime of a chunk.
  *
  * @type {Set<ModuleId>}
  */
 const runtimeModules = new Set();
+/**
+ * The IDs of the modules that were executed, which are collected into usage
+ * profiles to guide the chunking.
+ *
+ * @type {Set<ModuleId>}
+ */
+const executedModules = new Set();
 /**
  * Map from module ID to the chunks that contain this module.
  *
@@ -164,6 +206,18 @@ function esm(exports, getters) {
   }
 }
 
+/**
+ * Adds the getters to the exports object of an ES module compiled by
+ * turbopack.
+ *
+ * @param {Module} module
+ * @param {Record<string, () => any>} getters
+ */
+function esmExport(module, getters) {
+  esmModules.add(module);
+  esm(module.exports, getters);
+}
+
 /**
  * Adds the getters to the exports object
  *
@@ -185,26 +239,32 @@ function exportValue(module, value) {
 }
 
 /**
- * @param {Record<string, any>} obj
+ * @param {() => Record<string, any>} getObj
  * @param {string} key
  */
-function createGetter(obj, key) {
-  return () => obj[key];
+function createGetter(getObj, key) {
+  return () => getObj()[key];
 }
 
 /**
  * @param {Exports} raw
  * @param {EsmInteropNamespace} ns
  * @param {boolean} [allowExportDefault]
+ * @param {() => Exports} [getRaw] Returns the current exports, which can be
+ * reassigned after the namespace has been created in circular dependencies.
  */
-function interopEsm(raw, ns, allowExportDefault) {
+function interopEsm(raw, ns, allowExportDefault, getRaw = () => raw) {
   /** @type {Object.<string, () => any>} */
   const getters = { __proto__: null };
-  for (const key in raw) {
-    getters[key] = createGetter(raw, key);
+  // `module.exports` can be any value, but only objects and functions have
+  // named exports.
+  if (raw != null && (typeof raw === "object" || typeof raw === "function")) {
+    for (const key in raw) {
+      getters[key] = createGetter(getRaw, key);
+    }
   }
   if (!(allowExportDefault && "default" in getters)) {
-    getters["default"] = () => raw;
+    getters["default"] = getRaw;
   }
   esm(ns, getters);
 }
@@ -212,19 +272,94 @@ function interopEsm(raw, ns, allowExportDefault) {
 /**
  * @param {Module} sourceModule
  * @param {ModuleId} id
- * @param {boolean} allowExportDefault
+ * @param {boolean} [allowExportDefault]
+ * @param {boolean} [nodeInterop] Imports CommonJS modules that set
+ * `__esModule` like Node.js does: the default export is `module.exports`
+ * instead of `exports.default`.
  * @returns {EsmInteropNamespace}
  */
-function esmImport(sourceModule, id, allowExportDefault) {
+function esmImport(sourceModule, id, allowExportDefault, nodeInterop) {
   const module = getOrInstantiateModuleFromParent(id, sourceModule);
   const raw = module.exports;
-  if (raw.__esModule) return raw;
+  if (esmModules.has(module)) return raw;
+  if (!nodeInterop && raw != null && raw.__esModule) return raw;
   if (module.interopNamespace) return module.interopNamespace;
   const ns = (module.interopNamespace = {});
-  interopEsm(raw, ns, allowExportDefault);
+  interopEsm(raw, ns, allowExportDefault, () => module.exports);
   return ns;
 }
 
+/**
+ * Evaluates the body of a module that uses top-level await. The promise of
+ * the evaluation is stored on the module, so that importers can wait for it.
+ *
+ * @param {Module} module
+ * @param {() => Promise<void>} body
+ */
+function asyncModule(module, body) {
+  module.waitingFor = new Set();
+  module.async = body
+    .call(module.exports)
+    .then(() => {
+      // The exports can be reassigned by the body after importers have
+      // created the interop namespace.
+      if (module.interopNamespace) {
+        interopEsm(
+          module.exports,
+          module.interopNamespace,
+          false,
+          () => module.exports
+        );
+      }
+    })
+    .finally(() => {
+      module.waitingFor.clear();
+    });
+}
+
+/**
+ * Waits for the evaluation of an imported async module to finish. Waiting is
+ * skipped when the imported module is (transitively) waiting for the
+ * importing module, as a cycle of async modules would never resolve.
+ *
+ * @param {Module} sourceModule
+ * @param {ModuleId} id
+ * @returns {Promise<void>}
+ */
+function waitForModule(sourceModule, id) {
+  const module = moduleCache[id];
+  if (module == null || module.async == null) return Promise.resolve();
+  if (isWaitingFor(module, sourceModule)) return Promise.resolve();
+  sourceModule.waitingFor.add(module);
+  return module.async;
+}
+
+/**
+ * @param {Module} module
+ * @param {Module} target
+ * @returns {boolean}
+ */
+function isWaitingFor(module, target) {
+  const queue = [module];
+  const visited = new Set();
+  while (queue.length > 0) {
+    const current = queue.pop();
+    if (current === target) return true;
+    if (visited.has(current) || current.waitingFor == null) continue;
+    visited.add(current);
+    queue.push(...current.waitingFor);
+  }
+  return false;
+}
+
+/**
+ * The module federation API for the modules that use it. It only exists when
+ * the chunking context enables module federation, which adds
+ * `runtime.federation.js` to the runtime.
+ */
+const federation =
+  typeof createFederation === "function" ? createFederation() : undefined;
+
 /**
  * @param {Module} sourceModule
  * @param {ModuleId} id
@@ -245,7 +380,7 @@ function externalRequire(id, esm) {
     // compilation error.
     throw new Error(`Failed to load external module ${id}: ${err}`);
   }
-  if (!esm || raw.__esModule) {
+  if (!esm || (raw != null && raw.__esModule)) {
     return raw;
   }
   const ns = {};
@@ -271,6 +406,21 @@ function loadChunk(from, chunkPath) {
   return chunkLoader.promise;
 }
 
+/**
+ * Fetches a chunk ahead of time without evaluating it, so it's cached when
+ * it's loaded later on.
+ *
+ * @param {string} chunkPath
+ * @param {"prefetch" | "preload"} hint
+ */
+function hintChunk(chunkPath, hint) {
+  if (loadedChunks.has(chunkPath) || chunkLoaders.has(chunkPath)) {
+    return;
+  }
+
+  BACKEND.hintChunk?.(chunkPath, hint);
+}
+
 /**
  * @param {string} chunkPath
  * @param {ModuleId} from
@@ -365,6 +515,8 @@ function instantiateModule(id, sourceType, sourceId) {
     );
   }
 
+  executedModules.add(id);
+
   const hotData = moduleHotData.get(id);
   const { hot, hotState } = createModuleHot(hotData);
 
@@ -396,12 +548,16 @@ function instantiateModule(id, sourceType, sourceId) {
       r: commonJsRequire.bind(null, module),
       x: externalRequire,
       i: esmImport.bind(null, module),
-      s: esm.bind(null, module.exports),
+      s: esmExport.bind(null, module),
       j: cjs.bind(null, module.exports),
       v: exportValue.bind(null, module),
       m: module,
       c: moduleCache,
       l: loadChunk.bind(null, id),
+      h: hintChunk,
+      a: asyncModule.bind(null, module),
+      w: waitForModule.bind(null, module),
+      f: federation,
       p: _process,
       g: globalThis,
       __dirname: module.id.replace(/(^|\/)[\/]+$/, ""),
@@ -411,7 +567,12 @@ function instantiateModule(id, sourceType, sourceId) {
   module.loaded = true;
   if (module.interopNamespace) {
     // in case of a circular dependency: cjs1 -> esm2 -> cjs1
-    interopEsm(module.exports, module.interopNamespace);
+    interopEsm(
+      module.exports,
+      module.interopNamespace,
+      false,
+      () => module.exports
+    );
   }
 
   return module;
@@ -548,9 +709,48 @@ function formatDependencyChain(dependencyChain) {
  * @private
  */
 function _eval({ code, url, map }) {
-  code += `\n\n//# sourceURL=${location.origin}${url}`;
-  if (map) code += `\n//# sourceMappingURL=${map}`;
-  return eval(code);
+  let comments = `\n\n//# sourceURL=${location.origin}${url}`;
+  if (map) comments += `\n//# sourceMappingURL=${map}`;
+  if (STRICT_CSP) return evalWithScript(code, comments);
+  return eval(code + comments);
+}
+
+let evalScriptId = 0;
+
+/**
+ * Evaluates the code of a module factory in a script element with the nonce
+ * of the page, as a strict Content Security Policy doesn't allow `eval`. The
+ * script passes the factory to a temporary global callback.
+ *
+ * @param {string} code
+ * @param {string} comments
+ * @returns {ModuleFactory}
+ */
+function evalWithScript(code, comments) {
+  const callback = `__turbopack_eval_${evalScriptId++}__`;
+  /** @type {ModuleFactory | undefined} */
+  let factory;
+  globalThis[callback] = (f) => {
+    factory = f;
+  };
+  const script = document.createElement("script");
+  if (CSP_NONCE) script.nonce = CSP_NONCE;
+  // The callback is on the first line of the code, so the source map of the
+  // code is only off by a few columns on that line.
+  script.text = `${callback}(${code}\n);${comments}`;
+  try {
+    // Inline scripts are executed when they are added.
+    document.head.appendChild(script);
+  } finally {
+    script.remove();
+    delete globalThis[callback];
+  }
+  if (factory === undefined) {
+    throw new Error(
+      "cannot apply update: the update script was blocked, the Content Security Policy of the page needs to allow scripts with its nonce"
+    );
+  }
+  return factory;
 }
 
 /**
@@ -1052,6 +1252,8 @@ function registerChunk([chunkPath, chunkModules, ...run]) {
 globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS =
   globalThis.TURBOPACK_CHUNK_UPDATE_LISTENERS || [];
 
+globalThis.TURBOPACK_EXECUTED_MODULES = executedModules;
+
 globalThis.TURBOPACK.forEach(registerChunk);
 globalThis.TURBOPACK = {
   push: registerChunk,
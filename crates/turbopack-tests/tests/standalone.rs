@@ -0,0 +1,207 @@
+#![cfg(test)]
+
+//! Tests for the standalone output of node.js entries. The asset graph is
+//! built in memory, nothing is read from or written to disk.
+
+mod util;
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, TurboTasks};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileContent, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    reference::{AssetReferencesVc, SingleAssetReferenceVc},
+};
+use turbopack_node::standalone::{standalone_assets, StandaloneOptions, SEA_BLOB_NAME};
+use util::WORKSPACE_ROOT;
+
+util::register!("standalone", turbopack_node::register);
+
+/// An asset whose code is its name, which references other assets.
+#[turbo_tasks::value]
+struct TestAsset {
+    path: FileSystemPathVc,
+    references: Vec<AssetVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for TestAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let name = self.path.await?.file_name().to_string();
+        Ok(AssetContent::File(FileContent::Content(File::from(name)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::cell(
+            self.references
+                .iter()
+                .map(|&asset| {
+                    SingleAssetReferenceVc::new(asset, StringVc::cell("test".to_string())).into()
+                })
+                .collect(),
+        )
+    }
+}
+
+fn asset(path: FileSystemPathVc, references: Vec<AssetVc>) -> AssetVc {
+    TestAsset { path, references }.cell().into()
+}
+
+/// The standalone assets of an entry with a chunk, which requires a package
+/// with a native addon in the project and a file outside of it, by their path
+/// relative to the output path.
+async fn standalone_output(sea: bool) -> Result<BTreeMap<String, String>> {
+    let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+    let root = fs.root();
+    let project_root = root.join("app");
+    let output_root = project_root.join(".next/server");
+    let output_path = root.join("standalone");
+
+    let addon = asset(project_root.join("node_modules/pkg/addon.node"), vec![]);
+    let package = asset(project_root.join("node_modules/pkg/index.js"), vec![addon]);
+    let global = asset(root.join("global/cache.js"), vec![]);
+    let chunk = asset(output_root.join("chunks/chunk.js"), vec![package, global]);
+    let entry = asset(output_root.join("index.js"), vec![chunk]);
+
+    let assets = standalone_assets(
+        entry,
+        output_root,
+        project_root,
+        output_path,
+        StandaloneOptions { sea }.cell(),
+    )
+    .await?;
+    let output_path = output_path.await?;
+    let mut output = BTreeMap::new();
+    for asset in assets.iter() {
+        let path = asset.path().await?;
+        let path = output_path
+            .get_path_to(&path)
+            .expect("the asset is in the output path")
+            .to_string();
+        let AssetContent::File(file) = &*asset.content().await? else {
+            panic!("{path} is not a file");
+        };
+        let FileContent::Content(file) = &*file.await? else {
+            panic!("{path} has no content");
+        };
+        output.insert(path, file.content().to_str()?.to_string());
+    }
+    Ok(output)
+}
+
+#[test]
+fn standalone_output_without_sea() {
+    util::run(run_standalone_output_without_sea());
+}
+
+async fn run_standalone_output_without_sea() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let output = standalone_output(false).await?;
+        // The artifact keeps the layout of the project. Files outside of the
+        // project are expected where the artifact is deployed.
+        assert_eq!(
+            output.keys().map(String::as_str).collect::<Vec<_>>(),
+            [
+                ".next/server/chunks/chunk.js",
+                ".next/server/index.js",
+                "native-dependencies.json",
+                "node_modules/pkg/addon.node",
+                "node_modules/pkg/index.js",
+                "package.json",
+            ]
+        );
+        assert_eq!(output[".next/server/index.js"], "index.js");
+        assert_eq!(output["node_modules/pkg/addon.node"], "addon.node");
+        let native_dependencies: serde_json::Value =
+            serde_json::from_str(&output["native-dependencies.json"])?;
+        assert_eq!(
+            native_dependencies,
+            serde_json::json!({
+                "files": ["node_modules/pkg/addon.node"],
+                "packages": ["node_modules/pkg"],
+            })
+        );
+        let package_json: serde_json::Value = serde_json::from_str(&output["package.json"])?;
+        assert_eq!(
+            package_json,
+            serde_json::json!({
+                "private": true,
+                "type": "commonjs",
+                "main": ".next/server/index.js",
+            })
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn standalone_output_with_sea() {
+    util::run(run_standalone_output_with_sea());
+}
+
+async fn run_standalone_output_with_sea() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let output = standalone_output(true).await?;
+        let sea_config: serde_json::Value = serde_json::from_str(&output["sea-config.json"])?;
+        assert_eq!(
+            sea_config,
+            serde_json::json!({
+                "main": ".next/server/index.js",
+                "output": SEA_BLOB_NAME,
+                "disableExperimentalSEAWarning": true,
+            })
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn standalone_output_outside_of_project() {
+    util::run(run_standalone_output_outside_of_project());
+}
+
+async fn run_standalone_output_outside_of_project() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let root = fs.root();
+        let output_root = root.join("dist");
+        let entry = asset(output_root.join("index.js"), vec![]);
+        let result = standalone_assets(
+            entry,
+            output_root,
+            root.join("app"),
+            root.join("standalone"),
+            StandaloneOptions::default().cell(),
+        )
+        .await;
+        // The layout of the project can't be kept.
+        let error = result
+            .err()
+            .expect("the output root is outside of the project");
+        assert!(format!("{error:#}").contains("is not in the project root"));
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,136 @@
+#![cfg(test)]
+
+//! Tests for the unused code analysis. The module graph of `tests/unused` is
+//! analyzed.
+
+mod util;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::DiskFileSystemVc;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::unused::{emit_unused_code_issues, UnusedCodeOptions},
+    module_options::ModuleOptionsContext,
+    resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::AssetsVc,
+    context::AssetContext,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    issue::{IssueSeverity, IssueVc},
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use util::WORKSPACE_ROOT;
+
+util::register!("unused");
+
+/// The descriptions of the issues with the title.
+fn descriptions<'a>(issues: &'a [(IssueSeverity, String, String)], title: &str) -> Vec<&'a str> {
+    issues
+        .iter()
+        .filter(|(_, issue_title, _)| issue_title == title)
+        .map(|(_, _, description)| description.as_str())
+        .collect()
+}
+
+#[test]
+fn reports_unused_code() {
+    util::run(run());
+}
+
+async fn run() -> Result<()> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        let fs = DiskFileSystemVc::new("project".to_string(), WORKSPACE_ROOT.clone());
+        let project_dir = fs.root().join("crates/turbopack-tests/tests/unused");
+        let environment = EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::NodeJsLambda(
+                NodeJsEnvironment::default().into(),
+            )),
+            Value::new(EnvironmentIntention::ServerRendering),
+        );
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            environment,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let entry = context.process(
+            SourceAssetVc::new(project_dir.join("index.js")).into(),
+            Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+        );
+
+        let completion = emit_unused_code_issues(
+            AssetsVc::cell(vec![entry]),
+            project_dir,
+            UnusedCodeOptions {
+                unused_exports: Some(IssueSeverity::Warning),
+                missing_exports: Some(IssueSeverity::Error),
+                unreachable_modules: Some(IssueSeverity::Hint),
+            }
+            .cell(),
+        );
+        completion.await?;
+        let issues = IssueVc::peek_issues_with_path(completion)
+            .await?
+            .await?
+            .get_plain_issues()
+            .await?;
+        let mut issues = issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue.severity,
+                    issue.title.clone(),
+                    issue.description.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        issues.sort();
+
+        // Modules in node_modules are neither reported as unreachable nor for
+        // their unused exports.
+        assert!(
+            descriptions(&issues, "unused export")
+                .iter()
+                .all(|description| !description.contains("`used`")
+                    && !description.contains("unusedInPackage")),
+            "{issues:#?}"
+        );
+        assert_eq!(
+            descriptions(&issues, "module is unreachable").len(),
+            1,
+            "{issues:#?}"
+        );
+        assert!(issues.iter().any(|(severity, title, description)| {
+            *severity == IssueSeverity::Warning
+                && title == "unused export"
+                && description.contains("`unused`")
+        }));
+        assert!(issues.iter().any(|(severity, title, description)| {
+            *severity == IssueSeverity::Error
+                && title == "imported export doesn't exist"
+                && description.contains("`missing` is imported from")
+                && description.ends_with("It exports `unused`, `used`.")
+        }));
+        assert!(issues.iter().any(|(severity, title, description)| {
+            *severity == IssueSeverity::Hint
+                && title == "module is unreachable"
+                && description.contains("unreachable.js")
+        }));
+        Ok(())
+    })
+    .await
+}
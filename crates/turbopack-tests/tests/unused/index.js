@@ -0,0 +1,4 @@
+import { used, missing } from "./lib.js";
+import "pkg";
+
+console.log(used, missing);
@@ -0,0 +1 @@
+export const unreachable = "unreachable";
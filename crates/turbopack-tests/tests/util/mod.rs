@@ -0,0 +1,46 @@
+//! Helpers shared by the tests, which include them with `mod util;`. Every
+//! test binary compiles the module on its own and uses a part of it.
+#![allow(dead_code)]
+
+use std::{future::Future, path::PathBuf};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+/// The root of the workspace, which the tests use as the root of the project
+/// file system.
+pub static WORKSPACE_ROOT: Lazy<String> = Lazy::new(|| {
+    let package_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    package_root
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string()
+});
+
+/// Defines the `register` function of a test, which registers turbopack, the
+/// crates passed as further arguments, and the values of the test itself.
+/// `$test` is the name of the test file without the extension.
+macro_rules! register {
+    ($test:literal $(, $register:path)* $(,)?) => {
+        fn register() {
+            turbopack::register();
+            $($register();)*
+            include!(concat!(env!("OUT_DIR"), "/register_test_", $test, ".rs"));
+        }
+    };
+}
+pub(crate) use register;
+
+/// Runs a test on a single threaded runtime, and panics when it fails.
+pub fn run<T>(test: impl Future<Output = Result<T>>) -> T {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(test)
+        .unwrap()
+}
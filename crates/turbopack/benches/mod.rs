@@ -1,6 +1,8 @@
 use criterion::{criterion_group, criterion_main};
 
 mod node_file_trace;
+mod resolve;
 
 criterion_group!(node_file_trace_benches, node_file_trace::benchmark);
-criterion_main!(node_file_trace_benches);
+criterion_group!(resolve_benches, resolve::benchmark);
+criterion_main!(node_file_trace_benches, resolve_benches);
@@ -0,0 +1,87 @@
+use std::{path::PathBuf, time::Duration};
+
+use criterion::{BenchmarkId, Criterion};
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::DiskFileSystemVc;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    register, resolve::resolve_options, resolve_options_context::ResolveOptionsContext,
+};
+use turbopack_core::{
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    resolve::{parse::RequestVc, pattern::Pattern, resolve},
+};
+
+/// Typical specifiers: relative files and directories, node.js builtins, bare
+/// and scoped packages, and subpaths of packages.
+const SPECIFIERS: &[&str] = &[
+    "./empty.js",
+    "./array-map-require",
+    "fs",
+    "node:path",
+    "axios",
+    "axios/lib/utils",
+    "@azure/cosmos",
+    "react",
+];
+
+pub fn benchmark(c: &mut Criterion) {
+    register();
+
+    let tests_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/node-file-trace")
+        .to_string_lossy()
+        .to_string();
+
+    let mut group = c.benchmark_group("resolve");
+    group.warm_up_time(Duration::from_secs(1));
+    group.measurement_time(Duration::from_secs(5));
+
+    for &specifier in SPECIFIERS {
+        // Every iteration starts with fresh caches, so this measures the
+        // initial resolve, including reading the file system.
+        group.bench_with_input(
+            BenchmarkId::new("cold", specifier),
+            &specifier,
+            |b, &specifier| {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                b.to_async(rt).iter(|| {
+                    let tt = TurboTasks::new(MemoryBackend::default());
+                    let tests_root = tests_root.clone();
+                    async move {
+                        let task = tt.spawn_once_task(async move {
+                            let fs = DiskFileSystemVc::new("tests".to_string(), tests_root);
+                            let context = fs.root().join("integration");
+                            let environment = EnvironmentVc::new(
+                                Value::new(ExecutionEnvironment::NodeJsLambda(
+                                    NodeJsEnvironment::default().into(),
+                                )),
+                                Value::new(EnvironmentIntention::ServerRendering),
+                            );
+                            let options = resolve_options(
+                                context,
+                                ResolveOptionsContext {
+                                    emulate_environment: Some(environment),
+                                    enable_node_modules: true,
+                                    ..Default::default()
+                                }
+                                .cell(),
+                            );
+                            let request = RequestVc::parse(Value::new(Pattern::Constant(
+                                specifier.to_string(),
+                            )));
+                            resolve(context, request, options).await?;
+                            Ok(NothingVc::new().into())
+                        });
+                        tt.wait_task_completion(task, true).await.unwrap();
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
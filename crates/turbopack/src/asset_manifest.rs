@@ -0,0 +1,74 @@
+//! The asset manifest lets application code reference emitted assets, e.g.
+//! og-images or wasm files, by a logical name instead of hardcoding their
+//! URLs:
+//!
+//! ```js
+//! import manifest from "__turbopack_manifest__";
+//!
+//! fetch(manifest["og-image"]);
+//! ```
+//!
+//! The manifest is a generated module which requires the assets, so it maps
+//! the names to the current URLs of the assets, which contain a hash of their
+//! content. A changed asset updates the manifest over HMR.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde_json::json;
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack_core::{
+    asset::AssetVc,
+    resolve::{
+        options::{ImportMap, ImportMapVc, ImportMapping},
+        ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+/// The request which imports the asset manifest.
+pub const ASSET_MANIFEST_REQUEST: &str = "__turbopack_manifest__";
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct AssetManifest {
+    /// The directory which the requests of the assets are relative to.
+    pub project_path: FileSystemPathVc,
+    /// The logical names of assets with the requests of their files, e.g.
+    /// `og-image` => `./public/og.png`.
+    pub assets: BTreeMap<String, String>,
+}
+
+/// The import map which maps the [ASSET_MANIFEST_REQUEST] to the manifest
+/// module.
+#[turbo_tasks::function]
+pub fn asset_manifest_import_map(manifest: AssetManifestVc) -> ImportMapVc {
+    let mut import_map = ImportMap::empty();
+    import_map.insert_exact_alias(
+        ASSET_MANIFEST_REQUEST,
+        ImportMapping::Direct(ResolveResult::asset(asset_manifest_module(manifest)).into()).cell(),
+    );
+    import_map.cell()
+}
+
+/// The module which exports the URLs of the assets by their names.
+#[turbo_tasks::function]
+async fn asset_manifest_module(manifest: AssetManifestVc) -> Result<AssetVc> {
+    let manifest = manifest.await?;
+    let mut entries = String::new();
+    for (name, request) in manifest.assets.iter() {
+        entries.push_str(&format!(
+            "  {}: require({}),\n",
+            json!(name),
+            json!(request)
+        ));
+    }
+    let code = format!("__turbopack_export_value__({{\n{entries}}});\n");
+    Ok(VirtualAssetVc::new(
+        manifest
+            .project_path
+            .join(&format!("{ASSET_MANIFEST_REQUEST}.js")),
+        File::from(code).into(),
+    )
+    .into())
+}
@@ -0,0 +1,188 @@
+//! A driver for production builds which runs outside of the dev server, e.g.
+//! for long-running builders in electron or browser extension development.
+//!
+//! In watch mode the build keeps running, and recomputes the outputs when the
+//! inputs change. Only outputs whose content changed are written again, and
+//! outputs which are no longer emitted, e.g. of a removed entry, are deleted.
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use turbo_tasks::{
+    backend::Backend, CompletionVc, CompletionsVc, NothingVc, TryJoinIterExt, TurboTasks,
+};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetsVc},
+    reference::all_assets,
+};
+
+use crate::{
+    emit_asset,
+    project::{BuildResultReadRef, ProjectOptions, ProjectOptionsVc, ProjectVc},
+};
+
+/// Emits the assets, and all the assets they reference, into `output_dir`.
+/// Referenced assets outside of `output_dir`, e.g. sources, are not emitted.
+///
+/// The assets are emitted in the order of their paths, independent of the
+/// order in which they are found or computed, so that the same inputs always
+/// result in the same output tree.
+///
+/// Files which a previous emit into `output_dir` wrote, but which are no
+/// longer part of the build, e.g. the chunks of a removed entry, are deleted,
+/// so they don't linger in the output. Other files in `output_dir` are left
+/// alone.
+#[turbo_tasks::function]
+pub async fn emit_build(assets: AssetsVc, output_dir: FileSystemPathVc) -> Result<CompletionVc> {
+    let output_dir_value = output_dir.await?;
+    let graphs = assets
+        .await?
+        .iter()
+        .map(|&asset| all_assets(asset))
+        .try_join()
+        .await?;
+    // The paths are resolved concurrently instead of one asset after another.
+    let mut outputs = graphs
+        .iter()
+        .flat_map(|graph| graph.iter())
+        .map(|&asset| async move { Ok::<_, anyhow::Error>((asset.path().await?, asset)) })
+        .try_join()
+        .await?
+        .into_iter()
+        .filter(|(path, _)| path.is_inside(&output_dir_value))
+        .map(|(path, asset)| (path.path.clone(), asset))
+        .collect::<Vec<_>>();
+    outputs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    outputs.dedup_by(|(a, _), (b, _)| a == b);
+
+    let stale_paths = {
+        let emitted_files = emitted_files(output_dir).await?;
+        let mut paths = emitted_files.paths.lock().unwrap();
+        let current_paths = outputs
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect::<BTreeSet<_>>();
+        let stale_paths = paths
+            .difference(&current_paths)
+            .cloned()
+            .collect::<Vec<_>>();
+        *paths = current_paths;
+        stale_paths
+    };
+    let root = output_dir.root();
+    let completions = outputs
+        .into_iter()
+        .map(|(_, asset)| emit_asset(asset))
+        .chain(
+            stale_paths
+                .iter()
+                .map(|path| root.join(path).write(FileContent::NotFound.cell())),
+        )
+        .collect();
+    Ok(CompletionsVc::cell(completions).all())
+}
+
+/// The paths of the files [emit_build] wrote into an output directory.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+struct EmittedFiles {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    paths: Mutex<BTreeSet<String>>,
+}
+
+/// Doesn't read anything, so it's only executed once per output directory,
+/// and the emitted files are kept between builds.
+#[turbo_tasks::function]
+fn emitted_files(_output_dir: FileSystemPathVc) -> EmittedFilesVc {
+    EmittedFiles {
+        paths: Mutex::new(BTreeSet::new()),
+    }
+    .cell()
+}
+
+/// Enables the optimizations of production builds: tree shaking, and
+/// minification with the default options unless `options` configure it.
+#[turbo_tasks::function]
+pub async fn production_options(options: ProjectOptionsVc) -> Result<ProjectOptionsVc> {
+    let options = options.await?;
+    Ok(ProjectOptions {
+        tree_shaking: true,
+        minify: Some(options.minify.clone().unwrap_or_default()),
+        ..options.clone_value()
+    }
+    .cell())
+}
+
+/// Options of [run_build].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Keeps running and rebuilds when inputs change. The file systems of the
+    /// inputs need to be watched, see
+    /// [turbo_tasks_fs::DiskFileSystem::start_watching].
+    pub watch: bool,
+}
+
+/// A finished build of [run_build].
+pub struct BuildReport {
+    /// The outputs and issues of the build, or the error which failed it.
+    pub result: Result<BuildResultReadRef>,
+    /// How long the tasks of the build took to execute.
+    pub duration: Duration,
+    /// The number of tasks which were executed.
+    pub tasks: usize,
+}
+
+/// Runs a production build of the project with the options returned by
+/// `project_options`, see [production_options]. They are created in a root
+/// task, so `project_options` can create the file systems and contexts of the
+/// project.
+///
+/// Every build is passed to `on_build`. Without watching, this returns when
+/// the build is done. With watching, the root task is recomputed whenever an
+/// input changes, and this never returns. A failing build doesn't stop
+/// watching, as the next change may fix it.
+pub async fn run_build<B>(
+    turbo_tasks: Arc<TurboTasks<B>>,
+    options: BuildOptions,
+    project_options: impl Fn() -> ProjectOptionsVc + Send + Sync + 'static,
+    mut on_build: impl FnMut(BuildReport),
+) -> Result<()>
+where
+    B: Backend + 'static,
+{
+    let latest = Arc::new(Mutex::new(None));
+    let task = {
+        let latest = latest.clone();
+        turbo_tasks.spawn_root_task(move || {
+            let project = ProjectVc::new(production_options(project_options()));
+            let latest = latest.clone();
+            Box::pin(async move {
+                let result = project.build().strongly_consistent().await;
+                *latest.lock().unwrap() = Some(result);
+                Ok(NothingVc::new().into())
+            })
+        })
+    };
+
+    loop {
+        let (duration, tasks) = turbo_tasks
+            .get_or_wait_update_info(Duration::from_millis(100))
+            .await;
+        turbo_tasks.wait_task_completion(task, true).await?;
+        let result = latest.lock().unwrap().take();
+        if let Some(result) = result {
+            on_build(BuildReport {
+                result,
+                duration,
+                tasks,
+            });
+        }
+        if !options.watch {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Leaves requests to packages in `node_modules` as external `require()`
+//! calls, so server bundles use the packages that are installed natively next
+//! to them at runtime.
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc, StringsVc},
+    ValueToString, ValueToStringVc,
+};
+use turbo_tasks_fs::{
+    glob::GlobVc, DirectoryEntry, FileJsonContent, FileSystemEntryType, FileSystemPathVc,
+};
+use turbopack_core::{
+    asset::Asset,
+    reference::{AssetReference, AssetReferenceVc},
+    resolve::{
+        find_context_file,
+        node::node_cjs_resolve_options,
+        package_json,
+        parse::{Request, RequestVc},
+        plugin::{ResolvePlugin, ResolvePluginConditionVc},
+        resolve, FindContextFileResult, PrimaryResolveResult, ResolveResult, ResolveResultOptionVc,
+        ResolveResultVc,
+    },
+    source_asset::SourceAssetVc,
+};
+
+/// A resolve plugin which marks requests to CommonJS packages in
+/// `node_modules` as external, when Node.js resolves them to the same file.
+/// ES module packages are bundled, as they can't be loaded with `require()`.
+#[turbo_tasks::value]
+pub struct ExternalPackagesResolvePlugin {
+    root: FileSystemPathVc,
+    bundled_packages: StringsVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ExternalPackagesResolvePluginVc {
+    /// `bundled_packages` are bundled nonetheless, e.g. because they need to
+    /// be transpiled.
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc, bundled_packages: StringsVc) -> Self {
+        ExternalPackagesResolvePlugin {
+            root,
+            bundled_packages,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::function]
+async fn is_node_resolveable(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    expected: FileSystemPathVc,
+) -> Result<BoolVc> {
+    let node_resolve_result = resolve(context, request, node_cjs_resolve_options(context.root()));
+    let primary_node_assets = node_resolve_result.primary_assets().await?;
+    let Some(node_asset) = primary_node_assets.first() else {
+        // can't resolve request with node.js options
+        return Ok(BoolVc::cell(false));
+    };
+
+    if node_asset.path().resolve().await? != expected.resolve().await? {
+        // node.js resolves to a different file
+        return Ok(BoolVc::cell(false));
+    }
+
+    Ok(BoolVc::cell(true))
+}
+
+lazy_static! {
+    static ref PNPM: Regex = Regex::new(r"(?:/|^)node_modules/(.pnpm/.+)").unwrap();
+}
+
+#[turbo_tasks::function]
+fn condition(root: FileSystemPathVc) -> ResolvePluginConditionVc {
+    ResolvePluginConditionVc::new(root.root(), GlobVc::new("**/node_modules/**"))
+}
+
+#[turbo_tasks::value_impl]
+impl ResolvePlugin for ExternalPackagesResolvePlugin {
+    #[turbo_tasks::function]
+    fn after_resolve_condition(&self) -> ResolvePluginConditionVc {
+        condition(self.root)
+    }
+
+    #[turbo_tasks::function]
+    async fn after_resolve(
+        &self,
+        fs_path: FileSystemPathVc,
+        context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ResolveResultOptionVc> {
+        if *condition(self.root).matches(context).await? {
+            return Ok(ResolveResultOptionVc::none());
+        }
+        if !matches!(&*request.await?, Request::Module { .. }) {
+            return Ok(ResolveResultOptionVc::none());
+        }
+
+        let raw_fs_path = &*fs_path.await?;
+
+        // always bundle packages that are configured to be bundled
+        let bundled_glob = packages_glob(self.bundled_packages).await?;
+        if bundled_glob.execute(&raw_fs_path.path) {
+            return Ok(ResolveResultOptionVc::none());
+        }
+
+        // node.js only supports these file extensions
+        // mjs is an esm module and we can't bundle that yet
+        if !matches!(
+            raw_fs_path.extension(),
+            Some("cjs" | "js" | "node" | "json")
+        ) {
+            return Ok(ResolveResultOptionVc::none());
+        }
+
+        let FindContextFileResult::Found(package_json, _) =
+            *find_context_file(fs_path.parent(), package_json()).await?
+        else {
+            // can't find package.json
+            return Ok(ResolveResultOptionVc::none());
+        };
+        let FileJsonContent::Content(package) = &*package_json.read_json().await? else {
+            // can't parse package.json
+            return Ok(ResolveResultOptionVc::none());
+        };
+
+        // always bundle esm modules
+        if let Some("module") = package["type"].as_str() {
+            return Ok(ResolveResultOptionVc::none());
+        }
+
+        // check if we can resolve the package from the project dir with node.js resolve
+        // options (might be hidden by pnpm)
+        if *is_node_resolveable(self.root.root(), request, fs_path).await? {
+            // mark as external
+            return Ok(ResolveResultOptionVc::some(
+                ResolveResult::primary(PrimaryResolveResult::OriginalReferenceExternal).cell(),
+            ));
+        }
+
+        // Special behavior for pnpm as we could reference all .pnpm modules by
+        // referencing the `.pnpm` folder as module, e. g.
+        // /node_modules/.pnpm/some-package@2.29.2/node_modules/some-package/dir/file.js
+        // becomes
+        // .pnpm/some-package@2.29.2/node_modules/some-package/dir/file.js
+        if let Some(captures) = PNPM.captures(&fs_path.await?.path) {
+            if let Some(import_path) = captures.get(1) {
+                // we could load it directly as external, but we want to make sure node.js would
+                // resolve it the same way e. g. that we didn't follow any special resolve
+                // options, to come here like the `module` field in package.json
+                if *is_node_resolveable(context, request, fs_path).await? {
+                    // mark as external
+                    return Ok(ResolveResultOptionVc::some(
+                        ResolveResult::primary(
+                            PrimaryResolveResult::OriginalReferenceTypeExternal(
+                                import_path.as_str().to_string(),
+                            ),
+                        )
+                        .cell(),
+                    ));
+                }
+            }
+        }
+        Ok(ResolveResultOptionVc::none())
+    }
+}
+
+#[turbo_tasks::function]
+async fn packages_glob(packages: StringsVc) -> Result<GlobVc> {
+    Ok(GlobVc::new(&format!(
+        "**/node_modules/{{{}}}/**",
+        packages.await?.join(",")
+    )))
+}
+
+/// A resolve plugin which marks requests to packages with native addons as
+/// external, i. e. packages with a `binding.gyp` or prebuilt binaries in a
+/// `prebuilds` directory. They load their binaries from paths which are
+/// computed at runtime, so they break when they are bundled.
+///
+/// The external references the `.node` files of the package, so they are
+/// traced like the other files the server needs.
+#[turbo_tasks::value]
+pub struct NativePackagesResolvePlugin {
+    root: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NativePackagesResolvePluginVc {
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc) -> Self {
+        NativePackagesResolvePlugin { root }.cell()
+    }
+}
+
+#[turbo_tasks::function]
+async fn is_native_package(package_dir: FileSystemPathVc) -> Result<BoolVc> {
+    if let FileSystemEntryType::File = *package_dir.join("binding.gyp").get_type().await? {
+        return Ok(BoolVc::cell(true));
+    }
+    Ok(BoolVc::cell(matches!(
+        *package_dir.join("prebuilds").get_type().await?,
+        FileSystemEntryType::Directory
+    )))
+}
+
+#[turbo_tasks::value_impl]
+impl ResolvePlugin for NativePackagesResolvePlugin {
+    #[turbo_tasks::function]
+    fn after_resolve_condition(&self) -> ResolvePluginConditionVc {
+        condition(self.root)
+    }
+
+    #[turbo_tasks::function]
+    async fn after_resolve(
+        &self,
+        fs_path: FileSystemPathVc,
+        context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ResolveResultOptionVc> {
+        if !matches!(&*request.await?, Request::Module { .. }) {
+            return Ok(ResolveResultOptionVc::none());
+        }
+        let FindContextFileResult::Found(package_json, _) =
+            *find_context_file(fs_path.parent(), package_json()).await?
+        else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+        let package_dir = package_json.parent();
+        if !*is_native_package(package_dir).await? {
+            return Ok(ResolveResultOptionVc::none());
+        }
+        // Node.js needs to find the same package at runtime
+        if !*is_node_resolveable(context, request, fs_path).await? {
+            return Ok(ResolveResultOptionVc::none());
+        }
+        Ok(ResolveResultOptionVc::some(
+            ResolveResult::primary_with_references(
+                PrimaryResolveResult::OriginalReferenceExternal,
+                vec![NativeBinariesReferenceVc::new(package_dir).into()],
+            )
+            .cell(),
+        ))
+    }
+}
+
+/// References the `.node` files of a native package.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct NativeBinariesReference {
+    package_dir: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NativeBinariesReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(package_dir: FileSystemPathVc) -> Self {
+        NativeBinariesReference { package_dir }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for NativeBinariesReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        let mut files = self
+            .package_dir
+            .read_glob(GlobVc::new("**/*.node"), false)
+            .all_matches()
+            .await?
+            .into_iter()
+            .filter_map(|(relative_path, entry)| match entry {
+                DirectoryEntry::File(path) => Some((relative_path, path)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // The glob results are unordered.
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(ResolveResult::assets(
+            files
+                .into_iter()
+                .map(|(_, path)| SourceAssetVc::new(path).into())
+                .collect(),
+        )
+        .into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for NativeBinariesReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "native binaries of {}",
+            self.package_dir.to_string().await?
+        )))
+    }
+}
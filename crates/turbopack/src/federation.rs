@@ -0,0 +1,390 @@
+//! Module federation lets separately built applications share modules at
+//! runtime. A build exposes modules through a container, imports modules
+//! exposed by the containers of other builds (remotes), and negotiates shared
+//! packages like `react` with the other containers on the page.
+//!
+//! The federated modules are generated JavaScript modules in the
+//! [FEDERATION_DIRECTORY], which use the federation runtime. Requests from
+//! this directory are resolved without the federation import map, so they
+//! reach the local modules. The runtime of the chunking context needs to
+//! include the federation runtime, see `ChunkingContext::federation`.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use turbo_tasks::trace::TraceRawVcs;
+use turbo_tasks_fs::{File, FileJsonContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+    resolve::{
+        options::{
+            ImportMap, ImportMapResult, ImportMapResultVc, ImportMapVc, ImportMapping,
+            ImportMappingReplacement, ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::RequestVc,
+        ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+/// The directory of the generated federation modules.
+pub const FEDERATION_DIRECTORY: &str = "__federation__";
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+pub struct FederationOptions {
+    /// The name of the container, under which other builds load it.
+    pub name: String,
+    /// Modules that are exposed to other builds, e.g. `./Button` =>
+    /// `./src/Button`. The requests are relative to the project directory.
+    pub exposes: BTreeMap<String, String>,
+    /// The containers of other builds by their name, with the URL of their
+    /// remote entry, e.g. `app2` => `https://app2.example.com/remoteEntry.js`.
+    /// `import "app2/Button"` imports the module `./Button` exposed by `app2`.
+    pub remotes: BTreeMap<String, String>,
+    /// Packages that are shared with the other containers on the page.
+    pub shared: BTreeMap<String, SharedDependency>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct SharedDependency {
+    /// The version of the local package. Defaults to the version in the
+    /// package.json of the package in `node_modules`.
+    pub version: Option<String>,
+    /// The versions of the package that can be used, e.g. `^18.0.0`. When not
+    /// set, any version is used.
+    pub required_version: Option<String>,
+    /// Only a single version of the package is used on the page, even when it
+    /// doesn't satisfy the required version.
+    pub singleton: bool,
+}
+
+/// The import map that replaces requests to remotes and shared packages with
+/// the federated modules. `root` is the directory the federated modules are
+/// placed in.
+#[turbo_tasks::function]
+pub async fn federation_import_map(
+    root: FileSystemPathVc,
+    options: FederationOptionsVc,
+) -> Result<ImportMapVc> {
+    let options_value = options.await?;
+    let mut import_map = ImportMap::empty();
+    for (name, url) in options_value.remotes.iter() {
+        import_map.insert_wildcard_alias(
+            format!("{name}/"),
+            ImportMapping::Dynamic(
+                FederationRemoteReplacerVc::new(root, name.clone(), url.clone()).into(),
+            )
+            .cell(),
+        );
+    }
+    for name in options_value.shared.keys() {
+        let module = shared_module(root, options, name.clone());
+        import_map.insert_exact_alias(
+            name,
+            ImportMapping::Direct(ResolveResult::asset(module).into()).cell(),
+        );
+    }
+    Ok(import_map.cell())
+}
+
+#[turbo_tasks::value(shared)]
+struct FederationRemoteReplacer {
+    root: FileSystemPathVc,
+    name: String,
+    url: String,
+}
+
+#[turbo_tasks::value_impl]
+impl FederationRemoteReplacerVc {
+    #[turbo_tasks::function]
+    fn new(root: FileSystemPathVc, name: String, url: String) -> Self {
+        Self::cell(FederationRemoteReplacer { root, name, url })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for FederationRemoteReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, capture: &str) -> ImportMappingVc {
+        let module = remote_module(
+            self.root,
+            self.name.clone(),
+            self.url.clone(),
+            capture.to_string(),
+        );
+        ImportMapping::Direct(ResolveResult::asset(module).into()).cell()
+    }
+
+    #[turbo_tasks::function]
+    fn result(&self, _request: RequestVc) -> ImportMapResultVc {
+        ImportMapResult::NoEntry.into()
+    }
+}
+
+/// A module that loads the module exposed by a remote container. It uses
+/// top-level await, so importers wait until the remote module is loaded.
+#[turbo_tasks::function]
+fn remote_module(root: FileSystemPathVc, name: String, url: String, module: String) -> AssetVc {
+    VirtualAssetVc::new(
+        root.join(&format!("{FEDERATION_DIRECTORY}/remote/{name}/{module}.js")),
+        File::from(remote_module_code(&name, &url, &module)).into(),
+    )
+    .into()
+}
+
+fn remote_module_code(name: &str, url: &str, module: &str) -> String {
+    format!(
+        "const namespace = await __turbopack_federation__.loadRemote({}, {}, \
+         {});\n__turbopack_export_value__(namespace);\nexport {{}};\n",
+        json!(name),
+        json!(url),
+        json!(format!("./{module}")),
+    )
+}
+
+/// A module that uses the best version of a shared package that is loaded on
+/// the page, falling back to the local package.
+#[turbo_tasks::function]
+async fn shared_module(
+    root: FileSystemPathVc,
+    options: FederationOptionsVc,
+    name: String,
+) -> Result<AssetVc> {
+    let options = options.await?;
+    let mut shared = options.shared.get(&name).cloned().unwrap_or_default();
+    if shared.version.is_none() {
+        shared.version = local_package_version(root, &name).await?;
+    }
+    Ok(VirtualAssetVc::new(
+        root.join(&format!("{FEDERATION_DIRECTORY}/shared/{name}.js")),
+        File::from(shared_module_code(&options.name, &name, &shared)).into(),
+    )
+    .into())
+}
+
+fn shared_module_code(container: &str, name: &str, shared: &SharedDependency) -> String {
+    let config = json!({
+        "version": shared.version,
+        "requiredVersion": shared.required_version,
+        "singleton": shared.singleton,
+        "from": container,
+    });
+    format!(
+        "import * as local from \
+         {};\n__turbopack_export_value__(__turbopack_federation__.consumeShared({}, {config}, \
+         local));\n",
+        json!(name),
+        json!(name),
+    )
+}
+
+async fn local_package_version(root: FileSystemPathVc, name: &str) -> Result<Option<String>> {
+    let package_json = root.join(&format!("node_modules/{name}/package.json"));
+    Ok(match &*package_json.read_json().await? {
+        FileJsonContent::Content(json) => json["version"].as_str().map(|v| v.to_string()),
+        _ => None,
+    })
+}
+
+/// The entry module of the container, which exposes modules to other builds.
+/// It needs to be evaluated by a chunk of its own, the remote entry that other
+/// builds load. The exposed modules are loaded on demand.
+#[turbo_tasks::function]
+pub async fn federation_container_entry(
+    project_path: FileSystemPathVc,
+    options: FederationOptionsVc,
+) -> Result<AssetVc> {
+    let options = options.await?;
+    Ok(VirtualAssetVc::new(
+        project_path.join(&format!("{FEDERATION_DIRECTORY}/container.js")),
+        File::from(container_entry_code(&options)).into(),
+    )
+    .into())
+}
+
+fn container_entry_code(options: &FederationOptions) -> String {
+    let mut exposes = String::new();
+    for (key, request) in options.exposes.iter() {
+        // The container is placed in a directory of the project.
+        let request = match request.strip_prefix("./") {
+            Some(path) => format!("../{path}"),
+            None => request.clone(),
+        };
+        exposes.push_str(&format!(
+            "  {}: () => import({}),\n",
+            json!(key),
+            json!(request)
+        ));
+    }
+    format!(
+        "__turbopack_export_value__(__turbopack_federation__.createContainer({}, \
+         {{\n{exposes}}}));\n",
+        json!(options.name),
+    )
+}
+
+/// A JSON manifest that describes the container, i. e. its name, the exposed
+/// modules, the remotes and the shared packages. `remoteEntry` is the path of
+/// the chunk of the [federation_container_entry], relative to the manifest,
+/// which other builds load.
+#[turbo_tasks::value]
+pub struct FederationManifestAsset {
+    path: FileSystemPathVc,
+    options: FederationOptionsVc,
+    remote_entry: Option<AssetVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl FederationManifestAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        options: FederationOptionsVc,
+        remote_entry: Option<AssetVc>,
+    ) -> Self {
+        Self::cell(FederationManifestAsset {
+            path,
+            options,
+            remote_entry,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for FederationManifestAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let options = self.options.await?;
+        let remote_entry = match self.remote_entry {
+            Some(remote_entry) => self
+                .path
+                .parent()
+                .await?
+                .get_path_to(&*remote_entry.path().await?)
+                .map(|path| path.to_string()),
+            None => None,
+        };
+        Ok(File::from(manifest_json(&options, remote_entry.as_deref())?).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+fn manifest_json(options: &FederationOptions, remote_entry: Option<&str>) -> Result<String> {
+    let shared: BTreeMap<_, _> = options
+        .shared
+        .iter()
+        .map(|(name, shared)| {
+            (
+                name,
+                json!({
+                    "version": shared.version,
+                    "requiredVersion": shared.required_version,
+                    "singleton": shared.singleton,
+                }),
+            )
+        })
+        .collect();
+    let manifest = json!({
+        "name": options.name,
+        "remoteEntry": remote_entry,
+        "exposes": options.exposes.keys().collect::<Vec<_>>(),
+        "remotes": options.remotes,
+        "shared": shared,
+    });
+    Ok(serde_json::to_string_pretty(&manifest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> FederationOptions {
+        FederationOptions {
+            name: "app1".to_string(),
+            exposes: BTreeMap::from([
+                ("./Button".to_string(), "./src/Button".to_string()),
+                ("./utils".to_string(), "utils-package".to_string()),
+            ]),
+            remotes: BTreeMap::from([(
+                "app2".to_string(),
+                "https://app2.example.com/remoteEntry.js".to_string(),
+            )]),
+            shared: BTreeMap::from([(
+                "react".to_string(),
+                SharedDependency {
+                    version: None,
+                    required_version: Some("^18.0.0".to_string()),
+                    singleton: true,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn remote_module_loads_the_exposed_module() {
+        assert_eq!(
+            remote_module_code("app2", "https://app2.example.com/remoteEntry.js", "Button"),
+            "const namespace = await __turbopack_federation__.loadRemote(\"app2\", \
+             \"https://app2.example.com/remoteEntry.js\", \
+             \"./Button\");\n__turbopack_export_value__(namespace);\nexport {};\n"
+        );
+    }
+
+    #[test]
+    fn shared_module_falls_back_to_the_local_package() {
+        let shared = SharedDependency {
+            version: Some("18.2.0".to_string()),
+            required_version: Some("^18.0.0".to_string()),
+            singleton: true,
+        };
+        assert_eq!(
+            shared_module_code("app1", "react", &shared),
+            r#"import * as local from "react";
+__turbopack_export_value__(__turbopack_federation__.consumeShared("react", {"version":"18.2.0","requiredVersion":"^18.0.0","singleton":true,"from":"app1"}, local));
+"#
+        );
+    }
+
+    #[test]
+    fn container_imports_exposed_modules_from_the_project() {
+        assert_eq!(
+            container_entry_code(&options()),
+            "__turbopack_export_value__(__turbopack_federation__.createContainer(\"app1\", {\n  \
+             \"./Button\": () => import(\"../src/Button\"),\n  \"./utils\": () => \
+             import(\"utils-package\"),\n}));\n"
+        );
+    }
+
+    #[test]
+    fn manifest_describes_the_container() {
+        let manifest: serde_json::Value = serde_json::from_str(
+            &manifest_json(&options(), Some("__federation___container_1f2a3b.js")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            json!({
+                "name": "app1",
+                "remoteEntry": "__federation___container_1f2a3b.js",
+                "exposes": ["./Button", "./utils"],
+                "remotes": { "app2": "https://app2.example.com/remoteEntry.js" },
+                "shared": {
+                    "react": { "version": null, "requiredVersion": "^18.0.0", "singleton": true },
+                },
+            })
+        );
+    }
+}
@@ -1,6 +1,5 @@
-use std::collections::HashSet;
-
 use anyhow::Result;
+use indexmap::IndexSet;
 use turbopack_core::{asset::AssetVc, reference::all_referenced_assets};
 
 #[turbo_tasks::value(shared)]
@@ -8,8 +7,8 @@ pub enum AggregatedGraph {
     Leaf(AssetVc),
     Node {
         depth: usize,
-        content: HashSet<AggregatedGraphVc>,
-        references: HashSet<AggregatedGraphVc>,
+        content: IndexSet<AggregatedGraphVc>,
+        references: IndexSet<AggregatedGraphVc>,
     },
 }
 
@@ -46,7 +45,7 @@ impl AggregatedGraphVc {
     async fn references(self) -> Result<AggregatedGraphsSetVc> {
         Ok(match *self.await? {
             AggregatedGraph::Leaf(asset) => {
-                let mut refs = HashSet::new();
+                let mut refs = IndexSet::new();
                 for reference in all_referenced_assets(asset).await?.iter() {
                     let reference = reference.resolve().await?;
                     if asset != reference {
@@ -56,7 +55,7 @@ impl AggregatedGraphVc {
                 AggregatedGraphsSet { set: refs }.into()
             }
             AggregatedGraph::Node { ref references, .. } => {
-                let mut set = HashSet::new();
+                let mut set = IndexSet::new();
                 for item in references
                     .iter()
                     .map(|&reference| aggregate_more(reference))
@@ -85,9 +84,9 @@ impl AggregatedGraphVc {
     #[turbo_tasks::function]
     async fn valued_references(self) -> Result<AggregatedGraphsValuedReferencesVc> {
         let self_cost = self.cost().await?.0;
-        let mut inner = HashSet::new();
-        let mut outer = HashSet::new();
-        let mut references = HashSet::new();
+        let mut inner = IndexSet::new();
+        let mut outer = IndexSet::new();
+        let mut references = IndexSet::new();
         for (reference, cost) in self
             .references()
             .await?
@@ -133,9 +132,9 @@ struct AggregationCost(usize);
 async fn aggregate_more(node: AggregatedGraphVc) -> Result<AggregatedGraphVc> {
     let node_data = node.await?;
     let depth = node_data.depth();
-    let mut in_progress = HashSet::new();
-    let mut content = HashSet::new();
-    let mut references = HashSet::new();
+    let mut in_progress = IndexSet::new();
+    let mut content = IndexSet::new();
+    let mut references = IndexSet::new();
     in_progress.insert(node);
 
     // only one kind of aggregation can't eliminate cycles with that
@@ -147,7 +146,7 @@ async fn aggregate_more(node: AggregatedGraphVc) -> Result<AggregatedGraphVc> {
             content.insert(node);
         }
         let valued_refs = in_progress
-            .drain()
+            .drain(..)
             .map(|node| node.valued_references())
             .collect::<Vec<_>>();
         for valued_refs in valued_refs {
@@ -165,7 +164,7 @@ async fn aggregate_more(node: AggregatedGraphVc) -> Result<AggregatedGraphVc> {
                 if content.contains(&reference) {
                     continue;
                 }
-                references.remove(&reference);
+                references.shift_remove(&reference);
                 in_progress.insert(reference);
             }
         }
@@ -183,18 +182,18 @@ async fn aggregate_more(node: AggregatedGraphVc) -> Result<AggregatedGraphVc> {
 
 #[turbo_tasks::value(shared)]
 struct AggregatedGraphsSet {
-    pub set: HashSet<AggregatedGraphVc>,
+    pub set: IndexSet<AggregatedGraphVc>,
 }
 
 #[turbo_tasks::value(shared)]
 pub enum AggregatedGraphNodeContent {
     Asset(AssetVc),
-    Children(HashSet<AggregatedGraphVc>),
+    Children(IndexSet<AggregatedGraphVc>),
 }
 
 #[turbo_tasks::value(shared)]
 struct AggregatedGraphsValuedReferences {
-    pub inner: HashSet<AggregatedGraphVc>,
-    pub outer: HashSet<AggregatedGraphVc>,
-    pub references: HashSet<AggregatedGraphVc>,
+    pub inner: IndexSet<AggregatedGraphVc>,
+    pub outer: IndexSet<AggregatedGraphVc>,
+    pub references: IndexSet<AggregatedGraphVc>,
 }
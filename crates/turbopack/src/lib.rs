@@ -16,8 +16,8 @@ use std::{
 use anyhow::Result;
 use css::{CssModuleAssetVc, ModuleCssModuleAssetVc};
 use ecmascript::{
-    typescript::resolve::TypescriptTypesAssetReferenceVc, EcmascriptModuleAssetType,
-    EcmascriptModuleAssetVc,
+    native_module::NodeNativeModuleAssetVc, typescript::resolve::TypescriptTypesAssetReferenceVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
 };
 use graph::{aggregate, AggregatedGraphNodeContent, AggregatedGraphVc};
 use lazy_static::lazy_static;
@@ -27,7 +27,7 @@ use module_options::{
 pub use resolve::resolve_options;
 use turbo_tasks::{
     primitives::{BoolVc, StringVc},
-    CompletionVc, Value,
+    CompletionVc, CompletionsVc, Value,
 };
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
@@ -48,10 +48,16 @@ use turbopack_core::{
 
 use crate::transition::Transition;
 
+pub mod asset_manifest;
+pub mod build;
 pub mod condition;
 pub mod evaluate_context;
+pub mod external_packages;
+pub mod federation;
 mod graph;
 pub mod module_options;
+pub mod module_replacement;
+pub mod project;
 pub mod rebase;
 pub mod resolve;
 pub mod resolve_options_context;
@@ -59,6 +65,8 @@ pub mod transition;
 
 pub use turbopack_css as css;
 pub use turbopack_ecmascript as ecmascript;
+pub use turbopack_html as html;
+use turbopack_html::HtmlModuleAssetVc;
 use turbopack_json::JsonModuleAssetVc;
 use turbopack_mdx::MdxModuleAssetVc;
 use turbopack_static::StaticModuleAssetVc;
@@ -111,40 +119,45 @@ async fn apply_module_type(
     module_type: ModuleTypeVc,
 ) -> Result<AssetVc> {
     Ok(match &*module_type.await? {
-        ModuleType::Ecmascript(transforms) => EcmascriptModuleAssetVc::new(
+        ModuleType::Ecmascript(transforms, options) => EcmascriptModuleAssetVc::new(
             source,
             context.into(),
             Value::new(EcmascriptModuleAssetType::Ecmascript),
             *transforms,
+            Value::new(*options),
             context.environment(),
         )
         .into(),
-        ModuleType::Typescript(transforms) => EcmascriptModuleAssetVc::new(
+        ModuleType::Typescript(transforms, options) => EcmascriptModuleAssetVc::new(
             source,
             context.into(),
             Value::new(EcmascriptModuleAssetType::Typescript),
             *transforms,
+            Value::new(*options),
             context.environment(),
         )
         .into(),
-        ModuleType::TypescriptWithTypes(transforms) => EcmascriptModuleAssetVc::new(
+        ModuleType::TypescriptWithTypes(transforms, options) => EcmascriptModuleAssetVc::new(
             source,
             context.with_types_resolving_enabled().into(),
             Value::new(EcmascriptModuleAssetType::TypescriptWithTypes),
             *transforms,
+            Value::new(*options),
             context.environment(),
         )
         .into(),
-        ModuleType::TypescriptDeclaration(transforms) => EcmascriptModuleAssetVc::new(
+        ModuleType::TypescriptDeclaration(transforms, options) => EcmascriptModuleAssetVc::new(
             source,
             context.with_types_resolving_enabled().into(),
             Value::new(EcmascriptModuleAssetType::TypescriptDeclaration),
             *transforms,
+            Value::new(*options),
             context.environment(),
         )
         .into(),
         ModuleType::Json => JsonModuleAssetVc::new(source).into(),
         ModuleType::Raw => source,
+        ModuleType::NodeNative => NodeNativeModuleAssetVc::new(source).into(),
         ModuleType::Css(transforms) => {
             CssModuleAssetVc::new(source, context.into(), *transforms).into()
         }
@@ -152,6 +165,7 @@ async fn apply_module_type(
             ModuleCssModuleAssetVc::new(source, context.into(), *transforms).into()
         }
         ModuleType::Static => StaticModuleAssetVc::new(source, context.into()).into(),
+        ModuleType::Html => HtmlModuleAssetVc::new(source, context.into()).into(),
         ModuleType::Mdx(transforms) => {
             MdxModuleAssetVc::new(source, context.into(), *transforms).into()
         }
@@ -187,15 +201,22 @@ async fn module(
                     }
                     ModuleRuleEffect::AddEcmascriptTransforms(additional_transforms) => {
                         current_module_type = match current_module_type {
-                            Some(ModuleType::Ecmascript(transforms)) => Some(
-                                ModuleType::Ecmascript(transforms.extend(*additional_transforms)),
-                            ),
-                            Some(ModuleType::Typescript(transforms)) => Some(
-                                ModuleType::Typescript(transforms.extend(*additional_transforms)),
-                            ),
-                            Some(ModuleType::TypescriptWithTypes(transforms)) => {
+                            Some(ModuleType::Ecmascript(transforms, options)) => {
+                                Some(ModuleType::Ecmascript(
+                                    transforms.extend(*additional_transforms),
+                                    options,
+                                ))
+                            }
+                            Some(ModuleType::Typescript(transforms, options)) => {
+                                Some(ModuleType::Typescript(
+                                    transforms.extend(*additional_transforms),
+                                    options,
+                                ))
+                            }
+                            Some(ModuleType::TypescriptWithTypes(transforms, options)) => {
                                 Some(ModuleType::TypescriptWithTypes(
                                     transforms.extend(*additional_transforms),
+                                    options,
                                 ))
                             }
                             Some(module_type) => {
@@ -460,12 +481,15 @@ async fn emit_aggregated_assets(
 ) -> Result<CompletionVc> {
     Ok(match &*aggregated.content().await? {
         AggregatedGraphNodeContent::Asset(asset) => emit_asset_into_dir(*asset, output_dir),
-        AggregatedGraphNodeContent::Children(children) => {
-            for aggregated in children {
-                emit_aggregated_assets(*aggregated, output_dir).await?;
-            }
-            CompletionVc::new()
-        }
+        // The children are emitted in parallel. Only the writes to disk are
+        // limited by the file system.
+        AggregatedGraphNodeContent::Children(children) => CompletionsVc::cell(
+            children
+                .iter()
+                .map(|aggregated| emit_aggregated_assets(*aggregated, output_dir))
+                .collect(),
+        )
+        .all(),
     })
 }
 
@@ -618,6 +642,7 @@ pub fn register() {
     turbopack_ecmascript::register();
     turbopack_node::register();
     turbopack_env::register();
+    turbopack_html::register();
     turbopack_mdx::register();
     turbopack_json::register();
     turbopack_static::register();
@@ -8,12 +8,23 @@ pub use module_rule::*;
 pub use rule_condition::*;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    reference_type::{ReferenceType, UrlReferenceSubType},
-    resolve::options::{ImportMap, ImportMapVc, ImportMapping, ImportMappingVc},
+    reference_type::{
+        EcmaScriptModulesReferenceSubType, EntryReferenceSubType, ImportWithType, ReferenceType,
+        UrlReferenceSubType,
+    },
+    resolve::{
+        find_context_file,
+        options::{ImportMap, ImportMapVc, ImportMapping, ImportMappingVc},
+        FindContextFileResult,
+    },
     source_transform::SourceTransformsVc,
 };
 use turbopack_css::{CssInputTransform, CssInputTransformsVc};
-use turbopack_ecmascript::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
+use turbopack_ecmascript::{
+    typescript::resolve::{tsconfig, tsconfig_decorators_options},
+    DecoratorsOptions, EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptOptions,
+    ReactRefreshOptions,
+};
 use turbopack_node::transforms::{postcss::PostCssTransformVc, webpack::WebpackLoadersVc};
 
 use crate::evaluate_context::node_evaluate_asset_context;
@@ -51,18 +62,24 @@ impl ModuleOptionsVc {
             enable_jsx,
             enable_emotion,
             enable_react_refresh,
+            react_refresh_options,
             enable_styled_jsx,
             enable_styled_components,
             enable_types,
             enable_typescript_transform,
+            decorators,
             enable_mdx,
             ref enable_postcss_transform,
             ref enable_webpack_loaders,
             preset_env_versions,
+            preset_env_polyfills,
+            compile_time_defines,
+            feature_flags,
             ref custom_ecmascript_app_transforms,
             ref custom_ecmascript_transforms,
             ref custom_rules,
             execution_context,
+            allow_eval,
             ref rules,
             ..
         } = *context.await?;
@@ -74,8 +91,19 @@ impl ModuleOptionsVc {
                 }
             }
         }
+        // Feature flags and defines are replaced first, so that all following
+        // transforms see the constant values.
+        let mut base_vendor_transforms = Vec::new();
+        if let Some(flags) = feature_flags {
+            base_vendor_transforms.push(EcmascriptInputTransform::FeatureFlags(flags));
+        }
+        if let Some(defines) = compile_time_defines {
+            base_vendor_transforms.push(EcmascriptInputTransform::Defines(defines));
+        }
+        base_vendor_transforms.extend(custom_ecmascript_transforms.iter().cloned());
+
         let mut transforms = custom_ecmascript_app_transforms.clone();
-        transforms.extend(custom_ecmascript_transforms.iter().cloned());
+        transforms.extend(base_vendor_transforms.iter().cloned());
 
         // Order of transforms is important. e.g. if the React transform occurs before
         // Styled JSX, there won't be JSX nodes for Styled JSX to transform.
@@ -90,20 +118,38 @@ impl ModuleOptionsVc {
         }
         if enable_jsx {
             transforms.push(EcmascriptInputTransform::React {
-                refresh: enable_react_refresh,
+                refresh: enable_react_refresh.then(|| {
+                    react_refresh_options.unwrap_or_else(|| ReactRefreshOptions::default().cell())
+                }),
             });
         }
 
         if let Some(env) = preset_env_versions {
-            transforms.push(EcmascriptInputTransform::PresetEnv(env));
+            transforms.push(EcmascriptInputTransform::PresetEnv {
+                env,
+                polyfills: preset_env_polyfills,
+            });
         }
 
         let app_transforms = EcmascriptInputTransformsVc::cell(transforms);
-        let vendor_transforms =
-            EcmascriptInputTransformsVc::cell(custom_ecmascript_transforms.clone());
+        let vendor_transforms = EcmascriptInputTransformsVc::cell(base_vendor_transforms.clone());
         let ts_app_transforms = if enable_typescript_transform {
-            let mut base_transforms = vec![EcmascriptInputTransform::TypeScript];
-            base_transforms.extend(custom_ecmascript_transforms.iter().cloned());
+            let decorators = if let Some(decorators) = decorators {
+                decorators
+            } else if let FindContextFileResult::Found(tsconfig, _) =
+                *find_context_file(path, tsconfig()).await?
+            {
+                tsconfig_decorators_options(tsconfig)
+            } else {
+                DecoratorsOptions::default().cell()
+            };
+            // Decorators are transformed before types are stripped, so that their
+            // metadata can be emitted.
+            let mut base_transforms = vec![
+                EcmascriptInputTransform::Decorators(decorators),
+                EcmascriptInputTransform::TypeScript,
+            ];
+            base_transforms.extend(base_vendor_transforms.iter().cloned());
             EcmascriptInputTransformsVc::cell(
                 base_transforms
                     .iter()
@@ -115,6 +161,8 @@ impl ModuleOptionsVc {
             app_transforms
         };
 
+        let ecmascript_options = EcmascriptOptions { allow_eval };
+
         let css_transforms = CssInputTransformsVc::cell(vec![CssInputTransform::Nested]);
         let mdx_transforms = EcmascriptInputTransformsVc::cell(
             vec![EcmascriptInputTransform::TypeScript]
@@ -129,6 +177,10 @@ impl ModuleOptionsVc {
                 ModuleRuleCondition::ResourcePathEndsWith(".json".to_string()),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Json)],
             ),
+            ModuleRule::new(
+                ModuleRuleCondition::ResourcePathEndsWith(".node".to_string()),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::NodeNative)],
+            ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathEndsWith(".css".to_string()),
                 [
@@ -173,18 +225,21 @@ impl ModuleOptionsVc {
                 ]),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
                     app_transforms,
+                    ecmascript_options,
                 ))],
             ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathEndsWith(".mjs".to_string()),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
                     app_transforms,
+                    ecmascript_options,
                 ))],
             ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathEndsWith(".cjs".to_string()),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
                     app_transforms,
+                    ecmascript_options,
                 ))],
             ),
             ModuleRule::new(
@@ -193,15 +248,21 @@ impl ModuleOptionsVc {
                     ModuleRuleCondition::ResourcePathEndsWith(".tsx".to_string()),
                 ]),
                 vec![if enable_types {
-                    ModuleRuleEffect::ModuleType(ModuleType::TypescriptWithTypes(ts_app_transforms))
+                    ModuleRuleEffect::ModuleType(ModuleType::TypescriptWithTypes(
+                        ts_app_transforms,
+                        ecmascript_options,
+                    ))
                 } else {
-                    ModuleRuleEffect::ModuleType(ModuleType::Typescript(ts_app_transforms))
+                    ModuleRuleEffect::ModuleType(ModuleType::Typescript(
+                        ts_app_transforms,
+                        ecmascript_options,
+                    ))
                 }],
             ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathEndsWith(".d.ts".to_string()),
                 vec![ModuleRuleEffect::ModuleType(
-                    ModuleType::TypescriptDeclaration(vendor_transforms),
+                    ModuleType::TypescriptDeclaration(vendor_transforms, ecmascript_options),
                 )],
             ),
             ModuleRule::new(
@@ -223,6 +284,7 @@ impl ModuleOptionsVc {
                 ModuleRuleCondition::ResourcePathHasNoExtension,
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
                     vendor_transforms,
+                    ecmascript_options,
                 ))],
             ),
             ModuleRule::new(
@@ -231,6 +293,17 @@ impl ModuleOptionsVc {
                 )),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Static)],
             ),
+            // HTML pages can only be entries, e.g. `index.html` of a project
+            // without a framework.
+            ModuleRule::new(
+                ModuleRuleCondition::all(vec![
+                    ModuleRuleCondition::ReferenceType(ReferenceType::Entry(
+                        EntryReferenceSubType::Undefined,
+                    )),
+                    ModuleRuleCondition::ResourcePathEndsWith(".html".to_string()),
+                ]),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::Html)],
+            ),
         ];
 
         if enable_mdx {
@@ -250,7 +323,10 @@ impl ModuleOptionsVc {
                 rules.push(ModuleRule::new(
                     ModuleRuleCondition::ResourcePathEndsWith(ext.to_string()),
                     vec![
-                        ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(app_transforms)),
+                        ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
+                            app_transforms,
+                            ecmascript_options,
+                        )),
                         ModuleRuleEffect::SourceTransforms(SourceTransformsVc::cell(vec![
                             WebpackLoadersVc::new(
                                 node_evaluate_asset_context(None),
@@ -264,6 +340,23 @@ impl ModuleOptionsVc {
             }
         }
 
+        // The `type` import attribute overrides the module type that is
+        // determined by the file extension.
+        rules.push(ModuleRule::new(
+            ModuleRuleCondition::ReferenceType(ReferenceType::EcmaScriptModules(
+                EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Json),
+            )),
+            vec![ModuleRuleEffect::ModuleType(ModuleType::Json)],
+        ));
+        rules.push(ModuleRule::new(
+            ModuleRuleCondition::ReferenceType(ReferenceType::EcmaScriptModules(
+                EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Css),
+            )),
+            vec![ModuleRuleEffect::ModuleType(ModuleType::Css(
+                css_transforms,
+            ))],
+        ));
+
         rules.extend(custom_rules.iter().cloned());
 
         Ok(ModuleOptionsVc::cell(ModuleOptions { rules }))
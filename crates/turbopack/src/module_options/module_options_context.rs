@@ -1,8 +1,14 @@
+use anyhow::Result;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::trace::TraceRawVcs;
-use turbopack_core::{environment::EnvironmentVc, resolve::options::ImportMappingVc};
-use turbopack_ecmascript::EcmascriptInputTransform;
+use turbopack_core::{
+    config::TransformConfigVc, environment::EnvironmentVc, resolve::options::ImportMappingVc,
+};
+use turbopack_ecmascript::{
+    CompileTimeDefinesVc, CoreJsPolyfills, DecoratorsOptionsVc, EcmascriptInputTransform,
+    FeatureFlagsVc, ReactRefreshOptionsVc,
+};
 use turbopack_node::{
     execution_context::ExecutionContextVc, transforms::webpack::WebpackLoaderConfigsVc,
 };
@@ -43,19 +49,37 @@ pub struct ModuleOptionsContext {
     pub enable_jsx: bool,
     pub enable_emotion: bool,
     pub enable_react_refresh: bool,
+    /// Configures the globals that React Refresh registers components with.
+    /// Defaults to `$RefreshReg$` and `$RefreshSig$`.
+    pub react_refresh_options: Option<ReactRefreshOptionsVc>,
     pub enable_styled_components: bool,
     pub enable_styled_jsx: bool,
     pub enable_postcss_transform: Option<PostCssTransformOptions>,
     pub enable_webpack_loaders: Option<WebpackLoadersOptions>,
     pub enable_types: bool,
     pub enable_typescript_transform: bool,
+    /// Options for transforming decorators in TypeScript files. When not set,
+    /// they are read from the closest tsconfig.json.
+    pub decorators: Option<DecoratorsOptionsVc>,
     pub enable_mdx: bool,
     pub preset_env_versions: Option<EnvironmentVc>,
+    /// The `core-js` polyfills injected when `preset_env_versions` is set.
+    pub preset_env_polyfills: CoreJsPolyfills,
+    /// Expressions like `process.env.NODE_ENV` that are replaced with
+    /// constant values in app and vendor code before analysis.
+    pub compile_time_defines: Option<CompileTimeDefinesVc>,
+    /// Named boolean flags, e.g. for preview features. Code in the branches
+    /// that are dead because of a flag, and the modules only imported there,
+    /// are excluded from the build.
+    pub feature_flags: Option<FeatureFlagsVc>,
     pub custom_ecmascript_app_transforms: Vec<EcmascriptInputTransform>,
     pub custom_ecmascript_transforms: Vec<EcmascriptInputTransform>,
     /// Custom rules to be applied after all default rules.
     pub custom_rules: Vec<ModuleRule>,
     pub execution_context: Option<ExecutionContextVc>,
+    /// Keeps direct `eval` calls working instead of reporting them. Usually
+    /// only enabled for the packages that need it via `rules`.
+    pub allow_eval: bool,
     /// A list of rules to use a different module option context for certain
     /// context paths. The first matching is used.
     pub rules: Vec<(ContextCondition, ModuleOptionsContextVc)>,
@@ -68,6 +92,27 @@ impl ModuleOptionsContextVc {
     pub fn default() -> Self {
         Self::cell(Default::default())
     }
+
+    /// Returns a new [ModuleOptionsContextVc] with the transforms which the
+    /// transform section of a project config sets enabled or disabled.
+    #[turbo_tasks::function]
+    pub async fn with_config(self, config: TransformConfigVc) -> Result<Self> {
+        let config = config.await?;
+        let mut module_options_context = self.await?.clone_value();
+        if let Some(jsx) = config.jsx {
+            module_options_context.enable_jsx = jsx;
+        }
+        if let Some(typescript) = config.typescript {
+            module_options_context.enable_typescript_transform = typescript;
+        }
+        if let Some(styled_components) = config.styled_components {
+            module_options_context.enable_styled_components = styled_components;
+        }
+        if let Some(emotion) = config.emotion {
+            module_options_context.enable_emotion = emotion;
+        }
+        Ok(module_options_context.cell())
+    }
 }
 
 impl Default for ModuleOptionsContextVc {
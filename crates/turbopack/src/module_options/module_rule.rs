@@ -4,7 +4,7 @@ use turbo_tasks::trace::TraceRawVcs;
 use turbo_tasks_fs::FileSystemPath;
 use turbopack_core::{reference_type::ReferenceType, source_transform::SourceTransformsVc};
 use turbopack_css::CssInputTransformsVc;
-use turbopack_ecmascript::EcmascriptInputTransformsVc;
+use turbopack_ecmascript::{EcmascriptInputTransformsVc, EcmascriptOptions};
 
 use super::ModuleRuleCondition;
 
@@ -40,16 +40,21 @@ pub enum ModuleRuleEffect {
 #[turbo_tasks::value(serialization = "auto_for_input", shared)]
 #[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
 pub enum ModuleType {
-    Ecmascript(EcmascriptInputTransformsVc),
-    Typescript(EcmascriptInputTransformsVc),
-    TypescriptWithTypes(EcmascriptInputTransformsVc),
-    TypescriptDeclaration(EcmascriptInputTransformsVc),
+    Ecmascript(EcmascriptInputTransformsVc, EcmascriptOptions),
+    Typescript(EcmascriptInputTransformsVc, EcmascriptOptions),
+    TypescriptWithTypes(EcmascriptInputTransformsVc, EcmascriptOptions),
+    TypescriptDeclaration(EcmascriptInputTransformsVc, EcmascriptOptions),
     Json,
     Raw,
+    /// A native addon, see
+    /// [turbopack_ecmascript::native_module::NodeNativeModuleAsset].
+    NodeNative,
     Mdx(EcmascriptInputTransformsVc),
     Css(CssInputTransformsVc),
     CssModule(CssInputTransformsVc),
     Static,
+    /// An HTML entry page, see [turbopack_html::HtmlModuleAsset].
+    Html,
     // TODO allow custom function when we support function pointers
     Custom(u8),
 }
@@ -0,0 +1,188 @@
+//! Replaces resolved modules with other modules, e.g. to mock modules in
+//! component workshops or to swap in platform specific files like
+//! `button.ios.js` for `button.js`.
+
+use std::{future::Future, pin::Pin};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{
+    primitives::{BoolVc, Regex},
+    trace::TraceRawVcs,
+};
+use turbo_tasks_fs::{glob::GlobVc, FileSystemEntryType, FileSystemPathVc};
+use turbopack_core::{
+    resolve::{
+        parse::RequestVc,
+        plugin::{ResolvePlugin, ResolvePluginConditionVc},
+        ResolveResult, ResolveResultOptionVc,
+    },
+    source_asset::SourceAssetVc,
+};
+
+/// A condition that is evaluated in code, for replacements that can't be
+/// expressed with the other [ModuleReplacementCondition]s.
+#[turbo_tasks::value_trait]
+pub trait ModuleReplacementMatcher {
+    /// `issuer` is the directory of the module that contains the request.
+    fn matches(
+        &self,
+        request: RequestVc,
+        issuer: FileSystemPathVc,
+        resolved: FileSystemPathVc,
+    ) -> BoolVc;
+}
+
+/// Decides which resolved requests a [ModuleReplacementRule] applies to.
+/// Paths are matched relative to the root of their filesystem.
+#[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
+pub enum ModuleReplacementCondition {
+    All(Vec<ModuleReplacementCondition>),
+    Any(Vec<ModuleReplacementCondition>),
+    Not(Box<ModuleReplacementCondition>),
+    /// Matches the request as written in the source, e.g. `./api` or
+    /// `lodash/get`.
+    Request(Regex),
+    /// Matches the path of the directory of the module that contains the
+    /// request.
+    Issuer(Regex),
+    /// Matches the path the request was resolved to.
+    ResolvedPath(Regex),
+    Custom(ModuleReplacementMatcherVc),
+}
+
+impl ModuleReplacementCondition {
+    /// `issuer` is the directory of the module that contains the request.
+    pub fn matches<'a>(
+        &'a self,
+        request: RequestVc,
+        issuer: FileSystemPathVc,
+        resolved: FileSystemPathVc,
+    ) -> Pin<Box<dyn Future<Output = Result<bool>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(match self {
+                ModuleReplacementCondition::All(conditions) => {
+                    for condition in conditions {
+                        if !condition.matches(request, issuer, resolved).await? {
+                            return Ok(false);
+                        }
+                    }
+                    true
+                }
+                ModuleReplacementCondition::Any(conditions) => {
+                    for condition in conditions {
+                        if condition.matches(request, issuer, resolved).await? {
+                            return Ok(true);
+                        }
+                    }
+                    false
+                }
+                ModuleReplacementCondition::Not(condition) => {
+                    !condition.matches(request, issuer, resolved).await?
+                }
+                ModuleReplacementCondition::Request(regex) => request
+                    .await?
+                    .request()
+                    .map_or(false, |request| regex.is_match(&request)),
+                ModuleReplacementCondition::Issuer(regex) => regex.is_match(&issuer.await?.path),
+                ModuleReplacementCondition::ResolvedPath(regex) => {
+                    regex.is_match(&resolved.await?.path)
+                }
+                ModuleReplacementCondition::Custom(matcher) => {
+                    *matcher.matches(request, issuer, resolved).await?
+                }
+            })
+        })
+    }
+}
+
+/// The module that replaces a matching module.
+#[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
+pub enum ModuleReplacement {
+    /// Replaces the module with the file at this path, e.g. a mock.
+    Path(FileSystemPathVc),
+    /// Replaces the module with the file that has this suffix appended to its
+    /// stem, e.g. `.ios` replaces `button.js` with `button.ios.js`. When that
+    /// file doesn't exist, the module is kept and the next rule is tried.
+    StemSuffix(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
+pub struct ModuleReplacementRule {
+    pub condition: ModuleReplacementCondition,
+    pub replacement: ModuleReplacement,
+}
+
+impl ModuleReplacementRule {
+    pub fn new(condition: ModuleReplacementCondition, replacement: ModuleReplacement) -> Self {
+        ModuleReplacementRule {
+            condition,
+            replacement,
+        }
+    }
+}
+
+/// The replacement rules, of which the first matching one is applied.
+#[turbo_tasks::value(transparent)]
+pub struct ModuleReplacementRules(Vec<ModuleReplacementRule>);
+
+/// A resolve plugin which applies [ModuleReplacementRule]s to the results of
+/// resolving requests. Unlike an import map, it applies to all requests that
+/// resolve to a module, regardless how the module was requested.
+#[turbo_tasks::value]
+pub struct ModuleReplacementResolvePlugin {
+    root: FileSystemPathVc,
+    rules: ModuleReplacementRulesVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleReplacementResolvePluginVc {
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc, rules: ModuleReplacementRulesVc) -> Self {
+        ModuleReplacementResolvePlugin { root, rules }.cell()
+    }
+}
+
+#[turbo_tasks::function]
+fn condition(root: FileSystemPathVc) -> ResolvePluginConditionVc {
+    ResolvePluginConditionVc::new(root.root(), GlobVc::new("**"))
+}
+
+#[turbo_tasks::value_impl]
+impl ResolvePlugin for ModuleReplacementResolvePlugin {
+    #[turbo_tasks::function]
+    fn after_resolve_condition(&self) -> ResolvePluginConditionVc {
+        condition(self.root)
+    }
+
+    #[turbo_tasks::function]
+    async fn after_resolve(
+        &self,
+        fs_path: FileSystemPathVc,
+        context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ResolveResultOptionVc> {
+        for rule in self.rules.await?.iter() {
+            if !rule.condition.matches(request, context, fs_path).await? {
+                continue;
+            }
+            let path = match &rule.replacement {
+                ModuleReplacement::Path(path) => *path,
+                ModuleReplacement::StemSuffix(suffix) => {
+                    let path = fs_path.append_to_stem(suffix);
+                    if !matches!(&*path.get_type().await?, FileSystemEntryType::File) {
+                        continue;
+                    }
+                    path
+                }
+            };
+            if path.resolve().await? == fs_path.resolve().await? {
+                return Ok(ResolveResultOptionVc::none());
+            }
+            return Ok(ResolveResultOptionVc::some(
+                ResolveResult::asset(SourceAssetVc::new(path).into()).cell(),
+            ));
+        }
+        Ok(ResolveResultOptionVc::none())
+    }
+}
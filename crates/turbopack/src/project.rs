@@ -0,0 +1,356 @@
+//! A high level API to embed turbopack in other tools. A [Project] is created
+//! from [ProjectOptions], and returns its [Entrypoints] and [BuildResult]s as
+//! plain values, so embedders don't need to walk the asset graph themselves.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs, TryJoinIterExt, Value, ValueToString};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetVc, AssetsVc},
+    chunk::{
+        ChunkGroupVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, MinifyOptions,
+    },
+    context::{AssetContext, AssetContextVc},
+    environment::EnvironmentVc,
+    issue::{IssueVc, PlainIssueReadRef},
+    reference::all_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    resolve::{origin::PlainResolveOriginVc, parse::RequestVc},
+};
+
+use crate::{
+    build::emit_build,
+    ecmascript::{
+        chunk::EcmascriptChunkPlaceableVc, references::esm::ExportUsageGraphVc,
+        EcmascriptModuleAssetVc,
+    },
+    federation::{federation_container_entry, FederationManifestAssetVc, FederationOptionsVc},
+    module_options::ModuleOptionsContextVc,
+    resolve_options_context::{ResolveOptionsContext, ResolveOptionsContextVc},
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+
+#[turbo_tasks::value(shared)]
+#[derive(Clone)]
+pub struct ProjectOptions {
+    /// The root of the project. Entries are resolved relative to it.
+    pub project_path: FileSystemPathVc,
+    /// The directory the outputs are emitted to.
+    pub output_path: FileSystemPathVc,
+    /// The requests of the entries, e.g. `./src/index.js`.
+    pub entries: Vec<String>,
+    /// The environment the outputs are executed in.
+    pub environment: EnvironmentVc,
+    pub module_options_context: ModuleOptionsContextVc,
+    pub resolve_options_context: ResolveOptionsContextVc,
+    /// Places the chunks. Its output root needs to be inside of
+    /// `output_path`.
+    pub chunking_context: ChunkingContextVc,
+    /// Removes the exports which are not used by any module reachable from
+    /// the entries. See [ProjectVc::explain_export].
+    pub tree_shaking: bool,
+    /// Minifies the emitted chunks with these options.
+    pub minify: Option<MinifyOptions>,
+    /// Shares modules with other builds at runtime. The container which
+    /// exposes modules and its `federation-manifest.json` are emitted next to
+    /// the entries.
+    pub federation: Option<FederationOptionsVc>,
+}
+
+#[turbo_tasks::value]
+pub struct Project {
+    options: ProjectOptionsVc,
+}
+
+/// An entry of a project with the chunks which need to be loaded for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct Entrypoint {
+    /// The request of the entry, as passed in [ProjectOptions::entries].
+    pub request: String,
+    /// The chunks of the entry, relative to the output path. They are loaded
+    /// in order.
+    pub chunks: Vec<String>,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct Entrypoints {
+    pub entrypoints: Vec<Entrypoint>,
+}
+
+/// An asset which was emitted by a build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct EmittedAsset {
+    /// The path of the asset, relative to the output path.
+    pub path: String,
+    /// The size of the asset in bytes.
+    pub size: usize,
+}
+
+#[turbo_tasks::value(shared, serialization = "none")]
+pub struct BuildResult {
+    pub entrypoints: Vec<Entrypoint>,
+    /// All emitted assets, sorted by path.
+    pub assets: Vec<EmittedAsset>,
+    /// The issues that were reported during the build. A build with errors
+    /// may still have emitted assets.
+    pub issues: Vec<PlainIssueReadRef>,
+}
+
+#[turbo_tasks::value_impl]
+impl ProjectVc {
+    #[turbo_tasks::function]
+    pub fn new(options: ProjectOptionsVc) -> Self {
+        Project { options }.cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn asset_context(self) -> Result<AssetContextVc> {
+        let options = self.await?.options.await?;
+        let resolve_options_context = match options.federation {
+            Some(federation) => ResolveOptionsContext {
+                federation: Some(federation),
+                ..options.resolve_options_context.await?.clone_value()
+            }
+            .cell(),
+            None => options.resolve_options_context,
+        };
+        Ok(ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(Default::default()),
+            options.environment,
+            options.module_options_context,
+            resolve_options_context,
+        )
+        .into())
+    }
+
+    /// Resolves a request relative to the project root, e.g. an entry.
+    #[turbo_tasks::function]
+    async fn resolve_request(self, request: &str) -> Result<AssetVc> {
+        let options = self.await?.options.await?;
+        let origin =
+            PlainResolveOriginVc::new(self.asset_context(), options.project_path.join("_"))
+                .as_resolve_origin();
+        let ty = Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined));
+        origin
+            .resolve_asset(
+                RequestVc::parse_string(request.to_string()),
+                origin.resolve_options(ty.clone()),
+                ty,
+            )
+            .primary_assets()
+            .await?
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("{} can't be resolved", request))
+    }
+
+    /// The module of every entry of the project.
+    #[turbo_tasks::function]
+    async fn entry_modules(self) -> Result<AssetsVc> {
+        let options = self.await?.options.await?;
+        let modules = options
+            .entries
+            .iter()
+            .map(|entry| async move { self.resolve_request(entry).resolve().await })
+            .try_join()
+            .await?;
+        Ok(AssetsVc::cell(modules))
+    }
+
+    /// The chunking context of the project, which tree shakes the modules of
+    /// the entries and minifies the chunks when enabled.
+    #[turbo_tasks::function]
+    async fn chunking_context(self) -> Result<ChunkingContextVc> {
+        let options = self.await?.options.await?;
+        let mut chunking_context = options.chunking_context;
+        if options.tree_shaking {
+            chunking_context = chunking_context.with_tree_shaking(self.entry_modules());
+        }
+        if let Some(minify) = &options.minify {
+            chunking_context = chunking_context.with_minify(minify.clone().cell());
+        }
+        if options.federation.is_some() {
+            chunking_context = chunking_context.with_federation();
+        }
+        Ok(chunking_context)
+    }
+
+    /// The assets which are emitted in addition to the entries, i.e. the
+    /// chunk of the federation container and the federation manifest.
+    #[turbo_tasks::function]
+    async fn federation_assets(self) -> Result<AssetsVc> {
+        let options = self.await?.options.await?;
+        let Some(federation) = options.federation else {
+            return Ok(AssetsVc::empty());
+        };
+        let container = if federation.await?.exposes.is_empty() {
+            None
+        } else {
+            let module = self.asset_context().process(
+                federation_container_entry(options.project_path, federation),
+                Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+            );
+            Some(
+                entry_chunk(module, self.chunking_context())
+                    .await?
+                    .as_asset(),
+            )
+        };
+        let manifest = FederationManifestAssetVc::new(
+            options.output_path.join("federation-manifest.json"),
+            federation,
+            container,
+        );
+        Ok(AssetsVc::cell(
+            container
+                .into_iter()
+                .chain(std::iter::once(manifest.into()))
+                .collect(),
+        ))
+    }
+
+    /// The entry chunks and the other assets which are emitted by a build.
+    #[turbo_tasks::function]
+    async fn output_assets(self) -> Result<AssetsVc> {
+        let mut assets = self.entry_chunks().await?.clone_value();
+        assets.extend(self.federation_assets().await?.iter().copied());
+        Ok(AssetsVc::cell(assets))
+    }
+
+    /// The evaluated chunk of every entry of the project.
+    #[turbo_tasks::function]
+    async fn entry_chunks(self) -> Result<AssetsVc> {
+        let chunking_context = self.chunking_context();
+        let chunks = self
+            .entry_modules()
+            .await?
+            .iter()
+            .map(|&module| entry_chunk(module, chunking_context))
+            .try_join()
+            .await?;
+        Ok(AssetsVc::cell(
+            chunks.into_iter().map(|chunk| chunk.into()).collect(),
+        ))
+    }
+
+    /// Explains why an export of the module that `request` resolves to, e.g.
+    /// `./src/utils.js`, is retained or removed by tree shaking.
+    #[turbo_tasks::function]
+    pub async fn explain_export(self, request: &str, export: &str) -> Result<StringVc> {
+        let options = self.await?.options.await?;
+        if !options.tree_shaking {
+            return Ok(StringVc::cell(
+                "tree shaking is disabled, all exports are retained".to_string(),
+            ));
+        }
+        let module = self.resolve_request(request);
+        let Some(module) = EcmascriptChunkPlaceableVc::resolve_from(module).await? else {
+            return Ok(StringVc::cell(format!(
+                "{} is not an ecmascript module, all exports are retained",
+                module.path().to_string().await?
+            )));
+        };
+        Ok(
+            ExportUsageGraphVc::new(self.chunking_context().tree_shaking_roots())
+                .explain(module, export),
+        )
+    }
+
+    #[turbo_tasks::function]
+    pub async fn entrypoints(self) -> Result<EntrypointsVc> {
+        let options = self.await?.options.await?;
+        let output_path = options.output_path.await?;
+        let entry_chunks = self.entry_chunks().await?;
+        let entrypoints = options
+            .entries
+            .iter()
+            .zip(entry_chunks.iter())
+            .map(|(request, entry_chunk)| {
+                let output_path = &output_path;
+                async move {
+                    let Some(entry_chunk) = ChunkVc::resolve_from(*entry_chunk).await? else {
+                        return Err(anyhow!("the entry of {} is not a chunk", request));
+                    };
+                    let chunks = ChunkGroupVc::from_chunk(entry_chunk).chunks().await?;
+                    let mut paths = Vec::new();
+                    for chunk in chunks.iter() {
+                        if let Some(path) = output_path.get_path_to(&*chunk.path().await?) {
+                            paths.push(path.to_string());
+                        }
+                    }
+                    Ok(Entrypoint {
+                        request: request.clone(),
+                        chunks: paths,
+                    })
+                }
+            })
+            .try_join()
+            .await?;
+        Ok(Entrypoints { entrypoints }.cell())
+    }
+
+    /// Emits the outputs of the project into the output path.
+    #[turbo_tasks::function]
+    pub async fn build(self) -> Result<BuildResultVc> {
+        let options = self.await?.options.await?;
+        let output_path = options.output_path.await?;
+        let output_assets = self.output_assets();
+        let emitted = emit_build(output_assets, options.output_path);
+        emitted.await?;
+
+        let mut assets = Vec::new();
+        for output_asset in output_assets.await?.iter() {
+            for asset in all_assets(*output_asset).await?.iter() {
+                let path = asset.path().await?;
+                let Some(path) = output_path.get_path_to(&path) else {
+                    continue;
+                };
+                let size = match &*asset.content().await? {
+                    AssetContent::File(file) => match &*file.await? {
+                        FileContent::Content(file) => file.content().len(),
+                        FileContent::NotFound => continue,
+                    },
+                    AssetContent::Redirect { .. } => continue,
+                };
+                assets.push(EmittedAsset {
+                    path: path.to_string(),
+                    size,
+                });
+            }
+        }
+        assets.sort_by(|a, b| a.path.cmp(&b.path));
+        assets.dedup_by(|a, b| a.path == b.path);
+
+        let captured = IssueVc::peek_issues_with_path(emitted).await?.await?;
+        let issues = captured
+            .iter()
+            .map(|issue| async move { issue.into_plain().await })
+            .try_join()
+            .await?;
+
+        Ok(BuildResult {
+            entrypoints: self.entrypoints().await?.entrypoints.clone(),
+            assets,
+            issues,
+        }
+        .cell())
+    }
+}
+
+async fn entry_chunk(module: AssetVc, chunking_context: ChunkingContextVc) -> Result<ChunkVc> {
+    if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
+        Ok(ecmascript.as_evaluated_chunk(chunking_context, None))
+    } else if let Some(chunkable) = ChunkableAssetVc::resolve_from(module).await? {
+        Ok(chunkable.as_chunk(chunking_context))
+    } else {
+        Err(anyhow!(
+            "entry {} is not chunkable",
+            module.path().await?.path
+        ))
+    }
+}
@@ -1,74 +1,29 @@
 use std::collections::BTreeMap;
 
 use anyhow::Result;
+use turbo_tasks::primitives::StringsVc;
 use turbo_tasks_fs::{FileSystem, FileSystemPathVc};
 use turbopack_core::resolve::{
     find_context_file,
+    node::NODE_BUILTINS,
     options::{
         ConditionValue, ImportMap, ImportMapping, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveOptions, ResolveOptionsVc,
     },
-    AliasMap, AliasPattern, FindContextFileResult,
+    package_json, AliasMap, AliasPattern, FindContextFileResult,
 };
 use turbopack_ecmascript::typescript::resolve::{
     apply_tsconfig_resolve_options, tsconfig, tsconfig_resolve_options,
 };
 
-use crate::resolve_options_context::ResolveOptionsContextVc;
-
-const NODE_EXTERNALS: [&str; 51] = [
-    "assert",
-    "async_hooks",
-    "buffer",
-    "child_process",
-    "cluster",
-    "console",
-    "constants",
-    "crypto",
-    "dgram",
-    "diagnostics_channel",
-    "dns",
-    "dns/promises",
-    "domain",
-    "events",
-    "fs",
-    "fs/promises",
-    "http",
-    "http2",
-    "https",
-    "inspector",
-    "module",
-    "net",
-    "os",
-    "path",
-    "path/posix",
-    "path/win32",
-    "perf_hooks",
-    "process",
-    "punycode",
-    "querystring",
-    "readline",
-    "repl",
-    "stream",
-    "stream/promises",
-    "stream/web",
-    "string_decoder",
-    "sys",
-    "timers",
-    "timers/promises",
-    "tls",
-    "trace_events",
-    "tty",
-    "url",
-    "util",
-    "util/types",
-    "v8",
-    "vm",
-    "wasi",
-    "worker_threads",
-    "zlib",
-    "pnpapi",
-];
+use crate::{
+    asset_manifest::asset_manifest_import_map,
+    condition::ContextCondition,
+    external_packages::{ExternalPackagesResolvePluginVc, NativePackagesResolvePluginVc},
+    federation::{federation_import_map, FEDERATION_DIRECTORY},
+    module_replacement::ModuleReplacementResolvePluginVc,
+    resolve_options_context::{NodeBuiltinsFallback, ResolveOptionsContextVc},
+};
 
 #[turbo_tasks::function]
 async fn base_resolve_options(
@@ -89,8 +44,9 @@ async fn base_resolve_options(
     } else {
         opt.enable_node_externals
     };
+    let mut fallback_mappings = AliasMap::new();
     if node_externals {
-        for req in NODE_EXTERNALS {
+        for req in NODE_BUILTINS.into_iter().chain(["pnpapi"]) {
             direct_mappings.insert(
                 AliasPattern::exact(req),
                 ImportMapping::External(None).into(),
@@ -100,6 +56,32 @@ async fn base_resolve_options(
                 ImportMapping::External(None).into(),
             );
         }
+    } else {
+        let node_builtins = &opt.node_builtins;
+        let polyfills: BTreeMap<_, _> = node_builtins
+            .polyfills
+            .iter()
+            .map(|(req, polyfill)| (req.strip_prefix("node:").unwrap_or(req), polyfill))
+            .collect();
+        for (req, polyfill) in polyfills.iter() {
+            let mapping = ImportMapping::PrimaryAlternative(polyfill.to_string(), None).cell();
+            direct_mappings.insert(AliasPattern::exact(*req), mapping);
+            direct_mappings.insert(AliasPattern::exact(format!("node:{req}")), mapping);
+        }
+        for req in NODE_BUILTINS {
+            if polyfills.contains_key(req) {
+                continue;
+            }
+            // `node:` requests can only refer to the builtin module, but a package
+            // with the same name might be installed as a polyfill.
+            direct_mappings.insert(
+                AliasPattern::exact(format!("node:{req}")),
+                ImportMapping::PrimaryAlternative(req.to_string(), None).into(),
+            );
+            if let NodeBuiltinsFallback::Empty = node_builtins.fallback {
+                fallback_mappings.insert(AliasPattern::exact(req), ImportMapping::Empty.into());
+            }
+        }
     }
 
     let mut import_map = ImportMap::new(direct_mappings);
@@ -108,28 +90,56 @@ async fn base_resolve_options(
         import_map.extend(&additional_import_map);
     }
     let import_map = import_map.cell();
+    let fallback_import_map = ImportMap::new(fallback_mappings).cell();
+
+    let mut plugins = opt.plugins.clone();
+    // The first plugin that returns a result wins, so replacements take
+    // precedence over externals.
+    if let Some(module_replacements) = opt.module_replacements {
+        plugins.push(ModuleReplacementResolvePluginVc::new(root, module_replacements).into());
+    }
+    if opt.enable_external_native_packages {
+        plugins.push(NativePackagesResolvePluginVc::new(root).into());
+    }
+    if opt.enable_external_packages {
+        plugins.push(
+            ExternalPackagesResolvePluginVc::new(
+                root,
+                StringsVc::cell(opt.bundled_packages.clone()),
+            )
+            .into(),
+        );
+    }
+
+    let extensions = if let Some(environment) = emulating {
+        environment.resolve_extensions().await?.clone_value()
+    } else {
+        let mut ext = Vec::new();
+        if opt.enable_typescript && opt.enable_react {
+            ext.push(".tsx".to_string());
+        }
+        if opt.enable_typescript {
+            ext.push(".ts".to_string());
+        }
+        if opt.enable_react {
+            ext.push(".jsx".to_string());
+        }
+        ext.push(".js".to_string());
+        if opt.enable_node_native_modules {
+            ext.push(".node".to_string());
+        }
+        ext.push(".json".to_string());
+        ext
+    };
+    let platform_suffixes = match emulating {
+        Some(environment) if opt.platform_suffixes.is_empty() => {
+            environment.platform_suffixes().await?.clone_value()
+        }
+        _ => opt.platform_suffixes.clone(),
+    };
 
     Ok(ResolveOptions {
-        extensions: if let Some(environment) = emulating {
-            environment.resolve_extensions().await?.clone_value()
-        } else {
-            let mut ext = Vec::new();
-            if opt.enable_typescript && opt.enable_react {
-                ext.push(".tsx".to_string());
-            }
-            if opt.enable_typescript {
-                ext.push(".ts".to_string());
-            }
-            if opt.enable_react {
-                ext.push(".jsx".to_string());
-            }
-            ext.push(".js".to_string());
-            if opt.enable_node_native_modules {
-                ext.push(".node".to_string());
-            }
-            ext.push(".json".to_string());
-            ext
-        },
+        extensions: with_platform_suffixes(extensions, &platform_suffixes),
         modules: if let Some(environment) = emulating {
             if *environment.resolve_node_modules().await? {
                 vec![ResolveModules::Nested(
@@ -219,13 +229,28 @@ async fn base_resolve_options(
             resolve_in
         },
         import_map: Some(import_map),
+        fallback_import_map: Some(fallback_import_map),
         resolved_map: opt.resolved_map,
-        plugins: opt.plugins.clone(),
+        plugins,
+        forbidden_imports: opt.forbidden_imports.clone(),
+        validate_dependencies: opt.validate_dependencies,
         ..Default::default()
     }
     .into())
 }
 
+/// Prepends the extensions with a platform suffix to the extensions, e.g.
+/// `.browser.ts`, `.browser.js`, `.ts`, `.js` for the suffix `.browser`, so
+/// `./file` resolves to `file.browser.js` before `file.ts`. Earlier suffixes
+/// take precedence.
+fn with_platform_suffixes(extensions: Vec<String>, platform_suffixes: &[String]) -> Vec<String> {
+    platform_suffixes
+        .iter()
+        .flat_map(|suffix| extensions.iter().map(move |ext| format!("{suffix}{ext}")))
+        .chain(extensions.iter().cloned())
+        .collect()
+}
+
 #[turbo_tasks::function]
 pub async fn resolve_options(
     context: FileSystemPathVc,
@@ -255,6 +280,30 @@ pub async fn resolve_options(
         resolve_options
     };
 
+    // Requests from the federated modules reach the local modules.
+    let resolve_options = match options_context_value.federation {
+        Some(federation)
+            if !ContextCondition::InDirectory(FEDERATION_DIRECTORY.to_string())
+                .matches(&*context.await?) =>
+        {
+            // The federated modules are placed in the package of the importer, so
+            // that the local packages are resolved from there.
+            let root = match *find_context_file(context, package_json()).await? {
+                FindContextFileResult::Found(path, _) => path.parent(),
+                FindContextFileResult::NotFound(_) => context,
+            };
+            resolve_options.with_extended_import_map(federation_import_map(root, federation))
+        }
+        _ => resolve_options,
+    };
+
+    let resolve_options = match options_context_value.asset_manifest {
+        Some(manifest) => {
+            resolve_options.with_extended_import_map(asset_manifest_import_map(manifest))
+        }
+        None => resolve_options,
+    };
+
     // Make sure to always apply `options_context.import_map` last, so it properly
     // overwrites any other mappings.
     let resolve_options = options_context_value
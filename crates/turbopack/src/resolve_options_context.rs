@@ -1,13 +1,47 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
+    config::ResolveConfigVc,
     environment::EnvironmentVc,
     resolve::{
-        options::{ImportMapVc, ResolvedMapVc},
+        options::{
+            ForbiddenImport, ImportMap, ImportMapVc, ImportMapping, ImportMappingVc, ResolvedMapVc,
+        },
         plugin::ResolvePluginVc,
+        AliasPattern,
     },
 };
 
-use crate::condition::ContextCondition;
+use crate::{
+    asset_manifest::{AssetManifest, AssetManifestVc},
+    condition::ContextCondition,
+    federation::FederationOptionsVc,
+    module_replacement::ModuleReplacementRulesVc,
+};
+
+/// What happens to imports of Node.js builtin modules that have no polyfill
+/// and can't be resolved otherwise.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum NodeBuiltinsFallback {
+    /// Report an issue that explains how to provide the module.
+    #[default]
+    Error,
+    /// Replace the module with an empty module.
+    Empty,
+}
+
+/// How imports of Node.js builtin modules (e.g. `fs` or `node:path`) are
+/// handled when they aren't external, e.g. when bundling for the browser.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+pub struct NodeBuiltinsOptions {
+    /// Requests that replace builtin modules, e.g. `buffer` => `buffer/`.
+    pub polyfills: BTreeMap<String, String>,
+    pub fallback: NodeBuiltinsFallback,
+}
 
 #[turbo_tasks::value(shared)]
 #[derive(Default, Clone)]
@@ -21,11 +55,29 @@ pub struct ResolveOptionsContext {
     /// Mark well-known Node.js modules as external imports and load them using
     /// native `require`. e.g. url, querystring, os
     pub enable_node_externals: bool,
+    /// How Node.js builtin modules are handled when they aren't external.
+    pub node_builtins: NodeBuiltinsOptions,
+    /// Leave requests to CommonJS packages in `node_modules` as external
+    /// `require()` calls, which Node.js resolves at runtime. e.g. for server
+    /// bundles that run next to the installed packages.
+    pub enable_external_packages: bool,
+    /// Packages which are bundled even when external packages are enabled,
+    /// e.g. because they need to be transpiled.
+    pub bundled_packages: Vec<String>,
+    /// Leave requests to packages with native addons as external `require()`
+    /// calls, even when they would be bundled otherwise. Their `.node` files
+    /// are traced.
+    pub enable_external_native_packages: bool,
     /// Enables the "browser" field and export condition in package.json
     pub browser: bool,
     /// Enables the "module" field and export condition in package.json
     pub module: bool,
     pub custom_conditions: Vec<String>,
+    /// Suffixes of platform specific files, e.g. `.browser` or `.ios`, so a
+    /// request to `./file` resolves to `file.browser.js` before `file.js`.
+    /// Earlier suffixes take precedence. When empty, the suffixes of the
+    /// emulated environment are used.
+    pub platform_suffixes: Vec<String>,
     /// An additional import map to use when resolving modules.
     ///
     /// If set, this import map will be applied to `ResolveOption::import_map`.
@@ -40,12 +92,26 @@ pub struct ResolveOptionsContext {
     pub fallback_import_map: Option<ImportMapVc>,
     /// An additional resolved map to use after modules have been resolved.
     pub resolved_map: Option<ResolvedMapVc>,
+    /// Module federation options. Requests to remotes and shared packages are
+    /// replaced with federated modules.
+    pub federation: Option<FederationOptionsVc>,
+    /// Logical names of assets, which application code can import the URLs of
+    /// from the `__turbopack_manifest__` module.
+    pub asset_manifest: Option<AssetManifestVc>,
     /// A list of rules to use a different resolve option context for certain
     /// context paths. The first matching is used.
     pub rules: Vec<(ContextCondition, ResolveOptionsContextVc)>,
     /// A list of plugins which get applied before (in the future) and after
     /// resolving.
     pub plugins: Vec<ResolvePluginVc>,
+    /// Rules which replace resolved modules with other modules, e.g. mocks or
+    /// platform specific files.
+    pub module_replacements: Option<ModuleReplacementRulesVc>,
+    /// Modules that can't be imported, e.g. `server-only` in client code.
+    pub forbidden_imports: Vec<ForbiddenImport>,
+    /// Report imports of packages that aren't declared as dependency, e.g.
+    /// dependencies of dependencies that happen to be hoisted.
+    pub validate_dependencies: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -96,6 +162,76 @@ impl ResolveOptionsContextVc {
         );
         Ok(resolve_options_context.into())
     }
+
+    /// Returns a new [ResolveOptionsContextVc] with the resolve section of a
+    /// project config applied. Aliases take precedence over the import map
+    /// and are relative to `config_dir`.
+    ///
+    /// Only the fields which are read here invalidate the context. A changed
+    /// replacement of an alias only invalidates the resolves which use it.
+    #[turbo_tasks::function]
+    pub async fn with_config(
+        self,
+        config: ResolveConfigVc,
+        config_dir: FileSystemPathVc,
+    ) -> Result<Self> {
+        let mut resolve_options_context = self.await?.clone_value();
+        for condition in config.conditions().await?.iter() {
+            if !resolve_options_context
+                .custom_conditions
+                .contains(condition)
+            {
+                resolve_options_context
+                    .custom_conditions
+                    .push(condition.clone());
+            }
+        }
+        let platform_suffixes = config.platform_suffixes().await?;
+        if !platform_suffixes.is_empty() {
+            resolve_options_context.platform_suffixes = platform_suffixes.clone_value();
+        }
+        let assets = config.assets().await?;
+        if !assets.is_empty() {
+            resolve_options_context.asset_manifest = Some(
+                AssetManifest {
+                    project_path: config_dir,
+                    assets: assets
+                        .iter()
+                        .map(|(name, request)| (name.clone(), request.clone()))
+                        .collect(),
+                }
+                .cell(),
+            );
+        }
+        let context = resolve_options_context.cell();
+        let alias_keys = config.alias_keys().await?;
+        if alias_keys.is_empty() {
+            return Ok(context);
+        }
+        let mut import_map = ImportMap::default();
+        for alias in alias_keys.iter() {
+            import_map.insert_alias(
+                AliasPattern::parse(alias.as_str()),
+                config_alias_mapping(config, alias, config_dir),
+            );
+        }
+        Ok(context.with_extended_import_map(import_map.cell()))
+    }
+}
+
+/// The mapping of an alias of a project config. It's a cell of its own, so the
+/// import map which contains it doesn't change when the replacement changes.
+#[turbo_tasks::function]
+async fn config_alias_mapping(
+    config: ResolveConfigVc,
+    alias: &str,
+    config_dir: FileSystemPathVc,
+) -> Result<ImportMappingVc> {
+    Ok(match config.alias(alias).await?.clone_value() {
+        Some(request) => ImportMapping::PrimaryAlternative(request, Some(config_dir)).cell(),
+        // The alias was removed, which also updates the import map.
+        None => ImportMapping::PrimaryAlternative(alias.to_string(), None).cell(),
+    })
 }
 
 impl Default for ResolveOptionsContextVc {